@@ -0,0 +1,205 @@
+//! Steps per second of a single ant on a large bit map, of one far down its highway
+//! on a 16384x16384 one where rows are 2 KiB apart, and of ants spread over a huge
+//! one where every step lands on another page. Also turns per second by the
+//! direction table and by matching on the turn, and 16 ants walked in an
+//! [`Ensemble`] against one at a time
+//!
+//! ```bash
+//! cargo bench --bench walk
+//! cargo bench --bench walk --features huge-pages
+//! ```
+
+use antventure::{Ant, ColorMap, Direction, Ensemble, Map, Pos, Rule, Simulation, Turn};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+const SIZE: usize = 4096;
+// Langton's ant is well into its highway by then but still on the map
+const STEPS: usize = 100_000;
+
+fn walk(c: &mut Criterion) {
+    let mut group = c.benchmark_group("4096x4096");
+    group.sample_size(20);
+
+    group.throughput(Throughput::Elements(STEPS as u64));
+    group.bench_function("walk", |b| {
+        b.iter_batched_ref(
+            Map::<SIZE, SIZE>::new_white,
+            |map| {
+                let center = Pos::new(SIZE as isize / 2, SIZE as isize / 2);
+                let mut ant = Ant::new(map, center, Direction::North).unwrap();
+                assert!(black_box(ant.walk_at_most(STEPS)))
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    let mut map = Map::<SIZE, SIZE>::new_white();
+    let center = Pos::new(SIZE as isize / 2, SIZE as isize / 2);
+    Ant::new(&mut map, center, Direction::North)
+        .unwrap()
+        .walk_at_most(STEPS);
+    group.throughput(Throughput::Elements((SIZE * SIZE) as u64));
+    group.bench_function("count_black_tiles", |b| {
+        b.iter(|| black_box(&map).count_black_tiles())
+    });
+
+    let white = Map::<SIZE, SIZE>::new_white();
+    group.bench_function("diff", |b| b.iter(|| black_box(&map).diff(&white)));
+    group.bench_function("xor", |b| {
+        b.iter_batched_ref(|| map.clone(), |map| map.xor(&white), BatchSize::LargeInput)
+    });
+
+    group.finish();
+}
+
+const WIDE: usize = 1 << 14;
+// The highway takes the ant about 5800 cells diagonally, short of the edge
+const HIGHWAY_STEPS: usize = 300_000;
+
+fn highway(c: &mut Criterion) {
+    let mut group = c.benchmark_group("16384x16384");
+    group.sample_size(10);
+
+    group.throughput(Throughput::Elements(HIGHWAY_STEPS as u64));
+    group.bench_function("highway", |b| {
+        b.iter_batched_ref(
+            Map::<WIDE, WIDE>::new_white,
+            |map| {
+                let center = Pos::new(WIDE as isize / 2, WIDE as isize / 2);
+                let mut ant = Ant::new(map, center, Direction::North).unwrap();
+                assert!(black_box(ant.walk_at_most(HIGHWAY_STEPS)))
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+const TURNS: usize = 1 << 20;
+
+// Turning by the table against matching on the turn and the direction, over the same
+// scrambled turns so the branch predictor can't learn them
+fn turns(c: &mut Criterion) {
+    let kinds = [Turn::Left, Turn::Right, Turn::UTurn, Turn::None];
+    let mut state = 0x9E37_79B9_7F4A_7C15_u64;
+    let turns = (0..TURNS)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            kinds[(state >> 62) as usize]
+        })
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("turns");
+    group.throughput(Throughput::Elements(TURNS as u64));
+    group.bench_function("table", |b| {
+        b.iter(|| {
+            black_box(&turns).iter().fold(
+                (Direction::North, Pos::new(0, 0)),
+                |(dir, pos), &turn| {
+                    let (dir, shift) = dir.turned(turn);
+                    (dir, Pos::new(pos.x + shift.x, pos.y + shift.y))
+                },
+            )
+        })
+    });
+    group.bench_function("match", |b| {
+        b.iter(|| {
+            black_box(&turns).iter().fold(
+                (Direction::North, Pos::new(0, 0)),
+                |(dir, pos), &turn| {
+                    let dir = dir.turn(turn);
+                    let shift = dir.to_shift();
+                    (dir, Pos::new(pos.x + shift.x, pos.y + shift.y))
+                },
+            )
+        })
+    });
+
+    group.finish();
+}
+
+const LANES: usize = 16;
+const SMALL: usize = 128;
+const LANE_STEPS: u64 = 5_000;
+
+// Sixteen starts of one rule, the way TargetSearch tries them: in lockstep in an
+// Ensemble, one Ant after another, and one Simulation after another as it used to
+fn ensemble(c: &mut Criterion) {
+    let starts: [(Pos, Direction); LANES] = std::array::from_fn(|i| {
+        (
+            Pos::new(40 + 3 * i as isize, 64),
+            Direction::VARIANTS[i % 4],
+        )
+    });
+    let rule = "RL".parse::<Rule>().unwrap();
+
+    let mut group = c.benchmark_group("128x128x16");
+    group.throughput(Throughput::Elements(LANE_STEPS * LANES as u64));
+    group.bench_function("ensemble", |b| {
+        b.iter(|| {
+            let mut ensemble = Ensemble::<SMALL, SMALL, LANES>::with_rule(&rule, starts).unwrap();
+            black_box(ensemble.walk_at_most(LANE_STEPS))
+        })
+    });
+    group.bench_function("ants", |b| {
+        b.iter(|| {
+            for (pos, dir) in starts {
+                let mut map = Map::<SMALL, SMALL>::new_white();
+                let mut ant = Ant::with_rule(&mut map, pos, dir, rule.clone()).unwrap();
+                black_box(ant.walk_at_most(LANE_STEPS as usize));
+            }
+        })
+    });
+    group.bench_function("simulations", |b| {
+        b.iter(|| {
+            for (pos, dir) in starts {
+                let mut sim = Simulation::new(ColorMap::<SMALL, SMALL>::new_white(), rule.clone());
+                sim.spawn(pos, dir).unwrap();
+                black_box(sim.run_at_most(LANE_STEPS));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+const HUGE: usize = 1 << 15;
+// 16 by 16 ants, 2048 cells apart
+const SPREAD: usize = 16;
+const SPREAD_STEPS: u64 = 2_000;
+
+fn spread(c: &mut Criterion) {
+    let mut group = c.benchmark_group("32768x32768");
+    group.sample_size(10);
+
+    group.throughput(Throughput::Elements(
+        SPREAD_STEPS * (SPREAD * SPREAD) as u64,
+    ));
+    group.bench_function("spread", |b| {
+        b.iter_batched_ref(
+            || {
+                let mut sim = Simulation::new(Map::<HUGE, HUGE>::new_white(), Default::default());
+                let apart = HUGE / SPREAD;
+                for i in 0..SPREAD * SPREAD {
+                    let (x, y) = (
+                        i % SPREAD * apart + apart / 2,
+                        i / SPREAD * apart + apart / 2,
+                    );
+                    sim.spawn(Pos::new(x as _, y as _), Direction::North)
+                        .unwrap();
+                }
+                sim
+            },
+            |sim| assert!(black_box(sim.run_at_most(SPREAD_STEPS))),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, walk, turns, ensemble, highway, spread);
+criterion_main!(benches);
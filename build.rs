@@ -0,0 +1,26 @@
+//! Writes `include/antventure.h` for the C API of src/ffi.rs, with the `ffi` feature
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "ffi")]
+    header();
+}
+
+#[cfg(feature = "ffi")]
+fn header() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    let config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        header: Some("/* Generated from src/ffi.rs by build.rs, don't edit */".to_owned()),
+        include_guard: Some("ANTVENTURE_H".to_owned()),
+        cpp_compat: true,
+        usize_is_size_t: true,
+        ..cbindgen::Config::default()
+    };
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src("src/ffi.rs")
+        .generate()
+        .expect("Error in generating the C header")
+        .write_to_file("include/antventure.h");
+}
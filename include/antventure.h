@@ -0,0 +1,109 @@
+/* Generated from src/ffi.rs by build.rs, don't edit */
+
+#ifndef ANTVENTURE_H
+#define ANTVENTURE_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define ANTVENTURE_NORTH 0
+
+#define ANTVENTURE_EAST 1
+
+#define ANTVENTURE_SOUTH 2
+
+#define ANTVENTURE_WEST 3
+
+/**
+ * Square board with ants on it, only ever handled through a pointer
+ */
+typedef struct AntventureSim AntventureSim;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * White board of 64, 128, 256, 512, 1024, 2048 or 4096 cells a side walked by `rule`,
+ * e.g. `"LR"`, with an ant in the middle facing north. Null for any other size or a
+ * rule that doesn't parse
+ *
+ * # Safety
+ *
+ * `rule` must be a nul-terminated string
+ */
+struct AntventureSim *antventure_new(size_t size, const char *rule);
+
+/**
+ * Add an ant at `x`, `y` facing one of `ANTVENTURE_NORTH` to `ANTVENTURE_WEST`, false
+ * if that's off the map or not a direction
+ *
+ * # Safety
+ *
+ * `sim` must come from [`antventure_new`] and not be freed yet
+ */
+bool antventure_spawn(struct AntventureSim *sim, int64_t x, int64_t y, uint32_t direction);
+
+/**
+ * Walk every ant `steps` steps, returns whether any ant is still on the map
+ *
+ * # Safety
+ *
+ * `sim` must come from [`antventure_new`] and not be freed yet
+ */
+bool antventure_step_n(struct AntventureSim *sim, uint64_t steps);
+
+/**
+ * Steps done so far
+ *
+ * # Safety
+ *
+ * `sim` must come from [`antventure_new`] and not be freed yet
+ */
+uint64_t antventure_steps(const struct AntventureSim *sim);
+
+/**
+ * Cells on a side of the board
+ *
+ * # Safety
+ *
+ * `sim` must come from [`antventure_new`] and not be freed yet
+ */
+size_t antventure_size(const struct AntventureSim *sim);
+
+/**
+ * Colors cells can have, from white `0` up
+ *
+ * # Safety
+ *
+ * `sim` must come from [`antventure_new`] and not be freed yet
+ */
+size_t antventure_colors(const struct AntventureSim *sim);
+
+/**
+ * Color of every cell in row-major order, `size * size` bytes from white `0` up.
+ * Read-only, and good until the next step or free
+ *
+ * # Safety
+ *
+ * `sim` must come from [`antventure_new`] and not be freed yet
+ */
+const uint8_t *antventure_map_ptr(const struct AntventureSim *sim);
+
+/**
+ * Free a simulation, null does nothing
+ *
+ * # Safety
+ *
+ * `sim` must come from [`antventure_new`] and not be freed yet
+ */
+void antventure_free(struct AntventureSim *sim);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ANTVENTURE_H */
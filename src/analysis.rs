@@ -0,0 +1,413 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, BufRead, Read},
+};
+
+use png::{Decoder, DecodingError, Transformations};
+
+use crate::{ControlHandle, Map, Pos};
+
+/// Summary of a black and white board, built up one row at a time
+/// so a board never has to be in memory as a whole
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BoardStats {
+    pub width: usize,
+    pub height: usize,
+    pub black: usize,
+    /// Smallest rectangle holding every black cell, as inclusive `(min, max)` corners
+    pub bounding_box: Option<(Pos, Pos)>,
+    /// Cells equal to their mirror image across the vertical center line
+    pub mirrored: usize,
+}
+
+impl BoardStats {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            ..Default::default()
+        }
+    }
+
+    /// Account for the next row, `true` is a black cell
+    pub fn push_row(&mut self, row: &[bool]) {
+        debug_assert_eq!(row.len(), self.width);
+        let y = self.height as isize;
+
+        let first = row.iter().position(|b| *b);
+        let last = row.iter().rposition(|b| *b);
+        if let (Some(first), Some(last)) = (first, last) {
+            let (min, max) = self
+                .bounding_box
+                .get_or_insert((Pos::new(first as _, y), Pos::new(last as _, y)));
+            min.x = min.x.min(first as _);
+            max.x = max.x.max(last as _);
+            max.y = y;
+        }
+
+        self.black += row.iter().filter(|b| **b).count();
+        self.mirrored += row
+            .iter()
+            .zip(row.iter().rev())
+            .filter(|(a, b)| a == b)
+            .count();
+        self.height += 1;
+    }
+
+    /// Account for the next row given 64 cells a word, the first cell in the highest
+    /// bit of the first word and set bits black. Bits past the width must be clear
+    pub fn push_words(&mut self, black: &[u64]) {
+        debug_assert_eq!(black.len(), self.width.div_ceil(64));
+        let y = self.height as isize;
+
+        let first = black.iter().position(|w| *w != 0);
+        let last = black.iter().rposition(|w| *w != 0);
+        if let (Some(first), Some(last)) = (first, last) {
+            let first = first * 64 + black[first].leading_zeros() as usize;
+            let last = last * 64 + 63 - black[last].trailing_zeros() as usize;
+            let (min, max) = self
+                .bounding_box
+                .get_or_insert((Pos::new(first as _, y), Pos::new(last as _, y)));
+            min.x = min.x.min(first as _);
+            max.x = max.x.max(last as _);
+            max.y = y;
+        }
+
+        self.black += black.iter().map(|w| w.count_ones() as usize).sum::<usize>();
+        // The row back to front starts `pad` bits into its last word reversed, shifted
+        // over it lines up with the row, the clear bits past both count as equal
+        let pad = black.len() * 64 - self.width;
+        let reversed = |i: usize| {
+            black
+                .get(black.len().wrapping_sub(i + 1))
+                .map_or(0, |w| w.reverse_bits())
+        };
+        let equal = (0..black.len())
+            .map(|i| {
+                let mirror = match pad {
+                    0 => reversed(i),
+                    _ => reversed(i) << pad | reversed(i + 1) >> (64 - pad),
+                };
+                (!(black[i] ^ mirror)).count_ones() as usize
+            })
+            .sum::<usize>();
+        self.mirrored += equal - pad;
+        self.height += 1;
+    }
+
+    /// Stats of a whole map, scanned a word at a time
+    pub fn of_map<const W: usize, const H: usize>(map: &Map<W, H>) -> Self {
+        let mut stats = Self::new(W);
+        let mut black = Vec::with_capacity(W.div_ceil(64));
+        for y in 0..H {
+            black.clear();
+            black.extend(map.row_words(y).map(|word| word.black()));
+            stats.push_words(&black);
+        }
+        stats
+    }
+
+    pub fn cells(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Share of black cells
+    pub fn density(&self) -> f64 {
+        self.black as f64 / self.cells().max(1) as f64
+    }
+
+    /// Share of cells matching their left-right mirror image, `1.0` for a symmetric board
+    pub fn symmetry(&self) -> f64 {
+        self.mirrored as f64 / self.cells().max(1) as f64
+    }
+}
+
+/// Stream a PNG of any color type, pixels darker than mid-gray are black
+///
+/// Once `control` is cancelled the scan stops with the stats of the rows read so far,
+/// as do the other scans
+pub fn analyze_png(reader: impl Read, control: &ControlHandle) -> Result<BoardStats, AnalyzeError> {
+    let mut decoder = Decoder::new(reader);
+    decoder.set_transformations(Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+
+    let width = reader.info().width as usize;
+    let channels = reader.output_color_type().0.samples();
+    let mut stats = BoardStats::new(width);
+    let mut cells = vec![false; width];
+
+    while let Some(row) = reader.next_row()? {
+        if control.is_cancelled() {
+            return Err(AnalyzeError::Cancelled(stats));
+        }
+        for (cell, pixel) in cells.iter_mut().zip(row.data().chunks(channels)) {
+            // Alpha is ignored, gray and RGB are averaged
+            let color = &pixel[..channels.min(3)];
+            let luma = color.iter().map(|c| *c as usize).sum::<usize>() / color.len();
+            *cell = luma < 128;
+        }
+        stats.push_row(&cells);
+    }
+
+    Ok(stats)
+}
+
+/// Stream a plain (`P1`) or binary (`P4`) PBM bitmap
+pub fn analyze_pbm(
+    mut reader: impl BufRead,
+    control: &ControlHandle,
+) -> Result<BoardStats, AnalyzeError> {
+    let magic = read_token(&mut reader)?;
+    let width = read_token(&mut reader)?.parse::<usize>();
+    let height = read_token(&mut reader)?.parse::<usize>();
+    let (Ok(width), Ok(height)) = (width, height) else {
+        return Err(AnalyzeError::Format("bad PBM dimensions".into()));
+    };
+
+    let mut stats = BoardStats::new(width);
+    let mut cells = vec![false; width];
+
+    match magic.as_str() {
+        "P4" => {
+            let mut bytes = vec![0; width.div_ceil(8)];
+            let mut words = vec![0; width.div_ceil(64)];
+            for _ in 0..height {
+                if control.is_cancelled() {
+                    return Err(AnalyzeError::Cancelled(stats));
+                }
+                reader.read_exact(&mut bytes)?;
+                pack_row(&bytes, &mut words, width, true);
+                stats.push_words(&words);
+            }
+        }
+        "P1" => {
+            for _ in 0..height {
+                if control.is_cancelled() {
+                    return Err(AnalyzeError::Cancelled(stats));
+                }
+                for cell in cells.iter_mut() {
+                    *cell = read_digit(&mut reader)?;
+                }
+                stats.push_row(&cells);
+            }
+        }
+        _ => return Err(AnalyzeError::Format(format!("not a PBM file ({magic})"))),
+    }
+
+    Ok(stats)
+}
+
+/// Stream headerless 1-bit rows in the same layout [`crate::Map`] keeps in memory:
+/// rows padded to whole bytes, leftmost cell in the highest bit, set bits are white
+pub fn analyze_raw(
+    mut reader: impl Read,
+    width: usize,
+    control: &ControlHandle,
+) -> Result<BoardStats, AnalyzeError> {
+    let mut stats = BoardStats::new(width);
+    let mut bytes = vec![0; width.div_ceil(8)];
+    let mut words = vec![0; width.div_ceil(64)];
+
+    loop {
+        if control.is_cancelled() {
+            return Err(AnalyzeError::Cancelled(stats));
+        }
+        match reader.read_exact(&mut bytes) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        pack_row(&bytes, &mut words, width, false);
+        stats.push_words(&words);
+    }
+
+    Ok(stats)
+}
+
+/// Pack MSB-first bytes into words of black cells for [`BoardStats::push_words`],
+/// `set_is_black` tells what a set bit means
+fn pack_row(bytes: &[u8], words: &mut [u64], width: usize, set_is_black: bool) {
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks(8)) {
+        let mut be = [0; 8];
+        be[..chunk.len()].copy_from_slice(chunk);
+        *word = u64::from_be_bytes(be);
+        if !set_is_black {
+            *word = !*word;
+        }
+    }
+    if let (Some(last), tail @ 1..) = (words.last_mut(), width % 64) {
+        *last &= !(u64::MAX >> tail);
+    }
+}
+
+/// Next whitespace separated PBM header token, skipping `#` comments
+fn read_token(reader: &mut impl BufRead) -> Result<String, AnalyzeError> {
+    let mut token = String::new();
+    let mut in_comment = false;
+
+    loop {
+        let mut byte = [0];
+        if reader.read(&mut byte)? == 0 {
+            break;
+        }
+
+        match byte[0] {
+            b'\n' if in_comment => in_comment = false,
+            _ if in_comment => {}
+            b'#' => in_comment = true,
+            b if b.is_ascii_whitespace() && token.is_empty() => {}
+            // A single whitespace ends the last header token, binary data follows
+            b if b.is_ascii_whitespace() => break,
+            b => token.push(b as char),
+        }
+    }
+
+    match token.is_empty() {
+        true => Err(AnalyzeError::Format("truncated PBM header".into())),
+        false => Ok(token),
+    }
+}
+
+fn read_digit(reader: &mut impl BufRead) -> Result<bool, AnalyzeError> {
+    loop {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        match byte[0] {
+            b'0' => return Ok(false),
+            b'1' => return Ok(true),
+            b if b.is_ascii_whitespace() => continue,
+            b => {
+                return Err(AnalyzeError::Format(format!(
+                    "unexpected {:?} in P1 data",
+                    b as char
+                )))
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AnalyzeError {
+    Io(io::Error),
+    Png(DecodingError),
+    Format(String),
+    /// Stopped by a [`ControlHandle`], with the stats of the rows before that
+    Cancelled(BoardStats),
+}
+
+impl Display for AnalyzeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnalyzeError::Io(e) => write!(f, "{e}"),
+            AnalyzeError::Png(e) => write!(f, "{e}"),
+            AnalyzeError::Format(e) => write!(f, "{e}"),
+            AnalyzeError::Cancelled(stats) => {
+                write!(f, "cancelled after {} rows", stats.height)
+            }
+        }
+    }
+}
+
+impl Error for AnalyzeError {}
+
+impl From<io::Error> for AnalyzeError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<DecodingError> for AnalyzeError {
+    fn from(e: DecodingError) -> Self {
+        Self::Png(e)
+    }
+}
+
+#[test]
+fn pbm_stats() {
+    let plain = "P1\n# a comment\n4 3\n0 0 0 0\n0 1 1 0\n0 0 1 0\n";
+    let control = ControlHandle::new();
+    let stats = analyze_pbm(plain.as_bytes(), &control).unwrap();
+
+    assert_eq!((stats.width, stats.height, stats.black), (4, 3, 3));
+    assert_eq!(stats.bounding_box, Some((Pos::new(1, 1), Pos::new(2, 2))));
+    assert_eq!(stats.mirrored, 10);
+
+    let binary = [
+        b"P4 4 3\n".as_slice(),
+        &[0b0000_0000, 0b0110_0000, 0b0010_0000],
+    ]
+    .concat();
+    assert_eq!(analyze_pbm(binary.as_slice(), &control).unwrap(), stats);
+
+    // Same board as raw Map bytes, where set bits are white
+    let raw = [0b1111_0000, 0b1001_0000, 0b1101_0000];
+    assert_eq!(analyze_raw(raw.as_slice(), 4, &control).unwrap(), stats);
+
+    control.cancel();
+    match analyze_raw(raw.as_slice(), 4, &control) {
+        Err(AnalyzeError::Cancelled(partial)) => {
+            assert_eq!((partial.width, partial.height), (4, 0))
+        }
+        other => panic!("expected a cancelled scan, got {other:?}"),
+    }
+}
+
+#[test]
+fn png_matches_map() {
+    use crate::{save_map_to_file, Ant, Direction, Map};
+
+    let mut map = Map::<64, 64>::new_white();
+    Ant::new(&mut map, Pos::new(32, 32), Direction::North)
+        .unwrap()
+        .walk_until_end();
+
+    let file = std::env::temp_dir().join("antventure_png_matches_map.png");
+    save_map_to_file(&map, &file).unwrap();
+    let stats = analyze_png(std::fs::File::open(&file).unwrap(), &ControlHandle::new()).unwrap();
+    std::fs::remove_file(file).ok();
+
+    assert_eq!((stats.width, stats.height), (64, 64));
+    assert_eq!(stats.black, map.count_black_tiles());
+}
+
+#[test]
+fn words_scan_like_cells() {
+    use crate::{Ant, Direction, RowWord};
+
+    fn by_cells<const W: usize, const H: usize>(map: &Map<W, H>) -> BoardStats {
+        let mut stats = BoardStats::new(W);
+        for row in map.rows() {
+            stats.push_row(&row.map(|white| !white).collect::<Vec<_>>());
+        }
+        stats
+    }
+
+    let mut map = Map::<100, 70>::new_white();
+    Ant::new(&mut map, Pos::new(70, 35), Direction::North)
+        .unwrap()
+        .walk_until_end();
+    assert_eq!(BoardStats::of_map(&map), by_cells(&map));
+    assert_eq!(BoardStats::of_map(&map).black, map.count_black_tiles());
+
+    let words = map.row_words(35).collect::<Vec<_>>();
+    assert_eq!(
+        words.iter().map(|w| (w.x, w.len)).collect::<Vec<_>>(),
+        [(0, 64), (64, 36)]
+    );
+    let tail = RowWord {
+        x: 64,
+        white: u64::MAX << 28,
+        len: 36,
+    };
+    assert_eq!(tail.black(), 0);
+
+    let mut map = Map::<64, 9>::new_white();
+    Ant::new(&mut map, Pos::new(40, 4), Direction::East)
+        .unwrap()
+        .walk_until_end();
+    assert_eq!(BoardStats::of_map(&map), by_cells(&map));
+    assert_eq!(
+        BoardStats::of_map(&Map::<3, 3>::new_white()).symmetry(),
+        1.0
+    );
+}
@@ -0,0 +1,367 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use gif::{Frame, Repeat};
+use png::{BitDepth, ColorType, Encoder};
+
+use crate::{
+    color::tag_srgb,
+    render::{cropped_colors, gray_depth, pack_gray},
+    CellStore, OutputSink,
+};
+
+/// Animated image formats an [`Animation`] can be written as
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+impl AnimationFormat {
+    /// Guess the format from the file extension
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "gif" => Some(Self::Gif),
+            "png" | "apng" => Some(Self::Apng),
+            _ => None,
+        }
+    }
+}
+
+/// Board snapshots taken while the simulation runs, played back in grayscale, or
+/// pictures painted elsewhere, e.g. by a [`LayeredRenderer`](crate::LayeredRenderer),
+/// played back as they are
+///
+/// Every frame goes to the file as it's captured, so long runs don't pile up in memory
+pub struct Animation {
+    width: usize,
+    height: usize,
+    colors: usize,
+    delay_ms: u16,
+    format: AnimationFormat,
+    // Another handle to the file, to fix up the APNG frame count at the end
+    file: File,
+    stream: Option<Stream>,
+    frames: usize,
+    /// Frames are RGBA pixels rather than cell colors
+    rgba: bool,
+}
+
+enum Stream {
+    /// Nothing written yet, the header depends on what the first frame is
+    Pending(BufWriter<File>),
+    Gif(gif::Encoder<BufWriter<File>>),
+    Apng(png::Writer<BufWriter<File>>),
+}
+
+impl Animation {
+    /// Animation of a `width`x`height` board holding `colors` colors written to `file`,
+    /// 10 frames a second
+    pub fn create(
+        file: impl AsRef<Path>,
+        format: AnimationFormat,
+        width: usize,
+        height: usize,
+        colors: usize,
+    ) -> io::Result<Self> {
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(file)?;
+        Ok(Self {
+            width,
+            height,
+            colors,
+            delay_ms: 100,
+            format,
+            stream: Some(Stream::Pending(BufWriter::new(file.try_clone()?))),
+            file,
+            frames: 0,
+            rgba: false,
+        })
+    }
+
+    /// How long every frame stays on screen
+    pub fn with_delay(mut self, ms: u16) -> Self {
+        self.delay_ms = ms;
+        self
+    }
+
+    /// Write the current state of `map` as the next frame
+    pub fn capture<const W: usize, const H: usize>(
+        &mut self,
+        map: &impl CellStore<W, H>,
+    ) -> io::Result<()> {
+        let (cells, width, height) = cropped_colors(map, None);
+        self.capture_cells(cells, width, height)
+    }
+
+    fn capture_cells(&mut self, mut cells: Vec<u8>, width: usize, height: usize) -> io::Result<()> {
+        assert_eq!(
+            (width, height),
+            (self.width, self.height),
+            "map size doesn't match"
+        );
+        assert!(
+            !self.rgba || self.frames == 0,
+            "can't mix cell and RGBA frames"
+        );
+        self.write_frame(&mut cells, false)
+    }
+
+    /// Write a picture of 4 bytes per cell as the next frame
+    ///
+    /// # Panics
+    ///
+    /// If the picture is of another size, or cell frames were captured before
+    pub fn capture_rgba(&mut self, mut rgba: Vec<u8>) -> io::Result<()> {
+        assert_eq!(
+            rgba.len(),
+            self.width * self.height * 4,
+            "size doesn't match"
+        );
+        assert!(
+            self.rgba || self.frames == 0,
+            "can't mix cell and RGBA frames"
+        );
+        self.write_frame(&mut rgba, true)
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    fn write_frame(&mut self, pixels: &mut [u8], rgba: bool) -> io::Result<()> {
+        let stream = match self
+            .stream
+            .take()
+            .expect("animation failed to write before")
+        {
+            Stream::Pending(w) => {
+                self.rgba = rgba;
+                match self.format {
+                    AnimationFormat::Gif => Stream::Gif(self.gif_encoder(w)?),
+                    AnimationFormat::Apng => Stream::Apng(self.apng_writer(w)?),
+                }
+            }
+            stream => stream,
+        };
+        let stream = self.stream.insert(stream);
+
+        match stream {
+            Stream::Gif(encoder) => {
+                // Sizes were checked when the encoder was made
+                let (width, height) = (self.width as u16, self.height as u16);
+                let mut frame = match self.rgba {
+                    // Every frame gets a palette of its own
+                    true => Frame::from_rgba_speed(width, height, pixels, 10),
+                    false => Frame::from_indexed_pixels(width, height, &*pixels, None),
+                };
+                // GIF counts in hundredths of a second
+                frame.delay = self.delay_ms / 10;
+                encoder.write_frame(&frame).map_err(io::Error::other)?;
+            }
+            Stream::Apng(writer) => match self.rgba {
+                true => writer.write_image_data(pixels)?,
+                false => {
+                    let depth = gray_depth(self.colors);
+                    writer.write_image_data(&pack_gray(pixels, self.width, self.colors, depth))?
+                }
+            },
+            Stream::Pending(_) => unreachable!(),
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn gif_encoder(&self, w: BufWriter<File>) -> io::Result<gif::Encoder<BufWriter<File>>> {
+        let too_big = || io::Error::new(io::ErrorKind::InvalidInput, "GIF is at most 65535 wide");
+        let width = u16::try_from(self.width).map_err(|_| too_big())?;
+        let height = u16::try_from(self.height).map_err(|_| too_big())?;
+
+        // Cell colors are palette indices already
+        let darkest = self.colors.saturating_sub(1).max(1);
+        let palette = (0..self.colors)
+            .filter(|_| !self.rgba)
+            .flat_map(|c| [(255 - c * 255 / darkest) as u8; 3])
+            .collect::<Vec<_>>();
+
+        let mut encoder =
+            gif::Encoder::new(w, width, height, &palette).map_err(io::Error::other)?;
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(io::Error::other)?;
+        Ok(encoder)
+    }
+
+    fn apng_writer(&self, w: BufWriter<File>) -> io::Result<png::Writer<BufWriter<File>>> {
+        let mut encoder = Encoder::new(w, self.width as _, self.height as _);
+        encoder.set_color(match self.rgba {
+            true => ColorType::Rgba,
+            false => ColorType::Grayscale,
+        });
+        encoder.set_depth(match self.rgba {
+            true => BitDepth::Eight,
+            false => gray_depth(self.colors),
+        });
+        if self.rgba {
+            tag_srgb(&mut encoder);
+        }
+        // The count isn't known yet, `finish` puts the real one in
+        encoder.set_animated(u32::MAX, 0)?;
+        encoder.set_frame_delay(self.delay_ms, 1000)?;
+        Ok(encoder.write_header()?)
+    }
+
+    /// End the file, looping forever
+    ///
+    /// # Errors
+    ///
+    /// If no frame was captured, or writing fails
+    pub fn finish(mut self) -> io::Result<()> {
+        self.end()
+    }
+
+    fn end(&mut self) -> io::Result<()> {
+        match self.stream.take() {
+            None | Some(Stream::Pending(_)) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "animation has no frames",
+            )),
+            Some(Stream::Gif(encoder)) => encoder.into_inner()?.flush(),
+            Some(Stream::Apng(writer)) => {
+                writer.finish()?;
+                set_frame_count(&mut self.file, self.frames as _)
+            }
+        }
+    }
+}
+
+/// Frames are of cell colors, see [`capture_rgba`](Animation::capture_rgba) for painted ones
+impl OutputSink for Animation {
+    /// # Panics
+    ///
+    /// If the frame is of another size, or RGBA frames were captured before
+    fn accept_frame(&mut self, frame: &crate::Frame, _step: u64) -> io::Result<()> {
+        self.capture_cells(frame.cells.clone(), frame.width, frame.height)
+    }
+
+    /// End the file, see [`finish`](Animation::finish)
+    fn finalize(&mut self) -> io::Result<()> {
+        self.end()
+    }
+
+    fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+/// Overwrite the frame count in the acTL chunk of the PNG in `file`
+fn set_frame_count(file: &mut File, frames: u32) -> io::Result<()> {
+    // Chunks come after the 8 byte signature, each a length, a type, data and a CRC
+    let mut pos = 8;
+    loop {
+        let mut header = [0; 8];
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut header)?;
+        let len = u32::from_be_bytes(header[..4].try_into().unwrap());
+        if &header[4..] == b"acTL" {
+            let mut chunk = [0; 12];
+            chunk[..4].copy_from_slice(b"acTL");
+            file.read_exact(&mut chunk[4..])?;
+            chunk[4..8].copy_from_slice(&frames.to_be_bytes());
+            file.seek(SeekFrom::Start(pos + 8))?;
+            file.write_all(&chunk[4..])?;
+            file.write_all(&crc32(&chunk).to_be_bytes())?;
+            return file.flush();
+        }
+        if &header[4..] == b"IDAT" {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "APNG has no animation control",
+            ));
+        }
+        pos += 12 + len as u64;
+    }
+}
+
+/// CRC of a PNG chunk type and data
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+#[test]
+fn animation_round_trip() {
+    use crate::{Map, MapPos, Pos};
+
+    let mut map = Map::<4, 2>::new_white();
+    let file = std::env::temp_dir().join("antventure_animation_round_trip.gif");
+    for format in [AnimationFormat::Gif, AnimationFormat::Apng] {
+        let file = file.with_extension(match format {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Apng => "png",
+        });
+        let mut animation = Animation::create(&file, format, 4, 2, 2).unwrap();
+        animation.capture(&map).unwrap();
+        map.set(MapPos::validate_pos(Pos::new(1, 1)).unwrap(), false);
+        animation.capture(&map).unwrap();
+        map.set(MapPos::validate_pos(Pos::new(1, 1)).unwrap(), true);
+        assert_eq!(animation.frames(), 2);
+        animation.finish().unwrap();
+
+        let mut frames = Vec::new();
+        match format {
+            AnimationFormat::Gif => {
+                let mut decoder = gif::DecodeOptions::new()
+                    .read_info(File::open(&file).unwrap())
+                    .unwrap();
+                while let Some(frame) = decoder.read_next_frame().unwrap() {
+                    frames.push(frame.buffer.to_vec());
+                }
+            }
+            AnimationFormat::Apng => {
+                let mut reader = png::Decoder::new(File::open(&file).unwrap())
+                    .read_info()
+                    .unwrap();
+                assert_eq!(reader.info().animation_control.unwrap().num_frames, 2);
+                let mut buf = vec![0; reader.output_buffer_size()];
+                while reader.next_frame(&mut buf).is_ok() {
+                    // 1 bit a cell, white is 1
+                    frames.push((0..8).map(|i| !buf[i / 4] >> (7 - i % 4) & 1).collect());
+                }
+            }
+        }
+        std::fs::remove_file(&file).ok();
+        assert_eq!(frames, [vec![0; 8], vec![0, 0, 0, 0, 0, 1, 0, 0]]);
+    }
+
+    let mut animation = Animation::create(&file, AnimationFormat::Gif, 1, 1, 2).unwrap();
+    animation.capture_rgba(vec![255, 0, 0, 255]).unwrap();
+    animation.finish().unwrap();
+    let mut decoder = gif::DecodeOptions::new();
+    decoder.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = decoder.read_info(File::open(&file).unwrap()).unwrap();
+    let frame = decoder.read_next_frame().unwrap().unwrap();
+    assert_eq!(*frame.buffer, [255, 0, 0, 255]);
+    std::fs::remove_file(&file).ok();
+
+    let animation = Animation::create(&file, AnimationFormat::Gif, 1, 1, 2).unwrap();
+    assert!(animation.finish().is_err());
+    std::fs::remove_file(&file).ok();
+}
@@ -0,0 +1,385 @@
+use std::collections::VecDeque;
+
+use crate::{AntventureError, CellStore, Direction, Map, MapPos, Pos, TurmiteRule, TurnAction};
+
+/// Turmite walking over a board, classic Langton's ant unless told otherwise
+// Ant has lifetime because he can mutate map and can't outlive it
+pub struct Ant<'m, const W: usize, const H: usize, M = Map<W, H>> {
+    map: &'m mut M,
+    rule: TurmiteRule,
+    state: usize,
+    pos: MapPos<'m, W, H>,
+    dir: Direction,
+    // Steps taken since walking off the edge, they repaint the edge cell in place
+    off_map: usize,
+    /// See [`TurmiteRule::inverse_table`], `None` for rules that need the history
+    inverse: Option<Vec<(usize, u8)>>,
+    history: Option<History<'m, W, H>>,
+}
+
+/// Last steps of an [`Ant`], oldest first, see [`Ant::with_history`]
+struct History<'m, const W: usize, const H: usize> {
+    steps: VecDeque<Undo<'m, W, H>>,
+    capacity: usize,
+}
+
+/// Everything a step changed, as it was before
+#[derive(Clone, Copy)]
+struct Undo<'m, const W: usize, const H: usize> {
+    pos: MapPos<'m, W, H>,
+    dir: Direction,
+    state: usize,
+    color: u8,
+    off_map: usize,
+}
+
+impl<'m, const W: usize, const H: usize, M: CellStore<W, H>> Ant<'m, W, H, M> {
+    pub fn new(map: &'m mut M, pos: Pos, dir: Direction) -> Result<Self, AntventureError> {
+        Self::with_rule(map, pos, dir, TurmiteRule::langton())
+    }
+
+    /// Spawn an ant following `rule`, a [`Rule`](crate::Rule) or a [`TurmiteRule`]
+    /// starting in state `0`
+    ///
+    /// # Panics
+    ///
+    /// If the rule has more colors than the map can hold
+    pub fn with_rule(
+        map: &'m mut M,
+        pos: Pos,
+        dir: Direction,
+        rule: impl Into<TurmiteRule>,
+    ) -> Result<Self, AntventureError> {
+        let rule = rule.into();
+        assert!(
+            rule.colors() <= M::COLORS,
+            "rule {} needs {} colors but the map holds {}",
+            rule.name().unwrap_or("turmite"),
+            rule.colors(),
+            M::COLORS
+        );
+
+        Ok(Self {
+            pos: MapPos::validate_within(pos, map.size()).map_err(AntventureError::Spawn)?,
+            map,
+            inverse: rule.inverse_table(),
+            rule,
+            state: 0,
+            dir,
+            off_map: 0,
+            history: None,
+        })
+    }
+
+    /// Remember the last `capacity` steps so [`walk_back`](Self::walk_back) can undo
+    /// them whatever the rule, for turmites that aren't [reversible](TurmiteRule::is_reversible)
+    pub fn with_history(mut self, capacity: usize) -> Self {
+        self.history = Some(History {
+            steps: VecDeque::with_capacity(capacity.min(1 << 16)),
+            capacity,
+        });
+        self
+    }
+
+    pub fn pos(&self) -> MapPos<'m, W, H> {
+        self.pos
+    }
+
+    pub fn dir(&self) -> Direction {
+        self.dir
+    }
+
+    pub fn rule(&self) -> &TurmiteRule {
+        &self.rule
+    }
+
+    /// Internal state of the turmite, always `0` for plain rules
+    pub fn state(&self) -> usize {
+        self.state
+    }
+
+    /// Whether the last step kept the ant on the map
+    pub fn is_on_map(&self) -> bool {
+        self.off_map == 0
+    }
+
+    /// Returns whether the ant can walk any further. An ant that left the map
+    /// stays at the edge and keeps turning and repainting the cell it left from
+    pub fn walk(&mut self) -> bool {
+        self.step().1
+    }
+
+    /// One step, returns what happened and whether the ant is still on the map
+    fn step(&mut self) -> (StepEvent<'m, W, H>, bool) {
+        let pos = self.pos;
+        let color = self.map.color(pos);
+        if let Some(history) = &mut self.history {
+            if history.steps.len() == history.capacity {
+                history.steps.pop_front();
+            }
+            if history.capacity > 0 {
+                history.steps.push_back(Undo {
+                    pos,
+                    dir: self.dir,
+                    state: self.state,
+                    color,
+                    off_map: self.off_map,
+                });
+            }
+        }
+        let transition = self.rule.transition(self.state, color);
+        let flipped_to = transition.write;
+        self.map.set_color(pos, flipped_to);
+        self.state = transition.next;
+
+        let (dir, Pos { x: dx, y: dy }) = match transition.action {
+            TurnAction::Turn(turn) => self.dir.turned(turn.into()),
+            TurnAction::Face(dir) => (dir, dir.to_shift()),
+        };
+        self.dir = dir;
+
+        let event = StepEvent {
+            pos,
+            dir,
+            flipped_to,
+        };
+
+        // Once off the map it stays off, repainting the edge cell it left from
+        let next = MapPos::validate_within(pos + Pos::new(dx, dy), self.map.size());
+        let (Ok(pos), 0) = (next, self.off_map) else {
+            self.off_map += 1;
+            return (event, false);
+        };
+
+        self.pos = pos;
+        (event, true)
+    }
+
+    /// Undo the last step: step back, turn back and give the cell its old color.
+    /// Returns the cell with the color it got back and the direction the ant faces
+    /// again, `None` if there is nothing to undo
+    ///
+    /// Reversible rules walk back as far as asked, past the start too, in which case
+    /// the cells behind the ant get whatever colors would have led to where it started.
+    /// Other turmites go back as many steps as the [history](Self::with_history) holds
+    ///
+    /// ```
+    /// use antventure::{Ant, Direction, Map, Pos};
+    ///
+    /// let mut map = Map::<64, 64>::new_white();
+    /// let mut ant = Ant::new(&mut map, Pos::new(32, 32), Direction::North).unwrap();
+    /// ant.walk_at_most(1000);
+    /// for _ in 0..1000 {
+    ///     ant.walk_back();
+    /// }
+    /// assert_eq!((Pos::from(ant.pos()), ant.dir()), (Pos::new(32, 32), Direction::North));
+    /// assert_eq!(map.black_count(), 0);
+    /// ```
+    pub fn walk_back(&mut self) -> Option<StepEvent<'m, W, H>> {
+        if let Some(undo) = self.history.as_mut().and_then(|h| h.steps.pop_back()) {
+            self.map.set_color(undo.pos, undo.color);
+            self.pos = undo.pos;
+            self.dir = undo.dir;
+            self.state = undo.state;
+            self.off_map = undo.off_map;
+            return Some(StepEvent {
+                pos: undo.pos,
+                dir: undo.dir,
+                flipped_to: undo.color,
+            });
+        }
+
+        let inverse = self.inverse.as_ref()?;
+        // A step off the map left the ant where it was
+        let pos = match self.off_map {
+            0 => {
+                let Pos { x: dx, y: dy } = self.dir.to_shift();
+                MapPos::validate_within(self.pos + Pos::new(-dx, -dy), self.map.size()).ok()?
+            }
+            _ => self.pos,
+        };
+        let written = self.map.color(pos);
+        let (state, color) = inverse[self.state * self.rule.colors() + written as usize];
+        let TurnAction::Turn(turn) = self.rule.transition(state, color).action else {
+            unreachable!("rules facing compass directions have no inverse table");
+        };
+
+        self.map.set_color(pos, color);
+        self.pos = pos;
+        self.dir = turn.inverse().apply(self.dir);
+        self.state = state;
+        self.off_map = self.off_map.saturating_sub(1);
+        Some(StepEvent {
+            pos,
+            dir: self.dir,
+            flipped_to: color,
+        })
+    }
+
+    /// Step by step walk, ends after the step that takes the ant off the map
+    ///
+    /// ```
+    /// use antventure::{Ant, Direction, Map, Pos};
+    ///
+    /// let mut map = Map::<64, 64>::new_white();
+    /// let mut ant = Ant::new(&mut map, Pos::new(32, 32), Direction::North).unwrap();
+    /// let first_whitened = ant.steps().position(|e| e.flipped_to == 0);
+    /// assert_eq!(first_whitened, Some(4));
+    /// ```
+    pub fn steps(&mut self) -> Steps<'_, 'm, W, H, M> {
+        Steps {
+            ant: self,
+            done: false,
+        }
+    }
+
+    pub fn walk_until_end(&mut self) {
+        while self.walk() {}
+    }
+
+    /// Walk until the ant leaves the map or `steps` steps are done,
+    /// returns whether the ant is still on the map
+    pub fn walk_at_most(&mut self, steps: usize) -> bool {
+        (0..steps).all(|_| self.walk())
+    }
+}
+
+/// A single step of an [`Ant`]
+#[derive(Clone, Copy)]
+pub struct StepEvent<'m, const W: usize, const H: usize> {
+    /// Cell the ant stood on and repainted
+    pub pos: MapPos<'m, W, H>,
+    /// Direction after the turn, the ant moves this way
+    pub dir: Direction,
+    /// New color of the cell
+    pub flipped_to: u8,
+}
+
+/// Iterator over the steps of an [`Ant`], see [`Ant::steps`]
+pub struct Steps<'a, 'm, const W: usize, const H: usize, M> {
+    ant: &'a mut Ant<'m, W, H, M>,
+    done: bool,
+}
+
+impl<'a, 'm, const W: usize, const H: usize, M: CellStore<W, H>> Iterator
+    for Steps<'a, 'm, W, H, M>
+{
+    type Item = StepEvent<'m, W, H>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let (event, on_map) = self.ant.step();
+        self.done = !on_map;
+        Some(event)
+    }
+}
+
+#[test]
+fn check_map_bounds() {
+    let mut map = Map::<1, 1>::new_white();
+
+    let mut ant = Ant::new(&mut map, Pos::new(0, 0), Direction::North)
+        .expect("Can't spawn ant on invalid position");
+
+    assert!(!ant.walk()); // ant can't go any further
+}
+
+#[test]
+fn steps_end_off_the_map() {
+    let mut map = Map::<1, 1>::new_white();
+    let mut ant = Ant::new(&mut map, Pos::new(0, 0), Direction::North).unwrap();
+
+    let events = ant.steps().collect::<Vec<_>>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].flipped_to, 1);
+    // Off the map it repaints the edge cell it left from once per walk
+    assert_eq!(ant.steps().count(), 1);
+}
+
+#[test]
+fn two_color_rule_on_color_map_matches_bit_map() {
+    use crate::{ColorMap, Rule};
+
+    let mut map = Map::<64, 64>::new_white();
+    Ant::new(&mut map, Pos::new(32, 32), Direction::North)
+        .unwrap()
+        .walk_until_end();
+
+    let mut colors = ColorMap::<64, 64>::new_white();
+    Ant::with_rule(
+        &mut colors,
+        Pos::new(32, 32),
+        Direction::North,
+        Rule::default(),
+    )
+    .unwrap()
+    .walk_until_end();
+
+    assert_eq!(colors.count_colors(2)[1], map.count_black_tiles());
+}
+
+#[test]
+fn walks_back_exactly() {
+    use crate::{ColorMap, Rule, Transition, TurmiteTurn};
+
+    // Off the edge of a small map and back
+    let mut map = ColorMap::<8, 8>::new_white();
+    let rule = "LRRL".parse::<Rule>().unwrap();
+    let mut ant = Ant::with_rule(&mut map, Pos::new(4, 4), Direction::East, rule).unwrap();
+    let mut trail = vec![(Pos::from(ant.pos()), ant.dir())];
+    while ant.walk() {
+        trail.push((Pos::from(ant.pos()), ant.dir()));
+    }
+    assert!(!ant.walk() && !ant.is_on_map());
+    ant.walk_back().unwrap();
+    ant.walk_back().unwrap();
+    assert!(ant.is_on_map());
+    while let Some((pos, dir)) = trail.pop() {
+        assert_eq!((Pos::from(ant.pos()), ant.dir()), (pos, dir));
+        if !trail.is_empty() {
+            ant.walk_back().unwrap();
+        }
+    }
+    drop(ant);
+    assert_eq!(map.count_colored(), 0);
+
+    // Two transitions end in state 1 on color 1, only the history tells them apart
+    let fibonacci = TurmiteRule::new(
+        2,
+        2,
+        [
+            (0, 0, 1, TurmiteTurn::Left, 1),
+            (0, 1, 1, TurmiteTurn::Left, 1),
+        ]
+        .into_iter()
+        .chain([
+            (1, 0, 1, TurmiteTurn::Right, 1),
+            (1, 1, 0, TurmiteTurn::None, 0),
+        ])
+        .map(|(state, color, write, turn, next)| {
+            let action = turn.into();
+            (
+                state,
+                color,
+                Transition {
+                    write,
+                    action,
+                    next,
+                },
+            )
+        }),
+    )
+    .unwrap();
+    assert!(!fibonacci.is_reversible() && TurmiteRule::langton().is_reversible());
+
+    let mut map = Map::<64, 64>::new_white();
+    let mut ant = Ant::with_rule(&mut map, Pos::new(32, 32), Direction::North, fibonacci)
+        .unwrap()
+        .with_history(50);
+    ant.walk_at_most(80);
+    let steps = std::iter::from_fn(|| ant.walk_back()).count();
+    assert_eq!(steps, 50);
+}
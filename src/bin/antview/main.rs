@@ -0,0 +1,852 @@
+mod session;
+
+use std::{
+    cell::Cell,
+    env, fs, iter,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::SystemTime,
+};
+
+use antventure::{
+    Boundary, CellStore, ColorMap, Direction, ImportOptions, MapPos, Observer, Palette, Pattern,
+    Pos, Rule, Simulation,
+};
+use arboard::{Clipboard, ImageData};
+use clap::Parser;
+use image::ColorType;
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
+use session::{Action, Recorder, Session};
+
+/// Map sizes the viewer is built for, like the ones of `serious`
+const SIZES: [usize; 6] = [256, 512, 1024, 2048, 4096, 8192];
+// Side of the window to open with, bigger boards start zoomed out to fit
+const WINDOW: usize = 1024;
+// Zoomed all the way in a cell is 64 pixels a side, all the way out a pixel is 64 cells
+const MAX_ZOOM: f64 = 6.0;
+// A scroll of the wheel zooms by a quarter of a power of two
+const ZOOM_STEP: f64 = 0.25;
+// Side of the squares of cells the zoomed out pictures are redone in when one repaints,
+// as big as a pixel all the way out
+const TILE: usize = 1 << MAX_ZOOM as usize;
+const MAX_SPEED: u64 = 1 << 24;
+// Around the board
+const BACKGROUND: u32 = 0x20_20_40;
+// Most pixels a side of the minimap, and how far it's kept from the corner
+const MINIMAP: usize = 128;
+const MINIMAP_MARGIN: usize = 8;
+// The viewport on the minimap
+const FRAME: u32 = 0xff_d0_20;
+// Between the boards of --versus
+const DIVIDER: u32 = 0x80_80_80;
+
+/// Watch an ant walk in a window, a pixel per cell or as close as you like
+///
+/// Drag to pan and scroll to zoom around the mouse. Space runs or pauses, `n` steps
+/// once while paused, `+`/`-` double or halve the speed, `f` fits the board to the
+/// window, `m` shows or hides the minimap, `p` turns the brush on and off, `r` starts
+/// over, `s` saves the view as a PNG, `c` copies it to the clipboard and escape or `q`
+/// quits. While paused or with the brush a click gives the cell the next color and
+/// dragging paints a line of cells the same, the right button pans then
+#[derive(Parser)]
+struct Args {
+    /// Board to start from, an image whose dark pixels are black cells placed at the top
+    /// left or a Golly .rle pattern in the middle. It's loaded again whenever it's saved,
+    /// and dropping a file on antview in a file manager opens it
+    board: Option<PathBuf>,
+
+    /// Map width and height in cells: 256, 512, 1024, 2048, 4096 or 8192
+    #[arg(long, default_value_t = 1024)]
+    size: usize,
+
+    /// Turn for each cell color, e.g. RL, LLRR or RLU
+    #[arg(long, short, default_value_t = Rule::default())]
+    rule: Rule,
+
+    /// Initial direction: north, east, south or west
+    #[arg(long, short, default_value = "north")]
+    direction: Direction,
+
+    /// Steps per frame to start with
+    #[arg(long, default_value_t = 64)]
+    speed: u64,
+
+    /// Glue opposite edges together so the ant never leaves
+    #[arg(long)]
+    wrap: bool,
+
+    /// Cell colors, e.g. #fff,#000,#36c, or colorblind, gray without one
+    #[arg(long)]
+    palette: Option<Palette>,
+
+    /// Run a second ant with this rule on a copy of the board, side by side and in
+    /// lockstep with the first, to compare them
+    #[arg(long, value_name = "RULE")]
+    versus: Option<Rule>,
+
+    /// Start paused with the brush on, to paint cells before the ant sets off
+    #[arg(long)]
+    paint: bool,
+
+    /// Write what's done in the window to a file, frame by frame, to play it back later
+    #[arg(long, value_name = "FILE")]
+    record_session: Option<PathBuf>,
+
+    /// Play back a recorded session with the options it was recorded with, the window
+    /// takes input again once it's over
+    #[arg(long, value_name = "FILE", conflicts_with = "record_session")]
+    play: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let mut args = Args::parse();
+    let mut session = None;
+    if let Some(path) = &args.play {
+        let loaded = Session::load(path).and_then(|session| {
+            let recorded = iter::once("antview").chain(session.args.iter().map(String::as_str));
+            let args = Args::try_parse_from(recorded).map_err(|e| e.to_string())?;
+            Ok((args, session))
+        });
+        match loaded {
+            Ok((recorded, loaded)) => (args, session) = (recorded, Some(loaded)),
+            Err(e) => {
+                eprintln!("Can't play {}: {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    let colors = iter::once(&args.rule)
+        .chain(&args.versus)
+        .map(Rule::colors)
+        .max();
+    let colors = colors.unwrap_or(2);
+    if args
+        .palette
+        .as_ref()
+        .is_some_and(|p| p.colors.len() < colors)
+    {
+        eprintln!("The palette needs a color for each of the {colors} colors of the rules");
+        return ExitCode::FAILURE;
+    }
+    let result = match args.size {
+        256 => view::<256>(&args, session),
+        512 => view::<512>(&args, session),
+        1024 => view::<1024>(&args, session),
+        2048 => view::<2048>(&args, session),
+        4096 => view::<4096>(&args, session),
+        8192 => view::<8192>(&args, session),
+        size => {
+            eprintln!("Unsupported map size {size}, expected one of {SIZES:?}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Part of the board the window shows
+struct View {
+    width: usize,
+    height: usize,
+    /// Map cell under the top left pixel, fractions included
+    origin: (f64, f64),
+    /// Cells are `2^zoom` pixels a side
+    zoom: f64,
+}
+
+impl View {
+    fn scale(&self) -> f64 {
+        self.zoom.exp2()
+    }
+
+    /// Map cell under a pixel of the window
+    fn cell_at(&self, (x, y): (f32, f32)) -> (f64, f64) {
+        let scale = self.scale();
+        (
+            self.origin.0 + x as f64 / scale,
+            self.origin.1 + y as f64 / scale,
+        )
+    }
+
+    /// Zoom `by` powers of two, keeping the cell under `pixel` where it is
+    fn zoom_at(&mut self, pixel: (f32, f32), by: f64) {
+        let (x, y) = self.cell_at(pixel);
+        self.zoom = (self.zoom + by).clamp(-MAX_ZOOM, MAX_ZOOM);
+        let scale = self.scale();
+        self.origin = (x - pixel.0 as f64 / scale, y - pixel.1 as f64 / scale);
+    }
+
+    /// Biggest zoom showing all of a `size` board, centered
+    fn fit(&mut self, size: usize) {
+        let side = self.width.min(self.height).max(1) as f64;
+        self.zoom = (side / size as f64).log2().clamp(-MAX_ZOOM, MAX_ZOOM);
+        let scale = self.scale();
+        let half = size as f64 / 2.0;
+        self.origin = (
+            half - self.width as f64 / scale / 2.0,
+            half - self.height as f64 / scale / 2.0,
+        );
+    }
+
+    /// Draw the cells, zoomed out a pixel shows the average of the cells it covers
+    fn draw(&self, pyramid: &Pyramid, cells: &[u8], colors: &[u32], pixels: &mut [u32]) {
+        let size = pyramid.size;
+        let scale = self.scale();
+        // Zoomed in between powers of two a pixel covers up to twice the cells of a level
+        let level = (-self.zoom).floor().clamp(0.0, MAX_ZOOM) as usize;
+        let (side, shrunk) = (size >> level, &pyramid.levels[level.max(1) - 1]);
+        let column = |px: usize| (self.origin.0 + px as f64 / scale).floor();
+        let columns = (0..self.width).map(column).collect::<Vec<_>>();
+        for (py, row) in pixels.chunks_exact_mut(self.width).enumerate() {
+            let y = (self.origin.1 + py as f64 / scale).floor();
+            if y < 0.0 || y >= size as f64 {
+                row.fill(BACKGROUND);
+                continue;
+            }
+            let y = y as usize;
+            for (pixel, &x) in row.iter_mut().zip(&columns) {
+                *pixel = match (x >= 0.0 && x < size as f64, level) {
+                    (false, _) => BACKGROUND,
+                    (true, 0) => colors[cells[y * size + x as usize] as usize],
+                    (true, _) => shrunk[(y >> level) * side + (x as usize >> level)],
+                };
+            }
+        }
+    }
+
+    /// The whole board in the bottom right corner with the viewport framed on it, from
+    /// the smallest level of the pyramid at least [`MINIMAP`] pixels a side unless the
+    /// board is smaller. Nothing when the board fits in the window
+    fn draw_minimap(&self, pyramid: &Pyramid, pixels: &mut [u32]) {
+        let size = pyramid.size as f64;
+        let scale = self.scale();
+        let (right, bottom) = (
+            self.origin.0 + self.width as f64 / scale,
+            self.origin.1 + self.height as f64 / scale,
+        );
+        let fits = self.origin.0 <= 0.0 && self.origin.1 <= 0.0 && right >= size;
+        let level = (pyramid.size / MINIMAP).max(2).ilog2().min(MAX_ZOOM as u32) as usize;
+        let side = pyramid.size >> level;
+        if (fits && bottom >= size) || self.width.min(self.height) < side + 2 * MINIMAP_MARGIN {
+            return;
+        }
+
+        let (left, top) = (
+            self.width - side - MINIMAP_MARGIN,
+            self.height - side - MINIMAP_MARGIN,
+        );
+        let shrunk = &pyramid.levels[level - 1];
+        for (y, row) in shrunk.chunks_exact(side).enumerate() {
+            pixels[(top + y) * self.width + left..][..side].copy_from_slice(row);
+        }
+        // The viewport in pixels of the minimap, clamped to it
+        let to_minimap = |cell: f64| (cell / size * side as f64).clamp(0.0, side as f64 - 1.0);
+        let (x0, x1) = (to_minimap(self.origin.0), to_minimap(right));
+        let (y0, y1) = (to_minimap(self.origin.1), to_minimap(bottom));
+        let (x0, x1, y0, y1) = (x0 as usize, x1 as usize, y0 as usize, y1 as usize);
+        for x in x0..=x1 {
+            pixels[(top + y0) * self.width + left + x] = FRAME;
+            pixels[(top + y1) * self.width + left + x] = FRAME;
+        }
+        for y in y0..=y1 {
+            pixels[(top + y) * self.width + left + x0] = FRAME;
+            pixels[(top + y) * self.width + left + x1] = FRAME;
+        }
+    }
+
+    /// Cover the pixels of a cell with `color`, at least one pixel when zoomed out
+    fn mark(&self, cell: Pos, color: u32, pixels: &mut [u32]) {
+        let scale = self.scale();
+        let x0 = ((cell.x as f64 - self.origin.0) * scale).floor();
+        let y0 = ((cell.y as f64 - self.origin.1) * scale).floor();
+        let side = scale.max(1.0);
+        for y in y0 as isize..(y0 + side) as isize {
+            for x in x0 as isize..(x0 + side) as isize {
+                if (0..self.width as isize).contains(&x) && (0..self.height as isize).contains(&y) {
+                    pixels[y as usize * self.width + x as usize] = color;
+                }
+            }
+        }
+    }
+}
+
+/// The board shrunk by every power of two up to [`MAX_ZOOM`], each pixel the average
+/// color of the cells it covers, so zoomed out pictures show gray where cells are mixed
+/// instead of whichever cell a pixel happens to land on
+struct Pyramid {
+    size: usize,
+    /// Halved once, twice and so on
+    levels: Vec<Vec<u32>>,
+}
+
+impl Pyramid {
+    fn new(size: usize) -> Self {
+        let levels = (1..=MAX_ZOOM as usize)
+            .map(|level| vec![0; (size >> level) * (size >> level)])
+            .collect();
+        Self { size, levels }
+    }
+
+    /// Redo the tiles with repainted cells
+    fn update(&mut self, cells: &[u8], colors: &[u32], repainted: &Repainted) {
+        let tiles = self.size / TILE;
+        for (i, tile) in repainted.tiles.iter().enumerate() {
+            if tile.replace(false) {
+                self.redo(i % tiles, i / tiles, cells, colors);
+            }
+        }
+    }
+
+    fn redo(&mut self, tx: usize, ty: usize, cells: &[u8], colors: &[u32]) {
+        for level in 1..=MAX_ZOOM as usize {
+            let (span, side) = (TILE >> level, self.size >> level);
+            let (below, rest) = self.levels.split_at_mut(level - 1);
+            let shrunk = &mut rest[0];
+            for y in ty * span..(ty + 1) * span {
+                for x in tx * span..(tx + 1) * span {
+                    let quad = [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| {
+                        let (x, y) = (2 * x + dx, 2 * y + dy);
+                        match below.last() {
+                            Some(below) => below[y * 2 * side + x],
+                            None => colors[cells[y * self.size + x] as usize],
+                        }
+                    });
+                    shrunk[y * side + x] = average(quad);
+                }
+            }
+        }
+    }
+}
+
+/// Squares of [`TILE`] cells repainted since they were last drawn, told by the
+/// simulation or marked by the brush
+struct Repainted {
+    tiles: Vec<Cell<bool>>,
+    /// Tiles in a row
+    side: usize,
+}
+
+impl Repainted {
+    /// Every tile starts repainted, to be drawn the first time
+    fn new(size: usize) -> Self {
+        let side = size / TILE;
+        let tiles = (0..side * side).map(|_| Cell::new(true)).collect();
+        Self { tiles, side }
+    }
+
+    fn mark(&self, x: usize, y: usize) {
+        self.tiles[y / TILE * self.side + x / TILE].set(true);
+    }
+}
+
+impl<const S: usize> Observer<S, S, ColorMap<S, S>> for Repainted {
+    fn on_cell_flip(&mut self, _ant: usize, pos: MapPos<'static, S, S>, _from: u8, _to: u8) {
+        self.mark(pos.x(), pos.y());
+    }
+}
+
+/// Cells from `from` to `to` as a straight line without gaps, both ends included
+fn line(from: Pos, to: Pos) -> impl Iterator<Item = Pos> {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let steps = dx.abs().max(dy.abs());
+    (0..=steps).map(move |i| match steps {
+        0 => from,
+        _ => Pos::new(
+            from.x + (dx * i * 2 + steps).div_euclid(2 * steps),
+            from.y + (dy * i * 2 + steps).div_euclid(2 * steps),
+        ),
+    })
+}
+
+fn average(pixels: [u32; 4]) -> u32 {
+    let channel = |shift: u32| pixels.iter().map(|p| p >> shift & 0xff).sum::<u32>() / 4;
+    channel(16) << 16 | channel(8) << 8 | channel(0)
+}
+
+/// Pixels of the window as RGBA bytes, for images and the clipboard
+fn rgba(pixels: &[u32]) -> Vec<u8> {
+    let bytes = pixels.iter().flat_map(|pixel| {
+        let [_, r, g, b] = pixel.to_be_bytes();
+        [r, g, b, 255]
+    });
+    bytes.collect()
+}
+
+fn rgb([r, g, b]: [u8; 3]) -> u32 {
+    u32::from_be_bytes([0, r, g, b])
+}
+
+/// The board at `path` the way [`Args::board`] says, like `serious --import` reads images
+/// and `serious --load` patterns
+fn load_board<const S: usize>(path: &Path, colors: usize) -> Result<ColorMap<S, S>, String> {
+    let rle = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("rle"));
+    let board = match rle {
+        true => {
+            let rle = fs::read_to_string(path).map_err(|e| e.to_string())?;
+            let pattern = rle.parse::<Pattern>().map_err(|e| e.to_string())?;
+            if pattern.colors() > colors {
+                return Err(format!(
+                    "the pattern has {} colors but the rule only {colors}",
+                    pattern.colors()
+                ));
+            }
+            pattern.to_map()
+        }
+        false => {
+            let image = image::open(path).map_err(|e| e.to_string())?;
+            ColorMap::import(&image, &ImportOptions::default())
+        }
+    };
+    Ok(board)
+}
+
+/// When the board file was last saved, to load it again after that changes
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// The options antview was started with, for a session to play back with, without
+/// the file it's recorded to
+fn recorded_args() -> Vec<String> {
+    let mut args = env::args().skip(1);
+    let mut kept = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--record-session" => drop(args.next()),
+            _ if arg.starts_with("--record-session=") => {}
+            _ => kept.push(arg),
+        }
+    }
+    kept
+}
+
+fn repainted<const S: usize>(sim: &Simulation<S, S, ColorMap<S, S>>) -> &Repainted {
+    sim.observer()
+        .expect("the viewer watches for repainted cells")
+}
+
+/// One board in the window, `--versus` puts a second one to the right of it
+struct Pane<const S: usize> {
+    rule: Rule,
+    sim: Simulation<S, S, ColorMap<S, S>>,
+    pyramid: Pyramid,
+    stopped: bool,
+    /// What the pane shows, copied into the window each frame
+    pixels: Vec<u32>,
+}
+
+impl<const S: usize> Pane<S> {
+    /// Cells of colors the rule has, the others wrap around
+    fn paint(&mut self, pos: MapPos<'_, S, S>, color: u8) {
+        let colors = self.rule.colors() as u8;
+        self.sim.map_mut().set_color(pos, color % colors);
+        repainted(&self.sim).mark(pos.x(), pos.y());
+    }
+}
+
+fn view<const S: usize>(args: &Args, mut session: Option<Session>) -> Result<(), String> {
+    let window_error = |e: minifb::Error| format!("Can't show the window: {e}");
+    let board_error = |path: &Path, e: String| format!("Can't load {}: {e}", path.display());
+    let rules = iter::once(&args.rule).chain(&args.versus);
+    // Shades for the rule with the most colors, boards for the one with the fewest
+    let colors = rules.clone().map(Rule::colors).max().unwrap_or(2);
+    let fewest = rules.clone().map(Rule::colors).min().unwrap_or(2);
+    let palette = args
+        .palette
+        .clone()
+        .unwrap_or_else(|| Palette::gray(colors));
+    let shades = palette.colors.iter().copied().map(rgb).collect::<Vec<_>>();
+    let marker = rgb(palette.marker);
+
+    let boundary = match args.wrap {
+        true => Boundary::Wrap,
+        false => Boundary::Stop,
+    };
+    let start = |board: ColorMap<S, S>, rule: &Rule| {
+        let mut sim = Simulation::new(board, rule.clone())
+            .with_boundary(boundary)
+            .with_observer(Repainted::new(S));
+        let middle = S as isize / 2;
+        sim.spawn(Pos::new(middle, middle), args.direction)
+            .expect("the middle is on the map");
+        sim
+    };
+    let restart = |panes: &mut [Pane<S>], board: &ColorMap<S, S>| {
+        for pane in panes {
+            pane.sim = start(board.clone(), &pane.rule);
+            pane.stopped = false;
+        }
+    };
+    let mut board = match &args.board {
+        Some(path) => load_board(path, fewest).map_err(|e| board_error(path, e))?,
+        None => ColorMap::new_white(),
+    };
+    let mut saved = args.board.as_deref().and_then(modified);
+
+    let count = rules.clone().count();
+    let side = S.min(WINDOW / count);
+    let mut panes = rules
+        .map(|rule| Pane {
+            rule: rule.clone(),
+            sim: start(board.clone(), rule),
+            pyramid: Pyramid::new(S),
+            stopped: false,
+            pixels: vec![0; side * side],
+        })
+        .collect::<Vec<_>>();
+
+    let options = WindowOptions {
+        resize: true,
+        ..WindowOptions::default()
+    };
+    let mut window = Window::new("antview", side * count, side, options).map_err(window_error)?;
+    window.set_target_fps(60);
+
+    // The viewport of every pane, they move together
+    let mut view = View {
+        width: side,
+        height: side,
+        origin: (0.0, 0.0),
+        zoom: 0.0,
+    };
+    view.fit(S);
+    let mut pixels = vec![0; side * count * side];
+    let mut speed = args.speed.clamp(1, MAX_SPEED);
+    let mut paused = args.paint;
+    let mut brush = args.paint;
+    let mut minimap = true;
+    // Screenshots asked for, taken once the frame is drawn
+    let (mut save, mut copy) = (false, false);
+    // Kept open, on X11 the copied picture is gone once nothing holds the clipboard
+    let mut clipboard = None;
+    // Where the mouse was last frame while a button was down, and the color the brush
+    // stroke paints with the cell it got to
+    let mut dragging: Option<(f32, f32)> = None;
+    let mut stroke: Option<(u8, Pos)> = None;
+    let mut recorder = match &args.record_session {
+        Some(path) => Some(
+            Recorder::create(path, &recorded_args())
+                .map_err(|e| format!("Can't record to {}: {e}", path.display()))?,
+        ),
+        None => None,
+    };
+    let record_error = |e: std::io::Error| format!("Can't record the session: {e}");
+    // Where the viewport was after the last frame, moving it is an action too
+    let mut shown = (view.origin, view.zoom);
+
+    for frame in 0.. {
+        if !window.is_open() || window.is_key_down(Key::Escape) || window.is_key_down(Key::Q) {
+            break;
+        }
+        // A session being played back takes no input but screenshots
+        let playing = session.as_ref().is_some_and(|session| !session.is_over());
+        let mut actions = Vec::new();
+        for key in window.get_keys_pressed(KeyRepeat::Yes) {
+            match key {
+                Key::S => save = true,
+                Key::C => copy = true,
+                _ if playing => {}
+                Key::Space => actions.push(Action::Pause),
+                Key::N if paused => actions.push(Action::Step),
+                Key::Equal | Key::NumPadPlus => actions.push(Action::Speed(speed * 2)),
+                Key::Minus | Key::NumPadMinus => actions.push(Action::Speed(speed / 2)),
+                Key::F => view.fit(S),
+                Key::M => actions.push(Action::Minimap),
+                Key::P => actions.push(Action::Brush),
+                Key::R => actions.push(Action::Restart),
+                _ => {}
+            }
+        }
+
+        // Saved again, by an editor or whatever made it. Played back sessions keep to
+        // the board they start with
+        if let Some(path) = args.board.as_ref().filter(|_| session.is_none()) {
+            let now = modified(path);
+            if now != saved {
+                saved = now;
+                match load_board(path, fewest) {
+                    Ok(loaded) => {
+                        board = loaded;
+                        restart(&mut panes, &board);
+                    }
+                    Err(e) => eprintln!("{}", board_error(path, e)),
+                }
+            }
+        }
+
+        let (width, height) = window.get_size();
+        let width = width / count;
+        if (width, height) != (view.width, view.height) && width * height > 0 {
+            let center = (view.width as f32 / 2.0, view.height as f32 / 2.0);
+            let (x, y) = view.cell_at(center);
+            (view.width, view.height) = (width, height);
+            let scale = view.scale();
+            view.origin = (
+                x - width as f64 / scale / 2.0,
+                y - height as f64 / scale / 2.0,
+            );
+            pixels = vec![0; width * count * height];
+            for pane in &mut panes {
+                pane.pixels = vec![0; width * height];
+            }
+        }
+
+        // Over any pane, they all show the same cells
+        let mouse = window
+            .get_mouse_pos(MouseMode::Discard)
+            .filter(|_| !playing)
+            .map(|(x, y)| (x % view.width as f32, y));
+        if let (Some(mouse), Some((_, scroll))) = (mouse, window.get_scroll_wheel()) {
+            view.zoom_at(mouse, scroll.signum() as f64 * ZOOM_STEP);
+        }
+        let left = window.get_mouse_down(MouseButton::Left);
+        let right = window.get_mouse_down(MouseButton::Right);
+        match mouse {
+            Some(mouse) if left && (brush || paused) => {
+                let (x, y) = view.cell_at(mouse);
+                let to = Pos::new(x.floor() as isize, y.floor() as isize);
+                // The mouse skips cells when it moves fast, the stroke doesn't
+                let from = stroke.map_or(to, |(_, last)| last);
+                for pos in line(from, to).filter_map(|pos| MapPos::<S, S>::validate_pos(pos).ok()) {
+                    let next = (panes[0].sim.map().color(pos) + 1) % colors as u8;
+                    let (color, _) = *stroke.get_or_insert((next, to));
+                    actions.push(Action::Paint(pos.into(), color));
+                }
+                stroke = stroke.map(|(color, _)| (color, to));
+            }
+            Some(mouse) if left || right => {
+                if let Some(last) = dragging {
+                    let scale = view.scale();
+                    view.origin.0 -= (mouse.0 - last.0) as f64 / scale;
+                    view.origin.1 -= (mouse.1 - last.1) as f64 / scale;
+                }
+                dragging = Some(mouse);
+            }
+            _ => (dragging, stroke) = (None, None),
+        }
+
+        if playing {
+            actions.extend(session.as_mut().into_iter().flat_map(|s| s.take(frame)));
+        } else if (view.origin, view.zoom) != shown {
+            actions.push(Action::View(view.origin, view.zoom));
+        }
+        for action in actions {
+            if let Some(recorder) = &mut recorder {
+                recorder.record(frame, &action).map_err(record_error)?;
+            }
+            match action {
+                Action::Pause => paused = !paused,
+                Action::Step if paused => {
+                    for pane in &mut panes {
+                        pane.stopped = !pane.sim.step();
+                    }
+                }
+                Action::Step => {}
+                Action::Speed(steps) => speed = steps.clamp(1, MAX_SPEED),
+                Action::Brush => brush = !brush,
+                Action::Minimap => minimap = !minimap,
+                Action::Restart => restart(&mut panes, &board),
+                Action::Paint(pos, color) => {
+                    if let Ok(pos) = MapPos::<S, S>::validate_pos(pos) {
+                        panes.iter_mut().for_each(|pane| pane.paint(pos, color));
+                    }
+                }
+                Action::View(origin, zoom) => {
+                    view.origin = origin;
+                    view.zoom = zoom.clamp(-MAX_ZOOM, MAX_ZOOM);
+                }
+            }
+        }
+        shown = (view.origin, view.zoom);
+        if let Some(recorder) = &mut recorder {
+            recorder.flush().map_err(record_error)?;
+        }
+
+        // In lockstep, an ant that left its map waits for the other one
+        for pane in panes.iter_mut().filter(|pane| !paused && !pane.stopped) {
+            pane.stopped = !pane.sim.run_at_most(speed);
+        }
+
+        for pane in &mut panes {
+            let cells = pane.sim.map().colors();
+            pane.pyramid.update(cells, &shades, repainted(&pane.sim));
+            view.draw(&pane.pyramid, cells, &shades, &mut pane.pixels);
+            for ant in pane.sim.ants().iter().filter(|ant| ant.is_on_map()) {
+                view.mark(ant.pos().into(), marker, &mut pane.pixels);
+            }
+        }
+        blit(&panes, view.width, &mut pixels);
+        let steps = panes[0].sim.steps();
+        if save {
+            let file = format!("antview-{steps}.png");
+            let (width, height) = ((view.width * count) as u32, view.height as u32);
+            match image::save_buffer(&file, &rgba(&pixels), width, height, ColorType::Rgba8) {
+                Ok(()) => println!("Saved the view as {file}"),
+                Err(e) => eprintln!("Can't save {file}: {e}"),
+            }
+            save = false;
+        }
+        if copy {
+            let picture = ImageData {
+                width: view.width * count,
+                height: view.height,
+                bytes: rgba(&pixels).into(),
+            };
+            let copied = match &mut clipboard {
+                Some(clipboard) => Ok(clipboard),
+                None => Clipboard::new().map(|opened| clipboard.insert(opened)),
+            };
+            match copied.and_then(|clipboard| clipboard.set_image(picture)) {
+                Ok(()) => println!("Copied the view to the clipboard"),
+                Err(e) => eprintln!("Can't copy the view: {e}"),
+            }
+            copy = false;
+        }
+        // On the rightmost pane only, the others show the same part of the board
+        if let Some(pane) = panes.last_mut().filter(|_| minimap) {
+            view.draw_minimap(&pane.pyramid, &mut pane.pixels);
+            blit(&panes, view.width, &mut pixels);
+        }
+
+        let state = match (panes.iter().all(|pane| pane.stopped), paused) {
+            (true, _) => "the ant left the map",
+            (false, true) => "paused",
+            (false, false) => "running",
+        };
+        let painting = match (playing, brush || paused) {
+            (true, _) => ", playing back",
+            (false, true) => ", painting",
+            (false, false) => "",
+        };
+        let rules = panes.iter().map(|pane| pane.rule.to_string());
+        window.set_title(&format!(
+            "antview: {} on {S}x{S}, step {steps}, {speed} steps a frame, {state}{painting}",
+            rules.collect::<Vec<_>>().join(" vs "),
+        ));
+        window
+            .update_with_buffer(&pixels, view.width * count, view.height)
+            .map_err(window_error)?;
+    }
+    Ok(())
+}
+
+/// Copy the `width` pixels wide panes into the window side by side, with a line
+/// between them
+fn blit<const S: usize>(panes: &[Pane<S>], width: usize, pixels: &mut [u32]) {
+    let rows = pixels.chunks_exact_mut(width * panes.len());
+    for (y, row) in rows.enumerate() {
+        for (pane, part) in panes.iter().zip(row.chunks_exact_mut(width)) {
+            part.copy_from_slice(&pane.pixels[y * width..][..width]);
+        }
+        for x in (1..panes.len()).map(|i| i * width) {
+            row[x] = DIVIDER;
+        }
+    }
+}
+
+#[test]
+fn pyramid_averages_repainted_tiles() {
+    let size = 2 * TILE;
+    let colors = [rgb([255, 255, 255]), rgb([0, 0, 0])];
+    let mut cells = vec![0; size * size];
+    let (mut pyramid, repainted) = (Pyramid::new(size), Repainted::new(size));
+    pyramid.update(&cells, &colors, &repainted);
+    assert!(pyramid.levels.iter().flatten().all(|&p| p == colors[0]));
+
+    // Black half of the first tile, only that one is redone
+    for row in cells.chunks_exact_mut(size).take(TILE) {
+        row[..TILE / 2].fill(1);
+    }
+    cells[size * size - 1] = 1;
+    repainted.mark(0, 0);
+    pyramid.update(&cells, &colors, &repainted);
+    let top = pyramid.levels.last().unwrap();
+    assert_eq!(
+        top,
+        &[rgb([127, 127, 127]), colors[0], colors[0], colors[0]]
+    );
+    assert_eq!(pyramid.levels[0][size / 2 - 1], colors[0]);
+    assert!(repainted.tiles.iter().all(|tile| !tile.get()));
+}
+
+#[test]
+fn minimap_frames_the_viewport() {
+    let mut view = View {
+        width: 400,
+        height: 300,
+        origin: (0.0, 0.0),
+        zoom: 0.0,
+    };
+    let pyramid = Pyramid::new(256);
+    let mut pixels = vec![BACKGROUND; 400 * 300];
+    view.fit(256);
+    view.draw_minimap(&pyramid, &mut pixels);
+    assert!(!pixels.contains(&FRAME), "the whole board is in the window");
+
+    // A quarter of the board across from its middle, on a 128 pixel minimap
+    view.zoom = 2.0;
+    view.origin = (128.0, 128.0);
+    view.draw_minimap(&pyramid, &mut pixels);
+    let (left, top) = (400 - 128 - MINIMAP_MARGIN, 300 - 128 - MINIMAP_MARGIN);
+    let at = |x: usize, y: usize| pixels[(top + y) * 400 + left + x];
+    assert_eq!(at(64, 64), FRAME);
+    assert_eq!(at(64 + 50, 64), FRAME);
+    assert_eq!(at(65, 65), 0);
+    assert_eq!(at(127, 127), 0);
+}
+
+#[test]
+fn strokes_have_no_gaps() {
+    let cells = |from, to| line(from, to).map(|p| (p.x, p.y)).collect::<Vec<_>>();
+    assert_eq!(cells(Pos::new(3, 3), Pos::new(3, 3)), [(3, 3)]);
+    assert_eq!(
+        cells(Pos::new(0, 0), Pos::new(4, -2)),
+        [(0, 0), (1, 0), (2, -1), (3, -1), (4, -2)]
+    );
+    let long = cells(Pos::new(-5, 7), Pos::new(20, 1));
+    assert_eq!((long[0], long[25], long.len()), ((-5, 7), (20, 1), 26));
+    assert!(long.windows(2).all(|w| (w[1].1 - w[0].1).abs() <= 1));
+}
+
+#[test]
+fn boards_load_from_images_and_patterns() {
+    let rle = std::env::temp_dir().join("antventure_antview_board.rle");
+    fs::write(&rle, "x = 2, y = 1\n2o!\n").unwrap();
+    let board = load_board::<256>(&rle, 2).unwrap();
+    assert_eq!(board.count_colored(), 2);
+    let middle = MapPos::validate_pos(Pos::new(127, 128)).unwrap();
+    assert_eq!(board.color(middle), 1);
+
+    let png = std::env::temp_dir().join("antventure_antview_board.png");
+    image::GrayImage::from_fn(3, 2, |x, _| image::Luma([x as u8 * 100]))
+        .save(&png)
+        .unwrap();
+    let board = load_board::<256>(&png, 2).unwrap();
+    assert_eq!(board.count_colored(), 4);
+
+    let missing = std::env::temp_dir().join("antventure_antview_missing.png");
+    assert!(load_board::<256>(&missing, 2).is_err());
+    assert!(modified(&missing).is_none() && modified(&png).is_some());
+}
+
+#[test]
+fn panes_go_side_by_side() {
+    let pane = |rule: &str, fill: u32| Pane::<256> {
+        rule: rule.parse().unwrap(),
+        sim: Simulation::new(ColorMap::new_white(), rule.parse().unwrap()),
+        pyramid: Pyramid::new(256),
+        stopped: false,
+        pixels: vec![fill; 3 * 2],
+    };
+    let panes = [pane("LR", 1), pane("LLRR", 2)];
+    let mut pixels = vec![0; 6 * 2];
+    blit(&panes, 3, &mut pixels);
+    assert_eq!(pixels, [1, 1, 1, DIVIDER, 2, 2, 1, 1, 1, DIVIDER, 2, 2]);
+}
@@ -0,0 +1,210 @@
+use std::{
+    fmt::{self, Display},
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use antventure::Pos;
+
+// First line of every session file
+const HEADER: &str = "antview session 1";
+
+/// Something done in the window that changes the run or what it shows
+///
+/// Screenshots, quitting and saving the board file again aren't part of it
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Space, run or pause
+    Pause,
+    /// `n` while paused
+    Step,
+    Speed(u64),
+    Brush,
+    Minimap,
+    /// `r`, from the board the viewer started with
+    Restart,
+    Paint(Pos, u8),
+    /// The viewport moved to an origin and zoom, by panning, zooming or a resize
+    View((f64, f64), f64),
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Pause => write!(f, "pause"),
+            Action::Step => write!(f, "step"),
+            Action::Speed(speed) => write!(f, "speed {speed}"),
+            Action::Brush => write!(f, "brush"),
+            Action::Minimap => write!(f, "minimap"),
+            Action::Restart => write!(f, "restart"),
+            Action::Paint(pos, color) => write!(f, "paint {} {} {color}", pos.x, pos.y),
+            Action::View((x, y), zoom) => write!(f, "view {x} {y} {zoom}"),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let mut number = |what: &str| {
+            let word = words.next().ok_or(format!("{name} without {what}"))?;
+            word.parse::<f64>()
+                .map_err(|_| format!("{what} of {name} isn't a number: {word:?}"))
+        };
+        let action = match name {
+            "pause" => Action::Pause,
+            "step" => Action::Step,
+            "speed" => Action::Speed(number("steps")? as u64),
+            "brush" => Action::Brush,
+            "minimap" => Action::Minimap,
+            "restart" => Action::Restart,
+            "paint" => {
+                let (x, y) = (number("x")? as isize, number("y")? as isize);
+                Action::Paint(Pos::new(x, y), number("color")? as u8)
+            }
+            "view" => Action::View((number("x")?, number("y")?), number("zoom")?),
+            _ => return Err(format!("unknown action {name:?}")),
+        };
+        match words.next() {
+            Some(extra) => Err(format!("{name} followed by {extra:?}")),
+            None => Ok(action),
+        }
+    }
+}
+
+/// Writes what's done in the window to a session file as it happens, after the
+/// options the viewer was started with
+pub struct Recorder {
+    file: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, args: &[String]) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "{HEADER}")?;
+        for arg in args {
+            writeln!(file, "arg {arg}")?;
+        }
+        Ok(Self { file })
+    }
+
+    /// Keep `action` done at `frame`, the file is flushed once a frame
+    pub fn record(&mut self, frame: u64, action: &Action) -> io::Result<()> {
+        writeln!(self.file, "{frame} {action}")
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// A recorded session to play back, the options it was started with and every
+/// action by frame
+#[derive(Debug, PartialEq)]
+pub struct Session {
+    pub args: Vec<String>,
+    actions: Vec<(u64, Action)>,
+    played: usize,
+}
+
+impl Session {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        fs::read_to_string(path).map_err(|e| e.to_string())?.parse()
+    }
+
+    /// Actions of `frame`, once
+    pub fn take(&mut self, frame: u64) -> impl Iterator<Item = Action> + '_ {
+        let first = self.played;
+        let due = self.actions[first..]
+            .iter()
+            .take_while(|(at, _)| *at <= frame);
+        self.played += due.count();
+        self.actions[first..self.played]
+            .iter()
+            .map(|(_, action)| action.clone())
+    }
+
+    /// Whether every action was played
+    pub fn is_over(&self) -> bool {
+        self.played == self.actions.len()
+    }
+}
+
+impl FromStr for Session {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate();
+        if lines.next().map(|(_, line)| line.trim()) != Some(HEADER) {
+            return Err(format!("not a session, it doesn't start with {HEADER:?}"));
+        }
+        let mut session = Session {
+            args: Vec::new(),
+            actions: Vec::new(),
+            played: 0,
+        };
+        for (i, line) in lines.filter(|(_, line)| !line.trim().is_empty()) {
+            let at = |e: String| format!("line {}: {e}", i + 1);
+            if let Some(arg) = line.strip_prefix("arg ") {
+                session.args.push(arg.to_owned());
+                continue;
+            }
+            let (frame, action) = line.split_once(' ').unwrap_or((line, ""));
+            let frame = frame
+                .parse::<u64>()
+                .map_err(|_| at(format!("expected a frame number, got {frame:?}")))?;
+            if session
+                .actions
+                .last()
+                .is_some_and(|(last, _)| frame < *last)
+            {
+                return Err(at(format!("frame {frame} comes after a later one")));
+            }
+            session.actions.push((frame, action.parse().map_err(at)?));
+        }
+        Ok(session)
+    }
+}
+
+#[test]
+fn sessions_play_back_what_was_recorded() {
+    let path = std::env::temp_dir().join("antventure_antview_session.txt");
+    let args = ["--size".to_owned(), "512".to_owned()];
+    let actions = [
+        (0, Action::Pause),
+        (0, Action::Paint(Pos::new(-1, 30), 2)),
+        (7, Action::View((10.5, -3.25), -1.75)),
+        (9, Action::Speed(128)),
+        (9, Action::Restart),
+    ];
+    let mut recorder = Recorder::create(&path, &args).unwrap();
+    for (frame, action) in &actions {
+        recorder.record(*frame, action).unwrap();
+    }
+    recorder.flush().unwrap();
+
+    let mut session = Session::load(&path).unwrap();
+    assert_eq!(session.args, args);
+    assert_eq!(session.take(0).count(), 2);
+    assert_eq!(session.take(5).count(), 0);
+    assert_eq!(session.take(8).collect::<Vec<_>>(), [actions[2].1.clone()]);
+    assert!(!session.is_over());
+    assert_eq!(session.take(20).count(), 2);
+    assert!(session.is_over());
+
+    for invalid in [
+        "",
+        "antview session 1\nx pause",
+        "antview session 1\n3 jump",
+        "antview session 1\n3 speed fast",
+        "antview session 1\n3 pause\n2 step",
+        "antview session 1\n3 step 4",
+    ] {
+        assert!(invalid.parse::<Session>().is_err(), "{invalid:?}");
+    }
+}
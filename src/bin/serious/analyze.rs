@@ -0,0 +1,257 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use antventure::{
+    analyze_pbm, analyze_png, analyze_raw, AnalyzeError, BoardStats, CellStore, ColorMap,
+    ControlHandle, Direction, Divergence, Map, MapPos, Pos, Rule, Simulation,
+};
+
+use crate::{
+    config::{parse_cell, parse_size, with_size},
+    i18n::tr,
+};
+
+#[derive(clap::Args)]
+pub struct AnalyzeArgs {
+    /// Board image: PNG, PBM, or raw 1-bit rows (.raw/.bin) with --width
+    #[arg(required_unless_present = "perturb")]
+    file: Option<PathBuf>,
+
+    /// Read the board row by row instead of decoding it whole, for boards bigger than RAM.
+    /// Only PNG, PBM and raw files can be streamed
+    #[arg(long)]
+    stream: bool,
+
+    /// Row width in cells of a raw file
+    #[arg(long)]
+    width: Option<usize>,
+
+    /// Instead of reading a board, run an ant from the map center, repaint the cell x,y
+    /// at step --at and report how the run and its perturbed copy drift apart
+    #[arg(long, value_parser = parse_cell, conflicts_with = "file")]
+    perturb: Option<Pos>,
+
+    /// Step to repaint the perturbed cell at
+    #[arg(long, default_value_t = 0, requires = "perturb")]
+    at: u64,
+
+    /// Steps to follow both branches for after the perturbation
+    #[arg(long, default_value_t = 100_000, requires = "perturb")]
+    steps: u64,
+
+    /// Print the distance every this many steps
+    #[arg(long, default_value_t = 10_000, requires = "perturb")]
+    sample_every: u64,
+
+    /// Map width and height of the perturbed run
+    #[arg(long, value_parser = parse_size, default_value_t = 1024, requires = "perturb")]
+    size: usize,
+
+    /// Turn for each cell color of the perturbed run
+    #[arg(long, short, default_value_t = Rule::default(), requires = "perturb")]
+    rule: Rule,
+
+    /// Initial direction of the perturbed run
+    #[arg(long, short, default_value = "north", requires = "perturb")]
+    direction: Direction,
+}
+
+pub fn run(args: AnalyzeArgs) -> ExitCode {
+    let file = match (&args.file, args.perturb) {
+        (_, Some(cell)) => return perturb(&args, cell),
+        (Some(file), None) => file,
+        (None, None) => unreachable!("clap asks for a file without --perturb"),
+    };
+
+    let stats = match args.stream {
+        true => stream(file, args.width),
+        false => decode(file, args.width),
+    };
+
+    match stats {
+        Ok(stats) => {
+            print_stats(&stats);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!(
+                "{}",
+                tr!(
+                    "cant-analyze",
+                    file = file.display().to_string(),
+                    error = e.to_string()
+                )
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn perturb(args: &AnalyzeArgs, cell: Pos) -> ExitCode {
+    with_size!(args.size, perturb_sized(args, cell)).unwrap_or(ExitCode::FAILURE)
+}
+
+fn perturb_sized<const S: usize>(args: &AnalyzeArgs, cell: Pos) -> ExitCode {
+    let Ok(cell) = MapPos::<S, S>::validate_pos(cell) else {
+        eprintln!("{}", tr!("cant-perturb", x = cell.x, y = cell.y));
+        return ExitCode::FAILURE;
+    };
+
+    let divergence = match args.rule.colors() <= Map::<S, S>::COLORS {
+        true => diverge(Map::<S, S>::new_white(), args, cell),
+        false => diverge(ColorMap::<S, S>::new_white(), args, cell),
+    };
+
+    for (step, distance) in &divergence.distances {
+        println!(
+            "{}",
+            tr!("cells-differ", step = *step, distance = *distance)
+        );
+    }
+    match divergence.parted_at {
+        Some(step) => println!("{}", tr!("ants-parted", step = step)),
+        None => println!("{}", tr!("ants-never-parted")),
+    }
+    ExitCode::SUCCESS
+}
+
+fn diverge<const S: usize, M: CellStore<S, S> + Clone>(
+    map: M,
+    args: &AnalyzeArgs,
+    cell: MapPos<'_, S, S>,
+) -> Divergence {
+    let mut sim = Simulation::new(map, args.rule.clone());
+    let center = S as isize / 2;
+    sim.spawn(Pos::new(center, center), args.direction)
+        .expect("the center is on the map");
+    if !sim.run_at_most(args.at) {
+        println!("{}", tr!("ant-left-at", step = sim.steps()));
+    }
+    Divergence::measure(&sim, cell, args.steps, args.sample_every)
+}
+
+fn stream(file: &Path, width: Option<usize>) -> Result<BoardStats, AnalyzeError> {
+    let reader = BufReader::new(File::open(file)?);
+    // Nothing cancels a scan from the command line, Ctrl-C ends the process anyway
+    let control = ControlHandle::new();
+
+    match extension(file).as_str() {
+        "png" => analyze_png(reader, &control),
+        "pbm" => analyze_pbm(reader, &control),
+        "raw" | "bin" => match width {
+            Some(width) => analyze_raw(reader, width, &control),
+            None => Err(AnalyzeError::Format("raw files need --width".into())),
+        },
+        ext => Err(AnalyzeError::Format(format!("can't stream .{ext} files"))),
+    }
+}
+
+/// Load the whole image with `image`, which reads many more formats
+fn decode(file: &Path, width: Option<usize>) -> Result<BoardStats, AnalyzeError> {
+    if matches!(extension(file).as_str(), "raw" | "bin") {
+        return stream(file, width);
+    }
+
+    let image = image::open(file)
+        .map_err(|e| AnalyzeError::Format(e.to_string()))?
+        .into_luma8();
+
+    let mut stats = BoardStats::new(image.width() as _);
+    for row in image.rows() {
+        let cells = row.map(|p| p.0[0] < 128).collect::<Vec<_>>();
+        stats.push_row(&cells);
+    }
+    Ok(stats)
+}
+
+fn extension(file: &Path) -> String {
+    file.extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+fn print_stats(stats: &BoardStats) {
+    println!(
+        "{}",
+        tr!("stats-size", width = stats.width, height = stats.height)
+    );
+    println!(
+        "{}",
+        tr!(
+            "stats-black",
+            black = stats.black,
+            percent = format!("{:.3}", stats.density() * 100.0)
+        )
+    );
+    match stats.bounding_box {
+        Some((min, max)) => println!(
+            "{}",
+            tr!(
+                "stats-bounds",
+                x0 = min.x,
+                y0 = min.y,
+                x1 = max.x,
+                y1 = max.y,
+                width = max.x - min.x + 1,
+                height = max.y - min.y + 1
+            )
+        ),
+        None => println!("{}", tr!("stats-bounds-empty")),
+    }
+    println!(
+        "{}",
+        tr!(
+            "stats-symmetry",
+            percent = format!("{:.3}", stats.symmetry() * 100.0)
+        )
+    );
+}
+
+#[test]
+fn boards_are_analyzed_streamed_or_decoded() {
+    use crate::{Cli, Command};
+    use clap::Parser;
+
+    let analyze = |args: &[&str]| {
+        let args = ["serious", "analyze"].iter().chain(args);
+        match Cli::try_parse_from(args).map(|cli| cli.command) {
+            Ok(Some(Command::Analyze(args))) => Ok(args),
+            Ok(_) => panic!("not an analyze command"),
+            Err(e) => Err(e),
+        }
+    };
+    let args = analyze(&["board.raw", "--stream", "--width", "8"]).unwrap();
+    assert_eq!(args.file, Some(PathBuf::from("board.raw")));
+    assert!(args.stream);
+    assert_eq!(args.width, Some(8));
+    let args = analyze(&["--perturb", "3,4", "--at", "10", "--size", "256"]).unwrap();
+    assert_eq!(args.perturb, Some(Pos::new(3, 4)));
+    assert_eq!((args.at, args.size), (10, 256));
+    // A board or a perturbed run, never both or neither
+    assert!(analyze(&[]).is_err());
+    assert!(analyze(&["board.png", "--perturb", "3,4"]).is_err());
+
+    // Four cells a row, two rows of which three cells are black
+    let file = std::env::temp_dir().join("antventure_boards_are_analyzed.pbm");
+    std::fs::write(&file, "P1\n4 2\n1 0 0 1\n0 0 1 0\n").unwrap();
+    for stats in [stream(&file, None), decode(&file, None)] {
+        let stats = stats.unwrap();
+        assert_eq!((stats.width, stats.height, stats.black), (4, 2, 3));
+    }
+    let raw = file.with_extension("raw");
+    // Set bits are white in raw rows
+    std::fs::write(&raw, [0b0110_0000, 0b1101_0000]).unwrap();
+    assert_eq!(stream(&raw, Some(4)).unwrap().black, 3);
+    assert!(matches!(stream(&raw, None), Err(AnalyzeError::Format(_))));
+    std::fs::remove_file(file).unwrap();
+    std::fs::remove_file(raw).unwrap();
+
+    let args = analyze(&["--perturb", "130,128", "--steps", "500", "--size", "256"]).unwrap();
+    assert!(run(args) == ExitCode::SUCCESS);
+    let args = analyze(&["--perturb", "300,0", "--size", "256"]).unwrap();
+    assert!(run(args) == ExitCode::FAILURE);
+}
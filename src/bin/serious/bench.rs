@@ -0,0 +1,220 @@
+use std::{
+    fs,
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use antventure::{
+    naive, Ant, Boundary, CellStore, ColorMap, Direction, Map, Pos, Rule, Simulation, UnboundedAnt,
+};
+
+use crate::i18n::tr;
+
+#[derive(clap::Args)]
+pub struct BenchArgs {
+    /// Steps of every ant in every workload, more take longer but vary less
+    #[arg(long, default_value_t = 5_000_000)]
+    steps: u64,
+
+    /// Only the workloads with this in their name, e.g. 8192 or sparse
+    #[arg(long)]
+    only: Option<String>,
+
+    /// Also race the naive array of bools against the bit-packed map, an ant walking
+    /// off a 1024x1024 board and starting over from the middle
+    #[arg(long)]
+    naive: bool,
+}
+
+/// A standard run on one backend, returns the ant steps it took and how long
+struct Workload {
+    name: &'static str,
+    backend: &'static str,
+    run: fn(u64) -> (u64, Duration),
+}
+
+// Ants of the multi-ant workload
+const ANTS: usize = 16;
+
+const WORKLOADS: [Workload; 7] = [
+    Workload {
+        name: "1024x1024",
+        backend: "Map",
+        run: fixed::<1024, Map<1024, 1024>, 1>,
+    },
+    Workload {
+        name: "1024x1024",
+        backend: "ColorMap",
+        run: fixed::<1024, ColorMap<1024, 1024>, 1>,
+    },
+    Workload {
+        name: "8192x8192",
+        backend: "Map",
+        run: fixed::<8192, Map<8192, 8192>, 1>,
+    },
+    Workload {
+        name: "8192x8192",
+        backend: "ColorMap",
+        run: fixed::<8192, ColorMap<8192, 8192>, 1>,
+    },
+    Workload {
+        name: "sparse",
+        backend: "SparseMap",
+        run: sparse,
+    },
+    Workload {
+        name: "1024x1024, 16 ants",
+        backend: "Map",
+        run: fixed::<1024, Map<1024, 1024>, ANTS>,
+    },
+    Workload {
+        name: "1024x1024, 16 ants",
+        backend: "ColorMap",
+        run: fixed::<1024, ColorMap<1024, 1024>, ANTS>,
+    },
+];
+
+// Run with --naive, the naive ant can't wrap around so both start over instead
+const NAIVE_WORKLOADS: [Workload; 2] = [
+    Workload {
+        name: "1024x1024, restarts",
+        backend: "naive",
+        run: naive_restarts,
+    },
+    Workload {
+        name: "1024x1024, restarts",
+        backend: "Map",
+        run: map_restarts,
+    },
+];
+
+/// Time the same walks on every backend, printed as a Markdown table to paste
+/// into an issue
+pub fn run(args: BenchArgs) -> ExitCode {
+    let naive = match args.naive {
+        true => &NAIVE_WORKLOADS[..],
+        false => &[],
+    };
+    let workloads = WORKLOADS
+        .iter()
+        .chain(naive)
+        .filter(|w| {
+            args.only
+                .as_ref()
+                .is_none_or(|only| w.name.contains(only.as_str()))
+        })
+        .collect::<Vec<_>>();
+    if workloads.is_empty() {
+        eprintln!("{}", tr!("bench-no-workloads"));
+        return ExitCode::FAILURE;
+    }
+
+    if cfg!(debug_assertions) {
+        eprintln!("{}", tr!("bench-debug-build"));
+    }
+    println!(
+        "{}",
+        tr!(
+            "bench-machine",
+            version = env!("CARGO_PKG_VERSION"),
+            os = std::env::consts::OS,
+            arch = std::env::consts::ARCH,
+            cpus = num_cpus::get()
+        )
+    );
+    println!();
+    println!("{}", tr!("bench-header"));
+    println!("|---|---|--:|--:|--:|");
+    for workload in workloads {
+        let fresh = reset_peak_memory();
+        let (steps, time) = (workload.run)(args.steps);
+        let speed = steps as f64 / time.as_secs_f64().max(f64::EPSILON);
+        let peak = match peak_memory() {
+            Some(bytes) if fresh => format!("{:.1} MiB", bytes as f64 / (1 << 20) as f64),
+            _ => "-".to_owned(),
+        };
+        println!(
+            "| {} | {} | {:.1} M | {:.2} s | {peak} |",
+            workload.name,
+            workload.backend,
+            speed / 1e6,
+            time.as_secs_f64()
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+/// Forget the peak memory so far, so the next one is of the next workload alone.
+/// Linux only, `false` where it can't be done
+fn reset_peak_memory() -> bool {
+    fs::write("/proc/self/clear_refs", "5").is_ok()
+}
+
+/// Most memory the process had in RAM at once since the last reset, in bytes
+fn peak_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib = line
+        .trim_start_matches("VmHWM:")
+        .trim()
+        .strip_suffix("kB")?;
+    Some(kib.trim().parse::<u64>().ok()? * 1024)
+}
+
+/// `N` ants spread over a diagonal of a wrapping board, so none ever stops
+fn fixed<const S: usize, M: CellStore<S, S>, const N: usize>(steps: u64) -> (u64, Duration) {
+    let mut sim = Simulation::new(M::with_colors(vec![0; S * S]), Rule::default())
+        .with_boundary(Boundary::Wrap);
+    for i in 1..=N {
+        let at = (S * i / (N + 1)) as isize;
+        sim.spawn(Pos::new(at, at), Direction::North)
+            .expect("the diagonal is on the map");
+    }
+
+    let started = Instant::now();
+    sim.run_at_most(steps);
+    (sim.steps() * N as u64, started.elapsed())
+}
+
+/// The naive ant from the middle of a board, over and over as it walks off
+fn naive_restarts(steps: u64) -> (u64, Duration) {
+    let mut map = Box::new(naive::Map::<1024, 1024>::new());
+    let started = Instant::now();
+    let mut walked = 0;
+    while walked < steps {
+        let mut ant = naive::Ant::new(Pos::new(512, 512), Direction::North);
+        while walked < steps {
+            walked += 1;
+            if !ant.walk(&mut map) {
+                break;
+            }
+        }
+    }
+    (walked, started.elapsed())
+}
+
+/// The same walks as [`naive_restarts`] on the bit-packed map
+fn map_restarts(steps: u64) -> (u64, Duration) {
+    let mut map = Map::<1024, 1024>::new_white();
+    let started = Instant::now();
+    let mut walked = 0;
+    while walked < steps {
+        let mut ant = Ant::new(&mut map, Pos::new(512, 512), Direction::North)
+            .expect("the middle is on the map");
+        while walked < steps {
+            walked += 1;
+            if !ant.walk() {
+                break;
+            }
+        }
+    }
+    (walked, started.elapsed())
+}
+
+/// A single ant on a board that grows as it goes
+fn sparse(steps: u64) -> (u64, Duration) {
+    let mut ant = UnboundedAnt::new(Pos::new(0, 0), Direction::North);
+    let started = Instant::now();
+    ant.walk_steps(steps as usize);
+    (steps, started.elapsed())
+}
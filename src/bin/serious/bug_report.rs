@@ -0,0 +1,164 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    panic::{self, PanicHookInfo},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use antventure::TrajectoryTail;
+use serde::Serialize;
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::{config::Config, i18n::tr, reproduce_command};
+
+// Trace points kept for the report, the last steps of every ant
+const TAIL: usize = 1000;
+
+/// What a bug report holds, gathered as the run goes so a panic hook can still write it
+struct Bundle {
+    /// `--bug-report`, a crash without it goes to a new file in the working directory
+    path: Option<PathBuf>,
+    command: String,
+    seed: Option<u64>,
+    tail: Option<TrajectoryTail>,
+    progress: Mutex<Progress>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Progress {
+    /// Last step the run was known to reach
+    step: u64,
+    /// Latest digest of the simulation and the step it was taken at
+    digest: Option<(u64, u64)>,
+}
+
+static BUNDLE: OnceLock<Bundle> = OnceLock::new();
+
+/// `report.json` of the bundle, next to the trace in `trace.csv`
+#[derive(Serialize)]
+struct Summary<'a> {
+    version: &'static str,
+    os: &'static str,
+    arch: &'static str,
+    cpus: usize,
+    /// Environment and arguments that repeat the run
+    command: &'a str,
+    seed: Option<u64>,
+    step: u64,
+    /// Digest of the simulation as lowercase hex, to check a repeated run against
+    state_hash: Option<String>,
+    state_hash_step: Option<u64>,
+    panic: Option<String>,
+}
+
+/// Write a bundle if the run panics from now on, and keep a trace for the one
+/// `--bug-report` asks for. Only then, as watching every step slows the run down
+pub fn install(config: &Config) {
+    let bundle = Bundle {
+        path: config.bug_report.clone(),
+        command: reproduce_command(config),
+        seed: config.seed,
+        tail: config
+            .bug_report
+            .is_some()
+            .then(|| TrajectoryTail::new(TAIL, 1)),
+        progress: Mutex::default(),
+    };
+    if BUNDLE.set(bundle).is_err() {
+        return;
+    }
+
+    let default = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default(info);
+        let bundle = BUNDLE.get().expect("set before the hook");
+        let path = bundle.path.clone().unwrap_or_else(|| {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH);
+            let secs = now.map_or(0, |time| time.as_secs());
+            PathBuf::from(format!("antventure-crash-{secs}.zip"))
+        });
+        let file = path.display().to_string();
+        match bundle.write(&path, Some(panic_message(info))) {
+            Ok(()) => eprintln!("{}", tr!("crash-report-saved", file = file)),
+            Err(e) => eprintln!("{}", tr!("cant-save-bug-report", error = e.to_string())),
+        }
+    }));
+}
+
+/// The trace to observe the run with, if `--bug-report` asked for one
+pub fn tail() -> Option<TrajectoryTail> {
+    BUNDLE.get()?.tail.clone()
+}
+
+/// The run got to `step`
+pub fn progress(step: u64) {
+    if let Some(bundle) = BUNDLE.get() {
+        bundle.lock().step = step;
+    }
+}
+
+/// The simulation had `digest` at `step`
+pub fn digest(step: u64, digest: u64) {
+    if let Some(bundle) = BUNDLE.get() {
+        *bundle.lock() = Progress {
+            step,
+            digest: Some((step, digest)),
+        };
+    }
+}
+
+/// Write the bundle of a run that got to its end, where `--bug-report` asked for it
+pub fn save() -> Option<io::Result<&'static Path>> {
+    let bundle = BUNDLE.get()?;
+    let path = bundle.path.as_deref()?;
+    Some(bundle.write(path, None).map(|()| path))
+}
+
+impl Bundle {
+    fn write(&self, path: &Path, panic: Option<String>) -> io::Result<()> {
+        let progress = *self.lock();
+        let summary = Summary {
+            version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            cpus: num_cpus::get(),
+            command: &self.command,
+            seed: self.seed,
+            step: progress.step,
+            state_hash: progress.digest.map(|(_, digest)| format!("{digest:016x}")),
+            state_hash_step: progress.digest.map(|(step, _)| step),
+            panic,
+        };
+
+        let mut zip = ZipWriter::new(File::create(path)?);
+        zip.start_file("report.json", FileOptions::default())?;
+        serde_json::to_writer_pretty(&mut zip, &summary)?;
+        writeln!(zip)?;
+        if let Some(tail) = &self.tail {
+            zip.start_file("trace.csv", FileOptions::default())?;
+            tail.write_csv(&mut zip)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Progress> {
+        self.progress.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// What the panic said and where
+fn panic_message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("panicked");
+    match info.location() {
+        Some(location) => format!("{message} at {location}"),
+        None => message.to_owned(),
+    }
+}
@@ -0,0 +1,1679 @@
+#[cfg(feature = "serve")]
+use std::net::SocketAddr;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use antventure::{
+    Boundary, Channel, Composition, ConflictPolicy, Coordinates, Cue, DeepZoom, Direction, Figure,
+    HeatColors, ImageOrientation, ImportOptions, LifeRule, ObstaclePolicy, Origin, Palette, Pitch,
+    Plot, Pos, Preset, RasterFormat, RawFormat, Relief, Retention, Rule, Schematic, Sound,
+    StepFilter, StochasticRule, Symmetry, Timeline, Volume, YAxis,
+};
+use png::BitDepth;
+
+use crate::i18n::tr;
+
+/// Map sizes the binary is built for, `Map` dimensions are const generics
+pub const SIZES: [usize; 6] = [256, 512, 1024, 2048, 4096, 8192];
+
+/// The smallest of [`SIZES`] a map `size` cells a side fits in, `None` past the biggest
+pub fn board_size(size: usize) -> Option<usize> {
+    SIZES.into_iter().find(|&board| board >= size)
+}
+
+/// `$run::<S>(args..)` with the const `S` equal to `$size`, or `None` after telling which
+/// sizes there are
+macro_rules! with_size {
+    ($size:expr, $run:ident($($arg:expr),* $(,)?)) => {
+        match $size {
+            256 => Some($run::<256>($($arg),*)),
+            512 => Some($run::<512>($($arg),*)),
+            1024 => Some($run::<1024>($($arg),*)),
+            2048 => Some($run::<2048>($($arg),*)),
+            4096 => Some($run::<4096>($($arg),*)),
+            8192 => Some($run::<8192>($($arg),*)),
+            size => {
+                let sizes = format!("{:?}", $crate::config::SIZES);
+                eprintln!("{}", $crate::i18n::tr!("unsupported-size", size = size, sizes = sizes));
+                None
+            }
+        }
+    };
+}
+pub(crate) use with_size;
+
+/// Simulation options, every one falls back to its `ANTVENTURE_*` environment variable
+/// (or `.env` entry)
+#[derive(clap::Args)]
+pub struct Args {
+    /// Read the run from a TOML file first: keys are the ANTVENTURE_* variables in
+    /// lowercase, e.g. size = 2048 or ants = ["10,10,north", "20,20,east"]; the
+    /// environment and other options win over it
+    #[arg(long, value_name = "TOML")]
+    config: Option<PathBuf>,
+
+    /// Start from a famous rule with its map size, steps and palette: langton, rlr-chaos,
+    /// llrr-cardioid, lrrrrrllr-square, llrrrlrlrllr-highway or rll-filled-triangle;
+    /// other options still win over it
+    #[arg(long)]
+    preset: Option<Preset>,
+
+    /// Map width and height in cells, up to 8192. Sizes other than powers of two from
+    /// 256 take two-color rules
+    #[arg(long, value_parser = parse_map_size)]
+    size: Option<usize>,
+
+    /// Spawn column, map center by default
+    #[arg(long, allow_negative_numbers = true)]
+    start_x: Option<isize>,
+
+    /// Spawn row, map center by default
+    #[arg(long, allow_negative_numbers = true)]
+    start_y: Option<isize>,
+
+    /// Which way y grows in the positions typed and printed: down the screen, or up
+    /// like in math
+    #[arg(long)]
+    y_axis: Option<YAxis>,
+
+    /// Cell the positions typed and printed count from: corner (top left, bottom left
+    /// with --y-axis up) or center
+    #[arg(long)]
+    origin: Option<Origin>,
+
+    /// Initial direction: north, east, south or west
+    #[arg(long, short)]
+    direction: Option<Direction>,
+
+    /// Spawn an ant at x,y looking at dir, e.g. --ant 100,200,east; repeat for more ants.
+    /// Replaces the single ant from --start-x/--start-y/--direction
+    #[arg(long = "ant", value_parser = parse_ant)]
+    ants: Vec<(Pos, Direction)>,
+
+    /// Add symmetric copies of every ant: mirror, mirror4, rotate2 or rotate4
+    #[arg(long)]
+    symmetry: Option<Symmetry>,
+
+    /// What ants sharing a cell do: sequential, flip-once or exclusive
+    #[arg(long)]
+    conflict: Option<ConflictPolicy>,
+
+    /// Walk ants far apart from each other on this many threads, 0 for one per core;
+    /// the board comes out the same as on one
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// Turn for each cell color, e.g. RL, LLRR or RLU; the default LR is the classic walk
+    #[arg(long, short)]
+    rule: Option<Rule>,
+
+    /// Draw the turns at random: comma separated turns with their percent chance,
+    /// e.g. L,R90 turns right on black 9 times out of 10; wins over --rule
+    #[arg(long, value_name = "RULE")]
+    stochastic_rule: Option<StochasticRule>,
+
+    /// Run a cellular automaton on the board under the ants, e.g. B3/S23 for Life or
+    /// B2/S/C3 for Brian's Brain
+    #[arg(long, value_name = "RULE")]
+    automaton: Option<LifeRule>,
+
+    /// Steps between generations of --automaton
+    #[arg(long, value_name = "STEPS")]
+    automaton_every: Option<u64>,
+
+    /// Switch to another rule at a step, e.g. --rule-at 10000:RL; repeat for more changes
+    #[arg(long = "rule-at", value_name = "STEP:RULE", value_parser = parse_rule_change)]
+    rule_changes: Vec<(u64, Rule)>,
+
+    /// Play a cue at a step: spawn X,Y DIR, rule RULE, flip X0,Y0 X1,Y1 or palette COLORS,
+    /// e.g. --at "5000: spawn 10,10 north"; repeat for more cues
+    #[arg(long = "at", value_name = "STEP:ACTION", value_parser = parse_cue)]
+    timeline: Vec<(u64, Cue)>,
+
+    /// Stop after this many steps even if the ant is still on the map
+    #[arg(long)]
+    max_steps: Option<usize>,
+
+    /// Stop after this many seconds of walking, e.g. 90 or 0.5, still saving everything
+    #[arg(long, value_parser = parse_seconds)]
+    max_seconds: Option<Duration>,
+
+    /// Walk at most this many steps a second, to follow the run live, e.g. with --serve
+    #[arg(long, value_name = "STEPS", value_parser = parse_pace)]
+    pace: Option<f64>,
+
+    /// Serve the stats and map of the run over HTTP while it walks, e.g. 127.0.0.1:8080,
+    /// with pause, resume and step buttons
+    #[cfg(feature = "serve")]
+    #[arg(long, value_name = "ADDR")]
+    serve: Option<SocketAddr>,
+
+    /// Run without a terminal, e.g. in a container: tell the run is alive every few
+    /// seconds and refuse to open the watch view
+    #[arg(long)]
+    headless: bool,
+
+    /// Stop once the (first) ant builds a highway or cycles, and report the period
+    #[arg(long)]
+    detect_period: bool,
+
+    /// Stop this many steps after the first ant starts building a highway
+    #[arg(long)]
+    stop_after_highway: Option<usize>,
+
+    /// Stop once the visited area is as wide or tall as this share of the map, e.g. 0.5
+    #[arg(long, value_parser = parse_coverage)]
+    stop_at_coverage: Option<f64>,
+
+    /// Steps that have to repeat before a period counts
+    #[arg(long)]
+    period_window: Option<usize>,
+
+    /// Save the board as PREFIX-<phase>-<step>.png whenever the first ant builds a highway,
+    /// cycles, stops growing or reaches the map edge
+    #[arg(long, value_name = "PREFIX")]
+    phase_snapshots: Option<PathBuf>,
+
+    /// Save the board every this many steps, to --snapshot-path
+    #[arg(long)]
+    snapshot_every: Option<u64>,
+
+    /// Where --snapshot-every saves the board, {step} is replaced with the step
+    #[arg(long, value_name = "TEMPLATE")]
+    snapshot_path: Option<String>,
+
+    /// What ants do at the map edge: stop, wrap or reflect; anything but stop needs --max-steps or --max-seconds
+    #[arg(long)]
+    boundary: Option<Boundary>,
+
+    /// Glue opposite map edges together so the image tiles seamlessly, short for --boundary wrap
+    #[arg(long)]
+    wrap: bool,
+
+    /// Mask image whose dark pixels are cells no ant can enter, thresholded like --import
+    #[arg(long, value_name = "MASK")]
+    obstacles: Option<PathBuf>,
+
+    /// What ants do in front of an obstacle: reflect, wait or stop
+    #[arg(long)]
+    obstacle_policy: Option<ObstaclePolicy>,
+
+    /// Walk an endless board instead of a fixed one, needs --max-steps or --max-seconds
+    #[arg(long)]
+    unbounded: bool,
+
+    /// Where to save the final map, .pdf, .eps and .tex (TikZ) give a vector figure,
+    /// .gcode and .hpgl give pen plotter paths, .stl a 3D printable relief,
+    /// .schem and .litematic a Minecraft schematic, .dzi a pyramid of tiles for deep
+    /// zoom viewers, .pbm and .pgm a Netpbm image and .txt ASCII art
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+
+    /// Format of the final map whatever the extension of --output: png, svg, pbm,
+    /// plain-pbm, pgm, plain-pgm or ascii
+    #[arg(long)]
+    format: Option<ImageFormat>,
+
+    /// Save the PNG as RGBA with the command that made it hidden in the pixels,
+    /// `serious extract-params` reads it back
+    #[arg(long)]
+    embed_params: bool,
+
+    /// What to draw: board (cell colors), heatmap (how often each cell was stepped on)
+    /// or path (the board with the ant's way over it, brighter where it went later)
+    #[arg(long)]
+    render: Option<Render>,
+
+    /// Colors of heatmaps: gray or fire
+    #[arg(long)]
+    heat_colors: Option<HeatColors>,
+
+    /// Cell size of plotter paths and STL reliefs in millimeters
+    #[arg(long)]
+    cell_mm: Option<f64>,
+
+    /// Thickness of the STL relief under white cells in millimeters
+    #[arg(long)]
+    relief_base: Option<f64>,
+
+    /// How much black cells of the STL relief stand out in millimeters
+    #[arg(long)]
+    relief_height: Option<f64>,
+
+    /// Pixels on a side of the tiles of .dzi output
+    #[arg(long)]
+    tile_size: Option<usize>,
+
+    /// Minecraft blocks for each cell color of schematics, comma separated,
+    /// e.g. white_concrete,black_concrete
+    #[arg(long, value_delimiter = ',')]
+    blocks: Vec<String>,
+
+    /// Save the whole simulation state here every --checkpoint-every steps and at the end
+    #[arg(long)]
+    checkpoint: Option<PathBuf>,
+
+    /// Steps between checkpoints
+    #[arg(long)]
+    checkpoint_every: Option<usize>,
+
+    /// Keep this many of the newest checkpoints, each saved next to --checkpoint
+    /// with its step in the name
+    #[arg(long)]
+    keep_checkpoints: Option<usize>,
+
+    /// Keep checkpoints spread exponentially over the run, denser towards its end
+    #[arg(long)]
+    exponential_checkpoints: bool,
+
+    /// Delete the oldest checkpoints once they take more than this, e.g. 512M or 20G
+    #[arg(long, value_parser = parse_bytes)]
+    checkpoint_max_bytes: Option<u64>,
+
+    /// Carry on from a saved checkpoint, its map size and rule win over the other options
+    #[arg(long)]
+    resume: Option<PathBuf>,
+
+    /// Check the simulation keeps track of its map and ants right every this many steps
+    /// (1000000 if not given) and crash at the first mismatch; debug builds always do
+    #[arg(long, value_name = "STEPS", num_args = 0..=1, default_missing_value = "1000000")]
+    paranoid: Option<u64>,
+
+    /// Also save the walk as an animated .gif or .png (APNG)
+    #[arg(long)]
+    animate: Option<PathBuf>,
+
+    /// Steps between animation frames, or a ramp like 1:10000,100:1000000,10000 for a
+    /// frame every step up to step 10000, every 100 up to 1000000 and every 10000 after
+    #[arg(long, value_name = "STEPS")]
+    frame_every: Option<FrameSchedule>,
+
+    /// Write raw gray or rgb frames to stdout every --frame-every steps, for
+    /// `ffmpeg -f rawvideo`; messages go to stderr then
+    #[arg(long, value_name = "FORMAT")]
+    raw_frames: Option<RawFormat>,
+
+    /// Bits per pixel of multi-color maps: 2, 4 or 8, the smallest fitting one by default
+    #[arg(long, value_parser = parse_bit_depth)]
+    bit_depth: Option<BitDepth>,
+
+    /// Draw every cell of the PNG as an N by N block of pixels, for small maps
+    #[arg(long, value_name = "N")]
+    scale: Option<usize>,
+
+    /// Mirror the saved board, animation and snapshots: x (left to right), y (top to
+    /// bottom) or xy
+    #[arg(long, value_parser = parse_flip)]
+    flip: Option<(bool, bool)>,
+
+    /// Turn the saved board, animation and snapshots clockwise by 90, 180 or 270 degrees,
+    /// after --flip
+    #[arg(long, value_name = "DEGREES", value_parser = parse_rotation)]
+    rotate: Option<u8>,
+
+    /// Paint the PNG and animation from layers bottom up: cells, heat, path and ants, each
+    /// with =off or =OPACITY, e.g. cells,heat=0.5,ants
+    #[arg(long, value_name = "SPEC")]
+    layers: Option<Composition>,
+
+    /// Save a color PNG with these cell colors, comma separated, e.g. #fff,#000,#36c,
+    /// or colorblind for colors that stay apart with color blindness
+    #[arg(long)]
+    palette: Option<Palette>,
+
+    /// Mark where the ants ended up on a color PNG, gray cells unless --palette is given
+    #[arg(long)]
+    mark_ants: bool,
+
+    /// Color of the ant markers
+    #[arg(long, value_parser = Palette::parse_color)]
+    marker_color: Option<[u8; 3]>,
+
+    /// Draw cells as squares with a pattern for every color between white and the
+    /// darkest one, so they can be told apart in print or with color blindness
+    #[arg(long)]
+    hatch: bool,
+
+    /// Side of a hatched cell in pixels, at least 4
+    #[arg(long)]
+    hatch_size: Option<usize>,
+
+    /// Save only the part of the board the ants walked over, with this many cells
+    /// around it (none without a value)
+    #[arg(long, value_name = "PADDING", num_args = 0..=1, default_missing_value = "0")]
+    crop: Option<usize>,
+
+    /// Caption of vector figures
+    #[arg(long)]
+    title: Option<String>,
+
+    /// Frame vector figures and label cell coordinates
+    #[arg(long)]
+    axes: bool,
+
+    /// Draw a scale bar this many cells long under vector figures
+    #[arg(long)]
+    scale_bar: Option<usize>,
+
+    /// Start from this image instead of an all-white board
+    #[arg(long, short)]
+    import: Option<PathBuf>,
+
+    /// Imported pixels darker than this (0-255) become black cells
+    #[arg(long)]
+    threshold: Option<u8>,
+
+    /// Swap black and white cells of the imported image
+    #[arg(long)]
+    invert: bool,
+
+    /// Scale the imported image to fit the map instead of placing it 1:1
+    #[arg(long)]
+    fit: bool,
+
+    /// Image channel to threshold: luma, red, green, blue or alpha
+    #[arg(long)]
+    channel: Option<Channel>,
+
+    /// Start from a Golly RLE pattern instead of an all-white board
+    #[arg(long)]
+    load: Option<PathBuf>,
+
+    /// Also save the final board as a Golly RLE pattern
+    #[arg(long)]
+    save_rle: Option<PathBuf>,
+
+    /// Also save a small gray PNG of the final board, quick to make for any size
+    #[arg(long)]
+    thumbnail: Option<PathBuf>,
+
+    /// Pixels on the longer side of --thumbnail
+    #[arg(long)]
+    thumbnail_size: Option<usize>,
+
+    /// Shared library of a plugin, outputs with the extension of its image format are
+    /// written by it
+    #[arg(long, value_name = "LIB")]
+    plugin: Option<PathBuf>,
+
+    /// Start from random black cells, each with this chance (0-1)
+    #[arg(long)]
+    random_fill: Option<f64>,
+
+    /// List every file the run writes, with sizes and hashes, in this JSON file
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+
+    /// Write a zip with the command, seed, version, platform, last state hash and the
+    /// last steps of every ant here, to attach to an issue; crashes write one anyway
+    #[arg(long, value_name = "ZIP")]
+    bug_report: Option<PathBuf>,
+
+    /// Also write a single HTML page with the board, stats, charts and the command
+    /// that repeats the run
+    #[arg(long)]
+    report: Option<PathBuf>,
+
+    /// Also write a plain text description of the board, e.g. as alt text of the image
+    #[arg(long)]
+    describe: Option<PathBuf>,
+
+    /// Also write colored cells, turns, visited area and distance over the run to this
+    /// .csv or .json file
+    #[arg(long)]
+    stats: Option<PathBuf>,
+
+    /// Steps between the samples of --stats
+    #[arg(long)]
+    stats_every: Option<u64>,
+
+    /// Only sample --stats in the ticks of this filter, e.g.
+    /// "steps=10000..50000 region=0,0..255,255"
+    #[arg(long, value_name = "FILTER")]
+    stats_filter: Option<StepFilter>,
+
+    /// Also write the position and direction of every ant to this .csv or .ndjson file
+    #[arg(long)]
+    trajectory: Option<PathBuf>,
+
+    /// Steps between the rows of --trajectory and the points of the path
+    #[arg(long)]
+    trajectory_every: Option<u64>,
+
+    /// Only write the --trajectory rows of the ticks of this filter
+    #[arg(long, value_name = "FILTER")]
+    trajectory_filter: Option<StepFilter>,
+
+    /// Latest points of the path drawn by --render path or the path layer
+    #[arg(long, value_name = "POINTS")]
+    path_length: Option<usize>,
+
+    /// Also play the run as a tone into this .wav file
+    #[arg(long, value_name = "WAV")]
+    sonify: Option<PathBuf>,
+
+    /// What sets the pitch of every step of --sonify: turns, distance or color
+    #[arg(long)]
+    sound_pitch: Option<Pitch>,
+
+    /// What sets the loudness of every step of --sonify: constant, flips or distance
+    #[arg(long)]
+    sound_volume: Option<Volume>,
+
+    /// How long every step of --sonify sounds, 44100 samples a second
+    #[arg(long)]
+    samples_per_step: Option<usize>,
+
+    /// Also record every repainted cell into this .antr file, for the replay command
+    #[arg(long, value_name = "ANTR")]
+    record: Option<PathBuf>,
+
+    /// Seed of the random fill and the stochastic rule, a new one is picked and printed
+    /// without it
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+/// What the output image shows
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Render {
+    Board,
+    Heatmap,
+    Path,
+}
+
+impl FromStr for Render {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "board" => Ok(Render::Board),
+            "heatmap" => Ok(Render::Heatmap),
+            "path" => Ok(Render::Path),
+            _ => Err(format!(
+                "unknown render mode {s:?}, expected board, heatmap or path"
+            )),
+        }
+    }
+}
+
+/// Steps between animation frames, changing as the run goes on so a timelapse can show
+/// the first steps one by one and the rest at speed
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FrameSchedule {
+    /// Steps between frames up to a step, in order
+    ramp: Vec<(u64, u64)>,
+    /// Steps between frames after the ramp
+    then: u64,
+}
+
+impl FrameSchedule {
+    pub fn every(steps: u64) -> Self {
+        Self {
+            ramp: Vec::new(),
+            then: steps.max(1),
+        }
+    }
+
+    /// Steps from a frame at `step` to the next one
+    pub fn after(&self, step: u64) -> u64 {
+        let every = self.ramp.iter().find(|(_, until)| step < *until);
+        every.map_or(self.then, |(every, _)| *every)
+    }
+
+    /// First step with a frame past `step`, counting frames from step 0
+    pub fn next(&self, step: u64) -> u64 {
+        let mut from = 0;
+        for &(every, until) in &self.ramp {
+            if step < until {
+                let next = from + ((step - from) / every + 1) * every;
+                return next.min(until);
+            }
+            from = until;
+        }
+        from + ((step - from) / self.then + 1) * self.then
+    }
+}
+
+impl FromStr for FrameSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "expected steps between frames like 1000 or 1:10000,100:1000000,10000, got {s:?}"
+            )
+        };
+        let parts: Vec<_> = s.split(',').map(str::trim).collect();
+        let (then, ramp) = parts.split_last().ok_or_else(invalid)?;
+        let steps = |s: &str| s.parse::<u64>().ok().filter(|&steps| steps > 0);
+        let mut schedule = Self {
+            ramp: Vec::new(),
+            then: steps(then).ok_or_else(invalid)?,
+        };
+        for part in ramp {
+            let (every, until) = part.split_once(':').ok_or_else(invalid)?;
+            let (every, until) = (steps(every.trim()), steps(until.trim()));
+            let (every, until) = every.zip(until).ok_or_else(invalid)?;
+            if schedule.ramp.last().is_some_and(|&(_, last)| until <= last) {
+                return Err(format!("the steps of {s:?} don't go up"));
+            }
+            schedule.ramp.push((every, until));
+        }
+        Ok(schedule)
+    }
+}
+
+/// Image format of the final map, picked by the extension of the output unless given
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ImageFormat {
+    Png,
+    Svg,
+    Raster(RasterFormat),
+}
+
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "png" => Some(ImageFormat::Png),
+            "svg" => Some(ImageFormat::Svg),
+            _ => RasterFormat::from_path(path).map(ImageFormat::Raster),
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "png" => Ok(ImageFormat::Png),
+            "svg" => Ok(ImageFormat::Svg),
+            _ => s.parse().map(ImageFormat::Raster).map_err(|_| {
+                format!(
+                    "unknown image format {s:?}, expected png, svg, pbm, plain-pbm, pgm, \
+                     plain-pgm or ascii"
+                )
+            }),
+        }
+    }
+}
+
+/// Simulation settings, every key can be overridden with an `ANTVENTURE_*` variable
+/// either from the environment or from a `.env` file
+#[derive(Clone)]
+pub struct Config {
+    pub size: usize,
+    pub start_x: Option<isize>,
+    pub start_y: Option<isize>,
+    pub coordinates: Coordinates,
+    pub dir: Direction,
+    pub ants: Vec<(Pos, Direction)>,
+    pub symmetry: Option<Symmetry>,
+    pub conflict: ConflictPolicy,
+    pub threads: usize,
+    pub rule: Rule,
+    pub stochastic_rule: Option<StochasticRule>,
+    pub automaton: Option<LifeRule>,
+    pub automaton_every: u64,
+    /// Sorted by step
+    pub rule_changes: Vec<(u64, Rule)>,
+    pub timeline: Timeline,
+    pub steps: Option<usize>,
+    pub max_seconds: Option<Duration>,
+    pub pace: Option<f64>,
+    #[cfg(feature = "serve")]
+    pub serve: Option<SocketAddr>,
+    pub headless: bool,
+    pub detect_period: bool,
+    pub stop_after_highway: Option<usize>,
+    pub stop_at_coverage: Option<f64>,
+    pub period_window: usize,
+    pub phase_snapshots: Option<PathBuf>,
+    pub snapshot_every: Option<u64>,
+    pub snapshot_path: String,
+    pub boundary: Boundary,
+    pub obstacles: Option<PathBuf>,
+    pub obstacle_policy: ObstaclePolicy,
+    pub unbounded: bool,
+    pub output: PathBuf,
+    pub format: Option<ImageFormat>,
+    pub embed_params: bool,
+    pub render: Render,
+    pub heat_colors: HeatColors,
+    pub bit_depth: Option<BitDepth>,
+    pub scale: usize,
+    pub orientation: ImageOrientation,
+    pub layers: Option<Composition>,
+    pub palette: Option<Palette>,
+    pub mark_ants: bool,
+    pub marker_color: [u8; 3],
+    pub hatch: bool,
+    pub hatch_size: usize,
+    pub crop: Option<usize>,
+    pub checkpoint: Option<PathBuf>,
+    pub checkpoint_every: usize,
+    pub paranoid: Option<u64>,
+    pub retention: Retention,
+    pub resume: Option<PathBuf>,
+    pub animate: Option<PathBuf>,
+    pub frame_every: FrameSchedule,
+    pub raw_frames: Option<RawFormat>,
+    pub figure: Figure,
+    pub plot: Plot,
+    pub relief: Relief,
+    pub schematic: Schematic,
+    pub deep_zoom: DeepZoom,
+    pub import: Option<PathBuf>,
+    pub import_options: ImportOptions,
+    pub load: Option<PathBuf>,
+    pub save_rle: Option<PathBuf>,
+    pub thumbnail: Option<PathBuf>,
+    pub thumbnail_size: usize,
+    pub plugin: Option<PathBuf>,
+    pub random_fill: Option<f64>,
+    pub seed: Option<u64>,
+    pub manifest: Option<PathBuf>,
+    pub bug_report: Option<PathBuf>,
+    pub report: Option<PathBuf>,
+    pub describe: Option<PathBuf>,
+    pub stats: Option<PathBuf>,
+    pub stats_every: u64,
+    pub stats_filter: StepFilter,
+    pub trajectory: Option<PathBuf>,
+    pub trajectory_every: u64,
+    pub trajectory_filter: StepFilter,
+    pub path_length: usize,
+    pub sonify: Option<PathBuf>,
+    pub sound: Sound,
+    pub record: Option<PathBuf>,
+    /// `ANTVENTURE_*` variables no setting goes by, left out with a warning so a stale
+    /// one doesn't stop every run
+    pub unknown_keys: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            size: 1024,
+            start_x: None,
+            start_y: None,
+            coordinates: Coordinates::default(),
+            dir: Direction::North,
+            ants: Vec::new(),
+            symmetry: None,
+            conflict: ConflictPolicy::default(),
+            threads: 1,
+            rule: Rule::default(),
+            stochastic_rule: None,
+            automaton: None,
+            automaton_every: 1,
+            rule_changes: Vec::new(),
+            timeline: Timeline::new(),
+            steps: None,
+            max_seconds: None,
+            pace: None,
+            #[cfg(feature = "serve")]
+            serve: None,
+            headless: false,
+            detect_period: false,
+            stop_after_highway: None,
+            stop_at_coverage: None,
+            period_window: 1024,
+            phase_snapshots: None,
+            snapshot_every: None,
+            snapshot_path: "frame_{step}.png".into(),
+            boundary: Boundary::Stop,
+            obstacles: None,
+            obstacle_policy: ObstaclePolicy::Reflect,
+            unbounded: false,
+            output: PathBuf::from("ant.png"),
+            format: None,
+            embed_params: false,
+            render: Render::Board,
+            heat_colors: HeatColors::default(),
+            bit_depth: None,
+            scale: 1,
+            orientation: ImageOrientation::default(),
+            layers: None,
+            palette: None,
+            mark_ants: false,
+            marker_color: Palette::RED,
+            hatch: false,
+            hatch_size: 8,
+            crop: None,
+            checkpoint: None,
+            checkpoint_every: 100_000_000,
+            paranoid: cfg!(debug_assertions).then_some(1_000_000),
+            retention: Retention::default(),
+            resume: None,
+            animate: None,
+            frame_every: FrameSchedule::every(1000),
+            raw_frames: None,
+            figure: Figure::default(),
+            plot: Plot::default(),
+            relief: Relief::default(),
+            schematic: Schematic::default(),
+            deep_zoom: DeepZoom::default(),
+            import: None,
+            import_options: ImportOptions::default(),
+            load: None,
+            save_rle: None,
+            thumbnail: None,
+            thumbnail_size: 256,
+            plugin: None,
+            random_fill: None,
+            seed: None,
+            manifest: None,
+            bug_report: None,
+            report: None,
+            describe: None,
+            stats: None,
+            stats_every: 1000,
+            stats_filter: StepFilter::default(),
+            trajectory: None,
+            trajectory_every: 1,
+            trajectory_filter: StepFilter::default(),
+            path_length: 1 << 20,
+            sonify: None,
+            sound: Sound::default(),
+            record: None,
+            unknown_keys: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    pub const PREFIX: &'static str = "ANTVENTURE_";
+
+    /// The scenario, environment and `args` on top of each other, telling about the
+    /// variables no setting goes by
+    pub fn load(args: Args) -> Result<Self, String> {
+        let mut config = Self::from_env(&args)?;
+        for key in &config.unknown_keys {
+            eprintln!("{}", tr!("unknown-var", key = key.clone()));
+        }
+        config.apply_args(args);
+        Ok(config)
+    }
+
+    /// Settings of the environment on top of those of the `--config` scenario, or of
+    /// the `ANTVENTURE_CONFIG` file without one, on top of `--preset`
+    pub fn from_env(args: &Args) -> Result<Self, String> {
+        Self::from_sources(args, env::vars())
+    }
+
+    fn from_sources(
+        args: &Args,
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, String> {
+        let env = env.into_iter().collect::<Vec<_>>();
+        let key = format!("{}CONFIG", Self::PREFIX);
+        let scenario = args.config.clone().or_else(|| {
+            let var = env.iter().rfind(|(name, _)| *name == key);
+            var.map(|(_, value)| PathBuf::from(value))
+        });
+        let vars = match scenario {
+            Some(path) => {
+                let toml = fs::read_to_string(&path)
+                    .map_err(|e| format!("can't read {}: {e}", path.display()))?;
+                scenario_vars(&toml).map_err(|e| format!("{}: {e}", path.display()))?
+            }
+            None => Vec::new(),
+        };
+        // Last so it wins over the variable, presets go before every other key anyway
+        let preset = args
+            .preset
+            .map(|preset| (format!("{}PRESET", Self::PREFIX), preset.name.to_owned()));
+        Self::from_vars(vars.into_iter().chain(env).chain(preset))
+    }
+
+    fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> Result<Self, String> {
+        let mut config = Self::default();
+        let vars = vars.into_iter().collect::<Vec<_>>();
+
+        // Applied first, so the other keys win over it whatever their order
+        let preset = format!("{}PRESET", Self::PREFIX);
+        if let Some((_, value)) = vars.iter().rfind(|(key, _)| *key == preset) {
+            config.apply_preset(&parse_var("PRESET", value)?);
+        }
+
+        for (key, value) in vars {
+            let Some(key) = key.strip_prefix(Self::PREFIX) else {
+                continue;
+            };
+
+            match key {
+                // Read before the rest
+                "CONFIG" | "PRESET" => {}
+                "SIZE" => {
+                    config.size = parse_map_size(value.trim())
+                        .map_err(|e| format!("can't parse {}{key}: {e}", Self::PREFIX))?
+                }
+                "START_X" => config.start_x = Some(parse_var(key, &value)?),
+                "START_Y" => config.start_y = Some(parse_var(key, &value)?),
+                "Y_AXIS" => config.coordinates.y_axis = parse_var(key, &value)?,
+                "ORIGIN" => config.coordinates.origin = parse_var(key, &value)?,
+                "DIRECTION" => config.dir = parse_var(key, &value)?,
+                "ANTS" => {
+                    config.ants = value
+                        .split(';')
+                        .map(parse_ant)
+                        .collect::<Result<_, _>>()
+                        .map_err(|e| format!("can't parse {}{key}: {e}", Self::PREFIX))?
+                }
+                "SYMMETRY" => config.symmetry = Some(parse_var(key, &value)?),
+                "CONFLICT" => config.conflict = parse_var(key, &value)?,
+                "THREADS" => config.threads = parse_var(key, &value)?,
+                "RULE" => config.rule = parse_var(key, &value)?,
+                "STOCHASTIC_RULE" => config.stochastic_rule = Some(parse_var(key, &value)?),
+                "AUTOMATON" => config.automaton = Some(parse_var(key, &value)?),
+                "AUTOMATON_EVERY" => config.automaton_every = parse_var(key, &value)?,
+                "RULE_AT" => {
+                    config.rule_changes = value
+                        .split(';')
+                        .map(parse_rule_change)
+                        .collect::<Result<_, _>>()
+                        .map_err(|e| format!("can't parse {}{key}: {e}", Self::PREFIX))?;
+                    config.rule_changes.sort_by_key(|(step, _)| *step);
+                }
+                "TIMELINE" => {
+                    config.timeline = value
+                        .parse()
+                        .map_err(|e| format!("can't parse {}{key}: {e}", Self::PREFIX))?;
+                }
+                "STEPS" => config.steps = Some(parse_var(key, &value)?),
+                "SECONDS" => {
+                    config.max_seconds = Some(
+                        parse_seconds(&value)
+                            .map_err(|e| format!("can't parse {}{key}: {e}", Self::PREFIX))?,
+                    )
+                }
+                "PACE" => {
+                    config.pace = Some(
+                        parse_pace(&value)
+                            .map_err(|e| format!("can't parse {}{key}: {e}", Self::PREFIX))?,
+                    )
+                }
+                #[cfg(feature = "serve")]
+                "SERVE" => config.serve = Some(parse_var(key, &value)?),
+                "HEADLESS" => config.headless = parse_var(key, &value)?,
+                "DETECT_PERIOD" => config.detect_period = parse_var(key, &value)?,
+                "STOP_AFTER_HIGHWAY" => config.stop_after_highway = Some(parse_var(key, &value)?),
+                "STOP_AT_COVERAGE" => {
+                    config.stop_at_coverage = Some(
+                        parse_coverage(&value)
+                            .map_err(|e| format!("can't parse {}{key}: {e}", Self::PREFIX))?,
+                    )
+                }
+                "PERIOD_WINDOW" => config.period_window = parse_var(key, &value)?,
+                "PHASE_SNAPSHOTS" => config.phase_snapshots = Some(PathBuf::from(value)),
+                "SNAPSHOT_EVERY" => config.snapshot_every = Some(parse_var(key, &value)?),
+                "SNAPSHOT_PATH" => config.snapshot_path = value,
+                "BOUNDARY" => config.boundary = parse_var(key, &value)?,
+                "WRAP" => {
+                    if parse_var(key, &value)? {
+                        config.boundary = Boundary::Wrap;
+                    }
+                }
+                "OBSTACLES" => config.obstacles = Some(PathBuf::from(value)),
+                "OBSTACLE_POLICY" => config.obstacle_policy = parse_var(key, &value)?,
+                "UNBOUNDED" => config.unbounded = parse_var(key, &value)?,
+                "OUTPUT" => config.output = PathBuf::from(value),
+                "FORMAT" => config.format = Some(parse_var(key, &value)?),
+                "EMBED_PARAMS" => config.embed_params = parse_var(key, &value)?,
+                "RENDER" => config.render = parse_var(key, &value)?,
+                "HEAT_COLORS" => config.heat_colors = parse_var(key, &value)?,
+                "CHECKPOINT" => config.checkpoint = Some(PathBuf::from(value)),
+                "CHECKPOINT_EVERY" => config.checkpoint_every = parse_var(key, &value)?,
+                "PARANOID" => config.paranoid = Some(parse_var(key, &value)?),
+                "KEEP_CHECKPOINTS" => config.retention.keep_last = Some(parse_var(key, &value)?),
+                "EXPONENTIAL_CHECKPOINTS" => config.retention.exponential = parse_var(key, &value)?,
+                "CHECKPOINT_MAX_BYTES" => {
+                    config.retention.max_bytes = Some(
+                        parse_bytes(&value)
+                            .map_err(|e| format!("can't parse {}{key}: {e}", Self::PREFIX))?,
+                    )
+                }
+                "RESUME" => config.resume = Some(PathBuf::from(value)),
+                "ANIMATE" => config.animate = Some(PathBuf::from(value)),
+                "FRAME_EVERY" => config.frame_every = parse_var(key, &value)?,
+                "RAW_FRAMES" => config.raw_frames = Some(parse_var(key, &value)?),
+                "BIT_DEPTH" => {
+                    config.bit_depth = Some(
+                        parse_bit_depth(&value)
+                            .map_err(|e| format!("can't parse {}{key}: {e}", Self::PREFIX))?,
+                    )
+                }
+                "SCALE" => config.scale = parse_var(key, &value)?,
+                "FLIP" => {
+                    let orientation = &mut config.orientation;
+                    (orientation.flip_x, orientation.flip_y) = parse_flip(&value)
+                        .map_err(|e| format!("can't parse {}{key}: {e}", Self::PREFIX))?;
+                }
+                "ROTATE" => {
+                    config.orientation.quarter_turns = parse_rotation(&value)
+                        .map_err(|e| format!("can't parse {}{key}: {e}", Self::PREFIX))?;
+                }
+                "LAYERS" => config.layers = Some(parse_var(key, &value)?),
+                "PALETTE" => config.palette = Some(parse_var(key, &value)?),
+                "MARK_ANTS" => config.mark_ants = parse_var(key, &value)?,
+                "HATCH" => config.hatch = parse_var(key, &value)?,
+                "HATCH_SIZE" => config.hatch_size = parse_var(key, &value)?,
+                "CROP" => config.crop = Some(parse_var(key, &value)?),
+                "MARKER_COLOR" => {
+                    config.marker_color = Palette::parse_color(&value)
+                        .map_err(|e| format!("can't parse {}{key}: {e}", Self::PREFIX))?
+                }
+                "CELL_MM" => {
+                    config.plot.cell_mm = parse_var(key, &value)?;
+                    config.relief.cell_mm = config.plot.cell_mm;
+                }
+                "RELIEF_BASE" => config.relief.base_mm = parse_var(key, &value)?,
+                "RELIEF_HEIGHT" => config.relief.height_mm = parse_var(key, &value)?,
+                "TILE_SIZE" => config.deep_zoom.tile_size = parse_var(key, &value)?,
+                "BLOCKS" => {
+                    config.schematic.blocks =
+                        value.split(',').map(|b| b.trim().to_owned()).collect()
+                }
+                "TITLE" => config.figure.title = Some(value),
+                "AXES" => config.figure.axes = parse_var(key, &value)?,
+                "SCALE_BAR" => config.figure.scale_bar = Some(parse_var(key, &value)?),
+                "IMPORT" => config.import = Some(PathBuf::from(value)),
+                "THRESHOLD" => config.import_options.threshold = parse_var(key, &value)?,
+                "INVERT" => config.import_options.invert = parse_var(key, &value)?,
+                "FIT" => config.import_options.fit = parse_var(key, &value)?,
+                "CHANNEL" => config.import_options.channel = parse_var(key, &value)?,
+                "LOAD" => config.load = Some(PathBuf::from(value)),
+                "SAVE_RLE" => config.save_rle = Some(PathBuf::from(value)),
+                "THUMBNAIL" => config.thumbnail = Some(PathBuf::from(value)),
+                "THUMBNAIL_SIZE" => config.thumbnail_size = parse_var(key, &value)?,
+                "PLUGIN" => config.plugin = Some(PathBuf::from(value)),
+                "RANDOM_FILL" => config.random_fill = Some(parse_var(key, &value)?),
+                "SEED" => config.seed = Some(parse_var(key, &value)?),
+                "MANIFEST" => config.manifest = Some(PathBuf::from(value)),
+                "BUG_REPORT" => config.bug_report = Some(PathBuf::from(value)),
+                "REPORT" => config.report = Some(PathBuf::from(value)),
+                "DESCRIBE" => config.describe = Some(PathBuf::from(value)),
+                "STATS" => config.stats = Some(PathBuf::from(value)),
+                "STATS_EVERY" => config.stats_every = parse_var(key, &value)?,
+                "STATS_FILTER" => config.stats_filter = parse_var(key, &value)?,
+                "TRAJECTORY" => config.trajectory = Some(PathBuf::from(value)),
+                "TRAJECTORY_EVERY" => config.trajectory_every = parse_var(key, &value)?,
+                "TRAJECTORY_FILTER" => config.trajectory_filter = parse_var(key, &value)?,
+                "PATH_LENGTH" => config.path_length = parse_var(key, &value)?,
+                "SONIFY" => config.sonify = Some(PathBuf::from(value)),
+                "SOUND_PITCH" => config.sound.pitch = parse_var(key, &value)?,
+                "SOUND_VOLUME" => config.sound.volume = parse_var(key, &value)?,
+                "SAMPLES_PER_STEP" => config.sound.samples_per_step = parse_var(key, &value)?,
+                "RECORD" => config.record = Some(PathBuf::from(value)),
+                // Read by main, as messages are translated before the config is loaded
+                "LANG" => {}
+                _ => config.unknown_keys.push(format!("{}{key}", Self::PREFIX)),
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Rule, map size, steps and palette of a preset
+    fn apply_preset(&mut self, preset: &Preset) {
+        self.rule = preset.rule();
+        self.size = preset.size;
+        self.steps = preset.steps;
+        self.palette = preset.palette();
+    }
+
+    /// Command line flags win over the environment, `--preset` is read by
+    /// [`from_env`](Self::from_env) as the environment wins over it
+    pub fn apply_args(&mut self, args: Args) {
+        self.size = args.size.unwrap_or(self.size);
+        self.start_x = args.start_x.or(self.start_x);
+        self.start_y = args.start_y.or(self.start_y);
+        self.coordinates.y_axis = args.y_axis.unwrap_or(self.coordinates.y_axis);
+        self.coordinates.origin = args.origin.unwrap_or(self.coordinates.origin);
+        self.dir = args.direction.unwrap_or(self.dir);
+        if !args.ants.is_empty() {
+            self.ants = args.ants;
+        }
+        self.symmetry = args.symmetry.or(self.symmetry);
+        self.conflict = args.conflict.unwrap_or(self.conflict);
+        self.threads = args.threads.unwrap_or(self.threads);
+        self.rule = args.rule.unwrap_or(std::mem::take(&mut self.rule));
+        self.stochastic_rule = args.stochastic_rule.or(self.stochastic_rule.take());
+        self.automaton = args.automaton.or(self.automaton.take());
+        self.automaton_every = args.automaton_every.unwrap_or(self.automaton_every);
+        if !args.rule_changes.is_empty() {
+            self.rule_changes = args.rule_changes;
+            self.rule_changes.sort_by_key(|(step, _)| *step);
+        }
+        if !args.timeline.is_empty() {
+            self.timeline = args
+                .timeline
+                .into_iter()
+                .fold(Timeline::new(), |timeline, (step, cue)| {
+                    timeline.with_cue(step, cue)
+                });
+        }
+        self.steps = args.max_steps.or(self.steps);
+        self.max_seconds = args.max_seconds.or(self.max_seconds);
+        self.pace = args.pace.or(self.pace);
+        #[cfg(feature = "serve")]
+        {
+            self.serve = args.serve.or(self.serve);
+        }
+        self.headless |= args.headless;
+        self.detect_period |= args.detect_period;
+        self.stop_after_highway = args.stop_after_highway.or(self.stop_after_highway);
+        self.stop_at_coverage = args.stop_at_coverage.or(self.stop_at_coverage);
+        self.period_window = args.period_window.unwrap_or(self.period_window);
+        self.phase_snapshots = args.phase_snapshots.or(self.phase_snapshots.take());
+        self.snapshot_every = args.snapshot_every.or(self.snapshot_every);
+        self.snapshot_path = args
+            .snapshot_path
+            .unwrap_or(std::mem::take(&mut self.snapshot_path));
+        self.boundary = match args.wrap {
+            true => Boundary::Wrap,
+            false => args.boundary.unwrap_or(self.boundary),
+        };
+        self.obstacles = args.obstacles.or(self.obstacles.take());
+        self.obstacle_policy = args.obstacle_policy.unwrap_or(self.obstacle_policy);
+        self.unbounded |= args.unbounded;
+        self.output = args.output.unwrap_or(std::mem::take(&mut self.output));
+        self.format = args.format.or(self.format.take());
+        self.embed_params |= args.embed_params;
+        self.render = args.render.unwrap_or(self.render);
+        self.heat_colors = args.heat_colors.unwrap_or(self.heat_colors);
+        self.bit_depth = args.bit_depth.or(self.bit_depth);
+        self.scale = args.scale.unwrap_or(self.scale);
+        if let Some((flip_x, flip_y)) = args.flip {
+            (self.orientation.flip_x, self.orientation.flip_y) = (flip_x, flip_y);
+        }
+        self.orientation.quarter_turns = args.rotate.unwrap_or(self.orientation.quarter_turns);
+        self.layers = args.layers.or(self.layers.take());
+        self.palette = args.palette.or(self.palette.take());
+        self.mark_ants |= args.mark_ants;
+        self.marker_color = args.marker_color.unwrap_or(self.marker_color);
+        self.hatch |= args.hatch;
+        self.hatch_size = args.hatch_size.unwrap_or(self.hatch_size);
+        self.crop = args.crop.or(self.crop);
+        self.checkpoint = args.checkpoint.or(self.checkpoint.take());
+        self.checkpoint_every = args.checkpoint_every.unwrap_or(self.checkpoint_every);
+        self.paranoid = args.paranoid.or(self.paranoid);
+        let retention = &mut self.retention;
+        retention.keep_last = args.keep_checkpoints.or(retention.keep_last);
+        retention.exponential |= args.exponential_checkpoints;
+        retention.max_bytes = args.checkpoint_max_bytes.or(retention.max_bytes);
+        self.resume = args.resume.or(self.resume.take());
+        self.animate = args.animate.or(self.animate.take());
+        if let Some(schedule) = args.frame_every {
+            self.frame_every = schedule;
+        }
+        self.raw_frames = args.raw_frames.or(self.raw_frames);
+        self.plot.cell_mm = args.cell_mm.unwrap_or(self.plot.cell_mm);
+        self.relief.cell_mm = args.cell_mm.unwrap_or(self.relief.cell_mm);
+        self.relief.base_mm = args.relief_base.unwrap_or(self.relief.base_mm);
+        self.relief.height_mm = args.relief_height.unwrap_or(self.relief.height_mm);
+        self.deep_zoom.tile_size = args.tile_size.unwrap_or(self.deep_zoom.tile_size);
+        if !args.blocks.is_empty() {
+            self.schematic.blocks = args.blocks;
+        }
+        self.figure.title = args.title.or(self.figure.title.take());
+        self.figure.axes |= args.axes;
+        self.figure.scale_bar = args.scale_bar.or(self.figure.scale_bar);
+        self.import = args.import.or(self.import.take());
+        self.load = args.load.or(self.load.take());
+        self.save_rle = args.save_rle.or(self.save_rle.take());
+        self.thumbnail = args.thumbnail.or(self.thumbnail.take());
+        self.thumbnail_size = args.thumbnail_size.unwrap_or(self.thumbnail_size);
+        self.plugin = args.plugin.or(self.plugin.take());
+        self.random_fill = args.random_fill.or(self.random_fill);
+        self.seed = args.seed.or(self.seed);
+        self.manifest = args.manifest.or(self.manifest.take());
+        self.bug_report = args.bug_report.or(self.bug_report.take());
+        self.report = args.report.or(self.report.take());
+        self.describe = args.describe.or(self.describe.take());
+        self.stats = args.stats.or(self.stats.take());
+        self.stats_every = args.stats_every.unwrap_or(self.stats_every);
+        if let Some(filter) = args.stats_filter {
+            self.stats_filter = filter;
+        }
+        self.trajectory = args.trajectory.or(self.trajectory.take());
+        self.trajectory_every = args.trajectory_every.unwrap_or(self.trajectory_every);
+        if let Some(filter) = args.trajectory_filter {
+            self.trajectory_filter = filter;
+        }
+        self.path_length = args.path_length.unwrap_or(self.path_length);
+        self.sonify = args.sonify.or(self.sonify.take());
+        let sound = &mut self.sound;
+        sound.pitch = args.sound_pitch.unwrap_or(sound.pitch);
+        sound.volume = args.sound_volume.unwrap_or(sound.volume);
+        sound.samples_per_step = args.samples_per_step.unwrap_or(sound.samples_per_step);
+        self.record = args.record.or(self.record.take());
+
+        let import = &mut self.import_options;
+        import.threshold = args.threshold.unwrap_or(import.threshold);
+        import.invert |= args.invert;
+        import.fit |= args.fit;
+        import.channel = args.channel.unwrap_or(import.channel);
+    }
+
+    pub fn start(&self) -> Pos {
+        let center = self.size as isize / 2;
+        let center = self.position(Pos::new(center, center));
+        self.to_map(Pos::new(
+            self.start_x.unwrap_or(center.x),
+            self.start_y.unwrap_or(center.y),
+        ))
+    }
+
+    /// Map position of a position in the coordinates of --y-axis and --origin
+    pub fn to_map(&self, pos: Pos) -> Pos {
+        self.coordinates.to_map(pos, (self.size, self.size))
+    }
+
+    /// Position of a map cell in the coordinates of --y-axis and --origin
+    pub fn position(&self, pos: Pos) -> Pos {
+        self.coordinates.position(pos, (self.size, self.size))
+    }
+
+    /// Palette of color PNGs, `None` for plain grayscale ones
+    pub fn color_palette(&self, colors: usize) -> Option<Palette> {
+        if self.palette.is_none() && !self.mark_ants {
+            return None;
+        }
+        let mut palette = self.palette.clone().unwrap_or(Palette::gray(colors));
+        palette.marker = self.marker_color;
+        Some(palette)
+    }
+
+    /// Most colors any rule of the run needs, the first one or a later one
+    pub fn colors(&self) -> usize {
+        let later = self.rule_changes.iter().map(|(_, rule)| rule.colors());
+        later
+            .fold(self.rule.colors(), usize::max)
+            .max(self.timeline.colors())
+            .max(self.automaton.as_ref().map_or(0, LifeRule::states))
+    }
+
+    /// Cues of the run, `--rule-at` changes included ahead of other cues of their step
+    pub fn timeline(&self) -> Timeline {
+        let changes = self.rule_changes.iter();
+        let changes = changes.map(|(step, rule)| (*step, Cue::Rule(rule.clone())));
+        // Positions of cues are typed in the coordinates of --y-axis and --origin
+        let cues = self.timeline.cues().iter().map(|(step, cue)| {
+            let cue = match cue {
+                Cue::Spawn { pos, dir } => Cue::Spawn {
+                    pos: self.to_map(*pos),
+                    dir: *dir,
+                },
+                Cue::Flip { min, max } => {
+                    let (a, b) = (self.to_map(*min), self.to_map(*max));
+                    Cue::Flip {
+                        min: Pos::new(a.x.min(b.x), a.y.min(b.y)),
+                        max: Pos::new(a.x.max(b.x), a.y.max(b.y)),
+                    }
+                }
+                cue => cue.clone(),
+            };
+            (*step, cue)
+        });
+        let cues = changes.chain(cues);
+        cues.fold(Timeline::new(), |timeline, (step, cue)| {
+            timeline.with_cue(step, cue)
+        })
+    }
+
+    /// Ants to spawn, a single one at the start position unless listed explicitly
+    pub fn ants(&self) -> Vec<(Pos, Direction)> {
+        match self.ants.is_empty() {
+            true => vec![(self.start(), self.dir)],
+            false => self
+                .ants
+                .iter()
+                .map(|&(pos, dir)| (self.to_map(pos), dir))
+                .collect(),
+        }
+    }
+}
+
+/// `x,y,dir` ant spawn
+fn parse_ant(s: &str) -> Result<(Pos, Direction), String> {
+    let parts = s.split(',').map(str::trim).collect::<Vec<_>>();
+    let [x, y, dir] = parts[..] else {
+        return Err(format!("expected x,y,direction, got {s:?}"));
+    };
+
+    let coord = |c: &str| c.parse().map_err(|_| format!("bad coordinate {c:?}"));
+    let dir = dir.parse().map_err(|e| format!("{e}"))?;
+    Ok((Pos::new(coord(x)?, coord(y)?), dir))
+}
+
+/// `x`, `y` or `xy` mirroring
+pub fn parse_flip(s: &str) -> Result<(bool, bool), String> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "x" => Ok((true, false)),
+        "y" => Ok((false, true)),
+        "xy" | "yx" | "both" => Ok((true, true)),
+        _ => Err(format!("unknown flip {s:?}, expected x, y or xy")),
+    }
+}
+
+/// Clockwise turn in degrees as quarter turns
+pub fn parse_rotation(s: &str) -> Result<u8, String> {
+    match s.trim() {
+        "0" | "360" => Ok(0),
+        "90" => Ok(1),
+        "180" => Ok(2),
+        "270" | "-90" => Ok(3),
+        _ => Err(format!(
+            "can't turn by {s:?}, expected 90, 180 or 270 degrees"
+        )),
+    }
+}
+
+/// `step:rule` change of rule
+fn parse_rule_change(s: &str) -> Result<(u64, Rule), String> {
+    let Some((step, rule)) = s.split_once(':') else {
+        return Err(format!("expected step:rule, got {s:?}"));
+    };
+    let step = step
+        .trim()
+        .parse()
+        .map_err(|_| format!("bad step {:?}", step.trim()))?;
+    let rule = rule.trim().parse::<Rule>().map_err(|e| e.to_string())?;
+    Ok((step, rule))
+}
+
+/// `step: action` cue of the timeline
+fn parse_cue(s: &str) -> Result<(u64, Cue), String> {
+    let Some((step, cue)) = s.split_once(':') else {
+        return Err(format!("expected step: action, got {s:?}"));
+    };
+    let step = step
+        .trim()
+        .parse()
+        .map_err(|_| format!("bad step {:?}", step.trim()))?;
+    Ok((step, cue.parse()?))
+}
+
+/// `x,y` cell
+pub fn parse_cell(s: &str) -> Result<Pos, String> {
+    let (x, y) = s
+        .split_once(',')
+        .ok_or_else(|| format!("expected x,y, got {s:?}"))?;
+    let coord = |c: &str| {
+        c.trim()
+            .parse()
+            .map_err(|_| format!("bad coordinate {c:?}"))
+    };
+    Ok(Pos::new(coord(x)?, coord(y)?))
+}
+
+/// Byte count with an optional K, M or G (powers of 1024) suffix
+fn parse_bytes(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (number, unit) = match s.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&s[..i], c.to_ascii_uppercase()),
+        _ => (s, 'B'),
+    };
+    let shift = match unit {
+        'B' => 0,
+        'K' => 10,
+        'M' => 20,
+        'G' => 30,
+        'T' => 40,
+        _ => return Err(format!("unknown unit {unit:?}, expected K, M, G or T")),
+    };
+    let number = number
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| format!("expected a byte count like 512M, got {s:?}"))?;
+    number
+        .checked_mul(1 << shift)
+        .ok_or_else(|| format!("{s} is too many bytes"))
+}
+
+fn parse_seconds(s: &str) -> Result<Duration, String> {
+    let seconds = s
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| format!("{s:?} is not a number of seconds"))?;
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("{s} seconds is not a duration"))
+}
+
+fn parse_pace(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(steps) if steps > 0.0 && steps.is_finite() => Ok(steps),
+        _ => Err(format!("expected a number of steps above 0, got {s:?}")),
+    }
+}
+
+fn parse_coverage(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(share) if share > 0.0 && share <= 1.0 => Ok(share),
+        _ => Err(format!(
+            "expected a share of the map above 0 and up to 1, got {s:?}"
+        )),
+    }
+}
+
+fn parse_bit_depth(s: &str) -> Result<BitDepth, String> {
+    match s.trim() {
+        "2" => Ok(BitDepth::Two),
+        "4" => Ok(BitDepth::Four),
+        "8" => Ok(BitDepth::Eight),
+        _ => Err("expected 2, 4 or 8".to_owned()),
+    }
+}
+
+/// Any size a run takes, see [`board_size`]
+pub fn parse_map_size(s: &str) -> Result<usize, String> {
+    let max = SIZES[SIZES.len() - 1];
+    match s.parse() {
+        Ok(size) if (1..=max).contains(&size) => Ok(size),
+        _ => Err(format!("expected a size from 1 to {max}")),
+    }
+}
+
+pub fn parse_size(s: &str) -> Result<usize, String> {
+    match s.parse() {
+        Ok(size) if SIZES.contains(&size) => Ok(size),
+        _ => Err(format!("expected one of {SIZES:?}")),
+    }
+}
+
+// Keys whose lists are comma separated, the others take `;`
+const COMMA_LISTS: [&str; 3] = ["BLOCKS", "LAYERS", "PALETTE"];
+
+/// `ANTVENTURE_*` variables of a TOML scenario, lists are joined the way the
+/// variables take them
+fn scenario_vars(toml: &str) -> Result<Vec<(String, String)>, String> {
+    let table = toml.parse::<toml::Table>().map_err(|e| e.to_string())?;
+    let scalar = |value: toml::Value| match value {
+        toml::Value::String(s) => Some(s),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    };
+    table
+        .into_iter()
+        .map(|(name, value)| {
+            let key = name.to_ascii_uppercase().replace('-', "_");
+            if key == "CONFIG" {
+                return Err("a scenario can't read another one".to_owned());
+            }
+            let value = match value {
+                toml::Value::Array(items) => {
+                    let separator = if COMMA_LISTS.contains(&key.as_str()) {
+                        ","
+                    } else {
+                        ";"
+                    };
+                    let items = items.into_iter().map(scalar).collect::<Option<Vec<_>>>();
+                    items.map(|items| items.join(separator))
+                }
+                value => scalar(value),
+            };
+            let value = value.ok_or(format!(
+                "{name} should be a string, number, boolean or a list of them"
+            ))?;
+            Ok((format!("{}{key}", Config::PREFIX), value))
+        })
+        .collect()
+}
+
+fn parse_var<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("can't parse {}{key}={value:?}", Config::PREFIX))
+}
+
+#[test]
+fn config_from_vars() {
+    let vars = [
+        ("PATH", "/usr/bin"),
+        ("ANTVENTURE_START_X", "10"),
+        ("ANTVENTURE_DIRECTION", "west"),
+        ("ANTVENTURE_STEPS", "500"),
+        ("ANTVENTURE_RULE_AT", "900:LLRR; 100:RL"),
+        (
+            "ANTVENTURE_TIMELINE",
+            "100: spawn 1,2 east; 50: rule RLLLLR",
+        ),
+    ]
+    .map(|(k, v)| (k.to_owned(), v.to_owned()));
+
+    let config = Config::from_vars(vars).unwrap();
+
+    assert_eq!(config.start(), Pos::new(10, 512));
+    assert_eq!(config.dir, Direction::West);
+    assert_eq!(config.steps, Some(500));
+    assert_eq!(config.rule_changes[0], (100, "RL".parse().unwrap()));
+    assert_eq!(config.colors(), 6);
+    let timeline = config.timeline();
+    let steps = timeline.cues().iter().map(|(step, _)| *step);
+    assert_eq!(steps.collect::<Vec<_>>(), [50, 100, 100, 900]);
+    assert!(matches!(timeline.cues()[1].1, Cue::Rule(_)));
+
+    let vars = [("ANTVENTURE_STEPS".to_owned(), "a lot".to_owned())];
+    assert!(Config::from_vars(vars).is_err());
+    // Sizes are held to the range --size takes, from a scenario too
+    for size in ["0", "99999"] {
+        let vars = [("ANTVENTURE_SIZE".to_owned(), size.to_owned())];
+        assert!(Config::from_vars(vars).is_err(), "{size}");
+        let vars = scenario_vars(&format!("size = {size}")).unwrap();
+        assert!(Config::from_vars(vars).is_err(), "{size}");
+    }
+
+    // Later keys win over the preset even when they come first
+    let vars = [
+        ("ANTVENTURE_SIZE", "256"),
+        ("ANTVENTURE_PRESET", "llrr-cardioid"),
+    ]
+    .map(|(k, v)| (k.to_owned(), v.to_owned()));
+    let config = Config::from_vars(vars).unwrap();
+    assert_eq!(config.rule.to_string(), "LLRR");
+    assert_eq!(config.size, 256);
+    assert!(config.palette.is_some());
+}
+
+#[test]
+fn coordinates_and_orientation_from_vars() {
+    // Typed positions count up from the middle, the start stays there without them
+    let vars = [
+        ("ANTVENTURE_SIZE", "256"),
+        ("ANTVENTURE_Y_AXIS", "up"),
+        ("ANTVENTURE_ORIGIN", "center"),
+        ("ANTVENTURE_TIMELINE", "10: spawn 1,2 east"),
+        ("ANTVENTURE_FLIP", "y"),
+        ("ANTVENTURE_ROTATE", "270"),
+    ]
+    .map(|(k, v)| (k.to_owned(), v.to_owned()));
+    let mut config = Config::from_vars(vars).unwrap();
+    assert_eq!(config.start(), Pos::new(128, 128));
+    assert!(matches!(
+        config.timeline().cues()[0].1,
+        Cue::Spawn {
+            pos: Pos { x: 129, y: 126 },
+            ..
+        }
+    ));
+    assert_eq!(config.position(Pos::new(0, 0)), Pos::new(-128, 128));
+    config.start_y = Some(-10);
+    assert_eq!(config.start(), Pos::new(128, 138));
+    let orientation = config.orientation;
+    assert!(orientation.flip_y && !orientation.flip_x);
+    assert_eq!(orientation.quarter_turns, 3);
+}
+
+#[test]
+fn scenarios_become_vars() {
+    let toml = r##"
+        size = 512
+        rule = "LLRR"
+        ants = ["10,10,north", "20,20,east"]
+        palette = ["#fff", "#000", "#36c", "#c63"]
+        random-fill = 0.25
+        detect_period = true
+        timeline = """
+        100: spawn 1,2 east
+        50: rule RLLLLR
+        """
+    "##;
+    let vars = scenario_vars(toml).unwrap();
+    assert!(vars.contains(&(
+        "ANTVENTURE_ANTS".to_owned(),
+        "10,10,north;20,20,east".to_owned()
+    )));
+
+    // The environment comes after the scenario and wins
+    let env = ("ANTVENTURE_SIZE".to_owned(), "256".to_owned());
+    let config = Config::from_vars(vars.into_iter().chain([env])).unwrap();
+    assert_eq!(config.size, 256);
+    assert_eq!(config.rule.to_string(), "LLRR");
+    assert_eq!(config.ants().len(), 2);
+    assert_eq!(config.palette.as_ref().unwrap().colors.len(), 4);
+    assert_eq!(config.random_fill, Some(0.25));
+    assert!(config.detect_period);
+    assert_eq!(config.timeline().cues().len(), 2);
+
+    assert!(scenario_vars("size = [[1]]").is_err());
+    assert!(scenario_vars("[output]\npath = \"a.png\"").is_err());
+    assert!(scenario_vars("config = \"other.toml\"").is_err());
+}
+
+#[test]
+fn unknown_vars_are_skipped() {
+    let vars = [
+        ("ANTVENTURE_STEPS", "500"),
+        ("ANTVENTURE_MAP_SIZE", "256"),
+        ("ANTVENTURE_SIZE", "512"),
+    ]
+    .map(|(k, v)| (k.to_owned(), v.to_owned()));
+    let config = Config::from_vars(vars).unwrap();
+    assert_eq!(config.steps, Some(500));
+    assert_eq!(config.size, 512);
+    assert_eq!(config.unknown_keys, ["ANTVENTURE_MAP_SIZE"]);
+}
+
+#[test]
+fn args_override_env() {
+    use crate::Cli;
+    use clap::Parser;
+
+    let vars = [("ANTVENTURE_STEPS", "500"), ("ANTVENTURE_SIZE", "256")]
+        .map(|(k, v)| (k.to_owned(), v.to_owned()));
+    let mut config = Config::from_vars(vars).unwrap();
+
+    let args = [
+        "serious",
+        "--max-steps",
+        "10",
+        "-d",
+        "east",
+        "-o",
+        "out.png",
+        "-r",
+        "RLR",
+        "--bit-depth",
+        "4",
+    ];
+    config.apply_args(Cli::try_parse_from(args).unwrap().args);
+
+    assert_eq!(config.size, 256);
+    assert_eq!(config.start(), Pos::new(128, 128));
+    assert_eq!(config.dir, Direction::East);
+    assert_eq!(config.rule.to_string(), "RLR");
+    assert_eq!(config.steps, Some(10));
+    assert_eq!(config.output, PathBuf::from("out.png"));
+    assert_eq!(config.bit_depth, Some(BitDepth::Four));
+
+    let odd = Cli::try_parse_from(["serious", "--size", "1000"]).unwrap();
+    assert_eq!(odd.args.size, Some(1000));
+    assert_eq!(board_size(1000), Some(1024));
+    assert!(Cli::try_parse_from(["serious", "--size", "0"]).is_err());
+    assert!(Cli::try_parse_from(["serious", "--size", "10000"]).is_err());
+
+    let args = ["serious", "--ant", "1,2,n", "--ant", "3,-4,west"];
+    config.apply_args(Cli::try_parse_from(args).unwrap().args);
+    assert_eq!(
+        config.ants(),
+        [
+            (Pos::new(1, 2), Direction::North),
+            (Pos::new(3, -4), Direction::West)
+        ]
+    );
+    assert!(Cli::try_parse_from(["serious", "--ant", "1,2"]).is_err());
+}
+
+#[test]
+fn presets_yield_to_env() {
+    use crate::Cli;
+    use clap::Parser;
+
+    let cli = Cli::try_parse_from(["serious", "--preset", "llrr-cardioid", "-r", "RL"]).unwrap();
+    let env = [("ANTVENTURE_SIZE", "256"), ("ANTVENTURE_PRESET", "langton")]
+        .map(|(k, v)| (k.to_owned(), v.to_owned()));
+    let mut config = Config::from_sources(&cli.args, env).unwrap();
+    config.apply_args(cli.args);
+
+    // The flag picks the preset, the variable still wins over its size
+    assert_eq!(config.size, 256);
+    assert_eq!(config.steps, Some(3_000_000));
+    assert!(config.palette.is_some());
+    assert_eq!(config.rule.to_string(), "RL");
+}
+
+#[test]
+fn byte_sizes() {
+    assert_eq!(parse_bytes("1000"), Ok(1000));
+    assert_eq!(parse_bytes("512M"), Ok(512 << 20));
+    assert_eq!(parse_bytes("2g"), Ok(2 << 30));
+    assert!(parse_bytes("5X").is_err());
+    assert!(parse_bytes("M").is_err());
+}
+
+#[test]
+fn coverage_shares() {
+    assert_eq!(parse_coverage("0.5"), Ok(0.5));
+    assert_eq!(parse_coverage("1"), Ok(1.0));
+    for bad in ["0", "-0.5", "1.5", "NaN", "inf", "half"] {
+        assert!(parse_coverage(bad).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn seconds() {
+    assert_eq!(parse_seconds("90"), Ok(Duration::from_secs(90)));
+    assert_eq!(parse_seconds(" 0.5"), Ok(Duration::from_millis(500)));
+    assert!(parse_seconds("-1").is_err());
+    assert!(parse_seconds("soon").is_err());
+}
+
+#[test]
+fn frame_schedules() {
+    let schedule: FrameSchedule = "1:10, 5:30, 100".parse().unwrap();
+    assert_eq!(schedule.after(0), 1);
+    assert_eq!(schedule.after(10), 5);
+    assert_eq!(schedule.after(30), 100);
+    let mut frames = vec![0];
+    while frames.len() < 18 {
+        frames.push(schedule.next(*frames.last().unwrap()));
+    }
+    assert_eq!(frames[9..], [9, 10, 15, 20, 25, 30, 130, 230, 330]);
+    assert_eq!(schedule.next(12), 15);
+
+    assert_eq!("250".parse(), Ok(FrameSchedule::every(250)));
+    assert_eq!(FrameSchedule::every(250).next(600), 750);
+    for invalid in ["", "0", "10:0,5", "1:100,10:50,1000", "1:100"] {
+        assert!(invalid.parse::<FrameSchedule>().is_err(), "{invalid}");
+    }
+}
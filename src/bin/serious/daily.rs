@@ -0,0 +1,86 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use antventure::{
+    AntventureError, ColorMap, Daily, Date, Frame, PngRenderer, Pos, Renderer, Simulation,
+};
+
+use crate::i18n::{self, tr};
+
+#[derive(clap::Args)]
+pub struct DailyArgs {
+    /// Day to draw the picture of, YYYY-MM-DD; today in UTC by default
+    #[arg(long)]
+    date: Option<Date>,
+
+    /// Map width and height in cells: 256, 512 or 1024
+    #[arg(long, default_value_t = 512)]
+    size: usize,
+
+    /// Where to save the picture, antventure-<date>.png by default
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+/// Draw the picture of the day, the same date always gives the same picture
+pub fn run(args: DailyArgs) -> Result<ExitCode, AntventureError> {
+    let daily = Daily::new(args.date.unwrap_or_else(Date::today));
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| format!("antventure-{}.png", daily.date).into());
+
+    let command = format!("serious daily --date {} --size {}", daily.date, args.size);
+
+    match args.size {
+        256 => draw::<256>(&daily, &command, &output)?,
+        512 => draw::<512>(&daily, &command, &output)?,
+        1024 => draw::<1024>(&daily, &command, &output)?,
+        size => {
+            eprintln!(
+                "{}",
+                tr!("unsupported-size", size = size, sizes = "[256, 512, 1024]")
+            );
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+
+    println!(
+        "{}",
+        tr!(
+            "daily-drawn",
+            date = daily.date.to_string(),
+            rule = daily.rule.to_string(),
+            dir = i18n::direction(daily.dir).to_lowercase(),
+            steps = daily.steps,
+            file = output.display().to_string()
+        )
+    );
+    println!("{}", tr!("daily-repeat", command = command));
+    Ok(ExitCode::SUCCESS)
+}
+
+fn draw<const S: usize>(
+    daily: &Daily,
+    command: &str,
+    output: &Path,
+) -> Result<(), AntventureError> {
+    let mut sim = Simulation::new(ColorMap::<S, S>::new_white(), daily.rule.clone());
+    let center = Pos::new(S as isize / 2, S as isize / 2);
+    sim.spawn(center, daily.dir)
+        .expect("the center is on the map");
+    sim.run_at_most(daily.steps);
+
+    // The date is enough to draw it again, the rest is there for the curious
+    let mut text = daily.text();
+    text.push(("Source".into(), command.to_owned()));
+    let renderer = PngRenderer {
+        palette: Some(daily.palette.clone()),
+        text,
+        ..PngRenderer::default()
+    };
+    let frame = Frame::new(sim.map(), daily.rule.colors(), None);
+    Ok(renderer.save(&frame, output)?)
+}
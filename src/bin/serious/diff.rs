@@ -0,0 +1,193 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use antventure::{
+    save_palette_map_to_file, CellStore, Checkpoint, ColorMap, ImportOptions, Map, Palette,
+    Simulation,
+};
+use image::DynamicImage;
+
+use crate::{config::with_size, i18n::tr};
+
+#[derive(clap::Args)]
+pub struct DiffArgs {
+    /// First board: an .antv checkpoint or an image of a board
+    a: PathBuf,
+
+    /// Second board of the same size
+    b: PathBuf,
+
+    /// Image of the difference: vermillion cells are black on the first board only,
+    /// blue ones on the second board only and black ones on both
+    #[arg(long, short, default_value = "diff.png")]
+    output: PathBuf,
+}
+
+/// A board saved by an earlier run
+enum Saved {
+    Checkpoint(Checkpoint),
+    Image(DynamicImage),
+}
+
+impl Saved {
+    /// Images are told by their extension, anything else is taken for a checkpoint
+    fn load(path: &Path) -> Result<Self, String> {
+        match image::ImageFormat::from_path(path) {
+            Ok(_) => image::open(path)
+                .map(Saved::Image)
+                .map_err(|e| e.to_string()),
+            Err(_) => Checkpoint::load(path)
+                .map(Saved::Checkpoint)
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    fn size(&self) -> (usize, usize) {
+        match self {
+            Saved::Checkpoint(checkpoint) => (checkpoint.width, checkpoint.height),
+            Saved::Image(image) => (image.width() as usize, image.height() as usize),
+        }
+    }
+
+    /// Cells of any color but white are black
+    fn to_map<const N: usize>(&self) -> Result<Map<N, N>, String> {
+        match self {
+            Saved::Checkpoint(checkpoint) => {
+                let sim = Simulation::<N, N, ColorMap<N, N>>::from_checkpoint(checkpoint)
+                    .map_err(|e| e.to_string())?;
+                let colors = sim.map().to_colors();
+                Ok(Map::with_colors(
+                    colors.into_iter().map(|c| (c != 0) as u8).collect(),
+                ))
+            }
+            Saved::Image(image) => Ok(Map::import(image, &ImportOptions::default())),
+        }
+    }
+}
+
+/// Count the cells two saved boards disagree on and paint where they do, to compare
+/// rules or to check a resumed run against one that never stopped
+pub fn run(args: DiffArgs) -> ExitCode {
+    let load = |path: &Path| {
+        Saved::load(path).map_err(|error| {
+            let file = path.display().to_string();
+            eprintln!("{}", tr!("cant-diff", file = file, error = error));
+        })
+    };
+    let (Ok(a), Ok(b)) = (load(&args.a), load(&args.b)) else {
+        return ExitCode::FAILURE;
+    };
+    let ((w, h), (other_w, other_h)) = (a.size(), b.size());
+    if w != h || (w, h) != (other_w, other_h) {
+        eprintln!(
+            "{}",
+            tr!(
+                "diff-sizes",
+                a = format!("{w}x{h}"),
+                b = format!("{other_w}x{other_h}")
+            )
+        );
+        return ExitCode::FAILURE;
+    }
+    with_size!(w, diff(&a, &b, &args)).unwrap_or(ExitCode::FAILURE)
+}
+
+fn diff<const N: usize>(a: &Saved, b: &Saved, args: &DiffArgs) -> ExitCode {
+    let to_map = |saved: &Saved, path: &Path| {
+        saved.to_map::<N>().map_err(|error| {
+            let file = path.display().to_string();
+            eprintln!("{}", tr!("cant-diff", file = file, error = error));
+        })
+    };
+    let (Ok(a), Ok(b)) = (to_map(a, &args.a), to_map(b, &args.b)) else {
+        return ExitCode::FAILURE;
+    };
+    let stats = a.diff(&b);
+    println!(
+        "{}",
+        tr!(
+            "diff-stats",
+            changed = stats.changed(),
+            only_a = stats.only_a,
+            only_b = stats.only_b,
+            both = stats.both
+        )
+    );
+
+    let saved = save_palette_map_to_file(
+        &a.diff_map(&b),
+        4,
+        &Palette::diff(),
+        &[],
+        None,
+        &args.output,
+    );
+    if let Err(e) = saved {
+        let file = args.output.display().to_string();
+        eprintln!(
+            "{}",
+            tr!("diff-cant-save", file = file, error = e.to_string())
+        );
+        return ExitCode::FAILURE;
+    }
+    let file = args.output.display().to_string();
+    println!("{}", tr!("diff-saved", file = file));
+    ExitCode::SUCCESS
+}
+
+#[test]
+fn boards_of_the_same_size_are_diffed() {
+    use crate::{Cli, Command};
+    use clap::Parser;
+    use image::{GrayImage, Luma};
+
+    let diff = |args: &[&str]| {
+        let args = ["serious", "diff"].iter().chain(args);
+        match Cli::try_parse_from(args).map(|cli| cli.command) {
+            Ok(Some(Command::Diff(args))) => Ok(args),
+            Ok(_) => panic!("not a diff command"),
+            Err(e) => Err(e),
+        }
+    };
+    let args = diff(&["a.antv", "b.png"]).unwrap();
+    assert_eq!((args.a, args.b), ("a.antv".into(), "b.png".into()));
+    assert_eq!(args.output, PathBuf::from("diff.png"));
+    assert!(diff(&["a.antv"]).is_err());
+
+    let dir = std::env::temp_dir();
+    let (a, b, small) = (
+        dir.join("antventure_boards_are_diffed_a.png"),
+        dir.join("antventure_boards_are_diffed_b.png"),
+        dir.join("antventure_boards_are_diffed_small.png"),
+    );
+    let output = dir.join("antventure_boards_are_diffed.png");
+    let board = |black: &[(u32, u32)]| {
+        let mut image = GrayImage::from_pixel(256, 256, Luma([255]));
+        for &(x, y) in black {
+            image.put_pixel(x, y, Luma([0]));
+        }
+        image
+    };
+    board(&[(0, 0), (5, 5)]).save(&a).unwrap();
+    board(&[(0, 0), (9, 9)]).save(&b).unwrap();
+    GrayImage::new(128, 128).save(&small).unwrap();
+    let path = |file: &Path| file.display().to_string();
+
+    let args = diff(&[&path(&a), &path(&b), "-o", &path(&output)]).unwrap();
+    assert!(run(args) == ExitCode::SUCCESS);
+    let image = image::open(&output).unwrap().into_rgb8();
+    assert_eq!(image.dimensions(), (256, 256));
+    // Cells on both, on the first or the second board only and on neither differ
+    let colors = [(0, 0), (5, 5), (9, 9), (1, 1)].map(|(x, y)| image.get_pixel(x, y).0);
+    for (i, color) in colors.iter().enumerate() {
+        assert!(!colors[i + 1..].contains(color), "{colors:?}");
+    }
+
+    let args = diff(&[&path(&a), &path(&small), "-o", &path(&output)]).unwrap();
+    assert!(run(args) == ExitCode::FAILURE);
+    for file in [a, b, small, output] {
+        std::fs::remove_file(file).unwrap();
+    }
+}
@@ -0,0 +1,30 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use antventure::extract_params;
+
+use crate::i18n::tr;
+
+#[derive(clap::Args)]
+pub struct ExtractArgs {
+    /// PNG saved with --embed-params, any lossless copy of it works too
+    image: PathBuf,
+}
+
+/// Print the command an image was made with, to run it again
+pub fn run(args: ExtractArgs) -> ExitCode {
+    let file = args.image.display().to_string();
+    let params = image::open(&args.image)
+        .map_err(|e| e.to_string())
+        .and_then(|image| extract_params(image.to_rgba8().as_raw()).map_err(|e| e.to_string()));
+    match params {
+        Ok(params) => {
+            // Nothing else on stdout, so it can go straight into a shell
+            println!("{params}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", tr!("cant-extract", file = file, error = e));
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use antventure::{AntventureError, HexAnt, HexDirection, HexMap, HexRule};
+
+use crate::i18n::{self, tr};
+
+#[derive(clap::Args)]
+pub struct HexArgs {
+    /// Map width and height in cells
+    #[arg(long, default_value_t = 256)]
+    size: usize,
+
+    /// Turn for each cell color: L/R turn by 60°, l/r by 120°, N goes straight, U turns around
+    #[arg(long, short, default_value = "LR")]
+    rule: HexRule,
+
+    /// Initial direction: e, ne, nw, w, sw or se
+    #[arg(long, short, default_value = "e")]
+    direction: HexDirection,
+
+    /// Stop after this many steps even if the ant is still on the map
+    #[arg(long)]
+    max_steps: Option<usize>,
+
+    /// Hexagon size in pixels from center to corner
+    #[arg(long, default_value_t = 4)]
+    radius: usize,
+
+    /// Output PNG
+    #[arg(long, short, default_value = "hex.png")]
+    output: PathBuf,
+}
+
+/// A turmite on a grid of hexagons
+pub fn run(args: HexArgs) -> Result<ExitCode, AntventureError> {
+    if args.size == 0 {
+        eprintln!("{}", tr!("hex-empty-map"));
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let mut map = HexMap::new_white(args.size, args.size);
+    let center = map.center();
+    let mut ant = HexAnt::new(&mut map, center, args.direction, args.rule.clone())
+        .expect("the center is on the map");
+
+    let on_map = match args.max_steps {
+        Some(steps) => ant.walk_at_most(steps),
+        None => {
+            ant.walk_until_end();
+            false
+        }
+    };
+    let (pos, dir) = (ant.pos(), ant.dir());
+    let (name, pos, dir) = (
+        tr!("ant"),
+        format!("({}, {})", pos.q, pos.r),
+        i18n::direction(dir),
+    );
+    match on_map {
+        true => println!("{}", tr!("ant-stopped", name = name, pos = pos, dir = dir)),
+        false => println!("{}", tr!("ant-left", name = name, pos = pos, dir = dir)),
+    }
+
+    let colors = args.rule.colors();
+    println!(
+        "{}",
+        tr!(
+            "tiles-per-color",
+            counts = format!("{:?}", map.count_colors(colors))
+        )
+    );
+    map.save(colors, args.radius, &args.output)?;
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,117 @@
+use std::{env, fmt::Debug, sync::OnceLock};
+
+use fluent::{concurrent::FluentBundle, FluentArgs, FluentResource};
+use unic_langid::LanguageIdentifier;
+
+/// Translations built into the binary, English first as it's the fallback
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("locales/en.ftl")),
+    ("ru", include_str!("locales/ru.ftl")),
+];
+
+/// Bundle of the chosen language followed by the English one
+static BUNDLES: OnceLock<Vec<FluentBundle<FluentResource>>> = OnceLock::new();
+
+/// Pick the language of messages: `lang` if given, otherwise the one of the system
+/// locale, English if neither is translated
+pub fn init(lang: Option<&str>) {
+    let system = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .find_map(|var| env::var(var).ok().filter(|value| !value.is_empty()));
+    let wanted = lang.map(str::to_owned).or(system).unwrap_or_default();
+    // "ru_RU.UTF-8" and "ru-RU" both mean "ru"
+    let wanted = wanted
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    let chosen = LOCALES
+        .iter()
+        .position(|(name, _)| *name == wanted)
+        .unwrap_or(0);
+    let bundles = [chosen, 0]
+        .into_iter()
+        .take(if chosen == 0 { 1 } else { 2 })
+        .map(|i| bundle(LOCALES[i].0, LOCALES[i].1))
+        .collect();
+    let _ = BUNDLES.set(bundles);
+}
+
+/// Languages `--lang` knows
+pub fn languages() -> impl Iterator<Item = &'static str> {
+    LOCALES.iter().map(|(name, _)| *name)
+}
+
+fn bundle(name: &str, ftl: &'static str) -> FluentBundle<FluentResource> {
+    let lang = name
+        .parse::<LanguageIdentifier>()
+        .expect("locale names are valid");
+    let resource = FluentResource::try_new(ftl.to_owned()).expect("built-in translations parse");
+    let mut bundle = FluentBundle::new_concurrent(vec![lang]);
+    // Unicode isolation marks only show up as garbage in a terminal
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .expect("built-in translations have no duplicate messages");
+    bundle
+}
+
+/// Message `id` in the chosen language, the id itself if no language has it
+pub fn message(id: &str, args: Option<&FluentArgs>) -> String {
+    let bundles = BUNDLES.get_or_init(|| vec![bundle(LOCALES[0].0, LOCALES[0].1)]);
+    for bundle in bundles {
+        if let Some(pattern) = bundle.get_message(id).and_then(|m| m.value()) {
+            let mut errors = Vec::new();
+            return bundle
+                .format_pattern(pattern, args, &mut errors)
+                .into_owned();
+        }
+    }
+    id.to_owned()
+}
+
+/// Name of a direction, from its variant name
+pub fn direction(dir: impl Debug) -> String {
+    let id = format!("direction-{}", format!("{dir:?}").to_ascii_lowercase());
+    match message(&id, None) {
+        name if name == id => format!("{dir:?}"),
+        name => name,
+    }
+}
+
+/// Translated message, with fluent variables given as `name = value`
+///
+/// Numbers keep their type so plural forms work, anything else goes in as text
+macro_rules! tr {
+    ($id:literal) => {
+        $crate::i18n::message($id, None)
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $crate::i18n::message($id, Some(&args))
+    }};
+}
+pub(crate) use tr;
+
+#[test]
+fn every_locale_has_every_message() {
+    // Messages start a line with their id, attributes and continuations are indented
+    let ids = |ftl: &str| {
+        ftl.lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_alphabetic()))
+            .filter_map(|line| line.split_once(" =").map(|(id, _)| id.to_owned()))
+            .collect::<Vec<_>>()
+    };
+
+    let english = ids(LOCALES[0].1);
+    assert!(!english.is_empty());
+    for (name, ftl) in &LOCALES[1..] {
+        let bundle = bundle(name, ftl);
+        for id in &english {
+            assert!(bundle.has_message(id), "{name} misses {id}");
+        }
+        assert_eq!(ids(ftl).len(), english.len(), "{name} has extra messages");
+    }
+}
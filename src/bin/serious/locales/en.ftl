@@ -0,0 +1,264 @@
+# Messages of the serious binary, see i18n.rs for how they're picked
+
+direction-north = North
+direction-east = East
+direction-south = South
+direction-west = West
+direction-up = Up
+direction-down = Down
+direction-northeast = NorthEast
+direction-northwest = NorthWest
+direction-southeast = SouthEast
+direction-southwest = SouthWest
+
+invalid-config = Invalid configuration: { $error }
+unknown-var = Ignoring { $key }, no setting goes by that name
+unsupported-size = Unsupported map size { $size }, expected one of { $sizes }
+size-unsupported = { $option } needs a map size of { $sizes }
+boundary-needs-steps = Ants never leave a wrapped or reflecting map, set the number of steps or seconds
+rule-changed = Rule changed to { $rule } at step { $step }
+ant-joined = Ant joined at ({ $x }, { $y }) at step { $step }
+cells-flipped = Cells from ({ $x0 }, { $y0 }) to ({ $x1 }, { $y1 }) flipped at step { $step }
+palette-changed = Palette changed at step { $step }
+palette-too-short = The palette needs { $colors } colors
+raw-frames = Streaming { $size }x{ $size } { $pix_fmt } frames, e.g. into ffmpeg -f rawvideo -pix_fmt { $pix_fmt } -s { $size }x{ $size } -i - walk.mp4
+raw-frames-written = { $count ->
+    [one] { $count } frame
+   *[other] { $count } frames
+} written to stdout
+cant-play-cue = Can't play the timeline: { $error }
+cant-resume = Can't resume: { $error }
+resume-not-square = Can't resume: only square maps are supported
+cant-import = Can't import initial board: { $error }
+cant-load-obstacles = Can't load the obstacle mask: { $error }
+cant-load-pattern = Can't load pattern: { $error }
+pattern-colors = The pattern has { $pattern } colors but the rule only { $rule }
+bad-density = Random fill density { $density } is not between 0 and 1
+random-seed = Random fill seed: { $seed }
+stochastic-seed = Stochastic rule seed: { $seed }
+several-boards = Start from one of an image, a pattern or a random fill, not several
+cant-spawn = Can't spawn ant at ({ $x }, { $y }), it's outside of the map or on an obstacle
+cant-animate = Can't animate into { $file }, use .gif or .png
+cant-save-stats = Can't save statistics to { $file }, use .csv or .json
+cant-save-trajectory = Can't save the trajectory to { $file }, use .csv or .ndjson
+
+black-tiles = Black tiles count: { $count }
+tiles-per-color = Tiles per color: { $counts }
+storage-dense = a dense window
+storage-chunked = chunks looked up by position
+storage-moved = Board moved to { $storage } at { $chunks ->
+    [one] { $chunks } chunk
+   *[other] { $chunks } chunks
+} written
+ant = Ant
+ant-numbered = Ant #{ $index }
+ant-stopped = { $name } stopped at { $pos }, looking at { $dir }
+ant-left = { $name } leaved map at { $pos }, looking at { $dir }
+animation-frames = Animation frames: { $count }
+checkpoint-saved = Checkpoint saved after { $steps } steps
+most-visits = Most visits of a cell: { $count }
+relief-triangles = Relief triangles: { $count }
+plotter-strokes = Plotter strokes: { $count }
+deep-zoom-tiles = Deep zoom tiles: { $count } in { $levels } levels, { $blank } of them blank
+manifest-saved = Manifest lists { $count ->
+    [one] { $count } file
+   *[other] { $count } files
+}
+integrity-failed = Integrity check failed at step { $step }: { $error }
+bug-report-saved = Bug report saved to { $file }
+crash-report-saved = The run crashed, a report to attach to an issue is saved to { $file }
+cant-save-bug-report = Can't save the bug report: { $error }
+report-saved = Report saved to { $file }
+snapshots-saved = Saved { $count ->
+    [one] { $count } snapshot
+   *[other] { $count } snapshots
+} along the way
+description-saved = Description saved to { $file }
+stats-saved = { $count ->
+    [one] { $count } sample
+   *[other] { $count } samples
+} of statistics saved to { $file }
+trajectory-saved = { $count ->
+    [one] { $count } position
+   *[other] { $count } positions
+} saved to { $file }
+sound-saved = { $seconds } s of sound saved to { $file }
+replay-saved = { $steps } steps recorded to { $file }
+
+run-ended-edge = Run ended at step { $step }: every ant left the map
+run-ended-period = Run ended at step { $step }: the walk turned periodic
+run-ended-coverage = Run ended at step { $step }: the visited area is big enough
+run-ended-steps = Run ended at step { $step }: the step budget ran out
+run-ended-stopped = Run ended at step { $step }: it was stopped
+run-ended-time = Run ended at step { $step }: the time budget ran out after { $seconds } s
+heartbeat = Still walking at step { $step }, { $speed } steps a second
+highway-found = Highway found at step { $step }: period { $period }, moving by ({ $x }, { $y })
+cycle-found = Cycle found at step { $step }: period { $period }
+growth-stalled = Growth stalled at step { $step }: no new cells for { $since } steps
+coverage-reached = Visited area spans { $percent }% of the map at step { $step }
+boundary-reached = Map edge reached at step { $step } at ({ $x }, { $y })
+seams = Seams differ in { $vertical }% (left/right) and { $horizontal }% (top/bottom) of cells, { $interior }% inside
+seams-warning = Warning: the image won't tile seamlessly
+
+unbounded-needs-steps = An unbounded ant never leaves the map, set the number of steps or seconds
+unbounded-limits = Unbounded maps take a single ant with a two-color rule on a white board
+unbounded-format = Unbounded maps are saved as PNG, SVG, a raster format, a plugin format or a macrocell
+heatmap-needs-bounds = Heatmaps need a bounded map
+path-needs-bounds = Paths are drawn over a bounded map only
+serve-needs-bounds = --serve watches a bounded map only
+serving = Serving the run at { $url }
+headless-watch = watch needs a terminal, and --headless is set
+top-left-pixel = Top left pixel is cell ({ $x }, { $y })
+macrocell-nodes = Saved a macrocell of { $count ->
+    [one] { $count } node
+   *[other] { $count } nodes
+}, cells keep their positions
+
+cant-analyze = Can't analyze { $file }: { $error }
+cant-extract = Can't read parameters from { $file }: { $error }
+cant-perturb = Can't perturb ({ $x }, { $y }), it's outside of the map
+cells-differ = Step { $step }: { $distance } cells differ
+ants-parted = Ants parted ways at step { $step }
+ants-never-parted = Ants never parted ways
+ant-left-at = The ant left the map at step { $step }
+stats-size = Size: { $width }x{ $height }
+stats-black = Black cells: { $black } ({ $percent }%)
+stats-bounds = Bounding box: ({ $x0 }, { $y0 }) - ({ $x1 }, { $y1 }), { $width }x{ $height }
+stats-bounds-empty = Bounding box: empty
+stats-symmetry = Left-right symmetry: { $percent }%
+
+cant-load-turmite = Can't load turmite { $file }: { $error }
+cant-load-plugin = Can't load plugin { $file }: { $error }
+plugin-without-turmite = plugin { $name } has no turmite
+terminal-error = Terminal error: { $error }
+watch-left = left the map
+watch-paused = paused
+watch-running = running
+watch-following = , following
+watch-status = step { $step } | { $state } | { $speed } steps/frame | view at ({ $x }, { $y }){ $following } | space n + - arrows f m s c q
+watch-saved = Saved the view as { $file }
+watch-cant-save = Can't save { $file }: { $error }
+watch-copied = Copied the view to the clipboard as text
+lesson-start = Press space to take a step or p to play
+lesson-step = Step { $step }: the ant at ({ $x }, { $y }) faces { $from } on a { $color } cell, so it { $turn }, paints the cell { $painted } and moves { $dir }
+lesson-back = Step { $step } taken back
+lesson-turn-left = turns left
+lesson-turn-right = turns right
+lesson-turn-none = goes straight on
+lesson-turn-back = turns around
+lesson-white = white
+lesson-black = black
+lesson-gray = gray { $color }
+lesson-status = step { $step } | { $state }{ $following } | space step, p play, arrows f s c q
+
+tutorial-welcome = Welcome to antventure! This tutorial runs a few simulations and keeps what they make in { $dir }.
+tutorial-step = Step { $step } of { $steps }: { $title }
+tutorial-command = $ { $command }
+tutorial-press-enter = Press Enter to run it, or q and Enter to quit.
+tutorial-command-failed = the command failed with { $status }
+tutorial-run-title = run an ant
+tutorial-run-intro = An ant starts in the middle of a white board. On a white cell it turns left, on a black one right, then it flips the color of the cell and moves on. This walks it for 11000 steps on a 256 by 256 board and saves the board as a PNG:
+tutorial-run-check = The board is { $width } by { $height } cells and { $black } of them are black, open { $file } to see the highway leaving the mess in the middle.
+tutorial-no-black = { $file } has no black cells, the ant didn't walk
+tutorial-rule-title = change the rule
+tutorial-rule-intro = The rule gives the turn on every cell color in order, and a cell takes the next color when the ant leaves it. The ant above is LR, longer rules like LLRR or RLR use more colors and grow other shapes.
+tutorial-rule-prompt = Type a rule of L and R, or just press Enter for LLRR:
+tutorial-bad-rule = { $error }, try again:
+tutorial-wrong-depth = { $file } is { $bits }-bit but the rule needs { $expected }-bit grayscale
+tutorial-rule-check = The { $colors } colors were saved as { $bits }-bit grayscale and { $shades } shades show up, compare { $file } with the first board.
+tutorial-animate-title = save an animation
+tutorial-animate-intro = --animate saves the walk as a GIF too, with a frame every --frame-every steps, while -o still gets the last board:
+tutorial-no-frames = { $file } has no more than one frame
+tutorial-animate-check = The animation has { $frames } frames, open { $file } in a browser to play it.
+tutorial-failed = The tutorial stopped: { $error }
+tutorial-quit = Bye! Run the tutorial again to start over.
+tutorial-done = All done! --help lists every option, the README shows more examples and watch --lesson goes through the rule one step at a time.
+
+puzzle-unknown-level = There are puzzles 1 to { $levels } only
+puzzle-level = Puzzle { $level } of { $levels }: which rule and starting direction painted { $file }?
+puzzle-rules = The ant starts in the middle of a { $size } by { $size } board and walks { $steps } steps. Type a rule and a direction, e.g. LLRR west (north if left out), or q to give up.
+puzzle-extra = Only a rule and a direction please, not { $word }
+puzzle-score = { $score }% alike, best so far { $best }%, your board is in { $file }
+puzzle-solved = Solved in { $attempts ->
+    [one] { $attempts } try
+   *[other] { $attempts } tries
+}! Go on with --level { $next }.
+puzzle-solved-last = Solved in { $attempts ->
+    [one] { $attempts } try
+   *[other] { $attempts } tries
+}! That was the last puzzle.
+puzzle-gave-up = It was { $rule } facing { $dir }.
+
+hex-empty-map = The map needs at least one cell
+worm-eating = The worm is still eating after { $steps } edges and { $genes } genes
+worm-starved = The worm starved after { $steps } edges and { $genes } genes
+worm-undecided = The rule ran out of genes after { $steps } edges, add more to go on
+worm-blocked = Gene { $gene } points at an eaten edge after { $steps } edges
+voxel-too-big = A .vox model holds at most { $max } voxels a side, save PNG slices instead
+voxel-slices-saved = Saved { $count ->
+    [one] { $count } layer
+   *[other] { $count } layers
+}
+
+search-no-rules = No rules to try, --max-len needs to be at least 2
+cant-create = Can't create { $dir }: { $error }
+search-trying = Trying { $count } rules on a { $size }x{ $size } map
+search-speed = { $steps } steps on { $workers } workers, { $speed } steps/s per worker
+search-header = rank  rule              colored     area  highway at
+search-mosaic = Mosaic of the top { $count } rules saved
+daily-drawn = Picture of { $date }: rule { $rule } facing { $dir } for { $steps } steps, saved to { $file }
+daily-repeat = Anyone can draw it again with: { $command }
+optimize-trying = Trying { $count } rules from { $starts } start cells each on a { $size }x{ $size } map
+optimize-header = rank  rule              similarity  start
+bench-no-workloads = No workload matches --only
+bench-debug-build = This is a debug build, build with --release for numbers worth comparing
+bench-machine = antventure { $version } on { $os } { $arch }, { $cpus ->
+    [one] { $cpus } CPU
+   *[other] { $cpus } CPUs
+}
+bench-header = | workload | backend | steps/s | time | peak memory |
+optimize-best = Closest match is { $score }% alike: rule { $rule } from ({ $x }, { $y }) facing { $dir }, board saved to { $file }
+
+repl-welcome = A { $size }x{ $size } board walked by { $rule }, with an ant in the middle facing { $dir }. Type help for the commands.
+repl-help =
+    step [count]          walk every ant a step, or count steps
+    back [count]          take a step back, or count steps
+    get x y               color of a cell
+    set x y color         paint a cell white, black or a color number
+    spawn x y [direction] add an ant, facing north if left out
+    ants                  where every ant is
+    stats                 steps, turns and cells of every color
+    save file.png         save the board
+    reset                 start over on a white board
+    quit                  leave
+repl-stepped = Step { $steps }
+repl-all-left = Step { $steps }, every ant has left the map
+repl-cell = ({ $x }, { $y }) is { $name }, color { $color }
+repl-spawned = Ant #{ $index } spawned
+repl-stats = Step { $steps }, { $on_map } of { $ants } ants on the map, { $left } left and { $right } right turns
+repl-saved = Board saved to { $file }
+repl-cant-save = Can't save { $file }: { $error }
+repl-reset = Back to a white board with an ant in the middle
+repl-off-map = ({ $x }, { $y }) is off the map
+repl-no-color = No color { $color }, the rule has colors 0 to { $last }
+repl-not-a-number = { $word } is not a number
+repl-usage = Usage: { $usage }
+repl-unknown = No command { $command }, type help for the list
+
+verify-running = Walking { $rule } on { $backends } in lockstep, ants are compared every step and cells every { $every }
+verify-versus = Walking { $rule } and { $versus } on ColorMap in lockstep, ants are compared every step and cells every { $every }
+verify-agree = All { $count } backends agree after { $steps } steps
+verify-one-backend = Only ColorMap holds the colors of { $rule }, there's nothing to compare it with
+verify-no-mmap = Leaving out MmapMap, no memory to map: { $error }
+verify-left = At step { $step } the ant left the map on { $backend } but not on { $other }
+verify-ant = At step { $step } the ant is at { $pos } facing { $dir } on { $reference } but at { $other_pos } facing { $other_dir } on { $backend }
+verify-cells = At step { $step } { $count } cells of { $backend } differ from { $reference }, the first at ({ $x }, { $y })
+run-failed = The run failed: { $error }
+cant-diff = Can't compare { $file }: { $error }
+diff-sizes = Boards of { $a } and { $b } cells can't be compared, they need to be square and of the same size
+diff-stats = { $changed } cells differ: { $only_a } black on the first board only, { $only_b } on the second only, { $both } on both
+diff-cant-save = Can't save the difference to { $file }: { $error }
+diff-saved = Difference saved to { $file }
+cant-replay = Can't replay { $file }: { $error }
+replay-rendered = Board at step { $step } saved to { $file }
+replay-animated = { $frames } frames up to step { $step } saved to { $file }
@@ -0,0 +1,270 @@
+# Сообщения serious, как они выбираются — в i18n.rs
+
+direction-north = север
+direction-east = восток
+direction-south = юг
+direction-west = запад
+direction-up = вверх
+direction-down = вниз
+direction-northeast = северо-восток
+direction-northwest = северо-запад
+direction-southeast = юго-восток
+direction-southwest = юго-запад
+
+invalid-config = Неверная конфигурация: { $error }
+unknown-var = Пропускаю { $key }: такой настройки нет
+unsupported-size = Размер карты { $size } не поддерживается, возможны { $sizes }
+size-unsupported = { $option } работает только с размерами карты { $sizes }
+boundary-needs-steps = С замкнутой или отражающей карты муравьи никогда не уходят, задайте число шагов или секунд
+rule-changed = Правило сменилось на { $rule } на шаге { $step }
+ant-joined = Муравей появился в ({ $x }, { $y }) на шаге { $step }
+cells-flipped = Клетки от ({ $x0 }, { $y0 }) до ({ $x1 }, { $y1 }) перекрашены на шаге { $step }
+palette-changed = Палитра сменилась на шаге { $step }
+palette-too-short = В палитре нужно { $colors } цветов
+raw-frames = Кадры { $size }x{ $size } { $pix_fmt } идут в stdout, например в ffmpeg -f rawvideo -pix_fmt { $pix_fmt } -s { $size }x{ $size } -i - walk.mp4
+raw-frames-written = В stdout { $count ->
+    [one] записан { $count } кадр
+    [few] записано { $count } кадра
+   *[other] записано { $count } кадров
+}
+cant-play-cue = Нельзя проиграть сценарий: { $error }
+cant-resume = Не удалось продолжить: { $error }
+resume-not-square = Не удалось продолжить: поддерживаются только квадратные карты
+cant-import = Не удалось импортировать начальное поле: { $error }
+cant-load-obstacles = Не удалось загрузить маску препятствий: { $error }
+cant-load-pattern = Не удалось загрузить шаблон: { $error }
+pattern-colors = В шаблоне { $pattern } цветов, а в правиле только { $rule }
+bad-density = Плотность случайного заполнения { $density } не лежит между 0 и 1
+random-seed = Зерно случайного заполнения: { $seed }
+stochastic-seed = Зерно случайного правила: { $seed }
+several-boards = Начните с одного из: изображения, шаблона или случайного заполнения
+cant-spawn = Нельзя поставить муравья в ({ $x }, { $y }), это за пределами карты или на препятствии
+cant-animate = Нельзя сохранить анимацию в { $file }, используйте .gif или .png
+cant-save-stats = Нельзя сохранить статистику в { $file }, используйте .csv или .json
+cant-save-trajectory = Нельзя сохранить траекторию в { $file }, используйте .csv или .ndjson
+
+black-tiles = Чёрных клеток: { $count }
+tiles-per-color = Клеток каждого цвета: { $counts }
+storage-dense = плотное окно
+storage-chunked = блоки с поиском по координатам
+storage-moved = Поле перенесено в { $storage }, записано блоков: { $chunks }
+ant = Муравей
+ant-numbered = Муравей №{ $index }
+ant-stopped = { $name } остановился в { $pos }, смотрит на { $dir }
+ant-left = { $name } ушёл с карты в { $pos }, смотрит на { $dir }
+animation-frames = Кадров анимации: { $count }
+checkpoint-saved = Контрольная точка сохранена после { $steps ->
+    [one] { $steps } шага
+   *[other] { $steps } шагов
+}
+most-visits = Больше всего посещений одной клетки: { $count }
+relief-triangles = Треугольников рельефа: { $count }
+plotter-strokes = Штрихов плоттера: { $count }
+deep-zoom-tiles = Тайлов для глубокого зума: { $count } на { $levels } уровнях, пустых из них { $blank }
+manifest-saved = В манифесте { $count ->
+    [one] { $count } файл
+    [few] { $count } файла
+   *[other] { $count } файлов
+}
+integrity-failed = Проверка целостности не прошла на шаге { $step }: { $error }
+bug-report-saved = Отчёт об ошибке сохранён в { $file }
+crash-report-saved = Запуск завершился аварийно, отчёт для issue сохранён в { $file }
+cant-save-bug-report = Не удалось сохранить отчёт об ошибке: { $error }
+report-saved = Отчёт сохранён в { $file }
+snapshots-saved = По ходу сохранено { $count ->
+    [one] { $count } промежуточное изображение
+    [few] { $count } промежуточных изображения
+   *[other] { $count } промежуточных изображений
+}
+description-saved = Описание сохранено в { $file }
+stats-saved = Статистика ({ $count ->
+    [one] { $count } замер
+    [few] { $count } замера
+   *[other] { $count } замеров
+}) сохранена в { $file }
+trajectory-saved = Траектория ({ $count ->
+    [one] { $count } позиция
+    [few] { $count } позиции
+   *[other] { $count } позиций
+}) сохранена в { $file }
+sound-saved = Звук ({ $seconds } с) сохранён в { $file }
+replay-saved = Записано шагов: { $steps }, запись в { $file }
+
+run-ended-edge = Прогон закончен на шаге { $step }: все муравьи ушли с карты
+run-ended-period = Прогон закончен на шаге { $step }: движение стало периодическим
+run-ended-coverage = Прогон закончен на шаге { $step }: посещённая область достаточно велика
+run-ended-steps = Прогон закончен на шаге { $step }: шаги кончились
+run-ended-stopped = Прогон закончен на шаге { $step }: его остановили
+run-ended-time = Прогон закончен на шаге { $step }: время кончилось через { $seconds } с
+heartbeat = Всё ещё идёт, шаг { $step }, { $speed } шагов в секунду
+highway-found = Шоссе найдено на шаге { $step }: период { $period }, сдвиг на ({ $x }, { $y })
+cycle-found = Цикл найден на шаге { $step }: период { $period }
+growth-stalled = Рост остановился на шаге { $step }: новых клеток нет уже { $since ->
+    [one] { $since } шаг
+    [few] { $since } шага
+   *[other] { $since } шагов
+}
+coverage-reached = Посещённая область занимает { $percent }% карты на шаге { $step }
+boundary-reached = Край карты достигнут на шаге { $step } в ({ $x }, { $y })
+seams = Швы отличаются в { $vertical }% (слева/справа) и { $horizontal }% (сверху/снизу) клеток, внутри { $interior }%
+seams-warning = Внимание: изображение не будет бесшовно повторяться
+
+unbounded-needs-steps = Муравей на бесконечной карте никогда с неё не уходит, задайте число шагов или секунд
+unbounded-limits = На бесконечной карте — только один муравей с двухцветным правилом на белом поле
+unbounded-format = Бесконечную карту можно сохранить только в PNG, SVG, растровый формат, формат плагина или macrocell
+heatmap-needs-bounds = Тепловой карте нужна ограниченная карта
+path-needs-bounds = Путь рисуется только поверх ограниченной карты
+serve-needs-bounds = --serve следит только за ограниченной картой
+serving = Прогон доступен по адресу { $url }
+headless-watch = watch нужен терминал, а задан --headless
+top-left-pixel = Левый верхний пиксель — клетка ({ $x }, { $y })
+macrocell-nodes = Сохранён macrocell, узлов: { $count }, клетки на своих местах
+
+cant-analyze = Не удалось проанализировать { $file }: { $error }
+cant-extract = Не удалось прочитать параметры из { $file }: { $error }
+cant-perturb = Нельзя изменить ({ $x }, { $y }), это за пределами карты
+cells-differ = Шаг { $step }: различается { $distance ->
+    [one] { $distance } клетка
+    [few] { $distance } клетки
+   *[other] { $distance } клеток
+}
+ants-parted = Пути муравьёв разошлись на шаге { $step }
+ants-never-parted = Пути муравьёв так и не разошлись
+ant-left-at = Муравей ушёл с карты на шаге { $step }
+stats-size = Размер: { $width }x{ $height }
+stats-black = Чёрных клеток: { $black } ({ $percent }%)
+stats-bounds = Ограничивающий прямоугольник: ({ $x0 }, { $y0 }) - ({ $x1 }, { $y1 }), { $width }x{ $height }
+stats-bounds-empty = Ограничивающий прямоугольник: пусто
+stats-symmetry = Симметрия слева направо: { $percent }%
+
+cant-load-turmite = Не удалось загрузить тьюрмита { $file }: { $error }
+cant-load-plugin = Не удалось загрузить плагин { $file }: { $error }
+plugin-without-turmite = в плагине { $name } нет тьюрмита
+terminal-error = Ошибка терминала: { $error }
+watch-left = ушёл с карты
+watch-paused = пауза
+watch-running = идёт
+watch-following = , следим
+watch-status = шаг { $step } | { $state } | { $speed } шаг./кадр | вид из ({ $x }, { $y }){ $following } | пробел n + - стрелки f m s c q
+watch-saved = Вид сохранён в { $file }
+watch-cant-save = Не удалось сохранить { $file }: { $error }
+watch-copied = Вид скопирован в буфер обмена текстом
+lesson-start = Нажмите пробел, чтобы сделать шаг, или p, чтобы запустить
+lesson-step = Шаг { $step }: муравей в ({ $x }, { $y }) смотрит на { $from }, цвет клетки под ним — { $color }, поэтому он { $turn }, красит клетку в { $painted } и идёт на { $dir }
+lesson-back = Шаг { $step } отменён
+lesson-turn-left = поворачивает налево
+lesson-turn-right = поворачивает направо
+lesson-turn-none = идёт прямо
+lesson-turn-back = разворачивается
+lesson-white = белый
+lesson-black = чёрный
+lesson-gray = серый { $color }
+lesson-status = шаг { $step } | { $state }{ $following } | пробел шаг, p пуск, стрелки f s c q
+
+tutorial-welcome = Добро пожаловать в antventure! Обучение запустит несколько симуляций и сохранит результаты в { $dir }.
+tutorial-step = Шаг { $step } из { $steps }: { $title }
+tutorial-command = $ { $command }
+tutorial-press-enter = Нажмите Enter, чтобы запустить, или q и Enter, чтобы выйти.
+tutorial-command-failed = команда завершилась с ошибкой: { $status }
+tutorial-run-title = запуск муравья
+tutorial-run-intro = Муравей начинает в центре белого поля. На белой клетке он поворачивает налево, на чёрной — направо, затем меняет цвет клетки и идёт дальше. Эта команда делает 11000 шагов на поле 256 на 256 и сохраняет его в PNG:
+tutorial-run-check = Поле — { $width } на { $height } клеток, чёрных из них: { $black }. Откройте { $file }, чтобы увидеть шоссе, уходящее из хаоса в центре.
+tutorial-no-black = В { $file } нет чёрных клеток, муравей не двигался
+tutorial-rule-title = смена правила
+tutorial-rule-intro = Правило задаёт поворот для каждого цвета клетки по порядку, а клетка получает следующий цвет, когда муравей с неё уходит. Муравей выше — это LR, более длинные правила вроде LLRR или RLR используют больше цветов и рисуют другие фигуры.
+tutorial-rule-prompt = Введите правило из букв L и R или просто нажмите Enter для LLRR:
+tutorial-bad-rule = { $error }, попробуйте ещё раз:
+tutorial-wrong-depth = В { $file } { $bits } бит на пиксель, а правилу нужно { $expected }
+tutorial-rule-check = Цвета ({ $colors }) сохранены в { $bits }-битных оттенках серого, встречается оттенков: { $shades }. Сравните { $file } с первым полем.
+tutorial-animate-title = сохранение анимации
+tutorial-animate-intro = --animate сохраняет ещё и GIF с кадром каждые --frame-every шагов, а в -o по-прежнему попадает последнее поле:
+tutorial-no-frames = В { $file } не больше одного кадра
+tutorial-animate-check = Кадров в анимации: { $frames }. Откройте { $file } в браузере, чтобы её посмотреть.
+tutorial-failed = Обучение прервано: { $error }
+tutorial-quit = Пока! Запустите обучение снова, чтобы начать сначала.
+tutorial-done = Готово! --help перечисляет все параметры, в README больше примеров, а watch --lesson показывает правило по одному шагу.
+
+puzzle-unknown-level = Есть только головоломки с 1 по { $levels }
+puzzle-level = Головоломка { $level } из { $levels }: какое правило и начальное направление нарисовали { $file }?
+puzzle-rules = Муравей начинает в центре поля { $size } на { $size } и делает { $steps } шагов. Введите правило и направление, например LLRR west (north, если не указано), или q, чтобы сдаться.
+puzzle-extra = Нужны только правило и направление, без { $word }
+puzzle-score = Сходство { $score }%, лучшее пока { $best }%, ваше поле в { $file }
+puzzle-solved = Решено { $attempts ->
+    [one] за { $attempts } попытку
+    [few] за { $attempts } попытки
+   *[other] за { $attempts } попыток
+}! Дальше — --level { $next }.
+puzzle-solved-last = Решено { $attempts ->
+    [one] за { $attempts } попытку
+    [few] за { $attempts } попытки
+   *[other] за { $attempts } попыток
+}! Это была последняя головоломка.
+puzzle-gave-up = Это было { $rule } с направлением { $dir }.
+
+hex-empty-map = На карте должна быть хотя бы одна клетка
+worm-eating = Червь всё ещё ест: рёбер { $steps }, генов { $genes }
+worm-starved = Червь умер от голода: рёбер { $steps }, генов { $genes }
+worm-undecided = Гены правила кончились после { $steps } рёбер, добавьте ещё
+worm-blocked = Ген { $gene } указывает на съеденное ребро после { $steps } рёбер
+voxel-too-big = Модель .vox вмещает не больше { $max } вокселей по стороне, сохраните PNG-срезы
+voxel-slices-saved = Сохранено слоёв: { $count }
+
+search-no-rules = Нечего перебирать, --max-len должен быть не меньше 2
+cant-create = Не удалось создать { $dir }: { $error }
+search-trying = Правил к перебору: { $count }, карта { $size }x{ $size }
+search-speed = Шагов: { $steps }, потоков: { $workers }, { $speed } шагов/с на поток
+search-header = ранг  правило           цветных  площадь  шоссе на шаге
+search-mosaic = Мозаика лучших правил сохранена, их { $count }
+daily-drawn = Картина дня { $date }: правило { $rule }, направление { $dir }, { $steps } шагов, сохранена в { $file }
+daily-repeat = Нарисовать её снова может кто угодно: { $command }
+optimize-trying = Правил к перебору: { $count }, стартовых клеток у каждого: { $starts }, карта { $size }x{ $size }
+optimize-header = ранг  правило           сходство  старт
+bench-no-workloads = Ни одна нагрузка не подходит под --only
+bench-debug-build = Это отладочная сборка, соберите с --release, чтобы цифры можно было сравнивать
+bench-machine = antventure { $version }, { $os } { $arch }, процессоров: { $cpus }
+bench-header = | нагрузка | хранилище | шагов/с | время | пик памяти |
+optimize-best = Ближе всего, со сходством { $score }%: правило { $rule } из ({ $x }, { $y }) с направлением { $dir }, поле сохранено в { $file }
+
+repl-welcome = Поле { $size }x{ $size } с правилом { $rule } и муравьём в центре, смотрящим на { $dir }. Введите help, чтобы увидеть команды.
+repl-help =
+    step [count]          сделать шаг всеми муравьями, или count шагов
+    back [count]          вернуться на шаг назад, или на count шагов
+    get x y               цвет клетки
+    set x y color         покрасить клетку в white, black или цвет по номеру
+    spawn x y [direction] добавить муравья, смотрящего на north, если не указано
+    ants                  где каждый муравей
+    stats                 шаги, повороты и клетки каждого цвета
+    save file.png         сохранить поле
+    reset                 начать заново на белом поле
+    quit                  выйти
+repl-stepped = Шаг { $steps }
+repl-all-left = Шаг { $steps }, все муравьи ушли с карты
+repl-cell = ({ $x }, { $y }): { $name }, цвет { $color }
+repl-spawned = Муравей №{ $index } добавлен
+repl-stats = Шаг { $steps }, на карте { $on_map } из { $ants } муравьёв, поворотов налево { $left } и направо { $right }
+repl-saved = Поле сохранено в { $file }
+repl-cant-save = Не удалось сохранить { $file }: { $error }
+repl-reset = Снова белое поле с муравьём в центре
+repl-off-map = ({ $x }, { $y }) за пределами карты
+repl-no-color = Нет цвета { $color }, у правила цвета от 0 до { $last }
+repl-not-a-number = { $word } не число
+repl-usage = Использование: { $usage }
+repl-unknown = Нет команды { $command }, введите help, чтобы увидеть список
+
+verify-running = { $rule } идёт по { $backends } в ногу, муравьи сравниваются каждый шаг, а клетки каждые { $every }
+verify-versus = { $rule } и { $versus } идут по ColorMap в ногу, муравьи сравниваются каждый шаг, а клетки каждые { $every }
+verify-agree = Все { $count } бэкенда согласны после { $steps } шагов
+verify-one-backend = Цвета { $rule } есть только у ColorMap, сравнивать не с чем
+verify-no-mmap = MmapMap пропущен, не удалось отобразить память: { $error }
+verify-left = На шаге { $step } муравей ушёл с карты на { $backend }, но не на { $other }
+verify-ant = На шаге { $step } муравей на { $reference } в { $pos }, смотрит на { $dir }, а на { $backend } в { $other_pos }, смотрит на { $other_dir }
+verify-cells = На шаге { $step } { $count } клеток { $backend } отличаются от { $reference }, первая в ({ $x }, { $y })
+run-failed = Запуск не удался: { $error }
+cant-diff = Не удалось сравнить { $file }: { $error }
+diff-sizes = Доски { $a } и { $b } клеток нельзя сравнить, они должны быть квадратными и одного размера
+diff-stats = Отличаются { $changed } клеток: { $only_a } черны только на первой доске, { $only_b } только на второй, { $both } на обеих
+diff-cant-save = Не удалось сохранить разницу в { $file }: { $error }
+diff-saved = Разница сохранена в { $file }
+cant-replay = Не удалось воспроизвести { $file }: { $error }
+replay-rendered = Доска на шаге { $step } сохранена в { $file }
+replay-animated = Кадров до шага { $step }: { $frames }, сохранены в { $file }
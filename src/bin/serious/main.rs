@@ -0,0 +1,1843 @@
+mod analyze;
+mod bench;
+mod bug_report;
+mod config;
+mod daily;
+mod diff;
+mod extract;
+mod hex;
+mod i18n;
+mod optimize;
+mod puzzle;
+mod repl;
+mod replay;
+mod search;
+#[cfg(feature = "serve")]
+mod serve;
+mod tutorial;
+mod verify;
+mod voxel;
+mod watch;
+mod worm;
+
+use std::{
+    fmt::{self, Debug, Formatter},
+    fs,
+    io::{self, ErrorKind},
+    iter,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use antventure::{
+    save_color_map_to_file, save_hatched_map_to_file, save_map_to_file, save_palette_map_to_file,
+    save_sparse_map_to_file, write_macrocell, Animation, AnimationFormat, AntLayer,
+    AntventureError, ArtifactKind, Boundary, CellLayer, CellStore, Checkpoint, CheckpointError,
+    CheckpointSeries, ColorMap, ControlHandle, Coordinates, Crop, Cue, DeepZoom, DensityGrid,
+    Description, DynMap, FigureFormat, Filtered, Frame, HeatColors, HeatLayer, Heatmap,
+    LayeredRenderer, Manifest, Map, Obstacles, Oriented, Origin, OutputSink, Pacer, Palette,
+    PathLayer, Pattern, PhaseDetector, PhaseEvent, PlotFormat, Plugin, PngRenderer, Pos,
+    RandomFill, RawVideo, Renderer, ReplayRecorder, Report, RunSummary, SchematicFormat, Seams,
+    Series, Simulation, SnapshotWriter, SoundTrack, StatsFormat, StatsLog, Storage, SvgRenderer,
+    TrajectoryFormat, TrajectoryLog, TrajectoryTail, UnboundedAnt,
+};
+use clap::{Parser, Subcommand};
+use config::{with_size, Args, Config, ImageFormat, Render};
+use i18n::tr;
+use image::DynamicImage;
+use png::EncodingError;
+
+/// Set while raw frames go to stdout
+static FRAMES_ON_STDOUT: AtomicBool = AtomicBool::new(false);
+
+/// Boards wider than this are shown in HTML reports as a thumbnail this wide
+const REPORT_IMAGE_SIZE: usize = 1024;
+
+/// Steps between looks at the clock under `--max-seconds` and `--headless`
+const CLOCK_EVERY: u64 = 1 << 16;
+
+/// Time between the lines telling a `--headless` run is alive
+const HEARTBEAT: Duration = Duration::from_secs(10);
+
+/// Latest points of `--render path`, older ones fade towards black
+const PATH_COLOR: [u8; 3] = [0, 160, 255];
+
+/// `println!` for what a run tells, which moves to stderr while stdout carries frames
+macro_rules! say {
+    ($($arg:tt)*) => {
+        match FRAMES_ON_STDOUT.load(Ordering::Relaxed) {
+            true => eprintln!($($arg)*),
+            false => println!($($arg)*),
+        }
+    };
+}
+
+/// Langton's ant on a bit-packed map
+#[derive(Parser)]
+#[command(version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    args: Args,
+
+    /// Language of messages, ANTVENTURE_LANG or the system one by default
+    #[arg(long, global = true, value_parser = parse_lang)]
+    lang: Option<String>,
+}
+
+fn parse_lang(s: &str) -> Result<String, String> {
+    match i18n::languages().any(|lang| lang == s) {
+        true => Ok(s.to_owned()),
+        false => Err(format!(
+            "unknown language {s:?}, expected one of {}",
+            i18n::languages().collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print statistics of a saved board
+    Analyze(analyze::AnalyzeArgs),
+    /// Watch a single ant walk in the terminal
+    Watch(Box<watch::WatchArgs>),
+    /// Walk a turmite over hexagons instead of squares
+    Hex(hex::HexArgs),
+    /// Walk a turmite through a box of voxels in 3D
+    Voxel(voxel::VoxelArgs),
+    /// Let a Paterson's worm eat the edges of a triangular lattice
+    Worm(worm::WormArgs),
+    /// Simulate many rules in parallel and rank the interesting ones
+    Search(search::SearchArgs),
+    /// Learn the basics by running a few simulations step by step
+    Tutorial(tutorial::TutorialArgs),
+    /// Guess the rule and direction behind a board
+    Puzzle(puzzle::PuzzleArgs),
+    /// Look for the rule and start cell of an ant that draws something like an image
+    Optimize(optimize::OptimizeArgs),
+    /// Draw the picture of the day, picked from the date alone
+    Daily(daily::DailyArgs),
+    /// Print the command hidden in an image saved with --embed-params
+    ExtractParams(extract::ExtractArgs),
+    /// Time standard walks on every backend, to pick one or to report performance
+    Bench(bench::BenchArgs),
+    /// Drive a simulation by typing commands like step 1000, get x y or save board.png
+    Repl(repl::ReplArgs),
+    /// Walk the same ant on every backend in lockstep and report where they first disagree
+    Verify(verify::VerifyArgs),
+    /// Count and paint the cells two saved boards or checkpoints disagree on
+    Diff(diff::DiffArgs),
+    /// Render a run recorded with --record again, at any scale, crop or frame rate
+    Replay(replay::ReplayArgs),
+}
+
+fn main() -> Result<ExitCode, Failed> {
+    // Missing .env is fine, we just go with the real environment then
+    dotenvy::dotenv().ok();
+
+    let cli = Cli::parse();
+    // Messages are translated before the config is read, so the variable is looked at here
+    let lang = cli
+        .lang
+        .or_else(|| std::env::var(format!("{}LANG", Config::PREFIX)).ok());
+    i18n::init(lang.as_deref());
+    match cli.command {
+        Some(Command::Analyze(args)) => return Ok(analyze::run(args)),
+        Some(Command::Watch(args)) => return Ok(watch::run(*args)),
+        Some(Command::Hex(args)) => return Ok(hex::run(args)?),
+        Some(Command::Voxel(args)) => return Ok(voxel::run(args)?),
+        Some(Command::Worm(args)) => return Ok(worm::run(args)?),
+        Some(Command::Search(args)) => return Ok(search::run(args)?),
+        Some(Command::Tutorial(args)) => return Ok(tutorial::run(args, lang)),
+        Some(Command::Puzzle(args)) => return Ok(puzzle::run(args)?),
+        Some(Command::Optimize(args)) => return Ok(optimize::run(args)?),
+        Some(Command::Daily(args)) => return Ok(daily::run(args)?),
+        Some(Command::ExtractParams(args)) => return Ok(extract::run(args)),
+        Some(Command::Bench(args)) => return Ok(bench::run(args)),
+        Some(Command::Repl(args)) => return Ok(repl::run(args)),
+        Some(Command::Verify(args)) => return Ok(verify::run(args)),
+        Some(Command::Diff(args)) => return Ok(diff::run(args)),
+        Some(Command::Replay(args)) => return Ok(replay::run(args)),
+        None => {}
+    }
+
+    let mut config = match Config::load(cli.args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", tr!("invalid-config", error = e));
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    FRAMES_ON_STDOUT.store(config.raw_frames.is_some(), Ordering::Relaxed);
+
+    if config.unbounded {
+        bug_report::install(&config);
+        return Ok(run_unbounded(&config)?);
+    }
+    if config.boundary != Boundary::Stop && config.steps.is_none() && config.max_seconds.is_none() {
+        eprintln!("{}", tr!("boundary-needs-steps"));
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let resume = match config.resume.as_ref().map(Checkpoint::load).transpose() {
+        Ok(resume) => resume,
+        Err(e) => {
+            eprintln!("{}", tr!("cant-resume", error = e.to_string()));
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    if let Some(checkpoint) = &resume {
+        // The checkpoint decides what's being simulated, the rest of the config still applies
+        match checkpoint.rule() {
+            Ok(rule) if checkpoint.width == checkpoint.height => {
+                config.size = checkpoint.width;
+                config.rule = rule;
+            }
+            Ok(_) => {
+                eprintln!("{}", tr!("resume-not-square"));
+                return Ok(ExitCode::FAILURE);
+            }
+            Err(e) => {
+                eprintln!("{}", tr!("cant-resume", error = e.to_string()));
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+    let resume = resume.as_ref();
+
+    if let (Some(rule), None) = (&config.stochastic_rule, resume) {
+        // Its likely turns stand in for it wherever a plain rule is needed
+        config.rule = rule.likely_rule();
+        let seed = *config.seed.get_or_insert_with(random_seed);
+        say!("{}", tr!("stochastic-seed", seed = seed.to_string()));
+    }
+
+    let image = match config.import.as_ref().map(image::open).transpose() {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!("{}", tr!("cant-import", error = e.to_string()));
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    let mask = match config.obstacles.as_ref().map(image::open).transpose() {
+        Ok(mask) => mask,
+        Err(e) => {
+            eprintln!("{}", tr!("cant-load-obstacles", error = e.to_string()));
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    let pattern = match config.load.as_ref().map(load_pattern).transpose() {
+        Ok(pattern) => pattern,
+        Err(e) => {
+            eprintln!("{}", tr!("cant-load-pattern", error = e));
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    if let Some(pattern) = &pattern {
+        if pattern.colors() > config.rule.colors() {
+            eprintln!(
+                "{}",
+                tr!(
+                    "pattern-colors",
+                    pattern = pattern.colors(),
+                    rule = config.rule.colors()
+                )
+            );
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+    let board = match (&image, &pattern, config.random_fill) {
+        (None, None, None) => Board::White,
+        (Some(image), None, None) => Board::Image(image),
+        (None, Some(pattern), None) => Board::Pattern(pattern),
+        (None, None, Some(density)) => {
+            let seed = *config.seed.get_or_insert_with(random_seed);
+            let Some(fill) = RandomFill::new(density, seed) else {
+                eprintln!("{}", tr!("bad-density", density = density));
+                return Ok(ExitCode::FAILURE);
+            };
+            say!("{}", tr!("random-seed", seed = seed.to_string()));
+            Board::Random(fill)
+        }
+        _ => {
+            eprintln!("{}", tr!("several-boards"));
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    bug_report::install(&config);
+    let Some(board_size) = config::board_size(config.size) else {
+        let sizes = format!("{:?}", config::SIZES);
+        eprintln!(
+            "{}",
+            tr!("unsupported-size", size = config.size, sizes = sizes)
+        );
+        return Ok(ExitCode::FAILURE);
+    };
+    match with_size!(board_size, run(&config, board, mask.as_ref(), resume)) {
+        Some(ran) => Ok(ran?),
+        None => Ok(ExitCode::FAILURE),
+    }
+}
+
+/// What a run that failed gives up with, told to the user in their language
+struct Failed(AntventureError);
+
+impl From<AntventureError> for Failed {
+    fn from(e: AntventureError) -> Self {
+        Failed(e)
+    }
+}
+
+// Returned from main, so it's what gets printed after "Error:"
+impl Debug for Failed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            AntventureError::Spawn(pos) => {
+                write!(f, "{}", tr!("cant-spawn", x = pos.x, y = pos.y))
+            }
+            e => write!(f, "{}", tr!("run-failed", error = e.to_string())),
+        }
+    }
+}
+
+fn load_pattern(path: impl AsRef<Path>) -> Result<Pattern, String> {
+    let rle = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    rle.parse::<Pattern>().map_err(|e| e.to_string())
+}
+
+/// Seed for runs that didn't ask for one, printed so they can be repeated
+fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos() as u64)
+}
+
+/// What the map looks like before the first step
+#[derive(Clone, Copy)]
+enum Board<'a> {
+    White,
+    Image(&'a DynamicImage),
+    Pattern(&'a Pattern),
+    Random(RandomFill),
+}
+
+/// Start from `board` or carry on from `resume`, keeping ants off the dark cells of `mask`
+fn run<const S: usize>(
+    config: &Config,
+    board: Board,
+    mask: Option<&DynamicImage>,
+    resume: Option<&Checkpoint>,
+) -> Result<ExitCode, AntventureError> {
+    // Other sizes run on a bit map sized at runtime, in the top left of the next board up
+    if config.size != S {
+        let unsupported = match () {
+            _ if config.colors() > <DynMap as CellStore<S, S>>::COLORS => {
+                Some(format!("--rule {}", config.rule))
+            }
+            _ if config.checkpoint.is_some() => Some("--checkpoint".to_owned()),
+            _ => None,
+        };
+        if let Some(option) = unsupported {
+            let sizes = format!("{:?}", config::SIZES);
+            eprintln!(
+                "{}",
+                tr!("size-unsupported", option = option, sizes = sizes)
+            );
+            return Ok(ExitCode::FAILURE);
+        }
+        return run_on::<S, DynMap>(config, board, mask, resume);
+    }
+    // Two colors fit the bit-packed map, anything more needs a byte per cell
+    match config.colors() <= Map::<S, S>::COLORS {
+        true => run_on::<S, Map<S, S>>(config, board, mask, resume),
+        false => run_on::<S, ColorMap<S, S>>(config, board, mask, resume),
+    }
+}
+
+/// What [`run_on`] needs from a board beyond [`CellStore`]
+trait Store<const S: usize>: CellStore<S, S> + Clone + Sync {
+    /// White map `size` cells a side, stores of a compile-time size are `S` already
+    fn new_white(size: usize) -> Self;
+
+    /// Map `size` cells a side with these colors in row-major order
+    fn sized(colors: Vec<u8>, size: usize) -> Self;
+
+    /// Tell how many cells ended up in each color
+    fn say_counts(&self, colors: usize);
+
+    /// Save the whole board as a plain grayscale PNG
+    fn save_plain(&self, colors: usize, config: &Config) -> Result<(), EncodingError>;
+}
+
+impl<const S: usize> Store<S> for Map<S, S> {
+    fn new_white(_: usize) -> Self {
+        Map::new_white()
+    }
+
+    fn sized(colors: Vec<u8>, _: usize) -> Self {
+        Map::with_colors(colors)
+    }
+
+    fn say_counts(&self, _: usize) {
+        say!("{}", tr!("black-tiles", count = self.count_black_tiles()));
+    }
+
+    fn save_plain(&self, _: usize, config: &Config) -> Result<(), EncodingError> {
+        save_map_to_file(self, &config.output)
+    }
+}
+
+impl<const S: usize> Store<S> for ColorMap<S, S> {
+    fn new_white(_: usize) -> Self {
+        ColorMap::new_white()
+    }
+
+    fn sized(colors: Vec<u8>, _: usize) -> Self {
+        ColorMap::with_colors(colors)
+    }
+
+    fn say_counts(&self, colors: usize) {
+        let counts = format!("{:?}", self.count_colors(colors));
+        say!("{}", tr!("tiles-per-color", counts = counts));
+    }
+
+    fn save_plain(&self, colors: usize, config: &Config) -> Result<(), EncodingError> {
+        save_color_map_to_file(self, colors, config.bit_depth, None, &config.output)
+    }
+}
+
+impl<const S: usize> Store<S> for DynMap {
+    fn new_white(size: usize) -> Self {
+        DynMap::new_white(size, size)
+    }
+
+    fn sized(colors: Vec<u8>, size: usize) -> Self {
+        let mut map = <DynMap as CellStore<S, S>>::with_colors(colors);
+        map.resize(size, size);
+        map
+    }
+
+    fn say_counts(&self, _: usize) {
+        say!("{}", tr!("black-tiles", count = self.count_black_tiles()));
+    }
+
+    fn save_plain(&self, _: usize, config: &Config) -> Result<(), EncodingError> {
+        self.save(&config.output)
+    }
+}
+
+/// [`run`] on the board type picked for the number of colors
+fn run_on<const S: usize, M: Store<S>>(
+    config: &Config,
+    board: Board,
+    mask: Option<&DynamicImage>,
+    resume: Option<&Checkpoint>,
+) -> Result<ExitCode, AntventureError> {
+    let colors = config.colors();
+    let mut manifest = Manifest::new();
+
+    let size = config.size;
+    let black = |cells: Vec<bool>| cells.into_iter().map(u8::from).collect();
+    let new_map = || match board {
+        Board::White => M::new_white(size),
+        Board::Image(image) => {
+            let cells = config.import_options.black_cells(image, size, size);
+            M::sized(black(cells), size)
+        }
+        Board::Pattern(pattern) => M::sized(pattern.to_colors(size, size), size),
+        Board::Random(fill) => M::sized(black(fill.black_cells(size, size)), size),
+    };
+    let Some((map, ants, crop, palette, heat, path, heatmap)) =
+        simulate(new_map, mask, resume, config, &mut manifest)?
+    else {
+        return Ok(ExitCode::FAILURE);
+    };
+    let config = &with_palette(config, palette);
+
+    map.say_counts(colors);
+    if config.boundary == Boundary::Wrap {
+        report_seams(&map);
+    }
+    if let Some(path) = &config.save_rle {
+        fs::write(path, Pattern::from_map(&map).to_rle())?;
+        record(&mut manifest, ArtifactKind::Pattern, path, config)?;
+    }
+    let (shown, (marked, cropped)) = (
+        &Oriented::new(&map, config.orientation),
+        oriented_marks(&ants, crop, config),
+    );
+    save_thumbnail(shown, colors, config, &mut manifest)?;
+    if let Some(heatmap) = heatmap {
+        save_heatmap(&heatmap, cropped, config)?;
+        record(&mut manifest, ArtifactKind::Heatmap, &config.output, config)?;
+    }
+    if matches!(config.render, Render::Board | Render::Path) {
+        save(shown, colors, &marked, cropped, config, |shown| {
+            match (config.hatch, config.color_palette(colors)) {
+                // Layers read the board as it is and are turned once painted
+                _ if config.layers.is_some() || config.render == Render::Path => {
+                    save_layered(&map, colors, &ants, heat, path, crop, config)
+                }
+                _ if config.embed_params || config.scale > 1 && !config.hatch => {
+                    save_rendered(shown, colors, &marked, cropped, config)
+                }
+                (true, palette) => save_hatched(shown, colors, palette, &marked, cropped, config),
+                (false, Some(palette)) => save_palette_map_to_file(
+                    shown,
+                    colors,
+                    &palette,
+                    &marked,
+                    cropped,
+                    &config.output,
+                ),
+                (false, None) if cropped.is_some() || !config.orientation.is_identity() => {
+                    save_color_map_to_file(shown, colors, config.bit_depth, cropped, &config.output)
+                }
+                (false, None) => map.save_plain(colors, config),
+            }
+        })?;
+        record(&mut manifest, ArtifactKind::Board, &config.output, config)?;
+    }
+
+    save_manifest(&manifest, config)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// List a file the run wrote, if a manifest is asked for
+fn record(
+    manifest: &mut Manifest,
+    kind: ArtifactKind,
+    path: impl Into<PathBuf>,
+    config: &Config,
+) -> io::Result<()> {
+    match config.manifest {
+        Some(_) => manifest.add(kind, path),
+        None => Ok(()),
+    }
+}
+
+fn save_manifest(manifest: &Manifest, config: &Config) -> io::Result<()> {
+    if let Some(path) = &config.manifest {
+        manifest.save(path)?;
+        say!(
+            "{}",
+            tr!("manifest-saved", count = manifest.artifacts.len())
+        );
+    }
+    Ok(())
+}
+
+fn save_bug_report(manifest: &mut Manifest, config: &Config) -> io::Result<()> {
+    if let Some(saved) = bug_report::save() {
+        let path = saved?;
+        let file = path.display().to_string();
+        say!("{}", tr!("bug-report-saved", file = file));
+        record(manifest, ArtifactKind::BugReport, path, config)?;
+    }
+    Ok(())
+}
+
+/// Print a phase change and save a snapshot of the board if asked to
+fn report_phase<const S: usize, M: CellStore<S, S> + Clone>(
+    sim: &Simulation<S, S, M>,
+    event: PhaseEvent,
+    config: &Config,
+    manifest: &mut Manifest,
+) -> Result<(), AntventureError> {
+    let step = sim.steps();
+    let kind = match event {
+        PhaseEvent::Highway(periodicity) => {
+            // A shift, so the origin drops out
+            let (end, start) = (
+                config.position(periodicity.translation),
+                config.position(Pos::new(0, 0)),
+            );
+            let (x, y) = (end.x - start.x, end.y - start.y);
+            say!(
+                "{}",
+                tr!(
+                    "highway-found",
+                    step = step,
+                    period = periodicity.period,
+                    x = x,
+                    y = y
+                )
+            );
+            "highway"
+        }
+        PhaseEvent::Cycle(periodicity) => {
+            say!(
+                "{}",
+                tr!("cycle-found", step = step, period = periodicity.period)
+            );
+            "cycle"
+        }
+        PhaseEvent::GrowthStalled { since } => {
+            say!("{}", tr!("growth-stalled", step = step, since = since));
+            "stall"
+        }
+        PhaseEvent::Coverage { fraction } => {
+            let percent = format!("{:.1}", fraction * 100.0);
+            say!(
+                "{}",
+                tr!("coverage-reached", percent = percent, step = step)
+            );
+            "coverage"
+        }
+        PhaseEvent::BoundaryReached { pos } => {
+            let pos = config.position(pos);
+            say!(
+                "{}",
+                tr!("boundary-reached", step = step, x = pos.x, y = pos.y)
+            );
+            "boundary"
+        }
+    };
+
+    if let Some(prefix) = &config.phase_snapshots {
+        let mut name = prefix.clone().into_os_string();
+        name.push(format!("-{kind}-{step}.png"));
+        let colors = sim.rule().colors();
+        let map = Oriented::new(sim.map(), config.orientation);
+        save_palette_map_to_file(&map, colors, &Palette::gray(colors), &[], None, &name)?;
+        record(manifest, ArtifactKind::Snapshot, name, config)?;
+    }
+    Ok(())
+}
+
+/// Give every output the board as it is, leaving out the ones nobody reads anymore
+fn feed<const S: usize, M: CellStore<S, S> + Clone>(
+    sinks: &mut Vec<(Box<dyn OutputSink>, Output)>,
+    sim: &Simulation<S, S, M>,
+    config: &Config,
+) -> io::Result<()> {
+    if sinks.is_empty() {
+        return Ok(());
+    }
+    let map = Oriented::new(sim.map(), config.orientation);
+    let frame = Frame::new(&map, config.colors(), None);
+    let mut failed = Ok(());
+    sinks.retain_mut(|(sink, _)| match sink.accept_frame(&frame, sim.steps()) {
+        Ok(()) => true,
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => false,
+        Err(e) => {
+            failed = Err(e);
+            true
+        }
+    });
+    failed
+}
+
+/// The config with the palette the timeline switched to, if it did
+fn with_palette(config: &Config, palette: Option<Palette>) -> Config {
+    let mut config = config.clone();
+    config.palette = palette.or(config.palette.take());
+    config
+}
+
+/// Print what a cue of the timeline did
+fn report_cue(step: u64, cue: &Cue) {
+    let message = match cue {
+        Cue::Spawn { pos, .. } => tr!("ant-joined", x = pos.x, y = pos.y, step = step),
+        Cue::Rule(rule) => tr!("rule-changed", rule = rule.to_string(), step = step),
+        Cue::Flip { min, max } => tr!(
+            "cells-flipped",
+            x0 = min.x,
+            y0 = min.y,
+            x1 = max.x,
+            y1 = max.y,
+            step = step
+        ),
+        Cue::Palette(_) => tr!("palette-changed", step = step),
+    };
+    say!("{message}");
+}
+
+/// Compare the edges of a wrapped map, they should continue each other like the inside does
+fn report_seams<const S: usize>(map: &impl CellStore<S, S>) {
+    let seams = Seams::measure(map);
+    let percent = |share: f64| format!("{:.1}", share * 100.0);
+    say!(
+        "{}",
+        tr!(
+            "seams",
+            vertical = percent(seams.vertical),
+            horizontal = percent(seams.horizontal),
+            interior = percent(seams.interior)
+        )
+    );
+    if !seams.is_seamless() {
+        say!("{}", tr!("seams-warning"));
+    }
+}
+
+/// A single ant on an endless board, starting at `(0, 0)` unless told otherwise
+fn run_unbounded(config: &Config) -> Result<ExitCode, AntventureError> {
+    if config.steps.is_none() && config.max_seconds.is_none() {
+        eprintln!("{}", tr!("unbounded-needs-steps"));
+        return Ok(ExitCode::FAILURE);
+    }
+    if config.rule.colors() > 2
+        || config.ants.len() > 1
+        || config.import.is_some()
+        || config.load.is_some()
+        || config.obstacles.is_some()
+        || !config.rule_changes.is_empty()
+        || !config.timeline.is_empty()
+    {
+        eprintln!("{}", tr!("unbounded-limits"));
+        return Ok(ExitCode::FAILURE);
+    }
+
+    if config.render == Render::Heatmap {
+        eprintln!("{}", tr!("heatmap-needs-bounds"));
+        return Ok(ExitCode::FAILURE);
+    }
+    if config.render == Render::Path {
+        eprintln!("{}", tr!("path-needs-bounds"));
+        return Ok(ExitCode::FAILURE);
+    }
+    #[cfg(feature = "serve")]
+    if config.serve.is_some() {
+        eprintln!("{}", tr!("serve-needs-bounds"));
+        return Ok(ExitCode::FAILURE);
+    }
+
+    // An endless board has no corners, positions count from where ants start
+    let coordinates = Coordinates {
+        origin: Origin::Center,
+        ..config.coordinates
+    };
+    let (pos, dir) = match config.ants.first() {
+        Some(&ant) => ant,
+        None => (
+            Pos::new(config.start_x.unwrap_or(0), config.start_y.unwrap_or(0)),
+            config.dir,
+        ),
+    };
+    let pos = coordinates.to_map(pos, (0, 0));
+    let mut ant = UnboundedAnt::with_rule(pos, dir, config.rule.clone());
+    let started = Instant::now();
+    let deadline = config.max_seconds.map(|seconds| started + seconds);
+    let mut left = config.steps.map_or(u64::MAX, |steps| steps as u64);
+    let mut walked = 0;
+    let mut heartbeat = Heartbeat::new(config, 0);
+    let ending = loop {
+        if left == 0 {
+            break Ending::Steps;
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            break Ending::Time;
+        }
+        if let Some(heartbeat) = &mut heartbeat {
+            heartbeat.beat(walked);
+        }
+        let steps = left.min(CLOCK_EVERY);
+        ant.walk_steps(steps as usize);
+        (left, walked) = (left - steps, walked + steps);
+    };
+    report_ending(ending, walked, started);
+
+    say!(
+        "{}",
+        tr!(
+            "ant-stopped",
+            name = tr!("ant"),
+            pos = {
+                let pos = coordinates.position(ant.pos(), (0, 0));
+                format!("({}, {})", pos.x, pos.y)
+            },
+            dir = i18n::direction(ant.dir())
+        )
+    );
+    say!(
+        "{}",
+        tr!("black-tiles", count = ant.map().count_black_tiles())
+    );
+    for migration in ant.map().migrations() {
+        let storage = match migration.to {
+            Storage::Dense => tr!("storage-dense"),
+            Storage::Chunked => tr!("storage-chunked"),
+        };
+        say!(
+            "{}",
+            tr!(
+                "storage-moved",
+                storage = storage,
+                chunks = migration.chunks
+            )
+        );
+    }
+    let mut manifest = Manifest::new();
+    let macrocell = config
+        .output
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mc"));
+    match macrocell {
+        true => {
+            let w = io::BufWriter::new(fs::File::create(&config.output)?);
+            let nodes = write_macrocell(ant.map(), w)?;
+            say!("{}", tr!("macrocell-nodes", count = nodes));
+            record(&mut manifest, ArtifactKind::Pattern, &config.output, config)?;
+        }
+        false => {
+            let frame = || Frame::from_sparse(ant.map()).with_ants(&[ant.pos()]);
+            let origin = match save_frame(frame, 2, config)? {
+                true => frame().origin,
+                false if reads_board(config) => {
+                    eprintln!("{}", tr!("unbounded-format"));
+                    return Ok(ExitCode::FAILURE);
+                }
+                false => save_sparse_map_to_file(ant.map(), &config.output)?,
+            };
+            say!("{}", tr!("top-left-pixel", x = origin.x, y = origin.y));
+            record(&mut manifest, ArtifactKind::Board, &config.output, config)?;
+        }
+    }
+    bug_report::progress(walked);
+    save_bug_report(&mut manifest, config)?;
+    save_manifest(&manifest, config)?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Gray thumbnail of the final map, if `--thumbnail` asks for one
+fn save_thumbnail<const S: usize>(
+    map: &impl CellStore<S, S>,
+    colors: usize,
+    config: &Config,
+    manifest: &mut Manifest,
+) -> Result<(), AntventureError> {
+    if let Some(path) = &config.thumbnail {
+        DensityGrid::new(map, colors, config.thumbnail_size).save(path)?;
+        record(manifest, ArtifactKind::Thumbnail, path, config)?;
+    }
+    Ok(())
+}
+
+/// Save the final map in the format the output extension asks for, `save_png` otherwise
+fn save<const S: usize, M: CellStore<S, S>>(
+    map: &M,
+    colors: usize,
+    ants: &[Pos],
+    crop: Option<Crop>,
+    config: &Config,
+    save_png: impl FnOnce(&M) -> Result<(), EncodingError>,
+) -> Result<(), AntventureError> {
+    if save_frame(
+        || Frame::new(map, colors, crop).with_ants(ants),
+        colors,
+        config,
+    )? {
+        return Ok(());
+    }
+    let output = &config.output;
+    if config.format == Some(ImageFormat::Png) {
+        save_png(map)?;
+    } else if let Some(format) = FigureFormat::from_path(output) {
+        config.figure.save(map, colors, format, output)?;
+    } else if output
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("stl"))
+    {
+        let triangles = config.relief.save(map, colors, output)?;
+        say!("{}", tr!("relief-triangles", count = triangles));
+    } else if let Some(format) = PlotFormat::from_path(output) {
+        let strokes = config.plot.save(map, format, output)?;
+        say!("{}", tr!("plotter-strokes", count = strokes));
+    } else if output
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("dzi"))
+    {
+        let deep_zoom = DeepZoom {
+            palette: config.color_palette(colors),
+            ..config.deep_zoom.clone()
+        };
+        let pyramid = deep_zoom.save(map, colors, output)?;
+        say!(
+            "{}",
+            tr!(
+                "deep-zoom-tiles",
+                count = pyramid.tiles,
+                blank = pyramid.blank,
+                levels = pyramid.levels
+            )
+        );
+    } else if let Some(format) = SchematicFormat::from_path(output) {
+        config.schematic.save(map, colors, format, output)?;
+    } else {
+        save_png(map)?;
+    }
+    Ok(())
+}
+
+/// Visit counts as a board of heat levels, saved in any format a board is
+fn save_heatmap<const S: usize>(
+    heatmap: &Heatmap<S, S>,
+    crop: Option<Crop>,
+    config: &Config,
+) -> Result<(), AntventureError> {
+    say!("{}", tr!("most-visits", count = heatmap.max()));
+    let palette = config.heat_colors.palette();
+    let config = &with_palette(config, Some(palette.clone()));
+    let visits = ColorMap::<S, S>::with_colors(heatmap.levels());
+    let shown = &Oriented::new(&visits, config.orientation);
+    let levels = HeatColors::LEVELS;
+    save(shown, levels, &[], crop, config, |shown| {
+        save_palette_map_to_file(shown, levels, &palette, &[], crop, &config.output)
+    })
+}
+
+/// Write `frame` if the output is in a format made from one, `false` for PNG and
+/// the formats reading the whole board
+fn save_frame(
+    frame: impl FnOnce() -> Frame,
+    colors: usize,
+    config: &Config,
+) -> Result<bool, AntventureError> {
+    let output = &config.output;
+    let format = config.format.or_else(|| ImageFormat::from_path(output));
+    let plugin = match &config.plugin {
+        // SAFETY: --plugin is trusted to be a plugin like any program it runs
+        Some(lib) => Some(unsafe { Plugin::load(lib) }?),
+        None => None,
+    };
+    let plugin_renderer = plugin
+        .as_ref()
+        .filter(|plugin| {
+            let extension = plugin.extension().unwrap_or_default();
+            output
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+        })
+        .and_then(Plugin::renderer);
+    if let Some(renderer) = plugin_renderer {
+        renderer.save(&frame(), output)?;
+    } else if format == Some(ImageFormat::Svg) {
+        let renderer = SvgRenderer {
+            palette: config.color_palette(colors),
+        };
+        renderer.save(&frame(), output)?;
+    } else if let Some(ImageFormat::Raster(renderer)) = format {
+        renderer.save(&frame(), output)?;
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Whether the output is in a format built from a whole bounded board, like figures
+/// or reliefs
+fn reads_board(config: &Config) -> bool {
+    let output = &config.output;
+    let extension = |name: &str| {
+        output
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(name))
+    };
+    config.format != Some(ImageFormat::Png)
+        && (FigureFormat::from_path(output).is_some()
+            || PlotFormat::from_path(output).is_some()
+            || SchematicFormat::from_path(output).is_some()
+            || extension("stl")
+            || extension("dzi"))
+}
+
+/// Board with a pattern per color, in gray unless a palette is given
+fn save_hatched<const S: usize>(
+    map: &impl CellStore<S, S>,
+    colors: usize,
+    palette: Option<Palette>,
+    ants: &[Pos],
+    crop: Option<Crop>,
+    config: &Config,
+) -> Result<(), EncodingError> {
+    let palette = palette.unwrap_or_else(|| Palette::gray(colors));
+    save_hatched_map_to_file(
+        map,
+        colors,
+        &palette,
+        ants,
+        config.hatch_size,
+        crop,
+        &config.output,
+    )
+}
+
+/// RGBA PNG with the command line of the run hidden in its pixels
+fn save_rendered<const S: usize>(
+    map: &impl CellStore<S, S>,
+    colors: usize,
+    ants: &[Pos],
+    crop: Option<Crop>,
+    config: &Config,
+) -> Result<(), EncodingError> {
+    let renderer = PngRenderer {
+        depth: config.bit_depth,
+        palette: config.color_palette(colors),
+        params: config.embed_params.then(|| reproduce_command(config)),
+        scale: config.scale,
+        ..PngRenderer::default()
+    };
+    let frame = Frame::new(map, colors, crop).with_ants(ants);
+    Ok(renderer.save(&frame, &config.output)?)
+}
+
+/// RGB PNG painted from the layers of `--layers`, or of `--render path`
+fn save_layered<const S: usize>(
+    map: &impl CellStore<S, S>,
+    colors: usize,
+    ants: &[Pos],
+    heat: Option<HeatLayer>,
+    path: Option<PathLayer>,
+    crop: Option<Crop>,
+    config: &Config,
+) -> Result<(), EncodingError> {
+    let frame = Frame::new(map, colors, crop).with_ants(ants);
+    Ok(layered(config, colors, heat, path).save(&frame, &config.output)?)
+}
+
+/// Cells, heat, path and ants stacked as `--layers` says, shared by pictures and animations
+fn layered(
+    config: &Config,
+    colors: usize,
+    heat: Option<HeatLayer>,
+    path: Option<PathLayer>,
+) -> LayeredRenderer {
+    let cells = CellLayer {
+        palette: config.color_palette(colors),
+    };
+    let ants = AntLayer {
+        color: config.marker_color,
+    };
+    let mut renderer = LayeredRenderer::new()
+        .with_orientation(config.orientation)
+        .with_layer("cells", cells);
+    if let Some(heat) = heat {
+        renderer = renderer.with_layer("heat", heat);
+    }
+    if let Some(path) = path {
+        renderer = renderer.with_layer("path", path);
+    }
+    renderer = renderer.with_layer("ants", ants);
+    match &config.layers {
+        Some(composition) => renderer.with_composition(composition),
+        None => renderer,
+    }
+}
+
+/// Ants and crop of the board as --flip and --rotate turn it
+fn oriented_marks(ants: &[Pos], crop: Option<Crop>, config: &Config) -> (Vec<Pos>, Option<Crop>) {
+    let (orientation, size) = (config.orientation, config.size);
+    let ants = ants.iter().map(|&ant| orientation.apply(ant, size, size));
+    let crop = crop.map(|crop| {
+        let (a, b) = (
+            orientation.apply(crop.min, size, size),
+            orientation.apply(crop.max, size, size),
+        );
+        Crop {
+            min: Pos::new(a.x.min(b.x), a.y.min(b.y)),
+            max: Pos::new(a.x.max(b.x), a.y.max(b.y)),
+        }
+    });
+    (ants.collect(), crop)
+}
+
+/// Whether `--layers` shows the layer called `name`
+fn shows_layer(config: &Config, name: &str) -> bool {
+    config
+        .layers
+        .as_ref()
+        .is_some_and(|layers| layers.shows(name))
+}
+
+/// Overwrite the single checkpoint file, or add to the series when checkpoints are retained
+fn save_checkpoint<const S: usize, M: CellStore<S, S>>(
+    sim: &Simulation<S, S, M>,
+    path: &Path,
+    config: &Config,
+) -> Result<(), CheckpointError> {
+    let checkpoint = sim.checkpoint();
+    bug_report::digest(sim.steps(), sim.digest());
+    match config.retention.is_set() {
+        true => CheckpointSeries::new(path, config.retention)
+            .save(&checkpoint)
+            .map(|_| ()),
+        false => checkpoint.save(path),
+    }
+}
+
+/// Crash on a mismatch, with a bug report of how the run got there
+fn check_integrity<const S: usize, M: CellStore<S, S>>(sim: &Simulation<S, S, M>) {
+    if let Err(e) = sim.check_integrity() {
+        let error = e.to_string();
+        panic!(
+            "{}",
+            tr!("integrity-failed", step = sim.steps(), error = error)
+        );
+    }
+}
+
+/// Final map, the cells the ants ended on, the part of the map to save, the palette
+/// the timeline switched to and the heat and path of `--layers`
+type Finished<const S: usize, M> = (
+    M,
+    Vec<Pos>,
+    Option<Crop>,
+    Option<Palette>,
+    Option<HeatLayer>,
+    Option<PathLayer>,
+    Option<Heatmap<S, S>>,
+);
+
+/// Walk the configured ants over a fresh map or carry on from a checkpoint, `None` if
+/// the config doesn't hold together, which is told already
+fn simulate<const S: usize, M: CellStore<S, S> + Clone + Sync>(
+    new_map: impl FnOnce() -> M,
+    mask: Option<&DynamicImage>,
+    resume: Option<&Checkpoint>,
+    config: &Config,
+    manifest: &mut Manifest,
+) -> Result<Option<Finished<S, M>>, AntventureError> {
+    // Checkpoints don't hold obstacles, they come from the mask on every run
+    let with_obstacles = |sim: Simulation<S, S, M>| match mask {
+        Some(mask) => {
+            let size = config.size;
+            let cells = config.import_options.black_cells(mask, size, size);
+            // Rows of a map sized at runtime are the width of the board apart
+            let padding = iter::repeat_n(false, S - size);
+            let cells = cells
+                .chunks(size.max(1))
+                .flat_map(|row| row.iter().copied().chain(padding.clone()));
+            sim.with_obstacles(Obstacles::from_cells(cells), config.obstacle_policy)
+        }
+        None => sim,
+    };
+    let mut sim = match resume {
+        Some(checkpoint) => match Simulation::from_checkpoint(checkpoint) {
+            Ok(sim) => with_obstacles(sim.with_boundary(config.boundary)),
+            Err(e) => {
+                eprintln!("{}", tr!("cant-resume", error = e.to_string()));
+                return Ok(None);
+            }
+        },
+        None => {
+            let sim = Simulation::new(new_map(), config.rule.clone())
+                .with_conflict_policy(config.conflict)
+                .with_boundary(config.boundary);
+            let sim = match &config.stochastic_rule {
+                Some(rule) => {
+                    let seed = config.seed.expect("picked before the run");
+                    sim.with_stochastic_rule(rule.clone(), seed)
+                }
+                None => sim,
+            };
+            let mut sim = with_obstacles(sim);
+            for (pos, dir) in config.ants() {
+                match config.symmetry {
+                    Some(symmetry) => sim.spawn_symmetric(pos, dir, symmetry)?,
+                    None => sim.spawn(pos, dir)?,
+                };
+            }
+            sim
+        }
+    };
+
+    if let Some(rule) = &config.automaton {
+        sim = sim.with_automaton(rule.clone(), config.automaton_every.max(1));
+    }
+    if config.render == Render::Heatmap || shows_layer(config, "heat") {
+        sim = sim.with_heatmap();
+    }
+
+    // Every output of frames gets the same ones, but an animation painted from
+    // `--layers` is captured from the simulation, as a frame doesn't hold the heat
+    let mut sinks = Vec::<(Box<dyn OutputSink>, Output)>::new();
+    let mut layered_animation = None;
+    if let Some(path) = &config.animate {
+        let Some(format) = AnimationFormat::from_path(path) else {
+            let file = path.display().to_string();
+            eprintln!("{}", tr!("cant-animate", file = file));
+            return Ok(None);
+        };
+        let animation = Animation::create(path, format, config.size, config.size, config.colors())?;
+        match config.layers {
+            Some(_) => layered_animation = Some((animation, path)),
+            None => sinks.push((Box::new(animation), Output::Animation(path))),
+        }
+    }
+    if let Some(format) = config.raw_frames {
+        let colors = config.colors();
+        let palette = config.color_palette(colors);
+        if palette.as_ref().is_some_and(|p| p.colors.len() < colors) {
+            eprintln!("{}", tr!("palette-too-short", colors = colors));
+            return Ok(None);
+        }
+        let pix_fmt = format.pix_fmt().to_owned();
+        eprintln!(
+            "{}",
+            tr!("raw-frames", size = config.size, pix_fmt = pix_fmt)
+        );
+        let video = RawVideo::new(io::stdout().lock(), format, colors, palette.as_ref());
+        sinks.push((Box::new(video), Output::RawFrames));
+    }
+
+    // Watches the first ant only
+    let watch_phases = config.detect_period
+        || config.phase_snapshots.is_some()
+        || config.stop_after_highway.is_some()
+        || config.stop_at_coverage.is_some();
+    let mut detector = watch_phases.then(|| {
+        let detector = PhaseDetector::new(config.size, config.size, config.period_window);
+        match config.stop_at_coverage {
+            Some(fraction) => detector.with_coverage(fraction),
+            None => detector,
+        }
+    });
+
+    let stats = match &config.stats {
+        Some(path) => match StatsFormat::from_path(path) {
+            Some(format) => {
+                let mut log = StatsLog::new(config.stats_every);
+                log.sample(&sim);
+                let log = Filtered::new(log, config.stats_filter.clone()).at_step(sim.steps());
+                sim = sim.with_observer(log);
+                Some((format, path))
+            }
+            None => {
+                let file = path.display().to_string();
+                eprintln!("{}", tr!("cant-save-stats", file = file));
+                return Ok(None);
+            }
+        },
+        None => None,
+    };
+    let trajectory = match &config.trajectory {
+        Some(path) => {
+            let file = path.display().to_string();
+            let Some(format) = TrajectoryFormat::from_path(path) else {
+                eprintln!("{}", tr!("cant-save-trajectory", file = file));
+                return Ok(None);
+            };
+            let mut log = TrajectoryLog::create(path, format, config.trajectory_every)?;
+            log.record(&sim);
+            let log = Filtered::new(log, config.trajectory_filter.clone()).at_step(sim.steps());
+            sim = sim.with_observer(log);
+            Some(path)
+        }
+        None => None,
+    };
+
+    let sound = match &config.sonify {
+        Some(path) => {
+            sim = sim.with_observer(SoundTrack::create(path, config.sound.clone())?);
+            Some(path)
+        }
+        None => None,
+    };
+    let replay = match &config.record {
+        Some(path) => {
+            let recorder = ReplayRecorder::create(path, &sim)?;
+            sim = sim.with_observer(recorder);
+            Some(path)
+        }
+        None => None,
+    };
+
+    if let Some(tail) = bug_report::tail() {
+        tail.record(&sim);
+        sim = sim.with_observer(tail);
+    }
+    // A clone stays here to read the path from, the bug report keeps a tail of its own
+    let path = (config.render == Render::Path || shows_layer(config, "path")).then(|| {
+        let tail = TrajectoryTail::new(config.path_length, config.trajectory_every);
+        tail.record(&sim);
+        tail
+    });
+    if let Some(tail) = &path {
+        sim = sim.with_observer(tail.clone());
+    }
+
+    let started = Instant::now();
+    let mut samples = config.report.as_ref().map(|_| {
+        // About 256 samples, and one a step for runs shorter than that
+        let every = config
+            .steps
+            .map_or(1000, |steps| (steps as u64 / 256).max(1));
+        Samples::new(every, started)
+    });
+
+    // Cues due before a resumed run started were played already
+    let mut timeline = config.timeline();
+    timeline.skip_to(sim.steps());
+    let mut palette = None;
+
+    // Run in chunks up to the next animation frame, checkpoint, report sample or cue
+    let mut budget = config.steps.map_or(u64::MAX, |steps| steps as u64);
+    let checkpoint_every = config.checkpoint_every.max(1) as u64;
+    let mut next_frame = sim.steps();
+    let mut next_checkpoint = sim.steps() + checkpoint_every;
+    let mut next_check = config
+        .paranoid
+        .map_or(u64::MAX, |every| sim.steps() + every.max(1));
+    let mut snapshots = config.snapshot_every.map(|every| {
+        (
+            SnapshotWriter::new(&config.snapshot_path, config.colors()),
+            every.max(1),
+        )
+    });
+    let mut snapshot_paths = Vec::new();
+    let mut next_snapshot = snapshots
+        .as_ref()
+        .map_or(u64::MAX, |(_, every)| sim.steps().next_multiple_of(*every));
+    let mut on_map = sim.ants().iter().any(|ant| ant.is_on_map());
+    let deadline = config.max_seconds.map(|seconds| started + seconds);
+    let mut heartbeat = Heartbeat::new(config, sim.steps());
+    // Paused, paced and stopped by the server, `--pace` is where the speed starts
+    let control = ControlHandle::new();
+    control.set_speed(config.pace);
+    let mut pacer = None;
+    let mut ending = None;
+    #[cfg(feature = "serve")]
+    let mut server = match config.serve {
+        Some(addr) => {
+            let server = serve::Server::bind(addr, control.clone())?;
+            say!("{}", tr!("serving", url = format!("http://{addr}/")));
+            Some(server)
+        }
+        None => None,
+    };
+    let mut budget_ending = Ending::Steps;
+
+    while on_map && sim.steps() < budget {
+        bug_report::progress(sim.steps());
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            ending = Some(Ending::Time);
+            break;
+        }
+        if let Some(heartbeat) = &mut heartbeat {
+            heartbeat.beat(sim.steps());
+        }
+        let framed = !sinks.is_empty() || layered_animation.is_some();
+        if framed && sim.steps() >= next_frame {
+            if let Some((animation, _)) = &mut layered_animation {
+                capture(animation, &sim, config)?;
+            }
+            feed(&mut sinks, &sim, config)?;
+            next_frame += config.frame_every.after(next_frame);
+        }
+        if let Some(path) = &config.checkpoint {
+            if sim.steps() >= next_checkpoint {
+                save_checkpoint(&sim, path, config)?;
+                next_checkpoint += checkpoint_every;
+            }
+        }
+        if let Some(every) = config.paranoid {
+            if sim.steps() >= next_check {
+                check_integrity(&sim);
+                next_check += every.max(1);
+            }
+        }
+        if let Some((writer, every)) = &mut snapshots {
+            if sim.steps() >= next_snapshot {
+                let map = Oriented::new(sim.map(), config.orientation);
+                let path = writer.save(&map, sim.steps())?;
+                snapshot_paths.push(path);
+                next_snapshot += *every;
+            }
+        }
+        if let Some(samples) = &mut samples {
+            if sim.steps() >= samples.next_at() {
+                samples.take(&sim);
+            }
+        }
+        let played = match timeline.play(&mut sim) {
+            Ok(played) => played,
+            Err(e) => {
+                eprintln!("{}", tr!("cant-play-cue", error = e.to_string()));
+                return Ok(None);
+            }
+        };
+        for (step, cue) in played {
+            report_cue(*step, cue);
+            if let Cue::Palette(colors) = cue {
+                palette = Some(colors.clone());
+            }
+        }
+
+        let mut target = budget;
+        if !sinks.is_empty() || layered_animation.is_some() {
+            target = target.min(next_frame);
+        }
+        if config.checkpoint.is_some() {
+            target = target.min(next_checkpoint);
+        }
+        target = target.min(next_snapshot).min(next_check);
+        if let Some(samples) = &samples {
+            target = target.min(samples.next_at());
+        }
+        if let Some(step) = timeline.next_step() {
+            target = target.min(step);
+        }
+        if deadline.is_some() || heartbeat.is_some() {
+            target = target.min(sim.steps() + CLOCK_EVERY);
+        }
+        #[cfg(feature = "serve")]
+        if let Some(server) = &mut server {
+            target = target.min(server.answer(&sim, config));
+        }
+        if control.is_cancelled() {
+            ending = Some(Ending::Stopped);
+            break;
+        }
+        if let Some(paced) = Pacer::keep_to(&mut pacer, control.speed(), sim.steps()) {
+            target = target.min(sim.steps() + paced);
+        }
+        let steps = target - sim.steps();
+        match &mut detector {
+            Some(detector) => {
+                if let Some(event) = sim.run_watching(steps, detector) {
+                    report_phase(&sim, event, config, manifest)?;
+                    match event {
+                        PhaseEvent::Highway(_) | PhaseEvent::Cycle(_) if config.detect_period => {
+                            ending = Some(Ending::Period);
+                            break;
+                        }
+                        PhaseEvent::Highway(_) => {
+                            if let Some(steps) = config.stop_after_highway {
+                                let after_highway = sim.steps() + steps as u64;
+                                if after_highway < budget {
+                                    budget = after_highway;
+                                    budget_ending = Ending::Period;
+                                }
+                            }
+                        }
+                        PhaseEvent::Coverage { .. } => {
+                            ending = Some(Ending::Coverage);
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                on_map = sim.ants().iter().any(|ant| ant.is_on_map());
+            }
+            None => on_map = sim.run_parallel(steps, config.threads),
+        }
+    }
+    bug_report::progress(sim.steps());
+    let ending = ending.unwrap_or(match on_map {
+        true => budget_ending,
+        false => Ending::Edge,
+    });
+    report_ending(ending, sim.steps(), started);
+    if config.paranoid.is_some() {
+        check_integrity(&sim);
+    }
+    let config = &with_palette(config, palette.clone());
+
+    feed(&mut sinks, &sim, config)?;
+    let summary = RunSummary::of(&sim);
+    for (mut sink, output) in sinks {
+        sink.accept_summary(&summary)?;
+        match output {
+            Output::RawFrames => {
+                sink.finalize().ok();
+                eprintln!("{}", tr!("raw-frames-written", count = sink.frames()));
+            }
+            Output::Animation(path) => {
+                say!("{}", tr!("animation-frames", count = sink.frames()));
+                sink.finalize()?;
+                record(manifest, ArtifactKind::Animation, path, config)?;
+            }
+        }
+    }
+    if let Some((mut animation, path)) = layered_animation {
+        capture(&mut animation, &sim, config)?;
+        say!("{}", tr!("animation-frames", count = animation.frames()));
+        animation.finish()?;
+        record(manifest, ArtifactKind::Animation, path, config)?;
+    }
+    if let Some((mut writer, _)) = snapshots {
+        writer.finish()?;
+        say!("{}", tr!("snapshots-saved", count = writer.saved()));
+        for path in snapshot_paths {
+            record(manifest, ArtifactKind::Snapshot, path, config)?;
+        }
+    }
+    if let Some((format, path)) = stats {
+        sim.finish();
+        let log = sim
+            .observer::<Filtered<StatsLog>>()
+            .expect("added with the stats")
+            .inner();
+        log.save(format, path)?;
+        let file = path.display().to_string();
+        say!(
+            "{}",
+            tr!("stats-saved", count = log.samples.len(), file = file)
+        );
+        record(manifest, ArtifactKind::Stats, path, config)?;
+    }
+    if let Some(path) = trajectory {
+        sim.finish();
+        let log = sim
+            .observer::<Filtered<TrajectoryLog>>()
+            .expect("added with the trajectory")
+            .inner();
+        if let Some(error) = log.error() {
+            return Err(io::Error::new(error.kind(), error.to_string()).into());
+        }
+        let file = path.display().to_string();
+        say!(
+            "{}",
+            tr!("trajectory-saved", count = log.points(), file = file)
+        );
+        record(manifest, ArtifactKind::Trajectory, path, config)?;
+    }
+    if let Some(path) = sound {
+        sim.finish();
+        let track = sim.observer::<SoundTrack>().expect("added with --sonify");
+        if let Some(error) = track.error() {
+            return Err(io::Error::new(error.kind(), error.to_string()).into());
+        }
+        let (file, seconds) = (
+            path.display().to_string(),
+            format!("{:.1}", track.seconds()),
+        );
+        say!("{}", tr!("sound-saved", seconds = seconds, file = file));
+        record(manifest, ArtifactKind::Sound, path, config)?;
+    }
+    if let Some(path) = replay {
+        sim.finish();
+        let recorder = sim
+            .observer::<ReplayRecorder>()
+            .expect("added with --record");
+        if let Some(error) = recorder.error() {
+            return Err(io::Error::new(error.kind(), error.to_string()).into());
+        }
+        let file = path.display().to_string();
+        say!(
+            "{}",
+            tr!("replay-saved", steps = recorder.steps(), file = file)
+        );
+        record(manifest, ArtifactKind::Replay, path, config)?;
+    }
+    if let Some(path) = &config.checkpoint {
+        save_checkpoint(&sim, path, config)?;
+        say!("{}", tr!("checkpoint-saved", steps = sim.steps()));
+        // Only the checkpoints the retention policy left on disk
+        match config.retention.is_set() {
+            true => {
+                let series = CheckpointSeries::new(path, config.retention);
+                for (_, path, _) in series.list()? {
+                    record(manifest, ArtifactKind::Checkpoint, path, config)?;
+                }
+            }
+            false => record(manifest, ArtifactKind::Checkpoint, path, config)?,
+        }
+    }
+
+    for (i, ant) in sim.ants().iter().enumerate() {
+        let name = match sim.ants().len() {
+            1 => tr!("ant"),
+            _ => tr!("ant-numbered", index = i),
+        };
+        let pos = config.position(ant.pos().into());
+        let (pos, dir) = (
+            format!("({}, {})", pos.x, pos.y),
+            i18n::direction(ant.dir()),
+        );
+        match ant.is_on_map() {
+            true => say!("{}", tr!("ant-stopped", name = name, pos = pos, dir = dir)),
+            false => say!("{}", tr!("ant-left", name = name, pos = pos, dir = dir)),
+        }
+    }
+
+    if config.bug_report.is_some() {
+        bug_report::digest(sim.steps(), sim.digest());
+        save_bug_report(manifest, config)?;
+    }
+
+    if let Some(path) = &config.describe {
+        fs::write(path, describe(&sim, config))?;
+        let file = path.display().to_string();
+        say!("{}", tr!("description-saved", file = file));
+        record(manifest, ArtifactKind::Description, path, config)?;
+    }
+
+    // Positions only matter for the ant markers
+    let ants = match config.mark_ants || shows_layer(config, "ants") {
+        true => sim.ants().iter().map(|ant| ant.pos().into()).collect(),
+        false => Vec::new(),
+    };
+    if let (Some(path), Some(mut samples)) = (&config.report, samples) {
+        samples.take(&sim);
+        samples.report(&sim, &ants, config, started)?.save(path)?;
+        let file = path.display().to_string();
+        say!("{}", tr!("report-saved", file = file));
+        record(manifest, ArtifactKind::Report, path, config)?;
+    }
+    let crop = config
+        .crop
+        .zip(sim.touched())
+        .map(|(padding, touched)| Crop::around(touched, padding, sim.map().size()));
+    let heat = sim
+        .heatmap()
+        .map(|heatmap| HeatLayer::new(heatmap, config.heat_colors));
+    let path = path.map(|tail| PathLayer::new(&tail.points(), PATH_COLOR));
+    let heatmap = sim
+        .heatmap()
+        .filter(|_| config.render == Render::Heatmap)
+        .cloned();
+    Ok(Some((
+        sim.into_map(),
+        ants,
+        crop,
+        palette,
+        heat,
+        path,
+        heatmap,
+    )))
+}
+
+/// What an output of frames is, for what's told once it's finished
+enum Output<'a> {
+    /// `--animate`, recorded in the manifest
+    Animation(&'a PathBuf),
+    /// `--raw-frames` on stdout
+    RawFrames,
+}
+
+/// Why a run stopped, told before its outputs are saved
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Ending {
+    /// Every ant left the map
+    Edge,
+    /// The walk settled into a highway or a cycle
+    Period,
+    /// The visited area grew as big as `--stop-at-coverage` asked
+    Coverage,
+    /// `--max-steps` ran out
+    Steps,
+    /// `--max-seconds` ran out
+    Time,
+    /// Stopped from outside, by `POST /stop`
+    Stopped,
+}
+
+/// Lines telling a `--headless` run is still walking and how fast, for the logs of
+/// whatever runs it
+struct Heartbeat {
+    /// Step and time of the last line, or of the start
+    last: (u64, Instant),
+}
+
+impl Heartbeat {
+    fn new(config: &Config, step: u64) -> Option<Self> {
+        config.headless.then(|| Self {
+            last: (step, Instant::now()),
+        })
+    }
+
+    /// Tell the run is at `step` if it's been a while since the last line
+    fn beat(&mut self, step: u64) {
+        let (last_step, last_time) = self.last;
+        let elapsed = last_time.elapsed();
+        if elapsed < HEARTBEAT {
+            return;
+        }
+        let speed = format!("{:.0}", (step - last_step) as f64 / elapsed.as_secs_f64());
+        say!("{}", tr!("heartbeat", step = step, speed = speed));
+        self.last = (step, Instant::now());
+    }
+}
+
+fn report_ending(ending: Ending, step: u64, started: Instant) {
+    let message = match ending {
+        Ending::Edge => tr!("run-ended-edge", step = step),
+        Ending::Period => tr!("run-ended-period", step = step),
+        Ending::Coverage => tr!("run-ended-coverage", step = step),
+        Ending::Steps => tr!("run-ended-steps", step = step),
+        Ending::Stopped => tr!("run-ended-stopped", step = step),
+        Ending::Time => {
+            let seconds = format!("{:.1}", started.elapsed().as_secs_f64());
+            tr!("run-ended-time", step = step, seconds = seconds)
+        }
+    };
+    say!("{message}");
+}
+
+/// Add the simulation as it is to the animation, painted from `--layers`, the path only
+/// goes into the final picture
+fn capture<const S: usize, M: CellStore<S, S> + Clone>(
+    animation: &mut Animation,
+    sim: &Simulation<S, S, M>,
+    config: &Config,
+) -> io::Result<()> {
+    let colors = config.colors();
+    let heat = sim
+        .heatmap()
+        .map(|heatmap| HeatLayer::new(heatmap, config.heat_colors));
+    let ants = sim
+        .ants()
+        .iter()
+        .map(|ant| ant.pos().into())
+        .collect::<Vec<_>>();
+    let frame = Frame::new(sim.map(), colors, None).with_ants(&ants);
+    animation.capture_rgba(layered(config, colors, heat, None).paint(&frame))
+}
+
+/// Plain text account of the final board, naming colors the way the image shows them
+fn describe<const S: usize, M: CellStore<S, S>>(
+    sim: &Simulation<S, S, M>,
+    config: &Config,
+) -> String {
+    let colors = sim.rule().colors();
+    let mut description = Description::new()
+        .with_rule(sim.rule())
+        .with_steps(sim.steps());
+    if let Some(palette) = config.color_palette(colors) {
+        description = description.with_palette(palette);
+    }
+    if config.hatch {
+        description = description.with_hatching();
+    }
+    for ant in sim.ants() {
+        let pos = config.position(ant.pos().into());
+        description = description.with_ant(pos, ant.dir(), ant.is_on_map());
+    }
+    description.describe(sim.map(), colors)
+}
+
+/// Charts of an HTML report, sampled while the simulation runs
+struct Samples {
+    colored: Series,
+    speed: Series,
+    last: (u64, Instant),
+}
+
+impl Samples {
+    fn new(every: u64, started: Instant) -> Self {
+        Self {
+            colored: Series::new("Colored cells", every),
+            speed: Series::new("Steps per second", every),
+            last: (0, started),
+        }
+    }
+
+    fn next_at(&self) -> u64 {
+        self.colored.next_at()
+    }
+
+    fn take<const S: usize, M: CellStore<S, S>>(&mut self, sim: &Simulation<S, S, M>) {
+        let (steps, now) = (sim.steps(), Instant::now());
+        if self
+            .colored
+            .points
+            .last()
+            .is_some_and(|&(at, _)| at == steps)
+        {
+            return;
+        }
+        self.colored.push(steps, sim.map().count_colored() as f64);
+
+        let (last_steps, last_time) = self.last;
+        if steps > last_steps {
+            let seconds = (now - last_time).as_secs_f64().max(f64::EPSILON);
+            self.speed
+                .push(steps, ((steps - last_steps) as f64 / seconds).round());
+        }
+        self.last = (steps, now);
+    }
+
+    fn report<const S: usize, M: CellStore<S, S> + Clone>(
+        self,
+        sim: &Simulation<S, S, M>,
+        ants: &[Pos],
+        config: &Config,
+        started: Instant,
+    ) -> Result<Report, EncodingError> {
+        let rule = sim.rule();
+        let colors = rule.colors();
+        let palette = config
+            .color_palette(colors)
+            .unwrap_or_else(|| Palette::gray(colors));
+        let elapsed = started.elapsed().as_secs_f64();
+        let on_map = sim.ants().iter().filter(|ant| ant.is_on_map()).count();
+
+        // Bigger boards would make a page of megabytes, a thumbnail of them does
+        let size = config.size;
+        let (shown, (ants, _)) = (
+            Oriented::new(sim.map(), config.orientation),
+            oriented_marks(ants, None, config),
+        );
+        let report = match size > REPORT_IMAGE_SIZE {
+            true => Report::new(format!("{rule} on a {size}x{size} map"))
+                .with_thumbnail(&DensityGrid::new(&shown, colors, REPORT_IMAGE_SIZE)),
+            false => Report::new(format!("{rule} on a {size}x{size} map"))
+                .with_map(&shown, colors, &palette, &ants),
+        };
+        let mut report = report?
+            .with_stat("Rule", rule)
+            .with_stat("Map", format!("{size}x{size}"))
+            .with_stat("Steps", sim.steps())
+            .with_stat("Colored cells", sim.map().count_colored())
+            .with_stat(
+                "Ants on the map",
+                format!("{on_map} of {}", sim.ants().len()),
+            )
+            .with_stat("Time", format!("{elapsed:.2} s"))
+            .with_stat(
+                "Steps per second",
+                format!("{:.0}", sim.steps() as f64 / elapsed.max(f64::EPSILON)),
+            );
+        if let (Some(_), Some(seed)) = (config.random_fill, config.seed) {
+            report = report.with_stat("Random fill seed", seed);
+        }
+        if !sim.rule_changes().is_empty() {
+            let changes = sim
+                .rule_changes()
+                .iter()
+                .map(|(step, rule)| format!("{rule} at step {step}"))
+                .collect::<Vec<_>>();
+            report = report.with_stat("Rule changes", changes.join(", "));
+        }
+        Ok(report
+            .with_series(self.colored)
+            .with_series(self.speed)
+            .with_command(reproduce_command(config)))
+    }
+}
+
+/// The command line this run was started with, along with the settings taken
+/// from the environment and a seed that was picked at random
+fn reproduce_command(config: &Config) -> String {
+    let quote = |arg: String| match arg
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_./:=,+@%".contains(c))
+    {
+        true => arg,
+        false => format!("'{}'", arg.replace('\'', "'\\''")),
+    };
+
+    let mut command = std::env::vars()
+        .filter(|(key, _)| key.starts_with(Config::PREFIX))
+        .map(|(key, value)| format!("{key}={}", quote(value)))
+        .collect::<Vec<_>>();
+    command.sort();
+    command.extend(std::env::args().map(quote));
+
+    let seed_given = command
+        .iter()
+        .any(|arg| arg.starts_with("--seed") || arg.starts_with("ANTVENTURE_SEED="));
+    let random = config.random_fill.is_some() || config.stochastic_rule.is_some();
+    if let (true, Some(seed), false) = (random, config.seed, seed_given) {
+        command.push(format!("--seed {seed}"));
+    }
+    command.join(" ")
+}
@@ -0,0 +1,155 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use antventure::{
+    rules_up_to, save_palette_map_to_file, AntventureError, ImportOptions, Map, Palette, Rule,
+    SweepExecutor, TargetSearch,
+};
+use image::DynamicImage;
+
+use crate::i18n::{self, tr};
+
+#[derive(clap::Args)]
+pub struct OptimizeArgs {
+    /// Image to imitate, dark pixels are the cells to color; scaled to fit the map
+    target: PathBuf,
+
+    /// Try every rule of L and R up to this many letters
+    #[arg(long, default_value_t = 4)]
+    max_len: usize,
+
+    /// Try these rules instead, comma separated
+    #[arg(long, value_delimiter = ',', conflicts_with = "max_len")]
+    rules: Vec<Rule>,
+
+    /// Map width and height in cells: 64, 128, 256 or 512
+    #[arg(long, default_value_t = 128)]
+    size: usize,
+
+    /// Steps to walk each ant for, unless it leaves the map first
+    #[arg(long, default_value_t = 20_000)]
+    steps: u64,
+
+    /// Cells between the start cells tried first, smaller is slower but finds more
+    #[arg(long, default_value_t = 8)]
+    stride: usize,
+
+    /// Best rules to print
+    #[arg(long, default_value_t = 5)]
+    top: usize,
+
+    /// Worker threads, one per physical core by default
+    #[arg(long)]
+    workers: Option<usize>,
+
+    /// Where to save the board of the best match
+    #[arg(long, short, default_value = "best.png")]
+    output: PathBuf,
+}
+
+/// Look for the rule and start of an ant drawing something like an image
+pub fn run(args: OptimizeArgs) -> Result<ExitCode, AntventureError> {
+    let rules = match args.rules.is_empty() {
+        true => rules_up_to(args.max_len).collect(),
+        false => args.rules.clone(),
+    };
+    if rules.is_empty() {
+        eprintln!("{}", tr!("search-no-rules"));
+        return Ok(ExitCode::FAILURE);
+    }
+    let image = match image::open(&args.target) {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!("{}", tr!("cant-import", error = e.to_string()));
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    match args.size {
+        64 => optimize::<64>(&args, &image, rules)?,
+        128 => optimize::<128>(&args, &image, rules)?,
+        256 => optimize::<256>(&args, &image, rules)?,
+        512 => optimize::<512>(&args, &image, rules)?,
+        size => {
+            eprintln!(
+                "{}",
+                tr!(
+                    "unsupported-size",
+                    size = size,
+                    sizes = "[64, 128, 256, 512]"
+                )
+            );
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn optimize<const S: usize>(
+    args: &OptimizeArgs,
+    image: &DynamicImage,
+    rules: Vec<Rule>,
+) -> Result<(), AntventureError> {
+    let options = ImportOptions {
+        fit: true,
+        ..ImportOptions::default()
+    };
+    let target = Map::<S, S>::import(image, &options);
+
+    let mut executor = SweepExecutor::new();
+    if let Some(workers) = args.workers {
+        executor = executor.workers(workers);
+    }
+    let search = TargetSearch::<S>::new(args.steps)
+        .with_stride(args.stride)
+        .with_executor(executor);
+
+    let starts = S.div_ceil(args.stride.max(1)).pow(2);
+    println!(
+        "{}",
+        tr!(
+            "optimize-trying",
+            count = rules.len(),
+            starts = starts,
+            size = S
+        )
+    );
+    let (found, _) = search.run(&target, rules);
+
+    println!("{}", tr!("optimize-header"));
+    for (rank, candidate) in found.iter().enumerate().take(args.top) {
+        println!(
+            "{:>4}  {:<16} {:>9.1}%  ({}, {}) {}",
+            rank + 1,
+            candidate.rule.to_string(),
+            candidate.similarity * 100.0,
+            candidate.start.x,
+            candidate.start.y,
+            i18n::direction(candidate.dir).to_lowercase()
+        );
+    }
+
+    let best = &found[0];
+    let map = search.walk(&best.rule, best.start, best.dir);
+    let colors = best.rule.colors();
+    save_palette_map_to_file(
+        &map,
+        colors,
+        &Palette::gray(colors),
+        &[],
+        None,
+        &args.output,
+    )?;
+    println!(
+        "{}",
+        tr!(
+            "optimize-best",
+            score = format!("{:.1}", best.similarity * 100.0),
+            rule = best.rule.to_string(),
+            x = best.start.x,
+            y = best.start.y,
+            dir = i18n::direction(best.dir).to_lowercase(),
+            file = args.output.display().to_string()
+        )
+    );
+    Ok(())
+}
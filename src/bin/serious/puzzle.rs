@@ -0,0 +1,166 @@
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use antventure::{
+    hamming_distance, save_color_map_to_file, similarity, AntventureError, ColorMap, Direction,
+    Pos, Rule, Simulation,
+};
+
+use crate::i18n::{self, tr};
+
+/// Side of the puzzle boards, small enough to look at cell by cell
+const SIZE: usize = 128;
+
+/// Secret rule, starting direction and steps of every puzzle, easiest first
+const LEVELS: &[(&str, Direction, u64)] = &[
+    ("LR", Direction::North, 11000),
+    ("RL", Direction::East, 11000),
+    ("LLRR", Direction::North, 20000),
+    ("RRL", Direction::West, 4000),
+    ("RLLR", Direction::South, 15000),
+    ("LRRRRRLLR", Direction::North, 30000),
+];
+
+#[derive(clap::Args)]
+pub struct PuzzleArgs {
+    /// Puzzle to play, from 1
+    #[arg(long, default_value_t = 1)]
+    level: usize,
+
+    /// Directory for the target board and your attempts
+    #[arg(long, default_value = "antventure-puzzle")]
+    dir: PathBuf,
+}
+
+/// Show a board and score guesses of the rule and direction that painted it by how
+/// alike the boards are, until one matches exactly
+pub fn run(args: PuzzleArgs) -> Result<ExitCode, AntventureError> {
+    let Some(&(rule, dir, steps)) = args.level.checked_sub(1).and_then(|i| LEVELS.get(i)) else {
+        eprintln!("{}", tr!("puzzle-unknown-level", levels = LEVELS.len()));
+        return Ok(ExitCode::FAILURE);
+    };
+    let rule = rule.parse::<Rule>().expect("puzzle rules are valid");
+    if let Err(e) = fs::create_dir_all(&args.dir) {
+        let dir = args.dir.display().to_string();
+        eprintln!("{}", tr!("cant-create", dir = dir, error = e.to_string()));
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let target_file = args.dir.join("target.png");
+    let target = walk(&rule, dir, steps);
+    save(&target, &rule, &target_file)?;
+    println!(
+        "{}",
+        tr!(
+            "puzzle-level",
+            level = args.level,
+            levels = LEVELS.len(),
+            file = target_file.display().to_string()
+        )
+    );
+    println!("{}", tr!("puzzle-rules", size = SIZE, steps = steps));
+
+    let attempt_file = args.dir.join("attempt.png");
+    let mut attempts = 0;
+    let mut best = 0.0f64;
+    loop {
+        print!("> ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.eq_ignore_ascii_case("q") {
+            break;
+        }
+        let (guess, guess_dir) = match parse_attempt(line) {
+            Ok(attempt) => attempt,
+            Err(e) => {
+                println!("{e}");
+                continue;
+            }
+        };
+
+        attempts += 1;
+        let board = walk(&guess, guess_dir, steps);
+        if hamming_distance(&board, &target) == 0 {
+            match args.level < LEVELS.len() {
+                true => println!(
+                    "{}",
+                    tr!("puzzle-solved", attempts = attempts, next = args.level + 1)
+                ),
+                false => println!("{}", tr!("puzzle-solved-last", attempts = attempts)),
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        let score = similarity(&board, &target);
+        best = best.max(score);
+        save(&board, &guess, &attempt_file)?;
+        println!(
+            "{}",
+            tr!(
+                "puzzle-score",
+                score = format!("{:.1}", score * 100.0),
+                best = format!("{:.1}", best * 100.0),
+                file = attempt_file.display().to_string()
+            )
+        );
+    }
+
+    println!(
+        "{}",
+        tr!(
+            "puzzle-gave-up",
+            rule = rule.to_string(),
+            dir = i18n::direction(dir).to_lowercase()
+        )
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+/// A rule with an optional direction after it, north if there is none
+fn parse_attempt(line: &str) -> Result<(Rule, Direction), String> {
+    let mut words = line.split_whitespace();
+    let rule = words
+        .next()
+        .unwrap_or_default()
+        .parse::<Rule>()
+        .map_err(|e| e.to_string())?;
+    let dir = match words.next() {
+        Some(dir) => dir.parse::<Direction>().map_err(|e| e.to_string())?,
+        None => Direction::North,
+    };
+    match words.next() {
+        Some(extra) => Err(tr!("puzzle-extra", word = extra.to_owned())),
+        None => Ok((rule, dir)),
+    }
+}
+
+/// Board of an ant walking `rule` from the middle
+fn walk(rule: &Rule, dir: Direction, steps: u64) -> ColorMap<SIZE, SIZE> {
+    let mut sim = Simulation::new(ColorMap::<SIZE, SIZE>::new_white(), rule.clone());
+    let center = Pos::new(SIZE as isize / 2, SIZE as isize / 2);
+    sim.spawn(center, dir).expect("the center is on the map");
+    sim.run_at_most(steps);
+    sim.into_map()
+}
+
+fn save(board: &ColorMap<SIZE, SIZE>, rule: &Rule, file: &Path) -> Result<(), AntventureError> {
+    Ok(save_color_map_to_file(
+        board,
+        rule.colors(),
+        None,
+        None,
+        file,
+    )?)
+}
@@ -0,0 +1,310 @@
+use std::{
+    io::{self, BufRead, Write},
+    process::ExitCode,
+};
+
+use antventure::{
+    save_color_map_to_file, CellStore, ColorMap, Direction, ImageOrientation, MapPos, Oriented,
+    ParseDirectionError, Pos, Rule, Simulation,
+};
+
+use crate::{
+    config::{parse_flip, parse_rotation, parse_size, with_size},
+    i18n::{self, tr},
+};
+
+#[derive(clap::Args)]
+pub struct ReplArgs {
+    /// Map width and height in cells
+    #[arg(long, value_parser = parse_size, default_value_t = 256)]
+    size: usize,
+
+    /// Turn for each cell color, e.g. RL, LLRR or RLU
+    #[arg(long, short, default_value_t = Rule::default())]
+    rule: Rule,
+
+    /// Direction of the ant in the middle: north, east, south or west
+    #[arg(long, short, default_value = "north")]
+    direction: Direction,
+
+    /// Mirror the boards save writes: x (left to right), y (top to bottom) or xy
+    #[arg(long, value_parser = parse_flip)]
+    flip: Option<(bool, bool)>,
+
+    /// Turn the boards save writes clockwise by 90, 180 or 270 degrees, after --flip
+    #[arg(long, value_name = "DEGREES", value_parser = parse_rotation)]
+    rotate: Option<u8>,
+}
+
+/// A command typed at the prompt
+#[derive(Debug, PartialEq)]
+enum Line {
+    Step(u64),
+    Back(u64),
+    Get(Pos),
+    Set(Pos, String),
+    Spawn(Pos, Direction),
+    Ants,
+    Save(String),
+    Stats,
+    Reset,
+    Help,
+    Quit,
+}
+
+impl Line {
+    fn parse(line: &str) -> Result<Option<Self>, String> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let Some((&command, args)) = words.split_first() else {
+            return Ok(None);
+        };
+        let number = |word: &str| {
+            word.parse::<i64>()
+                .map_err(|_| tr!("repl-not-a-number", word = word.to_owned()))
+        };
+        let count = |args: &[&str]| match args {
+            [] => Ok(1),
+            [n] => n
+                .parse::<u64>()
+                .map_err(|_| tr!("repl-not-a-number", word = n.to_string())),
+            _ => Err(tr!("repl-usage", usage = format!("{command} [count]"))),
+        };
+        let pos = |x, y| Ok::<_, String>(Pos::new(number(x)? as _, number(y)? as _));
+        let line = match (command.to_ascii_lowercase().as_str(), args) {
+            ("step" | "s", args) => Line::Step(count(args)?),
+            ("back" | "b", args) => Line::Back(count(args)?),
+            ("get", [x, y]) => Line::Get(pos(x, y)?),
+            ("set", [x, y, color]) => Line::Set(pos(x, y)?, color.to_string()),
+            ("spawn", [x, y, dir @ ..]) if dir.len() < 2 => {
+                let dir = match dir {
+                    [dir] => dir
+                        .parse()
+                        .map_err(|e: ParseDirectionError| e.to_string())?,
+                    _ => Direction::North,
+                };
+                Line::Spawn(pos(x, y)?, dir)
+            }
+            ("ants", []) => Line::Ants,
+            ("save", [file]) => Line::Save(file.to_string()),
+            ("stats", []) => Line::Stats,
+            ("reset", []) => Line::Reset,
+            ("help" | "?", []) => Line::Help,
+            ("quit" | "exit" | "q", []) => Line::Quit,
+            ("get", _) => return Err(tr!("repl-usage", usage = "get x y")),
+            ("set", _) => return Err(tr!("repl-usage", usage = "set x y color")),
+            ("spawn", _) => return Err(tr!("repl-usage", usage = "spawn x y [direction]")),
+            ("save", _) => return Err(tr!("repl-usage", usage = "save file.png")),
+            _ => return Err(tr!("repl-unknown", command = command.to_owned())),
+        };
+        Ok(Some(line))
+    }
+}
+
+/// Prompt for commands that drive a simulation a line at a time, for poking at a
+/// board by hand without writing code
+pub fn run(args: ReplArgs) -> ExitCode {
+    match with_size!(args.size, repl(&args)) {
+        Some(()) => ExitCode::SUCCESS,
+        None => ExitCode::FAILURE,
+    }
+}
+
+fn repl<const N: usize>(args: &ReplArgs) {
+    let mut repl = Repl::<N> {
+        sim: new_sim(args),
+        colors: args.rule.colors(),
+        orientation: ImageOrientation {
+            flip_x: args.flip.is_some_and(|(x, _)| x),
+            flip_y: args.flip.is_some_and(|(_, y)| y),
+            quarter_turns: args.rotate.unwrap_or(0),
+        },
+    };
+    println!(
+        "{}",
+        tr!(
+            "repl-welcome",
+            size = N,
+            rule = args.rule.to_string(),
+            dir = i18n::direction(args.direction).to_lowercase()
+        )
+    );
+    loop {
+        print!("> ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        match Line::parse(&line) {
+            Ok(Some(Line::Quit)) => break,
+            Ok(Some(Line::Reset)) => {
+                repl.sim = new_sim(args);
+                println!("{}", tr!("repl-reset"));
+            }
+            Ok(Some(line)) => {
+                if let Err(e) = repl.run(line) {
+                    eprintln!("{e}");
+                }
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("{e}"),
+        }
+    }
+}
+
+/// White board with an ant in the middle
+fn new_sim<const N: usize>(args: &ReplArgs) -> Simulation<N, N, ColorMap<N, N>> {
+    let mut sim = Simulation::new(ColorMap::new_white(), args.rule.clone());
+    let middle = (N / 2) as isize;
+    sim.spawn(Pos::new(middle, middle), args.direction)
+        .expect("the middle is on the map");
+    sim
+}
+
+struct Repl<const N: usize> {
+    sim: Simulation<N, N, ColorMap<N, N>>,
+    colors: usize,
+    orientation: ImageOrientation,
+}
+
+impl<const N: usize> Repl<N> {
+    fn run(&mut self, line: Line) -> Result<(), String> {
+        match line {
+            Line::Step(steps) => {
+                let on_map = self.sim.run_at_most(steps);
+                let steps = self.sim.steps();
+                match on_map {
+                    true => println!("{}", tr!("repl-stepped", steps = steps)),
+                    false => println!("{}", tr!("repl-all-left", steps = steps)),
+                }
+            }
+            Line::Back(steps) => {
+                for _ in 0..steps {
+                    self.sim.step_back().map_err(|e| e.to_string())?;
+                }
+                println!("{}", tr!("repl-stepped", steps = self.sim.steps()));
+            }
+            Line::Get(pos) => {
+                let color = self.sim.map().color(self.cell(pos)?);
+                println!(
+                    "{}",
+                    tr!(
+                        "repl-cell",
+                        x = pos.x,
+                        y = pos.y,
+                        color = color,
+                        name = self.color_name(color)
+                    )
+                );
+            }
+            Line::Set(pos, color) => {
+                let cell = self.cell(pos)?;
+                let color = self.parse_color(&color)?;
+                self.sim.map_mut().set_color(cell, color);
+            }
+            Line::Spawn(pos, dir) => {
+                let index = self
+                    .sim
+                    .spawn(pos, dir)
+                    .map_err(|_| tr!("cant-spawn", x = pos.x, y = pos.y))?;
+                println!("{}", tr!("repl-spawned", index = index));
+            }
+            Line::Ants => {
+                for (index, ant) in self.sim.ants().iter().enumerate() {
+                    let pos = Pos::from(ant.pos());
+                    let (name, pos, dir) = (
+                        tr!("ant-numbered", index = index),
+                        format!("({}, {})", pos.x, pos.y),
+                        i18n::direction(ant.dir()),
+                    );
+                    match ant.is_on_map() {
+                        true => {
+                            println!("{}", tr!("ant-stopped", name = name, pos = pos, dir = dir))
+                        }
+                        false => println!("{}", tr!("ant-left", name = name, pos = pos, dir = dir)),
+                    }
+                }
+            }
+            Line::Save(file) => {
+                let map = Oriented::new(self.sim.map(), self.orientation);
+                save_color_map_to_file(&map, self.colors, None, None, &file).map_err(|e| {
+                    tr!("repl-cant-save", file = file.clone(), error = e.to_string())
+                })?;
+                println!("{}", tr!("repl-saved", file = file));
+            }
+            Line::Stats => {
+                let mut counts = vec![0usize; self.colors];
+                for color in self.sim.map().to_colors() {
+                    counts[color as usize] += 1;
+                }
+                let [left, right] = self.sim.turns();
+                let on_map = self.sim.ants().iter().filter(|ant| ant.is_on_map()).count();
+                println!(
+                    "{}",
+                    tr!(
+                        "repl-stats",
+                        steps = self.sim.steps(),
+                        on_map = on_map,
+                        ants = self.sim.ants().len(),
+                        left = left,
+                        right = right
+                    )
+                );
+                println!("{}", tr!("tiles-per-color", counts = format!("{counts:?}")));
+            }
+            Line::Help => println!("{}", tr!("repl-help")),
+            Line::Reset | Line::Quit => unreachable!("handled by the loop"),
+        }
+        Ok(())
+    }
+
+    fn cell(&self, pos: Pos) -> Result<MapPos<'static, N, N>, String> {
+        MapPos::validate_pos(pos).map_err(|pos| tr!("repl-off-map", x = pos.x, y = pos.y))
+    }
+
+    /// `white`, `black` or a color number of the rule
+    fn parse_color(&self, color: &str) -> Result<u8, String> {
+        let color = match color.to_ascii_lowercase().as_str() {
+            "white" => 0,
+            "black" => self.colors - 1,
+            number => number
+                .parse()
+                .map_err(|_| tr!("repl-not-a-number", word = number.to_owned()))?,
+        };
+        match color < self.colors {
+            true => Ok(color as u8),
+            false => Err(tr!("repl-no-color", color = color, last = self.colors - 1)),
+        }
+    }
+
+    fn color_name(&self, color: u8) -> String {
+        match color as usize {
+            0 => tr!("lesson-white"),
+            c if c + 1 == self.colors => tr!("lesson-black"),
+            c => tr!("lesson-gray", color = c),
+        }
+    }
+}
+
+#[test]
+fn lines_parse_into_commands() {
+    assert_eq!(Line::parse("  \n"), Ok(None));
+    assert_eq!(Line::parse("step"), Ok(Some(Line::Step(1))));
+    assert_eq!(Line::parse("STEP 1000\n"), Ok(Some(Line::Step(1000))));
+    assert_eq!(
+        Line::parse("set 3 -4 black"),
+        Ok(Some(Line::Set(Pos::new(3, -4), "black".into())))
+    );
+    assert_eq!(
+        Line::parse("spawn 1 2 e"),
+        Ok(Some(Line::Spawn(Pos::new(1, 2), Direction::East)))
+    );
+    assert_eq!(
+        Line::parse("save foo.png"),
+        Ok(Some(Line::Save("foo.png".into())))
+    );
+    assert!(Line::parse("step -1").is_err());
+    assert!(Line::parse("get 1").is_err());
+    assert!(Line::parse("fly").is_err());
+}
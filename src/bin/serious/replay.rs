@@ -0,0 +1,222 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use antventure::{
+    Animation, AnimationFormat, Crop, Frame, ImageOrientation, Palette, PngRenderer, Renderer,
+    Replay, ReplayError,
+};
+
+use crate::{
+    config::{parse_flip, parse_rotation, FrameSchedule},
+    i18n::tr,
+};
+
+#[derive(clap::Args)]
+pub struct ReplayArgs {
+    /// Run recorded with --record
+    input: PathBuf,
+
+    /// The board as a .png, or an animation of the run as a .gif or .apng
+    #[arg(long, short, default_value = "replay.png")]
+    output: PathBuf,
+
+    /// Stop at this step instead of the end of the recording
+    #[arg(long, value_name = "STEP")]
+    at: Option<u64>,
+
+    /// Draw every cell as a square of this many pixels a side
+    #[arg(long, default_value_t = 1)]
+    scale: usize,
+
+    /// Comma separated colors of the cells, or colorblind, grays without it
+    #[arg(long)]
+    palette: Option<Palette>,
+
+    /// Only render the part of the board the run repainted, with this many cells around it
+    #[arg(long, value_name = "PADDING")]
+    crop: Option<usize>,
+
+    /// Steps between the frames of an animation, or a ramp like 1:10000,1000
+    #[arg(long, value_name = "STEPS", default_value = "1000")]
+    frame_every: FrameSchedule,
+
+    /// Mirror the pictures: x (left to right), y (top to bottom) or xy
+    #[arg(long, value_parser = parse_flip)]
+    flip: Option<(bool, bool)>,
+
+    /// Turn the pictures clockwise by 90, 180 or 270 degrees, after --flip
+    #[arg(long, value_name = "DEGREES", value_parser = parse_rotation)]
+    rotate: Option<u8>,
+}
+
+/// Render a recorded run again without walking it, the recording holds every cell
+/// it repainted
+pub fn run(args: ReplayArgs) -> ExitCode {
+    match replay(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            let file = args.input.display().to_string();
+            eprintln!(
+                "{}",
+                tr!("cant-replay", file = file, error = error.to_string())
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn replay(args: &ReplayArgs) -> Result<(), ReplayError> {
+    let at = args.at.unwrap_or(u64::MAX);
+    // The cells repainted by the end are only known after playing up to it
+    let crop = match args.crop {
+        Some(padding) => {
+            let mut replay = Replay::open(&args.input)?;
+            replay.play_to(at)?;
+            replay
+                .touched()
+                .map(|touched| Crop::around(touched, padding, (replay.width(), replay.height())))
+        }
+        None => None,
+    };
+
+    let (flip_x, flip_y) = args.flip.unwrap_or_default();
+    let orientation = ImageOrientation {
+        flip_x,
+        flip_y,
+        quarter_turns: args.rotate.unwrap_or(0),
+    };
+
+    let mut replay = Replay::open(&args.input)?;
+    let file = args.output.display().to_string();
+    // A plain .png is the board, an animated one needs .apng
+    let still = args
+        .output
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+    let format = AnimationFormat::from_path(&args.output).filter(|_| !still);
+    let Some(format) = format else {
+        replay.play_to(at)?;
+        let frame = replay.frame(crop).oriented(orientation);
+        let renderer = PngRenderer {
+            palette: args.palette.clone(),
+            scale: args.scale,
+            ..Default::default()
+        };
+        renderer.save(&frame, &args.output)?;
+        println!(
+            "{}",
+            tr!("replay-rendered", step = replay.step(), file = file)
+        );
+        return Ok(());
+    };
+
+    let first = replay.frame(crop).oriented(orientation).scaled(args.scale);
+    let mut animation = Animation::create(
+        &args.output,
+        format,
+        first.width,
+        first.height,
+        replay.colors(),
+    )?;
+    let mut capture = |frame: Frame, colors: usize| {
+        let palette = args.palette.clone().unwrap_or(Palette::gray(colors));
+        let mut rgba = vec![0; frame.cells.len() * 4];
+        frame.fill_rgba(&palette, &mut rgba);
+        animation.capture_rgba(rgba)
+    };
+    capture(first, replay.colors())?;
+    loop {
+        let (before, next) = (replay.step(), args.frame_every.next(replay.step()));
+        let reached = replay.play_to(next.min(at))?;
+        if replay.step() > before {
+            let frame = replay.frame(crop).oriented(orientation);
+            capture(frame.scaled(args.scale), replay.colors())?;
+        }
+        if !reached || replay.step() >= at {
+            break;
+        }
+    }
+    let frames = animation.frames();
+    animation.finish()?;
+    println!(
+        "{}",
+        tr!(
+            "replay-animated",
+            frames = frames,
+            step = replay.step(),
+            file = file
+        )
+    );
+    Ok(())
+}
+
+#[test]
+fn recordings_render_as_boards_and_animations() {
+    use antventure::{ColorMap, Direction, Pos, ReplayRecorder, Rule, Simulation};
+    use clap::Parser;
+
+    use crate::{Cli, Command};
+
+    let replay = |args: &[&str]| {
+        let args = ["serious", "replay"].iter().chain(args);
+        match Cli::try_parse_from(args).map(|cli| cli.command) {
+            Ok(Some(Command::Replay(args))) => Ok(args),
+            Ok(_) => panic!("not a replay command"),
+            Err(e) => Err(e),
+        }
+    };
+    let args = replay(&["run.antr", "--at", "500", "--scale", "2", "--flip", "x"]).unwrap();
+    assert_eq!(args.input, PathBuf::from("run.antr"));
+    assert_eq!(args.output, PathBuf::from("replay.png"));
+    assert_eq!((args.at, args.scale), (Some(500), 2));
+    assert_eq!(args.flip, Some((true, false)));
+    assert!(replay(&[]).is_err());
+    assert!(replay(&["run.antr", "--rotate", "45"]).is_err());
+
+    let dir = std::env::temp_dir();
+    let recording = dir.join("antventure_recordings_render.antr");
+    let mut sim = Simulation::new(
+        ColorMap::<32, 32>::new_white(),
+        "LLRR".parse::<Rule>().unwrap(),
+    );
+    sim.spawn(Pos::new(16, 16), Direction::North).unwrap();
+    let recorder = ReplayRecorder::create(&recording, &sim).unwrap();
+    let mut sim = sim.with_observer(recorder);
+    sim.run_at_most(1000);
+    sim.finish();
+    drop(sim);
+    let path = |file: &std::path::Path| file.display().to_string();
+
+    let board = dir.join("antventure_recordings_render.png");
+    let args = [&path(&recording), "-o", &path(&board), "--scale", "3"];
+    assert!(run(replay(&args).unwrap()) == ExitCode::SUCCESS);
+    let image = image::open(&board).unwrap();
+    assert_eq!((image.width(), image.height()), (96, 96));
+    let args = [&path(&recording), "-o", &path(&board), "--crop", "1"];
+    assert!(run(replay(&args).unwrap()) == ExitCode::SUCCESS);
+    assert!(image::open(&board).unwrap().width() < 32);
+
+    let animation = dir.join("antventure_recordings_render.gif");
+    let args = [
+        &path(&recording),
+        "-o",
+        &path(&animation),
+        "--frame-every",
+        "100",
+    ];
+    assert!(run(replay(&args).unwrap()) == ExitCode::SUCCESS);
+    let mut decoder = gif::DecodeOptions::new()
+        .read_info(std::fs::File::open(&animation).unwrap())
+        .unwrap();
+    let mut frames = 0;
+    while decoder.next_frame_info().unwrap().is_some() {
+        frames += 1;
+    }
+    // The first board and one every 100 steps
+    assert_eq!(frames, 11);
+
+    let missing = dir.join("antventure_recordings_render_missing.antr");
+    assert!(run(replay(&[&path(&missing)]).unwrap()) == ExitCode::FAILURE);
+    for file in [recording, board, animation] {
+        std::fs::remove_file(file).unwrap();
+    }
+}
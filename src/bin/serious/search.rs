@@ -0,0 +1,267 @@
+use std::{fmt::Write as _, fs, path::PathBuf, process::ExitCode};
+
+use antventure::{
+    rules_up_to, save_palette_map_to_file, AntventureError, DensityGrid, Frame, Metric, Mosaic,
+    Palette, PngRenderer, Renderer, Rule, RuleSearch, SearchResult, SweepExecutor,
+};
+
+use crate::i18n::tr;
+
+#[derive(clap::Args)]
+pub struct SearchArgs {
+    /// Try every rule of L and R up to this many letters
+    #[arg(long, default_value_t = 8)]
+    max_len: usize,
+
+    /// Try these rules instead, comma separated
+    #[arg(long, value_delimiter = ',', conflicts_with = "max_len")]
+    rules: Vec<Rule>,
+
+    /// Map width and height in cells: 64, 128, 256 or 512
+    #[arg(long, default_value_t = 256)]
+    size: usize,
+
+    /// Steps to walk each ant for, unless it leaves the map first
+    #[arg(long, default_value_t = 100_000)]
+    steps: u64,
+
+    /// What to rank rules by: colored (most cells colored), growth (largest area walked)
+    /// or highway (quickest to build one)
+    #[arg(long, short, default_value_t = Metric::Colored)]
+    metric: Metric,
+
+    /// Rules to print and render thumbnails of
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+
+    /// Worker threads, one per physical core by default
+    #[arg(long)]
+    workers: Option<usize>,
+
+    /// Pin every worker to its own core
+    #[arg(long)]
+    pin: bool,
+
+    /// Pixels on a side of the gray thumbnail saved next to every top rule
+    #[arg(long, default_value_t = 256)]
+    thumbnail_size: usize,
+
+    /// Also save the top rules side by side in one PNG, a poster of the search
+    #[arg(long)]
+    mosaic: Option<PathBuf>,
+
+    /// Directory for report.csv and the thumbnails
+    #[arg(long, short, default_value = "search")]
+    output: PathBuf,
+}
+
+/// Simulate many rules in parallel and rank them
+pub fn run(args: SearchArgs) -> Result<ExitCode, AntventureError> {
+    let rules = match args.rules.is_empty() {
+        true => rules_up_to(args.max_len).collect(),
+        false => args.rules.clone(),
+    };
+    if rules.is_empty() {
+        eprintln!("{}", tr!("search-no-rules"));
+        return Ok(ExitCode::FAILURE);
+    }
+    if let Err(e) = fs::create_dir_all(&args.output) {
+        eprintln!(
+            "{}",
+            tr!(
+                "cant-create",
+                dir = args.output.display().to_string(),
+                error = e.to_string()
+            )
+        );
+        return Ok(ExitCode::FAILURE);
+    }
+
+    match args.size {
+        64 => search::<64>(&args, rules)?,
+        128 => search::<128>(&args, rules)?,
+        256 => search::<256>(&args, rules)?,
+        512 => search::<512>(&args, rules)?,
+        size => {
+            eprintln!(
+                "{}",
+                tr!(
+                    "unsupported-size",
+                    size = size,
+                    sizes = "[64, 128, 256, 512]"
+                )
+            );
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn search<const S: usize>(args: &SearchArgs, rules: Vec<Rule>) -> Result<(), AntventureError> {
+    let mut executor = SweepExecutor::new().pin_to_cores(args.pin);
+    if let Some(workers) = args.workers {
+        executor = executor.workers(workers);
+    }
+    let search = RuleSearch::<S>::new(args.steps).with_executor(executor);
+
+    println!("{}", tr!("search-trying", count = rules.len(), size = S));
+    let (mut results, stats) = search.run(rules);
+    let steps = stats.iter().map(|s| s.steps).sum::<u64>();
+    let busy = stats.iter().map(|s| s.busy.as_secs_f64()).sum::<f64>();
+    println!(
+        "{}",
+        tr!(
+            "search-speed",
+            steps = steps,
+            workers = stats.len(),
+            speed = format!("{:.0}", steps as f64 / busy.max(f64::EPSILON))
+        )
+    );
+
+    args.metric.rank(&mut results);
+    fs::write(args.output.join("report.csv"), report(&results))?;
+
+    println!("{}", tr!("search-header"));
+    let top = results.len().min(args.top);
+    let mut mosaic = Mosaic::square(top).with_spacing(S / 16);
+    // Rules of fewer colors are spread over the grays of the one with the most,
+    // so every board in the mosaic fades from white to black
+    let darkest = results[..top]
+        .iter()
+        .map(|r| r.rule.colors())
+        .max()
+        .unwrap_or(2)
+        - 1;
+    for (rank, result) in results.iter().enumerate().take(args.top) {
+        let highway = result
+            .highway
+            .map_or("-".to_owned(), |h| h.detected_at.to_string());
+        println!(
+            "{:>4}  {:<16} {:>8} {:>8}  {highway}",
+            rank + 1,
+            result.rule.to_string(),
+            result.colored,
+            result.area()
+        );
+
+        // Rerunning is cheaper than holding on to every map of the search
+        let (_, map) = search.simulate(result.rule.clone());
+        let colors = result.rule.colors();
+        let file = args
+            .output
+            .join(format!("{:03}-{}.png", rank + 1, result.rule));
+        save_palette_map_to_file(&map, colors, &Palette::gray(colors), &[], None, file)?;
+        let thumbnail = args
+            .output
+            .join(format!("{:03}-{}-thumb.png", rank + 1, result.rule));
+        DensityGrid::new(&map, colors, args.thumbnail_size).save(thumbnail)?;
+
+        if args.mosaic.is_some() {
+            let mut board = Frame::new(&map, darkest + 1, None);
+            for cell in &mut board.cells {
+                *cell = (*cell as usize * darkest / (colors - 1)) as u8;
+            }
+            mosaic.push(board);
+        }
+    }
+
+    if let Some(file) = &args.mosaic {
+        let renderer = PngRenderer {
+            palette: Some(Palette::gray(darkest + 1)),
+            ..PngRenderer::default()
+        };
+        renderer.save(&mosaic.frame(), file)?;
+        println!("{}", tr!("search-mosaic", count = mosaic.len()));
+    }
+    Ok(())
+}
+
+/// Every result as CSV, in ranked order
+fn report(results: &[SearchResult]) -> String {
+    let mut csv = "rank,rule,steps,colored,area,highway_at,highway_period,left_map\n".to_owned();
+    for (rank, result) in results.iter().enumerate() {
+        let (at, period) = match result.highway {
+            Some(h) => (h.detected_at.to_string(), h.period.to_string()),
+            None => Default::default(),
+        };
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{at},{period},{}",
+            rank + 1,
+            result.rule,
+            result.steps,
+            result.colored,
+            result.area(),
+            result.left_map
+        );
+    }
+    csv
+}
+
+#[test]
+fn searches_rank_and_report_rules() {
+    use crate::{Cli, Command};
+    use clap::Parser;
+
+    let search = |args: &[&str]| {
+        let args = ["serious", "search"].iter().chain(args);
+        match Cli::try_parse_from(args).map(|cli| cli.command) {
+            Ok(Some(Command::Search(args))) => Ok(args),
+            Ok(_) => panic!("not a search command"),
+            Err(e) => Err(e),
+        }
+    };
+    let args = search(&["--max-len", "3", "-m", "highway", "--pin"]).unwrap();
+    assert_eq!(args.max_len, 3);
+    assert!(args.rules.is_empty() && args.pin);
+    assert_eq!(args.metric, Metric::Highway);
+    let args = search(&["--rules", "RL,LLRR"]).unwrap();
+    assert_eq!(args.rules, ["RL".parse().unwrap(), "LLRR".parse().unwrap()]);
+    assert!(search(&["--rules", "RL", "--max-len", "3"]).is_err());
+    assert!(search(&["--rules", "RX"]).is_err());
+
+    let output = std::env::temp_dir().join("antventure_searches_rank_rules");
+    let mosaic = output.join("mosaic.png");
+    let (dir, poster) = (output.display().to_string(), mosaic.display().to_string());
+    let args = [
+        "--rules",
+        "RL,RLR,LLRR",
+        "--size",
+        "64",
+        "--steps",
+        "2000",
+        "--top",
+        "2",
+        "--workers",
+        "2",
+        "--thumbnail-size",
+        "16",
+        "--mosaic",
+        &poster,
+        "-o",
+        &dir,
+    ];
+    assert!(run(search(&args).unwrap()).unwrap() == ExitCode::SUCCESS);
+    let report = fs::read_to_string(output.join("report.csv")).unwrap();
+    let lines = report.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("rank,rule,"));
+    assert!(lines[1].starts_with("1,") && lines[3].starts_with("3,"));
+    let thumbnails = fs::read_dir(&output)
+        .unwrap()
+        .filter(|entry| {
+            entry
+                .as_ref()
+                .unwrap()
+                .path()
+                .to_string_lossy()
+                .ends_with("-thumb.png")
+        })
+        .count();
+    assert_eq!(thumbnails, 2);
+    assert!(mosaic.exists());
+
+    let args = ["--rules", "RL", "--size", "100", "-o", &dir];
+    assert!(run(search(&args).unwrap()).unwrap() == ExitCode::FAILURE);
+    fs::remove_dir_all(output).unwrap();
+}
@@ -0,0 +1,272 @@
+use std::{io, net::SocketAddr, thread, time::Instant};
+
+use antventure::{
+    mailbox, CellStore, ControlHandle, Frame, Mailbox, PngRenderer, Pos, Renderer, Simulation,
+};
+use serde_json::json;
+use tiny_http::{Header, Method, Response};
+
+use crate::config::Config;
+
+/// Longest stretch walked between looks at the requests, so answers come in milliseconds
+pub const ANSWER_EVERY: u64 = 1 << 16;
+
+/// Page at `/` showing the map and stats and holding the control buttons
+const PAGE: &str = r#"<!doctype html>
+<meta charset="utf-8">
+<title>antventure</title>
+<style>body { font-family: sans-serif } img { image-rendering: pixelated; max-width: 100% }</style>
+<p>
+  <button onclick="send('/pause')">Pause</button>
+  <button onclick="send('/resume')">Resume</button>
+  <button onclick="send('/step?n=' + n.value)">Step</button>
+  <input id="n" type="number" value="1000" min="1">
+  <button onclick="send('/speed?n=' + speed.value)">Speed</button>
+  <input id="speed" type="number" value="0" min="0">
+  <button onclick="send('/stop')">Stop</button>
+</p>
+<pre id="stats"></pre>
+<img id="map" src="/map.png">
+<script>
+  const show = stats => document.getElementById('stats').textContent = JSON.stringify(stats, null, 2);
+  const send = url => fetch(url, { method: 'POST' }).then(r => r.json()).then(show);
+  setInterval(() => {
+    fetch('/stats').then(r => r.json()).then(show);
+    document.getElementById('map').src = '/map.png?' + Date.now();
+  }, 2000);
+</script>
+"#;
+
+enum Request {
+    Page,
+    /// Whether the run is alive, for container health checks
+    Health,
+    Stats,
+    Map,
+    Pause,
+    Resume,
+    /// Walk this many steps while paused, then pause again
+    Step(u64),
+    /// Steps a second, `None` for as fast as it goes
+    Speed(Option<f64>),
+    Stop,
+}
+
+struct Reply {
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+/// `--serve`: an HTTP server on its own thread, answered by the simulation between
+/// stretches of walking
+///
+/// `GET /stats` gives the step, the ants and whether the run is paused as JSON,
+/// `GET /map.png` the board as it is now, and `POST /pause`, `/resume` and
+/// `/step?n=1000` hold and walk the run, `/speed?n=2000` paces it and `/stop` ends it.
+/// They go through the run's [`ControlHandle`]. `GET /` is a page showing all of it.
+/// `GET /healthz` is answered while the run goes on and with 503 once it's over
+pub struct Server {
+    requests: Mailbox<Request, Reply>,
+    control: ControlHandle,
+    /// Step to pause at again after `/step`
+    pause_at: Option<u64>,
+    started: Instant,
+}
+
+impl Server {
+    pub fn bind(addr: SocketAddr, control: ControlHandle) -> io::Result<Self> {
+        let http = tiny_http::Server::http(addr).map_err(io::Error::other)?;
+        let (postbox, requests) = mailbox::<Request, Reply>();
+        thread::spawn(move || {
+            for request in http.incoming_requests() {
+                let url = request.url().to_owned();
+                let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+                let parsed = match (request.method(), path) {
+                    (Method::Get, "/") => Some(Request::Page),
+                    (Method::Get, "/healthz") => Some(Request::Health),
+                    (Method::Get, "/stats") => Some(Request::Stats),
+                    (Method::Get, "/map.png") => Some(Request::Map),
+                    (Method::Post, "/pause") => Some(Request::Pause),
+                    (Method::Post, "/resume") => Some(Request::Resume),
+                    (Method::Post, "/step") => Some(Request::Step(step_count(query))),
+                    (Method::Post, "/speed") => Some(Request::Speed(speed(query))),
+                    (Method::Post, "/stop") => Some(Request::Stop),
+                    _ => None,
+                };
+                let Some(parsed) = parsed else {
+                    request.respond(Response::empty(404)).ok();
+                    continue;
+                };
+
+                let response = match postbox.ask(parsed).wait() {
+                    Some(reply) => {
+                        let header = Header::from_bytes("Content-Type", reply.content_type)
+                            .expect("a valid header");
+                        Response::from_data(reply.body).with_header(header)
+                    }
+                    // The run is over and nobody is left to answer
+                    None => Response::from_string("the run is over").with_status_code(503),
+                };
+                request.respond(response).ok();
+            }
+        });
+
+        Ok(Self {
+            requests,
+            control,
+            pause_at: None,
+            started: Instant::now(),
+        })
+    }
+
+    /// Answer the requests waiting, and wait for more while paused. Returns the step to
+    /// walk up to before coming back
+    pub fn answer<const S: usize, M: CellStore<S, S>>(
+        &mut self,
+        sim: &Simulation<S, S, M>,
+        config: &Config,
+    ) -> u64 {
+        loop {
+            let paused = self.control.is_paused() && !self.control.is_cancelled();
+            let holding = paused && self.pause_at.is_none_or(|at| sim.steps() >= at);
+            if holding {
+                self.pause_at = None;
+            }
+            let next = match holding {
+                true => self.requests.next(),
+                false => self.requests.try_next(),
+            };
+            let Some((request, reply)) = next else {
+                break;
+            };
+            match request {
+                Request::Pause => {
+                    self.control.pause();
+                    self.pause_at = None;
+                }
+                Request::Resume => {
+                    self.control.resume();
+                    self.pause_at = None;
+                }
+                Request::Step(steps) if paused => self.pause_at = Some(sim.steps() + steps),
+                Request::Speed(speed) => self.control.set_speed(speed),
+                Request::Stop => self.control.cancel(),
+                _ => {}
+            }
+            reply.send(self.reply(request, sim, config));
+        }
+        self.pause_at.unwrap_or(sim.steps() + ANSWER_EVERY)
+    }
+
+    fn reply<const S: usize, M: CellStore<S, S>>(
+        &self,
+        request: Request,
+        sim: &Simulation<S, S, M>,
+        config: &Config,
+    ) -> Reply {
+        match request {
+            Request::Page => Reply {
+                content_type: "text/html; charset=utf-8",
+                body: PAGE.into(),
+            },
+            Request::Health => Reply {
+                content_type: "application/json",
+                body: json!({ "status": "ok", "step": sim.steps() })
+                    .to_string()
+                    .into(),
+            },
+            Request::Map => {
+                let colors = config.colors();
+                let ants = sim
+                    .ants()
+                    .iter()
+                    .map(|ant| ant.pos().into())
+                    .collect::<Vec<Pos>>();
+                let frame = Frame::new(sim.map(), colors, None)
+                    .with_ants(&ants)
+                    .oriented(config.orientation);
+                let renderer = PngRenderer {
+                    palette: config.color_palette(colors),
+                    ..Default::default()
+                };
+                let mut body = Vec::new();
+                renderer
+                    .render(&frame, &mut body)
+                    .expect("writing to memory doesn't fail");
+                Reply {
+                    content_type: "image/png",
+                    body,
+                }
+            }
+            Request::Stats
+            | Request::Pause
+            | Request::Resume
+            | Request::Step(_)
+            | Request::Speed(_)
+            | Request::Stop => {
+                let ants = sim
+                    .ants()
+                    .iter()
+                    .map(|ant| {
+                        let pos = config.position(ant.pos().into());
+                        json!({
+                            "x": pos.x,
+                            "y": pos.y,
+                            "direction": format!("{:?}", ant.dir()).to_lowercase(),
+                            "on_map": ant.is_on_map(),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                let stats = json!({
+                    "step": sim.steps(),
+                    "paused": self.control.is_paused(),
+                    "speed": self.control.speed(),
+                    "stopped": self.control.is_cancelled(),
+                    "seconds": self.started.elapsed().as_secs_f64(),
+                    "rule": config.rule.to_string(),
+                    "size": config.size,
+                    "ants": ants,
+                });
+                Reply {
+                    content_type: "application/json",
+                    body: stats.to_string().into(),
+                }
+            }
+        }
+    }
+}
+
+/// `n` of a `/step` query, one step without it
+fn step_count(query: &str) -> u64 {
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("n="))
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// `n` of a `/speed` query, steps a second, `None` for as fast as it goes without it
+/// or with `n=0`
+fn speed(query: &str) -> Option<f64> {
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("n="))
+        .and_then(|n| n.parse::<f64>().ok())
+        .filter(|n| *n > 0.0 && n.is_finite())
+}
+
+#[test]
+fn step_counts() {
+    assert_eq!(step_count("n=1000"), 1000);
+    assert_eq!(step_count("x=1&n=5"), 5);
+    assert_eq!(step_count(""), 1);
+    assert_eq!(step_count("n=0"), 1);
+    assert_eq!(step_count("n=many"), 1);
+
+    assert_eq!(speed("n=2000"), Some(2000.0));
+    assert_eq!(speed("n=0.5&x=1"), Some(0.5));
+    assert_eq!(speed("n=0"), None);
+    assert_eq!(speed("n=-3"), None);
+    assert_eq!(speed(""), None);
+}
@@ -0,0 +1,382 @@
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    process::{self, ExitCode},
+};
+
+use antventure::{analyze_png, ControlHandle, Rule};
+
+use crate::{
+    config::{parse_flip, parse_rotation},
+    i18n::tr,
+};
+
+const STEPS: usize = 3;
+
+#[derive(clap::Args)]
+pub struct TutorialArgs {
+    /// Directory for the files the tutorial makes
+    #[arg(long, default_value = "antventure-tutorial")]
+    dir: PathBuf,
+
+    /// Run every step with the suggested values without waiting for Enter
+    #[arg(long, short)]
+    yes: bool,
+
+    /// Mirror the pictures the tutorial saves: x (left to right), y (top to bottom) or xy
+    #[arg(long, value_parser = |s: &str| parse_flip(s).map(|_| s.to_owned()))]
+    flip: Option<String>,
+
+    /// Turn the pictures the tutorial saves clockwise by 90, 180 or 270 degrees
+    #[arg(long, value_name = "DEGREES", value_parser = |s: &str| parse_rotation(s).map(|_| s.to_owned()))]
+    rotate: Option<String>,
+}
+
+/// Walk a new user through running the ant, changing its rule and saving an animation,
+/// every command is run for real and its output checked before going on
+pub fn run(args: TutorialArgs, lang: Option<String>) -> ExitCode {
+    let tutorial = Tutorial {
+        dir: args.dir,
+        yes: args.yes,
+        lang,
+        flip: args.flip,
+        rotate: args.rotate,
+    };
+    println!(
+        "{}",
+        tr!("tutorial-welcome", dir = tutorial.dir.display().to_string())
+    );
+    if let Err(e) = fs::create_dir_all(&tutorial.dir) {
+        eprintln!(
+            "{}",
+            tr!(
+                "cant-create",
+                dir = tutorial.dir.display().to_string(),
+                error = e.to_string()
+            )
+        );
+        return ExitCode::FAILURE;
+    }
+
+    match tutorial.lessons() {
+        Ok(true) => {
+            println!("\n{}", tr!("tutorial-done"));
+            ExitCode::SUCCESS
+        }
+        Ok(false) => {
+            println!("{}", tr!("tutorial-quit"));
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", tr!("tutorial-failed", error = e));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+struct Tutorial {
+    dir: PathBuf,
+    yes: bool,
+    // Passed on to the commands, they don't see our --lang
+    lang: Option<String>,
+    // Added to every command
+    flip: Option<String>,
+    rotate: Option<String>,
+}
+
+impl Tutorial {
+    /// Every lesson in order, `false` if the user quit on the way
+    fn lessons(&self) -> Result<bool, String> {
+        let board = self.dir.join("first.png");
+        self.title(1, tr!("tutorial-run-title"));
+        println!("{}", tr!("tutorial-run-intro"));
+        let board_arg = board.display().to_string();
+        if !self.run(&["--size", "256", "--max-steps", "11000", "-o", &board_arg])? {
+            return Ok(false);
+        }
+        let stats = analyze_png(
+            File::open(&board).map_err(|e| e.to_string())?,
+            &ControlHandle::new(),
+        )
+        .map_err(|e| e.to_string())?;
+        if stats.black == 0 {
+            return Err(tr!("tutorial-no-black", file = board_arg));
+        }
+        println!(
+            "{}",
+            tr!(
+                "tutorial-run-check",
+                width = stats.width,
+                height = stats.height,
+                black = stats.black,
+                file = board_arg
+            )
+        );
+
+        self.title(2, tr!("tutorial-rule-title"));
+        println!("{}", tr!("tutorial-rule-intro"));
+        let Some(rule) = self.ask_rule()? else {
+            return Ok(false);
+        };
+        let colored = self.dir.join("rule.png");
+        let colored_arg = colored.display().to_string();
+        let rule_arg = rule.to_string();
+        let args = [
+            "--rule",
+            &rule_arg,
+            "--size",
+            "256",
+            "--max-steps",
+            "20000",
+            "-o",
+            &colored_arg,
+        ];
+        if !self.run(&args)? {
+            return Ok(false);
+        }
+        let (bits, shades) = shades(&colored).map_err(|e| e.to_string())?;
+        // The smallest depth holding every color is picked, so it tells the rule was used
+        let expected = match rule.colors() {
+            0..=2 => 1,
+            3..=4 => 2,
+            5..=16 => 4,
+            _ => 8,
+        };
+        if bits != expected {
+            return Err(tr!(
+                "tutorial-wrong-depth",
+                file = colored_arg,
+                bits = bits,
+                expected = expected
+            ));
+        }
+        println!(
+            "{}",
+            tr!(
+                "tutorial-rule-check",
+                colors = rule.colors(),
+                bits = bits,
+                shades = shades,
+                file = colored_arg
+            )
+        );
+
+        self.title(3, tr!("tutorial-animate-title"));
+        println!("{}", tr!("tutorial-animate-intro"));
+        let animation = self.dir.join("walk.gif");
+        let animation_arg = animation.display().to_string();
+        let last = self.dir.join("last.png").display().to_string();
+        let args = [
+            "--rule",
+            &rule_arg,
+            "--size",
+            "256",
+            "--max-steps",
+            "11000",
+            "--animate",
+            &animation_arg,
+            "--frame-every",
+            "250",
+            "-o",
+            &last,
+        ];
+        if !self.run(&args)? {
+            return Ok(false);
+        }
+        let frames = frames(&animation).map_err(|e| e.to_string())?;
+        if frames < 2 {
+            return Err(tr!("tutorial-no-frames", file = animation_arg));
+        }
+        println!(
+            "{}",
+            tr!(
+                "tutorial-animate-check",
+                frames = frames,
+                file = animation_arg
+            )
+        );
+        Ok(true)
+    }
+
+    fn title(&self, step: usize, title: String) {
+        println!(
+            "\n{}",
+            tr!("tutorial-step", step = step, steps = STEPS, title = title)
+        );
+    }
+
+    /// Line typed by the user, `None` on `q` or the end of input
+    fn ask(&self) -> Result<Option<String>, String> {
+        io::stdout().flush().map_err(|e| e.to_string())?;
+        let mut line = String::new();
+        let read = io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .map_err(|e| e.to_string())?;
+        match line.trim() {
+            _ if read == 0 => Ok(None),
+            "q" | "Q" => Ok(None),
+            answer => Ok(Some(answer.to_owned())),
+        }
+    }
+
+    /// Rule typed by the user, LLRR if they just press Enter
+    fn ask_rule(&self) -> Result<Option<Rule>, String> {
+        let fallback = "LLRR".parse::<Rule>().expect("LLRR is a rule");
+        if self.yes {
+            return Ok(Some(fallback));
+        }
+        print!("{} ", tr!("tutorial-rule-prompt"));
+        loop {
+            let Some(answer) = self.ask()? else {
+                return Ok(None);
+            };
+            if answer.is_empty() {
+                return Ok(Some(fallback));
+            }
+            match answer.parse::<Rule>() {
+                Ok(rule) => return Ok(Some(rule)),
+                Err(e) => print!("{} ", tr!("tutorial-bad-rule", error = e.to_string())),
+            }
+        }
+    }
+
+    /// Show the command, wait for Enter and run it, `false` if the user quit instead
+    fn run(&self, args: &[&str]) -> Result<bool, String> {
+        let orientation = [("--flip", &self.flip), ("--rotate", &self.rotate)];
+        let orientation = orientation
+            .iter()
+            .filter_map(|(flag, value)| Some([*flag, value.as_deref()?]))
+            .flatten();
+        let args = args.iter().copied().chain(orientation).collect::<Vec<_>>();
+        let exe = env::current_exe().map_err(|e| e.to_string())?;
+        let name = exe
+            .file_stem()
+            .map_or("serious".into(), |name| name.to_string_lossy());
+        println!(
+            "\n    {}\n",
+            tr!(
+                "tutorial-command",
+                command = format!("{name} {}", args.join(" "))
+            )
+        );
+        if !self.yes {
+            print!("{} ", tr!("tutorial-press-enter"));
+            if self.ask()?.is_none() {
+                return Ok(false);
+            }
+        }
+
+        let mut command = process::Command::new(&exe);
+        command.args(&args);
+        if let Some(lang) = &self.lang {
+            command.env("ANTVENTURE_LANG", lang);
+        }
+        let status = command.status().map_err(|e| e.to_string())?;
+        match status.success() {
+            true => Ok(true),
+            false => Err(tr!("tutorial-command-failed", status = status.to_string())),
+        }
+    }
+}
+
+/// Bit depth of a grayscale PNG and how many gray levels it uses
+fn shades(file: &Path) -> Result<(usize, usize), png::DecodingError> {
+    let mut reader = png::Decoder::new(File::open(file)?).read_info()?;
+    let (width, bits) = (
+        reader.info().width as usize,
+        reader.info().bit_depth as usize,
+    );
+    let mut data = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut data)?;
+
+    // Rows are padded to whole bytes, the padding is left out
+    let mask = ((1u16 << bits) - 1) as u8;
+    let mut used = [false; 256];
+    for row in data[..frame.buffer_size()].chunks(frame.line_size) {
+        for x in 0..width {
+            let bit = x * bits;
+            used[(row[bit / 8] >> (8 - bits - bit % 8) & mask) as usize] = true;
+        }
+    }
+    Ok((bits, used.iter().filter(|&&used| used).count()))
+}
+
+/// Number of frames in a GIF
+fn frames(file: &Path) -> Result<usize, gif::DecodingError> {
+    let mut decoder = gif::DecodeOptions::new().read_info(File::open(file)?)?;
+    let mut frames = 0;
+    while decoder.next_frame_info()?.is_some() {
+        frames += 1;
+    }
+    Ok(frames)
+}
+
+#[test]
+fn tutorials_check_what_the_commands_saved() {
+    use crate::{Cli, Command};
+    use antventure::{Animation, AnimationFormat};
+    use clap::Parser;
+
+    let parse = |args: &[&str]| {
+        let args = ["serious", "tutorial"].iter().chain(args);
+        match Cli::try_parse_from(args).map(|cli| cli.command) {
+            Ok(Some(Command::Tutorial(args))) => Ok(args),
+            Ok(_) => panic!("not a tutorial command"),
+            Err(e) => Err(e),
+        }
+    };
+    let args = parse(&[]).unwrap();
+    assert_eq!(args.dir, PathBuf::from("antventure-tutorial"));
+    assert!(!args.yes && args.flip.is_none());
+    let args = parse(&["--dir", "t", "-y", "--flip", "xy", "--rotate", "90"]).unwrap();
+    assert_eq!(args.dir, PathBuf::from("t"));
+    assert!(args.yes);
+    // Checked here, passed on to the commands as typed
+    assert_eq!(args.flip.as_deref(), Some("xy"));
+    assert_eq!(args.rotate.as_deref(), Some("90"));
+    assert!(parse(&["--flip", "z"]).is_err());
+    assert!(parse(&["--rotate", "45"]).is_err());
+
+    let tutorial = Tutorial {
+        dir: args.dir,
+        yes: true,
+        lang: None,
+        flip: None,
+        rotate: None,
+    };
+    assert_eq!(tutorial.ask_rule(), Ok(Some("LLRR".parse().unwrap())));
+
+    // Six 2-bit cells in a row, the padding after them isn't a shade
+    let dir = env::temp_dir();
+    let board = dir.join("antventure_tutorials_check.png");
+    let mut encoder = png::Encoder::new(File::create(&board).unwrap(), 6, 1);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Two);
+    let mut writer = encoder.write_header().unwrap();
+    writer
+        .write_image_data(&[0b00_01_11_11, 0b00_11_00_00])
+        .unwrap();
+    writer.finish().unwrap();
+    assert_eq!(shades(&board).unwrap(), (2, 3));
+
+    let animation = dir.join("antventure_tutorials_check.gif");
+    let mut gif = Animation::create(&animation, AnimationFormat::Gif, 4, 4, 2).unwrap();
+    for shade in [255, 0, 255] {
+        gif.capture_rgba([shade, shade, shade, 255].repeat(16))
+            .unwrap();
+    }
+    gif.finish().unwrap();
+    assert_eq!(frames(&animation).unwrap(), 3);
+    fs::remove_file(board).unwrap();
+    fs::remove_file(animation).unwrap();
+
+    // The tutorial can't start without its directory
+    let blocked = dir.join("antventure_tutorials_check_file");
+    fs::write(&blocked, "").unwrap();
+    let dir = blocked.join("tutorial").display().to_string();
+    assert!(run(parse(&["--dir", &dir, "-y"]).unwrap(), None) == ExitCode::FAILURE);
+    fs::remove_file(blocked).unwrap();
+}
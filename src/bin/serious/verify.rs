@@ -0,0 +1,379 @@
+use std::process::ExitCode;
+
+use antventure::{
+    naive, CellStore, ColorMap, Direction, Map, MmapMap, Pos, Rule, Simulation, UnboundedAnt,
+};
+
+use crate::i18n::{self, tr};
+
+/// Sizes every backend can hold, the naive array lives on the stack while it's built
+const SIZES: [usize; 3] = [256, 512, 1024];
+
+#[derive(clap::Args)]
+pub struct VerifyArgs {
+    /// Map width and height in cells
+    #[arg(long, default_value_t = 1024)]
+    size: usize,
+
+    /// Turn for each cell color, two colors run on every backend and LR on the naive
+    /// one too, more only on ColorMap
+    #[arg(long, short, default_value_t = Rule::default())]
+    rule: Rule,
+
+    /// Initial direction: north, east, south or west
+    #[arg(long, short, default_value = "north")]
+    direction: Direction,
+
+    /// Stop after this many steps even if the ant is still on the map
+    #[arg(long, default_value_t = 10_000_000)]
+    max_steps: u64,
+
+    /// Compare the cells of every backend every this many steps, ants are compared
+    /// every step
+    #[arg(long, default_value_t = 1000)]
+    every: u64,
+
+    /// Walk this rule on ColorMap next to --rule instead of checking the backends, to
+    /// find the first step where the two rules part
+    #[arg(long)]
+    versus: Option<Rule>,
+
+    /// Cells on each side of the divergence printed for both boards
+    #[arg(long, default_value_t = 4)]
+    radius: usize,
+}
+
+/// A board and an ant walking it, the way one backend does it
+trait Backend<const N: usize> {
+    fn name(&self) -> &str;
+
+    /// Take a step, `false` once the ant left the map
+    fn step(&mut self) -> bool;
+
+    fn ant(&self) -> (Pos, Direction);
+
+    /// Every cell color in row-major order
+    fn colors(&self) -> Vec<u8>;
+
+    fn digest(&self) -> u64 {
+        ColorMap::<N, N>::with_colors(self.colors()).digest()
+    }
+}
+
+struct Sim<const N: usize, M: CellStore<N, N>> {
+    name: String,
+    sim: Simulation<N, N, M>,
+}
+
+impl<const N: usize, M: CellStore<N, N> + 'static> Sim<N, M> {
+    /// The ant of `args` walking `rule` in the middle of `map`
+    fn boxed(name: String, map: M, rule: &Rule, args: &VerifyArgs) -> Box<dyn Backend<N>> {
+        let mut sim = Simulation::new(map, rule.clone());
+        let middle = Pos::new(N as isize / 2, N as isize / 2);
+        sim.spawn(middle, args.direction)
+            .expect("the middle is on the map");
+        Box::new(Self { name, sim })
+    }
+}
+
+impl<const N: usize, M: CellStore<N, N>> Backend<N> for Sim<N, M> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn step(&mut self) -> bool {
+        self.sim.step()
+    }
+
+    fn ant(&self) -> (Pos, Direction) {
+        let ant = &self.sim.ants()[0];
+        (ant.pos().into(), ant.dir())
+    }
+
+    fn colors(&self) -> Vec<u8> {
+        self.sim.map().to_colors()
+    }
+
+    fn digest(&self) -> u64 {
+        self.sim.map().digest()
+    }
+}
+
+/// The plain array of bools with its own ant, Langton's only
+struct Naive<const N: usize> {
+    map: Box<naive::Map<N, N>>,
+    ant: naive::Ant,
+}
+
+impl<const N: usize> Backend<N> for Naive<N> {
+    fn name(&self) -> &str {
+        "naive"
+    }
+
+    fn step(&mut self) -> bool {
+        self.ant.walk(&mut self.map)
+    }
+
+    fn ant(&self) -> (Pos, Direction) {
+        (self.ant.pos, self.ant.dir)
+    }
+
+    fn colors(&self) -> Vec<u8> {
+        (0..N as isize)
+            .flat_map(|y| (0..N as isize).map(move |x| Pos::new(x, y)))
+            .map(|pos| !self.map.get(&pos).expect("cells are on the map") as u8)
+            .collect()
+    }
+}
+
+/// The sparse board, which has no edge, cut to the map of the others
+struct Sparse<const N: usize>(UnboundedAnt);
+
+impl<const N: usize> Backend<N> for Sparse<N> {
+    fn name(&self) -> &str {
+        "SparseMap"
+    }
+
+    fn step(&mut self) -> bool {
+        self.0.walk();
+        let pos = self.0.pos();
+        (0..N as isize).contains(&pos.x) && (0..N as isize).contains(&pos.y)
+    }
+
+    fn ant(&self) -> (Pos, Direction) {
+        (self.0.pos(), self.0.dir())
+    }
+
+    fn colors(&self) -> Vec<u8> {
+        (0..N as isize)
+            .flat_map(|y| (0..N as isize).map(move |x| Pos::new(x, y)))
+            .map(|pos| self.0.map().is_black(pos) as u8)
+            .collect()
+    }
+}
+
+/// Walk the same ant on every backend that holds the rule in lockstep, or two rules
+/// on ColorMap, and stop at the first step where one of them disagrees with the first
+pub fn run(args: VerifyArgs) -> ExitCode {
+    match args.size {
+        256 => verify::<256>(&args),
+        512 => verify::<512>(&args),
+        1024 => verify::<1024>(&args),
+        size => {
+            eprintln!(
+                "{}",
+                tr!(
+                    "unsupported-size",
+                    size = size,
+                    sizes = format!("{SIZES:?}")
+                )
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn verify<const N: usize>(args: &VerifyArgs) -> ExitCode {
+    let middle = Pos::new(N as isize / 2, N as isize / 2);
+    let rule = &args.rule;
+    let mut backends = Vec::<Box<dyn Backend<N>>>::new();
+    if let Some(versus) = &args.versus {
+        for rule in [rule, versus] {
+            let name = format!("ColorMap {rule}");
+            backends.push(Sim::boxed(name, ColorMap::<N, N>::new_white(), rule, args));
+        }
+        println!(
+            "{}",
+            tr!(
+                "verify-versus",
+                rule = rule.to_string(),
+                versus = versus.to_string(),
+                every = args.every
+            )
+        );
+    } else {
+        if *rule == Rule::default() {
+            backends.push(Box::new(Naive {
+                map: Box::new(naive::Map::new()),
+                ant: naive::Ant::new(middle, args.direction),
+            }));
+        }
+        if rule.colors() <= 2 {
+            backends.push(Sim::boxed(
+                "Map".into(),
+                Map::<N, N>::new_white(),
+                rule,
+                args,
+            ));
+            match MmapMap::<N, N>::anonymous() {
+                Ok(map) => backends.push(Sim::boxed("MmapMap".into(), map, rule, args)),
+                Err(e) => eprintln!("{}", tr!("verify-no-mmap", error = e.to_string())),
+            }
+            backends.push(Box::new(Sparse(UnboundedAnt::with_rule(
+                middle,
+                args.direction,
+                rule.clone(),
+            ))));
+        }
+        let map = ColorMap::<N, N>::new_white();
+        backends.push(Sim::boxed("ColorMap".into(), map, rule, args));
+        if backends.len() < 2 {
+            eprintln!("{}", tr!("verify-one-backend", rule = rule.to_string()));
+            return ExitCode::FAILURE;
+        }
+
+        let names = backends.iter().map(|b| b.name()).collect::<Vec<_>>();
+        println!(
+            "{}",
+            tr!(
+                "verify-running",
+                rule = rule.to_string(),
+                backends = names.join(", "),
+                every = args.every
+            )
+        );
+    }
+
+    let every = args.every.max(1);
+    let mut steps = 0;
+    // What differs, where, and the two backends it differs on
+    let diverged = loop {
+        let (reference, others) = backends.split_first_mut().expect("two backends");
+        let on_map = reference.step();
+        steps += 1;
+        let mut diverged = None;
+        for (i, other) in others.iter_mut().enumerate() {
+            if other.step() != on_map {
+                let message = tr!(
+                    "verify-left",
+                    step = steps,
+                    backend = [reference.name(), other.name()][on_map as usize],
+                    other = [reference.name(), other.name()][!on_map as usize]
+                );
+                diverged = Some((message, reference.ant().0, i + 1));
+            } else if on_map && other.ant() != reference.ant() {
+                let ((pos, dir), (other_pos, other_dir)) = (reference.ant(), other.ant());
+                let message = tr!(
+                    "verify-ant",
+                    step = steps,
+                    reference = reference.name(),
+                    pos = format!("({}, {})", pos.x, pos.y),
+                    dir = i18n::direction(dir),
+                    backend = other.name(),
+                    other_pos = format!("({}, {})", other_pos.x, other_pos.y),
+                    other_dir = i18n::direction(other_dir)
+                );
+                diverged = Some((message, pos, i + 1));
+            }
+            if diverged.is_some() {
+                break;
+            }
+        }
+        if diverged.is_some() {
+            break diverged;
+        }
+
+        let end = !on_map || steps == args.max_steps;
+        if steps.is_multiple_of(every) || end {
+            let digest = reference.digest();
+            if let Some(i) = others.iter().position(|other| other.digest() != digest) {
+                let (cells, other_cells) = (reference.colors(), others[i].colors());
+                let differ = (0..cells.len())
+                    .filter(|&i| cells[i] != other_cells[i])
+                    .collect::<Vec<_>>();
+                let (x, y) = (differ[0] % N, differ[0] / N);
+                let message = tr!(
+                    "verify-cells",
+                    step = steps,
+                    reference = reference.name(),
+                    backend = others[i].name(),
+                    count = differ.len(),
+                    x = x,
+                    y = y
+                );
+                break Some((message, Pos::new(x as _, y as _), i + 1));
+            }
+        }
+        if end {
+            break None;
+        }
+    };
+
+    match diverged {
+        Some((message, center, other)) => {
+            eprintln!("{message}");
+            let (reference, other) = (&*backends[0], &*backends[other]);
+            let grids = [reference, other].map(|b| neighborhood(b, center, args.radius));
+            let width = grids[0][0].chars().count().max(reference.name().len());
+            eprintln!("\n{:width$}   {}", reference.name(), other.name());
+            for (row, other_row) in grids[0].iter().zip(&grids[1]) {
+                eprintln!("{row:width$}   {other_row}");
+            }
+            ExitCode::FAILURE
+        }
+        None => {
+            println!(
+                "{}",
+                tr!("verify-agree", count = backends.len(), steps = steps)
+            );
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+/// Rows of the cells at most `radius` away from `center` on `backend`, white is `.`,
+/// other colors their number and the ant an arrow, cells past the map are blank
+fn neighborhood<const N: usize>(
+    backend: &dyn Backend<N>,
+    center: Pos,
+    radius: usize,
+) -> Vec<String> {
+    let colors = backend.colors();
+    let (ant, dir) = backend.ant();
+    let radius = radius as isize;
+    (center.y - radius..=center.y + radius)
+        .map(|y| {
+            (center.x - radius..=center.x + radius)
+                .map(|x| {
+                    let on_map = (0..N as isize).contains(&x) && (0..N as isize).contains(&y);
+                    if Pos::new(x, y) == ant {
+                        return match dir {
+                            Direction::North => '^',
+                            Direction::East => '>',
+                            Direction::South => 'v',
+                            Direction::West => '<',
+                        };
+                    }
+                    match on_map.then(|| colors[y as usize * N + x as usize]) {
+                        Some(0) => '.',
+                        Some(color) => char::from_digit(color as u32, 36).unwrap_or('#'),
+                        None => ' ',
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn neighborhoods_show_cells_and_the_ant() {
+    let args = VerifyArgs {
+        size: 16,
+        rule: Rule::default(),
+        direction: Direction::North,
+        max_steps: 0,
+        every: 1,
+        versus: None,
+        radius: 2,
+    };
+    let mut backend = Sim::<16, _>::boxed("Map".into(), Map::new_white(), &args.rule, &args);
+    backend.step();
+    assert_eq!(
+        neighborhood(&*backend, Pos::new(8, 8), 2),
+        [".....", ".....", ".<1..", ".....", "....."]
+    );
+    assert_eq!(
+        neighborhood(&*backend, Pos::new(0, 15), 1),
+        [" ..", " ..", "   "]
+    );
+}
@@ -0,0 +1,96 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use antventure::{AntventureError, Direction, Palette, VoxelAnt, VoxelMap, VoxelRule};
+
+use crate::i18n::{self, tr};
+
+#[derive(clap::Args)]
+pub struct VoxelArgs {
+    /// Width, height and depth of the box in voxels
+    #[arg(long, default_value_t = 64)]
+    size: usize,
+
+    /// Turn for each cell color: L/R turn left and right, U/D pitch up and down,
+    /// N goes straight and B turns back
+    #[arg(long, short, default_value = "LRUD")]
+    rule: VoxelRule,
+
+    /// Initial direction in the middle layer, up is towards the layers above
+    #[arg(long, short, default_value = "north")]
+    direction: Direction,
+
+    /// Stop after this many steps even if the ant is still in the box
+    #[arg(long)]
+    max_steps: Option<usize>,
+
+    /// Cell colors of the .vox model, comma separated, gray by default
+    #[arg(long)]
+    palette: Option<Palette>,
+
+    /// A MagicaVoxel .vox model, or a PNG per layer with {z} in the name replaced
+    /// by the layer, e.g. slices/z{z}.png
+    #[arg(long, short, default_value = "voxel.vox")]
+    output: PathBuf,
+}
+
+/// A turmite in a box of voxels
+pub fn run(args: VoxelArgs) -> Result<ExitCode, AntventureError> {
+    if args.size == 0 {
+        eprintln!("{}", tr!("hex-empty-map"));
+        return Ok(ExitCode::FAILURE);
+    }
+    let vox = args
+        .output
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("vox"));
+    if vox && args.size > VoxelMap::MAX_VOX_SIZE {
+        let max = VoxelMap::MAX_VOX_SIZE;
+        eprintln!("{}", tr!("voxel-too-big", max = max));
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let mut map = VoxelMap::new_white(args.size, args.size, args.size);
+    let center = map.center();
+    let mut ant = VoxelAnt::new(&mut map, center, args.direction.into(), args.rule.clone())
+        .expect("the center is in the box");
+
+    let on_map = match args.max_steps {
+        Some(steps) => ant.walk_at_most(steps),
+        None => {
+            ant.walk_until_end();
+            false
+        }
+    };
+    let (pos, dir) = (ant.pos(), ant.orientation().forward());
+    let (name, pos, dir) = (
+        tr!("ant"),
+        format!("({}, {}, {})", pos.x, pos.y, pos.z),
+        i18n::direction(dir),
+    );
+    match on_map {
+        true => println!("{}", tr!("ant-stopped", name = name, pos = pos, dir = dir)),
+        false => println!("{}", tr!("ant-left", name = name, pos = pos, dir = dir)),
+    }
+
+    let colors = args.rule.colors();
+    println!(
+        "{}",
+        tr!(
+            "tiles-per-color",
+            counts = format!("{:?}", map.count_colors(colors))
+        )
+    );
+    if vox {
+        map.save_vox(colors, args.palette.as_ref(), &args.output)?;
+    } else {
+        // A template without {z} gets the layer before the extension
+        let mut template = args.output.display().to_string();
+        if !template.contains("{z}") {
+            let stem = args.output.with_extension("").display().to_string();
+            template = format!("{stem}_{{z}}.png");
+        }
+        let files = map.save_slices(colors, &template)?;
+        println!("{}", tr!("voxel-slices-saved", count = files.len()));
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,760 @@
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use antventure::{
+    Ant, ColorMap, Direction, Frame, ImageOrientation, Pacer, Plugin, PngRenderer, Pos,
+    RasterFormat, Renderer, StepEvent, TurmiteRule,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind},
+    queue,
+    style::{self, Color},
+    terminal,
+};
+
+use crate::{
+    config::{with_size, Args, Config},
+    i18n::{self, tr},
+};
+
+const FRAMES_PER_SECOND: f64 = 30.0;
+// Slow enough to follow every step out loud
+const LESSON_PACE: Duration = Duration::from_secs(1);
+// Steps turmites that can't walk back on their own remember for undoing
+const HISTORY: usize = 100_000;
+// Columns of the minimap of boards bigger than the terminal, rows take two of them
+const MINIMAP: usize = 32;
+// How long a notice stands in for the status line
+const NOTICE: Duration = Duration::from_secs(3);
+
+#[derive(clap::Args)]
+pub struct WatchArgs {
+    /// Size, rule, plugin and first ant are read like for a run, from --config, the
+    /// environment and these
+    #[command(flatten)]
+    args: Args,
+
+    /// Walk a turmite with states from a TOML or JSON spec instead of --rule
+    #[arg(long)]
+    turmite: Option<PathBuf>,
+
+    /// Walk an absolute turmite instead of --rule, heading the given compass direction
+    /// off each cell color whichever way it looked, e.g. NESW
+    #[arg(long, value_name = "DIRECTIONS", value_parser = TurmiteRule::parse_absolute)]
+    absolute: Option<TurmiteRule>,
+
+    /// Steps per frame to start with
+    #[arg(long, default_value_t = 1)]
+    speed: usize,
+
+    /// Teaching mode: big cells, one step per space press or a second when playing,
+    /// every step explained in words
+    #[arg(long)]
+    lesson: bool,
+}
+
+/// Live view of a single ant in the terminal
+///
+/// Space pauses, `n` steps once and `b` back once while paused, `+`/`-` double or
+/// halve the speed, arrows or `hjkl` pan, `f` follows the ant again, `m` shows or
+/// hides the minimap, `s` saves the view as a PNG, `c` copies it to the clipboard as
+/// text and `q` quits.
+/// In a lesson space takes a single step and `p` plays or pauses instead
+pub fn run(args: WatchArgs) -> ExitCode {
+    let config = match Config::load(args.args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", tr!("invalid-config", error = e));
+            return ExitCode::FAILURE;
+        }
+    };
+    if config.headless {
+        eprintln!("{}", tr!("headless-watch"));
+        return ExitCode::FAILURE;
+    }
+    let rule = match &args.turmite {
+        Some(spec) => match TurmiteRule::load(spec) {
+            Ok(rule) => rule,
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    tr!(
+                        "cant-load-turmite",
+                        file = spec.display().to_string(),
+                        error = e.to_string()
+                    )
+                );
+                return ExitCode::FAILURE;
+            }
+        },
+        None => match (&args.absolute, &config.plugin) {
+            (Some(rule), _) => rule.clone(),
+            (None, Some(lib)) => match load_plugin(lib) {
+                Ok(rule) => rule,
+                Err(e) => {
+                    eprintln!(
+                        "{}",
+                        tr!(
+                            "cant-load-plugin",
+                            file = lib.display().to_string(),
+                            error = e
+                        )
+                    );
+                    return ExitCode::FAILURE;
+                }
+            },
+            (None, None) => config.rule.clone().into(),
+        },
+    };
+
+    let (pos, dir) = config.ants()[0];
+    let (speed, lesson) = (args.speed, args.lesson);
+    let orientation = config.orientation;
+    let Some(result) = with_size!(
+        config.size,
+        watch(pos, dir, rule, speed, lesson, orientation)
+    ) else {
+        return ExitCode::FAILURE;
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => {
+            eprintln!("{}", tr!("cant-spawn", x = pos.x, y = pos.y));
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("{}", tr!("terminal-error", error = e.to_string()));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Raw mode and the alternate screen for as long as it lives, also on panics
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        queue!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        io::stdout().flush()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = queue!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = io::stdout().flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// What the terminal shows, kept up to date from step events only
+struct View {
+    cells: Vec<u8>,
+    size: usize,
+    colors: usize,
+    ant: Option<(Pos, Direction)>,
+    steps: u64,
+    // The board is drawn flipped and turned as --flip and --rotate say, the viewport
+    // is on the board as drawn
+    orientation: ImageOrientation,
+    // Top left cell of the viewport
+    origin: Pos,
+    follow: bool,
+    paused: bool,
+    speed: usize,
+    lesson: bool,
+    minimap: bool,
+    // Cells the viewport showed last time it was drawn
+    shown: (isize, isize),
+    // Said on the status line for a while, like where a screenshot went
+    notice: Option<(String, Instant)>,
+    // What the last step did, in lessons only
+    explanation: String,
+}
+
+impl View {
+    fn apply<const S: usize>(&mut self, event: StepEvent<'_, S, S>) {
+        let cell = event.pos.y() * S + event.pos.x();
+        if self.lesson {
+            self.explanation = self.explain(self.cells[cell], &event);
+        }
+        self.cells[cell] = event.flipped_to;
+        let shift = event.dir.to_shift();
+        let pos = Pos::from(event.pos);
+        self.ant = Some((Pos::new(pos.x + shift.x, pos.y + shift.y), event.dir));
+        self.steps += 1;
+    }
+
+    /// Take back a step the ant walked back from
+    fn undo<const S: usize>(&mut self, event: StepEvent<'_, S, S>) {
+        self.cells[event.pos.y() * S + event.pos.x()] = event.flipped_to;
+        self.ant = Some((event.pos.into(), event.dir));
+        self.steps -= 1;
+        if self.lesson {
+            self.explanation = tr!("lesson-back", step = self.steps + 1);
+        }
+    }
+
+    /// The step about to be applied in words, `color` is the cell's color before it
+    fn explain<const S: usize>(&self, color: u8, event: &StepEvent<'_, S, S>) -> String {
+        let from = self.ant.map_or(event.dir, |(_, dir)| dir);
+        let turn = match event.dir {
+            dir if dir == from => tr!("lesson-turn-none"),
+            dir if dir == from.cw() => tr!("lesson-turn-right"),
+            dir if dir == from.ccw() => tr!("lesson-turn-left"),
+            _ => tr!("lesson-turn-back"),
+        };
+        tr!(
+            "lesson-step",
+            step = self.steps + 1,
+            x = event.pos.x(),
+            y = event.pos.y(),
+            from = i18n::direction(from).to_lowercase(),
+            color = self.color_name(color),
+            turn = turn,
+            painted = self.color_name(event.flipped_to),
+            dir = i18n::direction(event.dir).to_lowercase()
+        )
+    }
+
+    fn color_name(&self, color: u8) -> String {
+        match color as usize {
+            0 => tr!("lesson-white"),
+            c if c + 1 == self.colors => tr!("lesson-black"),
+            c => tr!("lesson-gray", color = c),
+        }
+    }
+
+    /// The ant on screen, where it's drawn and which way it looks there
+    fn shown_ant(&self) -> Option<(Pos, Direction)> {
+        let (pos, dir) = self.ant?;
+        let pos = self.orientation.apply(pos, self.size, self.size);
+        Some((pos, self.orientation.direction(dir)))
+    }
+
+    fn cell(&self, x: isize, y: isize) -> Color {
+        if let Some((ant, _)) = self.shown_ant() {
+            if ant == Pos::new(x, y) {
+                return Color::Red;
+            }
+        }
+        self.shade(x, y)
+    }
+
+    /// Color of the cell drawn at `x`, `y`, whether or not the ant is on it
+    fn shade(&self, x: isize, y: isize) -> Color {
+        match self.darkness(x, y) {
+            Some(darkness) => gray(255 - darkness),
+            None => Color::DarkBlue,
+        }
+    }
+
+    /// How dark the cell drawn at `x`, `y` is from 0 to 255, `None` off the board
+    fn darkness(&self, x: isize, y: isize) -> Option<u8> {
+        let pos = self
+            .orientation
+            .source(Pos::new(x, y), self.size, self.size);
+        let (x, y) = (pos.x, pos.y);
+        let size = self.size as isize;
+        if x < 0 || y < 0 || x >= size || y >= size {
+            return None;
+        }
+
+        let darkest = self.colors.saturating_sub(1).max(1);
+        let c = self.cells[y as usize * self.size + x as usize] as usize;
+        Some((c * 255 / darkest) as u8)
+    }
+
+    /// The whole board in [`MINIMAP`] squares a side, each as dark as a few cells of it
+    /// on average, with the viewport framed in yellow and the ant in red
+    fn minimap(&self, width: isize, height: isize) -> Vec<Color> {
+        let (size, side) = (self.size, MINIMAP as isize);
+        // Cells of the board a square covers
+        let span = size.div_ceil(MINIMAP);
+        let square = |x: isize| (x * side).div_euclid(size as isize);
+        let (left, top) = (square(self.origin.x), square(self.origin.y));
+        let right = square(self.origin.x + width - 1);
+        let bottom = square(self.origin.y + height - 1);
+        let ant = self
+            .shown_ant()
+            .map(|(ant, _)| (square(ant.x), square(ant.y)));
+
+        let mut squares = Vec::with_capacity(MINIMAP * MINIMAP);
+        for y in 0..side {
+            for x in 0..side {
+                let framed = ((x == left || x == right) && (top..=bottom).contains(&y))
+                    || ((y == top || y == bottom) && (left..=right).contains(&x));
+                squares.push(if ant == Some((x, y)) {
+                    Color::Red
+                } else if framed {
+                    Color::Yellow
+                } else {
+                    let (x, y) = (x as usize * size / MINIMAP, y as usize * size / MINIMAP);
+                    gray(255 - self.average_darkness(x, y, span))
+                });
+            }
+        }
+        squares
+    }
+
+    /// Darkness of a `span` cells square from `x`, `y` drawn, from at most 4 by 4 of them
+    fn average_darkness(&self, x: usize, y: usize, span: usize) -> u8 {
+        let stride = span.div_ceil(4);
+        let (mut sum, mut count) = (0, 0);
+        for dy in (0..span).step_by(stride) {
+            for dx in (0..span).step_by(stride) {
+                if let Some(darkness) = self.darkness((x + dx) as isize, (y + dy) as isize) {
+                    sum += darkness as usize;
+                    count += 1;
+                }
+            }
+        }
+        (sum / count.max(1)) as u8
+    }
+
+    /// Keep the ant on screen by jumping the viewport to it once it gets near an edge
+    fn follow_ant(&mut self, width: isize, height: isize) {
+        let Some((ant, _)) = self.shown_ant().filter(|_| self.follow) else {
+            return;
+        };
+        let margin_x = width / 8;
+        let margin_y = height / 8;
+        if ant.x < self.origin.x + margin_x || ant.x >= self.origin.x + width - margin_x {
+            self.origin.x = ant.x - width / 2;
+        }
+        if ant.y < self.origin.y + margin_y || ant.y >= self.origin.y + height - margin_y {
+            self.origin.y = ant.y - height / 2;
+        }
+    }
+
+    /// Every terminal row shows two map rows with `▀`, the upper one as foreground,
+    /// lessons take two columns of a row per cell instead
+    fn draw(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let (columns, rows) = terminal::size()?;
+        queue!(out, cursor::MoveTo(0, 0))?;
+        match self.lesson {
+            true => self.draw_lesson(out, columns, rows)?,
+            false => self.draw_cells(out, columns, rows)?,
+        }
+
+        let state = match (self.ant, self.paused) {
+            (None, _) => tr!("watch-left"),
+            (_, true) => tr!("watch-paused"),
+            (_, false) => tr!("watch-running"),
+        };
+        let following = match self.follow {
+            true => tr!("watch-following"),
+            false => String::new(),
+        };
+        // Fluent trims the leading space of a message
+        let notice = self
+            .notice
+            .as_ref()
+            .filter(|(_, since)| since.elapsed() < NOTICE);
+        let status = match (notice, self.lesson) {
+            (Some((notice, _)), _) => format!(" {notice}"),
+            (None, true) => format!(
+                " {}",
+                tr!(
+                    "lesson-status",
+                    step = self.steps,
+                    state = state,
+                    following = following
+                )
+            ),
+            (None, false) => format!(
+                " {}",
+                tr!(
+                    "watch-status",
+                    step = self.steps,
+                    state = state,
+                    speed = self.speed,
+                    x = self.origin.x,
+                    y = self.origin.y,
+                    following = following
+                )
+            ),
+        };
+        queue!(
+            out,
+            terminal::Clear(terminal::ClearType::CurrentLine),
+            style::Print(status.chars().take(columns as usize).collect::<String>())
+        )?;
+        out.flush()
+    }
+
+    /// The minimap goes in the top right corner when the board doesn't fit and the
+    /// terminal is twice as wide and high as it
+    fn draw_cells(&mut self, out: &mut impl Write, columns: u16, rows: u16) -> io::Result<()> {
+        let width = columns as isize;
+        let height = (rows.saturating_sub(1) as isize) * 2;
+        self.follow_ant(width, height);
+        self.shown = (width, height);
+
+        let side = MINIMAP as isize;
+        let fits = self.size as isize <= width && self.size as isize <= height;
+        let roomy = width >= 2 * side && height >= 2 * side;
+        let minimap = match self.minimap && !fits && roomy {
+            true => self.minimap(width, height),
+            false => Vec::new(),
+        };
+        for row in 0..height / 2 {
+            let y = self.origin.y + row * 2;
+            for (column, x) in (self.origin.x..self.origin.x + width).enumerate() {
+                let square = (column as isize - (width - side), row * 2);
+                let (top, bottom) = match square {
+                    (x, y) if !minimap.is_empty() && x >= 0 && y < side => {
+                        let i = (y * side + x) as usize;
+                        (minimap[i], minimap[i + MINIMAP])
+                    }
+                    _ => (self.cell(x, y), self.cell(x, y + 1)),
+                };
+                queue!(
+                    out,
+                    style::SetForegroundColor(top),
+                    style::SetBackgroundColor(bottom),
+                    style::Print('▀')
+                )?;
+            }
+            queue!(out, style::ResetColor, cursor::MoveToNextLine(1))?;
+        }
+        Ok(())
+    }
+
+    /// Cells two columns wide with the ant as an arrow on red, in the color of the
+    /// cell it stands on, and the last step explained under them
+    fn draw_lesson(&mut self, out: &mut impl Write, columns: u16, rows: u16) -> io::Result<()> {
+        let explanation = wrap(&self.explanation, columns.saturating_sub(2) as usize);
+        let width = columns as isize / 2;
+        let height = rows.saturating_sub(1 + explanation.len() as u16) as isize;
+        self.follow_ant(width, height);
+        self.shown = (width, height);
+
+        for y in self.origin.y..self.origin.y + height {
+            for x in self.origin.x..self.origin.x + width {
+                match self.shown_ant().filter(|(ant, _)| *ant == Pos::new(x, y)) {
+                    Some((_, dir)) => {
+                        let arrow = match dir {
+                            Direction::North => "^^",
+                            Direction::East => ">>",
+                            Direction::South => "vv",
+                            Direction::West => "<<",
+                        };
+                        queue!(
+                            out,
+                            style::SetForegroundColor(self.shade(x, y)),
+                            style::SetBackgroundColor(Color::Red),
+                            style::Print(arrow)
+                        )?;
+                    }
+                    None => queue!(
+                        out,
+                        style::SetBackgroundColor(self.shade(x, y)),
+                        style::Print("  ")
+                    )?,
+                }
+            }
+            queue!(out, style::ResetColor, cursor::MoveToNextLine(1))?;
+        }
+
+        for line in explanation {
+            queue!(
+                out,
+                terminal::Clear(terminal::ClearType::CurrentLine),
+                style::Print(format!(" {line}")),
+                cursor::MoveToNextLine(1)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Handle a key, returns `false` to quit
+    fn key(&mut self, code: KeyCode) -> bool {
+        // Pan by a quarter of a typical terminal
+        let pan = 16;
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Char('+') | KeyCode::Char('=') => self.speed = (self.speed * 2).min(1 << 20),
+            KeyCode::Char('-') => self.speed = (self.speed / 2).max(1),
+            KeyCode::Char('f') => self.follow = true,
+            KeyCode::Char('m') => self.minimap = !self.minimap,
+            KeyCode::Char('s') => self.notice = Some((self.screenshot(), Instant::now())),
+            KeyCode::Char('c') => {
+                let notice = match self.copy() {
+                    Ok(()) => tr!("watch-copied"),
+                    Err(e) => tr!("terminal-error", error = e.to_string()),
+                };
+                self.notice = Some((notice, Instant::now()));
+            }
+            KeyCode::Left | KeyCode::Char('h') => self.pan(-pan, 0),
+            KeyCode::Right | KeyCode::Char('l') => self.pan(pan, 0),
+            KeyCode::Up | KeyCode::Char('k') => self.pan(0, -pan),
+            KeyCode::Down | KeyCode::Char('j') => self.pan(0, pan),
+            _ => {}
+        }
+        true
+    }
+
+    /// The cells in the viewport as drawn, with the ant on them
+    fn viewport(&self) -> Frame {
+        let (width, height) = (self.shown.0.max(0) as usize, self.shown.1.max(0) as usize);
+        let mut cells = Vec::with_capacity(width * height);
+        for y in self.origin.y..self.origin.y + height as isize {
+            for x in self.origin.x..self.origin.x + width as isize {
+                let pos = self
+                    .orientation
+                    .source(Pos::new(x, y), self.size, self.size);
+                let on_board = (0..self.size as isize).contains(&pos.x)
+                    && (0..self.size as isize).contains(&pos.y);
+                cells.push(match on_board {
+                    true => self.cells[pos.y as usize * self.size + pos.x as usize],
+                    false => 0,
+                });
+            }
+        }
+        let ants = self
+            .shown_ant()
+            .map(|(ant, _)| Pos::new(ant.x - self.origin.x, ant.y - self.origin.y));
+        Frame {
+            cells,
+            width,
+            height,
+            colors: self.colors,
+            ants: ants.into_iter().collect(),
+            origin: self.origin,
+        }
+    }
+
+    /// Save the viewport as `watch-<step>.png`, a pixel a cell, and say where it went
+    fn screenshot(&self) -> String {
+        let file = format!("watch-{}.png", self.steps);
+        match PngRenderer::default().save(&self.viewport(), Path::new(&file)) {
+            Ok(()) => tr!("watch-saved", file = file),
+            Err(e) => tr!("watch-cant-save", file = file, error = e.to_string()),
+        }
+    }
+
+    /// Put the viewport on the clipboard as ASCII art with the OSC 52 escape code, which
+    /// most terminals take from programs even over SSH
+    fn copy(&self) -> io::Result<()> {
+        let mut text = Vec::new();
+        RasterFormat::Ascii.render(&self.viewport(), &mut text)?;
+        let mut out = io::stdout();
+        write!(out, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+        out.flush()
+    }
+
+    fn pan(&mut self, dx: isize, dy: isize) {
+        self.follow = false;
+        self.origin = Pos::new(self.origin.x + dx, self.origin.y + dy);
+    }
+}
+
+fn gray(level: u8) -> Color {
+    Color::Rgb {
+        r: level,
+        g: level,
+        b: level,
+    }
+}
+
+/// Split `text` into lines of at most `width` characters at spaces, words longer
+/// than that get a line of their own
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::<String>::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_owned()),
+        }
+    }
+    lines
+}
+
+/// Turmite of the plugin in `lib`, the library is closed again once it's asked
+fn load_plugin(lib: &Path) -> Result<TurmiteRule, String> {
+    // SAFETY: --plugin is trusted to be a plugin like any program it runs
+    let plugin = unsafe { Plugin::load(lib) }.map_err(|e| e.to_string())?;
+    plugin
+        .rule()
+        .cloned()
+        .ok_or_else(|| tr!("plugin-without-turmite", name = plugin.name()))
+}
+
+/// Watch an ant from `pos` until it's quit, `false` if `pos` is off the map
+fn watch<const S: usize>(
+    pos: Pos,
+    dir: Direction,
+    rule: TurmiteRule,
+    speed: usize,
+    lesson: bool,
+    orientation: ImageOrientation,
+) -> io::Result<bool> {
+    let colors = rule.colors();
+    let mut map = ColorMap::<S, S>::new_white();
+    let Ok(mut ant) = Ant::with_rule(&mut map, pos, dir, rule) else {
+        return Ok(false);
+    };
+    if !ant.rule().is_reversible() {
+        ant = ant.with_history(HISTORY);
+    }
+    // The view never touches the map, it replays the events instead
+
+    let mut view = View {
+        cells: vec![0; S * S],
+        size: S,
+        colors,
+        ant: Some((pos, dir)),
+        steps: 0,
+        orientation,
+        origin: orientation.apply(pos, S, S),
+        follow: true,
+        paused: lesson,
+        speed: speed.max(1),
+        lesson,
+        minimap: true,
+        shown: (0, 0),
+        notice: None,
+        explanation: tr!("lesson-start"),
+    };
+
+    let _screen = Screen::enter()?;
+    let mut out = io::BufWriter::new(io::stdout());
+    let mut single_step = false;
+    let mut last_step = Instant::now();
+    let frames = Pacer::new(FRAMES_PER_SECOND, 0);
+
+    loop {
+        let due = !view.lesson || last_step.elapsed() >= LESSON_PACE;
+        if view.ant.is_some() && ((!view.paused && due) || single_step) {
+            let count = if single_step || view.lesson {
+                1
+            } else {
+                view.speed
+            };
+            for _ in 0..count {
+                view.apply(ant.steps().next().expect("ants step even off the map"));
+                if !ant.is_on_map() {
+                    view.ant = None;
+                    break;
+                }
+            }
+            single_step = false;
+            last_step = Instant::now();
+        }
+        view.draw(&mut out)?;
+
+        // Keys are taken as they come, the next frame is drawn when it's due
+        if event::poll(frames.until(frames.due() + 1))? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    if view.lesson && key.code == KeyCode::Char(' ') {
+                        single_step = true;
+                        view.paused = true;
+                    } else if view.lesson && key.code == KeyCode::Char('p') {
+                        view.paused = !view.paused;
+                    } else if key.code == KeyCode::Char('n') {
+                        single_step = view.paused;
+                    } else if key.code == KeyCode::Char('b') {
+                        // Back to the start at most, not into whatever came before it
+                        if view.paused && view.steps > 0 {
+                            if let Some(event) = ant.walk_back() {
+                                view.undo(event);
+                            }
+                        }
+                    } else if !view.key(key.code) {
+                        return Ok(true);
+                    }
+                }
+                Event::Resize(..) => queue!(out, terminal::Clear(terminal::ClearType::All))?,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[test]
+fn views_follow_the_steps_and_keys() {
+    use crate::{Cli, Command};
+    use antventure::CellStore;
+    use clap::Parser;
+
+    let parse = |args: &[&str]| {
+        let args = ["serious", "watch"].iter().chain(args);
+        match Cli::try_parse_from(args).map(|cli| cli.command) {
+            Ok(Some(Command::Watch(args))) => Ok(args),
+            Ok(_) => panic!("not a watch command"),
+            Err(e) => Err(e),
+        }
+    };
+    let args = parse(&["-r", "LLRR", "--size", "256", "--speed", "4", "--lesson"]).unwrap();
+    assert_eq!((args.speed, args.lesson), (4, true));
+    assert!(args.turmite.is_none() && args.absolute.is_none());
+    let config = Config::load(args.args).unwrap();
+    assert_eq!((config.size, config.rule.to_string()), (256, "LLRR".into()));
+    let args = parse(&["--absolute", "NESW"]).unwrap();
+    assert_eq!(args.absolute.unwrap().colors(), 4);
+    assert!(parse(&["--absolute", "NEXW"]).is_err());
+    assert!(parse(&["--speed", "fast"]).is_err());
+    // Nothing to watch without a terminal
+    assert!(run(*parse(&["--headless"]).unwrap()) == ExitCode::FAILURE);
+
+    let (pos, dir) = (Pos::new(8, 8), Direction::North);
+    let rule = TurmiteRule::from("LLRR".parse::<antventure::Rule>().unwrap());
+    let mut map = ColorMap::<16, 16>::new_white();
+    let mut view = View {
+        cells: vec![0; 16 * 16],
+        size: 16,
+        colors: rule.colors(),
+        ant: Some((pos, dir)),
+        steps: 0,
+        orientation: ImageOrientation::default(),
+        origin: Pos::new(4, 4),
+        follow: true,
+        paused: false,
+        speed: 1,
+        lesson: true,
+        minimap: true,
+        shown: (8, 8),
+        notice: None,
+        explanation: String::new(),
+    };
+    let mut ant = Ant::with_rule(&mut map, pos, dir, rule).unwrap();
+    for _ in 0..100 {
+        view.apply(ant.steps().next().unwrap());
+    }
+    let at = (Pos::from(ant.pos()), ant.dir());
+    drop(ant);
+    // The view kept up from the events alone
+    assert_eq!(view.cells, map.to_colors());
+    assert_eq!((view.ant, view.steps), (Some(at), 100));
+    assert!(view.explanation.contains("100"), "{}", view.explanation);
+    let frame = view.viewport();
+    assert_eq!((frame.width, frame.height, frame.cells.len()), (8, 8, 64));
+    assert_eq!(frame.ants, [Pos::new(at.0.x - 4, at.0.y - 4)]);
+
+    assert!(view.key(KeyCode::Char('+')) && view.key(KeyCode::Char('+')));
+    assert!(view.key(KeyCode::Char('-')));
+    assert_eq!(view.speed, 2);
+    assert!(view.key(KeyCode::Char('h')));
+    assert_eq!((view.origin, view.follow), (Pos::new(-12, 4), false));
+    assert!(view.key(KeyCode::Char('f')) && view.follow);
+    assert!(view.key(KeyCode::Char(' ')) && view.paused);
+    assert!(!view.key(KeyCode::Char('q')));
+
+    assert_eq!(wrap("an ant walks on", 6), ["an ant", "walks", "on"]);
+    assert_eq!(wrap("highways", 4), ["highways"]);
+}
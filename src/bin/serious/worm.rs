@@ -0,0 +1,46 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use antventure::{AntventureError, Hex, HexDirection, Worm, WormEnd, WormRule};
+
+use crate::i18n::tr;
+
+#[derive(clap::Args)]
+pub struct WormArgs {
+    /// Turn for each new pattern of eaten edges: L/R turn by 60°, l/r by 120°, N goes straight
+    #[arg(long, short, default_value = "LRrR")]
+    rule: WormRule,
+
+    /// Initial direction: e, ne, nw, w, sw or se
+    #[arg(long, short, default_value = "e")]
+    direction: HexDirection,
+
+    /// Stop after eating this many edges even if the worm is still alive
+    #[arg(long, default_value_t = 1_000_000)]
+    max_steps: usize,
+
+    /// Distance between neighbouring lattice points in pixels
+    #[arg(long, default_value_t = 6)]
+    spacing: usize,
+
+    /// Output PNG
+    #[arg(long, short, default_value = "worm.png")]
+    output: PathBuf,
+}
+
+/// Paterson's worm eating edges of a triangular lattice
+pub fn run(args: WormArgs) -> Result<ExitCode, AntventureError> {
+    let mut worm = Worm::new(Hex::new(0, 0), args.direction, args.rule);
+    worm.walk_at_most(args.max_steps);
+
+    let (steps, genes) = (worm.steps(), worm.genes_used());
+    let message = match worm.end() {
+        None => tr!("worm-eating", steps = steps, genes = genes),
+        Some(WormEnd::Starved) => tr!("worm-starved", steps = steps, genes = genes),
+        Some(WormEnd::Undecided) => tr!("worm-undecided", steps = steps, genes = genes),
+        Some(WormEnd::Blocked) => tr!("worm-blocked", steps = steps, gene = genes + 1),
+    };
+    println!("{message}");
+
+    worm.save(args.spacing, &args.output)?;
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,269 @@
+use std::ops::Range;
+
+use crate::words::{words, Words};
+
+/// Fixed number of bits packed into `u64` words, the first bit in the highest bit
+/// of the first word so the big-endian bytes read left to right
+///
+/// Bits past `len` in the last word are always clear, so counting can go a word
+/// at a time
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct BitSet {
+    words: Words,
+    len: usize,
+}
+
+impl BitSet {
+    /// `len` bits, all set to `value`
+    pub fn filled(len: usize, value: bool) -> Self {
+        let fill = if value { u64::MAX } else { 0 };
+        let mut set = Self {
+            words: words(len.div_ceil(64), fill),
+            len,
+        };
+        set.clear_tail();
+        set
+    }
+
+    /// Clear the bits of the last word past the end, returns how many were set
+    fn clear_tail(&mut self) -> usize {
+        let (Some(last), tail @ 1..) = (self.words.last_mut(), self.len % 64) else {
+            return 0;
+        };
+        let past = *last & u64::MAX >> tail;
+        *last &= !past;
+        past.count_ones() as usize
+    }
+
+    /// Put `op` of each word and the word of `other` in its place, returns how many
+    /// bits are set after. A plain loop over the words the compiler vectorizes, so
+    /// combining gigabit sets goes at memory speed
+    pub fn combine(&mut self, other: &Self, op: impl Fn(u64, u64) -> u64) -> usize {
+        debug_assert_eq!(self.len, other.len, "sets of different lengths");
+        let mut ones = 0;
+        for (a, &b) in self.words.iter_mut().zip(other.words.iter()) {
+            *a = op(*a, b);
+            ones += a.count_ones() as usize;
+        }
+        // `op` may have set them from the clear bits on both sides
+        ones - self.clear_tail()
+    }
+
+    /// Word holding bit `i` and the mask of the bit in it
+    ///
+    /// # Panics
+    ///
+    /// If `i` is out of bounds, in debug builds only when it's in the last word
+    fn locate(&self, i: usize) -> (usize, u64) {
+        debug_assert!(i < self.len, "bit {i} of {}", self.len);
+        (i / 64, 1 << (63 - i % 64))
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        let (word, mask) = self.locate(i);
+        self.words[word] & mask != 0
+    }
+
+    pub fn set(&mut self, i: usize, value: bool) {
+        let (word, mask) = self.locate(i);
+        match value {
+            true => self.words[word] |= mask,
+            false => self.words[word] &= !mask,
+        }
+    }
+
+    /// Word holding bit `i` and the mask of the bit in it, to change it in place
+    pub fn word_mut(&mut self, i: usize) -> (&mut u64, u64) {
+        let (word, mask) = self.locate(i);
+        (&mut self.words[word], mask)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Set bits in `range`, a word at a time
+    pub fn count_ones_in(&self, range: Range<usize>) -> usize {
+        if range.is_empty() {
+            return 0;
+        }
+        debug_assert!(range.end <= self.len, "bits {range:?} of {}", self.len);
+        let (first, last) = (range.start / 64, (range.end - 1) / 64);
+        // Bits of the first word from the start on and of the last word up to the end
+        let head = u64::MAX >> (range.start % 64);
+        let tail = u64::MAX << (63 - (range.end - 1) % 64);
+        match first == last {
+            true => (self.words[first] & head & tail).count_ones() as usize,
+            false => {
+                let middle = self.words[first + 1..last]
+                    .iter()
+                    .map(|word| word.count_ones() as usize)
+                    .sum::<usize>();
+                (self.words[first] & head).count_ones() as usize
+                    + middle
+                    + (self.words[last] & tail).count_ones() as usize
+            }
+        }
+    }
+
+    /// `len` bits from bit `start`, up to 64, the first in the highest bit and the
+    /// ones past `len` clear
+    pub fn bits(&self, start: usize, len: usize) -> u64 {
+        debug_assert!(len <= 64 && start + len <= self.len, "bits {start}+{len}");
+        if len == 0 {
+            return 0;
+        }
+        let (word, offset) = (start / 64, start % 64);
+        let mut bits = self.words[word] << offset;
+        if offset > 0 && offset + len > 64 {
+            bits |= self.words[word + 1] >> (64 - offset);
+        }
+        bits & !(u64::MAX.checked_shr(len as u32).unwrap_or(0))
+    }
+
+    /// Ask the cache for the word of bit `index` ahead of reading it, nothing past the
+    /// end. Only x86-64 has a stable prefetch, elsewhere it does nothing
+    #[inline]
+    pub fn prefetch(&self, index: usize) {
+        #[cfg(target_arch = "x86_64")]
+        if let Some(word) = self.words.get(index / 64) {
+            use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+            // SAFETY: a prefetch never faults or changes memory, and every x86-64 has SSE
+            unsafe { _mm_prefetch::<_MM_HINT_T0>((word as *const u64).cast()) }
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        let _ = index;
+    }
+
+    /// Flip every bit in `range`, a word at a time
+    pub fn flip_range(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        debug_assert!(range.end <= self.len, "bits {range:?} of {}", self.len);
+        let (first, last) = (range.start / 64, (range.end - 1) / 64);
+        let head = u64::MAX >> (range.start % 64);
+        let tail = u64::MAX << (63 - (range.end - 1) % 64);
+        match first == last {
+            true => self.words[first] ^= head & tail,
+            false => {
+                self.words[first] ^= head;
+                for word in &mut self.words[first + 1..last] {
+                    *word = !*word;
+                }
+                self.words[last] ^= tail;
+            }
+        }
+    }
+
+    /// Set every bit of `bits` to its value. Bits in a row that share a word go into it
+    /// at once. Returns how many more bits are set than before
+    pub fn set_many(&mut self, bits: impl IntoIterator<Item = (usize, bool)>) -> isize {
+        // Word being filled in, with the bits to set and to clear in it
+        let mut pending = None;
+        let mut gained = 0;
+        for (i, value) in bits {
+            let (word, mask) = self.locate(i);
+            let (at, set, clear) = match pending {
+                Some((at, set, clear)) if at == word => (at, set, clear),
+                _ => {
+                    if let Some(pending) = pending {
+                        gained += self.apply(pending);
+                    }
+                    (word, 0, 0)
+                }
+            };
+            pending = Some(match value {
+                true => (at, set | mask, clear & !mask),
+                false => (at, set & !mask, clear | mask),
+            });
+        }
+        if let Some(pending) = pending {
+            gained += self.apply(pending);
+        }
+        gained
+    }
+
+    fn apply(&mut self, (word, set, clear): (usize, u64, u64)) -> isize {
+        let word = &mut self.words[word];
+        let before = word.count_ones() as isize;
+        *word = (*word | set) & !clear;
+        word.count_ones() as isize - before
+    }
+
+    /// Packed bits, 8 per byte with the first bit in the highest one
+    #[cfg(feature = "io")]
+    pub fn bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.words
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .take(self.len.div_ceil(8))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(|i| self.get(i))
+    }
+}
+
+#[test]
+fn bits_pack_into_words() {
+    let mut set = BitSet::filled(100, true);
+    assert_eq!(set.count_ones(), 100);
+    #[cfg(feature = "io")]
+    assert_eq!(set.bytes().count(), 13);
+    #[cfg(feature = "io")]
+    assert_eq!(set.bytes().last(), Some(0xF0));
+
+    set.set(0, false);
+    set.set(99, false);
+    let (word, mask) = set.word_mut(64);
+    *word ^= mask;
+    assert_eq!(set.count_ones(), 97);
+    assert!(!set.get(0) && set.get(1) && !set.get(64) && !set.get(99));
+    #[cfg(feature = "io")]
+    assert_eq!(set.bytes().next(), Some(0x7F));
+    assert_eq!(set.iter().filter(|&bit| !bit).count(), 3);
+    assert_eq!(set.count_ones_in(0..100), 97);
+    assert_eq!(set.count_ones_in(1..64), 63);
+    assert_eq!(set.count_ones_in(60..99), 38);
+    assert_eq!(set.count_ones_in(5..5), 0);
+
+    set.flip_range(60..100);
+    assert_eq!(set.count_ones_in(60..100), 2);
+    assert!(set.get(64) && !set.get(65) && set.get(99) && set.get(59));
+    set.flip_range(60..100);
+    assert_eq!(set.count_ones(), 97);
+    assert_eq!(
+        set.set_many([(0, true), (1, false), (2, false), (70, false)]),
+        -2
+    );
+    assert!(set.get(0) && !set.get(1) && !set.get(2) && !set.get(70));
+    assert_eq!(set.set_many([(1, true), (1, false)]), 0);
+    assert_eq!(set.bits(0, 4), 0b1001 << 60);
+    assert_eq!(set.bits(60, 8), 0b1111_0111 << 56);
+    assert_eq!(set.bits(36, 64), !(1 << 35 | 1 << 29 | 1));
+    assert_eq!(set.bits(99, 0), 0);
+
+    let mut other = BitSet::filled(100, true);
+    other.flip_range(0..50);
+    let mut xor = set.clone();
+    // Bits 1 and 2 clear on both sides, 47 set on both, and none past the end
+    assert_eq!(xor.combine(&other, |a, b| !(a ^ b)), 49);
+    assert_eq!(xor.count_ones(), 49);
+    assert_eq!(set.clone().combine(&other, |a, b| a & b), 47);
+    assert_eq!(set.clone().combine(&other, |a, b| a | b), 98);
+
+    let empty = BitSet::filled(64, false);
+    assert_eq!((empty.count_ones(), empty.len()), (0, 64));
+}
@@ -0,0 +1,203 @@
+use crate::{
+    map::clip, CellStore, Crop, EventKind, EventKinds, MapPos, Observer, Pos, SimEvent, Simulation,
+};
+
+/// Cells on a side of a [`BlockCounts`] block
+pub const BLOCK_SIZE: usize = 16;
+
+/// How many cells of every 16x16 block of a map aren't white, kept up to date flip by
+/// flip as an observer, so coarse pictures and counts over regions of a huge board
+/// don't have to go over all of its cells again
+///
+/// It starts from the map the simulation is made with. Cells repainted through
+/// [`Simulation::map_mut`] go unseen, automaton generations and rule changes are
+/// counted over again
+///
+/// ```
+/// use antventure::{BlockCounts, Crop, Direction, Map, Pos, Simulation};
+///
+/// let map = Map::<256, 256>::new_white();
+/// let counts = BlockCounts::new(&map);
+/// let mut sim = Simulation::new(map, Default::default()).with_observer(counts);
+/// sim.spawn(Pos::new(128, 128), Direction::North).unwrap();
+/// sim.run_at_most(10_000);
+///
+/// let counts = sim.observer::<BlockCounts<256, 256>>().unwrap();
+/// assert_eq!(counts.colored(), sim.map().black_count());
+/// let left = Crop { min: Pos::new(0, 0), max: Pos::new(127, 255) };
+/// assert_eq!(counts.colored_in(sim.map(), left), sim.map().black_cells_in(left));
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BlockCounts<const W: usize, const H: usize> {
+    counts: Vec<u16>,
+    colored: usize,
+    /// An automaton generation repainted cells without flips, counted over at the
+    /// next step
+    stale: bool,
+}
+
+impl<const W: usize, const H: usize> BlockCounts<W, H> {
+    /// Blocks in a row, the ones at the right edge cut short by the map
+    pub const WIDTH: usize = W.div_ceil(BLOCK_SIZE);
+    pub const HEIGHT: usize = H.div_ceil(BLOCK_SIZE);
+
+    pub fn new(map: &impl CellStore<W, H>) -> Self {
+        let mut counts = Self {
+            counts: vec![0; Self::WIDTH * Self::HEIGHT],
+            colored: 0,
+            stale: false,
+        };
+        counts.recount(map);
+        counts
+    }
+
+    /// Colored cells of every block in row-major order
+    pub fn counts(&self) -> &[u16] {
+        &self.counts
+    }
+
+    /// Colored cells of the block `x` blocks from the left and `y` from the top
+    pub fn get(&self, x: usize, y: usize) -> u16 {
+        self.counts[y * Self::WIDTH + x]
+    }
+
+    /// Cells of any color but white, on the whole map
+    pub fn colored(&self) -> usize {
+        self.colored
+    }
+
+    /// Cells of any color but white in `region`, the part of it past the edges left
+    /// out. Blocks inside it are taken as counted, only the cells of blocks its edges
+    /// cut through are looked at on `map`
+    pub fn colored_in(&self, map: &impl CellStore<W, H>, region: Crop) -> usize {
+        let (xs, ys) = clip::<W, H>(region);
+        if xs.is_empty() || ys.is_empty() {
+            return 0;
+        }
+        let mut colored = 0;
+        for by in ys.start / BLOCK_SIZE..ys.end.div_ceil(BLOCK_SIZE) {
+            let rows = (by * BLOCK_SIZE).max(ys.start)..((by + 1) * BLOCK_SIZE).min(ys.end);
+            let whole_rows = rows.len() == BLOCK_SIZE.min(H - by * BLOCK_SIZE);
+            for bx in xs.start / BLOCK_SIZE..xs.end.div_ceil(BLOCK_SIZE) {
+                let columns = (bx * BLOCK_SIZE).max(xs.start)..((bx + 1) * BLOCK_SIZE).min(xs.end);
+                if whole_rows && columns.len() == BLOCK_SIZE.min(W - bx * BLOCK_SIZE) {
+                    colored += self.get(bx, by) as usize;
+                    continue;
+                }
+                for y in rows.clone() {
+                    colored += columns
+                        .clone()
+                        .filter(|&x| map.color(at(x, y)) != 0)
+                        .count();
+                }
+            }
+        }
+        colored
+    }
+
+    fn recount(&mut self, map: &impl CellStore<W, H>) {
+        self.counts.fill(0);
+        let (width, height) = map.size();
+        for y in 0..height {
+            for x in 0..width {
+                if map.color(at(x, y)) != 0 {
+                    self.counts[y / BLOCK_SIZE * Self::WIDTH + x / BLOCK_SIZE] += 1;
+                }
+            }
+        }
+        self.colored = self.counts.iter().map(|&count| count as usize).sum();
+        self.stale = false;
+    }
+}
+
+fn at<const W: usize, const H: usize>(x: usize, y: usize) -> MapPos<'static, W, H> {
+    MapPos::validate_pos(Pos::new(x as _, y as _)).expect("x and y are within the map")
+}
+
+impl<const W: usize, const H: usize, M: CellStore<W, H>> Observer<W, H, M> for BlockCounts<W, H> {
+    fn on_step(&mut self, sim: &Simulation<W, H, M>) {
+        if self.stale {
+            self.recount(sim.map());
+        }
+    }
+
+    fn on_cell_flip(&mut self, _ant: usize, pos: MapPos<'static, W, H>, from: u8, to: u8) {
+        let (x, y) = (pos.x(), pos.y());
+        let count = &mut self.counts[y / BLOCK_SIZE * Self::WIDTH + x / BLOCK_SIZE];
+        match (from == 0, to == 0) {
+            (true, false) => {
+                *count += 1;
+                self.colored += 1;
+            }
+            (false, true) => {
+                *count -= 1;
+                self.colored -= 1;
+            }
+            _ => {}
+        }
+    }
+
+    fn on_rule_change(&mut self, sim: &Simulation<W, H, M>) {
+        // Cells in colors the new rule doesn't have were repainted
+        self.recount(sim.map());
+    }
+
+    fn subscriptions(&self) -> EventKinds {
+        EventKind::Generation.into()
+    }
+
+    fn on_event(&mut self, event: &SimEvent) {
+        if let SimEvent::Generation { .. } = event {
+            self.stale = true;
+        }
+    }
+}
+
+#[test]
+fn blocks_follow_every_flip() {
+    use crate::{ColorMap, Direction, LifeRule, Map, Rule};
+
+    let white = ColorMap::<50, 40>::new_white();
+    let mut sim = Simulation::new(white.clone(), "LLRR".parse().unwrap())
+        .with_observer(BlockCounts::new(&white));
+    sim.spawn(Pos::new(25, 20), Direction::North).unwrap();
+    sim.run_at_most(3000);
+    let counts = sim.observer::<BlockCounts<50, 40>>().unwrap();
+    assert_eq!(counts, &BlockCounts::new(sim.map()));
+    assert_eq!(counts.colored(), sim.map().count_colored());
+    assert_eq!(counts.counts().len(), 4 * 3);
+
+    let white = Map::<50, 40>::new_white();
+    let mut sim =
+        Simulation::new(white.clone(), Rule::default()).with_observer(BlockCounts::new(&white));
+    sim.spawn(Pos::new(25, 20), Direction::North).unwrap();
+    sim.run_at_most(3000);
+    let counts = sim.observer::<BlockCounts<50, 40>>().unwrap();
+    let corners = [
+        ((0, 0), (49, 39)),
+        ((3, 5), (40, 17)),
+        ((16, 16), (31, 31)),
+        ((-5, 30), (70, 90)),
+        ((9, 9), (8, 8)),
+    ];
+    for ((x0, y0), (x1, y1)) in corners {
+        let region = Crop {
+            min: Pos::new(x0, y0),
+            max: Pos::new(x1, y1),
+        };
+        let black = sim.map().black_cells_in(region);
+        assert_eq!(counts.colored_in(sim.map(), region), black, "{region:?}");
+    }
+
+    // Generations and new rules repaint cells without flips
+    let white = Map::<40, 40>::new_white();
+    let mut sim = Simulation::new(white.clone(), Rule::default())
+        .with_automaton(LifeRule::life(), 50)
+        .with_observer(BlockCounts::new(&white));
+    sim.spawn(Pos::new(20, 20), Direction::North).unwrap();
+    sim.run_at_most(500);
+    sim.change_rule("RL".parse().unwrap());
+    sim.run_at_most(20);
+    let counts = sim.observer::<BlockCounts<40, 40>>().unwrap();
+    assert_eq!(counts, &BlockCounts::new(sim.map()));
+}
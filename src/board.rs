@@ -0,0 +1,75 @@
+#[cfg(feature = "wasm")]
+use crate::Frame;
+use crate::{AntventureError, ColorMap, Direction, Pos, Rule, Simulation};
+
+/// A square simulation of any of the sizes of [`new_board`], for bindings to hold
+/// without knowing which one, the size is part of a simulation's type
+pub(crate) trait Board {
+    fn spawn(&mut self, pos: Pos, dir: Direction) -> Result<usize, AntventureError>;
+    fn run_at_most(&mut self, steps: u64) -> bool;
+    fn steps(&self) -> u64;
+    fn colors(&self) -> usize;
+    /// Color of every cell in row-major order
+    #[cfg(any(feature = "ffi", feature = "python"))]
+    fn cells(&self) -> &[u8];
+    /// Ants still on the map
+    #[cfg(any(feature = "wasm", feature = "python"))]
+    fn ants(&self) -> Vec<Pos>;
+    #[cfg(feature = "wasm")]
+    fn frame(&self) -> Frame;
+}
+
+impl<const S: usize> Board for Simulation<S, S, ColorMap<S, S>> {
+    fn spawn(&mut self, pos: Pos, dir: Direction) -> Result<usize, AntventureError> {
+        Simulation::spawn(self, pos, dir)
+    }
+
+    fn run_at_most(&mut self, steps: u64) -> bool {
+        Simulation::run_at_most(self, steps)
+    }
+
+    fn steps(&self) -> u64 {
+        Simulation::steps(self)
+    }
+
+    fn colors(&self) -> usize {
+        self.rule().colors()
+    }
+
+    #[cfg(any(feature = "ffi", feature = "python"))]
+    fn cells(&self) -> &[u8] {
+        self.map().colors()
+    }
+
+    #[cfg(any(feature = "wasm", feature = "python"))]
+    fn ants(&self) -> Vec<Pos> {
+        Simulation::ants(self)
+            .iter()
+            .filter(|ant| ant.is_on_map())
+            .map(|ant| ant.pos().into())
+            .collect()
+    }
+
+    #[cfg(feature = "wasm")]
+    fn frame(&self) -> Frame {
+        Frame::new(self.map(), self.colors(), None).with_ants(&Board::ants(self))
+    }
+}
+
+/// Empty white board of `size` cells a side walked by `rule`, `None` unless the size
+/// is 64, 128, 256, 512, 1024, 2048 or 4096
+pub(crate) fn new_board(size: usize, rule: Rule) -> Option<Box<dyn Board>> {
+    fn boxed<const S: usize>(rule: Rule) -> Box<dyn Board> {
+        Box::new(Simulation::new(ColorMap::<S, S>::new_white(), rule))
+    }
+    Some(match size {
+        64 => boxed::<64>(rule),
+        128 => boxed::<128>(rule),
+        256 => boxed::<256>(rule),
+        512 => boxed::<512>(rule),
+        1024 => boxed::<1024>(rule),
+        2048 => boxed::<2048>(rule),
+        4096 => boxed::<4096>(rule),
+        _ => return None,
+    })
+}
@@ -0,0 +1,194 @@
+use std::any::Any;
+#[cfg(feature = "io")]
+use std::{fs::File, io::BufWriter, path::Path};
+
+#[cfg(feature = "io")]
+use png::{BitDepth, ColorType, Encoder, EncodingError};
+
+use crate::MapPos;
+
+/// Value type of a [`CellChannel`]
+pub trait ChannelValue: Copy + Default + Ord + Into<u32> + 'static {
+    /// Bits per pixel of the rendered channel
+    #[cfg(feature = "io")]
+    const DEPTH: BitDepth;
+    const MAX: Self;
+
+    /// Big-endian pixel bytes of `value`
+    fn pixel(value: u32) -> Vec<u8>;
+}
+
+impl ChannelValue for u8 {
+    #[cfg(feature = "io")]
+    const DEPTH: BitDepth = BitDepth::Eight;
+    const MAX: Self = u8::MAX;
+
+    fn pixel(value: u32) -> Vec<u8> {
+        vec![value as u8]
+    }
+}
+
+impl ChannelValue for u16 {
+    #[cfg(feature = "io")]
+    const DEPTH: BitDepth = BitDepth::Sixteen;
+    const MAX: Self = u16::MAX;
+
+    fn pixel(value: u32) -> Vec<u8> {
+        (value as u16).to_be_bytes().to_vec()
+    }
+}
+
+/// An ant stepping off a cell, what a [`CellChannel`] update sees
+#[derive(Clone, Copy)]
+pub struct CellVisit<const W: usize, const H: usize> {
+    pub pos: MapPos<'static, W, H>,
+    /// Color the ant read, before repainting
+    pub color: u8,
+    /// Index of the ant
+    pub ant: usize,
+    /// Tick the step belongs to, counting from 0
+    pub step: u64,
+}
+
+/// Update callback that can be cloned along with its channel
+trait Update<T, const W: usize, const H: usize> {
+    fn call(&mut self, value: &mut T, visit: CellVisit<W, H>);
+    fn clone_box(&self) -> Box<dyn Update<T, W, H>>;
+}
+
+impl<T, F, const W: usize, const H: usize> Update<T, W, H> for F
+where
+    F: FnMut(&mut T, CellVisit<W, H>) + Clone + 'static,
+{
+    fn call(&mut self, value: &mut T, visit: CellVisit<W, H>) {
+        self(value, visit)
+    }
+
+    fn clone_box(&self) -> Box<dyn Update<T, W, H>> {
+        Box::new(self.clone())
+    }
+}
+
+/// A value of its own for every cell of a map, changed by a callback on every visit
+///
+/// Keeps experiments like "steps since the last visit" next to the map instead of in it:
+///
+/// ```
+/// use antventure::{CellChannel, Direction, Map, Pos, Rule, Simulation};
+///
+/// // Tick of the last visit, saturating at u16::MAX
+/// let last = CellChannel::<64, 64, u16>::new(|value, visit| {
+///     *value = visit.step.min(u16::MAX as u64) as u16
+/// });
+/// let mut sim = Simulation::new(Map::<64, 64>::new_white(), Rule::default()).with_channel(last);
+/// sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+/// sim.run_at_most(100);
+/// assert_eq!(sim.channel::<u16>().unwrap().max(), 99);
+/// ```
+pub struct CellChannel<const W: usize, const H: usize, T: ChannelValue = u8> {
+    values: Vec<T>,
+    update: Box<dyn Update<T, W, H>>,
+}
+
+impl<const W: usize, const H: usize, T: ChannelValue> CellChannel<W, H, T> {
+    /// Channel of default values, `update` changes the value of every cell visited
+    ///
+    /// The callback is cloned along with the channel, see [`Simulation::fork`](crate::Simulation::fork)
+    pub fn new(update: impl FnMut(&mut T, CellVisit<W, H>) + Clone + 'static) -> Self {
+        Self {
+            values: vec![T::default(); W * H],
+            update: Box::new(update),
+        }
+    }
+
+    pub fn visit(&mut self, visit: CellVisit<W, H>) {
+        self.update.call(&mut self.values[visit.pos.index()], visit);
+    }
+
+    pub fn get(&self, pos: MapPos<'_, W, H>) -> T {
+        self.values[pos.index()]
+    }
+
+    /// Values in row-major order
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    pub fn max(&self) -> T {
+        self.values.iter().copied().max().unwrap_or_default()
+    }
+
+    /// Save as a grayscale PNG of the value's bit depth, from white for the default
+    /// value to black for the largest one
+    #[cfg(feature = "io")]
+    pub fn save(&self, file: impl AsRef<Path>) -> Result<(), EncodingError> {
+        let top = self.max().into().max(1) as u64;
+        let full = T::MAX.into() as u64;
+        let data = self
+            .values
+            .iter()
+            .flat_map(|&v| T::pixel((full - v.into() as u64 * full / top) as u32))
+            .collect::<Vec<_>>();
+
+        let w = BufWriter::new(File::create(file)?);
+        let mut encoder = Encoder::new(w, W as _, H as _);
+        encoder.set_color(ColorType::Grayscale);
+        encoder.set_depth(T::DEPTH);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)
+    }
+}
+
+impl<const W: usize, const H: usize, T: ChannelValue> Clone for CellChannel<W, H, T> {
+    fn clone(&self) -> Self {
+        Self {
+            values: self.values.clone(),
+            update: self.update.clone_box(),
+        }
+    }
+}
+
+/// A [`CellChannel`] of any value type, so a simulation can hold one without
+/// carrying the type around
+pub(crate) trait AnyChannel<const W: usize, const H: usize> {
+    fn visit(&mut self, visit: CellVisit<W, H>);
+    fn as_any(&self) -> &dyn Any;
+    fn clone_box(&self) -> Box<dyn AnyChannel<W, H>>;
+}
+
+impl<const W: usize, const H: usize, T: ChannelValue> AnyChannel<W, H> for CellChannel<W, H, T> {
+    fn visit(&mut self, visit: CellVisit<W, H>) {
+        CellChannel::visit(self, visit)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn AnyChannel<W, H>> {
+        Box::new(self.clone())
+    }
+}
+
+#[test]
+fn channel_tracks_visits() {
+    use crate::{Direction, Map, Pos, Rule, Simulation};
+
+    let colors = CellChannel::<16, 16>::new(|value, visit| *value = visit.color + 1);
+    let mut sim = Simulation::new(Map::<16, 16>::new_white(), Rule::default()).with_channel(colors);
+    sim.spawn(Pos::new(8, 8), Direction::North).unwrap();
+    sim.run_at_most(40);
+
+    assert!(sim.channel::<u16>().is_none());
+    let channel = sim.channel::<u8>().unwrap();
+    // Cells left black were last stepped off while white, and the other way around
+    for (i, &value) in channel.values().iter().enumerate() {
+        let pos = MapPos::validate_pos(Pos::new((i % 16) as _, (i / 16) as _)).unwrap();
+        match value {
+            0 => {}
+            1 => assert!(!sim.map().get(pos)),
+            _ => assert!(sim.map().get(pos)),
+        }
+    }
+    assert_eq!(channel.max(), 2);
+}
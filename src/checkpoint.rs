@@ -0,0 +1,381 @@
+use std::{
+    cmp::Reverse,
+    error::Error,
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    formats::{STATE_MAGIC, STATE_VERSION},
+    state::{self, SEMANTICS_VERSION},
+    CellStore, ConflictPolicy, Direction, ParseRuleError, Rule,
+};
+
+/// Everything a [`Simulation`](crate::Simulation) needs to carry on where it stopped
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub width: usize,
+    pub height: usize,
+    pub(crate) rule: String,
+    pub(crate) conflict: ConflictPolicy,
+    /// Cell colors in row-major order, packed 8 per byte for two-color rules
+    pub(crate) cells: Vec<u8>,
+    pub(crate) ants: Vec<AntCheckpoint>,
+    pub steps: u64,
+    /// Stochastic rule and how far its dice rolled, `rule` is its likely one then
+    pub(crate) noise: Option<NoiseCheckpoint>,
+    /// [`SEMANTICS_VERSION`] of the release that took it, the serde encoding had none
+    #[serde(skip, default = "first_semantics")]
+    pub(crate) semantics: u16,
+}
+
+fn first_semantics() -> u16 {
+    1
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct AntCheckpoint {
+    pub x: usize,
+    pub y: usize,
+    pub dir: Direction,
+    pub on_map: bool,
+    pub mirrored: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct NoiseCheckpoint {
+    pub rule: String,
+    pub seed: u64,
+    pub word_pos: u128,
+}
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    Encoding(bincode::Error),
+    Rule(ParseRuleError),
+    /// The checkpoint was taken on a map of another size
+    Size(usize, usize),
+    /// Cells or ants don't fit the map
+    Corrupted,
+    /// The state doesn't add up to its checksum
+    Checksum,
+    /// The state is of a newer version of the format
+    Version(u16),
+    /// The checkpoint was taken by a release whose steps walk differently
+    Semantics(u16),
+}
+
+impl Display for CheckpointError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckpointError::Io(e) => write!(f, "{e}"),
+            CheckpointError::Encoding(e) => write!(f, "bad checkpoint: {e}"),
+            CheckpointError::Rule(e) => write!(f, "bad checkpoint rule: {e}"),
+            CheckpointError::Size(w, h) => write!(f, "checkpoint is for a {w}x{h} map"),
+            CheckpointError::Corrupted => write!(f, "checkpoint doesn't match its map size"),
+            CheckpointError::Checksum => write!(f, "checkpoint doesn't match its checksum"),
+            CheckpointError::Version(version) => write!(
+                f,
+                "checkpoint is of format version {version}, expected up to {STATE_VERSION}"
+            ),
+            CheckpointError::Semantics(semantics) => write!(
+                f,
+                "checkpoint was taken with step semantics {semantics}, this release steps \
+                 with {SEMANTICS_VERSION} and wouldn't carry on the same run"
+            ),
+        }
+    }
+}
+
+impl Error for CheckpointError {}
+
+impl From<io::Error> for CheckpointError {
+    fn from(e: io::Error) -> Self {
+        CheckpointError::Io(e)
+    }
+}
+
+impl From<bincode::Error> for CheckpointError {
+    fn from(e: bincode::Error) -> Self {
+        CheckpointError::Encoding(e)
+    }
+}
+
+impl Checkpoint {
+    /// Read an `.antv` state, or a checkpoint of the serde encoding older versions saved
+    pub fn load(file: impl AsRef<Path>) -> Result<Self, CheckpointError> {
+        let bytes = fs::read(file)?;
+        match bytes.starts_with(STATE_MAGIC) {
+            true => state::decode(&bytes),
+            // Lengths inside can't ask for more than the file holds, so a broken one
+            // fails instead of allocating whatever it claims
+            false => Ok(bincode::options()
+                .with_fixint_encoding()
+                .allow_trailing_bytes()
+                .with_limit(bytes.len() as u64)
+                .deserialize(&bytes)?),
+        }
+    }
+
+    /// Save as an `.antv` state, see [`save_state`](crate::save_state)
+    pub fn save(&self, file: impl AsRef<Path>) -> Result<(), CheckpointError> {
+        state::save_state(self, file)
+    }
+
+    /// [`SEMANTICS_VERSION`] of the release that took the checkpoint
+    pub fn semantics(&self) -> u16 {
+        self.semantics
+    }
+
+    pub fn rule(&self) -> Result<Rule, CheckpointError> {
+        self.rule.parse().map_err(CheckpointError::Rule)
+    }
+
+    pub(crate) fn pack_cells<const W: usize, const H: usize>(
+        map: &impl CellStore<W, H>,
+        colors: usize,
+    ) -> Vec<u8> {
+        let cells = map.to_colors();
+        match colors {
+            ..=2 => cells
+                .chunks(8)
+                .map(|bits| bits.iter().fold(0, |byte, &c| byte << 1 | c) << (8 - bits.len()))
+                .collect(),
+            _ => cells,
+        }
+    }
+
+    pub(crate) fn unpack_cells(&self, colors: usize) -> Result<Vec<u8>, CheckpointError> {
+        let len = self.width * self.height;
+        let cells = match colors {
+            ..=2 => self
+                .cells
+                .iter()
+                .flat_map(|byte| (0..8).rev().map(move |i| byte >> i & 1))
+                .take(len)
+                .collect::<Vec<_>>(),
+            _ => self.cells.clone(),
+        };
+
+        match cells.len() == len && cells.iter().all(|&c| (c as usize) < colors) {
+            true => Ok(cells),
+            false => Err(CheckpointError::Corrupted),
+        }
+    }
+}
+
+/// Which checkpoints of a [`CheckpointSeries`] stay on disk, with nothing set every one does
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Retention {
+    /// Keep this many of the newest checkpoints
+    pub keep_last: Option<usize>,
+    /// Keep the oldest checkpoint of every power of two steps back from the newest one,
+    /// so a long run keeps a handful of them spread over its whole history
+    pub exponential: bool,
+    /// Delete the oldest checkpoints until the series takes at most this many bytes,
+    /// the newest one always stays
+    pub max_bytes: Option<u64>,
+}
+
+impl Retention {
+    /// Whether any limit is set
+    pub fn is_set(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// Steps of the checkpoints to delete out of `checkpoints`, given as steps and file sizes
+    pub fn expired(&self, checkpoints: &[(u64, u64)]) -> Vec<u64> {
+        let mut newest_first = checkpoints.to_vec();
+        newest_first.sort_unstable_by_key(|&(steps, _)| Reverse(steps));
+        let Some(&(newest, _)) = newest_first.first() else {
+            return Vec::new();
+        };
+
+        let limited = self.keep_last.is_some() || self.exponential;
+        let mut kept = (0..newest_first.len())
+            .map(|i| i == 0 || !limited || self.keep_last.is_some_and(|n| i < n))
+            .collect::<Vec<_>>();
+        if self.exponential {
+            // Ages 0, 1, 2..3, 4..7 and so on share a bucket. The oldest checkpoint of
+            // a bucket stays, so the first one of the run is never lost
+            let mut buckets = Vec::new();
+            for (i, &(steps, _)) in newest_first.iter().enumerate().rev() {
+                let bucket = u64::BITS - (newest - steps).leading_zeros();
+                if !buckets.contains(&bucket) {
+                    buckets.push(bucket);
+                    kept[i] = true;
+                }
+            }
+        }
+
+        if let Some(max_bytes) = self.max_bytes {
+            let mut total = newest_first
+                .iter()
+                .zip(&kept)
+                .filter(|(_, &kept)| kept)
+                .map(|(&(_, bytes), _)| bytes)
+                .sum::<u64>();
+            for i in (1..newest_first.len()).rev() {
+                if total <= max_bytes {
+                    break;
+                }
+                if kept[i] {
+                    kept[i] = false;
+                    total -= newest_first[i].1;
+                }
+            }
+        }
+
+        newest_first
+            .iter()
+            .zip(kept)
+            .filter(|(_, kept)| !kept)
+            .map(|(&(steps, _), _)| steps)
+            .collect()
+    }
+}
+
+/// Checkpoints of one run saved side by side as `<name>-<steps>.<extension>`
+/// and pruned by a [`Retention`] after every save
+pub struct CheckpointSeries {
+    path: PathBuf,
+    retention: Retention,
+}
+
+impl CheckpointSeries {
+    /// Series named after `path`, `ant.bin` gives `ant-1000.bin`, `ant-2000.bin`...
+    pub fn new(path: impl Into<PathBuf>, retention: Retention) -> Self {
+        Self {
+            path: path.into(),
+            retention,
+        }
+    }
+
+    /// File of the checkpoint taken after `steps` steps
+    pub fn path_for(&self, steps: u64) -> PathBuf {
+        let stem = self.path.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match self.path.extension() {
+            Some(ext) => format!("{stem}-{steps}.{}", ext.to_string_lossy()),
+            None => format!("{stem}-{steps}"),
+        };
+        self.path.with_file_name(name)
+    }
+
+    /// Checkpoints of the series on disk as steps, files and sizes, oldest first
+    pub fn list(&self) -> io::Result<Vec<(u64, PathBuf, u64)>> {
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let stem = self.path.file_stem().unwrap_or_default().to_string_lossy();
+        let prefix = format!("{stem}-");
+
+        let mut checkpoints = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension() != self.path.extension() {
+                continue;
+            }
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            let Some(Ok(steps)) = name.strip_prefix(&prefix).map(str::parse) else {
+                continue;
+            };
+            checkpoints.push((steps, path, entry.metadata()?.len()));
+        }
+        checkpoints.sort_unstable_by_key(|&(steps, ..)| steps);
+        Ok(checkpoints)
+    }
+
+    /// Save `checkpoint` and delete the ones the retention lets go, returns the new file
+    pub fn save(&self, checkpoint: &Checkpoint) -> Result<PathBuf, CheckpointError> {
+        let file = self.path_for(checkpoint.steps);
+        checkpoint.save(&file)?;
+
+        let checkpoints = self.list()?;
+        let sizes = checkpoints
+            .iter()
+            .map(|&(steps, _, bytes)| (steps, bytes))
+            .collect::<Vec<_>>();
+        let expired = self.retention.expired(&sizes);
+        for (steps, path, _) in checkpoints {
+            if expired.contains(&steps) {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(file)
+    }
+}
+
+#[test]
+fn retention_policies() {
+    let series = (1..=20).map(|i| (i * 100, 10)).collect::<Vec<_>>();
+    let kept = |retention: Retention| {
+        let expired = retention.expired(&series);
+        series
+            .iter()
+            .map(|&(steps, _)| steps)
+            .filter(|steps| !expired.contains(steps))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(kept(Retention::default()).len(), 20);
+    let last = Retention {
+        keep_last: Some(3),
+        ..Default::default()
+    };
+    assert_eq!(kept(last), [1800, 1900, 2000]);
+
+    // One of ages 0, 64..127, 128..255, 256..511, 512..1023 and 1024..2047 steps each
+    let exponential = Retention {
+        exponential: true,
+        ..Default::default()
+    };
+    assert_eq!(kept(exponential), [100, 1000, 1500, 1800, 1900, 2000]);
+
+    let bytes = Retention {
+        keep_last: Some(5),
+        max_bytes: Some(25),
+        ..Default::default()
+    };
+    assert_eq!(kept(bytes), [1900, 2000]);
+    // The newest one stays even if it alone is too big
+    let tiny = Retention {
+        max_bytes: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(kept(tiny), [2000]);
+}
+
+#[test]
+fn legacy_checkpoints_stay_in_the_file() {
+    let checkpoint = Checkpoint {
+        width: 8,
+        height: 8,
+        rule: "RL".to_owned(),
+        conflict: ConflictPolicy::default(),
+        cells: vec![0; 8],
+        ants: Vec::new(),
+        steps: 42,
+        noise: None,
+        semantics: 1,
+    };
+    let file = std::env::temp_dir().join("antventure_legacy_checkpoint.bin");
+    fs::write(&file, bincode::serialize(&checkpoint).unwrap()).unwrap();
+    let loaded = Checkpoint::load(&file).unwrap();
+    assert_eq!((loaded.steps, loaded.semantics()), (42, 1));
+
+    // A cell count far past the end of the file
+    let mut bytes = bincode::serialize(&checkpoint).unwrap();
+    let cells = 2 * 8 + 8 + 2 + 4;
+    bytes[cells..cells + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+    fs::write(&file, bytes).unwrap();
+    let loaded = Checkpoint::load(&file);
+    fs::remove_file(file).ok();
+    assert!(matches!(loaded, Err(CheckpointError::Encoding(_))));
+}
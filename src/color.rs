@@ -0,0 +1,57 @@
+//! Pixels are stored in sRGB, where equal steps look about equally far apart, but
+//! light adds up linearly, so colors are mixed in linear light and stored back
+
+#[cfg(feature = "io")]
+use std::io::Write;
+
+#[cfg(feature = "io")]
+use png::{Encoder, SrgbRenderingIntent};
+
+/// Linear light of an sRGB channel, `0.0` for black to `1.0` for white
+pub fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    match c <= 0.04045 {
+        true => c / 12.92,
+        false => ((c + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+/// sRGB channel of linear light, clamped to `0.0..=1.0`
+pub fn linear_to_srgb(l: f32) -> u8 {
+    let l = l.clamp(0.0, 1.0);
+    let c = match l <= 0.003_130_8 {
+        true => l * 12.92,
+        false => 1.055 * l.powf(1.0 / 2.4) - 0.055,
+    };
+    (c * 255.0).round() as u8
+}
+
+/// `t` of the way from `a` to `b` in linear light, so a fade between two colors
+/// doesn't dip dark in the middle
+pub fn mix_srgb(a: [u8; 3], b: [u8; 3], t: f32) -> [u8; 3] {
+    let channel = |a: u8, b: u8| {
+        let (a, b) = (srgb_to_linear(a), srgb_to_linear(b));
+        linear_to_srgb(a + (b - a) * t)
+    };
+    [
+        channel(a[0], b[0]),
+        channel(a[1], b[1]),
+        channel(a[2], b[2]),
+    ]
+}
+
+/// Tag a color PNG as sRGB, so viewers show its colors the same way
+#[cfg(feature = "io")]
+pub(crate) fn tag_srgb<W: Write>(encoder: &mut Encoder<W>) {
+    encoder.set_source_srgb(SrgbRenderingIntent::Perceptual);
+}
+
+#[test]
+fn colors_mix_in_linear_light() {
+    for c in [0, 1, 10, 128, 200, 255] {
+        assert_eq!(linear_to_srgb(srgb_to_linear(c)), c);
+    }
+    // Half of the light of white is far brighter than half of its sRGB value
+    assert_eq!(mix_srgb([0; 3], [255; 3], 0.5), [188; 3]);
+    assert_eq!(mix_srgb([255, 0, 0], [0, 0, 255], 1.0), [0, 0, 255]);
+}
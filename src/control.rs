@@ -0,0 +1,231 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Condvar, Mutex,
+    },
+};
+
+/// Stops, holds or slows down a run, import, export or scan from another thread, e.g.
+/// when the user of a GUI or the client of a server gives up on it
+///
+/// Clones control the same operation. Operations check it between rows, writes or
+/// stretches of steps, so they stop soon after [`cancel`](Self::cancel) but not at once.
+/// A check is a load of an atomic, cheap enough for hot loops. Pausing and the speed are
+/// minded by runs, see [`Simulation::run_controlled`](crate::Simulation::run_controlled)
+#[derive(Clone, Debug, Default)]
+pub struct ControlHandle(Arc<Control>);
+
+#[derive(Debug, Default)]
+struct Control {
+    cancelled: AtomicBool,
+    paused: AtomicBool,
+    /// Bits of the steps a second, zero for as fast as it goes
+    speed: AtomicU64,
+    /// Woken on every change, for runs waiting out a pause
+    changed: (Mutex<()>, Condvar),
+}
+
+impl ControlHandle {
+    /// A handle nobody cancelled, paused or slowed down yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop for good, a cancelled operation isn't resumed
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed);
+        self.wake();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Hold runs before their next stretch of steps, until [`resume`](Self::resume)
+    pub fn pause(&self) {
+        self.0.paused.store(true, Ordering::Relaxed);
+        self.wake();
+    }
+
+    pub fn resume(&self) {
+        self.0.paused.store(false, Ordering::Relaxed);
+        self.wake();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.0.paused.load(Ordering::Relaxed)
+    }
+
+    /// Keep runs to `per_second` steps a second, `None` for as fast as they go
+    ///
+    /// # Panics
+    ///
+    /// If the speed isn't a positive number
+    pub fn set_speed(&self, per_second: Option<f64>) {
+        let bits = match per_second {
+            Some(per_second) => {
+                assert!(per_second > 0.0, "speed must be positive");
+                per_second.to_bits()
+            }
+            None => 0,
+        };
+        self.0.speed.store(bits, Ordering::Relaxed);
+        self.wake();
+    }
+
+    /// Steps a second runs are kept to, `None` for as fast as they go
+    pub fn speed(&self) -> Option<f64> {
+        match self.0.speed.load(Ordering::Relaxed) {
+            0 => None,
+            bits => Some(f64::from_bits(bits)),
+        }
+    }
+
+    /// Block while paused, `Err` once cancelled
+    pub fn wait_while_paused(&self) -> Result<(), Cancelled> {
+        let (lock, changed) = &self.0.changed;
+        let guard = lock.lock().expect("not poisoned");
+        let _guard = changed
+            .wait_while(guard, |_| self.is_paused() && !self.is_cancelled())
+            .expect("not poisoned");
+        self.check()
+    }
+
+    fn wake(&self) {
+        // Taking the lock orders the change before anyone's next look at it
+        let (lock, changed) = &self.0.changed;
+        drop(lock.lock().expect("not poisoned"));
+        changed.notify_all();
+    }
+
+    /// `Err` once cancelled, for `?` in loops
+    pub fn check(&self) -> Result<(), Cancelled> {
+        match self.is_cancelled() {
+            true => Err(Cancelled),
+            false => Ok(()),
+        }
+    }
+
+    /// Wrap a writer so writing fails with [`Cancelled`] once cancelled, which stops
+    /// any export writing through it
+    pub fn writer<W: Write>(&self, inner: W) -> ControlledWriter<W> {
+        ControlledWriter {
+            inner,
+            control: self.clone(),
+        }
+    }
+}
+
+/// The operation was stopped with [`ControlHandle::cancel`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Cancelled;
+
+impl Cancelled {
+    /// Whether an io error comes from a cancelled [`ControlledWriter`]
+    pub fn is(e: &io::Error) -> bool {
+        e.get_ref().is_some_and(|inner| inner.is::<Cancelled>())
+    }
+}
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+impl From<Cancelled> for io::Error {
+    fn from(e: Cancelled) -> Self {
+        // Not `Interrupted`, `write_all` would just try again
+        io::Error::other(e)
+    }
+}
+
+/// See [`ControlHandle::writer`]
+pub struct ControlledWriter<W> {
+    inner: W,
+    control: ControlHandle,
+}
+
+impl<W> ControlledWriter<W> {
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for ControlledWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.control.check()?;
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[test]
+fn cancelled_writes_fail() {
+    let control = ControlHandle::new();
+    let mut w = control.writer(Vec::new());
+    w.write_all(b"ant").unwrap();
+
+    control.clone().cancel();
+    let e = w.write_all(b"venture").unwrap_err();
+    assert!(Cancelled::is(&e));
+    assert!(!Cancelled::is(&io::Error::other("disk full")));
+    assert_eq!(w.into_inner(), b"ant");
+    assert_eq!(control.check(), Err(Cancelled));
+}
+
+#[test]
+fn paused_waits_end_on_resume_or_cancel() {
+    use std::{thread, time::Duration};
+
+    let control = ControlHandle::new();
+    assert_eq!(control.wait_while_paused(), Ok(()));
+    assert_eq!(control.speed(), None);
+    control.set_speed(Some(250.0));
+    assert_eq!(control.clone().speed(), Some(250.0));
+
+    for end in [ControlHandle::resume, ControlHandle::cancel] {
+        control.pause();
+        let waiting = thread::spawn({
+            let control = control.clone();
+            move || control.wait_while_paused()
+        });
+        thread::sleep(Duration::from_millis(20));
+        assert!(!waiting.is_finished());
+        end(&control);
+        let cancelled = control.is_cancelled();
+        assert_eq!(waiting.join().unwrap().is_err(), cancelled);
+    }
+}
+
+#[test]
+fn runs_mind_their_control() {
+    use std::time::{Duration, Instant};
+
+    use crate::{Direction, Map, Pos, Simulation};
+
+    let mut sim = Simulation::new(Map::<64, 64>::new_white(), Default::default());
+    sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+    let control = ControlHandle::new();
+    assert_eq!(sim.run_controlled(100, &control), Ok(true));
+
+    // A tenth of a second at 1000 steps a second
+    control.set_speed(Some(1000.0));
+    let started = Instant::now();
+    assert_eq!(sim.run_controlled(100, &control), Ok(true));
+    assert!(started.elapsed() >= Duration::from_millis(50));
+    assert_eq!(sim.steps(), 200);
+
+    control.pause();
+    control.cancel();
+    assert_eq!(sim.run_controlled(100, &control), Err(Cancelled));
+    assert_eq!(sim.steps(), 200);
+}
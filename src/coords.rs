@@ -0,0 +1,358 @@
+use std::{borrow::Cow, str::FromStr};
+
+use crate::{CellStore, Direction, IntegrityError, MapPos, Pos};
+
+/// Which way the y axis of the positions people type and read grows
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum YAxis {
+    /// Down the screen, the way images and the map count rows
+    #[default]
+    Down,
+    /// Up the screen, the way math and many papers draw it
+    Up,
+}
+
+impl FromStr for YAxis {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "down" | "screen" => Ok(YAxis::Down),
+            "up" | "math" => Ok(YAxis::Up),
+            _ => Err(format!("unknown y axis {s:?}, expected down or up")),
+        }
+    }
+}
+
+/// Cell the positions people type and read count from
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Origin {
+    /// The top left cell, or the bottom left one when y grows up
+    #[default]
+    Corner,
+    /// The middle cell, where ants start by default
+    Center,
+}
+
+impl FromStr for Origin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "corner" => Ok(Origin::Corner),
+            "center" | "centre" => Ok(Origin::Center),
+            _ => Err(format!("unknown origin {s:?}, expected corner or center")),
+        }
+    }
+}
+
+/// How the positions people type and read relate to map positions, which count from
+/// the top left cell with y growing down. Directions keep their meaning on screen:
+/// north is always up
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Coordinates {
+    pub y_axis: YAxis,
+    pub origin: Origin,
+}
+
+impl Coordinates {
+    /// Map position of `pos` on a `width`x`height` map
+    ///
+    /// An endless map has no corners, there [`Origin::Center`] with a `0`x`0` size
+    /// puts the origin at map `(0, 0)`
+    pub fn to_map(&self, pos: Pos, (width, height): (usize, usize)) -> Pos {
+        let origin = self.origin_on_map(width, height);
+        match self.y_axis {
+            YAxis::Down => Pos::new(origin.x + pos.x, origin.y + pos.y),
+            YAxis::Up => Pos::new(origin.x + pos.x, origin.y - pos.y),
+        }
+    }
+
+    /// Position of the map cell at `pos`, the other way around from [`to_map`](Self::to_map)
+    pub fn position(&self, pos: Pos, (width, height): (usize, usize)) -> Pos {
+        let origin = self.origin_on_map(width, height);
+        match self.y_axis {
+            YAxis::Down => Pos::new(pos.x - origin.x, pos.y - origin.y),
+            YAxis::Up => Pos::new(pos.x - origin.x, origin.y - pos.y),
+        }
+    }
+
+    fn origin_on_map(&self, width: usize, height: usize) -> Pos {
+        match (self.origin, self.y_axis) {
+            (Origin::Corner, YAxis::Down) => Pos::new(0, 0),
+            (Origin::Corner, YAxis::Up) => Pos::new(0, height as isize - 1),
+            (Origin::Center, _) => Pos::new(width as isize / 2, height as isize / 2),
+        }
+    }
+}
+
+/// Flips and quarter turns of output images, to match the pictures of papers and
+/// other simulators. The flips go first
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ImageOrientation {
+    /// Mirror left to right
+    pub flip_x: bool,
+    /// Mirror top to bottom
+    pub flip_y: bool,
+    /// Clockwise quarter turns, only the remainder of 4 counts
+    pub quarter_turns: u8,
+}
+
+impl ImageOrientation {
+    /// Whether images come out as they are
+    pub fn is_identity(&self) -> bool {
+        !self.flip_x && !self.flip_y && self.quarter_turns.is_multiple_of(4)
+    }
+
+    /// Width and height of a `width`x`height` image once oriented
+    pub fn size(&self, width: usize, height: usize) -> (usize, usize) {
+        match self.quarter_turns % 2 {
+            0 => (width, height),
+            _ => (height, width),
+        }
+    }
+
+    /// Where the pixel at `pos` of a `width`x`height` image ends up
+    pub fn apply(&self, pos: Pos, width: usize, height: usize) -> Pos {
+        let (mut w, mut h) = (width as isize, height as isize);
+        let mut x = if self.flip_x { w - 1 - pos.x } else { pos.x };
+        let mut y = if self.flip_y { h - 1 - pos.y } else { pos.y };
+        for _ in 0..self.quarter_turns % 4 {
+            (x, y) = (h - 1 - y, x);
+            (w, h) = (h, w);
+        }
+        Pos::new(x, y)
+    }
+
+    /// Pixel of a `width`x`height` image that ends up at `pos`, the other way around
+    /// from [`apply`](Self::apply)
+    pub fn source(&self, pos: Pos, width: usize, height: usize) -> Pos {
+        let (out_width, out_height) = self.size(width, height);
+        let (mut w, mut h) = (out_width as isize, out_height as isize);
+        let (mut x, mut y) = (pos.x, pos.y);
+        for _ in 0..self.quarter_turns % 4 {
+            (x, y) = (y, w - 1 - x);
+            (w, h) = (h, w);
+        }
+        let x = if self.flip_x { w - 1 - x } else { x };
+        let y = if self.flip_y { h - 1 - y } else { y };
+        Pos::new(x, y)
+    }
+
+    /// Which way `dir` points in the image once oriented
+    pub fn direction(&self, dir: Direction) -> Direction {
+        let dir = match (dir, self.flip_x, self.flip_y) {
+            (Direction::East | Direction::West, true, _)
+            | (Direction::North | Direction::South, _, true) => dir.cw().cw(),
+            _ => dir,
+        };
+        (0..self.quarter_turns % 4).fold(dir, |dir, _| dir.cw())
+    }
+
+    /// Pixels of a `width`x`height` image, `channels` values each, moved to where they
+    /// end up. The size they come out in is [`size`](Self::size)
+    pub fn pixels<T: Copy>(
+        &self,
+        pixels: &[T],
+        channels: usize,
+        width: usize,
+        height: usize,
+    ) -> Vec<T> {
+        if self.is_identity() {
+            return pixels.to_vec();
+        }
+        let (out_width, _) = self.size(width, height);
+        let mut out = pixels.to_vec();
+        for (i, pixel) in pixels.chunks_exact(channels).enumerate() {
+            let pos = Pos::new((i % width) as isize, (i / width) as isize);
+            let to = self.apply(pos, width, height);
+            let j = (to.y as usize * out_width + to.x as usize) * channels;
+            out[j..j + channels].copy_from_slice(pixel);
+        }
+        out
+    }
+}
+
+/// A board as an [`ImageOrientation`] shows it, read through to the board without
+/// copying it, for saving it in any format a board is. Writing copies it first
+///
+/// Quarter turns need its cells square, or they'd come out of the `W`x`H` board
+#[derive(Clone, Debug)]
+pub struct Oriented<'a, M: Clone> {
+    map: Cow<'a, M>,
+    orientation: ImageOrientation,
+}
+
+impl<'a, M: Clone> Oriented<'a, M> {
+    pub fn new(map: &'a M, orientation: ImageOrientation) -> Self {
+        Self {
+            map: Cow::Borrowed(map),
+            orientation,
+        }
+    }
+
+    /// Map position of the cell shown at `pos`, `None` past the board
+    fn source<const W: usize, const H: usize>(
+        &self,
+        pos: MapPos<'_, W, H>,
+    ) -> Option<MapPos<'static, W, H>>
+    where
+        M: CellStore<W, H>,
+    {
+        let (width, height) = self.map.size();
+        let pos = self.orientation.source(pos.into(), width, height);
+        MapPos::validate_within(pos, (width, height)).ok()
+    }
+}
+
+impl<const W: usize, const H: usize, M: CellStore<W, H> + Clone> CellStore<W, H>
+    for Oriented<'_, M>
+{
+    const COLORS: usize = M::COLORS;
+
+    /// Cells past the board are white
+    fn color(&self, pos: MapPos<'_, W, H>) -> u8 {
+        self.source(pos).map_or(0, |pos| self.map.color(pos))
+    }
+
+    /// # Panics
+    ///
+    /// If the cell is past the board
+    fn set_color(&mut self, pos: MapPos<'_, W, H>, color: u8) {
+        let pos = self.source(pos).expect("cell is past the board");
+        self.map.to_mut().set_color(pos, color);
+    }
+
+    /// Board of these colors shown as it is
+    fn with_colors(colors: Vec<u8>) -> Self {
+        Self {
+            map: Cow::Owned(M::with_colors(colors)),
+            orientation: ImageOrientation::default(),
+        }
+    }
+
+    fn size(&self) -> (usize, usize) {
+        let (width, height) = self.map.size();
+        self.orientation.size(width, height)
+    }
+
+    fn count_colored(&self) -> usize {
+        self.map.count_colored()
+    }
+
+    fn check_integrity(&self) -> Result<(), IntegrityError> {
+        self.map.check_integrity()
+    }
+}
+
+#[test]
+fn coordinates_convert_both_ways() {
+    assert_eq!("math".parse(), Ok(YAxis::Up));
+    assert_eq!("Centre".parse(), Ok(Origin::Center));
+    assert!("left".parse::<Origin>().is_err());
+
+    let size = (8, 4);
+    let screen = Coordinates::default();
+    assert_eq!(screen.to_map(Pos::new(2, 1), size), Pos::new(2, 1));
+
+    // The bottom left cell is (0, 0) and y grows up from it
+    let math = Coordinates {
+        y_axis: YAxis::Up,
+        origin: Origin::Corner,
+    };
+    assert_eq!(math.to_map(Pos::new(0, 0), size), Pos::new(0, 3));
+    assert_eq!(math.to_map(Pos::new(2, 1), size), Pos::new(2, 2));
+
+    let centered = Coordinates {
+        y_axis: YAxis::Up,
+        origin: Origin::Center,
+    };
+    assert_eq!(centered.to_map(Pos::new(-1, 1), size), Pos::new(3, 1));
+    assert_eq!(centered.to_map(Pos::new(5, 7), (0, 0)), Pos::new(5, -7));
+    for coordinates in [screen, math, centered] {
+        let pos = Pos::new(3, -2);
+        assert_eq!(
+            coordinates.position(coordinates.to_map(pos, size), size),
+            pos
+        );
+    }
+}
+
+#[test]
+fn orientations_move_pixels() {
+    // 3x2 image:
+    // 0 1 2
+    // 3 4 5
+    let pixels = [0, 1, 2, 3, 4, 5];
+    let turn = ImageOrientation {
+        quarter_turns: 1,
+        ..Default::default()
+    };
+    assert_eq!(turn.size(3, 2), (2, 3));
+    assert_eq!(turn.pixels(&pixels, 1, 3, 2), [3, 0, 4, 1, 5, 2]);
+    assert_eq!(turn.apply(Pos::new(0, 0), 3, 2), Pos::new(1, 0));
+
+    let flip = ImageOrientation {
+        flip_x: true,
+        ..Default::default()
+    };
+    assert_eq!(flip.pixels(&pixels, 1, 3, 2), [2, 1, 0, 5, 4, 3]);
+    let half_turn = ImageOrientation {
+        quarter_turns: 6,
+        ..Default::default()
+    };
+    let both_flips = ImageOrientation {
+        flip_x: true,
+        flip_y: true,
+        quarter_turns: 0,
+    };
+    assert_eq!(
+        half_turn.pixels(&pixels, 1, 3, 2),
+        both_flips.pixels(&pixels, 1, 3, 2)
+    );
+
+    // Channels move together
+    let rgba = [1, 1, 1, 1, 2, 2, 2, 2];
+    assert_eq!(flip.pixels(&rgba, 4, 2, 1), [2, 2, 2, 2, 1, 1, 1, 1]);
+    assert!(ImageOrientation::default().is_identity());
+}
+
+#[test]
+fn oriented_boards_read_through() {
+    use crate::ColorMap;
+
+    // 3x3 board:
+    // 0 1 2
+    // 3 4 5
+    // 6 7 8
+    let map = ColorMap::<3, 3>::with_colors((0..9).collect());
+    let cell = |x, y| MapPos::validate_pos(Pos::new(x, y)).unwrap();
+    let turn = ImageOrientation {
+        flip_x: true,
+        quarter_turns: 1,
+        ..Default::default()
+    };
+    let oriented = Oriented::new(&map, turn);
+    assert_eq!(oriented.to_colors(), turn.pixels(&map.to_colors(), 1, 3, 3));
+    assert_eq!(oriented.to_colors(), [8, 5, 2, 7, 4, 1, 6, 3, 0]);
+    for i in 0..9 {
+        let pos = Pos::new(i % 3, i / 3);
+        assert_eq!(turn.source(turn.apply(pos, 3, 3), 3, 3), pos);
+    }
+
+    // Writing leaves the board it was made from alone
+    let mut written = oriented.clone();
+    written.set_color(cell(2, 0), 0);
+    assert_eq!(written.color(cell(2, 0)), 0);
+    assert_eq!(map.color(cell(2, 0)), 2);
+
+    // Arrows turn with the picture
+    assert_eq!(turn.direction(Direction::North), Direction::East);
+    assert_eq!(turn.direction(Direction::East), Direction::North);
+    let flip = ImageOrientation {
+        flip_y: true,
+        ..Default::default()
+    };
+    assert_eq!(flip.direction(Direction::North), Direction::South);
+    assert_eq!(flip.direction(Direction::East), Direction::East);
+}
@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+
+use crate::{Direction, Pos};
+
+// Odd multiplier of the rolling hash, arithmetic wraps modulo 2^64
+const BASE: u64 = 0x100_0000_01b3;
+
+/// Periodic behaviour found by a [`CycleDetector`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Periodicity {
+    /// Steps in one period
+    pub period: u64,
+    /// How far the ant moves during one period, zero for an ant walking in place
+    pub translation: Pos,
+    /// Observed step the period was confirmed at
+    pub detected_at: u64,
+}
+
+impl Periodicity {
+    /// The ant builds a highway, as opposed to cycling in place
+    pub fn is_highway(&self) -> bool {
+        self.translation != Pos::new(0, 0)
+    }
+}
+
+/// Watches the cells an ant reads and the ways it faces for a repeating pattern
+///
+/// The last `window` steps are kept as a rolling hash, and Brent's algorithm looks for
+/// the hash repeating: the tortoise is parked at power of two distances and the hare
+/// is the newest step. A match, checked step by step against the tortoise's window,
+/// means the last `window` steps equal the ones a period ago
+pub struct CycleDetector {
+    window: usize,
+    symbols: VecDeque<u16>,
+    hash: u64,
+    // BASE^window, to drop the oldest symbol out of the hash
+    drop: u64,
+    steps: u64,
+    // Brent's tortoise: hash, step, ant position and direction
+    tortoise: Option<(u64, u64, Pos, Direction)>,
+    // Steps the tortoise's hash stands for, so a hash collision isn't taken for a period
+    tortoise_symbols: Vec<u16>,
+    power: u64,
+}
+
+impl CycleDetector {
+    /// Longer windows give fewer false alarms but need more steps to confirm a period
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            symbols: VecDeque::with_capacity(window + 1),
+            hash: 0,
+            drop: (0..window).fold(1u64, |p, _| p.wrapping_mul(BASE)),
+            steps: 0,
+            tortoise: None,
+            tortoise_symbols: Vec::with_capacity(window),
+            power: 1,
+        }
+    }
+
+    /// Feed one step, `color` is the color the ant read at `pos` while facing `dir`
+    pub fn observe(&mut self, color: u8, pos: Pos, dir: Direction) -> Option<Periodicity> {
+        let symbol = color as u16 * 4 + dir as u16 + 1;
+        self.hash = self.hash.wrapping_mul(BASE).wrapping_add(symbol as u64);
+        self.symbols.push_back(symbol);
+        if self.symbols.len() > self.window {
+            let old = self.symbols.pop_front().expect("window isn't empty") as u64;
+            self.hash = self.hash.wrapping_sub(old.wrapping_mul(self.drop));
+        }
+        self.steps += 1;
+
+        if self.symbols.len() < self.window {
+            return None;
+        }
+
+        let Some((hash, step, start, facing)) = self.tortoise else {
+            self.park_tortoise(pos, dir);
+            return None;
+        };
+
+        let lambda = self.steps - step;
+        if hash == self.hash && facing == dir && self.symbols.iter().eq(&self.tortoise_symbols) {
+            return Some(Periodicity {
+                period: lambda,
+                translation: Pos::new(pos.x - start.x, pos.y - start.y),
+                detected_at: self.steps,
+            });
+        }
+
+        // Teleport the tortoise to the hare every time the distance doubles
+        if lambda == self.power {
+            self.park_tortoise(pos, dir);
+            self.power *= 2;
+        }
+        None
+    }
+
+    fn park_tortoise(&mut self, pos: Pos, dir: Direction) {
+        self.tortoise = Some((self.hash, self.steps, pos, dir));
+        self.tortoise_symbols.clear();
+        self.tortoise_symbols.extend(&self.symbols);
+    }
+}
+
+#[test]
+fn langton_builds_a_highway() {
+    use crate::{Map, Rule, Simulation};
+
+    let mut sim = Simulation::new(Map::<1024, 1024>::new_white(), Rule::default());
+    sim.spawn(Pos::new(512, 512), Direction::North).unwrap();
+    let mut detector = CycleDetector::new(512);
+
+    // The highway starts after about 10000 steps
+    let periodicity = sim.run_until_periodic(30_000, &mut detector).unwrap();
+    assert_eq!(periodicity.period, 104);
+    assert_eq!(periodicity.translation.x.abs(), 2);
+    assert_eq!(periodicity.translation.y.abs(), 2);
+    assert!(periodicity.is_highway());
+}
+
+#[test]
+fn cycle_in_place() {
+    let mut detector = CycleDetector::new(8);
+    let found = (0..100).find_map(|i| {
+        let dir = Direction::VARIANTS[i % 4];
+        detector.observe((i % 3) as u8, dir.to_shift(), dir)
+    });
+    let found = found.unwrap();
+    assert_eq!(found.period, 12);
+    assert!(!found.is_highway());
+}
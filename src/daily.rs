@@ -0,0 +1,203 @@
+use std::{
+    fmt::Display,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use sha2::{Digest, Sha256};
+
+use crate::{Direction, Palette, Rule, Turn};
+
+/// Calendar day, written as `YYYY-MM-DD`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// Today in UTC, so everyone gets the same day at the same time
+    pub fn today() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        Self::from_days((secs / 86_400) as i64)
+    }
+
+    /// Day `days` after 1970-01-01, Howard Hinnant's `civil_from_days`
+    pub fn from_days(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + (month <= 2) as i64) as i32;
+        Self { year, month, day }
+    }
+
+    fn days_in_month(year: i32, month: u32) -> u32 {
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        match month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || format!("bad date {s:?}, expected YYYY-MM-DD");
+        let mut parts = s.trim().splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(bad);
+        let (year, month, day) = (next()?, next()?, next()?);
+        let year = year.parse::<i32>().map_err(|_| bad())?;
+        let month = month.parse::<u32>().map_err(|_| bad())?;
+        let day = day.parse::<u32>().map_err(|_| bad())?;
+        if !(1..=12).contains(&month) || !(1..=Self::days_in_month(year, month)).contains(&day) {
+            return Err(bad());
+        }
+        Ok(Self { year, month, day })
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Picture of the day: a rule, starting direction, run length and palette picked
+/// from nothing but the date, so the same day always gives the same picture
+#[derive(Clone, PartialEq, Debug)]
+pub struct Daily {
+    pub date: Date,
+    /// Drawn from a hash of the date, every other choice comes from it
+    pub seed: u64,
+    pub rule: Rule,
+    pub dir: Direction,
+    pub steps: u64,
+    /// A light background and a color for every other cell color of the rule
+    pub palette: Palette,
+}
+
+impl Daily {
+    pub fn new(date: Date) -> Self {
+        let hash = Sha256::digest(format!("antventure daily {date}"));
+        let seed = u64::from_le_bytes(hash[..8].try_into().expect("8 bytes"));
+        // ChaCha streams are fixed by the seed across platforms and rand versions
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+        // Turning one way only walks a little square, those are redrawn
+        let rule = loop {
+            let turns = (0..rng.gen_range(3..=8))
+                .map(|_| match rng.gen_bool(0.5) {
+                    true => Turn::Left,
+                    false => Turn::Right,
+                })
+                .collect::<Vec<_>>();
+            if turns.contains(&Turn::Left) && turns.contains(&Turn::Right) {
+                break Rule::new(turns).expect("3 to 8 letters");
+            }
+        };
+        let dir = Direction::VARIANTS[rng.gen_range(0..4)];
+        let steps = rng.gen_range(20..=300) * 1000;
+
+        // Hues spread evenly from a random one, getting darker towards the last color
+        let hue = rng.gen_range(0.0..360.0);
+        let spread = rng.gen_range(20.0..60.0);
+        let saturation = rng.gen_range(0.5..0.9);
+        let others = rule.colors() - 1;
+        let mut colors = vec![hsv(hue, 0.06, 0.98)];
+        colors.extend((0..others).map(|i| {
+            let value = 0.9 - 0.6 * i as f64 / others as f64;
+            hsv(hue + spread * (i + 1) as f64, saturation, value)
+        }));
+
+        Self {
+            date,
+            seed,
+            rule,
+            dir,
+            steps,
+            palette: Palette {
+                colors,
+                marker: Palette::RED,
+            },
+        }
+    }
+
+    /// Everything the picture was made from, as PNG text keywords and values
+    pub fn text(&self) -> Vec<(String, String)> {
+        let colors = self
+            .palette
+            .colors
+            .iter()
+            .map(|[r, g, b]| format!("#{r:02x}{g:02x}{b:02x}"))
+            .collect::<Vec<_>>();
+        let description = format!(
+            "rule {}, facing {}, {} steps, seed {}, palette {}",
+            self.rule,
+            format!("{:?}", self.dir).to_lowercase(),
+            self.steps,
+            self.seed,
+            colors.join(",")
+        );
+        vec![
+            ("Title".into(), format!("antventure daily {}", self.date)),
+            ("Description".into(), description),
+            ("Software".into(), "antventure".into()),
+        ]
+    }
+}
+
+/// RGB of a hue in degrees, saturation and value in `0.0..=1.0`
+fn hsv(hue: f64, saturation: f64, value: f64) -> [u8; 3] {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    [r, g, b].map(|c| ((c + m) * 255.0).round() as u8)
+}
+
+#[test]
+fn same_day_same_picture() {
+    assert_eq!(Date::from_days(0).to_string(), "1970-01-01");
+    assert_eq!(Date::from_days(11_016).to_string(), "2000-02-29");
+    assert_eq!(
+        Date::from_days(20_742),
+        Date {
+            year: 2026,
+            month: 10,
+            day: 16
+        }
+    );
+    assert!("2026-02-29".parse::<Date>().is_err());
+    assert!("2026-13-01".parse::<Date>().is_err());
+    assert!("yesterday".parse::<Date>().is_err());
+
+    let date = "2026-10-16".parse::<Date>().unwrap();
+    let daily = Daily::new(date);
+    assert_eq!(daily, Daily::new(date));
+    assert_ne!(daily, Daily::new("2026-10-17".parse().unwrap()));
+    assert_eq!(daily.palette.colors.len(), daily.rule.colors());
+    assert!(daily.text()[1].1.contains(&daily.rule.to_string()));
+    assert_eq!(hsv(120.0, 1.0, 1.0), [0, 255, 0]);
+}
@@ -0,0 +1,344 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use png::{BitDepth, ColorType, Encoder};
+
+use crate::{
+    color::{linear_to_srgb, srgb_to_linear, tag_srgb},
+    CellStore, MapPos, Palette, Pos,
+};
+
+type Pixel = [u8; 3];
+
+const NAMESPACE: &str = "http://schemas.microsoft.com/deepzoom/2008";
+
+/// Pyramid of PNG tiles in the Deep Zoom layout, for boards too big to look at as one
+/// picture. OpenSeadragon and other viewers open the `.dzi` file and fetch only the
+/// tiles on screen:
+///
+/// ```js
+/// OpenSeadragon({ id: "board", tileSources: "ant.dzi" });
+/// ```
+///
+/// The tiles go in a `_files` directory next to the `.dzi` file, a directory per level
+/// from a single pixel for the whole board up to a pixel per cell. Levels are built a
+/// band of tiles at a time as the rows of the level above come in, so the board is never
+/// in memory at once, and tiles of nothing but white cells are links to one blank tile
+#[derive(Clone, Debug)]
+pub struct DeepZoom {
+    /// Pixels on a side of a tile, not counting the overlap
+    pub tile_size: usize,
+    /// Pixels a tile shares with each of its neighbours, so they meet without seams
+    pub overlap: usize,
+    /// Needs an entry for each color, tiles are gray without one
+    pub palette: Option<Palette>,
+}
+
+impl Default for DeepZoom {
+    fn default() -> Self {
+        Self {
+            tile_size: 254,
+            overlap: 1,
+            palette: None,
+        }
+    }
+}
+
+/// What a [`DeepZoom::save`] wrote
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Pyramid {
+    pub levels: usize,
+    /// Tiles of every level, blank ones included
+    pub tiles: usize,
+    /// Tiles linked to a blank one instead of written
+    pub blank: usize,
+}
+
+impl DeepZoom {
+    /// Write `map` as `dzi` and its tiles, colors fade from white to black without a palette
+    pub fn save<const W: usize, const H: usize>(
+        &self,
+        map: &impl CellStore<W, H>,
+        colors: usize,
+        dzi: impl AsRef<Path>,
+    ) -> io::Result<Pyramid> {
+        let palette = self
+            .palette
+            .clone()
+            .unwrap_or_else(|| Palette::gray(colors));
+        if self.tile_size == 0 || palette.colors.len() < colors {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "tiles of {} pixels with {} colors but {} in the palette",
+                    self.tile_size,
+                    colors,
+                    palette.colors.len()
+                ),
+            ));
+        }
+
+        let dzi = dzi.as_ref();
+        let mut xml = BufWriter::new(File::create(dzi)?);
+        writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            xml,
+            r#"<Image xmlns="{NAMESPACE}" Format="png" Overlap="{}" TileSize="{}">"#,
+            self.overlap, self.tile_size
+        )?;
+        let (wide, high) = map.size();
+        writeln!(xml, r#"  <Size Width="{wide}" Height="{high}"/>"#)?;
+        writeln!(xml, "</Image>")?;
+        xml.flush()?;
+
+        let mut name = dzi.file_stem().unwrap_or_default().to_owned();
+        name.push("_files");
+        let mut tiles = Tiles {
+            dir: dzi.with_file_name(name),
+            zoom: self,
+            gray: self.palette.is_none(),
+            white: palette.colors[0],
+            blanks: HashMap::new(),
+            pyramid: Pyramid {
+                levels: 0,
+                tiles: 0,
+                blank: 0,
+            },
+        };
+
+        // Level `top` has a pixel per cell, each one below half as many a side
+        let top = wide.max(high).next_power_of_two().trailing_zeros() as usize;
+        let mut levels = (0..=top)
+            .map(|number| {
+                fs::create_dir_all(tiles.dir.join(number.to_string()))?;
+                let scale = 1 << (top - number);
+                Ok(Level {
+                    number,
+                    width: wide.div_ceil(scale),
+                    height: high.div_ceil(scale),
+                    rows: VecDeque::new(),
+                    first: 0,
+                    band: 0,
+                    pending: None,
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        tiles.pyramid.levels = levels.len();
+
+        for y in 0..high {
+            let row = (0..wide)
+                .map(|x| {
+                    let pos = MapPos::validate_pos(Pos::new(x as _, y as _))
+                        .expect("x and y are within the map");
+                    palette.colors[map.color(pos) as usize]
+                })
+                .collect();
+            push(&mut levels, top, row, &mut tiles)?;
+        }
+        Ok(tiles.pyramid)
+    }
+}
+
+/// Rows of a level that tiles still need and the row waiting for its pair to go down
+struct Level {
+    number: usize,
+    width: usize,
+    height: usize,
+    rows: VecDeque<Vec<Pixel>>,
+    /// Row number of the first row kept
+    first: usize,
+    /// Next band of tiles to write
+    band: usize,
+    pending: Option<Vec<Pixel>>,
+}
+
+/// Where tiles go and how they're written
+struct Tiles<'z> {
+    dir: PathBuf,
+    zoom: &'z DeepZoom,
+    gray: bool,
+    white: Pixel,
+    /// Blank tile of every size written so far
+    blanks: HashMap<(usize, usize), PathBuf>,
+    pyramid: Pyramid,
+}
+
+/// Hand the next row to level `number`, write the tiles it completes and pass every
+/// pair of rows down halved
+fn push(levels: &mut [Level], number: usize, row: Vec<Pixel>, tiles: &mut Tiles) -> io::Result<()> {
+    let (size, overlap) = (tiles.zoom.tile_size, tiles.zoom.overlap);
+    let level = &mut levels[number];
+    let y = level.first + level.rows.len();
+
+    let half = match level.pending.take() {
+        Some(above) => Some(halve(&above, &row)),
+        None if y + 1 == level.height => Some(halve(&row, &row)),
+        None => {
+            level.pending = Some(row.clone());
+            None
+        }
+    };
+    level.rows.push_back(row);
+
+    let received = level.first + level.rows.len();
+    while level.band * size < level.height
+        && received >= ((level.band + 1) * size + overlap).min(level.height)
+    {
+        tiles.write_band(level)?;
+        level.band += 1;
+        let keep = (level.band * size).saturating_sub(overlap);
+        while level.first < keep && !level.rows.is_empty() {
+            level.rows.pop_front();
+            level.first += 1;
+        }
+    }
+
+    match (half, number) {
+        (Some(half), 1..) => push(levels, number - 1, half, tiles),
+        _ => Ok(()),
+    }
+}
+
+/// A row of half the width, each pixel mixing a square of two by two in linear light
+fn halve(above: &[Pixel], below: &[Pixel]) -> Vec<Pixel> {
+    (0..above.len().div_ceil(2))
+        .map(|x| {
+            let right = (2 * x + 1).min(above.len() - 1);
+            let square = [above[2 * x], above[right], below[2 * x], below[right]];
+            if square.iter().all(|&pixel| pixel == square[0]) {
+                return square[0];
+            }
+            let channel = |c: usize| {
+                let light = square
+                    .iter()
+                    .map(|pixel| srgb_to_linear(pixel[c]))
+                    .sum::<f32>();
+                linear_to_srgb(light / 4.0)
+            };
+            [channel(0), channel(1), channel(2)]
+        })
+        .collect()
+}
+
+impl Tiles<'_> {
+    /// Write the tiles of the current band of `level`, its rows are all kept
+    fn write_band(&mut self, level: &Level) -> io::Result<()> {
+        let (size, overlap) = (self.zoom.tile_size, self.zoom.overlap);
+        let span = |i: usize, len: usize| {
+            ((i * size).saturating_sub(overlap))..((i + 1) * size + overlap).min(len)
+        };
+        let rows = span(level.band, level.height);
+        for col in 0..level.width.div_ceil(size) {
+            let cols = span(col, level.width);
+            let pixels = rows
+                .clone()
+                .flat_map(|y| &level.rows[y - level.first][cols.clone()])
+                .copied()
+                .collect::<Vec<_>>();
+            let file = self
+                .dir
+                .join(level.number.to_string())
+                .join(format!("{col}_{}.png", level.band));
+            let dims = (cols.len(), rows.len());
+            self.pyramid.tiles += 1;
+
+            if !pixels.iter().all(|&pixel| pixel == self.white) {
+                self.write_png(&pixels, dims, &file)?;
+                continue;
+            }
+            self.pyramid.blank += 1;
+            let blank = match self.blanks.get(&dims) {
+                Some(blank) => blank.clone(),
+                None => {
+                    let blank = self.dir.join(format!("blank_{}x{}.png", dims.0, dims.1));
+                    self.write_png(&pixels, dims, &blank)?;
+                    self.blanks.insert(dims, blank.clone());
+                    blank
+                }
+            };
+            // Hard links work on every platform, a copy is there for file systems
+            // without them
+            let _ = fs::remove_file(&file);
+            if fs::hard_link(&blank, &file).is_err() {
+                fs::copy(&blank, &file)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_png(
+        &self,
+        pixels: &[Pixel],
+        (width, height): (usize, usize),
+        file: &Path,
+    ) -> io::Result<()> {
+        let w = BufWriter::new(File::create(file)?);
+        let mut encoder = Encoder::new(w, width as _, height as _);
+        encoder.set_depth(BitDepth::Eight);
+        let data = match self.gray {
+            true => {
+                encoder.set_color(ColorType::Grayscale);
+                pixels.iter().map(|pixel| pixel[0]).collect()
+            }
+            false => {
+                encoder.set_color(ColorType::Rgb);
+                tag_srgb(&mut encoder);
+                pixels.concat()
+            }
+        };
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&data))
+            .map_err(io::Error::other)
+    }
+}
+
+#[test]
+fn tiles_cover_every_level() {
+    use crate::ColorMap;
+
+    let mut map = ColorMap::<10, 6>::new_white();
+    for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        map.set_color(MapPos::validate_pos(Pos::new(x, y)).unwrap(), 2);
+    }
+    let dir = std::env::temp_dir().join("antventure_tiles_cover_every_level");
+    fs::create_dir_all(&dir).unwrap();
+    let zoom = DeepZoom {
+        tile_size: 4,
+        ..DeepZoom::default()
+    };
+    let pyramid = zoom.save(&map, 3, dir.join("board.dzi")).unwrap();
+
+    // 10x6, 5x3, 3x2, 2x1 and 1x1 pixels, 3x2 tiles on top, 2x1 below and one on each
+    // other level. Only the black corner isn't blank on the two top levels
+    assert_eq!(pyramid.levels, 5);
+    assert_eq!(pyramid.tiles, 6 + 2 + 1 + 1 + 1);
+    assert_eq!(pyramid.blank, 6);
+    let xml = fs::read_to_string(dir.join("board.dzi")).unwrap();
+    assert!(xml.contains(r#"TileSize="4""#) && xml.contains(r#"<Size Width="10" Height="6"/>"#));
+
+    let read = |tile: &str| {
+        let file = File::open(dir.join("board_files").join(tile)).unwrap();
+        let mut reader = png::Decoder::new(file).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        pixels.truncate(info.buffer_size());
+        (info.width, info.height, pixels)
+    };
+    // Tiles overlap their neighbours by a pixel, the ones on the edge only inwards
+    let (width, height, pixels) = read("4/0_0.png");
+    assert_eq!((width, height), (5, 5));
+    assert_eq!(pixels[..3], [0, 0, 255]);
+    assert_eq!(read("4/1_0.png").0, 6);
+    assert_eq!(read("4/2_1.png").1, 3);
+    assert_eq!(read("4/1_1.png"), read("blank_6x3.png"));
+    assert_eq!(read("3/0_0.png").2[0], 0);
+    let (_, _, pixel) = read("0/0_0.png");
+    assert!(pixel[0] > 200 && pixel[0] < 255);
+
+    fs::remove_dir_all(dir).unwrap();
+}
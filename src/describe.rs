@@ -0,0 +1,213 @@
+use std::{fmt::Display, fmt::Write as _};
+
+use crate::{CellStore, Direction, Hatch, Palette, Pos};
+
+/// Plain text account of a board, to go along with an image as its alt text
+/// or for readers who can't see it
+///
+/// ```
+/// use antventure::{Description, Map};
+///
+/// let text = Description::new().with_steps(0).describe(&Map::<8, 8>::new_white(), 2);
+/// assert!(text.starts_with("The board is 8 by 8 cells, shown after 0 steps."));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Description {
+    rule: Option<String>,
+    steps: Option<u64>,
+    palette: Option<Palette>,
+    hatched: bool,
+    ants: Vec<(Pos, Direction, bool)>,
+}
+
+impl Description {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rule(mut self, rule: impl Display) -> Self {
+        self.rule = Some(rule.to_string());
+        self
+    }
+
+    pub fn with_steps(mut self, steps: u64) -> Self {
+        self.steps = Some(steps);
+        self
+    }
+
+    /// Name cell colors after this palette instead of shades of gray
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = Some(palette);
+        self
+    }
+
+    /// Mention the [`Hatch`] pattern of every color
+    pub fn with_hatching(mut self) -> Self {
+        self.hatched = true;
+        self
+    }
+
+    /// An ant that ended at `pos` looking at `dir`, `on_map` is false if it walked off
+    pub fn with_ant(mut self, pos: Pos, dir: Direction, on_map: bool) -> Self {
+        self.ants.push((pos, dir, on_map));
+        self
+    }
+
+    pub fn describe<const W: usize, const H: usize>(
+        &self,
+        map: &impl CellStore<W, H>,
+        colors: usize,
+    ) -> String {
+        let mut text = String::new();
+        let _ = write!(text, "The board is {W} by {H} cells");
+        if let Some(steps) = self.steps {
+            let _ = write!(text, ", shown after {steps} steps");
+        }
+        if let Some(rule) = &self.rule {
+            let _ = write!(text, " of rule {rule}");
+        }
+        text.push_str(".\n");
+
+        let cells = map.to_colors();
+        let mut counts = vec![0; colors];
+        let mut bounds: Option<(Pos, Pos)> = None;
+        for (i, &color) in cells.iter().enumerate() {
+            counts[color as usize] += 1;
+            if color != 0 {
+                let pos = Pos::new((i % W) as _, (i / W) as _);
+                let (min, max) = bounds.get_or_insert((pos, pos));
+                *min = Pos::new(min.x.min(pos.x), min.y.min(pos.y));
+                *max = Pos::new(max.x.max(pos.x), max.y.max(pos.y));
+            }
+        }
+
+        let colored = cells.len() - counts.first().copied().unwrap_or(0);
+        match bounds {
+            None => text.push_str("Every cell is white.\n"),
+            Some((min, max)) => {
+                let _ = writeln!(
+                    text,
+                    "{colored} of {} cells ({:.2}%) are colored, all within columns {} to {} \
+                     and rows {} to {}, a {} by {} area {}.",
+                    cells.len(),
+                    colored as f64 * 100.0 / cells.len().max(1) as f64,
+                    min.x,
+                    max.x,
+                    min.y,
+                    max.y,
+                    max.x - min.x + 1,
+                    max.y - min.y + 1,
+                    placement::<W, H>(min, max)
+                );
+            }
+        }
+
+        let gray = Palette::gray(colors);
+        let palette = self.palette.as_ref().unwrap_or(&gray);
+        let _ = write!(text, "Cells per color:");
+        for (color, count) in counts.iter().enumerate() {
+            let name = palette
+                .colors
+                .get(color)
+                .map_or(format!("color {color}"), |&rgb| color_name(rgb));
+            let _ = write!(text, "{} {name}", if color == 0 { "" } else { "," });
+            if self.hatched && colors <= Hatch::MAX_COLORS {
+                let _ = write!(text, " ({})", Hatch::of(color as u8, colors));
+            }
+            let _ = write!(text, " {count}");
+        }
+        text.push_str(".\n");
+
+        for (i, &(pos, dir, on_map)) in self.ants.iter().enumerate() {
+            let name = match self.ants.len() {
+                1 => "The ant".to_owned(),
+                _ => format!("Ant {}", i + 1),
+            };
+            let ended = if on_map { "stopped" } else { "left the map" };
+            let dir = format!("{dir:?}").to_ascii_lowercase();
+            let _ = writeln!(
+                text,
+                "{name} {ended} at ({}, {}) facing {dir}.",
+                pos.x, pos.y
+            );
+        }
+        text
+    }
+}
+
+/// Where the rectangle between `min` and `max` sits on the map, in words
+fn placement<const W: usize, const H: usize>(min: Pos, max: Pos) -> String {
+    // Centers are doubled to stay whole, anything within a tenth of the map is central
+    let (dx, dy) = (
+        min.x + max.x - W as isize + 1,
+        min.y + max.y - H as isize + 1,
+    );
+    let horizontal = match dx {
+        _ if dx.unsigned_abs() <= W / 10 => None,
+        _ if dx < 0 => Some("left of"),
+        _ => Some("right of"),
+    };
+    let vertical = match dy {
+        _ if dy.unsigned_abs() <= H / 10 => None,
+        _ if dy < 0 => Some("above"),
+        _ => Some("below"),
+    };
+    match (horizontal, vertical) {
+        (None, None) => "around the center".to_owned(),
+        (Some(side), None) | (None, Some(side)) => format!("{side} the center"),
+        (Some(h), Some(v)) => format!("{h} and {v} the center"),
+    }
+}
+
+/// Name of a palette color: white, black, a share of gray, one of the
+/// [colorblind](Palette::colorblind) ones or its hex code
+fn color_name(rgb: [u8; 3]) -> String {
+    if let Some((_, name)) = Palette::COLORBLIND.iter().find(|(c, _)| *c == rgb) {
+        return (*name).to_owned();
+    }
+    match rgb {
+        [r, g, b] if r == g && g == b => {
+            format!("{}% gray", ((255 - r as usize) * 100 + 127) / 255)
+        }
+        [r, g, b] => format!("#{r:02x}{g:02x}{b:02x}"),
+    }
+}
+
+#[test]
+fn describes_board() {
+    use crate::{ColorMap, MapPos, Rule};
+
+    let mut map = ColorMap::<100, 100>::new_white();
+    for (x, y, color) in [(5, 10, 1), (20, 30, 2), (6, 11, 1)] {
+        let pos = MapPos::validate_pos(Pos::new(x, y)).unwrap();
+        map.set_color(pos, color);
+    }
+
+    let text = Description::new()
+        .with_rule("LRR".parse::<Rule>().unwrap())
+        .with_steps(42)
+        .with_hatching()
+        .with_ant(Pos::new(20, 30), Direction::West, true)
+        .describe(&map, 3);
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines[0],
+        "The board is 100 by 100 cells, shown after 42 steps of rule LRR."
+    );
+    assert_eq!(
+        lines[1],
+        "3 of 10000 cells (0.03%) are colored, all within columns 5 to 20 and rows 10 to 30, \
+         a 16 by 21 area left of and above the center."
+    );
+    assert_eq!(
+        lines[2],
+        "Cells per color: white (plain) 9997, 50% gray (diagonal lines) 2, black (plain) 1."
+    );
+    assert_eq!(lines[3], "The ant stopped at (20, 30) facing west.");
+
+    let text = Description::new()
+        .with_palette(Palette::colorblind())
+        .describe(&ColorMap::<4, 4>::new_white(), 3);
+    assert!(text.contains("Every cell is white."));
+    assert!(text.contains("white 16, black 0, orange 0."));
+}
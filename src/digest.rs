@@ -0,0 +1,46 @@
+/// 64-bit FNV-1a, simple enough to stay the same across versions and platforms,
+/// unlike the hashers of std
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub(crate) fn new() -> Self {
+        Self(Self::OFFSET)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) -> &mut Self {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(Self::PRIME);
+        }
+        self
+    }
+
+    /// Integers go in as 8 little-endian bytes whatever their width on the platform
+    pub(crate) fn write_u64(&mut self, n: u64) -> &mut Self {
+        self.write(&n.to_le_bytes())
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Digest of a `width`x`height` board from its cell colors in row-major order
+pub(crate) fn board_digest(width: usize, height: usize, colors: impl Iterator<Item = u8>) -> u64 {
+    let mut hasher = Fnv1a::new();
+    hasher.write_u64(width as u64).write_u64(height as u64);
+    for color in colors {
+        hasher.write(&[color]);
+    }
+    hasher.finish()
+}
+
+#[test]
+fn fnv_test_vectors() {
+    assert_eq!(Fnv1a::new().finish(), 0xcbf29ce484222325);
+    assert_eq!(Fnv1a::new().write(b"a").finish(), 0xaf63dc4c8601ec8c);
+    assert_eq!(Fnv1a::new().write(b"foobar").finish(), 0x85944171f73967e8);
+}
@@ -0,0 +1,134 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Pos, Turn};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Direction {
+    North = 0,
+    East = 1,
+    South = 2,
+    West = 3,
+}
+
+impl Direction {
+    pub const VARIANTS: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// `TURNS[turn as usize][dir as usize]` is the new direction and its shift, so
+    /// the hot loop turns by a table lookup instead of matching on the turn in
+    /// [`turn`](Self::turn) and then on the new direction in [`to_shift`](Self::to_shift)
+    pub(crate) const TURNS: [[(Direction, isize, isize); 4]; 4] = {
+        let kinds = [Turn::Left, Turn::Right, Turn::UTurn, Turn::None];
+        let mut turns = [[(Direction::North, 0, 0); 4]; 4];
+
+        let mut t = 0;
+        while t < kinds.len() {
+            let mut i = 0;
+            while i < Self::VARIANTS.len() {
+                let dir = Self::VARIANTS[i].turn(kinds[t]);
+                turns[kinds[t] as usize][i] = (dir, dir.to_shift().x, dir.to_shift().y);
+                i += 1;
+            }
+            t += 1;
+        }
+
+        turns
+    };
+
+    /// Direction after `turn` and the shift of a step that way, read from [`TURNS`](Self::TURNS)
+    /// instead of working out [`turn`](Self::turn) and [`to_shift`](Self::to_shift)
+    #[inline]
+    pub fn turned(self, turn: Turn) -> (Direction, Pos) {
+        let (dir, dx, dy) = Self::TURNS[turn as usize][self as usize];
+        (dir, Pos::new(dx, dy))
+    }
+
+    pub const fn turn(self, turn: Turn) -> Self {
+        match turn {
+            Turn::Left => self.ccw(),
+            Turn::Right => self.cw(),
+            Turn::UTurn => self.cw().cw(),
+            Turn::None => self,
+        }
+    }
+
+    /// Rotate clockwise
+    pub const fn cw(self) -> Self {
+        Self::VARIANTS[(self as usize + 1) % Self::VARIANTS.len()]
+    }
+
+    /// Rotate counterclockwise
+    pub const fn ccw(self) -> Self {
+        Self::VARIANTS[(self as isize - 1).rem_euclid(Self::VARIANTS.len() as _) as usize]
+    }
+
+    pub const fn to_shift(self) -> Pos {
+        match self {
+            Direction::North => Pos::new(0, -1),
+            Direction::East => Pos::new(1, 0),
+            Direction::South => Pos::new(0, 1),
+            Direction::West => Pos::new(-1, 0),
+        }
+    }
+}
+
+impl FromStr for Direction {
+    type Err = ParseDirectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "n" | "north" => Ok(Direction::North),
+            "e" | "east" => Ok(Direction::East),
+            "s" | "south" => Ok(Direction::South),
+            "w" | "west" => Ok(Direction::West),
+            _ => Err(ParseDirectionError(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDirectionError(String);
+
+impl Display for ParseDirectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "unknown direction {:?}, expected one of north, east, south, west",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseDirectionError {}
+
+#[test]
+fn test_directions() {
+    assert_eq!(Direction::North.cw(), Direction::East);
+    assert_eq!(Direction::North.ccw(), Direction::West);
+    assert_eq!(Direction::North.cw().cw(), Direction::South);
+    assert_eq!(Direction::North.ccw().ccw(), Direction::South);
+}
+
+#[test]
+fn turn_table_matches_rotations() {
+    for turn in [Turn::Left, Turn::Right, Turn::UTurn, Turn::None] {
+        for dir in Direction::VARIANTS {
+            let rotated = match turn {
+                Turn::Left => dir.ccw(),
+                Turn::Right => dir.cw(),
+                Turn::UTurn => dir.cw().cw(),
+                Turn::None => dir,
+            };
+            let (to, dx, dy) = Direction::TURNS[turn as usize][dir as usize];
+            assert_eq!(to, rotated, "{turn:?} from {dir:?}");
+            assert_eq!((dx, dy), (to.to_shift().x, to.to_shift().y));
+            assert_eq!(dir.turned(turn), (to, to.to_shift()));
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{CellStore, MapPos, Simulation};
+
+/// Cells holding different colors on two boards of the same size
+pub fn hamming_distance<const W: usize, const H: usize>(
+    a: &impl CellStore<W, H>,
+    b: &impl CellStore<W, H>,
+) -> usize {
+    let (a, b) = (a.to_colors(), b.to_colors());
+    a.iter().zip(&b).filter(|(a, b)| a != b).count()
+}
+
+/// How alike the colored cells of two boards of the same size are, from 0 when they
+/// share none to 1 for the same board
+///
+/// It's twice the matching colored cells over the colored cells of both boards,
+/// the Dice coefficient for two colors, so a mostly white background doesn't count
+pub fn similarity<const W: usize, const H: usize>(
+    a: &impl CellStore<W, H>,
+    b: &impl CellStore<W, H>,
+) -> f64 {
+    let colored = a.count_colored() + b.count_colored();
+    if colored == 0 {
+        return 1.0;
+    }
+    (1.0 - hamming_distance(a, b) as f64 / colored as f64).max(0.0)
+}
+
+/// How a run and a copy of it with one cell repainted drift apart
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Divergence {
+    /// First step at which an ant stands somewhere else or faces another way
+    /// than its twin, `None` if they never did
+    pub parted_at: Option<u64>,
+    /// Hamming distance between the boards after the step, sampled as asked
+    /// and once more at the end
+    pub distances: Vec<(u64, usize)>,
+}
+
+impl Divergence {
+    /// Fork `sim` twice, repaint `cell` in one of them with the next color of the rule,
+    /// then walk both for `steps` ticks or until every ant left, sampling the distance
+    /// every `every` steps
+    pub fn measure<const W: usize, const H: usize, M: CellStore<W, H> + Clone>(
+        sim: &Simulation<W, H, M>,
+        cell: MapPos<'_, W, H>,
+        steps: u64,
+        every: u64,
+    ) -> Self {
+        let mut original = sim.fork();
+        let mut perturbed = sim.fork();
+        let color = perturbed.map().color(cell);
+        let next = perturbed.rule().next_color(color);
+        perturbed.map_mut().set_color(cell, next);
+
+        let every = every.max(1);
+        let mut parted_at = None;
+        let mut distances = vec![(
+            sim.steps(),
+            hamming_distance(original.map(), perturbed.map()),
+        )];
+        for i in 1..=steps {
+            let on_map = original.step() | perturbed.step();
+            if parted_at.is_none() {
+                let apart = original.ants().iter().zip(perturbed.ants()).any(|(a, b)| {
+                    (a.pos().index(), a.dir(), a.is_on_map())
+                        != (b.pos().index(), b.dir(), b.is_on_map())
+                });
+                if apart {
+                    parted_at = Some(original.steps());
+                }
+            }
+            if i % every == 0 || i == steps || !on_map {
+                let distance = hamming_distance(original.map(), perturbed.map());
+                distances.push((original.steps(), distance));
+            }
+            if !on_map {
+                break;
+            }
+        }
+
+        Self {
+            parted_at,
+            distances,
+        }
+    }
+
+    /// Distance at the last sample
+    pub fn last_distance(&self) -> usize {
+        self.distances.last().map_or(0, |&(_, distance)| distance)
+    }
+}
+
+#[test]
+fn flipped_cell_on_the_path_diverges() {
+    use crate::{Direction, Map, Pos, Rule};
+
+    let mut sim = Simulation::new(Map::<128, 128>::new_white(), Rule::default());
+    sim.spawn(Pos::new(64, 64), Direction::North).unwrap();
+    sim.run_at_most(1000);
+
+    // The cell the ant is about to step off
+    let under = sim.ants()[0].pos();
+    let divergence = Divergence::measure(&sim, under, 2000, 500);
+    assert_eq!(divergence.parted_at, Some(1001));
+    assert_eq!(divergence.distances[0], (1000, 1));
+    assert_eq!(divergence.distances.len(), 5);
+    assert!(divergence.last_distance() > 1);
+
+    // A far corner stays out of reach, only the flipped cell differs
+    let corner = MapPos::validate_pos(Pos::new(0, 0)).unwrap();
+    let divergence = Divergence::measure(&sim, corner, 2000, 500);
+    assert_eq!(divergence.parted_at, None);
+    assert_eq!(divergence.last_distance(), 1);
+}
+
+#[test]
+fn similarity_ignores_white() {
+    use crate::{Map, Pos};
+
+    let cell = |x, y| MapPos::validate_pos(Pos::new(x, y)).unwrap();
+    let (mut a, mut b) = (Map::<64, 64>::new_white(), Map::<64, 64>::new_white());
+    assert_eq!(similarity(&a, &b), 1.0);
+    for x in 0..4 {
+        a.set(cell(x, 0), false);
+    }
+    for x in 2..6 {
+        b.set(cell(x, 0), false);
+    }
+    // Two of four black cells match on both sides
+    assert_eq!(similarity(&a, &b), 0.5);
+    assert_eq!(similarity(&a, &Map::<64, 64>::new_white()), 0.0);
+}
@@ -0,0 +1,346 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError},
+        Arc, Condvar, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{CellStore, ControlHandle, Map, Pacer, Simulation};
+
+/// How often an idle or paused [`Driver`] looks at its [`ControlHandle`] between asks
+const IDLE_LOOK: Duration = Duration::from_millis(20);
+
+/// Runs a simulation on a worker thread of its own, walking it in stretches and doing
+/// what its [`DriverHandle`]s ask of it in between
+///
+/// The simulation is built on the worker, as observers and channels needn't be `Send`.
+/// Every ask comes back as a [`Promise`], to wait for or `.await` on any runtime, so
+/// servers of any kind share this one thread instead of each running their own
+///
+/// ```
+/// use antventure::{Direction, Driver, Map, Pos, Simulation};
+///
+/// let driver = Driver::spawn(1000, || {
+///     let mut sim = Simulation::new(Map::<64, 64>::new_white(), Default::default());
+///     sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+///     sim
+/// });
+/// let handle = driver.handle();
+/// let steps = handle.with(|sim| sim.steps()).wait().unwrap();
+/// let later = handle.with(|sim| sim.steps()).wait().unwrap();
+/// assert!(later >= steps);
+/// drop(handle);
+/// driver.join();
+/// ```
+pub struct Driver<const W: usize, const H: usize, M = Map<W, H>> {
+    handle: DriverHandle<W, H, M>,
+    control: ControlHandle,
+    worker: JoinHandle<()>,
+}
+
+type Job<const W: usize, const H: usize, M> = Box<dyn FnOnce(&mut Simulation<W, H, M>) + Send>;
+
+impl<const W: usize, const H: usize, M: CellStore<W, H> + 'static> Driver<W, H, M> {
+    /// Build a simulation with `build` on a new thread and walk it `stretch` steps at a
+    /// time until every ant left the map, answering in between. Once they did, or while
+    /// it's paused, it waits for asks. It ends once every handle is dropped or its
+    /// [`control`](Self::control) is cancelled
+    ///
+    /// # Panics
+    ///
+    /// If `stretch` is zero
+    pub fn spawn(
+        stretch: u64,
+        build: impl FnOnce() -> Simulation<W, H, M> + Send + 'static,
+    ) -> Self {
+        assert!(stretch > 0, "a driver walks at least a step at a time");
+        let (jobs, received) = mpsc::channel::<Job<W, H, M>>();
+        let control = ControlHandle::new();
+        let worker = thread::spawn({
+            let control = control.clone();
+            move || {
+                let mut sim = build();
+                let mut pacer = None;
+                while !control.is_cancelled() {
+                    loop {
+                        match received.try_recv() {
+                            Ok(job) => job(&mut sim),
+                            Err(TryRecvError::Empty) => break,
+                            Err(TryRecvError::Disconnected) => return,
+                        }
+                    }
+                    let on_map = sim.ants().iter().any(|ant| ant.is_on_map());
+                    if on_map && !control.is_paused() {
+                        let paced = Pacer::keep_to(&mut pacer, control.speed(), sim.steps());
+                        sim.run_at_most(paced.map_or(stretch, |paced| paced.min(stretch)));
+                        continue;
+                    }
+                    match received.recv_timeout(IDLE_LOOK) {
+                        Ok(job) => job(&mut sim),
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+            }
+        });
+        Self {
+            handle: DriverHandle { jobs },
+            control,
+            worker,
+        }
+    }
+}
+
+impl<const W: usize, const H: usize, M> Driver<W, H, M> {
+    pub fn handle(&self) -> DriverHandle<W, H, M> {
+        self.handle.clone()
+    }
+
+    /// Pauses, resumes, slows down or stops the worker
+    pub fn control(&self) -> ControlHandle {
+        self.control.clone()
+    }
+
+    /// Drop this driver's handle and wait for the worker, which ends once the other
+    /// handles are dropped too
+    pub fn join(self) {
+        drop(self.handle);
+        self.worker.join().expect("the worker panicked");
+    }
+}
+
+/// Asks a [`Driver`] to do something with its simulation, between two stretches of
+/// walking. Handles are cheap to clone and send to other threads
+pub struct DriverHandle<const W: usize, const H: usize, M = Map<W, H>> {
+    jobs: Sender<Job<W, H, M>>,
+}
+
+impl<const W: usize, const H: usize, M> Clone for DriverHandle<W, H, M> {
+    fn clone(&self) -> Self {
+        Self {
+            jobs: self.jobs.clone(),
+        }
+    }
+}
+
+impl<const W: usize, const H: usize, M: 'static> DriverHandle<W, H, M> {
+    /// Call `f` on the simulation, the promise gives what it returns, or `None` if the
+    /// driver is gone
+    pub fn with<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut Simulation<W, H, M>) -> R + Send + 'static,
+    ) -> Promise<R> {
+        let (answer, promise) = promise();
+        self.jobs
+            .send(Box::new(move |sim| answer.send(f(sim))))
+            .ok();
+        promise
+    }
+}
+
+/// Sends questions of type `Q` to the [`Mailbox`] they're answered from with an `A`,
+/// for threads that talk to a run without holding it, like an HTTP server
+///
+/// ```
+/// use antventure::mailbox;
+///
+/// let (postbox, mailbox) = mailbox::<u64, u64>();
+/// let asked = std::thread::spawn(move || postbox.ask(20).wait());
+/// let (question, answer) = mailbox.next().unwrap();
+/// answer.send(question + 1);
+/// assert_eq!(asked.join().unwrap(), Some(21));
+/// ```
+pub struct Postbox<Q, A> {
+    questions: Sender<(Q, Answer<A>)>,
+}
+
+impl<Q, A> Clone for Postbox<Q, A> {
+    fn clone(&self) -> Self {
+        Self {
+            questions: self.questions.clone(),
+        }
+    }
+}
+
+impl<Q, A> Postbox<Q, A> {
+    /// The promise gives `None` if the mailbox is gone or the question went unanswered
+    pub fn ask(&self, question: Q) -> Promise<A> {
+        let (answer, promise) = promise();
+        // A failed send drops the answer, which settles the promise
+        self.questions.send((question, answer)).ok();
+        promise
+    }
+}
+
+/// Where the questions of a [`Postbox`] are answered, see [`mailbox`]
+pub struct Mailbox<Q, A> {
+    questions: Receiver<(Q, Answer<A>)>,
+}
+
+impl<Q, A> Mailbox<Q, A> {
+    /// A question waiting, if there is one
+    pub fn try_next(&self) -> Option<(Q, Answer<A>)> {
+        self.questions.try_recv().ok()
+    }
+
+    /// Wait for the next question, `None` once every postbox is dropped
+    pub fn next(&self) -> Option<(Q, Answer<A>)> {
+        self.questions.recv().ok()
+    }
+}
+
+/// A postbox and the mailbox its questions go to
+pub fn mailbox<Q, A>() -> (Postbox<Q, A>, Mailbox<Q, A>) {
+    let (questions, received) = mpsc::channel();
+    (
+        Postbox { questions },
+        Mailbox {
+            questions: received,
+        },
+    )
+}
+
+struct Slot<A> {
+    value: Option<A>,
+    settled: bool,
+    waker: Option<Waker>,
+}
+
+type Shared<A> = Arc<(Mutex<Slot<A>>, Condvar)>;
+
+/// Answer to a question from a [`Postbox`], dropping it unsent answers `None`
+pub struct Answer<A>(Shared<A>);
+
+impl<A> Answer<A> {
+    pub fn send(self, value: A) {
+        self.0 .0.lock().expect("not poisoned").value = Some(value);
+    }
+}
+
+impl<A> Drop for Answer<A> {
+    fn drop(&mut self) {
+        let (slot, settled) = &*self.0;
+        let mut slot = slot.lock().expect("not poisoned");
+        slot.settled = true;
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+        settled.notify_all();
+    }
+}
+
+/// What an ask comes back with, to [`wait`](Self::wait) for on a thread or `.await`
+/// in async code. `None` if it was never answered
+pub struct Promise<A>(Shared<A>);
+
+impl<A> Promise<A> {
+    pub fn wait(self) -> Option<A> {
+        let (slot, settled) = &*self.0;
+        let slot = slot.lock().expect("not poisoned");
+        let mut slot = settled
+            .wait_while(slot, |slot| !slot.settled)
+            .expect("not poisoned");
+        slot.value.take()
+    }
+}
+
+impl<A> Future for Promise<A> {
+    type Output = Option<A>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<A>> {
+        let mut slot = self.0 .0.lock().expect("not poisoned");
+        match slot.settled {
+            true => Poll::Ready(slot.value.take()),
+            false => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+fn promise<A>() -> (Answer<A>, Promise<A>) {
+    let slot = Slot {
+        value: None,
+        settled: false,
+        waker: None,
+    };
+    let shared = Arc::new((Mutex::new(slot), Condvar::new()));
+    (Answer(shared.clone()), Promise(shared))
+}
+
+#[test]
+fn drivers_answer_from_their_thread() {
+    use std::{task::Wake, thread::Thread};
+
+    use crate::{Direction, Pos};
+
+    // Enough of an executor to `.await` a promise
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    let driver = Driver::spawn(100, || {
+        let mut sim = Simulation::new(Map::<32, 32>::new_white(), Default::default());
+        sim.spawn(Pos::new(16, 16), Direction::North).unwrap();
+        sim
+    });
+    let handle = driver.handle();
+    let asked = thread::spawn({
+        let handle = handle.clone();
+        move || block_on(async { handle.with(|sim| sim.steps()).await })
+    });
+    assert!(asked.join().unwrap().is_some());
+
+    // Once every ant left it waits for asks instead of walking
+    let ended = block_on(async {
+        loop {
+            let on_map = handle.with(|sim| sim.ants()[0].is_on_map()).await;
+            if on_map == Some(false) {
+                break handle.with(|sim| sim.steps()).await;
+            }
+        }
+    });
+    assert_eq!(handle.with(|sim| sim.steps()).wait(), ended);
+    let spawned = handle.with(|sim| sim.spawn(Pos::new(3, 3), Direction::East).is_ok());
+    assert_eq!(spawned.wait(), Some(true));
+
+    // Paused it still answers, without walking
+    let control = driver.control();
+    control.pause();
+    let held = handle.with(|sim| sim.steps()).wait();
+    thread::sleep(IDLE_LOOK * 2);
+    assert_eq!(handle.with(|sim| sim.steps()).wait(), held);
+    control.resume();
+    control.cancel();
+    drop(handle);
+    driver.join();
+
+    let (postbox, mailbox) = mailbox::<&str, usize>();
+    let unanswered = postbox.ask("dropped");
+    drop(mailbox.next());
+    assert_eq!(unanswered.wait(), None);
+    drop(mailbox);
+    assert_eq!(postbox.ask("gone").wait(), None);
+}
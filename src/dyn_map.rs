@@ -0,0 +1,280 @@
+#[cfg(feature = "io")]
+use std::{fs::File, io::BufWriter, path::Path};
+
+#[cfg(feature = "io")]
+use png::{BitDepth, ColorType, Encoder, EncodingError};
+
+use crate::{bitset::BitSet, digest::board_digest, CellStore, Map, MapPos, Pos};
+
+/// Bit-packed board like [`Map`], with the size picked at runtime
+///
+/// It's a [`CellStore`] of any `W`x`H` at least its size, ants of a [`Simulation`](crate::Simulation)
+/// or an [`Ant`](crate::Ant) walk its own cells at the top left and leave it at its edges
+///
+/// ```
+/// use antventure::{CellStore, Direction, DynMap, Pos, Simulation};
+///
+/// let mut sim = Simulation::<256, 256, _>::new(DynMap::new_white(100, 60), Default::default());
+/// sim.spawn(Pos::new(50, 30), Direction::North).unwrap();
+/// sim.run_until_end();
+/// assert_eq!(CellStore::<256, 256>::size(sim.map()), (100, 60));
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DynMap {
+    width: usize,
+    height: usize,
+    cells: BitSet,
+}
+
+/// A valid position on a [`DynMap`] of a certain size, checked when it's made
+/// instead of when it's compiled
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DynPos {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl DynPos {
+    pub const fn x(&self) -> usize {
+        self.x
+    }
+
+    pub const fn y(&self) -> usize {
+        self.y
+    }
+}
+
+impl From<DynPos> for Pos {
+    fn from(pos: DynPos) -> Self {
+        Pos::new(pos.x as _, pos.y as _)
+    }
+}
+
+impl DynMap {
+    pub fn new_white(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: BitSet::filled(width * height, true),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// `pos` if it's on the map, like [`MapPos::validate_pos`] but against the size of
+    /// this map
+    pub fn validate_pos(&self, pos: Pos) -> Result<DynPos, Pos> {
+        if pos.x < 0 || pos.x >= self.width as _ || pos.y < 0 || pos.y >= self.height as _ {
+            return Err(pos);
+        }
+        Ok(DynPos {
+            x: pos.x as _,
+            y: pos.y as _,
+            width: self.width,
+            height: self.height,
+        })
+    }
+
+    /// # Panics
+    ///
+    /// If `pos` was validated on a map of another size
+    fn index(&self, pos: DynPos) -> usize {
+        assert_eq!(
+            (pos.width, pos.height),
+            (self.width, self.height),
+            "position of a {}x{} map used on a {}x{} one",
+            pos.width,
+            pos.height,
+            self.width,
+            self.height
+        );
+        pos.y * self.width + pos.x
+    }
+
+    /// Whether the cell is white
+    pub fn get(&self, pos: DynPos) -> bool {
+        self.cells.get(self.index(pos))
+    }
+
+    pub fn set(&mut self, pos: DynPos, is_white: bool) {
+        let i = self.index(pos);
+        self.cells.set(i, is_white);
+    }
+
+    pub fn count_black_tiles(&self) -> usize {
+        self.cells.len() - self.cells.count_ones()
+    }
+
+    /// Make the map `width` by `height`, the cells both sizes have keep their color
+    /// and new ones are white
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let mut cells = BitSet::filled(width * height, true);
+        for y in 0..height.min(self.height) {
+            for x in 0..width.min(self.width) {
+                cells.set(y * width + x, self.cells.get(y * self.width + x));
+            }
+        }
+        *self = Self {
+            width,
+            height,
+            cells,
+        };
+    }
+
+    /// The same board with its size fixed at compile time, `None` if it's not `W`x`H`
+    pub fn to_map<const W: usize, const H: usize>(&self) -> Option<Map<W, H>> {
+        ((self.width, self.height) == (W, H)).then(|| Map::from_cells(self.cells.iter()))
+    }
+
+    /// Save as a 1-bit grayscale PNG
+    #[cfg(feature = "io")]
+    pub fn save(&self, file: impl AsRef<Path>) -> Result<(), EncodingError> {
+        let w = BufWriter::new(File::create(file)?);
+        let mut encoder = Encoder::new(w, self.width as _, self.height as _);
+        encoder.set_color(ColorType::Grayscale);
+        encoder.set_depth(BitDepth::One);
+        let mut writer = encoder.write_header()?;
+
+        // PNG rows start on a byte, the cells don't when the width isn't a multiple of 8
+        let row_bytes = self.width.div_ceil(8);
+        let mut data = vec![0; row_bytes * self.height];
+        for (i, is_white) in self.cells.iter().enumerate() {
+            let (x, y) = (i % self.width, i / self.width);
+            data[y * row_bytes + x / 8] |= (is_white as u8) << (7 - x % 8);
+        }
+        writer.write_image_data(&data)
+    }
+}
+
+impl<const W: usize, const H: usize> From<&Map<W, H>> for DynMap {
+    fn from(map: &Map<W, H>) -> Self {
+        let mut dyn_map = Self::new_white(W, H);
+        for (i, is_white) in map.cells().enumerate() {
+            dyn_map.cells.set(i, is_white);
+        }
+        dyn_map
+    }
+}
+
+impl<const W: usize, const H: usize> CellStore<W, H> for DynMap {
+    const COLORS: usize = 2;
+
+    /// Cells past the map are white
+    fn color(&self, pos: MapPos<'_, W, H>) -> u8 {
+        match pos.x() < self.width && pos.y() < self.height {
+            true => !self.cells.get(pos.y() * self.width + pos.x()) as u8,
+            false => 0,
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If the cell is past the map
+    fn set_color(&mut self, pos: MapPos<'_, W, H>, color: u8) {
+        debug_assert!(color < 2, "bit map only holds white and black");
+        let pos = self
+            .validate_pos(pos.into())
+            .unwrap_or_else(|pos| panic!("({}, {}) is past the map", pos.x, pos.y));
+        self.set(pos, color == 0);
+    }
+
+    /// A map of all `W`x`H` cells
+    fn with_colors(colors: Vec<u8>) -> Self {
+        let mut map = Self::new_white(W, H);
+        for (i, color) in colors.into_iter().take(W * H).enumerate() {
+            map.cells.set(i, color == 0);
+        }
+        map
+    }
+
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn count_colored(&self) -> usize {
+        self.count_black_tiles()
+    }
+
+    /// Digest of the cells of the map alone, the same as a [`Map`] of its size has
+    fn digest(&self) -> u64 {
+        let colors = self.cells.iter().map(|white| !white as u8);
+        board_digest(self.width, self.height, colors)
+    }
+}
+
+#[test]
+fn dyn_map_matches_map() {
+    use crate::{Ant, Direction};
+
+    let mut map = Map::<64, 64>::new_white();
+    let mut ant = Ant::new(&mut map, Pos::new(20, 40), Direction::East).unwrap();
+    ant.walk_until_end();
+    let (pos, dir) = (Pos::from(ant.pos()), ant.dir());
+
+    let mut dyn_map = DynMap::new_white(64, 64);
+    let mut ant = Ant::<64, 64, _>::new(&mut dyn_map, Pos::new(20, 40), Direction::East).unwrap();
+    ant.walk_until_end();
+    assert_eq!((Pos::from(ant.pos()), ant.dir()), (pos, dir));
+    assert_eq!(dyn_map.count_black_tiles(), map.count_black_tiles());
+    assert_eq!(DynMap::from(&map), dyn_map);
+    let back = dyn_map.to_map::<64, 64>().unwrap();
+    let corner = MapPos::validate_pos(Pos::new(63, 63)).unwrap();
+    assert_eq!(back.get(corner), map.get(corner));
+    assert!(dyn_map.to_map::<32, 32>().is_none());
+
+    let odd = DynMap::new_white(13, 7);
+    assert_eq!(odd.validate_pos(Pos::new(13, 0)), Err(Pos::new(13, 0)));
+}
+
+#[test]
+fn dyn_map_walks_like_a_map_of_its_size() {
+    use crate::{Boundary, Direction, Rule, Simulation};
+
+    // Sizes no const generic was written for, run in a bigger board
+    for boundary in [Boundary::Stop, Boundary::Wrap, Boundary::Reflect] {
+        let mut sim =
+            Simulation::new(Map::<13, 7>::new_white(), Rule::default()).with_boundary(boundary);
+        let mut dyn_sim = Simulation::<16, 16, _>::new(DynMap::new_white(13, 7), Rule::default())
+            .with_boundary(boundary);
+        sim.spawn(Pos::new(6, 3), Direction::North).unwrap();
+        dyn_sim.spawn(Pos::new(6, 3), Direction::North).unwrap();
+        assert!(dyn_sim.spawn(Pos::new(13, 3), Direction::North).is_err());
+
+        sim.run_at_most(500);
+        dyn_sim.run_at_most(500);
+        assert_eq!(dyn_sim.steps(), sim.steps());
+        assert_eq!(
+            CellStore::<16, 16>::digest(dyn_sim.map()),
+            sim.map().digest()
+        );
+        assert_eq!(
+            Pos::from(dyn_sim.ants()[0].pos()),
+            Pos::from(sim.ants()[0].pos())
+        );
+    }
+
+    let mut map = DynMap::new_white(13, 7);
+    map.set(map.validate_pos(Pos::new(12, 6)).unwrap(), false);
+    map.resize(16, 4);
+    assert_eq!((map.width(), map.count_black_tiles()), (16, 0));
+    map.set(map.validate_pos(Pos::new(15, 3)).unwrap(), false);
+    map.resize(13, 7);
+    assert_eq!(map.count_black_tiles(), 0);
+}
+
+#[test]
+#[should_panic(expected = "position of a 13x7 map used on a 7x13 one")]
+fn dyn_pos_of_another_size() {
+    let pos = DynMap::new_white(13, 7)
+        .validate_pos(Pos::new(1, 1))
+        .unwrap();
+    DynMap::new_white(7, 13).get(pos);
+}
@@ -0,0 +1,198 @@
+use crate::{AntventureError, ColorMap, Direction, Map, MapPos, Pos, Rule};
+
+/// `N` independent ants on `N` separate `W`x`H` boards stepped in lockstep
+///
+/// State is kept as a structure of arrays, and the boards are interleaved:
+/// every cell is a `u16` whose bit `lane` is the color of that cell on lane's board.
+/// This keeps a whole ensemble of small boards in one allocation
+/// and makes a step a tight loop over plain arrays, which is what sweeps over
+/// many small boards want. [`TargetSearch`](crate::TargetSearch) tries the starts
+/// of two-color rules 16 at a time in one
+pub struct Ensemble<const W: usize, const H: usize, const N: usize> {
+    /// Bit `lane` is set when the cell is white on that lane's board
+    cells: Vec<u16>,
+    /// Row of [`Direction::TURNS`] for a cell of each color
+    turns: [usize; 2],
+    xs: [usize; N],
+    ys: [usize; N],
+    dirs: [Direction; N],
+    /// Bit `lane` is set while that lane's ant is still on its board
+    alive: u16,
+    steps: u64,
+    /// Steps of each lane, counted once its ant is off the board
+    ended_at: [u64; N],
+}
+
+impl<const W: usize, const H: usize, const N: usize> Ensemble<W, H, N> {
+    /// Spawn one ant per lane, every board starts white
+    pub fn new(ants: [(Pos, Direction); N]) -> Result<Self, AntventureError> {
+        Self::with_rule(&Rule::default(), ants)
+    }
+
+    /// Spawn one ant per lane walking `rule`, every board starts white. The rule must
+    /// have exactly two colors, a bit per cell only holds white and black
+    pub fn with_rule(rule: &Rule, ants: [(Pos, Direction); N]) -> Result<Self, AntventureError> {
+        const { assert!(N > 0 && N <= u16::BITS as usize, "1..=16 lanes supported") };
+        if rule.colors() != 2 {
+            return Err(AntventureError::Colors {
+                rule: rule.colors(),
+                board: 2,
+            });
+        }
+
+        let mut xs = [0; N];
+        let mut ys = [0; N];
+        let mut dirs = [Direction::North; N];
+
+        for (lane, (pos, dir)) in ants.into_iter().enumerate() {
+            let pos = MapPos::<W, H>::validate_pos(pos).map_err(AntventureError::Spawn)?;
+            xs[lane] = pos.x();
+            ys[lane] = pos.y();
+            dirs[lane] = dir;
+        }
+
+        let all = (u32::MAX >> (u32::BITS as usize - N)) as u16;
+
+        Ok(Self {
+            cells: vec![all; W * H],
+            turns: [0, 1].map(|color| rule.turn(color) as usize),
+            xs,
+            ys,
+            dirs,
+            alive: all,
+            steps: 0,
+            ended_at: [0; N],
+        })
+    }
+
+    /// Step every ant that is still on its board, returns whether any of them is
+    pub fn step(&mut self) -> bool {
+        for lane in 0..N {
+            let bit = 1 << lane;
+            if self.alive & bit == 0 {
+                continue;
+            }
+
+            let cell = &mut self.cells[self.ys[lane] * W + self.xs[lane]];
+            *cell ^= bit;
+
+            // White after the flip means it was black
+            let turn = self.turns[(*cell & bit != 0) as usize];
+            let (dir, dx, dy) = Direction::TURNS[turn][self.dirs[lane] as usize];
+            self.dirs[lane] = dir;
+
+            // Wrapping on underflow sends the coordinate far out of bounds
+            let x = self.xs[lane].wrapping_add_signed(dx);
+            let y = self.ys[lane].wrapping_add_signed(dy);
+
+            if x >= W || y >= H {
+                self.alive &= !bit;
+                self.ended_at[lane] = self.steps + 1;
+            } else {
+                self.xs[lane] = x;
+                self.ys[lane] = y;
+            }
+        }
+
+        self.steps += 1;
+        self.alive != 0
+    }
+
+    pub fn walk_until_end(&mut self) {
+        while self.step() {}
+    }
+
+    /// Step until every ant is off its board or `steps` steps are done, returns
+    /// whether any ant is still on its board
+    pub fn walk_at_most(&mut self, steps: u64) -> bool {
+        (0..steps).all(|_| self.step())
+    }
+
+    /// Steps the lane's ant took, the one off the board included
+    pub fn steps(&self, lane: usize) -> u64 {
+        match self.is_alive(lane) {
+            true => self.steps,
+            false => self.ended_at[lane],
+        }
+    }
+
+    /// Position of the lane's ant, it stays at the edge cell once it leaves
+    pub fn pos(&self, lane: usize) -> MapPos<'_, W, H> {
+        MapPos::validate_pos(Pos::new(self.xs[lane] as _, self.ys[lane] as _))
+            .expect("lane positions are always on the board")
+    }
+
+    pub fn dir(&self, lane: usize) -> Direction {
+        self.dirs[lane]
+    }
+
+    pub fn is_alive(&self, lane: usize) -> bool {
+        self.alive & (1 << lane) != 0
+    }
+
+    pub fn count_black_tiles(&self, lane: usize) -> usize {
+        let bit = 1 << lane;
+        self.cells.iter().filter(|cell| **cell & bit == 0).count()
+    }
+
+    /// Copy the lane's board out into a standalone [`Map`]
+    pub fn to_map(&self, lane: usize) -> Map<W, H> {
+        let bit = 1 << lane;
+        Map::from_cells(self.cells.iter().map(|cell| cell & bit != 0))
+    }
+
+    /// Copy the lane's board out a byte a cell, `1` for black
+    pub fn to_color_map(&self, lane: usize) -> ColorMap<W, H> {
+        let bit = 1 << lane;
+        ColorMap::from_colors(self.cells.iter().map(|cell| (cell & bit == 0) as u8))
+    }
+}
+
+#[test]
+fn lanes_match_single_ants() {
+    use crate::Ant;
+
+    let starts = [
+        (Pos::new(16, 16), Direction::North),
+        (Pos::new(3, 20), Direction::East),
+        (Pos::new(30, 1), Direction::West),
+    ];
+
+    let mut ensemble = Ensemble::<32, 32, 3>::new(starts).unwrap();
+    ensemble.walk_until_end();
+
+    for (lane, (pos, dir)) in starts.into_iter().enumerate() {
+        let mut map = Map::<32, 32>::new_white();
+        let mut ant = Ant::new(&mut map, pos, dir).unwrap();
+        ant.walk_until_end();
+
+        assert_eq!(Pos::from(ensemble.pos(lane)), Pos::from(ant.pos()));
+        assert_eq!(ensemble.dir(lane), ant.dir());
+        assert!(!ensemble.is_alive(lane));
+        assert_eq!(ensemble.count_black_tiles(lane), map.count_black_tiles());
+        assert_eq!(
+            ensemble.to_map(lane).count_black_tiles(),
+            map.count_black_tiles()
+        );
+    }
+
+    let wide = "LLRR".parse::<Rule>().unwrap();
+    let refused = Ensemble::<32, 32, 3>::with_rule(&wide, starts);
+    assert!(matches!(
+        refused,
+        Err(AntventureError::Colors { rule: 4, board: 2 })
+    ));
+
+    // Other rules walk like a Simulation, step counts of lanes that left included
+    let rule = "RL".parse::<Rule>().unwrap();
+    let mut ensemble = Ensemble::<32, 32, 3>::with_rule(&rule, starts).unwrap();
+    ensemble.walk_at_most(400);
+    for (lane, (pos, dir)) in starts.into_iter().enumerate() {
+        let mut sim = crate::Simulation::new(ColorMap::<32, 32>::new_white(), rule.clone());
+        sim.spawn(pos, dir).unwrap();
+        sim.run_at_most(400);
+        assert_eq!(ensemble.steps(lane), sim.steps());
+        assert_eq!(ensemble.is_alive(lane), sim.ants()[0].is_on_map());
+        assert_eq!(ensemble.to_color_map(lane).colors(), sim.map().colors());
+    }
+}
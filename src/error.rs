@@ -0,0 +1,107 @@
+use std::{error::Error, fmt::Display, io};
+
+#[cfg(feature = "io")]
+use png::EncodingError;
+
+use crate::{CheckpointError, ParseRuleError, PluginError, Pos};
+
+/// Anything that can go wrong setting up, running or saving a simulation, for callers
+/// that want one type to match on rather than the error of every step
+#[derive(Debug)]
+pub enum AntventureError {
+    /// An ant was put off the map or on an obstacle
+    Spawn(Pos),
+    Io(io::Error),
+    /// A PNG couldn't be written
+    #[cfg(feature = "io")]
+    Encoding(EncodingError),
+    Rule(ParseRuleError),
+    Checkpoint(CheckpointError),
+    Plugin(PluginError),
+    /// A rule of `rule` colors on a board holding exactly `board`
+    Colors {
+        rule: usize,
+        board: usize,
+    },
+}
+
+impl Display for AntventureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AntventureError::Spawn(pos) => {
+                write!(f, "can't put an ant at ({}, {})", pos.x, pos.y)
+            }
+            AntventureError::Io(e) => write!(f, "{e}"),
+            #[cfg(feature = "io")]
+            AntventureError::Encoding(e) => write!(f, "{e}"),
+            AntventureError::Rule(e) => write!(f, "{e}"),
+            AntventureError::Checkpoint(e) => write!(f, "{e}"),
+            AntventureError::Plugin(e) => write!(f, "{e}"),
+            AntventureError::Colors { rule, board } => write!(
+                f,
+                "rule has {rule} colors, but the board takes exactly {board}"
+            ),
+        }
+    }
+}
+
+impl Error for AntventureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AntventureError::Spawn(_) | AntventureError::Colors { .. } => None,
+            AntventureError::Io(e) => Some(e),
+            #[cfg(feature = "io")]
+            AntventureError::Encoding(e) => Some(e),
+            AntventureError::Rule(e) => Some(e),
+            AntventureError::Checkpoint(e) => Some(e),
+            AntventureError::Plugin(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for AntventureError {
+    fn from(e: io::Error) -> Self {
+        AntventureError::Io(e)
+    }
+}
+
+#[cfg(feature = "io")]
+impl From<EncodingError> for AntventureError {
+    fn from(e: EncodingError) -> Self {
+        AntventureError::Encoding(e)
+    }
+}
+
+impl From<ParseRuleError> for AntventureError {
+    fn from(e: ParseRuleError) -> Self {
+        AntventureError::Rule(e)
+    }
+}
+
+impl From<CheckpointError> for AntventureError {
+    fn from(e: CheckpointError) -> Self {
+        AntventureError::Checkpoint(e)
+    }
+}
+
+impl From<PluginError> for AntventureError {
+    fn from(e: PluginError) -> Self {
+        AntventureError::Plugin(e)
+    }
+}
+
+#[test]
+fn errors_keep_their_cause() {
+    use crate::{ColorMap, Direction, Rule, Simulation};
+
+    let mut sim = Simulation::new(ColorMap::<8, 8>::new_white(), Rule::default());
+    let spawned = sim.spawn(Pos::new(8, 0), Direction::North);
+    assert!(matches!(
+        spawned,
+        Err(AntventureError::Spawn(Pos { x: 8, y: 0 }))
+    ));
+
+    let error = AntventureError::from("LX".parse::<Rule>().unwrap_err());
+    assert!(matches!(error, AntventureError::Rule(_)));
+    assert_eq!(error.to_string(), error.source().unwrap().to_string());
+}
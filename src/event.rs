@@ -0,0 +1,200 @@
+use std::ops::BitOr;
+
+use crate::{PhaseEvent, Pos};
+
+/// Something a [`Simulation`](crate::Simulation) did, handed to the observers that
+/// subscribed to its [`EventKind`], see [`Observer::on_event`](crate::Observer::on_event)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SimEvent {
+    /// Tick `step` is done
+    Step {
+        step: u64,
+    },
+    /// Ant number `ant` repainted the cell at `pos`
+    Flip {
+        ant: usize,
+        pos: Pos,
+        from: u8,
+        to: u8,
+    },
+    Spawn {
+        ant: usize,
+        pos: Pos,
+    },
+    /// The ant left the map at `pos`, or stopped there at an obstacle
+    Despawn {
+        ant: usize,
+        pos: Pos,
+    },
+    /// The ant walked into an edge from `pos`, and wrapped around, turned back or left
+    BoundaryHit {
+        ant: usize,
+        pos: Pos,
+    },
+    /// [`run_watching`](crate::Simulation::run_watching) saw the first ant change phase
+    PhaseChange(PhaseEvent),
+    /// The automaton under the ants advanced a generation after tick `step`
+    Generation {
+        step: u64,
+    },
+    /// The rule was swapped before tick `step`
+    RuleChange {
+        step: u64,
+    },
+}
+
+/// What a [`SimEvent`] is about, to subscribe to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EventKind {
+    Step,
+    Flip,
+    Spawn,
+    Despawn,
+    BoundaryHit,
+    PhaseChange,
+    Generation,
+    RuleChange,
+}
+
+impl SimEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            SimEvent::Step { .. } => EventKind::Step,
+            SimEvent::Flip { .. } => EventKind::Flip,
+            SimEvent::Spawn { .. } => EventKind::Spawn,
+            SimEvent::Despawn { .. } => EventKind::Despawn,
+            SimEvent::BoundaryHit { .. } => EventKind::BoundaryHit,
+            SimEvent::PhaseChange(_) => EventKind::PhaseChange,
+            SimEvent::Generation { .. } => EventKind::Generation,
+            SimEvent::RuleChange { .. } => EventKind::RuleChange,
+        }
+    }
+
+    /// Cell the event happened on, if it's about one
+    pub fn pos(&self) -> Option<Pos> {
+        match *self {
+            SimEvent::Flip { pos, .. }
+            | SimEvent::Spawn { pos, .. }
+            | SimEvent::Despawn { pos, .. }
+            | SimEvent::BoundaryHit { pos, .. } => Some(pos),
+            _ => None,
+        }
+    }
+}
+
+/// A set of [`EventKind`]s, written like `EventKind::Spawn | EventKind::Despawn`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct EventKinds(u16);
+
+impl EventKinds {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(u16::MAX);
+
+    pub fn contains(self, kind: EventKind) -> bool {
+        self.0 & Self::from(kind).0 != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl From<EventKind> for EventKinds {
+    fn from(kind: EventKind) -> Self {
+        Self(1 << kind as u16)
+    }
+}
+
+impl<T: Into<EventKinds>> BitOr<T> for EventKinds {
+    type Output = EventKinds;
+
+    fn bitor(self, other: T) -> EventKinds {
+        Self(self.0 | other.into().0)
+    }
+}
+
+impl<T: Into<EventKinds>> BitOr<T> for EventKind {
+    type Output = EventKinds;
+
+    fn bitor(self, other: T) -> EventKinds {
+        EventKinds::from(self) | other
+    }
+}
+
+#[test]
+fn observers_get_the_events_they_subscribe_to() {
+    use crate::{Boundary, Direction, LifeRule, Map, MapPos, Observer, Rule, Simulation};
+
+    #[derive(Default)]
+    struct Events {
+        events: Vec<SimEvent>,
+        flips: usize,
+    }
+
+    impl<const W: usize, const H: usize> Observer<W, H> for Events {
+        fn subscriptions(&self) -> EventKinds {
+            EventKind::Spawn
+                | EventKind::Despawn
+                | EventKind::BoundaryHit
+                | EventKind::Generation
+                | EventKind::RuleChange
+        }
+
+        fn on_event(&mut self, event: &SimEvent) {
+            self.events.push(*event);
+        }
+
+        fn on_cell_flip(&mut self, _ant: usize, _pos: MapPos<'static, W, H>, _from: u8, _to: u8) {
+            self.flips += 1;
+        }
+    }
+
+    let kinds = EventKind::Step | EventKind::Flip;
+    assert!(kinds.contains(EventKind::Flip) && !kinds.contains(EventKind::Spawn));
+    assert!(EventKinds::NONE.is_empty() && EventKinds::ALL.contains(EventKind::RuleChange));
+
+    // The ant turns right off the right edge and comes back in on the left
+    let mut sim = Simulation::new(Map::<8, 8>::new_white(), "RR".parse().unwrap())
+        .with_boundary(Boundary::Wrap)
+        .with_observer(Events::default());
+    sim.spawn(Pos::new(7, 4), Direction::North).unwrap();
+    sim.run_at_most(1);
+    let events = &sim.observer::<Events>().unwrap().events;
+    assert_eq!(
+        events,
+        &[
+            SimEvent::Spawn {
+                ant: 0,
+                pos: Pos::new(7, 4)
+            },
+            SimEvent::BoundaryHit {
+                ant: 0,
+                pos: Pos::new(7, 4)
+            },
+        ]
+    );
+
+    let mut sim = Simulation::new(Map::<8, 8>::new_white(), Rule::default())
+        .with_automaton(LifeRule::life(), 1)
+        .with_observer(Events::default());
+    sim.spawn(Pos::new(0, 0), Direction::West).unwrap();
+    sim.change_rule("RL".parse().unwrap());
+    sim.run_until_end();
+    let events = &sim.observer::<Events>().unwrap();
+    assert_eq!(events.flips, sim.steps() as usize);
+    assert_eq!(
+        events.events[1..],
+        [
+            SimEvent::RuleChange { step: 0 },
+            SimEvent::BoundaryHit {
+                ant: 0,
+                pos: Pos::new(0, 0)
+            },
+            SimEvent::Despawn {
+                ant: 0,
+                pos: Pos::new(0, 0)
+            },
+            SimEvent::Generation { step: 1 },
+        ]
+    );
+}
@@ -0,0 +1,172 @@
+//! C API for embedding a simulation in C and C++ programs, built with
+//! `cargo build --release --features ffi`, which also writes `include/antventure.h`
+//!
+//! ```c
+//! AntventureSim *sim = antventure_new(256, "LR");
+//! while (antventure_step_n(sim, 1000)) {
+//!     const uint8_t *cells = antventure_map_ptr(sim);
+//!     /* draw antventure_size(sim) rows of as many cell colors */
+//! }
+//! antventure_free(sim);
+//! ```
+
+use std::ffi::{c_char, CStr};
+
+use crate::{
+    board::{new_board, Board},
+    Direction, Pos, Rule,
+};
+
+pub const ANTVENTURE_NORTH: u32 = 0;
+pub const ANTVENTURE_EAST: u32 = 1;
+pub const ANTVENTURE_SOUTH: u32 = 2;
+pub const ANTVENTURE_WEST: u32 = 3;
+
+/// Square board with ants on it, only ever handled through a pointer
+pub struct AntventureSim {
+    board: Box<dyn Board>,
+    size: usize,
+}
+
+/// White board of 64, 128, 256, 512, 1024, 2048 or 4096 cells a side walked by `rule`,
+/// e.g. `"LR"`, with an ant in the middle facing north. Null for any other size or a
+/// rule that doesn't parse
+///
+/// # Safety
+///
+/// `rule` must be a nul-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn antventure_new(size: usize, rule: *const c_char) -> *mut AntventureSim {
+    if rule.is_null() {
+        return std::ptr::null_mut();
+    }
+    let Some(rule) = CStr::from_ptr(rule)
+        .to_str()
+        .ok()
+        .and_then(|rule| rule.parse::<Rule>().ok())
+    else {
+        return std::ptr::null_mut();
+    };
+    let Some(mut board) = new_board(size, rule) else {
+        return std::ptr::null_mut();
+    };
+    let middle = (size / 2) as isize;
+    board
+        .spawn(Pos::new(middle, middle), Direction::North)
+        .expect("the middle is on the map");
+    Box::into_raw(Box::new(AntventureSim { board, size }))
+}
+
+/// Add an ant at `x`, `y` facing one of `ANTVENTURE_NORTH` to `ANTVENTURE_WEST`, false
+/// if that's off the map or not a direction
+///
+/// # Safety
+///
+/// `sim` must come from [`antventure_new`] and not be freed yet
+#[no_mangle]
+pub unsafe extern "C" fn antventure_spawn(
+    sim: *mut AntventureSim,
+    x: i64,
+    y: i64,
+    direction: u32,
+) -> bool {
+    let (Some(sim), Some(&dir)) = (sim.as_mut(), Direction::VARIANTS.get(direction as usize))
+    else {
+        return false;
+    };
+    sim.board.spawn(Pos::new(x as _, y as _), dir).is_ok()
+}
+
+/// Walk every ant `steps` steps, returns whether any ant is still on the map
+///
+/// # Safety
+///
+/// `sim` must come from [`antventure_new`] and not be freed yet
+#[no_mangle]
+pub unsafe extern "C" fn antventure_step_n(sim: *mut AntventureSim, steps: u64) -> bool {
+    match sim.as_mut() {
+        Some(sim) => sim.board.run_at_most(steps),
+        None => false,
+    }
+}
+
+/// Steps done so far
+///
+/// # Safety
+///
+/// `sim` must come from [`antventure_new`] and not be freed yet
+#[no_mangle]
+pub unsafe extern "C" fn antventure_steps(sim: *const AntventureSim) -> u64 {
+    sim.as_ref().map_or(0, |sim| sim.board.steps())
+}
+
+/// Cells on a side of the board
+///
+/// # Safety
+///
+/// `sim` must come from [`antventure_new`] and not be freed yet
+#[no_mangle]
+pub unsafe extern "C" fn antventure_size(sim: *const AntventureSim) -> usize {
+    sim.as_ref().map_or(0, |sim| sim.size)
+}
+
+/// Colors cells can have, from white `0` up
+///
+/// # Safety
+///
+/// `sim` must come from [`antventure_new`] and not be freed yet
+#[no_mangle]
+pub unsafe extern "C" fn antventure_colors(sim: *const AntventureSim) -> usize {
+    sim.as_ref().map_or(0, |sim| sim.board.colors())
+}
+
+/// Color of every cell in row-major order, `size * size` bytes from white `0` up.
+/// Read-only, and good until the next step or free
+///
+/// # Safety
+///
+/// `sim` must come from [`antventure_new`] and not be freed yet
+#[no_mangle]
+pub unsafe extern "C" fn antventure_map_ptr(sim: *const AntventureSim) -> *const u8 {
+    sim.as_ref()
+        .map_or(std::ptr::null(), |sim| sim.board.cells().as_ptr())
+}
+
+/// Free a simulation, null does nothing
+///
+/// # Safety
+///
+/// `sim` must come from [`antventure_new`] and not be freed yet
+#[no_mangle]
+pub unsafe extern "C" fn antventure_free(sim: *mut AntventureSim) {
+    if !sim.is_null() {
+        drop(Box::from_raw(sim));
+    }
+}
+
+#[test]
+fn handles_walk_like_maps() {
+    use crate::{Ant, CellStore, Map};
+
+    unsafe {
+        assert!(antventure_new(100, c"LR".as_ptr()).is_null());
+        assert!(antventure_new(64, c"LX".as_ptr()).is_null());
+
+        let sim = antventure_new(64, c"LR".as_ptr());
+        assert_eq!((antventure_size(sim), antventure_colors(sim)), (64, 2));
+        assert!(!antventure_spawn(sim, 64, 0, ANTVENTURE_EAST));
+        assert!(!antventure_spawn(sim, 0, 0, 4));
+        while antventure_step_n(sim, 1000) {}
+
+        let mut map = Map::<64, 64>::new_white();
+        let steps = Ant::new(&mut map, Pos::new(32, 32), Direction::North)
+            .unwrap()
+            .steps()
+            .count();
+        assert_eq!(antventure_steps(sim), steps as u64);
+        let cells = std::slice::from_raw_parts(antventure_map_ptr(sim), 64 * 64);
+        assert_eq!(cells, map.to_colors());
+        antventure_free(sim);
+        antventure_free(std::ptr::null_mut());
+    }
+}
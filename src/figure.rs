@@ -0,0 +1,399 @@
+use std::{collections::HashMap, fmt::Write as _, fs, io, path::Path};
+
+use crate::{render::cropped_colors, CellStore};
+
+/// Width of the drawn board in points, whatever the map size is
+const BOARD_SIZE: f64 = 480.0;
+const MARGIN: f64 = 48.0;
+const FONT_SIZE: f64 = 10.0;
+
+/// Vector file formats a [`Figure`] can be written as
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FigureFormat {
+    Pdf,
+    Eps,
+    /// TikZ picture to `\input` into a LaTeX document, best kept to small boards
+    Tikz,
+}
+
+impl FigureFormat {
+    /// Guess the format from the file extension
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "pdf" => Some(Self::Pdf),
+            "eps" | "ps" => Some(Self::Eps),
+            "tex" | "tikz" => Some(Self::Tikz),
+            _ => None,
+        }
+    }
+}
+
+/// Publication-ready vector drawing of a board, black cells are merged into rectangles
+#[derive(Clone, Debug, Default)]
+pub struct Figure {
+    /// Caption above the board
+    pub title: Option<String>,
+    /// Frame the board and label cell coordinates along the top and left edges
+    pub axes: bool,
+    /// Draw a bar this many cells long below the board
+    pub scale_bar: Option<usize>,
+}
+
+/// Axis-aligned block of equally colored cells, in cells
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub w: usize,
+    pub h: usize,
+    pub color: u8,
+}
+
+/// Cover every non-white one of the row-major `cells` with as few rectangles as row
+/// runs allow: horizontal runs first, then runs repeating in the next row are stretched down
+pub(crate) fn rects(cells: &[u8], width: usize) -> Vec<Rect> {
+    let mut rects = Vec::<Rect>::new();
+    // Rectangles ending at the previous row, by (x, w, color)
+    let mut open = HashMap::<(usize, usize, u8), usize>::new();
+
+    for (y, row) in cells.chunks(width).enumerate() {
+        let mut next = HashMap::new();
+        let mut x = 0;
+        while x < width {
+            let color = row[x];
+            let start = x;
+            while x < width && row[x] == color {
+                x += 1;
+            }
+            if color == 0 {
+                continue;
+            }
+
+            let key = (start, x - start, color);
+            let i = match open.get(&key) {
+                Some(&i) => {
+                    rects[i].h += 1;
+                    i
+                }
+                None => {
+                    rects.push(Rect {
+                        x: start,
+                        y,
+                        w: x - start,
+                        h: 1,
+                        color,
+                    });
+                    rects.len() - 1
+                }
+            };
+            next.insert(key, i);
+        }
+        open = next;
+    }
+
+    rects
+}
+
+/// Drawing primitives in PostScript points, origin at the bottom left
+enum Shape {
+    /// x, y, width, height, gray level (`0` is black)
+    Rect(f64, f64, f64, f64, f64),
+    Line(f64, f64, f64, f64),
+    /// x, y, size, text
+    Text(f64, f64, f64, String),
+}
+
+impl Figure {
+    /// Write `map`, whose cells use `colors` colors fading from white to black, to `file`
+    pub fn save<const W: usize, const H: usize>(
+        &self,
+        map: &impl CellStore<W, H>,
+        colors: usize,
+        format: FigureFormat,
+        file: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        let (shapes, width, height) = self.shapes::<W, H>(map, colors);
+        let bytes = match format {
+            FigureFormat::Pdf => to_pdf(&shapes, width, height),
+            FigureFormat::Eps => to_eps(&shapes, width, height),
+            FigureFormat::Tikz => to_tikz(&shapes),
+        };
+        fs::write(file, bytes)
+    }
+
+    fn shapes<const W: usize, const H: usize>(
+        &self,
+        map: &impl CellStore<W, H>,
+        colors: usize,
+    ) -> (Vec<Shape>, f64, f64) {
+        let (cells, wide, high) = cropped_colors(map, None);
+        let cell = BOARD_SIZE / wide.max(high) as f64;
+        let (board_w, board_h) = (wide as f64 * cell, high as f64 * cell);
+        let top = match self.title {
+            Some(_) => MARGIN + FONT_SIZE * 2.0,
+            None => MARGIN,
+        };
+        let width = board_w + MARGIN * 2.0;
+        let height = board_h + top + MARGIN;
+
+        // Rows grow downwards on the board but upwards on paper
+        let left = MARGIN;
+        let bottom = MARGIN;
+        let to_y = |row: usize| bottom + board_h - row as f64 * cell;
+
+        let darkest = colors.saturating_sub(1).max(1) as f64;
+        let mut shapes = rects(&cells, wide)
+            .into_iter()
+            .map(|r| {
+                Shape::Rect(
+                    left + r.x as f64 * cell,
+                    to_y(r.y + r.h),
+                    r.w as f64 * cell,
+                    r.h as f64 * cell,
+                    1.0 - r.color as f64 / darkest,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(title) = &self.title {
+            let y = bottom + board_h + top - MARGIN / 2.0 - FONT_SIZE;
+            shapes.push(Shape::Text(left, y, FONT_SIZE * 1.4, title.clone()));
+        }
+
+        if self.axes {
+            let (right, top) = (left + board_w, bottom + board_h);
+            shapes.push(Shape::Line(left, bottom, right, bottom));
+            shapes.push(Shape::Line(right, bottom, right, top));
+            shapes.push(Shape::Line(right, top, left, top));
+            shapes.push(Shape::Line(left, top, left, bottom));
+
+            let tick = FONT_SIZE / 2.0;
+            for x in (0..=wide).step_by(tick_step(wide)) {
+                let px = left + x as f64 * cell;
+                shapes.push(Shape::Line(px, top, px, top + tick));
+                shapes.push(Shape::Text(px, top + tick * 2.0, FONT_SIZE, x.to_string()));
+            }
+            for y in (0..=high).step_by(tick_step(high)) {
+                let py = to_y(y);
+                let label = y.to_string();
+                let label_x = left - tick * 2.0 - label.len() as f64 * FONT_SIZE * 0.6;
+                shapes.push(Shape::Line(left - tick, py, left, py));
+                shapes.push(Shape::Text(label_x, py - FONT_SIZE / 3.0, FONT_SIZE, label));
+            }
+        }
+
+        if let Some(cells) = self.scale_bar {
+            let y = bottom - MARGIN / 2.0;
+            let end = left + cells as f64 * cell;
+            shapes.push(Shape::Line(left, y, end, y));
+            shapes.push(Shape::Line(left, y - 3.0, left, y + 3.0));
+            shapes.push(Shape::Line(end, y - 3.0, end, y + 3.0));
+            let label = format!("{cells} cells");
+            shapes.push(Shape::Text(
+                end + 6.0,
+                y - FONT_SIZE / 3.0,
+                FONT_SIZE,
+                label,
+            ));
+        }
+
+        (shapes, width, height)
+    }
+}
+
+/// Round tick distance giving about four ticks per axis
+fn tick_step(cells: usize) -> usize {
+    let rough = (cells / 4).max(1);
+    let pow = 10usize.pow(rough.ilog10());
+    [1, 2, 5, 10]
+        .into_iter()
+        .map(|m| m * pow)
+        .take_while(|&step| step <= rough)
+        .last()
+        .unwrap_or(pow)
+}
+
+/// Escape text for a PDF or PostScript string literal
+fn escape(text: &str) -> String {
+    text.chars()
+        .filter(char::is_ascii)
+        .fold(String::new(), |mut s, c| {
+            if matches!(c, '(' | ')' | '\\') {
+                s.push('\\');
+            }
+            s.push(c);
+            s
+        })
+}
+
+fn to_pdf(shapes: &[Shape], width: f64, height: f64) -> Vec<u8> {
+    let mut content = String::new();
+    for shape in shapes {
+        // Writing into a String can't fail
+        let _ = match shape {
+            Shape::Rect(x, y, w, h, gray) => {
+                writeln!(content, "{gray:.3} g {x:.3} {y:.3} {w:.3} {h:.3} re f")
+            }
+            Shape::Line(x0, y0, x1, y1) => {
+                writeln!(content, "0 G 0.5 w {x0:.3} {y0:.3} m {x1:.3} {y1:.3} l S")
+            }
+            Shape::Text(x, y, size, text) => writeln!(
+                content,
+                "0 g BT /F1 {size:.1} Tf {x:.3} {y:.3} Td ({}) Tj ET",
+                escape(text)
+            ),
+        };
+    }
+
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_owned(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {width:.3} {height:.3}] \
+             /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> >> >>"
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{content}endstream",
+            content.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_owned(),
+    ];
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        let _ = writeln!(pdf, "{} 0 obj\n{object}\nendobj", i + 1);
+    }
+
+    let xref = pdf.len();
+    let _ = writeln!(pdf, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(pdf, "{offset:010} 00000 n ");
+    }
+    let _ = write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    );
+
+    pdf.into_bytes()
+}
+
+fn to_eps(shapes: &[Shape], width: f64, height: f64) -> Vec<u8> {
+    let mut eps = format!(
+        "%!PS-Adobe-3.0 EPSF-3.0\n%%BoundingBox: 0 0 {} {}\n%%HiResBoundingBox: 0 0 {width:.3} {height:.3}\n%%EndComments\n",
+        width.ceil(),
+        height.ceil()
+    );
+    for shape in shapes {
+        let _ = match shape {
+            Shape::Rect(x, y, w, h, gray) => writeln!(
+                eps,
+                "{gray:.3} setgray {x:.3} {y:.3} {w:.3} {h:.3} rectfill"
+            ),
+            Shape::Line(x0, y0, x1, y1) => writeln!(
+                eps,
+                "0 setgray 0.5 setlinewidth newpath {x0:.3} {y0:.3} moveto {x1:.3} {y1:.3} lineto stroke"
+            ),
+            Shape::Text(x, y, size, text) => writeln!(
+                eps,
+                "0 setgray /Helvetica findfont {size:.1} scalefont setfont {x:.3} {y:.3} moveto ({}) show",
+                escape(text)
+            ),
+        };
+    }
+    eps.push_str("showpage\n%%EOF\n");
+
+    eps.into_bytes()
+}
+
+/// Escape text for LaTeX
+fn escape_tex(text: &str) -> String {
+    text.chars().fold(String::new(), |mut s, c| {
+        match c {
+            '\\' => s.push_str("\\textbackslash{}"),
+            '~' => s.push_str("\\textasciitilde{}"),
+            '^' => s.push_str("\\textasciicircum{}"),
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                s.push('\\');
+                s.push(c);
+            }
+            _ => s.push(c),
+        }
+        s
+    })
+}
+
+fn to_tikz(shapes: &[Shape]) -> Vec<u8> {
+    let mut tikz = String::from("\\begin{tikzpicture}[x=1pt, y=1pt]\n");
+    for shape in shapes {
+        let _ = match shape {
+            Shape::Rect(x, y, w, h, gray) => writeln!(
+                tikz,
+                "  \\fill[black!{:.0}] ({x:.3}, {y:.3}) rectangle +({w:.3}, {h:.3});",
+                (1.0 - gray) * 100.0
+            ),
+            Shape::Line(x0, y0, x1, y1) => writeln!(
+                tikz,
+                "  \\draw[line width=0.5pt] ({x0:.3}, {y0:.3}) -- ({x1:.3}, {y1:.3});"
+            ),
+            Shape::Text(x, y, size, text) => writeln!(
+                tikz,
+                "  \\node[anchor=base west, inner sep=0, font=\\fontsize{{{size:.1}}}{{{:.1}}}\\selectfont] at ({x:.3}, {y:.3}) {{{}}};",
+                size * 1.2,
+                escape_tex(text)
+            ),
+        };
+    }
+    tikz.push_str("\\end{tikzpicture}\n");
+
+    tikz.into_bytes()
+}
+
+#[test]
+fn merges_cells_into_rects() {
+    use crate::{Map, MapPos, Pos};
+
+    // ##.
+    // ##.
+    // .#.
+    let mut map = Map::<3, 3>::new_white();
+    for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1), (1, 2)] {
+        map.set(MapPos::validate_pos(Pos::new(x, y)).unwrap(), false);
+    }
+
+    let rect = |x, y, w, h| Rect {
+        x,
+        y,
+        w,
+        h,
+        color: 1,
+    };
+    assert_eq!(
+        rects(&map.to_colors(), 3),
+        [rect(0, 0, 2, 2), rect(1, 2, 1, 1)]
+    );
+
+    let figure = Figure {
+        title: Some("Ant (3x3)".to_owned()),
+        axes: true,
+        scale_bar: Some(1),
+    };
+    let (shapes, ..) = figure.shapes(&map, 2);
+    let pdf = String::from_utf8(to_pdf(&shapes, 100.0, 100.0)).unwrap();
+    assert!(pdf.starts_with("%PDF-1.4") && pdf.ends_with("%%EOF\n"));
+    assert!(pdf.contains("(Ant \\(3x3\\)) Tj"));
+
+    let tikz = String::from_utf8(to_tikz(&shapes)).unwrap();
+    assert_eq!(tikz.matches("\\fill[black!100]").count(), 2);
+    assert_eq!(escape_tex("50% & x_1"), "50\\% \\& x\\_1");
+
+    // Every xref entry points at its object
+    let xref = pdf.rfind("xref\n").unwrap();
+    for (i, line) in pdf[xref..].lines().skip(3).take(5).enumerate() {
+        let offset = line[..10].parse::<usize>().unwrap();
+        assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+    }
+}
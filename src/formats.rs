@@ -0,0 +1,146 @@
+//! The binary formats written to disk, and which of their versions this release reads
+//!
+//! Each starts with four magic bytes and a `u16` version, little-endian. A reader takes
+//! its own version and every one before it, turning older layouts into the current one
+//! as it goes, and refuses newer ones with an error naming both versions
+
+use std::{
+    fmt::{self, Display},
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// First bytes of every state
+pub const STATE_MAGIC: &[u8; 4] = b"ANTV";
+
+/// Version written by [`save_state`](crate::save_state), [`load_state`](crate::load_state)
+/// reads this one and the ones before
+pub const STATE_VERSION: u16 = 2;
+
+/// First bytes of every replay
+pub const REPLAY_MAGIC: &[u8; 4] = b"ANTR";
+
+/// Version written by `ReplayRecorder`, `Replay` reads this one and the ones before
+pub const REPLAY_VERSION: u16 = 1;
+
+/// A format of its own, told apart from the others by its first bytes
+///
+/// ```
+/// use antventure::Format;
+///
+/// assert_eq!(Format::sniff(b"ANTV\x01\x00..."), Some((Format::State, 1)));
+/// assert!(Format::State.reads(1) && !Format::State.reads(Format::State.version() + 1));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// `.antv` checkpoints, see `src/state.rs`
+    State,
+    /// `.antr` recordings of every repainted cell, see `src/replay.rs`
+    Replay,
+}
+
+impl Format {
+    pub const ALL: [Format; 2] = [Format::State, Format::Replay];
+
+    pub fn magic(self) -> &'static [u8; 4] {
+        match self {
+            Format::State => STATE_MAGIC,
+            Format::Replay => REPLAY_MAGIC,
+        }
+    }
+
+    /// Version this release writes
+    pub fn version(self) -> u16 {
+        match self {
+            Format::State => STATE_VERSION,
+            Format::Replay => REPLAY_VERSION,
+        }
+    }
+
+    /// Extension files of the format are saved with, without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::State => "antv",
+            Format::Replay => "antr",
+        }
+    }
+
+    /// Whether this release reads files of `version`
+    pub fn reads(self, version: u16) -> bool {
+        (1..=self.version()).contains(&version)
+    }
+
+    /// Format and version `bytes` start with, `None` if they're none of these
+    pub fn sniff(bytes: &[u8]) -> Option<(Format, u16)> {
+        let (magic, rest) = bytes.split_first_chunk::<4>()?;
+        let version = u16::from_le_bytes(*rest.first_chunk()?);
+        let format = Self::ALL.into_iter().find(|f| f.magic() == magic)?;
+        Some((format, version))
+    }
+
+    /// Format and version of the file at `path`, from its first bytes
+    pub fn sniff_file(path: impl AsRef<Path>) -> io::Result<Option<(Format, u16)>> {
+        let mut header = Vec::with_capacity(6);
+        File::open(path)?.take(6).read_to_end(&mut header)?;
+        Ok(Self::sniff(&header))
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::State => write!(f, "state"),
+            Format::Replay => write!(f, "replay"),
+        }
+    }
+}
+
+#[test]
+fn formats_round_trip() {
+    use crate::{Checkpoint, ColorMap, Direction, Pos, Rule, Simulation};
+
+    let rule = "LRRL".parse::<Rule>().unwrap();
+    let mut sim = Simulation::new(ColorMap::<24, 16>::new_white(), rule);
+    sim.spawn(Pos::new(12, 8), Direction::West).unwrap();
+    sim.run_at_most(200);
+
+    let dir = std::env::temp_dir();
+    let state = dir.join("antventure_formats.antv");
+    crate::save_state(&sim.checkpoint(), &state).unwrap();
+    let sniffed = Format::sniff_file(&state).unwrap();
+    assert_eq!(sniffed, Some((Format::State, STATE_VERSION)));
+    let loaded = Checkpoint::load(&state).unwrap();
+    std::fs::remove_file(&state).ok();
+    let restored = Simulation::<24, 16, ColorMap<24, 16>>::from_checkpoint(&loaded).unwrap();
+    assert_eq!(restored.digest(), sim.digest());
+
+    #[cfg(feature = "io")]
+    {
+        use crate::{CellStore, Replay, ReplayRecorder};
+
+        let file = dir.join("antventure_formats.antr");
+        let board = sim.map().to_colors();
+        let recorder = ReplayRecorder::create(&file, &sim).unwrap();
+        let mut sim = sim.with_observer(recorder);
+        sim.run_at_most(100);
+        sim.finish();
+        let sniffed = Format::sniff_file(&file).unwrap();
+        assert_eq!(sniffed, Some((Format::Replay, REPLAY_VERSION)));
+        let mut replay = Replay::open(&file).unwrap();
+        assert_eq!(replay.cells(), board);
+        assert!(replay.play_to(300).unwrap());
+        assert_eq!(replay.cells(), sim.map().to_colors());
+        std::fs::remove_file(&file).ok();
+    }
+
+    for format in Format::ALL {
+        assert!(format.reads(1) && format.reads(format.version()));
+        assert!(!format.reads(0) && !format.reads(format.version() + 1));
+        let mut header = format.magic().to_vec();
+        header.extend(7u16.to_le_bytes());
+        assert_eq!(Format::sniff(&header), Some((format, 7)));
+        assert_eq!(Format::sniff(&header[..5]), None);
+    }
+    assert_eq!(Format::sniff(b"\x89PNG\r\n"), None);
+}
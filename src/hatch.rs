@@ -0,0 +1,215 @@
+use std::fmt::Display;
+#[cfg(feature = "io")]
+use std::{fs::File, io, io::BufWriter, path::Path};
+
+#[cfg(feature = "io")]
+use png::{ColorType, Encoder, EncodingError};
+
+#[cfg(feature = "io")]
+use crate::{
+    color::tag_srgb,
+    render::{cropped_colors, gray_depth, pack_indices},
+    CellStore, Crop, Palette, Pos,
+};
+
+/// Pattern drawn over a cell so colors can be told apart without seeing them,
+/// in print, on a projector or with color blindness
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Hatch {
+    /// Just the cell color
+    Plain,
+    /// Lines from the bottom left to the top right
+    Diagonal,
+    /// Lines from the top left to the bottom right
+    AntiDiagonal,
+    Horizontal,
+    Vertical,
+    Cross,
+    Dots,
+}
+
+impl Hatch {
+    /// Patterns of the colors between white and the darkest one, in order
+    const BETWEEN: [Hatch; 6] = [
+        Hatch::Diagonal,
+        Hatch::Horizontal,
+        Hatch::AntiDiagonal,
+        Hatch::Vertical,
+        Hatch::Cross,
+        Hatch::Dots,
+    ];
+
+    /// Most colors that get a pattern of their own
+    pub const MAX_COLORS: usize = Self::BETWEEN.len() + 2;
+
+    /// Pattern of `color` out of `colors`: white and the darkest color stay plain
+    /// like on a black and white board, the ones between get lines and dots
+    ///
+    /// # Panics
+    ///
+    /// If `colors` is more than [`MAX_COLORS`](Self::MAX_COLORS)
+    pub fn of(color: u8, colors: usize) -> Self {
+        assert!(
+            colors <= Self::MAX_COLORS,
+            "{colors} colors, not enough patterns"
+        );
+        match color as usize {
+            0 => Hatch::Plain,
+            c if c + 1 >= colors => Hatch::Plain,
+            c => Self::BETWEEN[c - 1],
+        }
+    }
+
+    /// Whether pixel `(x, y)` of the image is ink, lines run across cell borders
+    /// so neighbours of the same color join up
+    pub fn ink(self, x: usize, y: usize) -> bool {
+        const SPACING: usize = 4;
+        match self {
+            Hatch::Plain => false,
+            Hatch::Diagonal => (x + y).is_multiple_of(SPACING),
+            Hatch::AntiDiagonal => (x + SPACING - y % SPACING).is_multiple_of(SPACING),
+            Hatch::Horizontal => y.is_multiple_of(SPACING),
+            Hatch::Vertical => x.is_multiple_of(SPACING),
+            Hatch::Cross => x.is_multiple_of(SPACING) || y.is_multiple_of(SPACING),
+            Hatch::Dots => x % SPACING == 1 && y % SPACING == 1,
+        }
+    }
+}
+
+impl Display for Hatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Hatch::Plain => "plain",
+            Hatch::Diagonal => "diagonal lines",
+            Hatch::AntiDiagonal => "reverse diagonal lines",
+            Hatch::Horizontal => "horizontal lines",
+            Hatch::Vertical => "vertical lines",
+            Hatch::Cross => "grid",
+            Hatch::Dots => "dots",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(feature = "io")]
+/// Save a map as an indexed PNG with every cell drawn as a `cell` pixel square,
+/// filled with its palette color and its [`Hatch`] pattern on top
+///
+/// The pattern is black on light colors and white on dark ones, `ants` get a solid
+/// square in the marker color. Only the `crop` part of the map is saved if given
+pub fn save_hatched_map_to_file<const W: usize, const H: usize>(
+    map: &impl CellStore<W, H>,
+    colors: usize,
+    palette: &Palette,
+    ants: &[Pos],
+    cell: usize,
+    crop: Option<Crop>,
+    file: impl AsRef<Path>,
+) -> Result<(), EncodingError> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+    if colors > Hatch::MAX_COLORS {
+        return Err(invalid(format!(
+            "{colors} colors but only {} can be told apart by patterns",
+            Hatch::MAX_COLORS
+        ))
+        .into());
+    }
+    if palette.colors.len() < colors {
+        return Err(invalid(format!(
+            "{colors} colors but the palette has only {}",
+            palette.colors.len()
+        ))
+        .into());
+    }
+    if cell < 4 {
+        return Err(invalid(format!(
+            "cells of {cell} pixels are too small for a pattern"
+        ))
+        .into());
+    }
+
+    // Cell colors come first, then black and white ink and the ant marker
+    let (black, white, marker) = (colors as u8, colors as u8 + 1, colors as u8 + 2);
+    let mut plte = palette.colors[..colors].concat();
+    plte.extend([[0; 3], [255; 3], palette.marker].concat());
+    let ink = palette.colors[..colors]
+        .iter()
+        .map(|&[r, g, b]| {
+            let luma = 299 * r as u32 + 587 * g as u32 + 114 * b as u32;
+            if luma < 128_000 {
+                white
+            } else {
+                black
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let (mut cells, columns, rows) = cropped_colors(map, crop);
+    let origin = crop.map_or(Pos::new(0, 0), |crop| crop.min);
+    for ant in ants {
+        let (x, y) = (ant.x - origin.x, ant.y - origin.y);
+        if (0..columns as isize).contains(&x) && (0..rows as isize).contains(&y) {
+            cells[y as usize * columns + x as usize] = marker;
+        }
+    }
+
+    let (width, height) = (columns * cell, rows * cell);
+    let mut pixels = vec![0; width * height];
+    for (y, row) in pixels.chunks_mut(width).enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let color = cells[y / cell * columns + x / cell];
+            *pixel = match color == marker {
+                true => marker,
+                false if Hatch::of(color, colors).ink(x, y) => ink[color as usize],
+                false => color,
+            };
+        }
+    }
+
+    let depth = gray_depth(plte.len() / 3);
+    let w = BufWriter::new(File::create(file)?);
+    let mut encoder = Encoder::new(w, width as _, height as _);
+    encoder.set_color(ColorType::Indexed);
+    encoder.set_depth(depth);
+    encoder.set_palette(plte);
+    tag_srgb(&mut encoder);
+    let mut writer = encoder.write_header()?;
+
+    writer.write_image_data(&pack_indices(&pixels, width, depth))
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn hatches_middle_colors() {
+    use crate::{ColorMap, MapPos};
+
+    assert_eq!(Hatch::of(0, 2), Hatch::Plain);
+    assert_eq!(Hatch::of(1, 2), Hatch::Plain);
+    assert_eq!(Hatch::of(1, 4), Hatch::Diagonal);
+    assert_eq!(Hatch::of(2, 4), Hatch::Horizontal);
+    assert_eq!(Hatch::of(3, 4), Hatch::Plain);
+
+    let mut map = ColorMap::<3, 1>::new_white();
+    for x in 0..3 {
+        let pos = MapPos::validate_pos(Pos::new(x, 0)).unwrap();
+        map.set_color(pos, x as u8);
+    }
+    let file = std::env::temp_dir().join("antventure_hatches_middle_colors.png");
+    save_hatched_map_to_file(&map, 3, &Palette::gray(3), &[], 4, None, &file).unwrap();
+
+    let decoder = png::Decoder::new(File::open(&file).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut data).unwrap();
+    std::fs::remove_file(&file).ok();
+
+    // 3 colors, 2 inks and the marker take 4 bits a pixel, 12 pixels a row
+    assert_eq!((reader.info().width, reader.info().height), (12, 4));
+    let row = |y: usize| data[y * 6..(y + 1) * 6].to_vec();
+    // The gray middle cell gets black diagonals, the others stay plain
+    assert_eq!(row(0), [0x00, 0x00, 0x31, 0x11, 0x22, 0x22]);
+    assert_eq!(row(1), [0x00, 0x00, 0x11, 0x13, 0x22, 0x22]);
+
+    assert!(save_hatched_map_to_file(&map, 9, &Palette::gray(9), &[], 4, None, &file).is_err());
+    assert!(save_hatched_map_to_file(&map, 3, &Palette::gray(3), &[], 2, None, &file).is_err());
+}
@@ -0,0 +1,185 @@
+use std::str::FromStr;
+#[cfg(feature = "io")]
+use std::{fs::File, io::BufWriter, path::Path};
+
+#[cfg(feature = "io")]
+use png::{BitDepth, ColorType, Encoder, EncodingError};
+
+#[cfg(feature = "io")]
+use crate::{color::tag_srgb, ImageOrientation};
+use crate::{linear_to_srgb, MapPos, Palette};
+
+/// How a [`Heatmap`] turns visit counts into pixels
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum HeatColors {
+    /// White for cells never visited, black for the busiest one
+    #[default]
+    Gray,
+    /// Black through red and yellow to white
+    Fire,
+}
+
+impl FromStr for HeatColors {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "gray" | "grey" => Ok(HeatColors::Gray),
+            "fire" => Ok(HeatColors::Fire),
+            _ => Err(format!("unknown heat colors {s:?}, expected gray or fire")),
+        }
+    }
+}
+
+impl HeatColors {
+    /// Distinct [`Heatmap::levels`] a cell can have
+    pub const LEVELS: usize = 256;
+
+    /// Color of every level of [`Heatmap::levels`], for saving it like a board
+    pub fn palette(self) -> Palette {
+        let colors = (0..Self::LEVELS)
+            .map(|level| {
+                let i = level as f64 / (Self::LEVELS - 1) as f64;
+                match self {
+                    HeatColors::Gray => [gray_heat(i); 3],
+                    HeatColors::Fire => fire(i),
+                }
+            })
+            .collect();
+        Palette {
+            colors,
+            marker: Palette::RED,
+        }
+    }
+}
+
+/// How many times ants stood on every cell of a map
+#[derive(Clone)]
+pub struct Heatmap<const W: usize, const H: usize>(Vec<u32>);
+
+impl<const W: usize, const H: usize> Heatmap<W, H> {
+    pub fn new() -> Self {
+        Self(vec![0; W * H])
+    }
+
+    /// Count a step off `pos`, saturates instead of wrapping around
+    pub fn visit(&mut self, pos: MapPos<'_, W, H>) {
+        let count = &mut self.0[pos.index()];
+        *count = count.saturating_add(1);
+    }
+
+    pub fn get(&self, pos: MapPos<'_, W, H>) -> u32 {
+        self.0[pos.index()]
+    }
+
+    /// Visit counts in row-major order
+    pub fn counts(&self) -> &[u32] {
+        &self.0
+    }
+
+    pub fn max(&self) -> u32 {
+        self.0.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Counts scaled to `0.0..=1.0` logarithmically, so cells visited a few times
+    /// still show next to the ones every highway lap crosses
+    pub fn intensities(&self) -> Vec<f64> {
+        let top = (self.max() as f64).ln_1p().max(f64::MIN_POSITIVE);
+        self.0.iter().map(|&c| (c as f64).ln_1p() / top).collect()
+    }
+
+    /// [`intensities`](Self::intensities) rounded to one of [`HeatColors::LEVELS`],
+    /// cell colors of a board in the [`HeatColors::palette`]
+    pub fn levels(&self) -> Vec<u8> {
+        let top = (HeatColors::LEVELS - 1) as f64;
+        self.intensities()
+            .iter()
+            .map(|&i| (i * top).round() as u8)
+            .collect()
+    }
+
+    /// Save as an 8-bit PNG
+    #[cfg(feature = "io")]
+    pub fn save(&self, colors: HeatColors, file: impl AsRef<Path>) -> Result<(), EncodingError> {
+        self.save_oriented(colors, ImageOrientation::default(), file)
+    }
+
+    /// [`save`](Self::save) mirrored and turned as `orientation` says
+    #[cfg(feature = "io")]
+    pub fn save_oriented(
+        &self,
+        colors: HeatColors,
+        orientation: ImageOrientation,
+        file: impl AsRef<Path>,
+    ) -> Result<(), EncodingError> {
+        let intensities = orientation.pixels(&self.intensities(), 1, W, H);
+        let (width, height) = orientation.size(W, H);
+
+        let w = BufWriter::new(File::create(file)?);
+        let mut encoder = Encoder::new(w, width as _, height as _);
+        encoder.set_depth(BitDepth::Eight);
+        tag_srgb(&mut encoder);
+        let data = match colors {
+            HeatColors::Gray => {
+                encoder.set_color(ColorType::Grayscale);
+                intensities
+                    .iter()
+                    .map(|&i| gray_heat(i))
+                    .collect::<Vec<_>>()
+            }
+            HeatColors::Fire => {
+                encoder.set_color(ColorType::Rgb);
+                intensities.iter().flat_map(|&i| fire(i)).collect()
+            }
+        };
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)
+    }
+}
+
+impl<const W: usize, const H: usize> Default for Heatmap<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// White fading to black in linear light
+pub(crate) fn gray_heat(i: f64) -> u8 {
+    linear_to_srgb(1.0 - i as f32)
+}
+
+/// Red light rises over the first third, green over the second and blue over the last
+pub(crate) fn fire(i: f64) -> [u8; 3] {
+    let channel = |from: f64| linear_to_srgb(((i - from) * 3.0) as f32);
+    [channel(0.0), channel(1.0 / 3.0), channel(2.0 / 3.0)]
+}
+
+#[test]
+fn heatmap_counts_steps() {
+    use crate::{Direction, Map, Pos, Rule, Simulation};
+
+    let mut sim = Simulation::new(Map::<16, 16>::new_white(), Rule::default()).with_heatmap();
+    sim.spawn(Pos::new(8, 8), Direction::North).unwrap();
+    sim.run_at_most(40);
+
+    let heatmap = sim.heatmap().unwrap();
+    assert_eq!(heatmap.counts().iter().sum::<u32>(), 40);
+    // Four left turns on white bring the ant back to its start cell
+    let start = MapPos::validate_pos(Pos::new(8, 8)).unwrap();
+    assert!(heatmap.get(start) > 1);
+
+    let intensities = heatmap.intensities();
+    assert_eq!(intensities.iter().copied().fold(0.0, f64::max), 1.0);
+    assert_eq!(fire(0.0), [0, 0, 0]);
+    assert_eq!(fire(1.0), [255, 255, 255]);
+    assert_eq!(fire(0.5), [255, 188, 0]);
+    assert_eq!(gray_heat(0.5), 188);
+
+    let levels = heatmap.levels();
+    assert_eq!(levels.iter().max(), Some(&255));
+    assert_eq!(levels[0], 0);
+    let palette = HeatColors::Fire.palette();
+    assert_eq!(palette.colors.len(), 256);
+    assert_eq!((palette.colors[0], palette.colors[255]), ([0; 3], [255; 3]));
+}
@@ -0,0 +1,432 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+#[cfg(feature = "io")]
+use std::{fs::File, io::BufWriter, path::Path};
+
+#[cfg(feature = "io")]
+use png::{BitDepth, ColorType, Encoder, EncodingError};
+
+/// Axial coordinates of a pointy-top hex cell, `q` grows east and `r` south-east
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Hex {
+    pub q: isize,
+    pub r: isize,
+}
+
+impl Hex {
+    pub const fn new(q: isize, r: isize) -> Self {
+        Self { q, r }
+    }
+
+    /// The neighbour towards `dir`
+    pub const fn neighbour(self, dir: HexDirection) -> Self {
+        let shift = dir.to_shift();
+        Self::new(self.q + shift.q, self.r + shift.r)
+    }
+}
+
+/// Heading towards one of the six neighbours of a hex cell, counterclockwise from east
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HexDirection {
+    East = 0,
+    NorthEast = 1,
+    NorthWest = 2,
+    West = 3,
+    SouthWest = 4,
+    SouthEast = 5,
+}
+
+impl HexDirection {
+    pub const VARIANTS: [HexDirection; 6] = [
+        HexDirection::East,
+        HexDirection::NorthEast,
+        HexDirection::NorthWest,
+        HexDirection::West,
+        HexDirection::SouthWest,
+        HexDirection::SouthEast,
+    ];
+
+    pub const fn turn(self, turn: HexTurn) -> Self {
+        let i = (self as isize + turn.sixths()).rem_euclid(Self::VARIANTS.len() as _);
+        Self::VARIANTS[i as usize]
+    }
+
+    pub const fn to_shift(self) -> Hex {
+        match self {
+            HexDirection::East => Hex::new(1, 0),
+            HexDirection::NorthEast => Hex::new(1, -1),
+            HexDirection::NorthWest => Hex::new(0, -1),
+            HexDirection::West => Hex::new(-1, 0),
+            HexDirection::SouthWest => Hex::new(-1, 1),
+            HexDirection::SouthEast => Hex::new(0, 1),
+        }
+    }
+}
+
+impl FromStr for HexDirection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "e" | "east" => Ok(HexDirection::East),
+            "ne" | "north-east" => Ok(HexDirection::NorthEast),
+            "nw" | "north-west" => Ok(HexDirection::NorthWest),
+            "w" | "west" => Ok(HexDirection::West),
+            "sw" | "south-west" => Ok(HexDirection::SouthWest),
+            "se" | "south-east" => Ok(HexDirection::SouthEast),
+            _ => Err(format!(
+                "unknown hex direction {s:?}, expected one of e, ne, nw, w, sw, se"
+            )),
+        }
+    }
+}
+
+/// Which way a hex turmite turns, by sixths of a full turn
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HexTurn {
+    /// Straight on, `N`
+    None,
+    /// 60° clockwise, `R`
+    Right1,
+    /// 120° clockwise, `r`
+    Right2,
+    /// Back the way it came, `U`
+    UTurn,
+    /// 120° counterclockwise, `l`
+    Left2,
+    /// 60° counterclockwise, `L`
+    Left1,
+}
+
+impl HexTurn {
+    /// Counterclockwise sixths
+    pub(crate) const fn sixths(self) -> isize {
+        match self {
+            HexTurn::None => 0,
+            HexTurn::Left1 => 1,
+            HexTurn::Left2 => 2,
+            HexTurn::UTurn => 3,
+            HexTurn::Right2 => -2,
+            HexTurn::Right1 => -1,
+        }
+    }
+
+    pub(crate) const fn letter(self) -> char {
+        match self {
+            HexTurn::None => 'N',
+            HexTurn::Right1 => 'R',
+            HexTurn::Right2 => 'r',
+            HexTurn::UTurn => 'U',
+            HexTurn::Left2 => 'l',
+            HexTurn::Left1 => 'L',
+        }
+    }
+}
+
+/// Hex turmite rule in Golly's letter notation, e.g. `"LR"` or `"LRrrrN"`
+///
+/// Letters are case-sensitive: `L`/`R` turn by 60°, `l`/`r` by 120°,
+/// `N` goes straight on and `U` turns around
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HexRule(Vec<HexTurn>);
+
+impl HexRule {
+    pub fn new(turns: Vec<HexTurn>) -> Result<Self, ParseHexRuleError> {
+        match turns.len() {
+            2..=crate::Rule::MAX_COLORS => Ok(Self(turns)),
+            len => Err(ParseHexRuleError::Length(len)),
+        }
+    }
+
+    /// Number of colors cells cycle through
+    pub fn colors(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn turn(&self, color: u8) -> HexTurn {
+        self.0[color as usize]
+    }
+
+    pub fn next_color(&self, color: u8) -> u8 {
+        ((color as usize + 1) % self.0.len()) as u8
+    }
+}
+
+impl FromStr for HexRule {
+    type Err = ParseHexRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let turns = s
+            .chars()
+            .map(|c| match c {
+                'N' => Ok(HexTurn::None),
+                'R' => Ok(HexTurn::Right1),
+                'r' => Ok(HexTurn::Right2),
+                'U' => Ok(HexTurn::UTurn),
+                'l' => Ok(HexTurn::Left2),
+                'L' => Ok(HexTurn::Left1),
+                _ => Err(ParseHexRuleError::Letter(c)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Self::new(turns)
+    }
+}
+
+impl Display for HexRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for turn in &self.0 {
+            write!(f, "{}", turn.letter())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseHexRuleError {
+    Letter(char),
+    Length(usize),
+}
+
+impl Display for ParseHexRuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseHexRuleError::Letter(c) => {
+                write!(f, "unknown hex turn {c:?}, expected N, R, r, U, l or L")
+            }
+            ParseHexRuleError::Length(len) => write!(
+                f,
+                "rule has {len} colors, expected 2 to {}",
+                crate::Rule::MAX_COLORS
+            ),
+        }
+    }
+}
+
+impl Error for ParseHexRuleError {}
+
+/// `width`x`height` rectangle of hex cells, odd rows sit half a cell further right
+#[derive(Clone)]
+pub struct HexMap {
+    width: usize,
+    height: usize,
+    cells: Vec<u8>,
+}
+
+impl HexMap {
+    pub fn new_white(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![0; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Cell in the middle of the rectangle
+    pub fn center(&self) -> Hex {
+        Self::to_axial(self.width / 2, self.height / 2)
+    }
+
+    /// Axial coordinates of the cell in column `col` of row `row`
+    pub fn to_axial(col: usize, row: usize) -> Hex {
+        let r = row as isize;
+        Hex::new(col as isize - (r - (r & 1)) / 2, r)
+    }
+
+    /// Row-major index of `hex`, `None` outside the rectangle
+    fn index(&self, hex: Hex) -> Option<usize> {
+        let col = hex.q + (hex.r - (hex.r & 1)) / 2;
+        let inside =
+            (0..self.width as isize).contains(&col) && (0..self.height as isize).contains(&hex.r);
+        inside.then(|| hex.r as usize * self.width + col as usize)
+    }
+
+    pub fn contains(&self, hex: Hex) -> bool {
+        self.index(hex).is_some()
+    }
+
+    pub fn color(&self, hex: Hex) -> Option<u8> {
+        self.index(hex).map(|i| self.cells[i])
+    }
+
+    /// Returns whether `hex` is on the map
+    pub fn set_color(&mut self, hex: Hex, color: u8) -> bool {
+        self.index(hex).map(|i| self.cells[i] = color).is_some()
+    }
+
+    /// Cell colors row by row
+    pub fn colors(&self) -> &[u8] {
+        &self.cells
+    }
+
+    /// How many cells hold each of `colors` colors
+    pub fn count_colors(&self, colors: usize) -> Vec<usize> {
+        let mut counts = vec![0; colors];
+        for &c in &self.cells {
+            counts[c as usize] += 1;
+        }
+        counts
+    }
+
+    /// Draw every cell as a hexagon `radius` pixels from center to corner into an
+    /// 8-bit grayscale PNG, colors fade from white (`0`) to black (`colors - 1`)
+    #[cfg(feature = "io")]
+    pub fn save(
+        &self,
+        colors: usize,
+        radius: usize,
+        file: impl AsRef<Path>,
+    ) -> Result<(), EncodingError> {
+        let radius = radius.max(1) as f64;
+        let cell_width = 3f64.sqrt() * radius;
+        let width = (cell_width * (self.width as f64 + 0.5)).ceil() as usize;
+        let height = (radius * (1.5 * self.height as f64 + 0.5)).ceil() as usize;
+        let darkest = colors.saturating_sub(1).max(1);
+
+        let mut data = vec![255; width * height];
+        for (py, row) in data.chunks_mut(width).enumerate() {
+            for (px, pixel) in row.iter_mut().enumerate() {
+                // Relative to the center of cell (0, 0)
+                let x = px as f64 + 0.5 - cell_width / 2.0;
+                let y = py as f64 + 0.5 - radius;
+                let q = (3f64.sqrt() / 3.0 * x - y / 3.0) / radius;
+                let r = 2.0 / 3.0 * y / radius;
+                if let Some(c) = self.color(round(q, r)) {
+                    *pixel = (255 - c as usize * 255 / darkest) as u8;
+                }
+            }
+        }
+
+        let w = BufWriter::new(File::create(file)?);
+        let mut encoder = Encoder::new(w, width as _, height as _);
+        encoder.set_color(ColorType::Grayscale);
+        encoder.set_depth(BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)
+    }
+}
+
+/// The hex holding fractional axial coordinates, rounded in cube coordinates
+#[cfg(feature = "io")]
+fn round(q: f64, r: f64) -> Hex {
+    let s = -q - r;
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+    Hex::new(rq as isize, rr as isize)
+}
+
+/// Turmite walking a [`HexMap`]
+pub struct HexAnt<'m> {
+    map: &'m mut HexMap,
+    rule: HexRule,
+    pos: Hex,
+    dir: HexDirection,
+}
+
+impl<'m> HexAnt<'m> {
+    /// Returns `Err(pos)` if `pos` isn't on the map
+    pub fn new(
+        map: &'m mut HexMap,
+        pos: Hex,
+        dir: HexDirection,
+        rule: HexRule,
+    ) -> Result<Self, Hex> {
+        match map.contains(pos) {
+            true => Ok(Self {
+                map,
+                rule,
+                pos,
+                dir,
+            }),
+            false => Err(pos),
+        }
+    }
+
+    pub fn pos(&self) -> Hex {
+        self.pos
+    }
+
+    pub fn dir(&self) -> HexDirection {
+        self.dir
+    }
+
+    /// Returns whether the ant can walk any further
+    pub fn walk(&mut self) -> bool {
+        let color = self.map.color(self.pos).expect("the ant is on the map");
+        self.map.set_color(self.pos, self.rule.next_color(color));
+
+        self.dir = self.dir.turn(self.rule.turn(color));
+        self.pos = self.pos.neighbour(self.dir);
+        self.map.contains(self.pos)
+    }
+
+    pub fn walk_until_end(&mut self) {
+        while self.walk() {}
+    }
+
+    /// Walk until the ant leaves the map or `steps` steps are done,
+    /// returns whether the ant is still on the map
+    pub fn walk_at_most(&mut self, steps: usize) -> bool {
+        (0..steps).all(|_| self.walk())
+    }
+}
+
+#[test]
+fn hex_ant_walks() {
+    assert_eq!(
+        HexDirection::East.turn(HexTurn::Left1),
+        HexDirection::NorthEast
+    );
+    assert_eq!(
+        HexDirection::East.turn(HexTurn::Right2),
+        HexDirection::SouthWest
+    );
+    assert_eq!(HexDirection::West.turn(HexTurn::UTurn), HexDirection::East);
+    let rule = "LRrrrN".parse::<HexRule>().unwrap();
+    assert_eq!(rule.to_string(), "LRrrrN");
+    assert_eq!("LX".parse::<HexRule>(), Err(ParseHexRuleError::Letter('X')));
+
+    // Odd rows are shifted right, so south-east of (3, 1) is column 4 of row 2
+    let map = HexMap::new_white(8, 8);
+    let hex = HexMap::to_axial(3, 1);
+    let shift = HexDirection::SouthEast.to_shift();
+    assert_eq!(
+        Hex::new(hex.q + shift.q, hex.r + shift.r),
+        HexMap::to_axial(4, 2)
+    );
+    assert!(map.contains(HexMap::to_axial(7, 7)));
+    assert!(!map.contains(HexMap::to_axial(8, 7)));
+
+    // Six left turns on white walk around a single hex and come back
+    let mut map = HexMap::new_white(16, 16);
+    let start = map.center();
+    let mut ant = HexAnt::new(&mut map, start, HexDirection::East, "LR".parse().unwrap()).unwrap();
+    assert!(ant.walk_at_most(6));
+    assert_eq!(ant.pos(), start);
+    assert_eq!(map.count_colors(2), [256 - 6, 6]);
+
+    #[cfg(feature = "io")]
+    {
+        let file = std::env::temp_dir().join("antventure_hex_ant_walks.png");
+        map.save(2, 3, &file).unwrap();
+        let image = image::open(&file).unwrap().into_luma8();
+        // The center of the ant's first cell is black, the top left corner is white
+        let cell_width = 3f64.sqrt() * 3.0;
+        let x = cell_width * 8.0 + cell_width / 2.0;
+        let y = 3.0 * 1.5 * 8.0 + 3.0;
+        assert_eq!(image.get_pixel(x as u32, y as u32).0, [0]);
+        assert_eq!(image.get_pixel(0, 0).0, [255]);
+    }
+}
@@ -0,0 +1,187 @@
+use std::{path::Path, str::FromStr};
+
+use image::{imageops::FilterType, DynamicImage, GenericImageView, ImageResult, Pixel};
+
+use crate::{ColorMap, ControlHandle, Map, Obstacles};
+
+/// Image channel deciding whether a cell starts black
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Channel {
+    /// Perceived brightness
+    #[default]
+    Luma,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl FromStr for Channel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "luma" | "gray" => Ok(Channel::Luma),
+            "r" | "red" => Ok(Channel::Red),
+            "g" | "green" => Ok(Channel::Green),
+            "b" | "blue" => Ok(Channel::Blue),
+            "a" | "alpha" => Ok(Channel::Alpha),
+            _ => Err(format!(
+                "unknown channel {s:?}, expected luma, red, green, blue or alpha"
+            )),
+        }
+    }
+}
+
+/// How an image turns into an initial board
+#[derive(Clone, Debug)]
+pub struct ImportOptions {
+    /// Cells whose channel value is below this start black
+    pub threshold: u8,
+    /// Swap black and white after thresholding
+    pub invert: bool,
+    /// Scale the image to fit the board keeping its aspect ratio, centered.
+    /// Otherwise it's placed 1:1 at the top left corner and cropped
+    pub fit: bool,
+    pub channel: Channel,
+    /// Stops the import once cancelled, the rows not read yet stay white
+    pub control: ControlHandle,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 128,
+            invert: false,
+            fit: false,
+            channel: Channel::Luma,
+            control: ControlHandle::new(),
+        }
+    }
+}
+
+impl ImportOptions {
+    /// Threshold the image onto a `width`x`height` board, `true` is a black cell.
+    /// Cells the image doesn't cover stay white
+    pub fn black_cells(&self, image: &DynamicImage, width: usize, height: usize) -> Vec<bool> {
+        let resized;
+        let (image, x0, y0) = match self.fit {
+            true => {
+                resized = image.resize(width as _, height as _, FilterType::Triangle);
+                let x0 = (width - resized.width() as usize) / 2;
+                let y0 = (height - resized.height() as usize) / 2;
+                (&resized, x0, y0)
+            }
+            false => (image, 0, 0),
+        };
+
+        let mut cells = vec![false; width * height];
+        let w = (image.width() as usize).min(width - x0);
+        let h = (image.height() as usize).min(height - y0);
+
+        for y in 0..h {
+            if self.control.is_cancelled() {
+                break;
+            }
+            for x in 0..w {
+                let pixel = image.get_pixel(x as _, y as _);
+                let value = match self.channel {
+                    Channel::Luma => pixel.to_luma().0[0],
+                    Channel::Red => pixel.0[0],
+                    Channel::Green => pixel.0[1],
+                    Channel::Blue => pixel.0[2],
+                    Channel::Alpha => pixel.0[3],
+                };
+                cells[(y0 + y) * width + x0 + x] = (value < self.threshold) != self.invert;
+            }
+        }
+
+        cells
+    }
+}
+
+impl<const W: usize, const H: usize> Map<W, H> {
+    /// Threshold an image into a starting board
+    pub fn import(image: &DynamicImage, options: &ImportOptions) -> Self {
+        let cells = options.black_cells(image, W, H);
+        Self::from_cells(cells.into_iter().map(|black| !black))
+    }
+
+    /// Open an image and turn pixels darker than `threshold` into black cells,
+    /// placed 1:1 at the top left corner, see [`import`](Self::import) for more options
+    pub fn from_image(path: impl AsRef<Path>, threshold: u8) -> ImageResult<Self> {
+        let image = image::open(path)?;
+        let options = ImportOptions {
+            threshold,
+            ..ImportOptions::default()
+        };
+        Ok(Self::import(&image, &options))
+    }
+}
+
+impl<const W: usize, const H: usize> ColorMap<W, H> {
+    /// Threshold an image into a starting board, black cells get color `1`
+    pub fn import(image: &DynamicImage, options: &ImportOptions) -> Self {
+        let cells = options.black_cells(image, W, H);
+        Self::from_colors(cells.into_iter().map(|black| black as u8))
+    }
+}
+
+impl<const W: usize, const H: usize> Obstacles<W, H> {
+    /// Threshold a mask image, dark cells become obstacles
+    pub fn import(image: &DynamicImage, options: &ImportOptions) -> Self {
+        Self::from_cells(options.black_cells(image, W, H))
+    }
+}
+
+#[test]
+fn threshold_and_place() {
+    use image::{Rgba, RgbaImage};
+
+    // Left half dark red, right half bright blue
+    let image = RgbaImage::from_fn(4, 2, |x, _| match x < 2 {
+        true => Rgba([120, 0, 0, 255]),
+        false => Rgba([0, 0, 250, 255]),
+    });
+    let image = DynamicImage::ImageRgba8(image);
+
+    let options = ImportOptions::default();
+    let cells = options.black_cells(&image, 6, 2);
+    assert_eq!(cells[..6], [true, true, true, true, false, false]);
+
+    let options = ImportOptions {
+        channel: Channel::Blue,
+        invert: true,
+        ..Default::default()
+    };
+    let cells = options.black_cells(&image, 6, 2);
+    assert_eq!(cells[6..], [false, false, true, true, false, false]);
+
+    // Fitting a 4x2 image onto 8x8 scales it 2x and centers it vertically
+    let options = ImportOptions {
+        fit: true,
+        ..Default::default()
+    };
+    let cells = options.black_cells(&image, 8, 8);
+    assert!(!cells[..8 * 2].contains(&true));
+    assert!(cells[8 * 2..8 * 6].iter().all(|&black| black));
+}
+
+#[test]
+fn map_from_image_file() {
+    use crate::{MapPos, Pos};
+    use image::{GrayImage, Luma};
+
+    // A diagonal gradient, darker towards the bottom right
+    let image = GrayImage::from_fn(8, 8, |x, y| Luma([255 - (x + y) as u8 * 16]));
+    let file = std::env::temp_dir().join("antventure_map_from_image_file.png");
+    image.save(&file).unwrap();
+
+    let map = Map::<16, 16>::from_image(&file, 100).unwrap();
+    let is_white = |x, y| map.get(MapPos::validate_pos(Pos::new(x, y)).unwrap());
+    assert!(is_white(0, 0));
+    assert!(!is_white(7, 7));
+    // Outside of the image
+    assert!(is_white(15, 15));
+    assert!(Map::<16, 16>::from_image(file.with_extension("missing"), 100).is_err());
+}
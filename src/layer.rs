@@ -0,0 +1,594 @@
+#[cfg(feature = "io")]
+use std::io::{self, Write};
+use std::{fmt::Display, str::FromStr};
+
+#[cfg(feature = "io")]
+use png::{BitDepth, ColorType, Encoder};
+
+#[cfg(feature = "io")]
+use crate::{color::tag_srgb, Renderer};
+use crate::{
+    heatmap::{fire, gray_heat},
+    linear_to_srgb, srgb_to_linear, Frame, HeatColors, Heatmap, ImageOrientation, Palette, Pos,
+    TrajectoryPoint,
+};
+
+/// One layer of a picture, painted over the layers below it
+///
+/// Pixels are 4 bytes per cell of the frame, RGBA row by row like
+/// [`Frame::fill_rgba`], layers leave alone the pixels they have nothing to say about
+pub trait Layer {
+    fn paint(&self, frame: &Frame, rgba: &mut [u8]);
+}
+
+/// The cells in the palette colors, or in grays fading from white to black
+#[derive(Clone, Debug, Default)]
+pub struct CellLayer {
+    /// Needs an entry for each color
+    pub palette: Option<Palette>,
+}
+
+impl Layer for CellLayer {
+    fn paint(&self, frame: &Frame, rgba: &mut [u8]) {
+        let gray = Palette::gray(frame.colors);
+        let palette = self.palette.as_ref().unwrap_or(&gray);
+        for (pixel, &cell) in rgba.chunks_exact_mut(4).zip(&frame.cells) {
+            let [r, g, b] = palette.colors[cell as usize];
+            pixel.copy_from_slice(&[r, g, b, 255]);
+        }
+    }
+}
+
+/// Visit counts of a [`Heatmap`] over the cells ants stood on, cells never visited
+/// show the layers below
+#[derive(Clone, Debug)]
+pub struct HeatLayer {
+    intensities: Vec<f64>,
+    width: usize,
+    colors: HeatColors,
+}
+
+impl HeatLayer {
+    pub fn new<const W: usize, const H: usize>(
+        heatmap: &Heatmap<W, H>,
+        colors: HeatColors,
+    ) -> Self {
+        Self {
+            intensities: heatmap.intensities(),
+            width: W,
+            colors,
+        }
+    }
+}
+
+impl Layer for HeatLayer {
+    fn paint(&self, frame: &Frame, rgba: &mut [u8]) {
+        for (i, pixel) in rgba.chunks_exact_mut(4).enumerate() {
+            let pos = frame_to_map(frame, i);
+            let Some(&intensity) = map_index(pos, self.width, self.intensities.len())
+                .map(|i| &self.intensities[i])
+                .filter(|&&intensity| intensity > 0.0)
+            else {
+                continue;
+            };
+            let [r, g, b] = match self.colors {
+                HeatColors::Gray => [gray_heat(intensity); 3],
+                HeatColors::Fire => fire(intensity),
+            };
+            pixel.copy_from_slice(&[r, g, b, 255]);
+        }
+    }
+}
+
+/// Map cells in one color, e.g. where an ant went
+#[derive(Clone, Debug)]
+pub struct TrailLayer {
+    pub cells: Vec<Pos>,
+    pub color: [u8; 3],
+}
+
+impl Layer for TrailLayer {
+    fn paint(&self, frame: &Frame, rgba: &mut [u8]) {
+        let [r, g, b] = self.color;
+        for &cell in &self.cells {
+            let pos = Pos::new(cell.x - frame.origin.x, cell.y - frame.origin.y);
+            if let Some(i) = frame_index(frame, pos) {
+                rgba[i * 4..i * 4 + 4].copy_from_slice(&[r, g, b, 255]);
+            }
+        }
+    }
+}
+
+/// Where the ants went, from dark for the oldest points to the full color for the
+/// latest, so the chaotic phase and the highway after it tell apart in one picture
+///
+/// Points come from a [`TrajectoryTail`](crate::TrajectoryTail) or any other
+/// trajectory, later points paint over earlier ones on the same cell
+#[derive(Clone, Debug)]
+pub struct PathLayer {
+    /// Cells and how recent they are, from `0.0` for the oldest to `1.0`, oldest first
+    cells: Vec<(Pos, f32)>,
+    pub color: [u8; 3],
+}
+
+impl PathLayer {
+    /// Light of the oldest point relative to the latest one
+    const DARKEST: f32 = 0.15;
+
+    pub fn new(points: &[TrajectoryPoint], color: [u8; 3]) -> Self {
+        let first = points.iter().map(|point| point.step).min().unwrap_or(0);
+        let last = points.iter().map(|point| point.step).max().unwrap_or(0);
+        let span = (last - first).max(1) as f32;
+        let mut cells = points
+            .iter()
+            .map(|point| {
+                let pos = Pos::new(point.x as isize, point.y as isize);
+                (pos, (point.step - first) as f32 / span)
+            })
+            .collect::<Vec<_>>();
+        cells.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        Self { cells, color }
+    }
+}
+
+impl Layer for PathLayer {
+    fn paint(&self, frame: &Frame, rgba: &mut [u8]) {
+        let color = self.color.map(srgb_to_linear);
+        for &(cell, age) in &self.cells {
+            let pos = Pos::new(cell.x - frame.origin.x, cell.y - frame.origin.y);
+            if let Some(i) = frame_index(frame, pos) {
+                let light = Self::DARKEST + (1.0 - Self::DARKEST) * age;
+                let [r, g, b] = color.map(|channel| linear_to_srgb(channel * light));
+                rgba[i * 4..i * 4 + 4].copy_from_slice(&[r, g, b, 255]);
+            }
+        }
+    }
+}
+
+/// A plus over every ant of the frame, clipped at the edges
+#[derive(Clone, Debug)]
+pub struct AntLayer {
+    pub color: [u8; 3],
+}
+
+impl Default for AntLayer {
+    fn default() -> Self {
+        Self {
+            color: Palette::RED,
+        }
+    }
+}
+
+impl Layer for AntLayer {
+    fn paint(&self, frame: &Frame, rgba: &mut [u8]) {
+        let [r, g, b] = self.color;
+        for ant in &frame.ants {
+            for (dx, dy) in [(0, 0), (-1, 0), (1, 0), (0, -1), (0, 1)] {
+                if let Some(i) = frame_index(frame, Pos::new(ant.x + dx, ant.y + dy)) {
+                    rgba[i * 4..i * 4 + 4].copy_from_slice(&[r, g, b, 255]);
+                }
+            }
+        }
+    }
+}
+
+/// Named [`Layer`]s painted bottom to top into an RGB PNG, so a picture is put
+/// together from parts rather than picked from a fixed set of styles
+///
+/// Every layer can be hidden or see-through, set one by one or all at once from a
+/// [`Composition`], so exports, animations and viewers draw the same stack
+///
+/// ```
+/// use antventure::{AntLayer, CellLayer, Frame, LayeredRenderer, Map, Pos, TrailLayer};
+///
+/// let renderer = LayeredRenderer::new()
+///     .with_layer("cells", CellLayer::default())
+///     .with_layer("trail", TrailLayer { cells: vec![Pos::new(1, 1)], color: [0, 0, 255] })
+///     .with_layer("ants", AntLayer::default());
+/// let frame = Frame::new(&Map::<4, 4>::new_white(), 2, None);
+/// assert_eq!(renderer.paint(&frame)[5 * 4..5 * 4 + 4], [0, 0, 255, 255]);
+/// ```
+#[derive(Default)]
+pub struct LayeredRenderer {
+    layers: Vec<Entry>,
+    orientation: ImageOrientation,
+}
+
+struct Entry {
+    name: String,
+    layer: Box<dyn Layer>,
+    setting: LayerSetting,
+}
+
+impl LayeredRenderer {
+    /// No layers, frames come out white
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Put `layer` on top, or in place of the layer called `name` if there is one,
+    /// which keeps its place and setting
+    pub fn with_layer(mut self, name: impl Into<String>, layer: impl Layer + 'static) -> Self {
+        let name = name.into();
+        let layer = Box::new(layer);
+        match self.entry(&name) {
+            Some(entry) => entry.layer = layer,
+            None => self.layers.push(Entry {
+                name,
+                layer,
+                setting: LayerSetting::default(),
+            }),
+        }
+        self
+    }
+
+    pub fn without_layer(mut self, name: &str) -> Self {
+        self.layers.retain(|entry| entry.name != name);
+        self
+    }
+
+    /// How much of the layer called `name` shows, from `0.0` for nothing to `1.0`
+    /// for all of it
+    pub fn with_opacity(mut self, name: &str, opacity: f32) -> Self {
+        if let Some(entry) = self.entry(name) {
+            entry.setting.opacity = opacity.clamp(0.0, 1.0);
+        }
+        self
+    }
+
+    /// Show or hide the layer called `name`, returns whether there is one. For
+    /// viewers turning layers on and off as they go
+    pub fn set_visible(&mut self, name: &str, visible: bool) -> bool {
+        self.entry(name)
+            .map(|entry| entry.setting.visible = visible)
+            .is_some()
+    }
+
+    pub fn is_visible(&self, name: &str) -> bool {
+        self.layers
+            .iter()
+            .any(|entry| entry.name == name && entry.setting.visible)
+    }
+
+    /// Stack and set up the layers as `composition` says: the layers it names go
+    /// first in its order, the ones it leaves out are hidden. Names without a layer
+    /// are skipped
+    pub fn with_composition(mut self, composition: &Composition) -> Self {
+        let mut layers = Vec::with_capacity(self.layers.len());
+        for (name, setting) in &composition.0 {
+            if let Some(i) = self.layers.iter().position(|entry| entry.name == *name) {
+                let mut entry = self.layers.remove(i);
+                entry.setting = *setting;
+                layers.push(entry);
+            }
+        }
+        for mut entry in self.layers {
+            entry.setting.visible = false;
+            layers.push(entry);
+        }
+        self.layers = layers;
+        self
+    }
+
+    /// Flip and turn what the layers paint, which read the map through frames as
+    /// they are. A quarter turn swaps the width and height of the pixels
+    pub fn with_orientation(mut self, orientation: ImageOrientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Names of the layers from the bottom up
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.layers.iter().map(|entry| entry.name.as_str())
+    }
+
+    /// RGBA pixels of the frame with every visible layer painted in, oriented as
+    /// [`with_orientation`](Self::with_orientation) says
+    pub fn paint(&self, frame: &Frame) -> Vec<u8> {
+        let mut rgba = vec![0; frame.cells.len() * 4];
+        self.paint_into(frame, &mut rgba);
+        rgba
+    }
+
+    /// [`paint`](Self::paint) into a buffer of 4 bytes per cell, e.g. the one
+    /// a viewer draws every frame from
+    ///
+    /// # Panics
+    ///
+    /// If `rgba` isn't 4 bytes per cell
+    pub fn paint_into(&self, frame: &Frame, rgba: &mut [u8]) {
+        assert_eq!(rgba.len(), frame.cells.len() * 4, "4 bytes per cell");
+        rgba.fill(255);
+        let mut below = Vec::new();
+        for entry in self.layers.iter().filter(|entry| entry.setting.visible) {
+            let opacity = entry.setting.opacity;
+            if opacity >= 1.0 {
+                entry.layer.paint(frame, rgba);
+                continue;
+            }
+            // See-through layers are painted on a copy and mixed into what's below
+            // in linear light
+            below.clear();
+            below.extend_from_slice(rgba);
+            entry.layer.paint(frame, rgba);
+            for (pixel, &under) in rgba.iter_mut().zip(&below) {
+                if *pixel != under {
+                    let under = srgb_to_linear(under);
+                    *pixel = linear_to_srgb(under + (srgb_to_linear(*pixel) - under) * opacity);
+                }
+            }
+        }
+        if !self.orientation.is_identity() {
+            let oriented = self.orientation.pixels(rgba, 4, frame.width, frame.height);
+            rgba.copy_from_slice(&oriented);
+        }
+    }
+
+    fn entry(&mut self, name: &str) -> Option<&mut Entry> {
+        self.layers.iter_mut().find(|entry| entry.name == name)
+    }
+}
+
+/// Whether a layer shows and how much of it
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LayerSetting {
+    pub visible: bool,
+    /// From `0.0` for nothing to `1.0` for all of it
+    pub opacity: f32,
+}
+
+impl Default for LayerSetting {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            opacity: 1.0,
+        }
+    }
+}
+
+/// Layers of a [`LayeredRenderer`] bottom up, with their settings
+///
+/// Written as comma separated layer names, each followed by `=off` to hide it or
+/// `=OPACITY` to let the layers below show through, e.g. `cells,heat=0.5,ants=off`
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Composition(pub Vec<(String, LayerSetting)>);
+
+impl Composition {
+    /// Whether the layer called `name` is in and not hidden, so it's worth making
+    pub fn shows(&self, name: &str) -> bool {
+        self.0
+            .iter()
+            .any(|(n, setting)| n == name && setting.visible)
+    }
+}
+
+impl FromStr for Composition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (name, setting) = entry.split_once('=').unwrap_or((entry, "on"));
+                let setting = match setting.trim().to_ascii_lowercase().as_str() {
+                    "on" => LayerSetting::default(),
+                    "off" => LayerSetting {
+                        visible: false,
+                        ..LayerSetting::default()
+                    },
+                    opacity => match opacity.parse::<f32>() {
+                        Ok(opacity) if (0.0..=1.0).contains(&opacity) => LayerSetting {
+                            opacity,
+                            ..LayerSetting::default()
+                        },
+                        _ => {
+                            return Err(format!(
+                                "bad setting {setting:?} of layer {name:?}, expected on, off or an opacity from 0 to 1"
+                            ))
+                        }
+                    },
+                };
+                Ok((name.trim().to_string(), setting))
+            })
+            .collect::<Result<_, _>>()
+            .map(Composition)
+    }
+}
+
+impl Display for Composition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (name, setting)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{name}")?;
+            match (setting.visible, setting.opacity) {
+                (false, _) => write!(f, "=off")?,
+                (true, opacity) if opacity < 1.0 => write!(f, "={opacity}")?,
+                (true, _) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "io")]
+impl Renderer for LayeredRenderer {
+    fn render(&self, frame: &Frame, w: &mut dyn Write) -> io::Result<()> {
+        let rgb = self
+            .paint(frame)
+            .chunks_exact(4)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+            .collect::<Vec<_>>();
+
+        let (width, height) = self.orientation.size(frame.width, frame.height);
+        let mut encoder = Encoder::new(w, width as _, height as _);
+        encoder.set_color(ColorType::Rgb);
+        encoder.set_depth(BitDepth::Eight);
+        tag_srgb(&mut encoder);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&rgb))
+            .map_err(io::Error::other)
+    }
+}
+
+/// Map position of pixel `i` of the frame
+fn frame_to_map(frame: &Frame, i: usize) -> Pos {
+    Pos::new(
+        frame.origin.x + (i % frame.width) as isize,
+        frame.origin.y + (i / frame.width) as isize,
+    )
+}
+
+/// Index of `pos` relative to the top left cell, `None` outside the frame
+fn frame_index(frame: &Frame, pos: Pos) -> Option<usize> {
+    let inside =
+        (0..frame.width as isize).contains(&pos.x) && (0..frame.height as isize).contains(&pos.y);
+    inside.then(|| pos.y as usize * frame.width + pos.x as usize)
+}
+
+/// Row-major index of `pos` on a map `width` cells wide and `len` cells big
+fn map_index(pos: Pos, width: usize, len: usize) -> Option<usize> {
+    let inside =
+        (0..width as isize).contains(&pos.x) && (0..(len / width) as isize).contains(&pos.y);
+    inside.then(|| pos.y as usize * width + pos.x as usize)
+}
+
+#[test]
+fn layers_paint_in_order() {
+    use crate::{Crop, Direction, Map, MapPos, Rule, Simulation};
+
+    let mut sim = Simulation::new(Map::<8, 8>::new_white(), Rule::default()).with_heatmap();
+    sim.spawn(Pos::new(4, 4), Direction::North).unwrap();
+    sim.run_at_most(3);
+    let ant = Pos::from(sim.ants()[0].pos());
+    let crop = Crop {
+        min: Pos::new(2, 2),
+        max: Pos::new(5, 5),
+    };
+    let frame = Frame::new(sim.map(), 2, Some(crop)).with_ants(&[ant]);
+    let pixel = |rgba: &[u8], x: isize, y: isize| {
+        let i = ((y - 2) * 4 + x - 2) as usize * 4;
+        rgba[i..i + 4].to_vec()
+    };
+
+    let renderer = LayeredRenderer::new()
+        .with_layer("cells", CellLayer::default())
+        .with_layer(
+            "heat",
+            HeatLayer::new(sim.heatmap().unwrap(), HeatColors::Fire),
+        );
+    let rgba = renderer.paint(&frame);
+    // The start cell is black, the heat layer paints it over
+    assert!(!sim.map().get(MapPos::validate_pos(Pos::new(4, 4)).unwrap()));
+    assert_eq!(pixel(&rgba, 4, 4), [255, 255, 255, 255]);
+    assert_eq!(pixel(&rgba, 2, 2), [255, 255, 255, 255]);
+
+    let renderer = renderer
+        .without_layer("heat")
+        .with_layer(
+            "trail",
+            TrailLayer {
+                cells: vec![Pos::new(2, 2), Pos::new(0, 0)],
+                color: [0, 0, 255],
+            },
+        )
+        .with_layer("ants", AntLayer::default())
+        .with_layer(
+            "cells",
+            CellLayer {
+                palette: Some("#fff,#0f0".parse().unwrap()),
+            },
+        );
+    assert_eq!(
+        renderer.names().collect::<Vec<_>>(),
+        ["cells", "trail", "ants"]
+    );
+    let rgba = renderer.paint(&frame);
+    assert_eq!(pixel(&rgba, 3, 4), [0, 255, 0, 255]);
+    assert_eq!(pixel(&rgba, 2, 2), [0, 0, 255, 255]);
+    assert_eq!(pixel(&rgba, ant.x, ant.y), [255, 0, 0, 255]);
+
+    // Mirrored top to bottom the trail cell at (2, 2) is painted on the bottom row
+    let flip = ImageOrientation {
+        flip_y: true,
+        ..Default::default()
+    };
+    let flipped = renderer.with_orientation(flip);
+    let rgba = flipped.paint(&frame);
+    assert_eq!(pixel(&rgba, 2, 5), [0, 0, 255, 255]);
+    assert_eq!(pixel(&rgba, ant.x, 7 - ant.y), [255, 0, 0, 255]);
+
+    #[cfg(feature = "io")]
+    {
+        let renderer = flipped.with_orientation(ImageOrientation::default());
+        let mut png = Vec::new();
+        renderer.render(&frame, &mut png).unwrap();
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (4, 4));
+        assert_eq!(data[..3], [0, 0, 255]);
+    }
+}
+
+#[test]
+fn compositions_set_up_layers() {
+    use crate::Map;
+
+    let composition = "ants=off, trail=0.5 ,cells".parse::<Composition>().unwrap();
+    assert_eq!(composition.to_string(), "ants=off,trail=0.5,cells");
+    assert!(composition.shows("trail") && !composition.shows("ants"));
+    assert!("cells=2".parse::<Composition>().is_err());
+
+    let trail = TrailLayer {
+        cells: vec![Pos::new(0, 0)],
+        color: [0, 0, 255],
+    };
+    let mut renderer = LayeredRenderer::new()
+        .with_layer("cells", CellLayer::default())
+        .with_layer("trail", trail)
+        .with_layer("heat", CellLayer::default())
+        .with_composition(&composition);
+    assert_eq!(
+        renderer.names().collect::<Vec<_>>(),
+        ["trail", "cells", "heat"]
+    );
+    assert!(!renderer.is_visible("heat"));
+
+    // The cells cover the trail below them, until they are see-through
+    let frame = Frame::new(&Map::<2, 1>::new_white(), 2, None);
+    assert_eq!(renderer.paint(&frame)[..4], [255, 255, 255, 255]);
+    renderer.set_visible("cells", false);
+    assert_eq!(renderer.paint(&frame)[..4], [188, 188, 255, 255]);
+    let renderer = renderer.with_opacity("trail", 1.0);
+    assert_eq!(
+        renderer.paint(&frame)[..8],
+        [0, 0, 255, 255, 255, 255, 255, 255]
+    );
+}
+
+#[test]
+fn paths_brighten_with_age() {
+    use crate::{Direction, Map, Rule, Simulation, TrajectoryTail};
+
+    let tail = TrajectoryTail::new(100, 1);
+    let mut sim =
+        Simulation::new(Map::<8, 8>::new_white(), Rule::default()).with_observer(tail.clone());
+    sim.spawn(Pos::new(4, 4), Direction::North).unwrap();
+    tail.record(&sim);
+    sim.run_at_most(3);
+
+    // West, south and east around three sides of a square
+    let path = PathLayer::new(&tail.points(), [255, 255, 255]);
+    let frame = Frame::new(sim.map(), 2, None);
+    let mut rgba = vec![0; 8 * 8 * 4];
+    path.paint(&frame, &mut rgba);
+    let light = |x: usize, y: usize| rgba[(y * 8 + x) * 4];
+    assert_eq!(light(4, 4), linear_to_srgb(PathLayer::DARKEST));
+    assert!(light(4, 4) < light(3, 4) && light(3, 4) < light(3, 5));
+    assert_eq!(light(4, 5), 255);
+    assert_eq!(light(0, 0), 0);
+}
@@ -0,0 +1,223 @@
+//! Langton's ant simulation core shared by the `naive` and `serious` binaries
+//!
+//! ```
+//! use antventure::{Ant, Direction, Map, Pos};
+//!
+//! let mut map = Map::<64, 64>::new_white();
+//! let mut ant = Ant::new(&mut map, Pos::new(32, 32), Direction::North).unwrap();
+//! ant.walk_until_end();
+//! ```
+
+#[cfg(feature = "io")]
+mod analysis;
+#[cfg(feature = "io")]
+mod animation;
+mod ant;
+mod bitset;
+mod block_counts;
+#[cfg(any(feature = "wasm", feature = "ffi", feature = "python"))]
+mod board;
+mod cell_channel;
+mod checkpoint;
+mod color;
+mod control;
+mod coords;
+mod cycle;
+mod daily;
+#[cfg(feature = "io")]
+mod deep_zoom;
+mod describe;
+mod digest;
+mod direction;
+mod divergence;
+#[cfg(not(target_arch = "wasm32"))]
+mod driver;
+mod dyn_map;
+mod ensemble;
+mod error;
+mod event;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod figure;
+mod formats;
+mod hatch;
+mod heatmap;
+mod hex;
+#[cfg(feature = "io")]
+mod import;
+mod layer;
+mod life;
+mod macrocell;
+mod manifest;
+mod map;
+#[cfg(feature = "io")]
+mod mmap_map;
+mod mosaic;
+pub mod naive;
+mod noise;
+mod observer;
+mod obstacle;
+mod optimize;
+#[cfg(not(target_arch = "wasm32"))]
+mod pace;
+mod palette;
+mod phase;
+mod plot;
+mod plugin;
+mod pos;
+mod preset;
+#[cfg(feature = "python")]
+mod python;
+mod raster;
+mod raw_video;
+mod relief;
+mod render;
+#[cfg(feature = "io")]
+mod replay;
+#[cfg(feature = "io")]
+mod report;
+mod rle;
+mod rule;
+#[cfg(feature = "sandbox")]
+mod sandbox;
+#[cfg(feature = "io")]
+mod schematic;
+mod search;
+mod shared_map;
+mod simulation;
+mod sink;
+#[cfg(feature = "io")]
+mod snapshot;
+mod sound;
+mod sparse;
+mod state;
+mod stats;
+mod stego;
+mod svg;
+mod sweep;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod thumbnail;
+mod tiling;
+mod timeline;
+mod trajectory;
+mod turmite;
+mod voxel;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod words;
+mod worm;
+
+#[cfg(feature = "io")]
+pub use analysis::{analyze_pbm, analyze_png, analyze_raw, AnalyzeError, BoardStats};
+#[cfg(feature = "io")]
+pub use animation::{Animation, AnimationFormat};
+pub use ant::{Ant, StepEvent, Steps};
+pub use block_counts::{BlockCounts, BLOCK_SIZE};
+pub use cell_channel::{CellChannel, CellVisit, ChannelValue};
+pub use checkpoint::{Checkpoint, CheckpointError, CheckpointSeries, Retention};
+pub use color::{linear_to_srgb, mix_srgb, srgb_to_linear};
+pub use control::{Cancelled, ControlHandle, ControlledWriter};
+pub use coords::{Coordinates, ImageOrientation, Oriented, Origin, YAxis};
+pub use cycle::{CycleDetector, Periodicity};
+pub use daily::{Daily, Date};
+#[cfg(feature = "io")]
+pub use deep_zoom::{DeepZoom, Pyramid};
+pub use describe::Description;
+pub use direction::{Direction, ParseDirectionError};
+pub use divergence::{hamming_distance, similarity, Divergence};
+#[cfg(not(target_arch = "wasm32"))]
+pub use driver::{mailbox, Answer, Driver, DriverHandle, Mailbox, Postbox, Promise};
+pub use dyn_map::{DynMap, DynPos};
+pub use ensemble::Ensemble;
+pub use error::AntventureError;
+pub use event::{EventKind, EventKinds, SimEvent};
+#[cfg(feature = "ffi")]
+pub use ffi::{
+    antventure_colors, antventure_free, antventure_map_ptr, antventure_new, antventure_size,
+    antventure_spawn, antventure_step_n, antventure_steps, AntventureSim, ANTVENTURE_EAST,
+    ANTVENTURE_NORTH, ANTVENTURE_SOUTH, ANTVENTURE_WEST,
+};
+pub use figure::{Figure, FigureFormat};
+pub use formats::{Format, REPLAY_MAGIC, REPLAY_VERSION, STATE_MAGIC, STATE_VERSION};
+#[cfg(feature = "io")]
+pub use hatch::save_hatched_map_to_file;
+pub use hatch::Hatch;
+pub use heatmap::{HeatColors, Heatmap};
+pub use hex::{Hex, HexAnt, HexDirection, HexMap, HexRule, HexTurn, ParseHexRuleError};
+#[cfg(feature = "io")]
+pub use import::{Channel, ImportOptions};
+pub use layer::{
+    AntLayer, CellLayer, Composition, HeatLayer, Layer, LayerSetting, LayeredRenderer, PathLayer,
+    TrailLayer,
+};
+pub use life::{count_neighbours, LifeRule};
+pub use macrocell::write_macrocell;
+pub use manifest::{Artifact, ArtifactKind, Manifest};
+pub use map::{CellMut, CellStore, ColorMap, DiffStats, Map, RowWord};
+#[cfg(feature = "io")]
+pub use mmap_map::MmapMap;
+pub use mosaic::Mosaic;
+pub use noise::RandomFill;
+pub use observer::{Filtered, Observer, StepFilter};
+pub use obstacle::{ObstaclePolicy, Obstacles};
+pub use optimize::{Candidate, TargetSearch};
+#[cfg(not(target_arch = "wasm32"))]
+pub use pace::Pacer;
+pub use palette::{Palette, ParsePaletteError};
+pub use phase::{PhaseDetector, PhaseEvent};
+pub use plot::{Plot, PlotFormat};
+pub use plugin::{
+    Plugin, PluginBehavior, PluginDecision, PluginDecl, PluginError, PluginFrame, PluginRenderer,
+    PLUGIN_ABI_VERSION, PLUGIN_ENTRY,
+};
+pub use pos::{MapPos, Pos};
+pub use preset::Preset;
+pub use raster::RasterFormat;
+pub use raw_video::{RawFormat, RawVideo};
+pub use relief::Relief;
+#[cfg(feature = "io")]
+pub use render::{
+    save_color_map_to_file, save_map_to_file, save_palette_map_to_file, save_sparse_map_to_file,
+    PngRenderer,
+};
+pub use render::{Crop, Frame, Renderer};
+#[cfg(feature = "io")]
+pub use replay::{Replay, ReplayError, ReplayRecorder};
+#[cfg(feature = "io")]
+pub use report::{Report, Series};
+pub use rle::{ParseRleError, Pattern};
+pub use rule::{ParseRuleError, Rule, StochasticRule, Turn};
+#[cfg(feature = "sandbox")]
+pub use sandbox::{Sandbox, SandboxError};
+#[cfg(feature = "io")]
+pub use schematic::{Schematic, SchematicFormat};
+pub use search::{rules_up_to, Metric, RuleSearch, SearchResult};
+pub use shared_map::{MapPublisher, MapReadHandle, MapSnapshot};
+pub use simulation::{
+    AntState, Boundary, ConflictPolicy, IntegrityError, ReverseError, Simulation, Symmetry,
+};
+pub use sink::{OutputSink, RunSummary};
+#[cfg(feature = "io")]
+pub use snapshot::SnapshotWriter;
+pub use sound::{Pitch, Sound, SoundTrack, Volume};
+pub use sparse::{
+    BitChunk, ChunkCoord, Migration, SparseMap, Storage, UnboundedAnt, CHUNK_SIZE, DENSE_LIMIT,
+};
+pub use state::{load_state, save_state, SEMANTICS_VERSION};
+pub use stats::{StatsFormat, StatsLog, StatsSample};
+pub use stego::{embed_params, extract_params, params_room, StegoError};
+pub use svg::SvgRenderer;
+pub use sweep::{SweepExecutor, WorkerStats};
+pub use thumbnail::DensityGrid;
+pub use tiling::Seams;
+pub use timeline::{Cue, Timeline, TimelineError};
+pub use trajectory::{TrajectoryFormat, TrajectoryLog, TrajectoryPoint, TrajectoryTail};
+pub use turmite::{Transition, TurmiteError, TurmiteRule, TurmiteTurn, TurnAction};
+pub use voxel::{
+    Orientation, ParseVoxelRuleError, Voxel, VoxelAnt, VoxelDirection, VoxelMap, VoxelRule,
+    VoxelTurn,
+};
+#[cfg(feature = "wasm")]
+pub use wasm::WasmSimulation;
+pub use worm::{ParseWormRuleError, Worm, WormEnd, WormRule};
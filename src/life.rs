@@ -0,0 +1,243 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{CellStore, MapPos, Pos};
+
+/// Cellular automaton where a cell lives on or comes to life by how many of its eight
+/// neighbours are alive, in Golly's notation: `B3/S23` is Game of Life, `B2/S` Seeds.
+/// Generations rules like Brian's Brain, `B2/S/C3`, have dying states that count for
+/// nothing and fade out one a generation
+///
+/// Runs on any board, white `0` cells are dead, black `1` ones alive and dying states
+/// are the colors after that, so ants painting black and white on a board walk into
+/// a live field and stir it up, see [`Simulation::with_automaton`](crate::Simulation::with_automaton)
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LifeRule {
+    /// Indexed by live neighbours
+    birth: [bool; 9],
+    survival: [bool; 9],
+    states: u8,
+}
+
+impl LifeRule {
+    /// Rule of `birth` and `survival` neighbour counts with `states` states, dead and
+    /// alive and the rest dying
+    pub fn new(birth: &[u8], survival: &[u8], states: u8) -> Result<Self, String> {
+        if states < 2 {
+            return Err(format!("{states} states, a cell is at least dead or alive"));
+        }
+        let counts = |counts: &[u8]| {
+            let mut set = [false; 9];
+            for &count in counts {
+                *set.get_mut(count as usize)
+                    .ok_or_else(|| format!("{count} neighbours, a cell has 8"))? = true;
+            }
+            Ok::<_, String>(set)
+        };
+        Ok(Self {
+            birth: counts(birth)?,
+            survival: counts(survival)?,
+            states,
+        })
+    }
+
+    /// Conway's Game of Life, `B3/S23`
+    pub fn life() -> Self {
+        Self::new(&[3], &[2, 3], 2).expect("Life is a rule")
+    }
+
+    /// Seeds, `B2/S`, where nothing survives
+    pub fn seeds() -> Self {
+        Self::new(&[2], &[], 2).expect("Seeds is a rule")
+    }
+
+    /// Brian's Brain, `B2/S/C3`
+    pub fn brians_brain() -> Self {
+        Self::new(&[2], &[], 3).expect("Brian's Brain is a rule")
+    }
+
+    /// Colors cells of the rule can have
+    pub fn states(&self) -> usize {
+        self.states as usize
+    }
+
+    /// State after `state` with `alive` live neighbours
+    pub fn next(&self, state: u8, alive: u8) -> u8 {
+        match state {
+            0 => self.birth[alive as usize] as u8,
+            1 if self.survival[alive as usize] => 1,
+            // Alive ones start dying, dying ones go on to dead
+            _ => (state + 1) % self.states,
+        }
+    }
+
+    /// Advance `map` a generation, returns how many cells changed. Cells past the edge
+    /// are dead unless `wrap` glues opposite edges together
+    ///
+    /// Only three rows are kept as they were, so it takes no memory to speak of
+    pub fn step<const W: usize, const H: usize>(
+        &self,
+        map: &mut impl CellStore<W, H>,
+        wrap: bool,
+    ) -> usize {
+        let alive = |states: &[u8]| states.iter().map(|&s| (s == 1) as u8).collect::<Vec<_>>();
+        let (width, height) = map.size();
+        let dead = vec![0; width];
+
+        let first = row(map, 0);
+        let mut above = match wrap {
+            true => alive(&row(map, height - 1)),
+            false => dead.clone(),
+        };
+        let mut states = first.clone();
+        let mut counts = vec![0; width];
+        let mut changed = 0;
+        for y in 0..height {
+            let below = match y + 1 {
+                next if next < height => row(map, next),
+                _ if wrap => first.clone(),
+                _ => dead.clone(),
+            };
+            let here = alive(&states);
+            count_neighbours(&above, &here, &alive(&below), wrap, &mut counts);
+            for x in 0..width {
+                let next = self.next(states[x], counts[x]);
+                if next != states[x] {
+                    let pos = MapPos::validate_pos(Pos::new(x as _, y as _))
+                        .expect("x and y are within the map");
+                    map.set_color(pos, next);
+                    changed += 1;
+                }
+            }
+            above = here;
+            states = below;
+        }
+        changed
+    }
+}
+
+fn row<const W: usize, const H: usize>(map: &impl CellStore<W, H>, y: usize) -> Vec<u8> {
+    (0..map.size().0)
+        .map(|x| {
+            let pos =
+                MapPos::validate_pos(Pos::new(x as _, y as _)).expect("x and y are within the map");
+            map.color(pos)
+        })
+        .collect()
+}
+
+/// Live neighbours of every cell of `row`, given which cells of it and of the rows
+/// above and below are alive as `0` or `1`
+///
+/// Columns of three are summed first and every cell adds up its column and the two
+/// next to it, so a cell takes four additions whatever its neighbours
+pub fn count_neighbours(above: &[u8], row: &[u8], below: &[u8], wrap: bool, out: &mut [u8]) {
+    let width = row.len();
+    let columns = (0..width)
+        .map(|x| above[x] + row[x] + below[x])
+        .collect::<Vec<_>>();
+    let column = |x: isize| match (x, wrap) {
+        (0.., _) if (x as usize) < width => columns[x as usize],
+        (_, true) => columns[x.rem_euclid(width as isize) as usize],
+        (_, false) => 0,
+    };
+    for (x, count) in out.iter_mut().enumerate().take(width) {
+        let x = x as isize;
+        *count = column(x - 1) + column(x) + column(x + 1) - row[x as usize];
+    }
+}
+
+impl FromStr for LifeRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('/');
+        let mut counts = |letter: char| {
+            let part = parts.next().unwrap_or_default();
+            let digits = part
+                .strip_prefix(letter)
+                .or_else(|| part.strip_prefix(letter.to_ascii_lowercase()))
+                .ok_or_else(|| format!("expected {letter} in {s:?}, e.g. B3/S23"))?;
+            digits
+                .chars()
+                .map(|c| {
+                    c.to_digit(10)
+                        .map(|d| d as u8)
+                        .ok_or_else(|| format!("{c:?} in {s:?} is not a neighbour count"))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let (birth, survival) = (counts('B')?, counts('S')?);
+        let states = match parts.next() {
+            Some(part) => part
+                .strip_prefix(['C', 'c'])
+                .and_then(|states| states.parse().ok())
+                .ok_or_else(|| format!("expected C and a number of states in {s:?}"))?,
+            None => 2,
+        };
+        if parts.next().is_some() {
+            return Err(format!("{s:?} has too many parts, e.g. B2/S/C3"));
+        }
+        Self::new(&birth, &survival, states)
+    }
+}
+
+impl Display for LifeRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let counts = |set: &[bool; 9]| {
+            (0..9)
+                .filter(|&count| set[count])
+                .map(|count| count.to_string())
+                .collect::<String>()
+        };
+        write!(f, "B{}/S{}", counts(&self.birth), counts(&self.survival))?;
+        match self.states {
+            2 => Ok(()),
+            states => write!(f, "/C{states}"),
+        }
+    }
+}
+
+#[test]
+fn life_runs_on_maps() {
+    use crate::{ColorMap, Map};
+
+    assert_eq!("B3/S23".parse(), Ok(LifeRule::life()));
+    assert_eq!("b2/s/c3".parse(), Ok(LifeRule::brians_brain()));
+    assert_eq!(LifeRule::brians_brain().to_string(), "B2/S/C3");
+    assert_eq!(LifeRule::seeds().to_string(), "B2/S");
+    assert!("B9/S".parse::<LifeRule>().is_err());
+    assert!("S23/B3".parse::<LifeRule>().is_err());
+
+    // A glider comes back a cell down and right every four generations, across the
+    // edge too on a wrapped board
+    let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+    let mut map = Map::<8, 8>::new_white();
+    for (x, y) in glider {
+        map.set(MapPos::validate_pos(Pos::new(x, y)).unwrap(), false);
+    }
+    for _ in 0..4 * 8 {
+        LifeRule::life().step(&mut map, true);
+    }
+    for (x, y) in glider {
+        assert_eq!(map.color(MapPos::validate_pos(Pos::new(x, y)).unwrap()), 1);
+    }
+    assert_eq!(map.count_black_tiles(), 5);
+
+    // Without wrapping it runs into the corner and settles as a block
+    for _ in 0..4 * 8 {
+        LifeRule::life().step(&mut map, false);
+    }
+    assert_eq!(map.count_black_tiles(), 4);
+    assert_eq!(LifeRule::life().step(&mut map, false), 0);
+
+    // Brian's Brain: two live cells make two more, then start dying
+    let mut map = ColorMap::<6, 4>::new_white();
+    for x in [2, 3] {
+        map.set_color(MapPos::validate_pos(Pos::new(x, 1)).unwrap(), 1);
+    }
+    assert_eq!(LifeRule::brians_brain().step(&mut map, false), 6);
+    let row = |map: &ColorMap<6, 4>, y: usize| map.colors()[y * 6..][..6].to_vec();
+    assert_eq!(row(&map, 0), [0, 0, 1, 1, 0, 0]);
+    assert_eq!(row(&map, 1), [0, 0, 2, 2, 0, 0]);
+    assert_eq!(row(&map, 2), [0, 0, 1, 1, 0, 0]);
+}
@@ -0,0 +1,221 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use crate::{BitChunk, ChunkCoord, SparseMap, CHUNK_SIZE};
+
+/// Level of a node covering one chunk, a node of level `k` is `2^k` cells a side
+const CHUNK_LEVEL: u32 = CHUNK_SIZE.trailing_zeros();
+
+/// Level of the 8x8 leaves, the smallest nodes of the format
+const LEAF_LEVEL: u32 = 3;
+
+/// Write `map` as a Golly macrocell (`.mc`) pattern, a quadtree where every distinct
+/// node is written once, so a board far too big for a PNG opens in Golly in a moment
+///
+/// Golly puts the center of the tree at `(0, 0)`, so cells keep their map positions.
+/// Returns how many nodes were written
+pub fn write_macrocell(map: &SparseMap, mut w: impl Write) -> io::Result<usize> {
+    let coords = map
+        .chunks()
+        .filter(|(_, chunk)| chunk.count_black_tiles() > 0)
+        .map(|(coord, _)| coord)
+        .collect::<Vec<_>>();
+
+    // The smallest tree centered at (0, 0) holding every chunk
+    let mut half = 1;
+    while coords
+        .iter()
+        .any(|c| c.x < -half || c.x >= half || c.y < -half || c.y >= half)
+    {
+        half *= 2;
+    }
+    let level = CHUNK_LEVEL + 1 + half.trailing_zeros();
+
+    let mut nodes = Nodes::default();
+    let corner = ChunkCoord { x: -half, y: -half };
+    if nodes.region(map, level, corner, coords) == 0 {
+        // Golly wants at least one node
+        nodes.lines.push(format!("{level} 0 0 0 0"));
+    }
+
+    writeln!(w, "[M2] (antventure)")?;
+    for line in &nodes.lines {
+        writeln!(w, "{line}")?;
+    }
+    Ok(nodes.lines.len())
+}
+
+/// Node lines in the order they're written, children before parents
+#[derive(Default)]
+struct Nodes {
+    lines: Vec<String>,
+    /// 1-based number of every line, `0` stands for an empty node
+    ids: HashMap<String, usize>,
+}
+
+impl Nodes {
+    fn add(&mut self, line: String) -> usize {
+        if let Some(&id) = self.ids.get(&line) {
+            return id;
+        }
+        self.lines.push(line.clone());
+        self.ids.insert(line, self.lines.len());
+        self.lines.len()
+    }
+
+    fn node(&mut self, level: u32, children: [usize; 4]) -> usize {
+        match children {
+            [0, 0, 0, 0] => 0,
+            [nw, ne, sw, se] => self.add(format!("{level} {nw} {ne} {sw} {se}")),
+        }
+    }
+
+    /// The node of `level` with its top left chunk at `corner`, `coords` are the
+    /// chunks inside it
+    fn region(
+        &mut self,
+        map: &SparseMap,
+        level: u32,
+        corner: ChunkCoord,
+        coords: Vec<ChunkCoord>,
+    ) -> usize {
+        if coords.is_empty() {
+            return 0;
+        }
+        if level == CHUNK_LEVEL {
+            let chunk = map.chunk(coords[0]).expect("only stored chunks are listed");
+            return self.inside(chunk, level, 0, 0);
+        }
+
+        let half = 1 << (level - 1 - CHUNK_LEVEL);
+        let mut quarters: [Vec<ChunkCoord>; 4] = Default::default();
+        for c in coords {
+            let east = (c.x >= corner.x + half) as usize;
+            let south = (c.y >= corner.y + half) as usize;
+            quarters[south * 2 + east].push(c);
+        }
+        let mut children = [0; 4];
+        for (i, quarter) in quarters.into_iter().enumerate() {
+            let corner = ChunkCoord {
+                x: corner.x + half * (i % 2) as isize,
+                y: corner.y + half * (i / 2) as isize,
+            };
+            children[i] = self.region(map, level - 1, corner, quarter);
+        }
+        self.node(level, children)
+    }
+
+    /// The node of `level` with its top left cell at `(x, y)` of `chunk`
+    fn inside(&mut self, chunk: &BitChunk, level: u32, x: usize, y: usize) -> usize {
+        if level == LEAF_LEVEL {
+            return self.leaf(chunk, x, y);
+        }
+        let half = 1 << (level - 1);
+        let children = [(0, 0), (half, 0), (0, half), (half, half)]
+            .map(|(dx, dy)| self.inside(chunk, level - 1, x + dx, y + dy));
+        self.node(level, children)
+    }
+
+    /// Rows of `.` and `*` ended by `$`, without trailing white cells or rows
+    fn leaf(&mut self, chunk: &BitChunk, x: usize, y: usize) -> usize {
+        let rows: [u8; 8] = std::array::from_fn(|i| (chunk.row(y + i) >> x) as u8);
+        let Some(last) = rows.iter().rposition(|&row| row != 0) else {
+            return 0;
+        };
+
+        let mut line = String::new();
+        for &row in &rows[..=last] {
+            for bit in 0..8 - row.leading_zeros() {
+                line.push(if row >> bit & 1 == 1 { '*' } else { '.' });
+            }
+            line.push('$');
+        }
+        self.add(line)
+    }
+}
+
+#[test]
+fn macrocells_hold_every_cell() {
+    use crate::Pos;
+
+    /// Black cells of a macrocell pattern, placed the way Golly places them
+    fn read(mc: &str) -> Vec<Pos> {
+        // Cells of every node relative to its top left corner, and its level
+        let mut nodes: Vec<(u32, Vec<Pos>)> = vec![(0, Vec::new())];
+        for line in mc.lines().skip(1) {
+            if line.ends_with('$') {
+                let cells = line.split('$').enumerate().flat_map(|(y, row)| {
+                    row.chars()
+                        .enumerate()
+                        .filter(|&(_, c)| c == '*')
+                        .map(move |(x, _)| Pos::new(x as isize, y as isize))
+                });
+                nodes.push((3, cells.collect()));
+                continue;
+            }
+            let numbers = line
+                .split(' ')
+                .map(|n| n.parse::<usize>().unwrap())
+                .collect::<Vec<_>>();
+            let level = numbers[0] as u32;
+            let half = 1 << (level - 1);
+            let mut cells = Vec::new();
+            for (i, &child) in numbers[1..].iter().enumerate() {
+                let (dx, dy) = (half * (i % 2) as isize, half * (i / 2) as isize);
+                cells.extend(nodes[child].1.iter().map(|p| Pos::new(p.x + dx, p.y + dy)));
+            }
+            nodes.push((level, cells));
+        }
+        let (level, cells) = nodes.pop().unwrap();
+        let half = 1 << (level - 1);
+        let mut cells: Vec<Pos> = cells
+            .into_iter()
+            .map(|p| Pos::new(p.x - half, p.y - half))
+            .collect();
+        cells.sort_by_key(|p| (p.y, p.x));
+        cells
+    }
+
+    let mut map = SparseMap::new_white();
+    let mut cells = vec![
+        Pos::new(0, 0),
+        Pos::new(-1, -1),
+        Pos::new(5, 3),
+        Pos::new(1000, -70_000),
+    ];
+    // The same pattern in two chunks is written once
+    for x in [0, 3 * CHUNK_SIZE as isize] {
+        cells.extend([
+            Pos::new(x + 1, 696),
+            Pos::new(x + 2, 697),
+            Pos::new(x + 9, 709),
+        ]);
+    }
+    for &pos in &cells {
+        map.set(pos, true);
+    }
+    // White again, the chunk is stored but empty
+    map.set(Pos::new(-500, 500), true);
+    map.set(Pos::new(-500, 500), false);
+
+    let mut mc = Vec::new();
+    let nodes = write_macrocell(&map, &mut mc).unwrap();
+    let mc = String::from_utf8(mc).unwrap();
+    assert!(mc.starts_with("[M2]"));
+    assert_eq!(mc.lines().count(), nodes + 1);
+    cells.sort_by_key(|p| (p.y, p.x));
+    assert_eq!(read(&mc), cells);
+    assert_eq!(mc.lines().filter(|line| *line == ".*$..*$").count(), 1);
+
+    let mut mc = Vec::new();
+    assert_eq!(
+        write_macrocell(&SparseMap::new_white(), &mut mc).unwrap(),
+        1
+    );
+    assert_eq!(
+        String::from_utf8(mc).unwrap(),
+        "[M2] (antventure)\n7 0 0 0 0\n"
+    );
+}
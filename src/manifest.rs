@@ -0,0 +1,162 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// What a file written by a run holds
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactKind {
+    /// Final board, as an image, figure, relief or plot
+    Board,
+    Heatmap,
+    Animation,
+    Checkpoint,
+    /// Board saved when a phase change was detected or every `--snapshot-every` steps
+    Snapshot,
+    /// Final board as a Golly RLE or macrocell pattern
+    Pattern,
+    /// HTML summary of the run
+    Report,
+    /// Plain text description of the final board
+    Description,
+    /// Statistics sampled over the run, as CSV or JSON
+    Stats,
+    /// Positions of the ants over the run, as CSV or newline-delimited JSON
+    Trajectory,
+    /// Zip of what it takes to repeat the run, see `--bug-report`
+    BugReport,
+    /// Small gray picture of the final board
+    Thumbnail,
+    /// The run played as a tone, see `--sonify`
+    Sound,
+    /// Every cell the run repainted, see `--record`
+    Replay,
+}
+
+/// A file written by a run, as it was when it was listed
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Artifact {
+    pub kind: ArtifactKind,
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// Lowercase hex SHA-256 of the contents
+    pub sha256: String,
+}
+
+impl Artifact {
+    /// Size and hash of the file at `path` as it is now
+    pub fn read(kind: ArtifactKind, path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let (bytes, sha256) = hash_file(&path)?;
+        Ok(Self {
+            kind,
+            path,
+            bytes,
+            sha256,
+        })
+    }
+}
+
+/// Every file a run produced, saved as JSON next to them so other tools can pick
+/// them up and check nothing changed since
+///
+/// ```json
+/// {"artifacts": [{"kind": "board", "path": "ant.png", "bytes": 1234, "sha256": "9f86d0..."}]}
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub artifacts: Vec<Artifact>,
+}
+
+impl Manifest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// List the file at `path`, replacing an earlier entry for the same path
+    pub fn add(&mut self, kind: ArtifactKind, path: impl Into<PathBuf>) -> io::Result<()> {
+        let artifact = Artifact::read(kind, path)?;
+        self.artifacts.retain(|a| a.path != artifact.path);
+        self.artifacts.push(artifact);
+        Ok(())
+    }
+
+    pub fn save(&self, file: impl AsRef<Path>) -> io::Result<()> {
+        let w = BufWriter::new(File::create(file)?);
+        serde_json::to_writer_pretty(w, self).map_err(io::Error::from)
+    }
+
+    pub fn load(file: impl AsRef<Path>) -> io::Result<Self> {
+        let r = BufReader::new(File::open(file)?);
+        serde_json::from_reader(r).map_err(io::Error::from)
+    }
+
+    /// Artifacts that are missing or no longer match their size and hash
+    pub fn verify(&self) -> Vec<&Artifact> {
+        self.artifacts
+            .iter()
+            .filter(|a| match hash_file(&a.path) {
+                Ok((bytes, sha256)) => bytes != a.bytes || sha256 != a.sha256,
+                Err(_) => true,
+            })
+            .collect()
+    }
+}
+
+/// Size and lowercase hex SHA-256 of a file
+fn hash_file(path: &Path) -> io::Result<(u64, String)> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0; 64 * 1024];
+    let mut bytes = 0;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        bytes += n as u64;
+    }
+    let sha256 = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    Ok((bytes, sha256))
+}
+
+#[test]
+fn manifest_round_trip() {
+    use std::fs;
+
+    let dir = std::env::temp_dir();
+    let board = dir.join("antventure_manifest_board.txt");
+    let file = dir.join("antventure_manifest.json");
+    fs::write(&board, "abc").unwrap();
+
+    let mut manifest = Manifest::new();
+    manifest.add(ArtifactKind::Board, &board).unwrap();
+    manifest.add(ArtifactKind::Board, &board).unwrap();
+    assert_eq!(manifest.artifacts.len(), 1);
+    assert_eq!(manifest.artifacts[0].bytes, 3);
+    assert_eq!(
+        manifest.artifacts[0].sha256,
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert!(manifest
+        .add(ArtifactKind::Pattern, dir.join("antventure_no_such_file"))
+        .is_err());
+
+    manifest.save(&file).unwrap();
+    let loaded = Manifest::load(&file).unwrap();
+    assert_eq!(loaded, manifest);
+    assert!(loaded.verify().is_empty());
+
+    fs::write(&board, "abd").unwrap();
+    assert_eq!(loaded.verify().len(), 1);
+}
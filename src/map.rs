@@ -0,0 +1,687 @@
+use std::ops::Range;
+
+use crate::{bitset::BitSet, digest::board_digest, Crop, IntegrityError, MapPos, Pos};
+
+/// Mutable handle to a single cell of a [`Map`]
+pub struct CellMut<'m> {
+    word: &'m mut u64,
+    mask: u64,
+    black: &'m mut usize,
+}
+
+impl<'m> CellMut<'m> {
+    pub(crate) fn new(word: &'m mut u64, mask: u64, black: &'m mut usize) -> Self {
+        Self { word, mask, black }
+    }
+
+    pub fn is_white(&self) -> bool {
+        *self.word & self.mask != 0
+    }
+
+    pub fn invert(&mut self) {
+        match self.is_white() {
+            true => *self.black += 1,
+            false => *self.black -= 1,
+        }
+        *self.word ^= self.mask;
+    }
+}
+
+/// Bit-packed `W`x`H` board, one bit per cell (set bit is a white cell)
+#[derive(Clone)]
+pub struct Map<const W: usize, const H: usize> {
+    cells: BitSet,
+    // Kept up to date on every change, recounting gigacell boards takes a while
+    black: usize,
+}
+
+impl<const W: usize, const H: usize> Map<W, H> {
+    pub fn new_white() -> Self {
+        Self {
+            cells: BitSet::filled(W * H, true),
+            black: 0,
+        }
+    }
+
+    /// Build a map from cell colors in row-major order (`true` is white)
+    pub(crate) fn from_cells(cells: impl IntoIterator<Item = bool>) -> Self {
+        let mut map = Self::new_white();
+        map.cells
+            .set_many(cells.into_iter().take(W * H).enumerate());
+        map.black = map.cells.len() - map.cells.count_ones();
+        map
+    }
+
+    pub fn get(&self, pos: MapPos<'_, W, H>) -> bool {
+        self.cells.get(pos.index())
+    }
+
+    pub fn get_mut<'m>(&'m mut self, pos: MapPos<'m, W, H>) -> CellMut<'m> {
+        let (word, mask) = self.cells.word_mut(pos.index());
+        CellMut::new(word, mask, &mut self.black)
+    }
+
+    /// Black cells right now, counted as they change so it takes no time
+    pub fn black_count(&self) -> usize {
+        self.black
+    }
+
+    /// Same as [`black_count`](Self::black_count)
+    pub fn count_black_tiles(&self) -> usize {
+        self.black
+    }
+
+    pub fn set(&mut self, pos: MapPos<'_, W, H>, is_white: bool) {
+        let (word, mask) = self.cells.word_mut(pos.index());
+        if (*word & mask != 0) != is_white {
+            *word ^= mask;
+            match is_white {
+                true => self.black -= 1,
+                false => self.black += 1,
+            }
+        }
+    }
+
+    /// Make each of `cells` white or black. Cells next to each other in a row go into
+    /// the board a word at a time, far faster than a [`set`](Self::set) for each
+    pub fn set_cells<'p>(&mut self, cells: impl IntoIterator<Item = (MapPos<'p, W, H>, bool)>) {
+        let cells = cells.into_iter().map(|(pos, white)| (pos.index(), white));
+        let whitened = self.cells.set_many(cells);
+        self.black = self
+            .black
+            .checked_add_signed(-whitened)
+            .expect("no more cells whitened than were black");
+    }
+
+    /// Turn the white cells of `region` black and the black ones white, 64 at a time,
+    /// the part of it past the edges left out
+    pub fn flip_rect(&mut self, region: Crop) {
+        let (xs, ys) = clip::<W, H>(region);
+        for y in ys {
+            let row = y * W + xs.start..y * W + xs.end;
+            let white = self.cells.count_ones_in(row.clone());
+            self.black = self.black - (row.len() - white) + white;
+            self.cells.flip_range(row);
+        }
+    }
+
+    /// Every cell in row-major order with whether it's white
+    pub fn iter(&self) -> impl Iterator<Item = (MapPos<'static, W, H>, bool)> + '_ {
+        self.cells.iter().enumerate().map(|(i, is_white)| {
+            let pos = Pos::new((i % W) as _, (i / W) as _);
+            let pos = MapPos::validate_pos(pos).expect("cells are on the map");
+            (pos, is_white)
+        })
+    }
+
+    /// Rows from the top, each its cells from the left (`true` is white)
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = bool> + '_> + '_ {
+        (0..H).map(move |y| (y * W..(y + 1) * W).map(move |i| self.cells.get(i)))
+    }
+
+    /// Row `y` 64 cells at a time from the left, for scans that look at whole words
+    /// rather than cell by cell
+    ///
+    /// # Panics
+    ///
+    /// If `y` is off the map
+    pub fn row_words(&self, y: usize) -> impl Iterator<Item = RowWord> + '_ {
+        assert!(y < H, "row {y} is off the map");
+        (0..W).step_by(64).map(move |x| {
+            let len = (W - x).min(64);
+            RowWord {
+                x,
+                white: self.cells.bits(y * W + x, len),
+                len: len as u32,
+            }
+        })
+    }
+
+    /// Cells of `region` in row-major order with whether they're white, the part of
+    /// it past the edges left out
+    pub fn iter_region(
+        &self,
+        region: Crop,
+    ) -> impl Iterator<Item = (MapPos<'static, W, H>, bool)> + '_ {
+        let (xs, ys) = clip::<W, H>(region);
+        ys.flat_map(move |y| xs.clone().map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let pos = MapPos::validate_pos(Pos::new(x as _, y as _))
+                    .expect("the region is clipped to the map");
+                (pos, self.cells.get(y * W + x))
+            })
+    }
+
+    /// Black cells in `region`, the part of it past the edges left out. Counted 64
+    /// cells at a time, so asking about a big region is cheap
+    pub fn black_cells_in(&self, region: Crop) -> usize {
+        let (xs, ys) = clip::<W, H>(region);
+        ys.map(|y| {
+            let row = y * W + xs.start..y * W + xs.end;
+            row.len() - self.cells.count_ones_in(row)
+        })
+        .sum()
+    }
+
+    /// Copy of the `SW`x`SH` part with its top left cell at `origin`, e.g. to keep a
+    /// highway in a small board for a test. `None` if it doesn't fit on the map there,
+    /// and a part bigger than the map doesn't compile:
+    ///
+    /// ```compile_fail
+    /// use antventure::{Map, Pos};
+    ///
+    /// let map = Map::<16, 16>::new_white();
+    /// map.sub_map::<32, 8>(Pos::new(0, 0));
+    /// ```
+    pub fn sub_map<const SW: usize, const SH: usize>(&self, origin: Pos) -> Option<Map<SW, SH>> {
+        const { assert!(SW <= W && SH <= H, "the sub-map is bigger than the map") };
+        let (x, y) = window::<W, H>(origin, SW, SH)?;
+        let rows = (y..y + SH).map(|row| row * W + x);
+        let cells = rows.flat_map(|start| (start..start + SW).map(|i| self.cells.get(i)));
+        Some(Map::from_cells(cells))
+    }
+
+    /// Cells black on this board, on `other` or on both, a word at a time
+    pub fn diff(&self, other: &Self) -> DiffStats {
+        let words = self.cells.words().iter().zip(other.cells.words());
+        let (mut only_a, mut only_b) = (0, 0);
+        for (a, b) in words {
+            // Set bits are white, and bits past the end are clear on both
+            only_a += (!a & b).count_ones() as usize;
+            only_b += (a & !b).count_ones() as usize;
+        }
+        DiffStats {
+            only_a,
+            only_b,
+            both: self.black - only_a,
+        }
+    }
+
+    /// Make black the cells black on exactly one of this board and `other`, and the
+    /// rest white, e.g. to see what a later step of the same run repainted. Like
+    /// [`and`](Self::and) and [`or`](Self::or) it goes through the boards a word at a
+    /// time, milliseconds for a gigacell board
+    pub fn xor(&mut self, other: &Self) {
+        // Set bits are white, so a cell is white where the boards agree
+        self.black = self.cells.len() - self.cells.combine(&other.cells, |a, b| !(a ^ b));
+    }
+
+    /// Keep black only the cells black on `other` too
+    pub fn and(&mut self, other: &Self) {
+        self.black = self.cells.len() - self.cells.combine(&other.cells, |a, b| a | b);
+    }
+
+    /// Make black the cells black on `other` as well, to lay boards over each other
+    pub fn or(&mut self, other: &Self) {
+        self.black = self.cells.len() - self.cells.combine(&other.cells, |a, b| a & b);
+    }
+
+    /// Board of which cells are black on this board and `other`: `0` on neither, `1`
+    /// on this one only, `2` on `other` only and `3` on both, to save with
+    /// [`Palette::diff`](crate::Palette::diff)
+    pub fn diff_map(&self, other: &Self) -> ColorMap<W, H> {
+        let cells = self.cells().zip(other.cells());
+        ColorMap::from_colors(cells.map(|(a, b)| !a as u8 | (!b as u8) << 1))
+    }
+
+    /// Cell colors in row-major order (`true` is white)
+    pub(crate) fn cells(&self) -> impl Iterator<Item = bool> + '_ {
+        self.cells.iter()
+    }
+
+    /// Packed cells, 8 per byte with the leftmost cell in the highest bit
+    #[cfg(feature = "io")]
+    pub(crate) fn bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        // The bit set is, in fact, 1-bit grayscale representation in memory
+        self.cells.bytes()
+    }
+}
+
+/// Up to 64 cells of a row of a [`Map`], see [`Map::row_words`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RowWord {
+    /// Column of the first cell
+    pub x: usize,
+    /// A set bit for each white cell, the first cell in the highest bit
+    pub white: u64,
+    /// Cells in the word, the low bits past them are clear
+    pub len: u32,
+}
+
+impl RowWord {
+    /// A set bit for each black cell, laid out like [`white`](Self::white)
+    pub fn black(&self) -> u64 {
+        !self.white & !(u64::MAX.checked_shr(self.len).unwrap_or(0))
+    }
+}
+
+/// How two boards of the same size differ, see [`Map::diff`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct DiffStats {
+    /// Cells black on the first board only
+    pub only_a: usize,
+    /// Cells black on the second board only
+    pub only_b: usize,
+    /// Cells black on both
+    pub both: usize,
+}
+
+impl DiffStats {
+    /// Cells of another color on the other board
+    pub fn changed(&self) -> usize {
+        self.only_a + self.only_b
+    }
+}
+
+/// Board the ant can walk on, cells hold colors `0..COLORS` with `0` being white
+pub trait CellStore<const W: usize, const H: usize> {
+    /// How many colors a cell can hold
+    const COLORS: usize;
+
+    fn color(&self, pos: MapPos<'_, W, H>) -> u8;
+
+    fn set_color(&mut self, pos: MapPos<'_, W, H>, color: u8);
+
+    /// Board with the given cell colors in row-major order
+    fn with_colors(colors: Vec<u8>) -> Self
+    where
+        Self: Sized;
+
+    /// Width and height of the cells ants walk on, the top left of the `W`x`H` board.
+    /// Only stores sized at runtime use less than all of it
+    fn size(&self) -> (usize, usize) {
+        (W, H)
+    }
+
+    /// Cells of any color but white
+    fn count_colored(&self) -> usize {
+        self.to_colors().iter().filter(|&&color| color != 0).count()
+    }
+
+    /// Every cell color in row-major order
+    fn to_colors(&self) -> Vec<u8> {
+        (0..H)
+            .flat_map(|y| (0..W).map(move |x| Pos::new(x as _, y as _)))
+            .map(|pos| self.color(MapPos::validate_pos(pos).expect("x and y are within the map")))
+            .collect()
+    }
+
+    /// Stable 64-bit hash of the size and every cell color, the same for equal boards
+    /// in any store, on any platform and in later versions. Regression tests can pin
+    /// it instead of a whole board
+    fn digest(&self) -> u64 {
+        let colors = (0..H)
+            .flat_map(|y| (0..W).map(move |x| Pos::new(x as _, y as _)))
+            .map(|pos| self.color(MapPos::validate_pos(pos).expect("x and y are within the map")));
+        board_digest(W, H, colors)
+    }
+
+    /// Move every cell of `region` on to the next of `colors` colors, like a rule with
+    /// that many colors does, the part of it past the edges left out
+    fn cycle_rect(&mut self, region: Crop, colors: u8) {
+        let (width, height) = self.size();
+        let span = |min: isize, max: isize, len: usize| min.max(0)..=max.min(len as isize - 1);
+        for y in span(region.min.y, region.max.y, height) {
+            for x in span(region.min.x, region.max.x, width) {
+                let pos = MapPos::validate_pos(Pos::new(x, y)).expect("clipped to the map");
+                self.set_color(pos, (self.color(pos) + 1) % colors);
+            }
+        }
+    }
+
+    /// Check whatever the store keeps besides the cells against the cells, see
+    /// [`Simulation::check_integrity`](crate::Simulation::check_integrity)
+    fn check_integrity(&self) -> Result<(), IntegrityError> {
+        Ok(())
+    }
+
+    /// Hint that the cell at `pos` is read soon, so stores too big for the cache can
+    /// start loading it. Cells off the board are ignored, and so is the hint by
+    /// stores that don't take it
+    #[inline]
+    fn prefetch(&self, pos: Pos) {
+        let _ = pos;
+    }
+}
+
+impl<const W: usize, const H: usize> CellStore<W, H> for Map<W, H> {
+    const COLORS: usize = 2;
+
+    fn color(&self, pos: MapPos<'_, W, H>) -> u8 {
+        !self.get(pos) as u8
+    }
+
+    fn set_color(&mut self, pos: MapPos<'_, W, H>, color: u8) {
+        debug_assert!(color < 2, "bit map only holds white and black");
+        self.set(pos, color == 0)
+    }
+
+    fn with_colors(colors: Vec<u8>) -> Self {
+        Self::from_cells(colors.into_iter().map(|c| c == 0))
+    }
+
+    fn count_colored(&self) -> usize {
+        self.count_black_tiles()
+    }
+
+    fn digest(&self) -> u64 {
+        board_digest(W, H, self.cells().map(|white| !white as u8))
+    }
+
+    fn cycle_rect(&mut self, region: Crop, colors: u8) {
+        debug_assert_eq!(colors, 2, "bit map only holds white and black");
+        self.flip_rect(region);
+    }
+
+    #[inline]
+    fn prefetch(&self, pos: Pos) {
+        if (0..W as isize).contains(&pos.x) && (0..H as isize).contains(&pos.y) {
+            self.cells.prefetch(pos.y as usize * W + pos.x as usize);
+        }
+    }
+
+    fn check_integrity(&self) -> Result<(), IntegrityError> {
+        let counted = self.cells.len() - self.cells.count_ones();
+        match self.black == counted {
+            true => Ok(()),
+            false => Err(IntegrityError::BlackCount {
+                kept: self.black,
+                counted,
+            }),
+        }
+    }
+}
+
+/// `W`x`H` board with a whole byte per cell, for rules with more than two colors
+#[derive(Clone)]
+pub struct ColorMap<const W: usize, const H: usize>(Vec<u8>);
+
+impl<const W: usize, const H: usize> ColorMap<W, H> {
+    pub fn new_white() -> Self {
+        Self(vec![0; W * H])
+    }
+
+    /// Build a map from cell colors in row-major order
+    pub(crate) fn from_colors(colors: impl IntoIterator<Item = u8>) -> Self {
+        let mut map = Self::new_white();
+        for (cell, color) in map.0.iter_mut().zip(colors) {
+            *cell = color;
+        }
+        map
+    }
+
+    /// Cell colors in row-major order
+    pub fn colors(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Copy of the `SW`x`SH` part with its top left cell at `origin`, see
+    /// [`Map::sub_map`]
+    pub fn sub_map<const SW: usize, const SH: usize>(
+        &self,
+        origin: Pos,
+    ) -> Option<ColorMap<SW, SH>> {
+        const { assert!(SW <= W && SH <= H, "the sub-map is bigger than the map") };
+        let (x, y) = window::<W, H>(origin, SW, SH)?;
+        let rows = self.0.chunks(W).skip(y).take(SH);
+        Some(ColorMap(
+            rows.flat_map(|row| &row[x..x + SW]).copied().collect(),
+        ))
+    }
+
+    /// How many cells hold each color, indexed by color
+    pub fn count_colors(&self, colors: usize) -> Vec<usize> {
+        let mut counts = vec![0; colors];
+        for &color in &self.0 {
+            counts[color as usize] += 1;
+        }
+        counts
+    }
+}
+
+impl<const W: usize, const H: usize> CellStore<W, H> for ColorMap<W, H> {
+    const COLORS: usize = u8::MAX as usize + 1;
+
+    fn color(&self, pos: MapPos<'_, W, H>) -> u8 {
+        self.0[pos.index()]
+    }
+
+    fn set_color(&mut self, pos: MapPos<'_, W, H>, color: u8) {
+        self.0[pos.index()] = color;
+    }
+
+    fn with_colors(colors: Vec<u8>) -> Self {
+        Self::from_colors(colors)
+    }
+
+    fn count_colored(&self) -> usize {
+        self.0.iter().filter(|&&color| color != 0).count()
+    }
+
+    fn to_colors(&self) -> Vec<u8> {
+        self.0.clone()
+    }
+
+    fn digest(&self) -> u64 {
+        board_digest(W, H, self.0.iter().copied())
+    }
+}
+
+/// Columns and rows of `region` that are on a `W`x`H` map
+pub(crate) fn clip<const W: usize, const H: usize>(region: Crop) -> (Range<usize>, Range<usize>) {
+    let span = |min: isize, max: isize, len: usize| {
+        let start = min.clamp(0, len as isize) as usize;
+        let end = max.saturating_add(1).clamp(0, len as isize) as usize;
+        start..end.max(start)
+    };
+    (
+        span(region.min.x, region.max.x, W),
+        span(region.min.y, region.max.y, H),
+    )
+}
+
+/// Top left corner of a `width`x`height` window at `origin` if it fits on a `W`x`H` map
+fn window<const W: usize, const H: usize>(
+    origin: Pos,
+    width: usize,
+    height: usize,
+) -> Option<(usize, usize)> {
+    let x = usize::try_from(origin.x).ok()?;
+    let y = usize::try_from(origin.y).ok()?;
+    (x + width <= W && y + height <= H).then_some((x, y))
+}
+
+#[test]
+fn black_count_follows_every_change() {
+    use crate::{Ant, Direction};
+
+    let at = |x, y| MapPos::validate_pos(Pos::new(x, y)).unwrap();
+    let mut map = Map::<100, 3>::from_cells((0..300).map(|i| i % 3 != 0));
+    assert_eq!(map.black_count(), 100);
+    map.set(at(0, 0), true);
+    map.set(at(0, 0), true);
+    map.set(at(1, 0), false);
+    map.get_mut(at(2, 0)).invert();
+    map.set_color(at(3, 0), 0);
+    assert_eq!(map.black_count(), 100);
+
+    let mut map = Map::<64, 64>::new_white();
+    Ant::new(&mut map, Pos::new(32, 32), Direction::North)
+        .unwrap()
+        .walk_until_end();
+    assert_eq!(
+        map.black_count(),
+        map.cells().filter(|white| !white).count()
+    );
+    assert_eq!(map.check_integrity(), Ok(()));
+    map.black += 1;
+    assert_eq!(
+        map.check_integrity(),
+        Err(IntegrityError::BlackCount {
+            kept: map.black,
+            counted: map.black - 1
+        })
+    );
+}
+
+#[test]
+fn sub_maps_copy_a_window() {
+    use crate::{Ant, Direction};
+
+    let mut map = Map::<64, 64>::new_white();
+    Ant::new(&mut map, Pos::new(32, 32), Direction::North)
+        .unwrap()
+        .walk_at_most(3000);
+    let sub = map.sub_map::<24, 16>(Pos::new(20, 25)).unwrap();
+    for (x, y) in (0..24).flat_map(|x| (0..16).map(move |y| (x, y))) {
+        let inner = MapPos::validate_pos(Pos::new(x, y)).unwrap();
+        let outer = MapPos::validate_pos(Pos::new(x + 20, y + 25)).unwrap();
+        assert_eq!(sub.get(inner), map.get(outer));
+    }
+    assert_eq!(
+        sub.black_count(),
+        sub.cells().filter(|white| !white).count()
+    );
+    assert!(sub.black_count() > 0);
+
+    assert!(map.sub_map::<64, 64>(Pos::new(0, 0)).is_some());
+    assert!(map.sub_map::<24, 16>(Pos::new(41, 0)).is_none());
+    assert!(map.sub_map::<24, 16>(Pos::new(0, -1)).is_none());
+
+    let colors = ColorMap::<4, 3>::from_colors(0..12);
+    let sub = colors.sub_map::<2, 2>(Pos::new(1, 1)).unwrap();
+    assert_eq!(sub.colors(), [5, 6, 9, 10]);
+}
+
+#[test]
+fn regions_count_like_cells() {
+    use crate::{Ant, Direction};
+
+    let mut map = Map::<100, 80>::new_white();
+    Ant::new(&mut map, Pos::new(50, 40), Direction::North)
+        .unwrap()
+        .walk_at_most(6000);
+    let rows = map
+        .rows()
+        .map(|row| row.collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!((rows.len(), rows[0].len()), (80, 100));
+    assert!(map
+        .iter()
+        .all(|(pos, white)| rows[pos.y()][pos.x()] == white));
+
+    let region = |min: (isize, isize), max: (isize, isize)| Crop {
+        min: Pos::new(min.0, min.1),
+        max: Pos::new(max.0, max.1),
+    };
+    // Word boundaries inside rows, a region past the edges and one off the map
+    for crop in [
+        region((0, 0), (99, 79)),
+        region((30, 20), (70, 61)),
+        region((63, 10), (64, 70)),
+        region((-10, 50), (200, 100)),
+        region((120, 0), (130, 10)),
+    ] {
+        let black = map.iter_region(crop).filter(|(_, white)| !white).count();
+        assert_eq!(map.black_cells_in(crop), black);
+        assert!(map.iter_region(crop).all(|(pos, white)| {
+            let (x, y) = (pos.x() as isize, pos.y() as isize);
+            (crop.min.x..=crop.max.x).contains(&x)
+                && (crop.min.y..=crop.max.y).contains(&y)
+                && map.get(pos) == white
+        }));
+    }
+    assert_eq!(
+        map.black_cells_in(region((0, 0), (99, 79))),
+        map.black_count()
+    );
+    assert_eq!(
+        map.iter_region(region((-10, 50), (200, 100))).count(),
+        100 * 30
+    );
+}
+
+#[test]
+fn batches_change_like_single_cells() {
+    let cell = |x, y| MapPos::validate_pos(Pos::new(x, y)).unwrap();
+    let mut batched = Map::<100, 30>::new_white();
+    let mut single = batched.clone();
+
+    // A diagonal line, a row across word boundaries and cells set twice
+    let cells = (0..30)
+        .map(|i| (cell(i * 3, i), false))
+        .chain((50..90).map(|x| (cell(x, 7), x % 3 != 0)))
+        .chain([(cell(0, 0), true), (cell(0, 0), false)])
+        .collect::<Vec<_>>();
+    batched.set_cells(cells.iter().copied());
+    for &(pos, white) in &cells {
+        single.set(pos, white);
+    }
+    assert_eq!(batched.black_count(), single.black_count());
+    assert!(batched.cells().eq(single.cells()));
+
+    let crop = Crop {
+        min: Pos::new(40, -5),
+        max: Pos::new(130, 12),
+    };
+    batched.flip_rect(crop);
+    for y in 0..=12 {
+        for x in 40..100 {
+            let pos = cell(x, y);
+            single.set(pos, !single.get(pos));
+        }
+    }
+    assert_eq!(batched.black_count(), single.black_count());
+    assert!(batched.cells().eq(single.cells()));
+    assert!(batched.check_integrity().is_ok());
+
+    // Colors go round the same in a byte a cell
+    let mut colors = ColorMap::<100, 30>::new_white();
+    colors.cycle_rect(crop, 3);
+    colors.cycle_rect(crop, 3);
+    assert_eq!(colors.count_colored(), 60 * 13);
+    assert_eq!(colors.color(cell(40, 0)), 2);
+}
+
+#[test]
+fn diffs_count_like_cells() {
+    use crate::{hamming_distance, Ant, Direction};
+
+    // 100x30 cells leave a part word at the end
+    let walk = |steps| {
+        let mut map = Map::<100, 30>::new_white();
+        Ant::new(&mut map, Pos::new(50, 15), Direction::North)
+            .unwrap()
+            .walk_at_most(steps);
+        map
+    };
+    let (a, b) = (walk(2000), walk(2500));
+    let stats = a.diff(&b);
+    let cells = a.cells().zip(b.cells()).collect::<Vec<_>>();
+    let count = |f: fn(&(bool, bool)) -> bool| cells.iter().filter(|c| f(c)).count();
+    assert_eq!(stats.only_a, count(|&(a, b)| !a && b));
+    assert_eq!(stats.only_b, count(|&(a, b)| a && !b));
+    assert_eq!(stats.both, count(|&(a, b)| !a && !b));
+    assert_eq!(stats.changed(), hamming_distance(&a, &b));
+    assert_eq!(a.diff(&a).changed(), 0);
+
+    let map = a.diff_map(&b);
+    assert_eq!(
+        map.count_colors(4)[1..],
+        [stats.only_a, stats.only_b, stats.both]
+    );
+
+    let combined = |op: fn(&mut Map<100, 30>, &Map<100, 30>)| {
+        let mut map = a.clone();
+        op(&mut map, &b);
+        assert!(map.check_integrity().is_ok());
+        map.black_count()
+    };
+    assert_eq!(combined(Map::xor), stats.changed());
+    assert_eq!(combined(Map::and), stats.both);
+    assert_eq!(combined(Map::or), stats.changed() + stats.both);
+    let mut xor = a.clone();
+    xor.xor(&b);
+    xor.xor(&b);
+    assert!(xor.cells().eq(a.cells()));
+}
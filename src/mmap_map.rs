@@ -0,0 +1,212 @@
+use std::{fs::OpenOptions, io, path::Path};
+
+use memmap2::MmapMut;
+
+use crate::{CellMut, CellStore, IntegrityError, MapPos, Pos};
+
+// Bytes filled or counted at a time, so the kernel can page the rest out
+const CHUNK: usize = 1 << 24;
+
+/// [`Map`](crate::Map) kept in a memory-mapped file instead of RAM, for boards
+/// bigger than the memory of the machine, e.g. `MmapMap<100_000, 100_000>` in 1.25 GB
+/// of disk. Only the pages the ants walk on are in memory at once
+///
+/// The file holds the cells the way a [`Map`](crate::Map) does, a bit per cell in
+/// row-major order, set for white, in big-endian 64-bit words, so it's the same on
+/// every machine and can be opened again to go on walking
+pub struct MmapMap<const W: usize, const H: usize> {
+    mmap: MmapMut,
+    black: usize,
+}
+
+impl<const W: usize, const H: usize> MmapMap<W, H> {
+    const WORDS: usize = (W * H).div_ceil(64);
+
+    /// White board in a new file at `path`, replacing whatever was there
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len((Self::WORDS * 8) as u64)?;
+        // SAFETY: the file was just created for this map and nothing else is meant to
+        // touch it while it's mapped, see `open`
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+        Ok(Self::white(mmap))
+    }
+
+    /// Board saved by an earlier [`create`](Self::create) at `path`, with the black
+    /// cells counted once in chunks
+    ///
+    /// The file must not be changed by anything else while the map is open, like a
+    /// board in memory can't be
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let len = file.metadata()?.len();
+        if len != (Self::WORDS * 8) as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{len} bytes is not a {W}x{H} map"),
+            ));
+        }
+        // SAFETY: as long as nothing else changes the file the mapping is plain memory
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+        let mut map = Self { mmap, black: 0 };
+        map.black = W * H - map.count_white();
+        Ok(map)
+    }
+
+    /// White board in memory that isn't backed by a file, swapped out like any
+    /// other memory
+    pub fn anonymous() -> io::Result<Self> {
+        Ok(Self::white(MmapMut::map_anon(Self::WORDS * 8)?))
+    }
+
+    fn white(mut mmap: MmapMut) -> Self {
+        for chunk in mmap.chunks_mut(CHUNK) {
+            chunk.fill(u8::MAX);
+        }
+        let mut map = Self { mmap, black: 0 };
+        // Bits past the last cell stay clear so counting can go a word at a time
+        if let (Some(last), tail @ 1..) = (map.words_mut().last_mut(), (W * H) % 64) {
+            *last &= (!(u64::MAX >> tail)).to_be();
+        }
+        map
+    }
+
+    fn words(&self) -> &[u64] {
+        bytemuck::cast_slice(&self.mmap)
+    }
+
+    fn words_mut(&mut self) -> &mut [u64] {
+        bytemuck::cast_slice_mut(&mut self.mmap)
+    }
+
+    /// Word holding cell `i` and the mask of the cell in it, the word as it's in memory
+    fn locate(i: usize) -> (usize, u64) {
+        (i / 64, (1u64 << (63 - i % 64)).to_be())
+    }
+
+    pub fn get(&self, pos: MapPos<'_, W, H>) -> bool {
+        let (word, mask) = Self::locate(pos.index());
+        self.words()[word] & mask != 0
+    }
+
+    pub fn get_mut<'m>(&'m mut self, pos: MapPos<'m, W, H>) -> CellMut<'m> {
+        let (word, mask) = Self::locate(pos.index());
+        let word = &mut bytemuck::cast_slice_mut::<u8, u64>(&mut self.mmap)[word];
+        CellMut::new(word, mask, &mut self.black)
+    }
+
+    /// Black cells right now, counted as they change so it takes no time
+    pub fn black_count(&self) -> usize {
+        self.black
+    }
+
+    /// Same as [`black_count`](Self::black_count)
+    pub fn count_black_tiles(&self) -> usize {
+        self.black
+    }
+
+    pub fn set(&mut self, pos: MapPos<'_, W, H>, is_white: bool) {
+        let (word, mask) = Self::locate(pos.index());
+        let word = &mut self.words_mut()[word];
+        if (*word & mask != 0) != is_white {
+            *word ^= mask;
+            match is_white {
+                true => self.black -= 1,
+                false => self.black += 1,
+            }
+        }
+    }
+
+    /// White cells counted over the whole file, a chunk at a time
+    fn count_white(&self) -> usize {
+        self.words()
+            .chunks(CHUNK / 8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .map(|word| word.count_ones() as usize)
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    /// Write the changed pages to the file, they get there on their own too but
+    /// only this says when
+    pub fn flush(&self) -> io::Result<()> {
+        self.mmap.flush()
+    }
+}
+
+impl<const W: usize, const H: usize> CellStore<W, H> for MmapMap<W, H> {
+    const COLORS: usize = 2;
+
+    fn color(&self, pos: MapPos<'_, W, H>) -> u8 {
+        !self.get(pos) as u8
+    }
+
+    fn set_color(&mut self, pos: MapPos<'_, W, H>, color: u8) {
+        debug_assert!(color < 2, "bit map only holds white and black");
+        self.set(pos, color == 0)
+    }
+
+    /// # Panics
+    ///
+    /// If the memory can't be mapped
+    fn with_colors(colors: Vec<u8>) -> Self {
+        let mut map = Self::anonymous().expect("Error in mapping memory");
+        for (i, color) in colors.into_iter().take(W * H).enumerate() {
+            let pos = Pos::new((i % W) as _, (i / W) as _);
+            let pos = MapPos::validate_pos(pos).expect("x and y are within the map");
+            map.set(pos, color == 0);
+        }
+        map
+    }
+
+    fn count_colored(&self) -> usize {
+        self.black
+    }
+
+    fn check_integrity(&self) -> Result<(), IntegrityError> {
+        let counted = W * H - self.count_white();
+        match self.black == counted {
+            true => Ok(()),
+            false => Err(IntegrityError::BlackCount {
+                kept: self.black,
+                counted,
+            }),
+        }
+    }
+}
+
+#[test]
+fn mapped_files_walk_like_maps() {
+    use crate::{Ant, Direction, Map};
+
+    crate::testing::conformance::<37, 29, MmapMap<37, 29>>();
+
+    let file = std::env::temp_dir().join("antventure_mapped_files_walk_like_maps.bits");
+    let mut mapped = MmapMap::<100, 70>::create(&file).unwrap();
+    let mut map = Map::<100, 70>::new_white();
+    Ant::new(&mut mapped, Pos::new(50, 35), Direction::North)
+        .unwrap()
+        .walk_until_end();
+    Ant::new(&mut map, Pos::new(50, 35), Direction::North)
+        .unwrap()
+        .walk_until_end();
+    assert_eq!(mapped.count_black_tiles(), map.count_black_tiles());
+    assert_eq!(mapped.digest(), map.digest());
+    assert_eq!(mapped.check_integrity(), Ok(()));
+    mapped.flush().unwrap();
+    drop(mapped);
+
+    let reopened = MmapMap::<100, 70>::open(&file).unwrap();
+    assert_eq!(reopened.black_count(), map.black_count());
+    assert_eq!(reopened.to_colors(), map.to_colors());
+    assert!(MmapMap::<100, 71>::open(&file).is_err());
+    std::fs::remove_file(file).unwrap();
+}
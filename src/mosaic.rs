@@ -0,0 +1,149 @@
+use crate::{CellStore, Frame, Pos};
+
+/// Finished boards laid out in a grid on one big board, for galleries of a sweep or
+/// a poster of several runs
+///
+/// Boards go left to right and then down, each one centered in a grid cell as big as
+/// the biggest board, with `spacing` cells of the gap color between them and around
+/// the edge, and around smaller boards too. Cells keep their colors, so boards under
+/// different rules need them brought to the same meaning first
+#[derive(Clone, Debug)]
+pub struct Mosaic {
+    columns: usize,
+    spacing: usize,
+    gap: u8,
+    boards: Vec<Frame>,
+}
+
+impl Mosaic {
+    /// Grid of `columns` boards a row, at least one
+    pub fn new(columns: usize) -> Self {
+        Self {
+            columns: columns.max(1),
+            spacing: 0,
+            gap: 0,
+            boards: Vec::new(),
+        }
+    }
+
+    /// Grid with as many rows as columns for `boards` boards, or one more column
+    pub fn square(boards: usize) -> Self {
+        let mut columns = 1;
+        while columns * columns < boards {
+            columns += 1;
+        }
+        Self::new(columns)
+    }
+
+    pub fn with_spacing(mut self, spacing: usize) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Color of the cells between boards, white unless set
+    pub fn with_gap_color(mut self, color: u8) -> Self {
+        self.gap = color;
+        self
+    }
+
+    /// Add a board, e.g. `Frame::new(&map, colors, None)`, ants on it stay marked
+    pub fn push(&mut self, board: Frame) {
+        self.boards.push(board);
+    }
+
+    pub fn len(&self) -> usize {
+        self.boards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boards.is_empty()
+    }
+
+    /// Width and height of a grid cell, the size of the biggest board
+    fn cell(&self) -> (usize, usize) {
+        let width = self.boards.iter().map(|b| b.width).max().unwrap_or(0);
+        let height = self.boards.iter().map(|b| b.height).max().unwrap_or(0);
+        (width, height)
+    }
+
+    fn rows(&self) -> usize {
+        self.boards.len().div_ceil(self.columns)
+    }
+
+    pub fn width(&self) -> usize {
+        let columns = self.columns.min(self.boards.len());
+        columns * self.cell().0 + (columns + 1) * self.spacing
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows() * self.cell().1 + (self.rows() + 1) * self.spacing
+    }
+
+    /// Every board in its place, ready for any [`Renderer`](crate::Renderer)
+    pub fn frame(&self) -> Frame {
+        let (width, height) = (self.width(), self.height());
+        let mut frame = Frame {
+            cells: vec![self.gap; width * height],
+            width,
+            height,
+            colors: self.boards.iter().map(|b| b.colors).max().unwrap_or(2),
+            ants: Vec::new(),
+            origin: Pos::new(0, 0),
+        };
+
+        let (cell_width, cell_height) = self.cell();
+        for (i, board) in self.boards.iter().enumerate() {
+            let (column, row) = (i % self.columns, i / self.columns);
+            let x = self.spacing
+                + column * (cell_width + self.spacing)
+                + (cell_width - board.width) / 2;
+            let y = self.spacing
+                + row * (cell_height + self.spacing)
+                + (cell_height - board.height) / 2;
+            for (dy, cells) in board.cells.chunks(board.width.max(1)).enumerate() {
+                let start = (y + dy) * width + x;
+                frame.cells[start..start + board.width].copy_from_slice(cells);
+            }
+            frame.ants.extend(
+                board
+                    .ants
+                    .iter()
+                    .map(|ant| Pos::new(ant.x + x as isize, ant.y + y as isize)),
+            );
+        }
+        frame
+    }
+
+    /// The mosaic as a `W`x`H` board to walk on or save like any other, `None` unless
+    /// it's exactly that size
+    pub fn to_map<const W: usize, const H: usize, M: CellStore<W, H>>(&self) -> Option<M> {
+        (self.width() == W && self.height() == H).then(|| M::with_colors(self.frame().cells))
+    }
+}
+
+#[test]
+fn boards_line_up_in_a_grid() {
+    use crate::{ColorMap, MapPos};
+
+    let mut small = ColorMap::<2, 2>::new_white();
+    small.set_color(MapPos::validate_pos(Pos::new(1, 1)).unwrap(), 2);
+    let mut mosaic = Mosaic::square(3).with_spacing(1).with_gap_color(1);
+    mosaic.push(Frame::new(&ColorMap::<4, 3>::new_white(), 2, None));
+    mosaic.push(Frame::new(&small, 3, None).with_ants(&[Pos::new(0, 0)]));
+    mosaic.push(Frame::new(&small, 3, None));
+    assert_eq!((mosaic.len(), mosaic.width(), mosaic.height()), (3, 11, 9));
+
+    let frame = mosaic.frame();
+    assert_eq!(frame.colors, 3);
+    // The small board sits in the middle of the second cell, a row down and a column in
+    assert_eq!(frame.ants, [Pos::new(7, 1)]);
+    let row = |y: usize| &frame.cells[y * 11..(y + 1) * 11];
+    assert_eq!(row(0), [1; 11]);
+    assert_eq!(row(1), [1, 0, 0, 0, 0, 1, 1, 0, 0, 1, 1]);
+    assert_eq!(row(2), [1, 0, 0, 0, 0, 1, 1, 0, 2, 1, 1]);
+    assert_eq!(row(6), [1, 1, 0, 2, 1, 1, 1, 1, 1, 1, 1]);
+
+    let map = mosaic.to_map::<11, 9, ColorMap<11, 9>>().unwrap();
+    assert_eq!(map.colors(), frame.cells);
+    assert!(mosaic.to_map::<9, 11, ColorMap<9, 11>>().is_none());
+}
@@ -0,0 +1,104 @@
+//! The naive solution: a plain 2D array of bools and an ant that checks bounds by hand
+
+#[cfg(feature = "io")]
+use std::path::Path;
+
+#[cfg(feature = "io")]
+use image::{GrayImage, Luma};
+
+use crate::{Direction, Pos};
+
+#[cfg(feature = "io")]
+/// Saves the map as an 8-bit grayscale image
+pub fn save_map_to_file<const W: usize, const H: usize>(
+    map: &Map<W, H>,
+    file: impl AsRef<Path>,
+) -> Result<(), image::ImageError> {
+    let mut img = GrayImage::new(W as _, H as _);
+
+    for y in 0..H as _ {
+        for x in 0..W as _ {
+            let is_white = map.get(&Pos::new(x, y)).unwrap();
+            img.put_pixel(x as _, y as _, Luma([if is_white { 255 } else { 0 }]));
+        }
+    }
+
+    img.save(file)
+}
+
+pub struct Map<const W: usize, const H: usize>([[bool; W]; H]);
+
+impl<const W: usize, const H: usize> Map<W, H> {
+    pub fn new() -> Self {
+        Self([[true; W]; H])
+    }
+
+    pub fn get_mut<'m>(&'m mut self, pos: &Pos) -> Option<&'m mut bool> {
+        self.0.get_mut(pos.y as usize)?.get_mut(pos.x as usize)
+    }
+
+    pub fn get(&self, pos: &Pos) -> Option<bool> {
+        self.0.get(pos.y as usize)?.get(pos.x as usize).copied()
+    }
+
+    pub fn count_black_tiles(&self) -> usize {
+        self.0.iter().flatten().filter(|e| !**e).count()
+    }
+}
+
+impl<const W: usize, const H: usize> Default for Map<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub struct Ant {
+    pub pos: Pos,
+    pub dir: Direction,
+}
+
+impl Ant {
+    pub fn new(pos: Pos, dir: Direction) -> Self {
+        Self { pos, dir }
+    }
+
+    /// Returns whether the ant can walk any further
+    pub fn walk<const W: usize, const H: usize>(&mut self, map: &mut Map<W, H>) -> bool {
+        let cell = map.get_mut(&self.pos).unwrap();
+
+        *cell = !*cell;
+
+        self.dir = match cell {
+            true => self.dir.cw(),
+            false => self.dir.ccw(),
+        };
+
+        let shift = self.dir.to_shift();
+
+        let new_x = self.pos.x + shift.x;
+        let new_y = self.pos.y + shift.y;
+
+        if new_x < 0 || new_x >= W as _ || new_y < 0 || new_y >= H as _ {
+            return false;
+        }
+
+        self.pos.x = new_x;
+        self.pos.y = new_y;
+
+        true
+    }
+
+    pub fn walk_until_end<const W: usize, const H: usize>(&mut self, map: &mut Map<W, H>) {
+        while self.walk(map) {}
+    }
+}
+
+#[test]
+fn check_map_bounds() {
+    let mut map = Map::<1, 1>::new();
+
+    let mut ant = Ant::new(Pos::new(0, 0), Direction::North);
+
+    assert!(!ant.walk(&mut map)); // ant can't go any further
+}
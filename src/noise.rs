@@ -0,0 +1,71 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::CellStore;
+
+/// Random black cells sprinkled over a white board, the same seed always gives
+/// the same board
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RandomFill {
+    density: f64,
+    seed: u64,
+}
+
+impl RandomFill {
+    /// `density` is the chance of every cell to start black, `None` unless it's
+    /// in `0.0..=1.0`
+    pub fn new(density: f64, seed: u64) -> Option<Self> {
+        (0.0..=1.0)
+            .contains(&density)
+            .then_some(Self { density, seed })
+    }
+
+    pub fn density(&self) -> f64 {
+        self.density
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// `true` is a black cell, row-major
+    pub fn black_cells(&self, width: usize, height: usize) -> Vec<bool> {
+        // A ChaCha stream is fixed by its seed on every platform, which cells turn
+        // black also depends on how rand's gen_bool reads it
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        (0..width * height)
+            .map(|_| rng.gen_bool(self.density))
+            .collect()
+    }
+
+    /// Board of white cells and black ones, black is color `1`
+    pub fn fill<const W: usize, const H: usize, M: CellStore<W, H>>(&self) -> M {
+        let cells = self.black_cells(W, H);
+        M::with_colors(cells.into_iter().map(u8::from).collect())
+    }
+}
+
+#[test]
+fn same_seed_same_board() {
+    use crate::{ColorMap, Map};
+
+    let fill = RandomFill::new(0.3, 42).unwrap();
+    let cells = fill.black_cells(64, 64);
+    assert_eq!(cells, fill.black_cells(64, 64));
+    assert_ne!(cells, RandomFill::new(0.3, 43).unwrap().black_cells(64, 64));
+
+    let black = cells.iter().filter(|&&black| black).count();
+    assert!((1100..1360).contains(&black), "{black} black cells");
+
+    let map: Map<64, 64> = fill.fill();
+    let colors: ColorMap<64, 64> = fill.fill();
+    assert_eq!(map.count_black_tiles(), black);
+    assert_eq!(map.to_colors(), colors.to_colors());
+    assert!(!RandomFill::new(0.0, 1)
+        .unwrap()
+        .black_cells(8, 8)
+        .contains(&true));
+    for density in [f64::NAN, -0.1, 1.5, f64::INFINITY] {
+        assert_eq!(RandomFill::new(density, 1), None);
+    }
+}
@@ -0,0 +1,388 @@
+use std::{
+    any::Any,
+    fmt::{self, Display},
+    ops::Range,
+    str::FromStr,
+};
+
+use crate::{CellStore, Crop, EventKinds, Map, MapPos, Pos, SimEvent, Simulation};
+
+/// Gets told what a [`Simulation`] does, so statistics, renderers or progress bars can
+/// follow a run without changing how it walks, see [`Simulation::with_observer`]
+///
+/// Every method does nothing unless implemented
+pub trait Observer<const W: usize, const H: usize, M = Map<W, H>> {
+    /// After every tick, once every ant moved
+    fn on_step(&mut self, _sim: &Simulation<W, H, M>) {}
+
+    /// Ant number `ant` repainted the cell at `pos` from color `from` to `to`
+    fn on_cell_flip(&mut self, _ant: usize, _pos: MapPos<'static, W, H>, _from: u8, _to: u8) {}
+
+    /// The rule was swapped for [`Simulation::rule`] before tick [`Simulation::steps`]
+    fn on_rule_change(&mut self, _sim: &Simulation<W, H, M>) {}
+
+    /// Once, after the last ant left the map or when [`Simulation::finish`] is called
+    fn on_finish(&mut self, _sim: &Simulation<W, H, M>) {}
+
+    /// Kinds of [`SimEvent`] to get in [`on_event`](Self::on_event), asked once when the
+    /// observer is added. None by default
+    fn subscriptions(&self) -> EventKinds {
+        EventKinds::NONE
+    }
+
+    /// Something the simulation did, of a kind in [`subscriptions`](Self::subscriptions)
+    fn on_event(&mut self, _event: &SimEvent) {}
+}
+
+/// An [`Observer`] of any type, so a simulation can hold several and hand them
+/// back by type
+pub(crate) trait AnyObserver<const W: usize, const H: usize, M>: Observer<W, H, M> {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<const W: usize, const H: usize, M, T: Observer<W, H, M> + 'static> AnyObserver<W, H, M> for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Which ticks a [`Filtered`] observer is told about, written like
+/// `every=100 steps=1000..50000 region=0,0..63,63`
+///
+/// Ticks pass when their step is a multiple of `every` in `steps`, and with a region
+/// when an ant stands in it. Cell flips pass in the ticks that do, inside the region
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct StepFilter {
+    pub every: u64,
+    pub steps: Range<u64>,
+    pub region: Option<Crop>,
+}
+
+impl Default for StepFilter {
+    /// Every tick and every cell
+    fn default() -> Self {
+        Self {
+            every: 1,
+            steps: 0..u64::MAX,
+            region: None,
+        }
+    }
+}
+
+impl StepFilter {
+    pub fn passes_step(&self, step: u64) -> bool {
+        self.steps.contains(&step) && step.is_multiple_of(self.every.max(1))
+    }
+
+    pub fn passes_cell(&self, pos: Pos) -> bool {
+        self.region.is_none_or(|region| {
+            (region.min.x..=region.max.x).contains(&pos.x)
+                && (region.min.y..=region.max.y).contains(&pos.y)
+        })
+    }
+}
+
+impl Display for StepFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut clauses = Vec::new();
+        if self.every != 1 {
+            clauses.push(format!("every={}", self.every));
+        }
+        if self.steps != (0..u64::MAX) {
+            let end = (self.steps.end != u64::MAX).then_some(self.steps.end);
+            let end = end.map_or(String::new(), |end| end.to_string());
+            clauses.push(format!("steps={}..{end}", self.steps.start));
+        }
+        if let Some(Crop { min, max }) = self.region {
+            clauses.push(format!("region={},{}..{},{}", min.x, min.y, max.x, max.y));
+        }
+        match clauses.is_empty() {
+            true => write!(f, "all"),
+            false => write!(f, "{}", clauses.join(" ")),
+        }
+    }
+}
+
+impl FromStr for StepFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = Self::default();
+        for clause in s.split_whitespace().filter(|&clause| clause != "all") {
+            let (key, value) = clause
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got {clause:?}"))?;
+            let number = |n: &str| {
+                n.trim()
+                    .parse::<i64>()
+                    .map_err(|_| format!("bad number {n:?} in {clause:?}"))
+            };
+            let range = || {
+                value
+                    .split_once("..")
+                    .ok_or_else(|| format!("expected a range like 10..20, got {value:?}"))
+            };
+            match key {
+                "every" => {
+                    filter.every = number(value)?
+                        .try_into()
+                        .ok()
+                        .filter(|&every| every > 0)
+                        .ok_or_else(|| format!("every must be positive, got {value:?}"))?;
+                }
+                "steps" => {
+                    let (start, end) = range()?;
+                    let bound = |n: &str, open| match n.trim() {
+                        "" => Ok(open),
+                        n => u64::try_from(number(n)?).map_err(|_| format!("negative step {n:?}")),
+                    };
+                    filter.steps = bound(start, 0)?..bound(end, u64::MAX)?;
+                    if filter.steps.is_empty() {
+                        return Err(format!("no step is in {value:?}"));
+                    }
+                }
+                "region" => {
+                    let (min, max) = range()?;
+                    let corner = |c: &str| {
+                        let (x, y) = c
+                            .split_once(',')
+                            .ok_or_else(|| format!("expected x,y, got {c:?}"))?;
+                        Ok::<_, String>(Pos::new(number(x)? as _, number(y)? as _))
+                    };
+                    let (min, max) = (corner(min)?, corner(max)?);
+                    if min.x > max.x || min.y > max.y {
+                        return Err(format!("region {value:?} is empty"));
+                    }
+                    filter.region = Some(Crop { min, max });
+                }
+                _ => {
+                    return Err(format!(
+                        "unknown filter {key:?}, expected every, steps or region"
+                    ))
+                }
+            }
+        }
+        Ok(filter)
+    }
+}
+
+/// An observer told only about what passes a [`StepFilter`], so it doesn't have to
+/// sample on its own. The rule changes and the end of the run always reach it
+///
+/// ```
+/// use antventure::{Direction, Filtered, Map, Observer, Pos, Simulation, StepFilter};
+///
+/// #[derive(Default)]
+/// struct Steps(Vec<u64>);
+///
+/// impl Observer<64, 64> for Steps {
+///     fn on_step(&mut self, sim: &Simulation<64, 64>) {
+///         self.0.push(sim.steps());
+///     }
+/// }
+///
+/// let filter = "every=100 steps=..500".parse::<StepFilter>().unwrap();
+/// let mut sim = Simulation::new(Map::<64, 64>::new_white(), Default::default())
+///     .with_observer(Filtered::new(Steps::default(), filter));
+/// sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+/// sim.run_at_most(1000);
+/// let steps = &sim.observer::<Filtered<Steps>>().unwrap().inner().0;
+/// assert_eq!(steps, &[100, 200, 300, 400]);
+/// ```
+pub struct Filtered<T> {
+    observer: T,
+    filter: StepFilter,
+    /// Last step seen, flips come during the one after it
+    step: u64,
+}
+
+impl<T> Filtered<T> {
+    pub fn new(observer: T, filter: StepFilter) -> Self {
+        Self {
+            observer,
+            filter,
+            step: 0,
+        }
+    }
+
+    /// For a run that doesn't start at step 0, so the flips of its first tick are
+    /// counted in the right one
+    pub fn at_step(mut self, step: u64) -> Self {
+        self.step = step;
+        self
+    }
+
+    pub fn filter(&self) -> &StepFilter {
+        &self.filter
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.observer
+    }
+
+    pub fn into_inner(self) -> T {
+        self.observer
+    }
+}
+
+impl<const W: usize, const H: usize, M: CellStore<W, H>, T: Observer<W, H, M>> Observer<W, H, M>
+    for Filtered<T>
+{
+    fn on_step(&mut self, sim: &Simulation<W, H, M>) {
+        self.step = sim.steps();
+        let in_region = || {
+            sim.ants()
+                .iter()
+                .any(|ant| ant.is_on_map() && self.filter.passes_cell(ant.pos().into()))
+        };
+        if self.filter.passes_step(self.step) && in_region() {
+            self.observer.on_step(sim);
+        }
+    }
+
+    fn on_cell_flip(&mut self, ant: usize, pos: MapPos<'static, W, H>, from: u8, to: u8) {
+        if self.filter.passes_step(self.step + 1) && self.filter.passes_cell(pos.into()) {
+            self.observer.on_cell_flip(ant, pos, from, to);
+        }
+    }
+
+    fn on_rule_change(&mut self, sim: &Simulation<W, H, M>) {
+        self.step = sim.steps();
+        self.observer.on_rule_change(sim);
+    }
+
+    fn on_finish(&mut self, sim: &Simulation<W, H, M>) {
+        self.observer.on_finish(sim);
+    }
+
+    fn subscriptions(&self) -> EventKinds {
+        self.observer.subscriptions()
+    }
+
+    fn on_event(&mut self, event: &SimEvent) {
+        let step = match *event {
+            SimEvent::Step { step } | SimEvent::Generation { step } => Some(step),
+            SimEvent::PhaseChange(_) | SimEvent::RuleChange { .. } => None,
+            _ => Some(self.step + 1),
+        };
+        let passes = step.is_none_or(|step| self.filter.passes_step(step))
+            && event.pos().is_none_or(|pos| self.filter.passes_cell(pos));
+        if passes {
+            self.observer.on_event(event);
+        }
+    }
+}
+
+#[test]
+fn observers_follow_the_run() {
+    use crate::{Direction, Pos, Rule};
+
+    #[derive(Default)]
+    struct Counts {
+        steps: u64,
+        flips: [usize; 2],
+        finished: usize,
+    }
+
+    impl<const W: usize, const H: usize> Observer<W, H> for Counts {
+        fn on_step(&mut self, sim: &Simulation<W, H>) {
+            self.steps += 1;
+            assert_eq!(self.steps, sim.steps());
+        }
+
+        fn on_cell_flip(&mut self, _ant: usize, _pos: MapPos<'static, W, H>, from: u8, to: u8) {
+            assert_ne!(from, to);
+            self.flips[to as usize] += 1;
+        }
+
+        fn on_finish(&mut self, _sim: &Simulation<W, H>) {
+            self.finished += 1;
+        }
+    }
+
+    let mut sim = Simulation::new(Map::<16, 16>::new_white(), Rule::default())
+        .with_observer(Counts::default());
+    sim.spawn(Pos::new(8, 8), Direction::North).unwrap();
+    sim.run_at_most(100);
+
+    let counts = sim.observer::<Counts>().unwrap();
+    assert_eq!((counts.steps, counts.finished), (100, 0));
+    // Every cell turned black and not back again is black now
+    assert_eq!(
+        counts.flips[1] - counts.flips[0],
+        sim.map().count_black_tiles()
+    );
+
+    sim.run_until_end();
+    sim.finish();
+    let counts = sim.observer::<Counts>().unwrap();
+    assert_eq!(counts.finished, 1);
+    assert_eq!(counts.steps, sim.steps());
+    assert!(sim.observer::<String>().is_none());
+}
+
+#[test]
+fn filters_pick_ticks_and_cells() {
+    use crate::{Direction, Rule};
+
+    #[derive(Default)]
+    struct Seen {
+        steps: Vec<u64>,
+        flips: Vec<Pos>,
+    }
+
+    impl<const W: usize, const H: usize> Observer<W, H> for Seen {
+        fn on_step(&mut self, sim: &Simulation<W, H>) {
+            self.steps.push(sim.steps());
+        }
+
+        fn on_cell_flip(&mut self, _ant: usize, pos: MapPos<'static, W, H>, _from: u8, _to: u8) {
+            self.flips.push(pos.into());
+        }
+    }
+
+    let filter = "every=3 steps=3..20 region=8,8..9,9"
+        .parse::<StepFilter>()
+        .unwrap();
+    assert_eq!(filter.to_string(), "every=3 steps=3..20 region=8,8..9,9");
+    let mut sim = Simulation::new(Map::<16, 16>::new_white(), Rule::default())
+        .with_observer(Filtered::new(Seen::default(), filter.clone()));
+    sim.spawn(Pos::new(8, 8), Direction::North).unwrap();
+    let mut everything =
+        Simulation::new(Map::<16, 16>::new_white(), Rule::default()).with_observer(Seen::default());
+    everything.spawn(Pos::new(8, 8), Direction::North).unwrap();
+    sim.run_at_most(40);
+    everything.run_at_most(40);
+
+    let seen = sim.observer::<Filtered<Seen>>().unwrap().inner();
+    let all = everything.observer::<Seen>().unwrap();
+    // The ant stands on a cell of the region after the steps of the flips that lead to it
+    let stands_in_region = |step: u64| filter.passes_cell(all.flips[step as usize]);
+    let expected = (3..20)
+        .step_by(3)
+        .filter(|&step| stands_in_region(step))
+        .collect::<Vec<_>>();
+    assert!(!expected.is_empty());
+    assert_eq!(seen.steps, expected);
+    let flips = (0..40)
+        .filter(|&i| filter.passes_step(i as u64 + 1) && filter.passes_cell(all.flips[i]))
+        .map(|i| all.flips[i])
+        .collect::<Vec<_>>();
+    assert_eq!(seen.flips, flips);
+
+    assert_eq!("all".parse(), Ok(StepFilter::default()));
+    assert_eq!(StepFilter::default().to_string(), "all");
+    assert_eq!(
+        "steps=100..".parse::<StepFilter>().unwrap().steps,
+        100..u64::MAX
+    );
+    for invalid in [
+        "every=0",
+        "steps=5..5",
+        "region=3,3..1,1",
+        "every",
+        "often=2",
+    ] {
+        assert!(invalid.parse::<StepFilter>().is_err(), "{invalid:?}");
+    }
+}
@@ -0,0 +1,88 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{bitset::BitSet, MapPos};
+
+/// What an ant does when the cell ahead of it is an obstacle
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum ObstaclePolicy {
+    /// It bounces back, staying on its cell and turning around
+    #[default]
+    Reflect,
+    /// It stays on its cell facing the obstacle, the next step turns it again
+    Wait,
+    /// It stops for good on the cell before the obstacle
+    Stop,
+}
+
+impl FromStr for ObstaclePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "reflect" => Ok(ObstaclePolicy::Reflect),
+            "wait" => Ok(ObstaclePolicy::Wait),
+            "stop" => Ok(ObstaclePolicy::Stop),
+            _ => Err(format!(
+                "unknown obstacle policy {s:?}, expected reflect, wait or stop"
+            )),
+        }
+    }
+}
+
+/// Cells no ant can enter, laid over a map of the same size
+///
+/// Obstacles keep their color on the map, ants just never stand on them
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Obstacles<const W: usize, const H: usize>(BitSet);
+
+impl<const W: usize, const H: usize> Obstacles<W, H> {
+    /// No obstacles at all
+    pub fn new() -> Self {
+        Self(BitSet::filled(W * H, false))
+    }
+
+    /// Cells row by row, `true` is an obstacle
+    pub fn from_cells(cells: impl IntoIterator<Item = bool>) -> Self {
+        let mut obstacles = Self::new();
+        for (i, blocked) in cells.into_iter().take(W * H).enumerate() {
+            obstacles.0.set(i, blocked);
+        }
+        obstacles
+    }
+
+    pub fn is_blocked(&self, pos: MapPos<W, H>) -> bool {
+        self.0.get(pos.index())
+    }
+
+    pub fn set(&mut self, pos: MapPos<W, H>, blocked: bool) {
+        self.0.set(pos.index(), blocked);
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.count_ones()
+    }
+}
+
+impl<const W: usize, const H: usize> Default for Obstacles<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn obstacles_by_cell() {
+    use crate::Pos;
+
+    let mut obstacles = Obstacles::<4, 3>::from_cells([false, true, false, false, true]);
+    let at = |x, y| MapPos::validate_pos(Pos::new(x, y)).unwrap();
+    assert!(obstacles.is_blocked(at(1, 0)) && obstacles.is_blocked(at(0, 1)));
+    assert!(!obstacles.is_blocked(at(0, 0)) && !obstacles.is_blocked(at(3, 2)));
+
+    obstacles.set(at(3, 2), true);
+    obstacles.set(at(1, 0), false);
+    assert_eq!(obstacles.count(), 2);
+    assert_eq!(" WaIt ".trim().parse(), Ok(ObstaclePolicy::Wait));
+    assert!("ignore".parse::<ObstaclePolicy>().is_err());
+}
@@ -0,0 +1,218 @@
+use std::{cmp::Ordering, collections::HashSet};
+
+use crate::{
+    similarity, CellStore, ColorMap, Direction, Ensemble, Map, Pos, Rule, Simulation,
+    SweepExecutor, WorkerStats,
+};
+
+/// Starts of a two-color rule walked at once, as many as an [`Ensemble`] holds
+const LANES: usize = 16;
+
+/// Where and how an ant has to start to draw something like the target,
+/// see [`TargetSearch`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Candidate {
+    pub rule: Rule,
+    pub start: Pos,
+    pub dir: Direction,
+    /// [`similarity`] of the board the ant left to the target
+    pub similarity: f64,
+}
+
+/// Looks for the rule, start cell and direction whose ant leaves a board most like
+/// a target after a fixed number of steps
+///
+/// Every rule gets a job on a [`SweepExecutor`]: its ant starts on a grid of cells
+/// every `stride` cells apart facing every way, then the best start is nudged to
+/// neighbouring cells at half the distance and less for as long as that helps.
+/// Two-color rules try their starts 16 at a time in an [`Ensemble`]. Only whether
+/// a cell is white counts, so the target can be a black and white image
+///
+/// ```
+/// use antventure::{CellStore, Map, Pos, Direction, TargetSearch};
+///
+/// let search = TargetSearch::<32>::new(500).with_stride(8);
+/// let target = search.walk(&"LR".parse().unwrap(), Pos::new(16, 16), Direction::East);
+/// let (found, _) = search.run(&Map::with_colors(target.to_colors()), vec!["LR".parse().unwrap()]);
+/// assert_eq!(found[0].similarity, 1.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TargetSearch<const S: usize> {
+    steps: u64,
+    stride: usize,
+    executor: SweepExecutor,
+}
+
+impl<const S: usize> TargetSearch<S> {
+    /// Walk every ant `steps` steps, or until it leaves the map
+    pub fn new(steps: u64) -> Self {
+        Self {
+            steps,
+            stride: 8,
+            executor: SweepExecutor::new(),
+        }
+    }
+
+    /// Cells between the starts tried first, smaller finds more but takes longer
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        self.stride = stride.max(1);
+        self
+    }
+
+    pub fn with_executor(mut self, executor: SweepExecutor) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// The best start of every rule, most similar first
+    pub fn run(&self, target: &Map<S, S>, rules: Vec<Rule>) -> (Vec<Candidate>, Vec<WorkerStats>) {
+        // Compared as bytes, going through the bits of a Map cell by cell is slow
+        let target = &ColorMap::<S, S>::from_colors(target.to_colors());
+        let jobs = rules
+            .into_iter()
+            .map(|rule| move || self.best_start(target, rule))
+            .collect();
+        let (mut found, stats) = self.executor.run(jobs);
+        found.sort_by(|a: &Candidate, b| {
+            b.similarity
+                .partial_cmp(&a.similarity)
+                .unwrap_or(Ordering::Equal)
+        });
+        (found, stats)
+    }
+
+    /// Board an ant walking `rule` from `start` leaves
+    pub fn walk(&self, rule: &Rule, start: Pos, dir: Direction) -> ColorMap<S, S> {
+        self.simulate(rule, start, dir).0
+    }
+
+    fn simulate(&self, rule: &Rule, start: Pos, dir: Direction) -> (ColorMap<S, S>, u64) {
+        let mut sim = Simulation::new(ColorMap::<S, S>::new_white(), rule.clone());
+        sim.spawn(start, dir).expect("starts are on the map");
+        sim.run_at_most(self.steps);
+        let steps = sim.steps();
+        (sim.into_map(), steps)
+    }
+
+    /// Similarity to `target` of the board each of `starts` leaves, and the steps
+    /// walked. Two-color rules walk 16 starts at a time in an [`Ensemble`]
+    fn score(
+        &self,
+        target: &ColorMap<S, S>,
+        rule: &Rule,
+        starts: &[(Pos, Direction)],
+    ) -> Vec<(f64, u64)> {
+        if rule.colors() != 2 {
+            return starts
+                .iter()
+                .map(|&(start, dir)| {
+                    let (board, steps) = self.simulate(rule, start, dir);
+                    let colored = board.colors().iter().map(|&color| color.min(1));
+                    (
+                        similarity(&ColorMap::<S, S>::from_colors(colored), target),
+                        steps,
+                    )
+                })
+                .collect();
+        }
+        starts
+            .chunks(LANES)
+            .flat_map(|chunk| {
+                // Lanes past the chunk walk its first start again and are left out
+                let ants = std::array::from_fn(|lane| chunk.get(lane).copied().unwrap_or(chunk[0]));
+                let mut ensemble = Ensemble::<S, S, LANES>::with_rule(rule, ants)
+                    .expect("two colors, and starts on the map");
+                ensemble.walk_at_most(self.steps);
+                (0..chunk.len())
+                    .map(|lane| {
+                        let board = ensemble.to_color_map(lane);
+                        (similarity(&board, target), ensemble.steps(lane))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Grid search and then hill climbing for a single rule, also returns the steps walked
+    fn best_start(&self, target: &ColorMap<S, S>, rule: Rule) -> (Candidate, u64) {
+        let mut tried = HashSet::new();
+        let mut walked = 0;
+        let mut best = Candidate {
+            rule,
+            start: Pos::new(S as isize / 2, S as isize / 2),
+            dir: Direction::North,
+            similarity: -1.0,
+        };
+        // Every way from each of `cells` not tried yet, scored together
+        let mut try_starts = |best: &mut Candidate, cells: Vec<Pos>| {
+            let on_map =
+                |pos: &Pos| (0..S as isize).contains(&pos.x) && (0..S as isize).contains(&pos.y);
+            let starts = cells
+                .into_iter()
+                .filter(on_map)
+                .flat_map(|start| Direction::VARIANTS.map(|dir| (start, dir)))
+                .filter(|&(start, dir)| tried.insert((start.x, start.y, dir as usize)))
+                .collect::<Vec<_>>();
+            let mut improved = false;
+            for ((start, dir), (score, steps)) in
+                starts.iter().zip(self.score(target, &best.rule, &starts))
+            {
+                walked += steps;
+                if score > best.similarity {
+                    (best.start, best.dir, best.similarity) = (*start, *dir, score);
+                    improved = true;
+                }
+            }
+            improved
+        };
+
+        // Grid cells sit in the middle of their square, the center is on it for even strides
+        let offset = self.stride / 2;
+        let grid = (offset..S).step_by(self.stride).flat_map(|y| {
+            (offset..S)
+                .step_by(self.stride)
+                .map(move |x| Pos::new(x as _, y as _))
+        });
+        try_starts(&mut best, grid.collect());
+
+        let mut reach = self.stride as isize / 2;
+        while reach > 0 {
+            let mut improved = true;
+            while improved {
+                let center = best.start;
+                let around = (-1..=1).flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)));
+                let cells =
+                    around.map(|(dx, dy)| Pos::new(center.x + dx * reach, center.y + dy * reach));
+                improved = try_starts(&mut best, cells.collect());
+            }
+            reach /= 2;
+        }
+        (best, walked)
+    }
+}
+
+#[test]
+fn finds_the_start_of_a_board() {
+    let search = TargetSearch::<48>::new(1500)
+        .with_stride(6)
+        .with_executor(SweepExecutor::new().workers(2));
+    let rule = "RL".parse::<Rule>().unwrap();
+    let board = search.walk(&rule, Pos::new(17, 30), Direction::West);
+    let target = Map::<48, 48>::with_colors(board.to_colors());
+
+    let rules = ["LR", "RL", "LLRR"]
+        .map(|rule| rule.parse().unwrap())
+        .to_vec();
+    let (found, stats) = search.run(&target, rules);
+    assert_eq!(stats.iter().map(|s| s.jobs).sum::<usize>(), 3);
+    assert_eq!(found.len(), 3);
+    assert!(found.windows(2).all(|w| w[0].similarity >= w[1].similarity));
+
+    // Off the grid, but the climb gets there
+    let best = &found[0];
+    assert_eq!(best.rule, rule);
+    assert_eq!(
+        (best.start, best.dir, best.similarity),
+        (Pos::new(17, 30), Direction::West, 1.0)
+    );
+}
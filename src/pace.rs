@@ -0,0 +1,99 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Keeps steps or frames to a steady rate a second for runs followed live, instead of
+/// going as fast as they can
+///
+/// Ticks are due on a schedule from the start rather than a wait after each one, so
+/// waking late once doesn't slow down everything after it. A pacer more than a second
+/// behind, e.g. after a pause, starts a new schedule instead of rushing to catch up
+#[derive(Clone, Debug)]
+pub struct Pacer {
+    per_second: f64,
+    /// Tick the schedule started at, and when
+    start: (u64, Instant),
+}
+
+impl Pacer {
+    /// # Panics
+    ///
+    /// If `per_second` isn't a positive number
+    pub fn new(per_second: f64, tick: u64) -> Self {
+        assert!(per_second > 0.0, "pace must be positive");
+        Self {
+            per_second,
+            start: (tick, Instant::now()),
+        }
+    }
+
+    pub fn per_second(&self) -> f64 {
+        self.per_second
+    }
+
+    /// Last tick due by now
+    pub fn due(&self) -> u64 {
+        let (tick, started) = self.start;
+        tick + (started.elapsed().as_secs_f64() * self.per_second) as u64
+    }
+
+    /// Time until `tick` is due, zero once it is
+    pub fn until(&self, tick: u64) -> Duration {
+        let (first, started) = self.start;
+        let at = tick.saturating_sub(first) as f64 / self.per_second;
+        Duration::from_secs_f64(at).saturating_sub(started.elapsed())
+    }
+
+    /// Sleep until `tick` is due
+    pub fn wait(&mut self, tick: u64) {
+        if self.due() > tick.saturating_add(self.per_second.ceil() as u64) {
+            self.start = (tick, Instant::now());
+        }
+        thread::sleep(self.until(tick));
+    }
+
+    /// Sleep until `tick` is due at `speed` steps a second, on the schedule kept in
+    /// `pacer` from one call to the next and started over when the speed changes.
+    /// Returns how many steps to walk before the next call, a twentieth of a second's
+    /// worth, or `None` at full speed
+    pub fn keep_to(pacer: &mut Option<Pacer>, speed: Option<f64>, tick: u64) -> Option<u64> {
+        let Some(speed) = speed else {
+            *pacer = None;
+            return None;
+        };
+        if pacer
+            .as_ref()
+            .is_none_or(|pacer| pacer.per_second() != speed)
+        {
+            *pacer = Some(Pacer::new(speed, tick));
+        }
+        pacer.as_mut().expect("made above").wait(tick);
+        Some((speed / 20.0).ceil() as u64)
+    }
+}
+
+#[test]
+fn pacer_keeps_a_schedule() {
+    let mut pacer = Pacer::new(1000.0, 500);
+    assert!(pacer.due() >= 500);
+    assert_eq!(pacer.until(400), Duration::ZERO);
+    let second = pacer.until(1500);
+    assert!(second > Duration::from_millis(900) && second <= Duration::from_secs(1));
+
+    let started = Instant::now();
+    pacer.wait(520);
+    assert!(started.elapsed() >= Duration::from_millis(15));
+    assert!(pacer.due() >= 520);
+
+    // Ticks that were due long ago start a new schedule
+    pacer.start.1 -= Duration::from_secs(5);
+    pacer.wait(600);
+    assert!(pacer.due() < 700);
+
+    let mut kept = None;
+    assert_eq!(Pacer::keep_to(&mut kept, Some(2000.0), 0), Some(100));
+    assert_eq!(kept.as_ref().map(Pacer::per_second), Some(2000.0));
+    assert_eq!(Pacer::keep_to(&mut kept, None, 100), None);
+    assert!(kept.is_none());
+}
@@ -0,0 +1,131 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+
+/// RGB colors of cells and of the markers drawn over ants in indexed PNGs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    /// Color of every cell color, in rule order
+    pub colors: Vec<[u8; 3]>,
+    pub marker: [u8; 3],
+}
+
+impl Palette {
+    pub const RED: [u8; 3] = [255, 0, 0];
+
+    /// Colors of [`colorblind`](Self::colorblind) with their names
+    pub const COLORBLIND: [([u8; 3], &'static str); 9] = [
+        ([255, 255, 255], "white"),
+        ([0, 0, 0], "black"),
+        ([230, 159, 0], "orange"),
+        ([86, 180, 233], "sky blue"),
+        ([0, 158, 115], "bluish green"),
+        ([240, 228, 66], "yellow"),
+        ([0, 114, 178], "blue"),
+        ([213, 94, 0], "vermillion"),
+        ([204, 121, 167], "reddish purple"),
+    ];
+
+    /// The grayscale fade from white to black the other outputs use, with red markers
+    pub fn gray(colors: usize) -> Self {
+        let darkest = colors.saturating_sub(1).max(1);
+        Self {
+            colors: (0..colors)
+                .map(|c| [(255 - c * 255 / darkest) as u8; 3])
+                .collect(),
+            marker: Self::RED,
+        }
+    }
+
+    /// White followed by the eight colors of Okabe and Ito's palette, black first,
+    /// which stay apart with every common kind of color blindness
+    pub fn colorblind() -> Self {
+        Self {
+            colors: Self::COLORBLIND.iter().map(|&(rgb, _)| rgb).collect(),
+            marker: Self::RED,
+        }
+    }
+
+    /// Colors of [`Map::diff_map`](crate::Map::diff_map): white where neither board is
+    /// black, vermillion where only the first one is, blue where only the second one is
+    /// and black where both are
+    pub fn diff() -> Self {
+        Self {
+            colors: vec![[255, 255, 255], [213, 94, 0], [0, 114, 178], [0, 0, 0]],
+            marker: Self::RED,
+        }
+    }
+
+    /// `#rrggbb`, `rrggbb` or the short `#rgb`
+    pub fn parse_color(s: &str) -> Result<[u8; 3], ParsePaletteError> {
+        let hex = s.trim();
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        let bad = || ParsePaletteError::Color(s.trim().to_owned());
+
+        let digits = hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| d as u8))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(bad)?;
+        match digits[..] {
+            [r, g, b] => Ok([r * 17, g * 17, b * 17]),
+            [r1, r0, g1, g0, b1, b0] => Ok([r1 << 4 | r0, g1 << 4 | g0, b1 << 4 | b0]),
+            _ => Err(bad()),
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = ParsePaletteError;
+
+    /// Comma separated cell colors or `colorblind`, markers stay red
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("colorblind") {
+            return Ok(Self::colorblind());
+        }
+        let colors = s
+            .split(',')
+            .filter(|c| !c.trim().is_empty())
+            .map(Self::parse_color)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match colors.is_empty() {
+            true => Err(ParsePaletteError::Empty),
+            false => Ok(Self {
+                colors,
+                marker: Self::RED,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePaletteError {
+    Color(String),
+    Empty,
+}
+
+impl Display for ParsePaletteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParsePaletteError::Color(c) => write!(f, "bad color {c:?}, expected #rrggbb"),
+            ParsePaletteError::Empty => write!(f, "palette has no colors"),
+        }
+    }
+}
+
+impl Error for ParsePaletteError {}
+
+#[test]
+fn parse_palette() {
+    let palette = "#fff, 000000,#1a2B3c".parse::<Palette>().unwrap();
+    assert_eq!(
+        palette.colors,
+        [[255, 255, 255], [0, 0, 0], [0x1a, 0x2b, 0x3c]]
+    );
+    assert_eq!(Palette::gray(3).colors[1], [128; 3]);
+
+    assert_eq!("Colorblind".parse(), Ok(Palette::colorblind()));
+
+    assert!("#ffff".parse::<Palette>().is_err());
+    assert!("red".parse::<Palette>().is_err());
+    assert_eq!("".parse::<Palette>(), Err(ParsePaletteError::Empty));
+}
@@ -0,0 +1,180 @@
+use std::collections::VecDeque;
+
+use crate::{CycleDetector, Direction, Periodicity, Pos};
+
+/// Transition in the behaviour of an ant, see [`PhaseDetector`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PhaseEvent {
+    /// The walk turned periodic and the ant moves away, reported once
+    Highway(Periodicity),
+    /// The walk turned periodic in place, reported once
+    Cycle(Periodicity),
+    /// The visited area hasn't grown for this many steps, reported again after it grows
+    GrowthStalled { since: u64 },
+    /// The ant stepped on an edge cell of the map for the first time
+    BoundaryReached { pos: Pos },
+    /// The visited area spans the share of the map set with
+    /// [`with_coverage`](PhaseDetector::with_coverage), reported once
+    Coverage { fraction: f64 },
+}
+
+/// Watches one ant for phase changes: the end of the chaotic phase,
+/// the visited area no longer growing and the walk reaching the map edge
+pub struct PhaseDetector {
+    width: usize,
+    height: usize,
+    cycles: Option<CycleDetector>,
+    // Visited cells so far, as inclusive corners
+    bounds: Option<(Pos, Pos)>,
+    grew_at: u64,
+    stall_after: u64,
+    stalled: bool,
+    boundary: bool,
+    coverage: Option<f64>,
+    steps: u64,
+    // Events of the same step queue up behind the one returned
+    pending: VecDeque<PhaseEvent>,
+}
+
+impl PhaseDetector {
+    /// Detector for a `width`x`height` map, `window` is the [`CycleDetector`] window
+    pub fn new(width: usize, height: usize, window: usize) -> Self {
+        Self {
+            width,
+            height,
+            cycles: Some(CycleDetector::new(window)),
+            bounds: None,
+            grew_at: 0,
+            stall_after: 10_000,
+            stalled: false,
+            boundary: false,
+            coverage: None,
+            steps: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Steps without the visited area growing before it counts as stalled
+    pub fn with_stall_after(mut self, steps: u64) -> Self {
+        self.stall_after = steps.max(1);
+        self
+    }
+
+    /// Report once the visited area is as wide or as tall as `fraction` of the map
+    pub fn with_coverage(mut self, fraction: f64) -> Self {
+        self.coverage = Some(fraction);
+        self
+    }
+
+    /// Smallest rectangle holding every cell the ant stepped off, as inclusive corners
+    pub fn bounding_box(&self) -> Option<(Pos, Pos)> {
+        self.bounds
+    }
+
+    /// Feed one step, `color` is the color the ant read at `pos` while facing `dir`
+    pub fn observe(&mut self, color: u8, pos: Pos, dir: Direction) -> Option<PhaseEvent> {
+        self.steps += 1;
+
+        let grown = match &mut self.bounds {
+            None => {
+                self.bounds = Some((pos, pos));
+                true
+            }
+            Some((min, max)) => {
+                let outside = pos.x < min.x || pos.y < min.y || pos.x > max.x || pos.y > max.y;
+                min.x = min.x.min(pos.x);
+                min.y = min.y.min(pos.y);
+                max.x = max.x.max(pos.x);
+                max.y = max.y.max(pos.y);
+                outside
+            }
+        };
+        if grown {
+            self.grew_at = self.steps;
+            self.stalled = false;
+
+            if let (Some(target), Some((min, max))) = (self.coverage, self.bounds) {
+                let fraction = f64::max(
+                    (max.x - min.x + 1) as f64 / self.width as f64,
+                    (max.y - min.y + 1) as f64 / self.height as f64,
+                );
+                if fraction >= target {
+                    self.coverage = None;
+                    self.pending.push_back(PhaseEvent::Coverage { fraction });
+                }
+            }
+        }
+
+        let (right, bottom) = (self.width as isize - 1, self.height as isize - 1);
+        let on_edge = pos.x == 0 || pos.y == 0 || pos.x == right || pos.y == bottom;
+        if on_edge && !self.boundary {
+            self.boundary = true;
+            self.pending.push_back(PhaseEvent::BoundaryReached { pos });
+        }
+
+        if let Some(periodicity) = self
+            .cycles
+            .as_mut()
+            .and_then(|c| c.observe(color, pos, dir))
+        {
+            // Periodic walks stay periodic, no need to keep hashing
+            self.cycles = None;
+            self.pending.push_back(match periodicity.is_highway() {
+                true => PhaseEvent::Highway(periodicity),
+                false => PhaseEvent::Cycle(periodicity),
+            });
+        }
+
+        let since = self.steps - self.grew_at;
+        if !self.stalled && since >= self.stall_after {
+            self.stalled = true;
+            self.pending.push_back(PhaseEvent::GrowthStalled { since });
+        }
+
+        self.pending.pop_front()
+    }
+
+    /// Another event of the last observed step, if several happened at once
+    pub fn pending(&mut self) -> Option<PhaseEvent> {
+        self.pending.pop_front()
+    }
+}
+
+#[test]
+fn langton_phases() {
+    use crate::{Map, Rule, Simulation};
+
+    let mut sim = Simulation::new(Map::<1024, 1024>::new_white(), Rule::default());
+    sim.spawn(Pos::new(512, 512), Direction::North).unwrap();
+    let mut detector = PhaseDetector::new(1024, 1024, 512).with_stall_after(5_000);
+
+    let mut events = Vec::new();
+    while let Some(event) = sim.run_watching(30_000, &mut detector) {
+        events.push((sim.steps(), event));
+    }
+
+    // The highway shows up after about 10000 chaotic steps and runs into the edge
+    assert!(matches!(events[0], (_, PhaseEvent::Highway(p)) if p.period == 104));
+    assert!(matches!(events[1], (_, PhaseEvent::BoundaryReached { .. })));
+    assert!(events[0].0 > 9_000 && events[1].0 > events[0].0);
+    // Highways keep growing
+    assert!(!events
+        .iter()
+        .any(|(_, e)| matches!(e, PhaseEvent::GrowthStalled { .. })));
+
+    let mut sim = Simulation::new(Map::<64, 64>::new_white(), Rule::default());
+    sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+    let mut detector = PhaseDetector::new(64, 64, 512).with_coverage(0.25);
+    while let Some(event) = sim.run_watching(u64::MAX, &mut detector) {
+        if let PhaseEvent::Coverage { fraction } = event {
+            let (min, max) = detector.bounding_box().unwrap();
+            assert_eq!(
+                fraction,
+                (max.x - min.x + 1).max(max.y - min.y + 1) as f64 / 64.0
+            );
+            assert_eq!(fraction, 0.25);
+            return;
+        }
+    }
+    panic!("the ant left before covering a quarter of the map");
+}
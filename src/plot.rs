@@ -0,0 +1,181 @@
+use std::{fmt::Write as _, fs, io, path::Path};
+
+use crate::{render::cropped_colors, CellStore};
+
+/// Pen plotter languages a [`Plot`] can be written in
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlotFormat {
+    Gcode,
+    Hpgl,
+}
+
+impl PlotFormat {
+    /// Guess the format from the file extension
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "gcode" | "nc" => Some(Self::Gcode),
+            "hpgl" | "plt" => Some(Self::Hpgl),
+            _ => None,
+        }
+    }
+}
+
+/// Pen plotter drawing of a board, every non-white cell is hatched with a horizontal stroke
+#[derive(Clone, Debug)]
+pub struct Plot {
+    /// Distance between cell centers in millimeters
+    pub cell_mm: f64,
+    /// G-code drawing speed in millimeters per minute
+    pub feed: u32,
+}
+
+impl Default for Plot {
+    fn default() -> Self {
+        Self {
+            cell_mm: 1.0,
+            feed: 1500,
+        }
+    }
+}
+
+/// Polylines through cell centers, the pen stays down along each one
+pub(crate) fn strokes<const W: usize, const H: usize>(
+    map: &impl CellStore<W, H>,
+) -> Vec<Vec<(usize, usize)>> {
+    let (cells, width, height) = cropped_colors(map, None);
+
+    // Runs of inked cells per row, (first x, last x, drawn)
+    let mut rows = cells
+        .chunks(width)
+        .map(|row| {
+            let mut runs = Vec::new();
+            let mut x = 0;
+            while x < width {
+                let start = x;
+                while x < width && row[x] != 0 {
+                    x += 1;
+                }
+                if x > start {
+                    runs.push((start, x - 1, false));
+                }
+                x += 1;
+            }
+            runs
+        })
+        .collect::<Vec<_>>();
+
+    let mut strokes = Vec::new();
+    for y in 0..height {
+        for i in 0..rows[y].len() {
+            let (a, b, drawn) = rows[y][i];
+            if drawn {
+                continue;
+            }
+            rows[y][i].2 = true;
+
+            let mut stroke = vec![(a, y), (b, y)];
+            let (mut x, mut y) = (b, y);
+            // Keep the pen down while the next row has a fresh run right under it
+            while let Some(run) = rows
+                .get_mut(y + 1)
+                .and_then(|runs| runs.iter_mut().find(|r| !r.2 && (r.0..=r.1).contains(&x)))
+            {
+                run.2 = true;
+                let (a, b, _) = *run;
+                y += 1;
+                stroke.push((x, y));
+
+                // Start from the nearer end so less of the run is drawn twice
+                let (near, far) = match x - a <= b - x {
+                    true => (a, b),
+                    false => (b, a),
+                };
+                if near != x {
+                    stroke.push((near, y));
+                }
+                if far != near {
+                    stroke.push((far, y));
+                }
+                x = far;
+            }
+
+            strokes.push(stroke);
+        }
+    }
+
+    strokes
+}
+
+impl Plot {
+    /// Write pen paths for `map` to `file`, returns how many strokes the pen draws
+    pub fn save<const W: usize, const H: usize>(
+        &self,
+        map: &impl CellStore<W, H>,
+        format: PlotFormat,
+        file: impl AsRef<Path>,
+    ) -> io::Result<usize> {
+        let strokes = strokes::<W, H>(map);
+        // Plotters have y growing upwards, boards downwards
+        let bottom = map.size().1 - 1;
+        let to_mm =
+            |(x, y): (usize, usize)| (x as f64 * self.cell_mm, (bottom - y) as f64 * self.cell_mm);
+
+        let mut out = String::new();
+        // Writing into a String can't fail
+        match format {
+            PlotFormat::Gcode => {
+                let _ = writeln!(out, "G21 ; millimeters\nG90 ; absolute positions\nG0 Z2");
+                for stroke in &strokes {
+                    let (x, y) = to_mm(stroke[0]);
+                    let _ = writeln!(out, "G0 X{x:.3} Y{y:.3}\nG1 Z0 F{}", self.feed);
+                    for &cell in &stroke[1..] {
+                        let (x, y) = to_mm(cell);
+                        let _ = writeln!(out, "G1 X{x:.3} Y{y:.3}");
+                    }
+                    let _ = writeln!(out, "G0 Z2");
+                }
+                let _ = writeln!(out, "G0 X0 Y0");
+            }
+            PlotFormat::Hpgl => {
+                // HPGL plotter units are 0.025 mm
+                let units = |cell| {
+                    let (x, y) = to_mm(cell);
+                    ((x * 40.0).round() as i64, (y * 40.0).round() as i64)
+                };
+                out.push_str("IN;SP1;");
+                for stroke in &strokes {
+                    let (x, y) = units(stroke[0]);
+                    let _ = write!(out, "\nPU{x},{y};PD");
+                    let points = stroke[1..].iter().map(|&cell| {
+                        let (x, y) = units(cell);
+                        format!("{x},{y}")
+                    });
+                    let _ = write!(out, "{};", points.collect::<Vec<_>>().join(","));
+                }
+                out.push_str("\nPU0,0;SP0;\n");
+            }
+        }
+
+        fs::write(file, out)?;
+        Ok(strokes.len())
+    }
+}
+
+#[test]
+fn strokes_follow_runs_down() {
+    use crate::{Map, MapPos, Pos};
+
+    // ###.
+    // ..##
+    // #...
+    let mut map = Map::<4, 3>::new_white();
+    for (x, y) in [(0, 0), (1, 0), (2, 0), (2, 1), (3, 1), (0, 2)] {
+        map.set(MapPos::validate_pos(Pos::new(x, y)).unwrap(), false);
+    }
+
+    assert_eq!(
+        strokes(&map),
+        [vec![(0, 0), (2, 0), (2, 1), (3, 1)], vec![(0, 2), (0, 2)]]
+    );
+}
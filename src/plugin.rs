@@ -0,0 +1,460 @@
+use std::{
+    error::Error,
+    ffi::{c_char, c_void, CStr},
+    fmt::Display,
+    io::{self, Write},
+    slice,
+};
+
+#[cfg(feature = "plugins")]
+use std::path::Path;
+
+#[cfg(feature = "plugins")]
+use libloading::Library;
+
+use crate::{
+    Direction, Frame, Renderer, Transition, TurmiteError, TurmiteRule, TurmiteTurn, TurnAction,
+};
+
+/// Version of the plugin ABI this crate speaks, plugins built for another one are
+/// turned down instead of crashing
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Name of the function a plugin library exports, `extern "C" fn() -> *const PluginDecl`
+pub const PLUGIN_ENTRY: &str = "antventure_plugin_v1";
+
+/// What a plugin library hands over, everything in it lives as long as the library
+///
+/// A plugin is a `cdylib` built against nothing but these `#[repr(C)]` types, so it
+/// works with any build of the crate speaking the same [`PLUGIN_ABI_VERSION`] and can
+/// be written in C just as well:
+///
+/// ```
+/// use std::ptr;
+/// use antventure::{PluginBehavior, PluginDecision, PluginDecl, PLUGIN_ABI_VERSION};
+///
+/// extern "C" fn decide(_state: u32, color: u8, out: *mut PluginDecision) -> bool {
+///     // Langton's ant: left on white, right on black, flipping the color
+///     let action = [PluginDecision::LEFT, PluginDecision::RIGHT][color as usize];
+///     unsafe { *out = PluginDecision { write: 1 - color, action, next: 0 } };
+///     true
+/// }
+///
+/// static BEHAVIOR: PluginBehavior = PluginBehavior { states: 1, colors: 2, decide };
+/// static DECL: PluginDecl = PluginDecl {
+///     abi_version: PLUGIN_ABI_VERSION,
+///     name: c"langton".as_ptr(),
+///     behavior: &BEHAVIOR,
+///     renderer: ptr::null(),
+/// };
+///
+/// #[no_mangle]
+/// pub extern "C" fn antventure_plugin_v1() -> *const PluginDecl {
+///     &DECL
+/// }
+/// ```
+#[repr(C)]
+pub struct PluginDecl {
+    /// [`PLUGIN_ABI_VERSION`] the plugin was built for
+    pub abi_version: u32,
+    /// Nul-terminated UTF-8
+    pub name: *const c_char,
+    /// Null for plugins without one
+    pub behavior: *const PluginBehavior,
+    /// Null for plugins without one
+    pub renderer: *const PluginRenderer,
+}
+
+// SAFETY: declarations are read-only and point at static data of the library
+unsafe impl Sync for PluginDecl {}
+
+/// Turmite of a plugin, asked once for every state and color when the plugin is
+/// loaded, so walking it is as fast as walking a [`TurmiteRule`] from a spec file
+#[repr(C)]
+pub struct PluginBehavior {
+    pub states: u32,
+    pub colors: u32,
+    /// Fill in what a turmite in `state` does on a cell of `color`, false if it can't
+    pub decide: extern "C" fn(state: u32, color: u8, out: *mut PluginDecision) -> bool,
+}
+
+/// A [`Transition`] across the ABI
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PluginDecision {
+    /// Color to repaint the cell with
+    pub write: u8,
+    /// One of the turns, [`NONE`](Self::NONE) to [`LEFT`](Self::LEFT), or a compass
+    /// direction to face, [`FACE_NORTH`](Self::FACE_NORTH) to
+    /// [`FACE_WEST`](Self::FACE_WEST)
+    pub action: u8,
+    /// State to carry on in
+    pub next: u32,
+}
+
+impl PluginDecision {
+    pub const NONE: u8 = 0;
+    pub const RIGHT: u8 = 1;
+    pub const UTURN: u8 = 2;
+    pub const LEFT: u8 = 3;
+    pub const FACE_NORTH: u8 = 4;
+    pub const FACE_EAST: u8 = 5;
+    pub const FACE_SOUTH: u8 = 6;
+    pub const FACE_WEST: u8 = 7;
+
+    pub(crate) fn transition(self) -> Option<Transition> {
+        let action = match self.action {
+            Self::NONE => TurmiteTurn::None.into(),
+            Self::RIGHT => TurmiteTurn::Right.into(),
+            Self::UTURN => TurmiteTurn::UTurn.into(),
+            Self::LEFT => TurmiteTurn::Left.into(),
+            face @ Self::FACE_NORTH..=Self::FACE_WEST => {
+                TurnAction::Face(Direction::VARIANTS[(face - Self::FACE_NORTH) as usize])
+            }
+            _ => return None,
+        };
+        Some(Transition {
+            write: self.write,
+            action,
+            next: self.next as usize,
+        })
+    }
+}
+
+/// Image format of a plugin
+#[repr(C)]
+pub struct PluginRenderer {
+    /// File extension the format goes by without the dot, nul-terminated UTF-8
+    pub extension: *const c_char,
+    /// Write `frame` through `write`, called with `sink` and the bytes as often as
+    /// needed. Returns zero when done, anything else is an error, as is a write
+    /// returning anything but zero, which the renderer should pass on
+    pub render: extern "C" fn(
+        frame: *const PluginFrame,
+        write: extern "C" fn(sink: *mut c_void, data: *const u8, len: usize) -> i32,
+        sink: *mut c_void,
+    ) -> i32,
+}
+
+// SAFETY: the extension is read-only static data of the library like the declaration
+unsafe impl Sync for PluginRenderer {}
+
+/// A [`Frame`] across the ABI, borrowed for the call to render it
+#[repr(C)]
+pub struct PluginFrame {
+    /// `width * height` cell colors in row-major order
+    pub cells: *const u8,
+    pub width: usize,
+    pub height: usize,
+    pub colors: usize,
+    /// `ant_count` pairs of x and y relative to the top left cell
+    pub ants: *const isize,
+    pub ant_count: usize,
+    pub origin_x: isize,
+    pub origin_y: isize,
+}
+
+#[derive(Debug)]
+pub enum PluginError {
+    /// The library couldn't be opened or has no [`PLUGIN_ENTRY`]
+    #[cfg(feature = "plugins")]
+    Load(libloading::Error),
+    /// The entry of the library gave no declaration
+    Missing,
+    /// The plugin speaks another version of the ABI
+    Version(u32),
+    /// The plugin's name isn't UTF-8
+    Name,
+    /// The behavior of the plugin isn't a turmite
+    Behavior(TurmiteError),
+}
+
+impl Display for PluginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "plugins")]
+            PluginError::Load(e) => write!(f, "{e}"),
+            PluginError::Missing => write!(f, "plugin declares nothing"),
+            PluginError::Version(version) => write!(
+                f,
+                "plugin is for ABI version {version}, expected {PLUGIN_ABI_VERSION}"
+            ),
+            PluginError::Name => write!(f, "plugin name or extension is not UTF-8"),
+            PluginError::Behavior(e) => write!(f, "plugin behavior: {e}"),
+        }
+    }
+}
+
+impl Error for PluginError {}
+
+#[cfg(feature = "plugins")]
+impl From<libloading::Error> for PluginError {
+    fn from(e: libloading::Error) -> Self {
+        PluginError::Load(e)
+    }
+}
+
+impl From<TurmiteError> for PluginError {
+    fn from(e: TurmiteError) -> Self {
+        PluginError::Behavior(e)
+    }
+}
+
+/// Behavior and image format from outside the crate, loaded from a shared library
+/// with [`load`](Self::load) or linked in with [`from_decl`](Self::from_decl)
+pub struct Plugin {
+    name: String,
+    rule: Option<TurmiteRule>,
+    renderer: Option<(String, &'static PluginRenderer)>,
+    // Dropped last, the renderer points into it
+    #[cfg(feature = "plugins")]
+    _library: Option<Library>,
+}
+
+impl Plugin {
+    /// Open the shared library at `path` and read its [`PluginDecl`]
+    ///
+    /// # Safety
+    ///
+    /// Loading runs the library's initialisers and calls its entry, so the library
+    /// must be a plugin built for this ABI, nothing checks what the symbol really is
+    #[cfg(feature = "plugins")]
+    pub unsafe fn load(path: impl AsRef<Path>) -> Result<Self, PluginError> {
+        let library = Library::new(path.as_ref())?;
+        let decl = {
+            let entry =
+                library.get::<extern "C" fn() -> *const PluginDecl>(PLUGIN_ENTRY.as_bytes())?;
+            entry()
+        };
+        let decl = decl.as_ref().ok_or(PluginError::Missing)?;
+        let mut plugin = Self::from_decl(decl)?;
+        plugin._library = Some(library);
+        Ok(plugin)
+    }
+
+    /// Plugin from a declaration already in the program
+    ///
+    /// # Safety
+    ///
+    /// The pointers of `decl` must be null or valid for as long as the plugin is used
+    pub unsafe fn from_decl(decl: &PluginDecl) -> Result<Self, PluginError> {
+        if decl.abi_version != PLUGIN_ABI_VERSION {
+            return Err(PluginError::Version(decl.abi_version));
+        }
+        let name = text(decl.name)?;
+
+        let rule = match decl.behavior.as_ref() {
+            Some(behavior) => Some(tabulate(behavior)?.with_name(&name)),
+            None => None,
+        };
+        let renderer = match decl.renderer.as_ref() {
+            Some(renderer) => Some((text(renderer.extension)?, renderer)),
+            None => None,
+        };
+        Ok(Self {
+            name,
+            rule,
+            renderer,
+            #[cfg(feature = "plugins")]
+            _library: None,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The plugin's turmite named after it, asked for every transition up front
+    pub fn rule(&self) -> Option<&TurmiteRule> {
+        self.rule.as_ref()
+    }
+
+    /// Extension of the plugin's image format, e.g. `"ppm"`
+    pub fn extension(&self) -> Option<&str> {
+        self.renderer
+            .as_ref()
+            .map(|(extension, _)| extension.as_str())
+    }
+
+    /// The plugin's image format, for anything taking a [`Renderer`]
+    pub fn renderer(&self) -> Option<impl Renderer + '_> {
+        self.renderer.as_ref().map(|&(_, renderer)| Foreign {
+            renderer,
+            _plugin: self,
+        })
+    }
+}
+
+/// Nul-terminated UTF-8 from a plugin, empty for null
+unsafe fn text(s: *const c_char) -> Result<String, PluginError> {
+    match s.is_null() {
+        true => Ok(String::new()),
+        false => CStr::from_ptr(s)
+            .to_str()
+            .map(str::to_owned)
+            .map_err(|_| PluginError::Name),
+    }
+}
+
+fn tabulate(behavior: &PluginBehavior) -> Result<TurmiteRule, TurmiteError> {
+    let (states, colors) = (behavior.states as usize, behavior.colors as usize);
+    // Checked here too so a huge count isn't asked about cell by cell
+    if colors > crate::Rule::MAX_COLORS {
+        return Err(TurmiteError::Invalid(format!("{colors} colors")));
+    }
+    let mut transitions = Vec::with_capacity(states * colors);
+    for state in 0..states {
+        for color in 0..colors as u8 {
+            let mut decision = PluginDecision::default();
+            let transition = match (behavior.decide)(state as u32, color, &mut decision) {
+                true => decision.transition(),
+                false => None,
+            };
+            let transition = transition.ok_or_else(|| {
+                TurmiteError::Invalid(format!("no transition for state {state} on color {color}"))
+            })?;
+            transitions.push((state, color, transition));
+        }
+    }
+    TurmiteRule::new(states, colors, transitions)
+}
+
+/// Renderer of a plugin, holding on to the plugin so its library stays loaded
+struct Foreign<'p> {
+    renderer: &'p PluginRenderer,
+    _plugin: &'p Plugin,
+}
+
+/// What the plugin writes to, and the first error writing it
+struct Sink<'w> {
+    w: &'w mut dyn Write,
+    error: Option<io::Error>,
+}
+
+extern "C" fn write_sink(sink: *mut c_void, data: *const u8, len: usize) -> i32 {
+    // SAFETY: `sink` is the one `render` passed, alive until it returns
+    let sink = unsafe { &mut *(sink as *mut Sink) };
+    let data = match len {
+        0 => &[][..],
+        // SAFETY: the plugin promises `len` bytes at `data` for the length of the call
+        _ => unsafe { slice::from_raw_parts(data, len) },
+    };
+    match sink.w.write_all(data) {
+        Ok(()) => 0,
+        Err(e) => {
+            sink.error = Some(e);
+            -1
+        }
+    }
+}
+
+impl Renderer for Foreign<'_> {
+    fn render(&self, frame: &Frame, w: &mut dyn Write) -> io::Result<()> {
+        let ants = frame
+            .ants
+            .iter()
+            .flat_map(|ant| [ant.x, ant.y])
+            .collect::<Vec<_>>();
+        let view = PluginFrame {
+            cells: frame.cells.as_ptr(),
+            width: frame.width,
+            height: frame.height,
+            colors: frame.colors,
+            ants: ants.as_ptr(),
+            ant_count: frame.ants.len(),
+            origin_x: frame.origin.x,
+            origin_y: frame.origin.y,
+        };
+        let mut sink = Sink { w, error: None };
+        let status =
+            (self.renderer.render)(&view, write_sink, &mut sink as *mut Sink as *mut c_void);
+        match (sink.error, status) {
+            (Some(e), _) => Err(e),
+            (None, 0) => Ok(()),
+            (None, status) => Err(io::Error::other(format!(
+                "plugin renderer failed with {status}"
+            ))),
+        }
+    }
+}
+
+#[test]
+fn plugins_walk_and_render() {
+    use std::ptr;
+
+    use crate::{Map, MapPos, Pos};
+
+    // Langton's ant, and a format listing the cells as digits
+    extern "C" fn decide(_state: u32, color: u8, out: *mut PluginDecision) -> bool {
+        let action = [PluginDecision::LEFT, PluginDecision::RIGHT][color as usize];
+        unsafe {
+            *out = PluginDecision {
+                write: 1 - color,
+                action,
+                next: 0,
+            }
+        };
+        true
+    }
+    extern "C" fn render(
+        frame: *const PluginFrame,
+        write: extern "C" fn(*mut c_void, *const u8, usize) -> i32,
+        sink: *mut c_void,
+    ) -> i32 {
+        let frame = unsafe { &*frame };
+        let cells = unsafe { slice::from_raw_parts(frame.cells, frame.width * frame.height) };
+        let ants = unsafe { slice::from_raw_parts(frame.ants, frame.ant_count * 2) };
+        for row in cells.chunks(frame.width) {
+            let mut line = row.iter().map(|c| b'0' + c).collect::<Vec<_>>();
+            line.push(b'\n');
+            if write(sink, line.as_ptr(), line.len()) != 0 {
+                return 1;
+            }
+        }
+        let ants = format!("{ants:?}");
+        write(sink, ants.as_ptr(), ants.len())
+    }
+    static BEHAVIOR: PluginBehavior = PluginBehavior {
+        states: 1,
+        colors: 2,
+        decide,
+    };
+    static RENDERER: PluginRenderer = PluginRenderer {
+        extension: c"txt".as_ptr(),
+        render,
+    };
+    static DECL: PluginDecl = PluginDecl {
+        abi_version: PLUGIN_ABI_VERSION,
+        name: c"digits".as_ptr(),
+        behavior: &BEHAVIOR,
+        renderer: &RENDERER,
+    };
+
+    let plugin = unsafe { Plugin::from_decl(&DECL) }.unwrap();
+    assert_eq!((plugin.name(), plugin.extension()), ("digits", Some("txt")));
+    let langton = TurmiteRule::langton().with_name("digits");
+    assert_eq!(plugin.rule(), Some(&langton));
+
+    let mut map = Map::<3, 2>::new_white();
+    map.set(MapPos::validate_pos(Pos::new(1, 0)).unwrap(), false);
+    let frame = Frame::new(&map, 2, None).with_ants(&[Pos::new(2, 1)]);
+    let mut out = Vec::new();
+    plugin.renderer().unwrap().render(&frame, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "010\n000\n[2, 1]");
+
+    let old = PluginDecl {
+        abi_version: 0,
+        ..DECL
+    };
+    assert!(matches!(
+        unsafe { Plugin::from_decl(&old) },
+        Err(PluginError::Version(0))
+    ));
+    let silent = PluginDecl {
+        behavior: ptr::null(),
+        renderer: ptr::null(),
+        ..DECL
+    };
+    let silent = unsafe { Plugin::from_decl(&silent) }.unwrap();
+    assert!(silent.rule().is_none() && silent.renderer().is_none());
+}
@@ -0,0 +1,85 @@
+use std::{fmt::Display, marker::PhantomData, ops::Add};
+
+use crate::Map;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Pos {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Pos {
+    pub const fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+}
+
+/// A valid position on a [`Map`]
+#[derive(Clone, Copy)]
+pub struct MapPos<'m, const W: usize, const H: usize> {
+    x: usize,
+    y: usize,
+    _p: PhantomData<&'m Map<W, H>>,
+}
+
+impl<'m, const W: usize, const H: usize> MapPos<'m, W, H> {
+    pub const fn validate_pos(pos: Pos) -> Result<Self, Pos> {
+        if pos.x < 0 || pos.x >= W as _ || pos.y < 0 || pos.y >= H as _ {
+            Err(pos)
+        } else {
+            Ok(Self {
+                x: pos.x as _,
+                y: pos.y as _,
+                _p: PhantomData,
+            })
+        }
+    }
+
+    /// `pos` if it's also among the `width` x `height` cells at the top left, all of the
+    /// map a store sized at runtime uses, see [`CellStore::size`](crate::CellStore::size)
+    pub(crate) const fn validate_within(
+        pos: Pos,
+        (width, height): (usize, usize),
+    ) -> Result<Self, Pos> {
+        if pos.x >= width as _ || pos.y >= height as _ {
+            return Err(pos);
+        }
+        Self::validate_pos(pos)
+    }
+
+    pub const fn x(&self) -> usize {
+        self.x
+    }
+
+    pub const fn y(&self) -> usize {
+        self.y
+    }
+
+    /// Index of the cell in row-major order
+    pub(crate) const fn index(&self) -> usize {
+        self.y * W + self.x
+    }
+}
+
+impl<'m, const W: usize, const H: usize> Display for MapPos<'m, W, H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl<'m, const W: usize, const H: usize> From<MapPos<'m, W, H>> for Pos {
+    fn from(pos: MapPos<'m, W, H>) -> Self {
+        Pos::new(pos.x as _, pos.y as _)
+    }
+}
+
+impl<'m, const W: usize, const H: usize> Add<Pos> for MapPos<'m, W, H> {
+    type Output = Pos;
+
+    fn add(self, rhs: Pos) -> Self::Output {
+        Self::Output {
+            x: self.x as isize + rhs.x,
+            y: self.y as isize + rhs.y,
+        }
+    }
+}
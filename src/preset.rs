@@ -0,0 +1,140 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{Palette, Rule};
+
+/// A famous rule with a board size, a run length and colors that show it off
+///
+/// Rules are written the way the literature has them, and as rows grow downwards here
+/// they draw the mirror images of the pictures found there. [`langton`](Self::ALL) is
+/// [`Rule::default`] instead, the walk this crate always did
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    rule: &'static str,
+    /// Width and height of the board
+    pub size: usize,
+    /// Steps to run, `None` to walk until the ant leaves the board
+    pub steps: Option<usize>,
+    palette: Option<&'static str>,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 6] = [
+        Preset {
+            name: "langton",
+            description: "Langton's ant: ten thousand steps of chaos, then a highway",
+            rule: "LR",
+            size: 1024,
+            steps: None,
+            palette: None,
+        },
+        Preset {
+            name: "rlr-chaos",
+            description: "Grows chaotically in every direction and never settles",
+            rule: "RLR",
+            size: 512,
+            steps: Some(2_000_000),
+            palette: Some("#ffffff,#0072b2,#e69f00"),
+        },
+        Preset {
+            name: "llrr-cardioid",
+            description: "A symmetric cardioid that keeps growing",
+            rule: "LLRR",
+            size: 1024,
+            steps: Some(3_000_000),
+            palette: Some("colorblind"),
+        },
+        Preset {
+            name: "lrrrrrllr-square",
+            description: "Fills a square around itself",
+            rule: "LRRRRRLLR",
+            size: 512,
+            steps: Some(2_000_000),
+            palette: Some("colorblind"),
+        },
+        Preset {
+            name: "llrrrlrlrllr-highway",
+            description: "A long chaotic start, then a convoluted highway",
+            rule: "LLRRRLRLRLLR",
+            size: 1024,
+            steps: Some(1_000_000),
+            palette: Some(TWELVE_COLORS),
+        },
+        Preset {
+            name: "rll-filled-triangle",
+            description: "RRLLLRLLLRRR, a filled triangle growing along a diagonal",
+            rule: "RRLLLRLLLRRR",
+            size: 1024,
+            steps: Some(5_000_000),
+            palette: Some(TWELVE_COLORS),
+        },
+    ];
+
+    /// The preset called `name`, whatever the case
+    pub fn find(name: &str) -> Option<&'static Preset> {
+        Self::ALL
+            .iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule.parse().expect("presets have valid rules")
+    }
+
+    /// Colors of the cells, `None` for the usual grays
+    pub fn palette(&self) -> Option<Palette> {
+        self.palette
+            .map(|palette| palette.parse().expect("presets have valid palettes"))
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::find(s).copied().ok_or_else(|| {
+            let names = Self::ALL.map(|preset| preset.name).join(", ");
+            format!("unknown preset {s:?}, expected one of {names}")
+        })
+    }
+}
+
+impl Display for Preset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+// The colorblind palette with three more that stay apart from it
+const TWELVE_COLORS: &str = "#ffffff,#000000,#e69f00,#56b4e9,#009e73,#f0e442,#0072b2,#d55e00,\
+                             #cc79a7,#999999,#882255,#44aa99";
+
+#[test]
+fn presets_are_valid() {
+    use crate::{Boundary, CellStore, ColorMap, Direction, Pos, Simulation};
+
+    for (i, preset) in Preset::ALL.iter().enumerate() {
+        let rule = preset.rule();
+        assert_eq!(rule.to_string(), preset.rule, "{}", preset.name);
+        assert!(preset.size.is_power_of_two() && preset.size >= 256);
+        if let Some(palette) = preset.palette() {
+            assert!(palette.colors.len() >= rule.colors(), "{}", preset.name);
+        }
+        assert_eq!(preset.name.parse(), Ok(*preset));
+        assert!(Preset::ALL[..i]
+            .iter()
+            .all(|other| other.name != preset.name));
+
+        // Every rule walks and paints only the colors it has
+        let mut sim = Simulation::new(ColorMap::<64, 64>::new_white(), rule.clone())
+            .with_boundary(Boundary::Wrap);
+        sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+        sim.run_at_most(5000);
+        let colors = sim.map().to_colors();
+        assert!(colors.iter().all(|&c| (c as usize) < rule.colors()));
+        assert!(colors.iter().any(|&c| c != 0));
+    }
+    assert_eq!(Preset::find(" Langton").unwrap().rule(), Rule::default());
+    assert!("langtons".parse::<Preset>().is_err());
+}
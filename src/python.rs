@@ -0,0 +1,227 @@
+//! Python module for driving runs from notebooks, built with
+//! `maturin develop --features python,pyo3/extension-module`
+//!
+//! ```python
+//! import antventure, numpy
+//!
+//! sim = antventure.Simulation(256, "LLRR")
+//! while sim.step(10_000):
+//!     pass
+//! cells = numpy.asarray(sim.map)  # 256x256 uint8, no copy
+//! ```
+
+use std::{
+    ffi::{c_int, c_void},
+    ptr,
+};
+
+use pyo3::{
+    exceptions::{PyBufferError, PyValueError},
+    ffi,
+    prelude::*,
+};
+
+use crate::{
+    board::{new_board, Board},
+    Direction, Pos,
+};
+
+/// A turn for each cell color, e.g. `Rule("LLRR")`
+#[pyclass(name = "Rule", module = "antventure", frozen)]
+struct PyRule(crate::Rule);
+
+#[pymethods]
+impl PyRule {
+    #[new]
+    fn new(rule: &str) -> PyResult<Self> {
+        rule.parse()
+            .map(Self)
+            .map_err(|e: crate::ParseRuleError| PyValueError::new_err(e.to_string()))
+    }
+
+    #[getter]
+    fn colors(&self) -> usize {
+        self.0.colors()
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Rule(\"{}\")", self.0)
+    }
+}
+
+/// Square board of 64, 128, 256, 512, 1024, 2048 or 4096 cells a side with an ant in
+/// the middle facing north
+#[pyclass(name = "Simulation", module = "antventure", unsendable)]
+struct PySimulation {
+    board: Box<dyn Board>,
+    size: usize,
+}
+
+#[pymethods]
+impl PySimulation {
+    /// `rule` is a `Rule` or a string like `"LR"`
+    #[new]
+    fn new(size: usize, rule: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let rule = match rule.downcast::<PyRule>() {
+            Ok(rule) => rule.get().0.clone(),
+            Err(_) => PyRule::new(rule.extract()?)?.0,
+        };
+        let mut board = new_board(size, rule).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "unsupported size {size}, expected 64, 128, 256, 512, 1024, 2048 or 4096"
+            ))
+        })?;
+        let middle = (size / 2) as isize;
+        board
+            .spawn(Pos::new(middle, middle), Direction::North)
+            .expect("the middle is on the map");
+        Ok(Self { board, size })
+    }
+
+    /// Add an ant facing `direction`: north, east, south, west or their first letter
+    #[pyo3(signature = (x, y, direction = "north"))]
+    fn spawn(&mut self, x: isize, y: isize, direction: &str) -> PyResult<()> {
+        let dir = direction
+            .parse::<Direction>()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        self.board
+            .spawn(Pos::new(x, y), dir)
+            .map(|_| ())
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Walk every ant `steps` steps, returns whether any ant is still on the map
+    #[pyo3(signature = (steps = 1))]
+    fn step(&mut self, steps: u64) -> bool {
+        self.board.run_at_most(steps)
+    }
+
+    #[getter]
+    fn steps(&self) -> u64 {
+        self.board.steps()
+    }
+
+    #[getter]
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    #[getter]
+    fn colors(&self) -> usize {
+        self.board.colors()
+    }
+
+    /// Positions `(x, y)` of the ants still on the map
+    #[getter]
+    fn ants(&self) -> Vec<(isize, isize)> {
+        let ants = self.board.ants();
+        ants.iter().map(|ant| (ant.x, ant.y)).collect()
+    }
+
+    /// The cells, a live view that follows the walk
+    #[getter]
+    fn map(slf: Py<Self>, py: Python<'_>) -> PyResult<PyMap> {
+        let size = slf.borrow(py).size as isize;
+        Ok(PyMap {
+            sim: slf,
+            shape: [size, size],
+            strides: [size, 1],
+        })
+    }
+}
+
+/// Cell colors of a simulation, `size` rows of `size` bytes from white `0` up, shared
+/// through the buffer protocol without a copy: `numpy.asarray(sim.map)` or
+/// `memoryview(sim.map)`. Read-only, and it changes as the ants walk
+#[pyclass(name = "Map", module = "antventure", unsendable)]
+struct PyMap {
+    sim: Py<PySimulation>,
+    shape: [isize; 2],
+    strides: [isize; 2],
+}
+
+#[pymethods]
+impl PyMap {
+    /// Black cells, or cells of any color but white
+    fn count_colored(&self, py: Python<'_>) -> usize {
+        let sim = self.sim.borrow(py);
+        sim.board
+            .cells()
+            .iter()
+            .filter(|&&color| color != 0)
+            .count()
+    }
+
+    fn __len__(&self) -> usize {
+        self.shape[0] as usize
+    }
+
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("no buffer to fill"));
+        }
+        if flags & ffi::PyBUF_WRITABLE != 0 {
+            return Err(PyBufferError::new_err("the map is read-only"));
+        }
+        let map = slf.borrow();
+        // The cells never move, the map keeps the simulation and the view the map
+        let cells = map.sim.borrow(slf.py()).board.cells().as_ptr();
+        let view = &mut *view;
+        view.buf = cells as *mut c_void;
+        view.len = map.shape[0] * map.shape[1];
+        view.readonly = 1;
+        view.itemsize = 1;
+        view.format = match flags & ffi::PyBUF_FORMAT != 0 {
+            true => c"B".as_ptr() as *mut _,
+            false => ptr::null_mut(),
+        };
+        view.ndim = 2;
+        view.shape = map.shape.as_ptr() as *mut _;
+        view.strides = map.strides.as_ptr() as *mut _;
+        view.suboffsets = ptr::null_mut();
+        view.internal = ptr::null_mut();
+        drop(map);
+        view.obj = slf.into_any().into_ptr();
+        Ok(())
+    }
+
+    unsafe fn __releasebuffer__(&self, _view: *mut ffi::Py_buffer) {}
+}
+
+#[pymodule]
+fn antventure(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyRule>()?;
+    m.add_class::<PySimulation>()?;
+    m.add_class::<PyMap>()?;
+    Ok(())
+}
+
+#[test]
+fn maps_share_their_cells() {
+    use pyo3::types::IntoPyDict;
+
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let module = PyModule::new(py, "antventure").unwrap();
+        antventure(&module).unwrap();
+        let locals = [("antventure", module)].into_py_dict(py).unwrap();
+        let script = c"
+sim = antventure.Simulation(64, antventure.Rule('LR'))
+cells = memoryview(sim.map)
+assert (cells.shape, cells.format, cells.readonly) == ((64, 64), 'B', True)
+sim.step(100)
+# The view follows the walk and outlives the simulation
+del sim
+assert cells.tobytes().count(1) == 20 and cells[32, 32] == 1
+";
+        py.run(script, None, Some(&locals)).unwrap();
+    });
+}
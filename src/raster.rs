@@ -0,0 +1,201 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use crate::{Frame, Palette, Renderer};
+
+// Longest line plain Netpbm files may have
+const PLAIN_LINE: usize = 70;
+// Characters of the ASCII dump from white to black
+const RAMP: &[u8] = b" .:-=+*#%";
+// Character of a cell with an ant in the ASCII dump
+const ANT: u8 = b'@';
+
+/// Netpbm bitmaps and graymaps and plain text, formats next to nothing is needed to
+/// read, each a [`Renderer`]
+///
+/// A PBM only tells white from black, cells of any color but white are black in it.
+/// A PGM fades colors from white to black like grayscale PNGs. The plain variants
+/// spell pixels out in ASCII digits. ASCII art draws a character a cell, from a space
+/// for white through `.:-=+*#` to `%` for the darkest color, and `@` where an ant
+/// is, so it's for boards small enough to read in a terminal; Netpbm has no place
+/// for ant markers
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RasterFormat {
+    Pbm { plain: bool },
+    Pgm { plain: bool },
+    Ascii,
+}
+
+impl RasterFormat {
+    /// Guess the format from the file extension, binary Netpbm for `.pbm` and `.pgm`
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "pbm" => Some(Self::Pbm { plain: false }),
+            "pgm" => Some(Self::Pgm { plain: false }),
+            "txt" | "asc" => Some(Self::Ascii),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for RasterFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pbm" => Ok(Self::Pbm { plain: false }),
+            "plain-pbm" => Ok(Self::Pbm { plain: true }),
+            "pgm" => Ok(Self::Pgm { plain: false }),
+            "plain-pgm" => Ok(Self::Pgm { plain: true }),
+            "ascii" | "txt" => Ok(Self::Ascii),
+            _ => Err(format!(
+                "unknown raster format {s:?}, expected pbm, plain-pbm, pgm, plain-pgm or ascii"
+            )),
+        }
+    }
+}
+
+impl Renderer for RasterFormat {
+    fn render(&self, frame: &Frame, w: &mut dyn Write) -> io::Result<()> {
+        let (width, height) = (frame.width, frame.height);
+        let rows = frame.cells.chunks(width.max(1));
+        match *self {
+            Self::Pbm { plain: false } => {
+                write!(w, "P4\n{width} {height}\n")?;
+                let mut packed = vec![0; width.div_ceil(8)];
+                for row in rows {
+                    packed.fill(0);
+                    for (x, _) in row.iter().enumerate().filter(|(_, &cell)| cell != 0) {
+                        packed[x / 8] |= 0x80 >> (x % 8);
+                    }
+                    w.write_all(&packed)?;
+                }
+                Ok(())
+            }
+            Self::Pbm { plain: true } => {
+                write!(w, "P1\n{width} {height}\n")?;
+                // Bits need nothing between them, every row starts a line
+                for row in rows {
+                    for line in row.chunks(PLAIN_LINE) {
+                        let bits = line
+                            .iter()
+                            .map(|&cell| if cell == 0 { b'0' } else { b'1' })
+                            .collect::<Vec<_>>();
+                        w.write_all(&bits)?;
+                        w.write_all(b"\n")?;
+                    }
+                }
+                Ok(())
+            }
+            Self::Pgm { plain } => {
+                let levels = Palette::gray(frame.colors)
+                    .colors
+                    .into_iter()
+                    .map(|[gray, _, _]| gray)
+                    .collect::<Vec<_>>();
+                let level = |cell: u8| levels.get(cell as usize).copied().unwrap_or(0);
+                if !plain {
+                    write!(w, "P5\n{width} {height}\n255\n")?;
+                    let bytes = frame.cells.iter().map(|&cell| level(cell));
+                    return w.write_all(&bytes.collect::<Vec<_>>());
+                }
+                write!(w, "P2\n{width} {height}\n255\n")?;
+                for row in rows {
+                    let mut line = String::new();
+                    for &cell in row {
+                        let value = level(cell).to_string();
+                        if !line.is_empty() && line.len() + 1 + value.len() > PLAIN_LINE {
+                            writeln!(w, "{line}")?;
+                            line.clear();
+                        }
+                        if !line.is_empty() {
+                            line.push(' ');
+                        }
+                        line.push_str(&value);
+                    }
+                    writeln!(w, "{line}")?;
+                }
+                Ok(())
+            }
+            Self::Ascii => {
+                let darkest = frame.colors.saturating_sub(1).max(1);
+                let mut lines = rows
+                    .map(|row| {
+                        row.iter()
+                            .map(|&cell| {
+                                RAMP[(cell as usize).min(darkest) * (RAMP.len() - 1) / darkest]
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                for ant in &frame.ants {
+                    if let Some(cell) = usize::try_from(ant.y)
+                        .ok()
+                        .and_then(|y| lines.get_mut(y))
+                        .zip(usize::try_from(ant.x).ok())
+                        .and_then(|(line, x)| line.get_mut(x))
+                    {
+                        *cell = ANT;
+                    }
+                }
+                for line in lines {
+                    w.write_all(&line)?;
+                    w.write_all(b"\n")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[test]
+fn rasters_write_netpbm_and_text() {
+    use crate::Pos;
+
+    let frame = Frame {
+        cells: vec![0, 1, 2, 0, 0, 2, 0, 1, 0, 0],
+        width: 10,
+        height: 1,
+        colors: 3,
+        ants: vec![Pos::new(3, 0), Pos::new(20, 0)],
+        origin: Pos::new(0, 0),
+    };
+    let render = |format: RasterFormat| {
+        let mut out = Vec::new();
+        format.render(&frame, &mut out).unwrap();
+        out
+    };
+
+    let binary = render("pbm".parse().unwrap());
+    assert_eq!(binary, b"P4\n10 1\n\x65\x00");
+    let plain = render("plain-pbm".parse().unwrap());
+    assert_eq!(plain, b"P1\n10 1\n0110010100\n");
+    #[cfg(feature = "io")]
+    {
+        use crate::{analyze_pbm, ControlHandle};
+
+        let control = ControlHandle::new();
+        let stats = analyze_pbm(binary.as_slice(), &control).unwrap();
+        assert_eq!(stats, analyze_pbm(plain.as_slice(), &control).unwrap());
+        assert_eq!(stats.black, 4);
+    }
+
+    assert_eq!(
+        render(RasterFormat::Pgm { plain: false }),
+        b"P5\n10 1\n255\n\xff\x80\x00\xff\xff\x00\xff\x80\xff\xff"
+    );
+    assert_eq!(
+        render(RasterFormat::Pgm { plain: true }),
+        b"P2\n10 1\n255\n255 128 0 255 255 0 255 128 255 255\n"
+    );
+    assert_eq!(render(RasterFormat::Ascii), b" =%@ % =  \n");
+    assert_eq!(
+        RasterFormat::from_path("walk.TXT"),
+        Some(RasterFormat::Ascii)
+    );
+    assert!("ppm".parse::<RasterFormat>().is_err());
+}
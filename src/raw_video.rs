@@ -0,0 +1,166 @@
+use std::{
+    io::{self, Write},
+    str::FromStr,
+};
+
+use crate::{CellStore, Frame, MapPos, OutputSink, Palette, Pos};
+
+/// Pixel layout of [`RawVideo`] frames
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum RawFormat {
+    /// A byte per cell, colors fade from white to black
+    #[default]
+    Gray,
+    /// Three bytes per cell in the palette colors
+    Rgb,
+}
+
+impl RawFormat {
+    /// What ffmpeg calls the layout, for `-pix_fmt`
+    pub fn pix_fmt(self) -> &'static str {
+        match self {
+            RawFormat::Gray => "gray",
+            RawFormat::Rgb => "rgb24",
+        }
+    }
+
+    pub fn bytes_per_cell(self) -> usize {
+        match self {
+            RawFormat::Gray => 1,
+            RawFormat::Rgb => 3,
+        }
+    }
+}
+
+impl FromStr for RawFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "gray" | "grey" => Ok(RawFormat::Gray),
+            "rgb" | "rgb24" => Ok(RawFormat::Rgb),
+            _ => Err(format!("unknown raw format {s:?}, expected gray or rgb")),
+        }
+    }
+}
+
+/// Headerless frames back to back, the input `ffmpeg -f rawvideo` takes, so any video
+/// format ffmpeg knows is a pipe away:
+///
+/// ```bash
+/// serious --raw-frames gray | ffmpeg -f rawvideo -pix_fmt gray -s 1024x1024 -i - walk.mp4
+/// ```
+///
+/// Every frame is built in the same buffer, nothing is allocated after the first one
+pub struct RawVideo<Wr: Write> {
+    out: Wr,
+    format: RawFormat,
+    /// Pixel bytes of every cell color
+    colors: Vec<Vec<u8>>,
+    buf: Vec<u8>,
+    frames: usize,
+}
+
+impl<Wr: Write> RawVideo<Wr> {
+    /// Frames of maps with `colors` colors, in `palette` colors for [`RawFormat::Rgb`]
+    /// or in gray without one
+    ///
+    /// # Panics
+    ///
+    /// If `palette` has fewer than `colors` colors
+    pub fn new(out: Wr, format: RawFormat, colors: usize, palette: Option<&Palette>) -> Self {
+        let gray = Palette::gray(colors);
+        let palette = palette.unwrap_or(&gray);
+        let colors = (0..colors)
+            .map(|color| match format {
+                RawFormat::Gray => vec![gray.colors[color][0]],
+                RawFormat::Rgb => palette.colors[color].to_vec(),
+            })
+            .collect();
+        Self {
+            out,
+            format,
+            colors,
+            buf: Vec::new(),
+            frames: 0,
+        }
+    }
+
+    /// Write the map as the next frame
+    pub fn push<const W: usize, const H: usize>(
+        &mut self,
+        map: &impl CellStore<W, H>,
+    ) -> io::Result<()> {
+        let (width, height) = map.size();
+        let cells = (0..height).flat_map(|y| {
+            (0..width).map(move |x| {
+                let pos = MapPos::validate_pos(Pos::new(x as _, y as _))
+                    .expect("x and y are within the map");
+                map.color(pos)
+            })
+        });
+        self.write_cells(width * height, cells)
+    }
+
+    fn write_cells(&mut self, len: usize, cells: impl Iterator<Item = u8>) -> io::Result<()> {
+        self.buf.clear();
+        self.buf.reserve(len * self.format.bytes_per_cell());
+        for color in cells {
+            self.buf.extend_from_slice(&self.colors[color as usize]);
+        }
+        self.out.write_all(&self.buf)?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Frames written so far
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> Wr {
+        self.out
+    }
+}
+
+impl<Wr: Write> OutputSink for RawVideo<Wr> {
+    /// Frames go out as they are, cropped or not, so keep them all the same size
+    fn accept_frame(&mut self, frame: &Frame, _step: u64) -> io::Result<()> {
+        self.write_cells(frame.cells.len(), frame.cells.iter().copied())
+    }
+
+    fn finalize(&mut self) -> io::Result<()> {
+        self.flush()
+    }
+
+    fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+#[test]
+fn frames_back_to_back() {
+    use crate::ColorMap;
+
+    let mut map = ColorMap::<3, 2>::new_white();
+    let mut video = RawVideo::new(Vec::new(), RawFormat::Gray, 3, None);
+    video.push(&map).unwrap();
+    map.set_color(MapPos::validate_pos(Pos::new(2, 1)).unwrap(), 2);
+    video.push(&map).unwrap();
+    assert_eq!(video.frames(), 2);
+    let bytes = video.into_inner();
+    assert_eq!(bytes.len(), 2 * 6);
+    assert_eq!(bytes[6..], [255, 255, 255, 255, 255, 0]);
+
+    let palette = "#102030,#405060,#708090".parse::<Palette>().unwrap();
+    let mut video = RawVideo::new(Vec::new(), RawFormat::Rgb, 3, Some(&palette));
+    video.push(&map).unwrap();
+    let bytes = video.into_inner();
+    assert_eq!(bytes[..3], [0x10, 0x20, 0x30]);
+    assert_eq!(bytes[15..], [0x70, 0x80, 0x90]);
+    assert_eq!("RGB24".parse(), Ok(RawFormat::Rgb));
+}
@@ -0,0 +1,227 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use crate::{render::cropped_colors, CellStore};
+
+/// 3D printable relief tile, every cell is a column standing on a common base
+#[derive(Clone, Debug)]
+pub struct Relief {
+    /// Width and depth of a cell in millimeters
+    pub cell_mm: f64,
+    /// Thickness of the tile under the lowest cells
+    pub base_mm: f64,
+    /// How much higher the highest cells are than the lowest ones
+    pub height_mm: f64,
+    /// Raise white cells instead of black ones
+    pub invert: bool,
+}
+
+impl Default for Relief {
+    fn default() -> Self {
+        Self {
+            cell_mm: 1.0,
+            base_mm: 1.0,
+            height_mm: 2.0,
+            invert: false,
+        }
+    }
+}
+
+type Vertex = [f32; 3];
+
+impl Relief {
+    /// Write `map` as a binary STL, darker colors stand higher, returns the triangle count
+    pub fn save<const W: usize, const H: usize>(
+        &self,
+        map: &impl CellStore<W, H>,
+        colors: usize,
+        file: impl AsRef<Path>,
+    ) -> io::Result<u32> {
+        let darkest = colors.saturating_sub(1).max(1) as f64;
+        let (cells, width, _) = cropped_colors(map, None);
+        let values = cells
+            .into_iter()
+            .map(|c| c as f64 / darkest)
+            .collect::<Vec<_>>();
+        self.save_values(&values, width, file)
+    }
+
+    /// Write a `width` wide row-major grid of `0.0..=1.0` values as a binary STL heightmap,
+    /// returns the triangle count
+    pub fn save_values(
+        &self,
+        values: &[f64],
+        width: usize,
+        file: impl AsRef<Path>,
+    ) -> io::Result<u32> {
+        let height = values.len() / width;
+        let z = |x: isize, y: isize| -> f64 {
+            if x < 0 || y < 0 || x >= width as isize || y >= height as isize {
+                return 0.0;
+            }
+            let v = values[y as usize * width + x as usize].clamp(0.0, 1.0);
+            let v = if self.invert { 1.0 - v } else { v };
+            self.base_mm + v * self.height_mm
+        };
+        // Board rows grow downwards, the tile's y axis upwards
+        let mm_x = |x: usize| (x as f64 * self.cell_mm) as f32;
+        let mm_y = |y: usize| ((height - y) as f64 * self.cell_mm) as f32;
+
+        // Heights of the cells around a corner, walls are split at each of them
+        // so neighbouring walls share their vertices
+        let levels = |x: usize, y: usize| {
+            let (x, y) = (x as isize, y as isize);
+            [z(x - 1, y - 1), z(x, y - 1), z(x - 1, y), z(x, y)].map(|z| z as f32)
+        };
+
+        let mut w = BufWriter::new(File::create(file)?);
+        w.write_all(&[0; 80])?;
+        // Triangle count goes here once it's known
+        w.write_all(&0u32.to_le_bytes())?;
+
+        let mut triangles = 0u32;
+        let mut triangle = |w: &mut BufWriter<File>, t: [Vertex; 3]| {
+            triangles += 1;
+            write_triangle(w, t)
+        };
+
+        for y in 0..height {
+            let (y0, y1) = (mm_y(y + 1), mm_y(y));
+            for x in 0..width {
+                let (x0, x1) = (mm_x(x), mm_x(x + 1));
+                let top = z(x as _, y as _) as f32;
+                for t in [
+                    [[x0, y0, top], [x1, y0, top], [x1, y1, top]],
+                    [[x0, y0, top], [x1, y1, top], [x0, y1, top]],
+                    [[x0, y0, 0.0], [x0, y1, 0.0], [x1, y1, 0.0]],
+                    [[x0, y0, 0.0], [x1, y1, 0.0], [x1, y0, 0.0]],
+                ] {
+                    triangle(&mut w, t)?;
+                }
+            }
+        }
+
+        // Walls between columns of different heights, the outside counts as height 0.
+        // A wall runs from corner `a` to corner `b` facing right of that direction
+        let mut walls = Vec::new();
+        for y in 0..height {
+            for x in 0..=width {
+                let (left, right) = (z(x as isize - 1, y as _), z(x as _, y as _));
+                let (top, bottom) = ((x, y), (x, y + 1));
+                if left > right {
+                    walls.push((bottom, top, right, left));
+                } else if right > left {
+                    walls.push((top, bottom, left, right));
+                }
+            }
+        }
+        for y in 0..=height {
+            for x in 0..width {
+                let (above, below) = (z(x as _, y as isize - 1), z(x as _, y as _));
+                let (left, right) = ((x, y), (x + 1, y));
+                if below > above {
+                    walls.push((right, left, above, below));
+                } else if above > below {
+                    walls.push((left, right, below, above));
+                }
+            }
+        }
+
+        for (a, b, lo, hi) in walls {
+            let (lo, hi) = (lo as f32, hi as f32);
+            let column = |(x, y): (usize, usize)| {
+                let mut zs = levels(x, y)
+                    .into_iter()
+                    .filter(|&z| z > lo && z < hi)
+                    .chain([lo, hi])
+                    .collect::<Vec<_>>();
+                zs.sort_by(f32::total_cmp);
+                zs.dedup();
+                zs.into_iter()
+                    .map(|z| [mm_x(x), mm_y(y), z])
+                    .collect::<Vec<_>>()
+            };
+            let (a, b) = (column(a), column(b));
+
+            // Zip both sides together from the bottom up
+            let (mut i, mut j) = (0, 0);
+            while i + 1 < a.len() || j + 1 < b.len() {
+                let up_b = i + 1 == a.len() || (j + 1 < b.len() && b[j + 1][2] <= a[i + 1][2]);
+                if up_b {
+                    triangle(&mut w, [a[i], b[j], b[j + 1]])?;
+                    j += 1;
+                } else {
+                    triangle(&mut w, [a[i], b[j], a[i + 1]])?;
+                    i += 1;
+                }
+            }
+        }
+
+        w.seek(SeekFrom::Start(80))?;
+        w.write_all(&triangles.to_le_bytes())?;
+        w.flush()?;
+        Ok(triangles)
+    }
+}
+
+fn write_triangle(w: &mut impl Write, [a, b, c]: [Vertex; 3]) -> io::Result<()> {
+    let (u, v) = (sub(b, a), sub(c, a));
+    let normal = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let len = normal
+        .iter()
+        .map(|n| n * n)
+        .sum::<f32>()
+        .sqrt()
+        .max(f32::EPSILON);
+
+    for vertex in [normal.map(|n| n / len), a, b, c] {
+        for coord in vertex {
+            w.write_all(&coord.to_le_bytes())?;
+        }
+    }
+    w.write_all(&[0; 2])
+}
+
+fn sub(a: Vertex, b: Vertex) -> Vertex {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+#[test]
+fn relief_is_closed() {
+    use std::collections::HashMap;
+
+    // Three levels meeting at corners need their walls split to stay closed
+    let values = [0.0, 1.0, 0.5, 0.5, 0.0, 1.0];
+
+    let file = std::env::temp_dir().join("antventure_relief_is_closed.stl");
+    let triangles = Relief::default().save_values(&values, 3, &file).unwrap();
+    let bytes = std::fs::read(&file).unwrap();
+    std::fs::remove_file(&file).ok();
+
+    assert_eq!(bytes.len(), 84 + 50 * triangles as usize);
+    assert_eq!(
+        u32::from_le_bytes(bytes[80..84].try_into().unwrap()),
+        triangles
+    );
+
+    // Every edge of a closed surface is walked once in each direction
+    let mut edges = HashMap::<_, i32>::new();
+    for t in bytes[84..].chunks(50) {
+        let v = (0..3)
+            .map(|i| t[12 + i * 12..24 + i * 12].to_vec())
+            .collect::<Vec<_>>();
+        for i in 0..3 {
+            let (a, b) = (v[i].clone(), v[(i + 1) % 3].clone());
+            *edges.entry((a.clone(), b.clone())).or_default() += 1;
+            *edges.entry((b, a)).or_default() -= 1;
+        }
+    }
+    assert!(edges.values().all(|&n| n == 0));
+}
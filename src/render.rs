@@ -0,0 +1,933 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+#[cfg(feature = "io")]
+use std::thread;
+
+#[cfg(feature = "io")]
+use png::{BitDepth, ColorType, Encoder, EncodingError};
+
+#[cfg(feature = "io")]
+use crate::{
+    color::tag_srgb, embed_params, params_room, stego::message_bits, ChunkCoord, Map, StegoError,
+};
+use crate::{
+    Cancelled, CellStore, ControlHandle, ImageOrientation, Palette, Pos, SparseMap, CHUNK_SIZE,
+};
+
+#[cfg(feature = "io")]
+/// Save the map as a 1-bit grayscale PNG
+pub fn save_map_to_file<const W: usize, const H: usize>(
+    map: &Map<W, H>,
+    file: impl AsRef<Path>,
+) -> Result<(), EncodingError> {
+    let file = File::create(file)?;
+    let w = BufWriter::new(file);
+
+    let mut encoder = Encoder::new(w, W as _, H as _);
+    encoder.set_color(ColorType::Grayscale);
+    encoder.set_depth(BitDepth::One);
+    let mut writer = encoder.write_header()?;
+
+    let bytes = map.bytes().collect::<Vec<_>>();
+
+    writer.write_image_data(&bytes[0..(W * H / u8::BITS as usize)])
+}
+
+/// Rectangle of a map to render or to look into with [`Map::iter_region`](crate::Map::iter_region),
+/// between inclusive corners on the map
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Crop {
+    pub min: Pos,
+    pub max: Pos,
+}
+
+impl Crop {
+    /// `bounds` grown by `padding` cells on every side, as far as a map of `size` goes
+    pub fn around((min, max): (Pos, Pos), padding: usize, (width, height): (usize, usize)) -> Self {
+        let padding = padding as isize;
+        Self {
+            min: Pos::new((min.x - padding).max(0), (min.y - padding).max(0)),
+            max: Pos::new(
+                (max.x + padding).min(width as isize - 1),
+                (max.y + padding).min(height as isize - 1),
+            ),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max.x - self.min.x + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max.y - self.min.y + 1) as usize
+    }
+}
+
+/// Cell colors of the `crop` part of a map, all of it without one, in row-major order
+/// along with the width and height
+pub(crate) fn cropped_colors<const W: usize, const H: usize>(
+    map: &impl CellStore<W, H>,
+    crop: Option<Crop>,
+) -> (Vec<u8>, usize, usize) {
+    let cells = map.to_colors();
+    // Stores sized at runtime leave the rest of the board white
+    let crop = match (crop, map.size()) {
+        (Some(crop), _) => crop,
+        (None, size) if size == (W, H) => return (cells, W, H),
+        (None, (width, height)) => Crop {
+            min: Pos::new(0, 0),
+            max: Pos::new(width as isize - 1, height as isize - 1),
+        },
+    };
+    let (x, width) = (crop.min.x as usize, crop.width());
+    let cropped = cells
+        .chunks(W)
+        .skip(crop.min.y as usize)
+        .take(crop.height())
+        .flat_map(|row| &row[x..x + width])
+        .copied()
+        .collect();
+    (cropped, width, crop.height())
+}
+
+/// Cell colors of a map, or of the cropped part of it, and the ants on it, ready
+/// for a [`Renderer`]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Frame {
+    /// Cell colors in row-major order
+    pub cells: Vec<u8>,
+    pub width: usize,
+    pub height: usize,
+    /// How many colors cells can have, for fading them from white to black
+    pub colors: usize,
+    /// Cells with an ant relative to the top left one, outside the frame if the ant is
+    pub ants: Vec<Pos>,
+    /// Map position of the top left cell
+    pub origin: Pos,
+}
+
+impl Frame {
+    /// The `crop` part of `map`, all of it without one
+    pub fn new<const W: usize, const H: usize>(
+        map: &impl CellStore<W, H>,
+        colors: usize,
+        crop: Option<Crop>,
+    ) -> Self {
+        let (cells, width, height) = cropped_colors(map, crop);
+        Self {
+            cells,
+            width,
+            height,
+            colors,
+            ants: Vec::new(),
+            origin: crop.map_or(Pos::new(0, 0), |crop| crop.min),
+        }
+    }
+
+    /// The touched chunks of an endless `map`, all white ones between them included,
+    /// or the first chunk if nothing was touched
+    pub fn from_sparse(map: &SparseMap) -> Self {
+        let last = CHUNK_SIZE as isize - 1;
+        let (min, max) = map
+            .bounding_box()
+            .unwrap_or((Pos::new(0, 0), Pos::new(last, last)));
+        let (width, height) = ((max.x - min.x + 1) as usize, (max.y - min.y + 1) as usize);
+        let cells = (min.y..=max.y)
+            .flat_map(|y| (min.x..=max.x).map(move |x| Pos::new(x, y)))
+            .map(|pos| map.is_black(pos) as u8)
+            .collect();
+        Self {
+            cells,
+            width,
+            height,
+            colors: 2,
+            ants: Vec::new(),
+            origin: min,
+        }
+    }
+
+    /// Mark ants standing on these map cells
+    pub fn with_ants(mut self, ants: &[Pos]) -> Self {
+        let origin = self.origin;
+        self.ants.extend(
+            ants.iter()
+                .map(|ant| Pos::new(ant.x - origin.x, ant.y - origin.y)),
+        );
+        self
+    }
+
+    /// Every cell as a square of `scale` cells a side, `0` and `1` leave it as it is
+    pub fn scaled(&self, scale: usize) -> Self {
+        let scale = scale.max(1);
+        let cells = self
+            .cells
+            .chunks(self.width.max(1))
+            .flat_map(|row| {
+                let row = row
+                    .iter()
+                    .flat_map(|&cell| std::iter::repeat_n(cell, scale))
+                    .collect::<Vec<_>>();
+                std::iter::repeat_n(row, scale).flatten()
+            })
+            .collect();
+        let scale = scale as isize;
+        Self {
+            cells,
+            width: self.width * scale as usize,
+            height: self.height * scale as usize,
+            colors: self.colors,
+            ants: self
+                .ants
+                .iter()
+                .map(|ant| Pos::new(ant.x * scale + scale / 2, ant.y * scale + scale / 2))
+                .collect(),
+            origin: self.origin,
+        }
+    }
+
+    /// Flipped and turned as `orientation` says. The origin stays the map position
+    /// of the top left cell before, so layers reading the map through it won't line
+    /// up, see [`LayeredRenderer::with_orientation`](crate::LayeredRenderer::with_orientation)
+    pub fn oriented(&self, orientation: ImageOrientation) -> Self {
+        let (width, height) = orientation.size(self.width, self.height);
+        Self {
+            cells: orientation.pixels(&self.cells, 1, self.width, self.height),
+            width,
+            height,
+            colors: self.colors,
+            ants: self
+                .ants
+                .iter()
+                .map(|&ant| orientation.apply(ant, self.width, self.height))
+                .collect(),
+            origin: self.origin,
+        }
+    }
+
+    /// Fill `rgba` with a pixel of 4 bytes per cell in `palette` colors, the layout
+    /// of a browser canvas, ants get the marker color
+    ///
+    /// # Panics
+    ///
+    /// If `rgba` isn't 4 bytes per cell or `palette` has fewer colors than the frame
+    pub fn fill_rgba(&self, palette: &Palette, rgba: &mut [u8]) {
+        assert_eq!(rgba.len(), self.cells.len() * 4, "4 bytes per cell");
+        assert!(
+            palette.colors.len() >= self.colors,
+            "a palette color per cell color"
+        );
+
+        for (pixel, &cell) in rgba.chunks_exact_mut(4).zip(&self.cells) {
+            let [r, g, b] = palette.colors[cell as usize];
+            pixel.copy_from_slice(&[r, g, b, 255]);
+        }
+        for ant in &self.ants {
+            if (0..self.width as isize).contains(&ant.x)
+                && (0..self.height as isize).contains(&ant.y)
+            {
+                let i = (ant.y as usize * self.width + ant.x as usize) * 4;
+                let [r, g, b] = palette.marker;
+                rgba[i..i + 4].copy_from_slice(&[r, g, b, 255]);
+            }
+        }
+    }
+}
+
+/// Image format a [`Frame`] can be written in
+pub trait Renderer {
+    fn render(&self, frame: &Frame, w: &mut dyn Write) -> io::Result<()>;
+
+    /// Render into a new file
+    fn save(&self, frame: &Frame, file: &Path) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(file)?);
+        self.render(frame, &mut w)?;
+        w.flush()
+    }
+
+    /// [`save`](Self::save) until `control` is cancelled, the half written file is
+    /// removed then and the error is [`Cancelled`](crate::Cancelled)
+    fn save_controlled(
+        &self,
+        frame: &Frame,
+        file: &Path,
+        control: &ControlHandle,
+    ) -> io::Result<()> {
+        let mut w = control.writer(BufWriter::new(File::create(file)?));
+        let saved = self.render(frame, &mut w).and_then(|()| w.flush());
+        if saved.as_ref().is_err_and(Cancelled::is) {
+            fs::remove_file(file)?;
+        }
+        saved
+    }
+}
+
+#[cfg(feature = "io")]
+/// PNG in grayscale, or in indexed color with a plus shaped marker over every ant
+/// when there is a palette
+#[derive(Clone, Debug, Default)]
+pub struct PngRenderer {
+    /// Grayscale bit depth, `None` picks the smallest one holding every color:
+    /// 1 bit for two colors, 2 bits for up to 4, 4 bits for up to 16, 8 bits otherwise
+    pub depth: Option<BitDepth>,
+    /// Needs an entry for each color, markers clip at the image edges
+    pub palette: Option<Palette>,
+    /// Keyword and text pairs stored as `tEXt` chunks, Latin-1 only
+    pub text: Vec<(String, String)>,
+    /// Parameters to hide in the pixels with [`embed_params`](crate::embed_params),
+    /// which makes the image RGBA in the palette colors, or gray without one
+    pub params: Option<String>,
+    /// Draw every cell as a square of this many pixels a side, `0` and `1` draw it
+    /// as one. The image is scaled a row at a time while it's encoded, so a big
+    /// factor costs no memory
+    pub scale: usize,
+    /// Threads mapping cells to pixels and packing rows for gray and indexed images,
+    /// `0` for one per core. Rows still reach the encoder in order and are compressed
+    /// on the calling thread; small images and failed spawns pack on it too
+    pub threads: usize,
+}
+
+#[cfg(feature = "io")]
+impl Renderer for PngRenderer {
+    fn render(&self, frame: &Frame, w: &mut dyn Write) -> io::Result<()> {
+        match (&self.params, &self.palette) {
+            (Some(params), palette) => {
+                let palette = palette
+                    .clone()
+                    .unwrap_or_else(|| Palette::gray(frame.colors));
+                write_rgba(frame, &palette, params, self.scale, &self.text, w)
+            }
+            (None, Some(palette)) => write_indexed(frame, palette, self, w),
+            (None, None) => write_gray(frame, self, w),
+        }
+        .map_err(|e| match e {
+            EncodingError::IoError(e) => e,
+            e => io::Error::other(e),
+        })
+    }
+}
+
+#[cfg(feature = "io")]
+fn write_gray(frame: &Frame, png: &PngRenderer, w: &mut dyn Write) -> Result<(), EncodingError> {
+    let colors = frame.colors;
+    let depth = png.depth.unwrap_or(gray_depth(colors));
+    let bits = depth as usize;
+    if bits > 8 || colors > 1 << bits {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{colors} colors don't fit into {bits}-bit grayscale"),
+        )
+        .into());
+    }
+
+    let scale = png.scale.max(1);
+    let (width, height) = (frame.width * scale, frame.height * scale);
+    let mut encoder = Encoder::new(w, width as _, height as _);
+    encoder.set_color(ColorType::Grayscale);
+    encoder.set_depth(depth);
+    add_text(&mut encoder, &png.text)?;
+    let writer = encoder.write_header()?;
+
+    let levels = gray_levels(colors, depth);
+    write_rows(
+        writer,
+        &frame.cells,
+        frame.width,
+        scale,
+        depth,
+        png.threads,
+        |cell| levels[cell as usize],
+    )
+}
+
+#[cfg(feature = "io")]
+/// Write row-major cells as `depth` bit values, every one `scale` times over and
+/// every row `scale` times, packing bands of rows on `threads` threads
+fn write_rows<W: Write>(
+    mut writer: png::Writer<W>,
+    cells: &[u8],
+    width: usize,
+    scale: usize,
+    depth: BitDepth,
+    threads: usize,
+    value: impl Fn(u8) -> u8 + Sync,
+) -> Result<(), EncodingError> {
+    let mut stream = writer.stream_writer()?;
+    let rows = cells.len() / width.max(1);
+    let (bits, per_byte) = (depth as usize, 8 / depth as usize);
+    let pack = |y: usize, packed: &mut Vec<u8>| {
+        // Packed straight into the band, the way `pack_indices` does it
+        let start = packed.len();
+        let row = &cells[y * width..(y + 1) * width];
+        let values = row
+            .iter()
+            .flat_map(|&cell| std::iter::repeat_n(value(cell), scale));
+        let (mut byte, mut filled) = (0, 0);
+        for value in values {
+            filled += 1;
+            byte |= value << (8 - bits * filled);
+            if filled == per_byte {
+                packed.push(byte);
+                (byte, filled) = (0, 0);
+            }
+        }
+        if filled > 0 {
+            packed.push(byte);
+        }
+        let end = packed.len();
+        for _ in 1..scale {
+            packed.extend_from_within(start..end);
+        }
+    };
+    pack_in_order(rows, threads, pack, |packed| stream.write_all(packed))?;
+    stream.finish()
+}
+
+#[cfg(feature = "io")]
+/// Rows `0..rows` filled in by `pack` on up to `threads` threads (`0` for one per core),
+/// handed to `write` in order a band at a time so only a band is ever in memory
+fn pack_in_order(
+    rows: usize,
+    threads: usize,
+    pack: impl Fn(usize, &mut Vec<u8>) + Sync,
+    mut write: impl FnMut(&[u8]) -> io::Result<()>,
+) -> io::Result<()> {
+    // Rows a thread packs in one go, a few megabytes of pixels for huge maps
+    const BAND: usize = 64;
+
+    let threads = match threads {
+        0 => num_cpus::get(),
+        threads => threads,
+    };
+    let threads = threads.min(rows.div_ceil(BAND)).max(1);
+    let mut bands = vec![Vec::new(); threads];
+    for start in (0..rows).step_by(threads * BAND) {
+        let band = |i: usize| {
+            let from = (start + i * BAND).min(rows);
+            from..(from + BAND).min(rows)
+        };
+        let packed = |i: usize, buf: &mut Vec<u8>| {
+            buf.clear();
+            band(i).for_each(|y| pack(y, buf));
+        };
+
+        let mut failed = Vec::new();
+        match threads {
+            1 => packed(0, &mut bands[0]),
+            _ => thread::scope(|s| {
+                for (i, buf) in bands.iter_mut().enumerate() {
+                    let packed = &packed;
+                    let spawned = thread::Builder::new().spawn_scoped(s, move || packed(i, buf));
+                    if spawned.is_err() {
+                        failed.push(i);
+                    }
+                }
+            }),
+        }
+        // No threads to be had, do their bands here
+        for i in failed {
+            packed(i, &mut bands[i]);
+        }
+        for buf in &bands {
+            write(buf)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "io")]
+fn write_indexed(
+    frame: &Frame,
+    palette: &Palette,
+    png: &PngRenderer,
+    w: &mut dyn Write,
+) -> Result<(), EncodingError> {
+    let colors = frame.colors;
+    if palette.colors.len() < colors {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{colors} colors but the palette has only {}",
+                palette.colors.len()
+            ),
+        )
+        .into());
+    }
+
+    // The marker takes the entry after the cell colors
+    let marker = colors as u8;
+    let entries = colors + !frame.ants.is_empty() as usize;
+    if entries > 256 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no palette entry left for the ant marker",
+        )
+        .into());
+    }
+
+    let (width, height) = (frame.width, frame.height);
+    let mut cells = frame.cells.clone();
+    for ant in &frame.ants {
+        for shift in [(0, 0), (-1, 0), (1, 0), (0, -1), (0, 1)] {
+            let (x, y) = (ant.x + shift.0, ant.y + shift.1);
+            if (0..width as isize).contains(&x) && (0..height as isize).contains(&y) {
+                cells[y as usize * width + x as usize] = marker;
+            }
+        }
+    }
+
+    let mut plte = palette.colors[..colors].concat();
+    if !frame.ants.is_empty() {
+        plte.extend(palette.marker);
+    }
+
+    let depth = gray_depth(entries);
+    let scale = png.scale.max(1);
+    let mut encoder = Encoder::new(w, (width * scale) as _, (height * scale) as _);
+    encoder.set_color(ColorType::Indexed);
+    encoder.set_depth(depth);
+    encoder.set_palette(plte);
+    tag_srgb(&mut encoder);
+    add_text(&mut encoder, &png.text)?;
+    let writer = encoder.write_header()?;
+
+    write_rows(writer, &cells, width, scale, depth, png.threads, |cell| {
+        cell
+    })
+}
+
+#[cfg(feature = "io")]
+fn write_rgba(
+    frame: &Frame,
+    palette: &Palette,
+    params: &str,
+    scale: usize,
+    text: &[(String, String)],
+    w: &mut dyn Write,
+) -> Result<(), EncodingError> {
+    if palette.colors.len() < frame.colors {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} colors but the palette has only {}",
+                frame.colors,
+                palette.colors.len()
+            ),
+        )
+        .into());
+    }
+
+    let scale = scale.max(1);
+    let (width, height) = (frame.width * scale, frame.height * scale);
+    let mut encoder = Encoder::new(w, width as _, height as _);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    tag_srgb(&mut encoder);
+    add_text(&mut encoder, text)?;
+
+    if scale == 1 {
+        let mut rgba = vec![0; frame.cells.len() * 4];
+        frame.fill_rgba(palette, &mut rgba);
+        embed_params(&mut rgba, params)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        return encoder.write_header()?.write_image_data(&rgba);
+    }
+
+    let room = params_room(width * height);
+    if params.len() > room {
+        let needed = params.len();
+        let e = StegoError::TooSmall { needed, room };
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, e).into());
+    }
+    // A row of cells at a time, scaled up, the hidden bits run on across the copies
+    let mut bits = message_bits(params);
+    let mut writer = encoder.write_header()?;
+    let mut stream = writer.stream_writer()?;
+    let mut colors = vec![[0; 3]; frame.width];
+    let mut scaled = vec![0; width * 4];
+    for (y, row) in frame.cells.chunks(frame.width).enumerate() {
+        for (color, &cell) in colors.iter_mut().zip(row) {
+            *color = palette.colors[cell as usize];
+        }
+        for ant in frame.ants.iter().filter(|ant| ant.y == y as isize) {
+            if let Some(color) = usize::try_from(ant.x).ok().and_then(|x| colors.get_mut(x)) {
+                *color = palette.marker;
+            }
+        }
+        for _ in 0..scale {
+            for (pixels, [r, g, b]) in scaled.chunks_exact_mut(4 * scale).zip(&colors) {
+                for pixel in pixels.chunks_exact_mut(4) {
+                    pixel.copy_from_slice(&[*r, *g, *b, 255]);
+                    for channel in &mut pixel[..3] {
+                        if let Some(bit) = bits.next() {
+                            *channel = *channel & !1 | bit;
+                        }
+                    }
+                }
+            }
+            stream.write_all(&scaled)?;
+        }
+    }
+    stream.finish()
+}
+
+#[cfg(feature = "io")]
+fn add_text<W: Write>(
+    encoder: &mut Encoder<W>,
+    text: &[(String, String)],
+) -> Result<(), EncodingError> {
+    for (keyword, text) in text {
+        encoder.add_text_chunk(keyword.clone(), text.clone())?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "io")]
+/// Save a multi-color map as grayscale, colors fade evenly from white (`0`)
+/// to black (`colors - 1`), only the `crop` part of it if given
+///
+/// `depth` is the PNG bit depth, see [`PngRenderer::depth`]
+pub fn save_color_map_to_file<const W: usize, const H: usize>(
+    map: &impl CellStore<W, H>,
+    colors: usize,
+    depth: Option<BitDepth>,
+    crop: Option<Crop>,
+    file: impl AsRef<Path>,
+) -> Result<(), EncodingError> {
+    let renderer = PngRenderer {
+        depth,
+        ..PngRenderer::default()
+    };
+    Ok(renderer.save(&Frame::new(map, colors, crop), file.as_ref())?)
+}
+
+#[cfg(feature = "io")]
+/// Smallest grayscale or indexed bit depth holding `colors` distinct levels
+pub(crate) fn gray_depth(colors: usize) -> BitDepth {
+    match colors {
+        0..=2 => BitDepth::One,
+        3..=4 => BitDepth::Two,
+        5..=16 => BitDepth::Four,
+        _ => BitDepth::Eight,
+    }
+}
+
+#[cfg(feature = "io")]
+/// Pack row-major cell colors into grayscale PNG rows, colors fade from white to black
+pub(crate) fn pack_gray(cells: &[u8], width: usize, colors: usize, depth: BitDepth) -> Vec<u8> {
+    let levels = gray_levels(colors, depth);
+    let cells = cells
+        .iter()
+        .map(|&c| levels[c as usize])
+        .collect::<Vec<_>>();
+    pack_indices(&cells, width, depth)
+}
+
+#[cfg(feature = "io")]
+/// `depth` bit gray of every color, from white to black
+fn gray_levels(colors: usize, depth: BitDepth) -> Vec<u8> {
+    let white = (1 << depth as usize) - 1;
+    let darkest = colors.saturating_sub(1).max(1);
+    (0..colors)
+        .map(|c| (white - c * white / darkest) as u8)
+        .collect()
+}
+
+#[cfg(feature = "io")]
+/// Pack row-major pixel values as they are into `depth` bit PNG rows
+pub(crate) fn pack_indices(cells: &[u8], width: usize, depth: BitDepth) -> Vec<u8> {
+    let bits = depth as usize;
+
+    // Rows are packed separately, each one starts on a fresh byte
+    let per_byte = 8 / bits;
+    let mut data = Vec::with_capacity(width.div_ceil(per_byte) * cells.len() / width);
+    for row in cells.chunks(width) {
+        for pixels in row.chunks(per_byte) {
+            let byte = pixels
+                .iter()
+                .enumerate()
+                .fold(0, |byte, (i, &c)| byte | c << (8 - bits * (i + 1)));
+            data.push(byte);
+        }
+    }
+    data
+}
+
+#[cfg(feature = "io")]
+/// Save a map as an indexed color PNG, with a plus shaped marker over every cell in `ants`,
+/// only the `crop` part of it if given
+///
+/// `palette` needs an entry for each of the `colors`, markers clip at the image edges
+pub fn save_palette_map_to_file<const W: usize, const H: usize>(
+    map: &impl CellStore<W, H>,
+    colors: usize,
+    palette: &Palette,
+    ants: &[Pos],
+    crop: Option<Crop>,
+    file: impl AsRef<Path>,
+) -> Result<(), EncodingError> {
+    let renderer = PngRenderer {
+        palette: Some(palette.clone()),
+        ..PngRenderer::default()
+    };
+    let frame = Frame::new(map, colors, crop).with_ants(ants);
+    Ok(renderer.save(&frame, file.as_ref())?)
+}
+
+#[cfg(feature = "io")]
+/// Save the touched chunks of an unbounded map as a 1-bit grayscale PNG,
+/// returns the board position of the top left pixel
+pub fn save_sparse_map_to_file(
+    map: &SparseMap,
+    file: impl AsRef<Path>,
+) -> Result<Pos, EncodingError> {
+    let last = CHUNK_SIZE as isize - 1;
+    let (min, max) = map
+        .bounding_box()
+        .unwrap_or((Pos::new(0, 0), Pos::new(last, last)));
+    let width = (max.x - min.x + 1) as usize;
+    let height = (max.y - min.y + 1) as usize;
+
+    let file = File::create(file)?;
+    let w = BufWriter::new(file);
+
+    let mut encoder = Encoder::new(w, width as _, height as _);
+    encoder.set_color(ColorType::Grayscale);
+    encoder.set_depth(BitDepth::One);
+    let mut writer = encoder.write_header()?;
+
+    let mut data = Vec::with_capacity(width / 8 * height);
+    for y in min.y..=max.y {
+        for x in (min.x..=max.x).step_by(CHUNK_SIZE) {
+            let (coord, _, row) = ChunkCoord::of(Pos::new(x, y));
+            let black = map.chunk(coord).map_or(0, |chunk| chunk.row(row));
+            // Chunk rows keep the leftmost cell in the lowest bit, PNG wants it in the highest
+            // and a set bit is white there
+            data.extend((!black).to_le_bytes().map(u8::reverse_bits));
+        }
+    }
+
+    writer.write_image_data(&data)?;
+    Ok(min)
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn packs_low_bit_depths() {
+    use crate::{ColorMap, MapPos};
+
+    let mut map = ColorMap::<3, 2>::new_white();
+    for (i, color) in [0, 1, 2, 3, 2, 1].into_iter().enumerate() {
+        let pos = MapPos::validate_pos(Pos::new(i as isize % 3, i as isize / 3)).unwrap();
+        map.set_color(pos, color);
+    }
+
+    let file = std::env::temp_dir().join("antventure_packs_low_bit_depths.png");
+    save_color_map_to_file(&map, 4, None, None, &file).unwrap();
+
+    let decoder = png::Decoder::new(File::open(&file).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut data).unwrap();
+    std::fs::remove_file(file).ok();
+
+    assert_eq!(reader.info().bit_depth, BitDepth::Two);
+    // White, light gray, dark gray in the first row, padded to a byte
+    assert_eq!(data, [0b11_10_01_00, 0b00_01_10_00]);
+
+    let file = std::env::temp_dir().join("antventure_rejects_small_depth.png");
+    assert!(save_color_map_to_file(&map, 4, Some(BitDepth::One), None, file).is_err());
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn marks_ants_in_palette() {
+    let map = Map::<4, 3>::new_white();
+    let palette = "#fff,#000".parse().unwrap();
+
+    let file = std::env::temp_dir().join("antventure_marks_ants_in_palette.png");
+    save_palette_map_to_file(&map, 2, &palette, &[Pos::new(0, 1)], None, &file).unwrap();
+
+    let decoder = png::Decoder::new(File::open(&file).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut data).unwrap();
+    std::fs::remove_file(file).ok();
+
+    let info = reader.info();
+    assert_eq!(info.color_type, ColorType::Indexed);
+    assert_eq!(info.bit_depth, BitDepth::Two);
+    assert_eq!(info.palette.as_deref().unwrap()[6..], Palette::RED);
+    // The plus is cut off by the left edge
+    assert_eq!(data, [0b10_00_00_00, 0b10_10_00_00, 0b10_00_00_00]);
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn crops_to_region() {
+    use crate::MapPos;
+
+    let mut map = Map::<8, 8>::new_white();
+    map.set(MapPos::validate_pos(Pos::new(5, 3)).unwrap(), false);
+    let crop = Crop::around((Pos::new(5, 3), Pos::new(5, 3)), 1, (8, 8));
+    assert_eq!((crop.min, crop.max), (Pos::new(4, 2), Pos::new(6, 4)));
+    let edges = Crop::around((Pos::new(1, 0), Pos::new(7, 6)), 2, (8, 8));
+    assert_eq!((edges.min, edges.max), (Pos::new(0, 0), Pos::new(7, 7)));
+
+    let file = std::env::temp_dir().join("antventure_crops_to_region.png");
+    save_color_map_to_file(&map, 2, None, Some(crop), &file).unwrap();
+
+    let decoder = png::Decoder::new(File::open(&file).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut data).unwrap();
+    std::fs::remove_file(file).ok();
+
+    assert_eq!((reader.info().width, reader.info().height), (3, 3));
+    assert_eq!(data, [0b1110_0000, 0b1010_0000, 0b1110_0000]);
+}
+
+#[test]
+fn frames_touched_chunks() {
+    let mut map = SparseMap::new_white();
+    map.set(Pos::new(-1, 2), true);
+    let frame = Frame::from_sparse(&map);
+
+    let size = CHUNK_SIZE as isize;
+    assert_eq!(frame.origin, Pos::new(-size, 0));
+    assert_eq!((frame.width, frame.height), (CHUNK_SIZE, CHUNK_SIZE));
+    assert_eq!(frame.cells.iter().filter(|&&c| c == 1).count(), 1);
+    assert_eq!(frame.cells[2 * CHUNK_SIZE + CHUNK_SIZE - 1], 1);
+}
+
+#[test]
+fn fills_rgba_pixels() {
+    use crate::{ColorMap, MapPos};
+
+    let mut map = ColorMap::<3, 2>::new_white();
+    map.set_color(MapPos::validate_pos(Pos::new(1, 0)).unwrap(), 2);
+    let frame = Frame::new(&map, 3, None).with_ants(&[Pos::new(2, 1), Pos::new(5, 5)]);
+
+    let mut rgba = vec![0; 3 * 2 * 4];
+    frame.fill_rgba(&Palette::gray(3), &mut rgba);
+    assert_eq!(rgba[..8], [255, 255, 255, 255, 0, 0, 0, 255]);
+    assert_eq!(rgba[20..], [255, 0, 0, 255]);
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn hides_params_in_rgba() {
+    let map = Map::<16, 16>::new_white();
+    let renderer = PngRenderer {
+        params: Some("serious --rule LR".into()),
+        ..PngRenderer::default()
+    };
+    let mut png = Vec::new();
+    renderer
+        .render(&Frame::new(&map, 2, None), &mut png)
+        .unwrap();
+
+    let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut data).unwrap();
+    assert_eq!(reader.info().color_type, ColorType::Rgba);
+    assert_eq!(
+        crate::extract_params(&data).as_deref(),
+        Ok("serious --rule LR")
+    );
+
+    // 16x16 pixels hold 96 bytes, 84 of them text
+    let renderer = PngRenderer {
+        params: Some("x".repeat(85)),
+        ..PngRenderer::default()
+    };
+    assert!(renderer
+        .render(&Frame::new(&map, 2, None), &mut Vec::new())
+        .is_err());
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn scales_cells_up() {
+    use crate::{ColorMap, MapPos};
+
+    let mut map = ColorMap::<3, 2>::new_white();
+    map.set_color(MapPos::validate_pos(Pos::new(1, 0)).unwrap(), 2);
+    let decode = |renderer: &PngRenderer, frame: &Frame| {
+        let mut png = Vec::new();
+        renderer.render(frame, &mut png).unwrap();
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+        let info = reader.info();
+        ((info.width, info.height), data)
+    };
+
+    let renderer = PngRenderer {
+        depth: Some(BitDepth::Eight),
+        scale: 2,
+        ..PngRenderer::default()
+    };
+    let (size, data) = decode(&renderer, &Frame::new(&map, 3, None));
+    assert_eq!(size, (6, 4));
+    let top = [255, 255, 0, 0, 255, 255];
+    assert_eq!(data[..12], [top, top].concat());
+    assert!(data[12..].iter().all(|&level| level == 255));
+
+    let renderer = PngRenderer {
+        palette: Some(Palette::gray(3)),
+        scale: 3,
+        ..PngRenderer::default()
+    };
+    let frame = Frame::new(&map, 3, None).with_ants(&[Pos::new(0, 1)]);
+    let (size, data) = decode(&renderer, &frame);
+    assert_eq!(size, (9, 6));
+    // Two bits per pixel, the marker is entry 3 and covers (0, 0) above the ant
+    assert_eq!(data[..3], [0b11_11_11_10, 0b10_10_00_00, 0b00_00_00_00]);
+
+    let renderer = PngRenderer {
+        params: Some("serious --scale 4".into()),
+        scale: 4,
+        ..PngRenderer::default()
+    };
+    let (size, data) = decode(&renderer, &frame);
+    assert_eq!(size, (12, 8));
+    assert_eq!(
+        crate::extract_params(&data).as_deref(),
+        Ok("serious --scale 4")
+    );
+    // The third cell is white under the hidden bits
+    assert!(data[32..35].iter().all(|&c| c >> 1 == 127));
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn threads_pack_the_same_rows() {
+    use crate::{ColorMap, MapPos};
+
+    let mut map = ColorMap::<37, 300>::new_white();
+    for i in 0..37 * 300 {
+        let pos = MapPos::validate_pos(Pos::new(i % 37, i / 37)).unwrap();
+        map.set_color(pos, (i * i % 7) as u8);
+    }
+    let frame = Frame::new(&map, 7, None).with_ants(&[Pos::new(3, 200)]);
+    let render = |threads, palette: Option<Palette>| {
+        let renderer = PngRenderer {
+            palette,
+            scale: 2,
+            threads,
+            ..PngRenderer::default()
+        };
+        let mut png = Vec::new();
+        renderer.render(&frame, &mut png).unwrap();
+        png
+    };
+
+    // Bands of 64 rows on 3 threads, the last band short
+    assert_eq!(render(1, None), render(3, None));
+    let palette = Palette::gray(7);
+    assert_eq!(render(1, Some(palette.clone())), render(0, Some(palette)));
+}
@@ -0,0 +1,406 @@
+//! `.antr`, the replay format: the board a run started from and every cell it repainted
+//!
+//! After `ANTR` and the `u16` [`REPLAY_VERSION`], everything is one zlib stream holding,
+//! little-endian:
+//!
+//! - width and height as `u32`, the colors of the rule as a `u8` and the step the
+//!   recording started at as `u64`
+//! - the cells in row-major order, a byte each
+//! - for every step the count of repainted cells, then for each how far it is from the
+//!   one before as `x` and `y`, and its new color as a `u8`
+//!
+//! Counts and distances are LEB128 varints, distances zigzag encoded. An ant seldom
+//! repaints far from where it just did, so a step usually takes a few bits once compressed
+
+use std::{error::Error, fmt::Display};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+
+use crate::{
+    formats::{REPLAY_MAGIC, REPLAY_VERSION},
+    render::cropped_colors,
+    CellStore, Crop, Format, Frame, MapPos, Observer, Pos, Simulation,
+};
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    /// Not a replay, or cells outside the board
+    Corrupted,
+    /// The replay is of a newer version of the format
+    Version(u16),
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "{e}"),
+            ReplayError::Corrupted => write!(f, "not a replay or a damaged one"),
+            ReplayError::Version(version) => write!(
+                f,
+                "replay is of format version {version}, expected up to {REPLAY_VERSION}"
+            ),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(e: io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+/// Records a run into a replay as it goes, see [`Replay`] to play it back
+///
+/// The recording is only complete once the run finishes, see [`Simulation::finish`]
+pub struct ReplayRecorder<Wr: Write = BufWriter<File>> {
+    out: ZlibEncoder<Wr>,
+    /// Cells repainted during the current step and their new colors
+    flips: Vec<(Pos, u8)>,
+    last: Pos,
+    steps: u64,
+    error: Option<io::Error>,
+}
+
+impl ReplayRecorder {
+    /// Record into a new file, starting from the board `sim` has now
+    pub fn create<const W: usize, const H: usize, M: CellStore<W, H>>(
+        file: impl AsRef<Path>,
+        sim: &Simulation<W, H, M>,
+    ) -> io::Result<Self> {
+        let out = BufWriter::new(File::create(file)?);
+        Ok(Self::new(out, sim))
+    }
+}
+
+impl<Wr: Write> ReplayRecorder<Wr> {
+    pub fn new<const W: usize, const H: usize, M: CellStore<W, H>>(
+        mut out: Wr,
+        sim: &Simulation<W, H, M>,
+    ) -> Self {
+        let header = out
+            .write_all(REPLAY_MAGIC)
+            .and_then(|()| out.write_all(&REPLAY_VERSION.to_le_bytes()));
+        let mut recorder = Self {
+            out: ZlibEncoder::new(out, Compression::default()),
+            flips: Vec::new(),
+            last: Pos::new(0, 0),
+            steps: 0,
+            error: header.err(),
+        };
+        let (cells, width, height) = cropped_colors(sim.map(), None);
+        recorder.write(|out| {
+            out.write_all(&(width as u32).to_le_bytes())?;
+            out.write_all(&(height as u32).to_le_bytes())?;
+            out.write_all(&[sim.rule().colors() as u8])?;
+            out.write_all(&sim.steps().to_le_bytes())?;
+            out.write_all(&cells)
+        });
+        recorder
+    }
+
+    /// Steps recorded so far
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// First error writing the replay, if there was one
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    fn write(&mut self, f: impl FnOnce(&mut ZlibEncoder<Wr>) -> io::Result<()>) {
+        if self.error.is_none() {
+            self.error = f(&mut self.out).err();
+        }
+    }
+}
+
+/// Writes the cells repainted in every step and ends the stream with the run
+impl<const W: usize, const H: usize, M: CellStore<W, H>, Wr: Write> Observer<W, H, M>
+    for ReplayRecorder<Wr>
+{
+    fn on_step(&mut self, _sim: &Simulation<W, H, M>) {
+        let mut bytes = Vec::with_capacity(1 + self.flips.len() * 4);
+        write_varint(&mut bytes, self.flips.len() as u64);
+        for (pos, color) in std::mem::take(&mut self.flips) {
+            write_varint(&mut bytes, zigzag(pos.x - self.last.x));
+            write_varint(&mut bytes, zigzag(pos.y - self.last.y));
+            bytes.push(color);
+            self.last = pos;
+        }
+        self.write(|out| out.write_all(&bytes));
+        self.steps += 1;
+    }
+
+    fn on_cell_flip(&mut self, _ant: usize, pos: MapPos<'static, W, H>, _from: u8, to: u8) {
+        self.flips.push((pos.into(), to));
+    }
+
+    fn on_finish(&mut self, _sim: &Simulation<W, H, M>) {
+        self.write(|out| {
+            out.try_finish()?;
+            out.get_mut().flush()
+        });
+    }
+}
+
+/// A recorded run played back step by step, without the rule or the ants
+pub struct Replay<R: Read = BufReader<File>> {
+    input: ZlibDecoder<R>,
+    width: usize,
+    height: usize,
+    colors: usize,
+    cells: Vec<u8>,
+    step: u64,
+    last: Pos,
+    touched: Option<(Pos, Pos)>,
+}
+
+impl Replay {
+    pub fn open(file: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        Self::new(BufReader::new(File::open(file)?))
+    }
+}
+
+impl<R: Read> Replay<R> {
+    /// Read the header and the starting board
+    pub fn new(mut input: R) -> Result<Self, ReplayError> {
+        let mut header = [0; 6];
+        input.read_exact(&mut header).map_err(corrupted)?;
+        if !header.starts_with(REPLAY_MAGIC) {
+            return Err(ReplayError::Corrupted);
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if !Format::Replay.reads(version) {
+            return Err(ReplayError::Version(version));
+        }
+
+        let mut input = ZlibDecoder::new(input);
+        let mut sizes = [0; 17];
+        input.read_exact(&mut sizes).map_err(corrupted)?;
+        let width = u32::from_le_bytes(sizes[0..4].try_into().unwrap()) as usize;
+        let height = u32::from_le_bytes(sizes[4..8].try_into().unwrap()) as usize;
+        let colors = sizes[8] as usize;
+        let step = u64::from_le_bytes(sizes[9..17].try_into().unwrap());
+        let mut cells = vec![0; width * height];
+        input.read_exact(&mut cells).map_err(corrupted)?;
+
+        Ok(Self {
+            input,
+            width,
+            height,
+            colors: colors.max(cells.iter().max().map_or(0, |&c| c as usize + 1)),
+            cells,
+            step,
+            last: Pos::new(0, 0),
+            touched: None,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Colors of the rule, or more if cells are in other colors
+    pub fn colors(&self) -> usize {
+        self.colors
+    }
+
+    /// The step the board is at now
+    pub fn step(&self) -> u64 {
+        self.step
+    }
+
+    /// Cell colors in row-major order
+    pub fn cells(&self) -> &[u8] {
+        &self.cells
+    }
+
+    /// Smallest rectangle holding every cell repainted so far, as inclusive corners
+    pub fn touched(&self) -> Option<(Pos, Pos)> {
+        self.touched
+    }
+
+    /// Repaint the cells of the next step, returns whether there was one
+    pub fn play_step(&mut self) -> Result<bool, ReplayError> {
+        let Some(flips) = read_varint(&mut self.input, true)? else {
+            return Ok(false);
+        };
+        for _ in 0..flips {
+            let read = |input: &mut ZlibDecoder<R>| read_varint(input, false).map(Option::unwrap);
+            let x = self.last.x + unzigzag(read(&mut self.input)?);
+            let y = self.last.y + unzigzag(read(&mut self.input)?);
+            let mut color = [0];
+            self.input.read_exact(&mut color).map_err(corrupted)?;
+
+            let inside =
+                (0..self.width as isize).contains(&x) && (0..self.height as isize).contains(&y);
+            if !inside {
+                return Err(ReplayError::Corrupted);
+            }
+            self.cells[y as usize * self.width + x as usize] = color[0];
+            self.colors = self.colors.max(color[0] as usize + 1);
+            self.last = Pos::new(x, y);
+            self.touched = Some(match self.touched {
+                Some((min, max)) => (
+                    Pos::new(min.x.min(x), min.y.min(y)),
+                    Pos::new(max.x.max(x), max.y.max(y)),
+                ),
+                None => (self.last, self.last),
+            });
+        }
+        self.step += 1;
+        Ok(true)
+    }
+
+    /// Play until step `step` or the end of the replay, returns whether it got there
+    pub fn play_to(&mut self, step: u64) -> Result<bool, ReplayError> {
+        while self.step < step {
+            if !self.play_step()? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// The `crop` part of the board as it is now, all of it without one
+    pub fn frame(&self, crop: Option<Crop>) -> Frame {
+        let crop = crop.unwrap_or(Crop {
+            min: Pos::new(0, 0),
+            max: Pos::new(self.width as isize - 1, self.height as isize - 1),
+        });
+        let (x, width) = (crop.min.x as usize, crop.width());
+        let cells = self
+            .cells
+            .chunks(self.width)
+            .skip(crop.min.y as usize)
+            .take(crop.height())
+            .flat_map(|row| &row[x..x + width])
+            .copied()
+            .collect();
+        Frame {
+            cells,
+            width,
+            height: crop.height(),
+            colors: self.colors,
+            ants: Vec::new(),
+            origin: crop.min,
+        }
+    }
+}
+
+fn corrupted(e: io::Error) -> ReplayError {
+    match e.kind() {
+        io::ErrorKind::UnexpectedEof | io::ErrorKind::InvalidInput => ReplayError::Corrupted,
+        _ => ReplayError::Io(e),
+    }
+}
+
+fn zigzag(n: isize) -> u64 {
+    ((n << 1) ^ (n >> (isize::BITS - 1))) as u64
+}
+
+fn unzigzag(n: u64) -> isize {
+    (n >> 1) as isize ^ -((n & 1) as isize)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+/// `None` if the input ends right away and `at_end` allows it
+fn read_varint(input: &mut impl Read, at_end: bool) -> Result<Option<u64>, ReplayError> {
+    let mut n = 0;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        if input.read(&mut byte)? == 0 {
+            return match shift == 0 && at_end {
+                true => Ok(None),
+                false => Err(ReplayError::Corrupted),
+            };
+        }
+        n |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] < 0x80 {
+            return Ok(Some(n));
+        }
+    }
+    Err(ReplayError::Corrupted)
+}
+
+#[test]
+fn replays_repaint_the_board() {
+    use crate::{Boundary, ColorMap, Direction, Rule};
+
+    for n in [0, 1, -1, 63, -64, 1 << 40, isize::MIN] {
+        assert_eq!(unzigzag(zigzag(n)), n);
+    }
+
+    let rule = "LRRL".parse::<Rule>().unwrap();
+    let mut sim =
+        Simulation::new(ColorMap::<32, 32>::new_white(), rule).with_boundary(Boundary::Wrap);
+    sim.spawn(Pos::new(16, 16), Direction::North).unwrap();
+    sim.spawn(Pos::new(3, 30), Direction::East).unwrap();
+    sim.run_at_most(50);
+    let recorder = ReplayRecorder::new(Vec::new(), &sim);
+    let mut sim = sim.with_observer(recorder);
+
+    let mut boards = vec![sim.map().to_colors()];
+    for _ in 0..500 {
+        sim.run_at_most(1);
+        boards.push(sim.map().to_colors());
+    }
+    sim.finish();
+    let recorder = sim.observer::<ReplayRecorder<Vec<u8>>>().unwrap();
+    assert!(recorder.error().is_none());
+    assert_eq!(recorder.steps(), 500);
+
+    let mut replay = Replay::new(recorder.out.get_ref().as_slice()).unwrap();
+    assert_eq!(
+        (replay.width(), replay.height(), replay.colors()),
+        (32, 32, 4)
+    );
+    assert_eq!(replay.step(), 50);
+    assert_eq!(replay.touched(), None);
+    assert_eq!(replay.cells(), boards[0].as_slice());
+    for board in &boards[1..] {
+        assert!(replay.play_step().unwrap());
+        assert_eq!(replay.cells(), board.as_slice());
+    }
+    assert!(!replay.play_step().unwrap());
+    assert_eq!(replay.step(), 550);
+
+    let (min, max) = replay.touched().unwrap();
+    let frame = replay.frame(Some(Crop { min, max }));
+    assert_eq!(frame.origin, min);
+    assert_eq!(frame.cells.len(), frame.width * frame.height);
+    let row = (min.y * 32 + min.x) as usize;
+    assert_eq!(
+        frame.cells[..frame.width],
+        boards[500][row..row + frame.width]
+    );
+
+    assert!(matches!(
+        Replay::new(&b"ANTR\x09\x00"[..]),
+        Err(ReplayError::Version(9))
+    ));
+    assert!(matches!(
+        Replay::new(&b"PNG"[..]),
+        Err(ReplayError::Corrupted)
+    ));
+}
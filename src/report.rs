@@ -0,0 +1,258 @@
+use std::{fmt::Display, fmt::Write as _, fs, io, path::Path};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use png::EncodingError;
+
+use crate::{CellStore, DensityGrid, Frame, Palette, PngRenderer, Pos, Renderer};
+
+/// Values sampled over a run, thinned out as it goes so a run of any length
+/// ends up with a few hundred points at most
+#[derive(Clone, PartialEq, Debug)]
+pub struct Series {
+    pub name: String,
+    /// Step and value
+    pub points: Vec<(u64, f64)>,
+    every: u64,
+}
+
+impl Series {
+    const MAX_POINTS: usize = 256;
+
+    /// Series sampled every `every` steps to begin with
+    pub fn new(name: impl Into<String>, every: u64) -> Self {
+        Self {
+            name: name.into(),
+            points: Vec::new(),
+            every: every.max(1),
+        }
+    }
+
+    /// Step the next point is due at
+    pub fn next_at(&self) -> u64 {
+        self.points.last().map_or(0, |&(step, _)| step + self.every)
+    }
+
+    /// Once the series is full every other point goes and the spacing doubles
+    pub fn push(&mut self, step: u64, value: f64) {
+        self.points.push((step, value));
+        if self.points.len() >= Self::MAX_POINTS {
+            let mut i = 0;
+            self.points.retain(|_| {
+                i += 1;
+                i % 2 == 1
+            });
+            self.every *= 2;
+        }
+    }
+}
+
+/// A single HTML file with everything about a run: the final board, key numbers,
+/// charts and the command that repeats it, with nothing to install to open it
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    title: String,
+    /// PNG of the final board
+    image: Option<Vec<u8>>,
+    stats: Vec<(String, String)>,
+    series: Vec<Series>,
+    command: Option<String>,
+}
+
+impl Report {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Embed `map` rendered like [`save_palette_map_to_file`](crate::save_palette_map_to_file)
+    pub fn with_map<const W: usize, const H: usize>(
+        mut self,
+        map: &impl CellStore<W, H>,
+        colors: usize,
+        palette: &Palette,
+        ants: &[Pos],
+    ) -> Result<Self, EncodingError> {
+        let renderer = PngRenderer {
+            palette: Some(palette.clone()),
+            ..PngRenderer::default()
+        };
+        let mut png = Vec::new();
+        renderer.render(&Frame::new(map, colors, None).with_ants(ants), &mut png)?;
+        self.image = Some(png);
+        Ok(self)
+    }
+
+    /// Embed a gray thumbnail of the board instead, for boards too big to embed whole
+    pub fn with_thumbnail(mut self, grid: &DensityGrid) -> Result<Self, EncodingError> {
+        let mut png = Vec::new();
+        grid.render(&mut png)?;
+        self.image = Some(png);
+        Ok(self)
+    }
+
+    pub fn with_stat(mut self, name: impl Into<String>, value: impl Display) -> Self {
+        self.stats.push((name.into(), value.to_string()));
+        self
+    }
+
+    pub fn with_series(mut self, series: Series) -> Self {
+        self.series.push(series);
+        self
+    }
+
+    /// Shell command that repeats the run
+    pub fn with_command(mut self, command: impl Into<String>) -> Self {
+        self.command = Some(command.into());
+        self
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let title = escape(&self.title);
+        let _ = writeln!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>"
+        );
+        html.push_str(STYLE);
+        let _ = writeln!(html, "</head>\n<body>\n<h1>{title}</h1>");
+
+        if let Some(png) = &self.image {
+            let _ = writeln!(
+                html,
+                "<img alt=\"Final board\" src=\"data:image/png;base64,{}\">",
+                STANDARD.encode(png)
+            );
+        }
+        if !self.stats.is_empty() {
+            html.push_str("<table>\n");
+            for (name, value) in &self.stats {
+                let _ = writeln!(
+                    html,
+                    "<tr><th>{}</th><td>{}</td></tr>",
+                    escape(name),
+                    escape(value)
+                );
+            }
+            html.push_str("</table>\n");
+        }
+        for series in &self.series {
+            let _ = writeln!(html, "<h2>{}</h2>", escape(&series.name));
+            html.push_str(&chart(&series.points));
+        }
+        if let Some(command) = &self.command {
+            let _ = writeln!(html, "<h2>Reproduce</h2>\n<pre>{}</pre>", escape(command));
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    pub fn save(&self, file: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(file, self.to_html())
+    }
+}
+
+const STYLE: &str = "<style>
+body { font-family: sans-serif; max-width: 60em; margin: 2em auto; padding: 0 1em; }
+img { width: 100%; max-width: 40em; image-rendering: pixelated; border: 1px solid #ccc; }
+th { text-align: left; padding-right: 2em; }
+pre { background: #f4f4f4; padding: 1em; white-space: pre-wrap; word-break: break-all; }
+svg text { font-size: 12px; }
+</style>
+";
+
+/// Line chart of the points as inline SVG, with the ranges labeled
+fn chart(points: &[(u64, f64)]) -> String {
+    const WIDTH: f64 = 600.0;
+    const HEIGHT: f64 = 200.0;
+    const MARGIN: f64 = 60.0;
+
+    let (Some(&(x0, _)), Some(&(x1, _))) = (points.first(), points.last()) else {
+        return "<p>No samples</p>\n".to_owned();
+    };
+    let y0 = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let y1 = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+    let span_x = (x1 - x0).max(1) as f64;
+    let span_y = (y1 - y0).max(f64::EPSILON);
+
+    let line = points
+        .iter()
+        .map(|&(x, y)| {
+            let px = MARGIN + (x - x0) as f64 / span_x * (WIDTH - MARGIN);
+            let py = HEIGHT - MARGIN / 2.0 - (y - y0) / span_y * (HEIGHT - MARGIN);
+            format!("{px:.1},{py:.1}")
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut svg = String::new();
+    let (top, bottom) = (MARGIN / 2.0, HEIGHT - MARGIN / 2.0);
+    let _ = writeln!(
+        svg,
+        "<svg viewBox=\"0 0 {WIDTH} {HEIGHT}\" width=\"{WIDTH}\" height=\"{HEIGHT}\">"
+    );
+    let _ = writeln!(
+        svg,
+        "<path d=\"M{MARGIN} {top} V{bottom} H{WIDTH}\" fill=\"none\" stroke=\"#888\"/>"
+    );
+    let _ = writeln!(
+        svg,
+        "<polyline points=\"{line}\" fill=\"none\" stroke=\"#c00\" stroke-width=\"1.5\"/>"
+    );
+    let _ = writeln!(
+        svg,
+        "<text x=\"{}\" y=\"{top}\" text-anchor=\"end\">{y1}</text>",
+        MARGIN - 4.0
+    );
+    let _ = writeln!(
+        svg,
+        "<text x=\"{}\" y=\"{bottom}\" text-anchor=\"end\">{y0}</text>",
+        MARGIN - 4.0
+    );
+    let _ = writeln!(
+        svg,
+        "<text x=\"{MARGIN}\" y=\"{HEIGHT}\">{x0}</text>\n\
+         <text x=\"{WIDTH}\" y=\"{HEIGHT}\" text-anchor=\"end\">step {x1}</text>\n</svg>"
+    );
+    svg
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[test]
+fn report_is_self_contained() {
+    use crate::Map;
+
+    let mut series = Series::new("Colored cells", 10);
+    while series.next_at() < 100_000 {
+        let step = series.next_at();
+        series.push(step, step as f64 / 2.0);
+    }
+    assert!(series.points.len() < Series::MAX_POINTS);
+    assert_eq!(series.points[0], (0, 0.0));
+    assert!(series.points.windows(2).all(|w| w[0].0 < w[1].0));
+
+    let report = Report::new("LR <test>")
+        .with_map(&Map::<16, 16>::new_white(), 2, &Palette::gray(2), &[])
+        .unwrap()
+        .with_stat("Steps", 100_000)
+        .with_series(series)
+        .with_series(Series::new("Empty", 1))
+        .with_command("serious --rule LR --title 'a & b'");
+    let html = report.to_html();
+
+    assert!(html.contains("<title>LR &lt;test&gt;</title>"));
+    assert!(html.contains("src=\"data:image/png;base64,iVBORw0KGgo"));
+    assert!(html.contains("<tr><th>Steps</th><td>100000</td></tr>"));
+    assert_eq!(html.matches("<polyline").count(), 1);
+    assert!(html.contains("<p>No samples</p>"));
+    assert!(html.contains("--title 'a &amp; b'"));
+    assert!(!html.contains("http"));
+}
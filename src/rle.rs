@@ -0,0 +1,354 @@
+use std::{error::Error, fmt::Display, fmt::Write as _, str::FromStr};
+
+use crate::{CellStore, Pos};
+
+// Golly keeps RLE lines at most this long
+const LINE: usize = 70;
+
+/// Rectangle of cell colors in Golly's RLE format, the exchange format of most
+/// cellular automaton tools
+///
+/// Two-color patterns use `b` and `o`, more colors `.` and `A` to `yO`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    /// Row-major cell colors
+    pub cells: Vec<u8>,
+    /// Top left cell relative to the map center, the pattern is centered without it
+    pub pos: Option<Pos>,
+}
+
+impl Pattern {
+    /// Most cells a parsed pattern may have, 16384 squared, so a header can't ask for
+    /// more memory than a board would ever take
+    pub const MAX_CELLS: usize = 1 << 28;
+
+    /// The smallest rectangle holding every non-white cell of `map`, an empty map gives
+    /// an empty pattern
+    pub fn from_map<const W: usize, const H: usize>(map: &impl CellStore<W, H>) -> Self {
+        let cells = map.to_colors();
+        let inked = |i: &usize| cells[*i] != 0;
+        let mut rows = (0..H).filter(|&y| (0..W).map(|x| y * W + x).any(|i| inked(&i)));
+        let mut columns = (0..W).filter(|&x| (0..H).map(|y| y * W + x).any(|i| inked(&i)));
+        let (Some(top), Some(left)) = (rows.clone().next(), columns.clone().next()) else {
+            return Self {
+                width: 0,
+                height: 0,
+                cells: Vec::new(),
+                pos: None,
+            };
+        };
+        let bottom = rows.next_back().expect("there's a first row");
+        let right = columns.next_back().expect("there's a first column");
+
+        let (width, height) = (right - left + 1, bottom - top + 1);
+        let cells = (top..=bottom)
+            .flat_map(|y| cells[y * W + left..=y * W + right].iter().copied())
+            .collect();
+        let (wide, high) = map.size();
+        let center = (wide / 2, high / 2);
+        Self {
+            width,
+            height,
+            cells,
+            pos: Some(Pos::new(
+                left as isize - center.0 as isize,
+                top as isize - center.1 as isize,
+            )),
+        }
+    }
+
+    /// Paint the pattern onto a white map, cells outside of it are cut off
+    ///
+    /// # Panics
+    ///
+    /// If the pattern has more colors than the map can hold
+    pub fn to_map<const W: usize, const H: usize, M: CellStore<W, H>>(&self) -> M {
+        assert!(
+            self.colors() <= M::COLORS,
+            "pattern has {} colors but the map holds {}",
+            self.colors(),
+            M::COLORS
+        );
+
+        M::with_colors(self.to_colors(W, H))
+    }
+
+    /// Cell colors of a white `width`x`height` board with the pattern painted on the way
+    /// [`to_map`](Self::to_map) does, in row-major order
+    pub fn to_colors(&self, width: usize, height: usize) -> Vec<u8> {
+        let pos = self.pos.unwrap_or(Pos::new(
+            -(self.width as isize / 2),
+            -(self.height as isize / 2),
+        ));
+        let left = width as isize / 2 + pos.x;
+        let top = height as isize / 2 + pos.y;
+
+        let mut colors = vec![0; width * height];
+        for (i, &c) in self.cells.iter().enumerate() {
+            let x = left + (i % self.width) as isize;
+            let y = top + (i / self.width) as isize;
+            if (0..width as isize).contains(&x) && (0..height as isize).contains(&y) {
+                colors[y as usize * width + x as usize] = c;
+            }
+        }
+        colors
+    }
+
+    /// Colors the pattern needs, at least 2
+    pub fn colors(&self) -> usize {
+        self.cells
+            .iter()
+            .max()
+            .map_or(2, |&c| (c as usize + 1).max(2))
+    }
+
+    pub fn to_rle(&self) -> String {
+        let multi = self.colors() > 2;
+        let tag = |c: u8| match (multi, c) {
+            (false, 0) => "b".to_owned(),
+            (false, _) => "o".to_owned(),
+            (true, 0) => ".".to_owned(),
+            (true, c) => {
+                let (prefix, letter) = ((c - 1) / 24, (c - 1) % 24);
+                let letter = (b'A' + letter) as char;
+                match prefix {
+                    0 => letter.to_string(),
+                    p => format!("{}{letter}", (b'o' + p) as char),
+                }
+            }
+        };
+
+        // Runs of (count, tag), rows end with `$` and trailing white cells are dropped
+        let mut runs: Vec<(usize, String)> = Vec::new();
+        let push =
+            |runs: &mut Vec<(usize, String)>, count: usize, tag: String| match runs.last_mut() {
+                Some((n, last)) if *last == tag => *n += count,
+                _ => runs.push((count, tag)),
+            };
+        for (y, row) in self.cells.chunks(self.width.max(1)).enumerate() {
+            let end = row.iter().rposition(|&c| c != 0).map_or(0, |i| i + 1);
+            let mut x = 0;
+            while x < end {
+                let len = row[x..end].iter().take_while(|&&c| c == row[x]).count();
+                push(&mut runs, len, tag(row[x]));
+                x += len;
+            }
+            if y + 1 < self.height {
+                push(&mut runs, 1, "$".to_owned());
+            }
+        }
+        // Empty rows at the end don't need to be written down
+        while runs.last().is_some_and(|(_, tag)| tag == "$") {
+            runs.pop();
+        }
+
+        let mut out = String::new();
+        // Writing into a String can't fail
+        if let Some(pos) = self.pos {
+            let _ = writeln!(out, "#CXRLE Pos={},{}", pos.x, pos.y);
+        }
+        let _ = writeln!(out, "x = {}, y = {}", self.width, self.height);
+        let mut line = String::new();
+        for (count, tag) in runs.into_iter().chain([(1, "!".to_owned())]) {
+            let token = match count {
+                1 => tag,
+                n => format!("{n}{tag}"),
+            };
+            if line.len() + token.len() > LINE {
+                let _ = writeln!(out, "{line}");
+                line.clear();
+            }
+            line.push_str(&token);
+        }
+        let _ = writeln!(out, "{line}");
+        out
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = ParseRleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(str::trim).filter(|l| !l.is_empty());
+
+        let mut pos = None;
+        let header = loop {
+            let line = lines.next().ok_or(ParseRleError::Header)?;
+            let Some(comment) = line.strip_prefix('#') else {
+                break line;
+            };
+            if let Some(xrle) = comment.strip_prefix("CXRLE") {
+                pos = xrle.split_whitespace().find_map(|field| {
+                    let (x, y) = field.strip_prefix("Pos=")?.split_once(',')?;
+                    Some(Pos::new(x.parse().ok()?, y.parse().ok()?))
+                });
+            }
+        };
+
+        let mut width = None::<usize>;
+        let mut height = None;
+        for field in header.split(',') {
+            let (key, value) = field.split_once('=').ok_or(ParseRleError::Header)?;
+            let value = value.trim();
+            match key.trim() {
+                "x" => width = Some(value.parse().map_err(|_| ParseRleError::Header)?),
+                "y" => height = Some(value.parse().map_err(|_| ParseRleError::Header)?),
+                // Rules are Golly's business, the ant brings its own
+                _ => {}
+            }
+        }
+        let (Some(width), Some(height)) = (width, height) else {
+            return Err(ParseRleError::Header);
+        };
+        let cells = width.checked_mul(height);
+        let Some(cells) = cells.filter(|&cells| cells <= Pattern::MAX_CELLS) else {
+            return Err(ParseRleError::TooBig);
+        };
+
+        let mut cells = vec![0; cells];
+        let (mut x, mut y) = (0, 0);
+        let mut count = None::<usize>;
+        let mut prefix = None::<u8>;
+        let mut put = |x: &mut usize, y: usize, n: usize, color: u8| {
+            if n > width - *x || y >= height {
+                return Err(ParseRleError::Size);
+            }
+            cells[y * width + *x..y * width + *x + n].fill(color);
+            *x += n;
+            Ok(())
+        };
+
+        'body: for c in lines.flat_map(str::chars) {
+            let n = count.unwrap_or(1);
+            match c {
+                '0'..='9' => {
+                    let digit = c as usize - '0' as usize;
+                    let more = count.unwrap_or(0).checked_mul(10);
+                    // No run fits in a pattern that many cells long anyway
+                    count = Some(
+                        more.and_then(|more| more.checked_add(digit))
+                            .ok_or(ParseRleError::Size)?,
+                    );
+                    continue;
+                }
+                'p'..='y' if prefix.is_none() => {
+                    prefix = Some(c as u8 - b'o');
+                    continue;
+                }
+                'b' | '.' => put(&mut x, y, n, 0)?,
+                'o' => put(&mut x, y, n, 1)?,
+                'A'..='X' => {
+                    let color =
+                        prefix.take().unwrap_or(0) as usize * 24 + (c as u8 - b'A') as usize + 1;
+                    let color = u8::try_from(color).map_err(|_| ParseRleError::Tag(c))?;
+                    put(&mut x, y, n, color)?;
+                }
+                '$' => {
+                    x = 0;
+                    y = y.checked_add(n).ok_or(ParseRleError::Size)?;
+                }
+                '!' => break 'body,
+                c if c.is_whitespace() => continue,
+                c => return Err(ParseRleError::Tag(c)),
+            }
+            if prefix.is_some() {
+                return Err(ParseRleError::Tag(c));
+            }
+            count = None;
+        }
+
+        Ok(Self {
+            width,
+            height,
+            cells,
+            pos,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseRleError {
+    /// No `x = .., y = ..` line
+    Header,
+    Tag(char),
+    /// Cells past the size the header gave
+    Size,
+    /// The header asks for more than [`Pattern::MAX_CELLS`]
+    TooBig,
+}
+
+impl Display for ParseRleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseRleError::Header => write!(f, "expected an \"x = width, y = height\" header"),
+            ParseRleError::Tag(c) => write!(f, "unknown cell state {c:?}"),
+            ParseRleError::Size => write!(f, "pattern is bigger than its header says"),
+            ParseRleError::TooBig => write!(
+                f,
+                "pattern is over {} cells, too big to load",
+                Pattern::MAX_CELLS
+            ),
+        }
+    }
+}
+
+impl Error for ParseRleError {}
+
+#[test]
+fn rle_round_trip() {
+    use crate::{ColorMap, Map, MapPos};
+
+    let glider = "#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!\n"
+        .parse::<Pattern>()
+        .unwrap();
+    assert_eq!(glider.cells, [0, 1, 0, 0, 0, 1, 1, 1, 1]);
+    assert_eq!(glider.pos, None);
+
+    // Centered on the map when there's no position
+    let map = glider.to_map::<8, 8, Map<8, 8>>();
+    assert!(!map.get(MapPos::validate_pos(Pos::new(4, 3)).unwrap()));
+    let back = Pattern::from_map(&map);
+    assert_eq!(back.pos, Some(Pos::new(-1, -1)));
+    assert_eq!(
+        back.to_rle(),
+        "#CXRLE Pos=-1,-1\nx = 3, y = 3\nbo$2bo$3o!\n"
+    );
+
+    let colors = "x = 4, y = 3\n.A2.$$pA3B!".parse::<Pattern>().unwrap();
+    assert_eq!(colors.cells, [0, 1, 0, 0, 0, 0, 0, 0, 25, 2, 2, 2]);
+    let map = colors.to_map::<8, 8, ColorMap<8, 8>>();
+    let back = Pattern::from_map(&map);
+    assert_eq!(back.to_rle(), "#CXRLE Pos=-2,-1\nx = 4, y = 3\n.A2$pA3B!\n");
+    assert_eq!(back.to_rle().parse::<Pattern>().unwrap(), back);
+
+    assert_eq!(
+        "x = 1, y = 1\n2o!".parse::<Pattern>(),
+        Err(ParseRleError::Size)
+    );
+    assert_eq!("bo$!".parse::<Pattern>(), Err(ParseRleError::Header));
+}
+
+#[test]
+fn rle_rejects_huge_sizes() {
+    let parse = |rle: &str| rle.parse::<Pattern>();
+    assert_eq!(
+        parse("x = 100000, y = 100000\n!"),
+        Err(ParseRleError::TooBig)
+    );
+    let overflow = format!("x = {}, y = 2\n!", usize::MAX);
+    assert_eq!(parse(&overflow), Err(ParseRleError::TooBig));
+    assert_eq!(
+        parse("x = 4, y = 1\n99999999999999999999999o!"),
+        Err(ParseRleError::Size)
+    );
+    assert_eq!(
+        parse("x = 4, y = 1\n18446744073709551615o!"),
+        Err(ParseRleError::Size)
+    );
+    assert_eq!(
+        parse("x = 4, y = 1\n18446744073709551615$o!"),
+        Err(ParseRleError::Size)
+    );
+}
@@ -0,0 +1,320 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use rand::Rng;
+
+/// Which way the ant turns before stepping off a cell
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Turn {
+    /// Counterclockwise
+    Left = 0,
+    /// Clockwise
+    Right = 1,
+    /// Turn around, `U` in rules
+    UTurn = 2,
+    /// Go straight on, `N` in rules
+    None = 3,
+}
+
+impl Turn {
+    /// The turn of the mirror image of a walk, left and right swap
+    pub const fn mirrored(self) -> Self {
+        match self {
+            Turn::Left => Turn::Right,
+            Turn::Right => Turn::Left,
+            turn => turn,
+        }
+    }
+
+    /// The turn that takes the ant back to where it was facing
+    pub const fn inverse(self) -> Self {
+        self.mirrored()
+    }
+}
+
+/// Turmite rule in the usual letter notation, e.g. `"RL"`, `"LLRR"` or `"RLU"`, where
+/// `U` turns the ant around and `N` lets it go straight on
+///
+/// A cell of color `i` makes the ant turn by the `i`-th letter
+/// and then repaints the cell with color `i + 1`, wrapping around to `0`.
+/// Color `0` is white.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Rule(Vec<Turn>);
+
+impl Rule {
+    /// Most colors a rule can cycle through, cells store colors as `u8`
+    pub const MAX_COLORS: usize = u8::MAX as usize + 1;
+
+    pub fn new(turns: Vec<Turn>) -> Result<Self, ParseRuleError> {
+        match turns.len() {
+            2..=Self::MAX_COLORS => Ok(Self(turns)),
+            len => Err(ParseRuleError::Length(len)),
+        }
+    }
+
+    /// Number of colors cells cycle through
+    pub fn colors(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn turns(&self) -> &[Turn] {
+        &self.0
+    }
+
+    pub fn turn(&self, color: u8) -> Turn {
+        self.0[color as usize]
+    }
+
+    pub fn next_color(&self, color: u8) -> u8 {
+        ((color as usize + 1) % self.0.len()) as u8
+    }
+}
+
+impl Default for Rule {
+    /// The walk this crate always did: left on white, right on black
+    ///
+    /// Since rows grow downwards this is the mirror image of the textbook `RL`
+    fn default() -> Self {
+        Self(vec![Turn::Left, Turn::Right])
+    }
+}
+
+impl FromStr for Rule {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let turns = s
+            .chars()
+            .map(|c| match c.to_ascii_uppercase() {
+                'L' => Ok(Turn::Left),
+                'R' => Ok(Turn::Right),
+                'U' => Ok(Turn::UTurn),
+                'N' => Ok(Turn::None),
+                _ => Err(ParseRuleError::Letter(c)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Self::new(turns)
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for turn in &self.0 {
+            let letter = match turn {
+                Turn::Left => 'L',
+                Turn::Right => 'R',
+                Turn::UTurn => 'U',
+                Turn::None => 'N',
+            };
+            write!(f, "{letter}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Rule where every color turns the ant right with some chance and left otherwise,
+/// for walks under noise
+///
+/// Written as comma separated turns, a letter for a sure one or a letter and the
+/// percent chance of taking that turn, e.g. `"L,R90"` turns left on white and right on
+/// black 9 times out of 10. Plain rules like `"LR"` are stochastic rules that never
+/// roll the dice
+#[derive(Clone, PartialEq, Debug)]
+pub struct StochasticRule(Vec<f64>);
+
+impl StochasticRule {
+    /// Rule with a chance of turning right for every color, each in `0.0..=1.0`
+    pub fn new(chances: Vec<f64>) -> Result<Self, ParseRuleError> {
+        if let Some(&chance) = chances.iter().find(|c| !(0.0..=1.0).contains(*c)) {
+            return Err(ParseRuleError::Chance(chance.to_string()));
+        }
+        match chances.len() {
+            2..=Rule::MAX_COLORS => Ok(Self(chances)),
+            len => Err(ParseRuleError::Length(len)),
+        }
+    }
+
+    pub fn colors(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Chance of a right turn on every color
+    pub fn chances(&self) -> &[f64] {
+        &self.0
+    }
+
+    /// The plain rule of the likelier turns, right on even odds
+    pub fn likely_rule(&self) -> Rule {
+        let turns = self.0.iter().map(|&chance| match chance >= 0.5 {
+            true => Turn::Right,
+            false => Turn::Left,
+        });
+        Rule(turns.collect())
+    }
+
+    /// Draw the turn on `color`, sure turns don't touch `rng`
+    pub fn turn(&self, color: u8, rng: &mut impl Rng) -> Turn {
+        match self.0[color as usize] {
+            chance if chance <= 0.0 => Turn::Left,
+            chance if chance >= 1.0 => Turn::Right,
+            chance => match rng.gen_bool(chance) {
+                true => Turn::Right,
+                false => Turn::Left,
+            },
+        }
+    }
+}
+
+/// Sure left and right turns, there's no chance of a U-turn or going straight
+impl TryFrom<&Rule> for StochasticRule {
+    type Error = ParseRuleError;
+
+    fn try_from(rule: &Rule) -> Result<Self, Self::Error> {
+        let chances = rule.0.iter().map(|&turn| match turn {
+            Turn::Left => Ok(0.0),
+            Turn::Right => Ok(1.0),
+            turn => Err(ParseRuleError::Chance(Rule(vec![turn]).to_string())),
+        });
+        Ok(Self(chances.collect::<Result<_, _>>()?))
+    }
+}
+
+impl FromStr for StochasticRule {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.contains(',') {
+            return Self::try_from(&s.parse::<Rule>()?);
+        }
+        let chances = s
+            .split(',')
+            .map(|turn| {
+                let turn = turn.trim();
+                let mut chars = turn.chars();
+                let letter = chars.next().ok_or(ParseRuleError::Chance(String::new()))?;
+                let percent = match chars.as_str().trim_end_matches('%') {
+                    "" => 100.0,
+                    percent => percent
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|percent| (0.0..=100.0).contains(percent))
+                        .ok_or_else(|| ParseRuleError::Chance(turn.to_owned()))?,
+                };
+                match letter.to_ascii_uppercase() {
+                    'R' => Ok(percent / 100.0),
+                    'L' => Ok(1.0 - percent / 100.0),
+                    'U' | 'N' => Err(ParseRuleError::Chance(turn.to_owned())),
+                    _ => Err(ParseRuleError::Letter(letter)),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Self::new(chances)
+    }
+}
+
+impl Display for StochasticRule {
+    /// Sure turns as letters, others as the likelier one with its chance
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Two decimals of a percent, 0.9 * 100.0 isn't quite 90
+        let percent = |chance: f64| (chance * 10_000.0).round() / 100.0;
+        for (i, &chance) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            match chance {
+                1.0 => write!(f, "R")?,
+                0.0 => write!(f, "L")?,
+                chance if chance >= 0.5 => write!(f, "R{}", percent(chance))?,
+                chance => write!(f, "L{}", percent(1.0 - chance))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseRuleError {
+    Letter(char),
+    Length(usize),
+    /// A turn of a [`StochasticRule`] with a chance that isn't a percent
+    Chance(String),
+}
+
+impl Display for ParseRuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseRuleError::Letter(c) => write!(f, "unknown turn {c:?}, expected L, R, U or N"),
+            ParseRuleError::Length(len) => write!(
+                f,
+                "rule has {len} colors, expected 2 to {}",
+                Rule::MAX_COLORS
+            ),
+            ParseRuleError::Chance(turn) => {
+                write!(
+                    f,
+                    "bad turn {turn:?}, expected L or R and a percent like R90"
+                )
+            }
+        }
+    }
+}
+
+impl Error for ParseRuleError {}
+
+#[test]
+fn parse_rules() {
+    let rule = "llRR".parse::<Rule>().unwrap();
+    assert_eq!(rule.colors(), 4);
+    assert_eq!(rule.turn(2), Turn::Right);
+    assert_eq!(rule.next_color(3), 0);
+    assert_eq!(rule.to_string(), "LLRR");
+    let rule = "rlun".parse::<Rule>().unwrap();
+    assert_eq!(
+        rule.turns(),
+        [Turn::Right, Turn::Left, Turn::UTurn, Turn::None]
+    );
+    assert_eq!(rule.to_string(), "RLUN");
+
+    assert_eq!("RXL".parse::<Rule>(), Err(ParseRuleError::Letter('X')));
+    assert_eq!("R".parse::<Rule>(), Err(ParseRuleError::Length(1)));
+    assert_eq!(Rule::default().to_string(), "LR");
+}
+
+#[test]
+fn parse_stochastic_rules() {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    let rule = "L, r90".parse::<StochasticRule>().unwrap();
+    assert_eq!(rule.chances(), [0.0, 0.9]);
+    assert_eq!(rule.to_string(), "L,R90");
+    assert_eq!(rule.likely_rule(), Rule::default());
+    assert_eq!(
+        "L25,R".parse::<StochasticRule>().unwrap().chances(),
+        [0.75, 1.0]
+    );
+    assert_eq!(
+        "LLR".parse::<StochasticRule>().unwrap(),
+        StochasticRule::try_from(&"LLR".parse::<Rule>().unwrap()).unwrap()
+    );
+    assert_eq!(
+        "LRU".parse::<StochasticRule>(),
+        Err(ParseRuleError::Chance("U".into()))
+    );
+    assert!(matches!(
+        "L,R120".parse::<StochasticRule>(),
+        Err(ParseRuleError::Chance(_))
+    ));
+    assert_eq!(
+        "L,X50".parse::<StochasticRule>(),
+        Err(ParseRuleError::Letter('X'))
+    );
+
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
+    let rights = (0..1000)
+        .filter(|_| rule.turn(1, &mut rng) == Turn::Right)
+        .count();
+    assert!((850..950).contains(&rights), "{rights} right turns");
+    assert_eq!(rule.turn(0, &mut rng), Turn::Left);
+}
@@ -0,0 +1,230 @@
+use std::{error::Error, fmt::Display};
+
+use wasmi::{core::Pages, Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::{PluginDecision, Rule, TurmiteError, TurmiteRule};
+
+// Bytes of a decision in the module's memory
+const RECORD: usize = 8;
+// Bytes of a WebAssembly memory page
+const PAGE: usize = 1 << 16;
+// Most transitions a module may declare, far more than any spec file lists
+const MAX_TRANSITIONS: usize = 1 << 16;
+
+/// Runs turmites shared as WebAssembly modules, with nothing to reach outside the
+/// module and only so much fuel and memory, so rules from anyone are safe to walk
+///
+/// A module exports its `memory`, `states` and `colors`, each `() -> i32`, and
+/// `decide(ptr: i32, len: i32) -> i32`. Instead of a call for every state and color
+/// the sandbox writes them all at `ptr` in one batch, `len` records of 8 bytes,
+/// little-endian as WebAssembly is: the state as a `u32` and the color as a `u32`.
+/// `decide` rewrites each record in place with what the turmite does, the next state
+/// as a `u32`, the color to write as a `u8` and the action, a turn or a direction to
+/// face coded like [`PluginDecision::action`], as a `u8`, and returns zero. Modules
+/// import nothing and the records are written past the end of the memory the module
+/// had, so a rule in hand-written WAT is a few lines
+///
+/// The module runs once when loading, the turmite walks from the table it filled
+#[derive(Clone, Debug)]
+pub struct Sandbox {
+    /// Instructions the module may run, about one fuel each
+    pub fuel: u64,
+    /// Bytes the module's memory may grow to
+    pub memory: usize,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self {
+            fuel: 10_000_000,
+            memory: 16 << 20,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SandboxError {
+    /// The module is text that doesn't parse
+    Text(String),
+    /// The module didn't compile or load, trapped or ran out of fuel or memory
+    Wasm(wasmi::Error),
+    /// A function or the memory is missing or has the wrong type
+    Export(&'static str),
+    /// More states or colors than a turmite can have
+    Size {
+        states: i32,
+        colors: i32,
+    },
+    /// `decide` returned something else than zero
+    Decide(i32),
+    /// A record `decide` wrote isn't a transition
+    Decision {
+        state: usize,
+        color: u8,
+    },
+    Rule(TurmiteError),
+}
+
+impl Display for SandboxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SandboxError::Text(e) => write!(f, "{e}"),
+            SandboxError::Wasm(e) => write!(f, "{e}"),
+            SandboxError::Export(name) => write!(f, "module exports no usable {name}"),
+            SandboxError::Size { states, colors } => {
+                write!(f, "{states} states and {colors} colors is too many")
+            }
+            SandboxError::Decide(status) => write!(f, "decide failed with {status}"),
+            SandboxError::Decision { state, color } => {
+                write!(f, "no transition for state {state} on color {color}")
+            }
+            SandboxError::Rule(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for SandboxError {}
+
+impl From<wasmi::Error> for SandboxError {
+    fn from(e: wasmi::Error) -> Self {
+        SandboxError::Wasm(e)
+    }
+}
+
+impl From<TurmiteError> for SandboxError {
+    fn from(e: TurmiteError) -> Self {
+        SandboxError::Rule(e)
+    }
+}
+
+impl Sandbox {
+    /// The turmite of the module `wasm`, binary or text
+    pub fn rule(&self, wasm: &[u8]) -> Result<TurmiteRule, SandboxError> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let mut store = Store::new(
+            &engine,
+            StoreLimitsBuilder::new().memory_size(self.memory).build(),
+        );
+        store.limiter(|limits: &mut StoreLimits| limits);
+        store.set_fuel(self.fuel).expect("fuel is on");
+
+        let wasm = wat::parse_bytes(wasm).map_err(|e| SandboxError::Text(e.to_string()))?;
+        let module = Module::new(&engine, &wasm)?;
+        // Nothing is linked in, a module importing anything doesn't load
+        let instance = Linker::new(&engine)
+            .instantiate(&mut store, &module)?
+            .start(&mut store)?;
+        let count = |store: &mut Store<_>, name| {
+            instance
+                .get_typed_func::<(), i32>(&*store, name)
+                .map_err(|_| SandboxError::Export(name))?
+                .call(store, ())
+                .map_err(SandboxError::Wasm)
+        };
+        let (states, colors) = (count(&mut store, "states")?, count(&mut store, "colors")?);
+        let too_many = SandboxError::Size { states, colors };
+        let (Ok(states @ 1..), Ok(colors @ 2..=Rule::MAX_COLORS)) =
+            (usize::try_from(states), usize::try_from(colors))
+        else {
+            return Err(too_many);
+        };
+        if states * colors > MAX_TRANSITIONS {
+            return Err(too_many);
+        }
+
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or(SandboxError::Export("memory"))?;
+        let len = states * colors;
+        let ptr = memory.data(&store).len();
+        let pages = Pages::new((len * RECORD).div_ceil(PAGE) as u32)
+            .ok_or(SandboxError::Export("memory"))?;
+        memory
+            .grow(&mut store, pages)
+            .map_err(|e| SandboxError::Wasm(e.into()))?;
+        let records = &mut memory.data_mut(&mut store)[ptr..ptr + len * RECORD];
+        for (i, record) in records.chunks_exact_mut(RECORD).enumerate() {
+            record[..4].copy_from_slice(&((i / colors) as u32).to_le_bytes());
+            record[4..].copy_from_slice(&((i % colors) as u32).to_le_bytes());
+        }
+
+        let status = instance
+            .get_typed_func::<(i32, i32), i32>(&store, "decide")
+            .map_err(|_| SandboxError::Export("decide"))?
+            .call(&mut store, (ptr as i32, len as i32))?;
+        if status != 0 {
+            return Err(SandboxError::Decide(status));
+        }
+
+        let records = &memory.data(&store)[ptr..ptr + len * RECORD];
+        let transitions = records
+            .chunks_exact(RECORD)
+            .enumerate()
+            .map(|(i, record)| {
+                let (state, color) = (i / colors, (i % colors) as u8);
+                let decision = PluginDecision {
+                    write: record[4],
+                    action: record[5],
+                    next: u32::from_le_bytes(record[..4].try_into().expect("4 bytes")),
+                };
+                let transition = decision
+                    .transition()
+                    .ok_or(SandboxError::Decision { state, color })?;
+                Ok((state, color, transition))
+            })
+            .collect::<Result<Vec<_>, SandboxError>>()?;
+        Ok(TurmiteRule::new(states, colors, transitions)?)
+    }
+}
+
+#[test]
+fn modules_decide_in_a_batch() {
+    // Langton's ant, left on white and right on black, flipping the color
+    let langton = r#"(module
+        (memory (export "memory") 1)
+        (func (export "states") (result i32) i32.const 1)
+        (func (export "colors") (result i32) i32.const 2)
+        (func (export "decide") (param $ptr i32) (param $len i32) (result i32)
+            (local $color i32)
+            (loop $next
+                (local.set $color (i32.load offset=4 (local.get $ptr)))
+                (i32.store (local.get $ptr) (i32.const 0))
+                (i32.store8 offset=4 (local.get $ptr) (i32.sub (i32.const 1) (local.get $color)))
+                (i32.store8 offset=5 (local.get $ptr)
+                    (select (i32.const 3) (i32.const 1) (i32.eqz (local.get $color))))
+                (local.set $ptr (i32.add (local.get $ptr) (i32.const 8)))
+                (br_if $next (local.tee $len (i32.sub (local.get $len) (i32.const 1)))))
+            (i32.const 0)))"#;
+    let sandbox = Sandbox::default();
+    let rule = sandbox.rule(langton.as_bytes()).unwrap();
+    assert_eq!(
+        rule.transition(0, 0),
+        TurmiteRule::langton().transition(0, 0)
+    );
+    assert_eq!(
+        rule.transition(0, 1),
+        TurmiteRule::langton().transition(0, 1)
+    );
+
+    // Rules looping forever run out of fuel, ones reaching out don't load at all
+    let spinning = langton.replace("(i32.const 0)))", "(loop $ever (br $ever)) (i32.const 0)))");
+    assert!(matches!(
+        sandbox.rule(spinning.as_bytes()),
+        Err(SandboxError::Wasm(_))
+    ));
+    let reaching = langton.replace(
+        "(memory",
+        r#"(import "env" "open" (func (param i32))) (memory"#,
+    );
+    assert!(matches!(
+        sandbox.rule(reaching.as_bytes()),
+        Err(SandboxError::Wasm(_))
+    ));
+    let greedy = langton.replace("i32.const 2)", "i32.const 300)");
+    assert!(matches!(
+        sandbox.rule(greedy.as_bytes()),
+        Err(SandboxError::Size { .. })
+    ));
+}
@@ -0,0 +1,300 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use flate2::{write::GzEncoder, Compression};
+
+use crate::{render::cropped_colors, CellStore};
+
+// Minecraft 1.20.1, older versions upgrade blocks on load
+const DATA_VERSION: i32 = 3465;
+
+/// Minecraft structure files a [`Schematic`] can be written as
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SchematicFormat {
+    /// Sponge schematic v2, loaded by WorldEdit and FAWE
+    Sponge,
+    /// Litematica mod schematic
+    Litematic,
+}
+
+impl SchematicFormat {
+    /// Guess the format from the file extension
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "schem" => Some(Self::Sponge),
+            "litematic" => Some(Self::Litematic),
+            _ => None,
+        }
+    }
+}
+
+/// Board laid flat as a single layer of blocks, x goes east and board rows go south
+#[derive(Clone, Debug)]
+pub struct Schematic {
+    /// Block for every cell color, `minecraft:` is assumed for names without a namespace
+    pub blocks: Vec<String>,
+    /// Name shown by Litematica
+    pub name: String,
+}
+
+impl Default for Schematic {
+    fn default() -> Self {
+        let wool = [
+            "white",
+            "black",
+            "gray",
+            "light_gray",
+            "red",
+            "orange",
+            "yellow",
+            "lime",
+            "green",
+            "cyan",
+            "light_blue",
+            "blue",
+            "purple",
+            "magenta",
+            "pink",
+            "brown",
+        ];
+        Self {
+            blocks: wool.map(|color| format!("{color}_wool")).to_vec(),
+            name: "antventure".to_owned(),
+        }
+    }
+}
+
+impl Schematic {
+    /// Write `map` to `file`, fails if there are fewer blocks than colors
+    pub fn save<const W: usize, const H: usize>(
+        &self,
+        map: &impl CellStore<W, H>,
+        colors: usize,
+        format: SchematicFormat,
+        file: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        if colors > self.blocks.len() {
+            let msg = format!("{colors} colors but only {} blocks", self.blocks.len());
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+        }
+        let too_big = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "schematics are at most 32767 wide",
+            )
+        };
+        // A single layer, so row-major cells are already in x + z * width order
+        let (cells, wide, long) = cropped_colors(map, None);
+        let width = i16::try_from(wide).map_err(|_| too_big())?;
+        let length = i16::try_from(long).map_err(|_| too_big())?;
+
+        let blocks = self.blocks[..colors.max(1)]
+            .iter()
+            .map(|block| match block.contains(':') {
+                true => block.clone(),
+                false => format!("minecraft:{block}"),
+            })
+            .collect::<Vec<_>>();
+
+        let mut nbt = Nbt::default();
+        match format {
+            SchematicFormat::Sponge => {
+                nbt.compound("Schematic");
+                nbt.int("Version", 2);
+                nbt.int("DataVersion", DATA_VERSION);
+                nbt.short("Width", width);
+                nbt.short("Height", 1);
+                nbt.short("Length", length);
+                nbt.int("PaletteMax", blocks.len() as i32);
+                nbt.compound("Palette");
+                for (i, block) in blocks.iter().enumerate() {
+                    nbt.int(block, i as i32);
+                }
+                nbt.end();
+                // Palette indices as varints
+                let mut data = Vec::with_capacity(cells.len());
+                for &c in &cells {
+                    let mut c = c as u32;
+                    while c >= 0x80 {
+                        data.push(c as u8 | 0x80);
+                        c >>= 7;
+                    }
+                    data.push(c as u8);
+                }
+                nbt.byte_array("BlockData", &data);
+                nbt.end();
+            }
+            SchematicFormat::Litematic => {
+                let size = |nbt: &mut Nbt, name| {
+                    nbt.compound(name);
+                    nbt.int("x", wide as i32);
+                    nbt.int("y", 1);
+                    nbt.int("z", long as i32);
+                    nbt.end();
+                };
+                let volume = (wide * long) as i32;
+
+                nbt.compound("");
+                nbt.int("Version", 6);
+                nbt.int("MinecraftDataVersion", DATA_VERSION);
+                nbt.compound("Metadata");
+                nbt.string("Name", &self.name);
+                nbt.string("Author", "antventure");
+                nbt.string("Description", "");
+                nbt.int("RegionCount", 1);
+                nbt.int("TotalBlocks", volume);
+                nbt.int("TotalVolume", volume);
+                size(&mut nbt, "EnclosingSize");
+                nbt.end();
+
+                nbt.compound("Regions");
+                nbt.compound(&self.name);
+                nbt.compound("Position");
+                for axis in ["x", "y", "z"] {
+                    nbt.int(axis, 0);
+                }
+                nbt.end();
+                size(&mut nbt, "Size");
+                // Litematica keeps air at index 0
+                nbt.compound_list("BlockStatePalette", blocks.len() + 1);
+                for block in ["minecraft:air"]
+                    .into_iter()
+                    .chain(blocks.iter().map(String::as_str))
+                {
+                    nbt.string("Name", block);
+                    nbt.end();
+                }
+                let bits = usize::BITS - blocks.len().leading_zeros();
+                let indices = cells.iter().map(|&c| c as u64 + 1);
+                nbt.long_array("BlockStates", &pack_bits(indices, bits.max(2)));
+                for list in [
+                    "Entities",
+                    "TileEntities",
+                    "PendingBlockTicks",
+                    "PendingFluidTicks",
+                ] {
+                    nbt.compound_list(list, 0);
+                }
+                nbt.end();
+                nbt.end();
+                nbt.end();
+            }
+        }
+
+        let mut w = GzEncoder::new(BufWriter::new(File::create(file)?), Compression::default());
+        w.write_all(&nbt.0)?;
+        w.finish()?.flush()
+    }
+}
+
+/// Pack `bits` wide values back to back into longs, values may straddle two longs
+pub(crate) fn pack_bits(values: impl ExactSizeIterator<Item = u64>, bits: u32) -> Vec<i64> {
+    let mut longs = vec![0u64; (values.len() * bits as usize).div_ceil(64)];
+    for (i, value) in values.enumerate() {
+        let start = i * bits as usize;
+        let (word, offset) = (start / 64, start % 64);
+        longs[word] |= value << offset;
+        if offset + bits as usize > 64 {
+            longs[word + 1] |= value >> (64 - offset);
+        }
+    }
+    longs.into_iter().map(|l| l as i64).collect()
+}
+
+/// Uncompressed big-endian NBT, compounds and lists are closed with `end`
+#[derive(Default)]
+struct Nbt(Vec<u8>);
+
+impl Nbt {
+    fn tag(&mut self, id: u8, name: &str) {
+        self.0.push(id);
+        self.0.extend((name.len() as u16).to_be_bytes());
+        self.0.extend(name.as_bytes());
+    }
+
+    fn end(&mut self) {
+        self.0.push(0);
+    }
+
+    fn compound(&mut self, name: &str) {
+        self.tag(10, name);
+    }
+
+    /// List of `len` compounds, each one is written as bare fields and an `end`
+    fn compound_list(&mut self, name: &str, len: usize) {
+        self.tag(9, name);
+        self.0.push(10);
+        self.0.extend((len as i32).to_be_bytes());
+    }
+
+    fn short(&mut self, name: &str, value: i16) {
+        self.tag(2, name);
+        self.0.extend(value.to_be_bytes());
+    }
+
+    fn int(&mut self, name: &str, value: i32) {
+        self.tag(3, name);
+        self.0.extend(value.to_be_bytes());
+    }
+
+    fn byte_array(&mut self, name: &str, bytes: &[u8]) {
+        self.tag(7, name);
+        self.0.extend((bytes.len() as i32).to_be_bytes());
+        self.0.extend(bytes);
+    }
+
+    fn string(&mut self, name: &str, value: &str) {
+        self.tag(8, name);
+        self.0.extend((value.len() as u16).to_be_bytes());
+        self.0.extend(value.as_bytes());
+    }
+
+    fn long_array(&mut self, name: &str, longs: &[i64]) {
+        self.tag(12, name);
+        self.0.extend((longs.len() as i32).to_be_bytes());
+        for long in longs {
+            self.0.extend(long.to_be_bytes());
+        }
+    }
+}
+
+#[test]
+fn packs_bits_across_longs() {
+    // 3 bit values, the 22nd one starts at bit 63
+    let longs = pack_bits([5u64; 22].into_iter(), 3);
+    assert_eq!(longs.len(), 2);
+    assert_eq!(
+        longs[0] as u64,
+        (0..21).fold(0, |l, i| l | 5 << (i * 3)) | 1 << 63
+    );
+    assert_eq!(longs[1], 2);
+}
+
+#[test]
+fn sponge_schematic_layout() {
+    use crate::{Map, MapPos, Pos};
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    let mut map = Map::<3, 2>::new_white();
+    map.set(MapPos::validate_pos(Pos::new(2, 1)).unwrap(), false);
+
+    let file = std::env::temp_dir().join("antventure_sponge_schematic_layout.schem");
+    Schematic::default()
+        .save(&map, 2, SchematicFormat::Sponge, &file)
+        .unwrap();
+    let mut nbt = Vec::new();
+    GzDecoder::new(File::open(&file).unwrap())
+        .read_to_end(&mut nbt)
+        .unwrap();
+    std::fs::remove_file(&file).ok();
+
+    assert_eq!(nbt[..12], *b"\x0a\x00\x09Schematic");
+    let palette = b"\x03\x00\x14minecraft:black_wool\x00\x00\x00\x01";
+    assert!(nbt.windows(palette.len()).any(|w| w == palette));
+    assert!(nbt.ends_with(b"BlockData\x00\x00\x00\x06\x00\x00\x00\x00\x00\x01\x00"));
+}
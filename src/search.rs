@@ -0,0 +1,208 @@
+use std::{cmp::Reverse, fmt::Display, str::FromStr};
+
+use crate::{
+    ColorMap, Direction, Periodicity, PhaseDetector, PhaseEvent, Pos, Rule, Simulation,
+    SweepExecutor, Turn, WorkerStats,
+};
+
+/// What makes a rule interesting, see [`RuleSearch`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Metric {
+    /// Most cells left colored
+    #[default]
+    Colored,
+    /// Largest area the ant walked over
+    Growth,
+    /// Quickest to settle into a highway
+    Highway,
+}
+
+impl Metric {
+    /// Sort `results` from the most interesting down
+    pub fn rank(self, results: &mut [SearchResult]) {
+        match self {
+            Metric::Colored => results.sort_by_key(|r| Reverse(r.colored)),
+            Metric::Growth => results.sort_by_key(|r| Reverse(r.area())),
+            Metric::Highway => {
+                results.sort_by_key(|r| r.highway.map_or(u64::MAX, |h| h.detected_at))
+            }
+        }
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "colored" => Ok(Metric::Colored),
+            "growth" => Ok(Metric::Growth),
+            "highway" => Ok(Metric::Highway),
+            _ => Err(format!(
+                "unknown metric {s:?}, expected colored, growth or highway"
+            )),
+        }
+    }
+}
+
+impl Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Metric::Colored => "colored",
+            Metric::Growth => "growth",
+            Metric::Highway => "highway",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// How a single rule of a [`RuleSearch`] went
+#[derive(Clone, PartialEq, Debug)]
+pub struct SearchResult {
+    pub rule: Rule,
+    pub steps: u64,
+    /// Cells of any color but white at the end
+    pub colored: usize,
+    /// Smallest rectangle holding every cell the ant stepped off, as inclusive corners
+    pub bounds: Option<(Pos, Pos)>,
+    /// First highway the ant built, if any
+    pub highway: Option<Periodicity>,
+    pub left_map: bool,
+}
+
+impl SearchResult {
+    /// Cells in [`bounds`](Self::bounds)
+    pub fn area(&self) -> usize {
+        self.bounds.map_or(0, |(min, max)| {
+            ((max.x - min.x + 1) * (max.y - min.y + 1)) as usize
+        })
+    }
+}
+
+/// Every rule of `L` and `R` from two up to `max_len` letters, shortest first
+pub fn rules_up_to(max_len: usize) -> impl Iterator<Item = Rule> {
+    (2..=max_len.min(u64::BITS as usize - 1)).flat_map(|len| {
+        (0..1u64 << len).map(move |bits| {
+            let turns = (0..len)
+                .map(|i| match bits >> (len - 1 - i) & 1 {
+                    0 => Turn::Left,
+                    _ => Turn::Right,
+                })
+                .collect();
+            Rule::new(turns).expect("two letters or more")
+        })
+    })
+}
+
+/// Walks a single ant from the center of a white `S`x`S` map for every rule,
+/// spread over a [`SweepExecutor`], so the interesting ones can be picked out
+///
+/// ```
+/// use antventure::{rules_up_to, Metric, RuleSearch};
+///
+/// let search = RuleSearch::<64>::new(2000);
+/// let (mut results, _) = search.run(rules_up_to(3).collect());
+/// Metric::Colored.rank(&mut results);
+/// assert_eq!(results.len(), 4 + 8);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RuleSearch<const S: usize> {
+    steps: u64,
+    window: usize,
+    executor: SweepExecutor,
+}
+
+impl<const S: usize> RuleSearch<S> {
+    /// Walk each ant `steps` steps, or until it leaves the map
+    pub fn new(steps: u64) -> Self {
+        Self {
+            steps,
+            window: 1024,
+            executor: SweepExecutor::new(),
+        }
+    }
+
+    /// [`CycleDetector`](crate::CycleDetector) window used to spot highways
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn with_executor(mut self, executor: SweepExecutor) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// Simulate every rule, results come in the order of `rules`
+    pub fn run(&self, rules: Vec<Rule>) -> (Vec<SearchResult>, Vec<WorkerStats>) {
+        let jobs = rules
+            .into_iter()
+            .map(|rule| {
+                move || {
+                    let (result, _) = self.simulate(rule);
+                    let steps = result.steps;
+                    (result, steps)
+                }
+            })
+            .collect();
+        self.executor.run(jobs)
+    }
+
+    /// Simulate a single rule, also returns the final map so the runs worth a look
+    /// can be rendered without keeping every map of a search around
+    pub fn simulate(&self, rule: Rule) -> (SearchResult, ColorMap<S, S>) {
+        let colors = rule.colors();
+        let mut sim = Simulation::new(ColorMap::<S, S>::new_white(), rule.clone());
+        let center = Pos::new(S as isize / 2, S as isize / 2);
+        sim.spawn(center, Direction::North)
+            .expect("the center is on the map");
+
+        let mut detector = PhaseDetector::new(S, S, self.window);
+        let mut highway = None;
+        while sim.steps() < self.steps {
+            match sim.run_watching(self.steps - sim.steps(), &mut detector) {
+                Some(PhaseEvent::Highway(periodicity)) => {
+                    highway.get_or_insert(periodicity);
+                }
+                Some(_) => {}
+                None => break,
+            }
+        }
+
+        let result = SearchResult {
+            rule,
+            steps: sim.steps(),
+            colored: S * S - sim.map().count_colors(colors)[0],
+            bounds: detector.bounding_box(),
+            highway,
+            left_map: !sim.ants()[0].is_on_map(),
+        };
+        (result, sim.into_map())
+    }
+}
+
+#[test]
+fn search_ranks_rules() {
+    let rules = rules_up_to(3).collect::<Vec<_>>();
+    assert_eq!(rules.len(), 12);
+    assert_eq!(rules[1].to_string(), "LR");
+    assert_eq!(rules[11].to_string(), "RRR");
+
+    let search = RuleSearch::<128>::new(20_000).with_executor(SweepExecutor::new().workers(2));
+    let (mut results, stats) = search.run(rules);
+    assert_eq!(stats.iter().map(|s| s.jobs).sum::<usize>(), 12);
+
+    // Turning the same way every time walks a 2x2 square forever
+    let same = results.iter().find(|r| r.rule.to_string() == "LL").unwrap();
+    assert_eq!((same.area(), same.left_map), (4, false));
+
+    Metric::Highway.rank(&mut results);
+    let first = &results[0];
+    assert!(["LLR", "RRL"].contains(&first.rule.to_string().as_str()));
+    assert!(first.highway.unwrap().is_highway());
+    assert_eq!(results[2].highway, None);
+
+    let (again, map) = search.simulate(first.rule.clone());
+    assert_eq!(&again, first);
+    assert_eq!(128 * 128 - map.count_colors(3)[0], first.colored);
+}
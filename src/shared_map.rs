@@ -0,0 +1,178 @@
+use std::{
+    ops::Deref,
+    sync::{Arc, Mutex},
+};
+
+use crate::{CellStore, Observer, Simulation};
+
+/// Copies the map of a running simulation out every so many steps, for viewers and
+/// stats threads to read through [`MapReadHandle`]s while it walks on
+///
+/// Two copies take turns: readers get the last one published, the other is written
+/// over by the next publish, unless a reader still holds it
+///
+/// ```
+/// use antventure::{Direction, Map, MapPublisher, Pos, Simulation};
+///
+/// let publisher = MapPublisher::new(100);
+/// let handle = publisher.handle();
+/// let mut sim = Simulation::new(Map::<64, 64>::new_white(), Default::default())
+///     .with_observer(publisher);
+/// sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+///
+/// let reader = std::thread::spawn(move || handle.try_load().map(|map| map.step()));
+/// sim.run_at_most(1000);
+/// assert!(reader.join().unwrap().is_none_or(|step| step <= 1000));
+/// ```
+pub struct MapPublisher<M> {
+    every: u64,
+    latest: Shared<M>,
+    /// The snapshot published before the latest, written over next if nobody holds it
+    spare: Option<Arc<MapSnapshot<M>>>,
+}
+
+type Shared<M> = Arc<Mutex<Option<Arc<MapSnapshot<M>>>>>;
+
+impl<M> MapPublisher<M> {
+    /// # Panics
+    ///
+    /// If `every` is zero
+    pub fn new(every: u64) -> Self {
+        assert!(every > 0, "maps are published every step at most");
+        Self {
+            every,
+            latest: Arc::new(Mutex::new(None)),
+            spare: None,
+        }
+    }
+
+    pub fn handle(&self) -> MapReadHandle<M> {
+        MapReadHandle {
+            latest: self.latest.clone(),
+        }
+    }
+}
+
+impl<M: Clone> MapPublisher<M> {
+    /// Publish `map` as it is at `step`, readers see it from their next load
+    pub fn publish(&mut self, map: &M, step: u64) {
+        let snapshot = match self.spare.take().map(Arc::try_unwrap) {
+            Some(Ok(mut snapshot)) => {
+                snapshot.map.clone_from(map);
+                snapshot.step = step;
+                snapshot
+            }
+            _ => MapSnapshot {
+                map: map.clone(),
+                step,
+            },
+        };
+        let mut latest = self.latest.lock().expect("not poisoned");
+        self.spare = latest.replace(Arc::new(snapshot));
+    }
+}
+
+impl<const W: usize, const H: usize, M: CellStore<W, H> + Clone> Observer<W, H, M>
+    for MapPublisher<M>
+{
+    fn on_step(&mut self, sim: &Simulation<W, H, M>) {
+        if sim.steps().is_multiple_of(self.every) {
+            self.publish(sim.map(), sim.steps());
+        }
+    }
+
+    fn on_rule_change(&mut self, sim: &Simulation<W, H, M>) {
+        // Cells in colors the new rule doesn't have were repainted
+        self.publish(sim.map(), sim.steps());
+    }
+
+    fn on_finish(&mut self, sim: &Simulation<W, H, M>) {
+        self.publish(sim.map(), sim.steps());
+    }
+}
+
+/// Reads the last map a [`MapPublisher`] published, from any thread. Handles are cheap
+/// to clone
+///
+/// A load only holds a lock long enough to clone an [`Arc`], and never waits on the
+/// simulation copying a map. The snapshot it gives stays the same however long it's
+/// kept
+pub struct MapReadHandle<M> {
+    latest: Shared<M>,
+}
+
+impl<M> Clone for MapReadHandle<M> {
+    fn clone(&self) -> Self {
+        Self {
+            latest: self.latest.clone(),
+        }
+    }
+}
+
+impl<M> MapReadHandle<M> {
+    /// The last snapshot published, `None` before the first
+    pub fn try_load(&self) -> Option<Arc<MapSnapshot<M>>> {
+        self.latest.lock().expect("not poisoned").clone()
+    }
+
+    /// # Panics
+    ///
+    /// If nothing was published yet
+    pub fn load(&self) -> Arc<MapSnapshot<M>> {
+        self.try_load().expect("a map was published")
+    }
+}
+
+/// A map as it was at a step, derefs to the map
+#[derive(Clone)]
+pub struct MapSnapshot<M> {
+    map: M,
+    step: u64,
+}
+
+impl<M> MapSnapshot<M> {
+    pub fn step(&self) -> u64 {
+        self.step
+    }
+}
+
+impl<M> Deref for MapSnapshot<M> {
+    type Target = M;
+
+    fn deref(&self) -> &M {
+        &self.map
+    }
+}
+
+#[test]
+fn readers_see_whole_published_maps() {
+    use crate::{ColorMap, Direction, Pos, Rule};
+
+    let rule = "LLRR".parse::<Rule>().unwrap();
+    let publisher = MapPublisher::new(250);
+    let handle = publisher.handle();
+    assert!(handle.try_load().is_none());
+    let mut sim =
+        Simulation::new(ColorMap::<48, 48>::new_white(), rule.clone()).with_observer(publisher);
+    sim.spawn(Pos::new(24, 24), Direction::North).unwrap();
+    sim.change_rule(rule);
+    assert_eq!(handle.load().step(), 0);
+
+    let held = handle.load();
+    let reader = std::thread::spawn({
+        let handle = handle.clone();
+        move || (0..200).map(|_| handle.load().count_colored()).max()
+    });
+    sim.run_at_most(1000);
+    assert!(reader.join().unwrap().is_some());
+
+    // Snapshots held stay as they were
+    let latest = handle.load();
+    assert_eq!(latest.step(), 1000);
+    assert_eq!(latest.to_colors(), sim.map().to_colors());
+    assert_eq!((held.step(), held.count_colored()), (0, 0));
+    sim.run_at_most(10);
+    sim.finish();
+    assert_eq!(handle.load().step(), 1010);
+    assert_eq!(latest.step(), 1000);
+}
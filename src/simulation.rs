@@ -0,0 +1,2010 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::Display,
+    str::FromStr,
+    thread,
+    time::Duration,
+};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::Pacer;
+use crate::{
+    cell_channel::AnyChannel,
+    checkpoint::{AntCheckpoint, NoiseCheckpoint},
+    digest::Fnv1a,
+    observer::AnyObserver,
+    AntventureError, Cancelled, CellChannel, CellStore, CellVisit, ChannelValue, Checkpoint,
+    CheckpointError, ControlHandle, CycleDetector, Direction, EventKinds, Heatmap, LifeRule, Map,
+    MapPos, Observer, ObstaclePolicy, Obstacles, Periodicity, PhaseDetector, PhaseEvent, Pos, Rule,
+    SimEvent, StochasticRule, Turn, SEMANTICS_VERSION,
+};
+
+/// What happens when several ants stand on the same cell in the same tick
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum ConflictPolicy {
+    /// Ants take turns in spawn order, each one sees the color the previous one wrote
+    #[default]
+    Sequential,
+    /// Every ant on the cell turns by the color it had at the start of the tick,
+    /// and the cell is repainted once
+    FlipOnce,
+    /// Only the earliest spawned ant acts, the rest wait for the next tick
+    Exclusive,
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sequential" => Ok(ConflictPolicy::Sequential),
+            "flip-once" => Ok(ConflictPolicy::FlipOnce),
+            "exclusive" => Ok(ConflictPolicy::Exclusive),
+            _ => Err(format!(
+                "unknown conflict policy {s:?}, expected sequential, flip-once or exclusive"
+            )),
+        }
+    }
+}
+
+/// What an ant does when it steps off the edge of the map
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Boundary {
+    /// It leaves the map and stops for good
+    #[default]
+    Stop,
+    /// It comes back in on the opposite edge, the map is a torus
+    Wrap,
+    /// It bounces back, staying on the edge cell and turning around
+    Reflect,
+}
+
+impl FromStr for Boundary {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "stop" => Ok(Boundary::Stop),
+            "wrap" => Ok(Boundary::Wrap),
+            "reflect" => Ok(Boundary::Reflect),
+            _ => Err(format!(
+                "unknown boundary {s:?}, expected stop, wrap or reflect"
+            )),
+        }
+    }
+}
+
+/// Copies of an ant [`Simulation::spawn_symmetric`] adds so the pattern stays symmetric
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Symmetry {
+    /// Two ants mirrored across the vertical center line
+    Mirror,
+    /// Four ants mirrored across both center lines
+    Mirror4,
+    /// Two ants half a turn apart around the map center
+    Rotate2,
+    /// Four ants a quarter turn apart, square maps only
+    Rotate4,
+}
+
+impl Symmetry {
+    /// Ants in the whole symmetric group, including the original one
+    pub fn ants(self) -> usize {
+        match self {
+            Symmetry::Mirror | Symmetry::Rotate2 => 2,
+            Symmetry::Mirror4 | Symmetry::Rotate4 => 4,
+        }
+    }
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "mirror" => Ok(Symmetry::Mirror),
+            "mirror4" => Ok(Symmetry::Mirror4),
+            "rotate2" => Ok(Symmetry::Rotate2),
+            "rotate4" => Ok(Symmetry::Rotate4),
+            _ => Err(format!(
+                "unknown symmetry {s:?}, expected mirror, mirror4, rotate2 or rotate4"
+            )),
+        }
+    }
+}
+
+/// Why a [`Simulation`] can't walk back a step, or didn't come back to where it was
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReverseError {
+    /// There is no step before the first one
+    AtStart,
+    /// The ant with this index left the map, there's no telling when
+    AntLeft(usize),
+    /// Ants bouncing off the edge or off obstacles turn around without a trace
+    Reflect,
+    Obstacles,
+    /// Ants skipped under [`ConflictPolicy::Exclusive`] look the same as ants that
+    /// didn't move
+    Exclusive,
+    /// The rule was changed at this step, cells it repainted can't be told apart
+    RuleChange(u64),
+    /// Turns of a [`StochasticRule`] were drawn, there's no telling which way they went
+    Stochastic,
+    /// An automaton runs on the board, generations can't be undone
+    Automaton,
+    /// Walking back ended in another state than the walk started from, after this
+    /// many steps each way
+    Diverged(u64),
+}
+
+impl Display for ReverseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReverseError::AtStart => write!(f, "no step to walk back from"),
+            ReverseError::AntLeft(ant) => write!(f, "ant {ant} left the map"),
+            ReverseError::Reflect => write!(f, "ants bounce off the edges"),
+            ReverseError::Obstacles => write!(f, "ants are kept off obstacles"),
+            ReverseError::Exclusive => write!(f, "ants wait under the exclusive policy"),
+            ReverseError::RuleChange(step) => write!(f, "the rule changed at step {step}"),
+            ReverseError::Stochastic => write!(f, "the turns were drawn at random"),
+            ReverseError::Automaton => write!(f, "an automaton runs on the board"),
+            ReverseError::Diverged(steps) => {
+                write!(f, "{steps} steps back didn't undo {steps} steps forward")
+            }
+        }
+    }
+}
+
+impl Error for ReverseError {}
+
+/// Something a [`Simulation`] keeps track of that doesn't match what it has, see
+/// [`Simulation::check_integrity`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IntegrityError {
+    /// The black cells a [`Map`] counted as they changed aren't the ones it has
+    BlackCount { kept: usize, counted: usize },
+    /// The ant with this index stands outside of the [`touched`](Simulation::touched) cells
+    Untouched(usize),
+    /// The touched cells reach past the map
+    OffMap(Pos, Pos),
+}
+
+impl Display for IntegrityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntegrityError::BlackCount { kept, counted } => {
+                write!(
+                    f,
+                    "{kept} black cells were counted but the map has {counted}"
+                )
+            }
+            IntegrityError::Untouched(ant) => {
+                write!(f, "ant {ant} stands outside of the touched cells")
+            }
+            IntegrityError::OffMap(min, max) => {
+                write!(
+                    f,
+                    "the touched cells from ({}, {}) to ({}, {}) reach past the map",
+                    min.x, min.y, max.x, max.y
+                )
+            }
+        }
+    }
+}
+
+impl Error for IntegrityError {}
+
+/// One of the ants of a [`Simulation`]
+#[derive(Clone, Copy)]
+pub struct AntState<const W: usize, const H: usize> {
+    pos: MapPos<'static, W, H>,
+    dir: Direction,
+    on_map: bool,
+    // Turns the other way round, for mirror images
+    mirrored: bool,
+}
+
+impl<const W: usize, const H: usize> AntState<W, H> {
+    /// Current position, or the last cell the ant stood on if it left the map
+    pub fn pos(&self) -> MapPos<'static, W, H> {
+        self.pos
+    }
+
+    pub fn dir(&self) -> Direction {
+        self.dir
+    }
+
+    pub fn is_on_map(&self) -> bool {
+        self.on_map
+    }
+
+    /// The ant is a mirror image and turns right where the rule says left
+    pub fn is_mirrored(&self) -> bool {
+        self.mirrored
+    }
+
+    /// Face the way the turn at `turn` in [`Direction::TURNS`] says and walk a cell
+    /// forward, minding the edges and the obstacles
+    fn advance(
+        &mut self,
+        turn: usize,
+        boundary: Boundary,
+        obstacles: Option<&Obstacles<W, H>>,
+        obstacle_policy: ObstaclePolicy,
+        size: (usize, usize),
+    ) -> Stride {
+        let (dir, dx, dy) = Direction::TURNS[turn][self.dir as usize];
+        self.dir = dir;
+
+        let (next, stride) = match MapPos::validate_within(self.pos + Pos::new(dx, dy), size) {
+            Ok(pos) => (pos, Stride::Moved),
+            Err(Pos { x, y }) if boundary == Boundary::Wrap => {
+                let pos = Pos::new(x.rem_euclid(size.0 as _), y.rem_euclid(size.1 as _));
+                let pos = MapPos::validate_within(pos, size).expect("wrapped onto the map");
+                (pos, Stride::Wrapped)
+            }
+            // Mirrored across the edge the cell beyond is the one the ant stands on
+            Err(_) if boundary == Boundary::Reflect => {
+                self.dir = self.dir.cw().cw();
+                return Stride::Reflected;
+            }
+            Err(_) => {
+                self.on_map = false;
+                return Stride::Left;
+            }
+        };
+        if obstacles.is_some_and(|o| o.is_blocked(next)) {
+            match obstacle_policy {
+                ObstaclePolicy::Reflect => self.dir = self.dir.cw().cw(),
+                ObstaclePolicy::Wait => {}
+                ObstaclePolicy::Stop => self.on_map = false,
+            }
+            return Stride::Blocked;
+        }
+        self.pos = next;
+        stride
+    }
+}
+
+/// How a step of [`AntState::advance`] went
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Stride {
+    Moved,
+    /// Moved, coming back in on the opposite edge
+    Wrapped,
+    /// Turned back at the edge
+    Reflected,
+    /// Walked off the map
+    Left,
+    /// Stayed put in front of an obstacle
+    Blocked,
+}
+
+impl Stride {
+    /// Whether the ant got to a new cell
+    fn moved(self) -> bool {
+        matches!(self, Stride::Moved | Stride::Wrapped)
+    }
+
+    fn at_edge(self) -> bool {
+        matches!(self, Stride::Wrapped | Stride::Reflected | Stride::Left)
+    }
+}
+
+/// A map owned together with any number of ants walking it in lockstep
+pub struct Simulation<const W: usize, const H: usize, M = Map<W, H>> {
+    map: M,
+    rule: Rule,
+    ants: Vec<AntState<W, H>>,
+    conflict: ConflictPolicy,
+    boundary: Boundary,
+    obstacles: Option<Obstacles<W, H>>,
+    obstacle_policy: ObstaclePolicy,
+    heatmap: Option<Heatmap<W, H>>,
+    channel: Option<Box<dyn AnyChannel<W, H>>>,
+    touched: Option<(Pos, Pos)>,
+    /// Indexed by [`Turn`]
+    turns: [u64; 4],
+    steps: u64,
+    rule_changes: Vec<(u64, Rule)>,
+    noise: Option<Noise>,
+    /// Automaton advanced a generation every so many steps
+    automaton: Option<(LifeRule, u64)>,
+    observers: Vec<Box<dyn AnyObserver<W, H, M>>>,
+    /// Every kind of event any observer subscribed to
+    subscribed: EventKinds,
+    finished: bool,
+    /// Steps between looks at the clock in [`step_for`](Self::step_for), kept from one
+    /// call to the next so a frame starts with the pace of the last one
+    batch: u64,
+}
+
+/// A stochastic rule and the dice it rolls, seeded so runs can be repeated
+#[derive(Clone)]
+struct Noise {
+    rule: StochasticRule,
+    seed: u64,
+    rng: ChaCha8Rng,
+}
+
+impl Noise {
+    fn new(rule: StochasticRule, seed: u64) -> Self {
+        // ChaCha streams are fixed by the seed across platforms and rand versions
+        let rng = ChaCha8Rng::seed_from_u64(seed);
+        Self { rule, seed, rng }
+    }
+}
+
+impl<const W: usize, const H: usize, M: CellStore<W, H>> Simulation<W, H, M> {
+    /// # Panics
+    ///
+    /// If the rule has more colors than the map can hold
+    pub fn new(map: M, rule: Rule) -> Self {
+        assert!(
+            rule.colors() <= M::COLORS,
+            "rule {rule} needs {} colors but the map holds {}",
+            rule.colors(),
+            M::COLORS
+        );
+
+        Self {
+            map,
+            rule,
+            ants: Vec::new(),
+            conflict: ConflictPolicy::default(),
+            boundary: Boundary::Stop,
+            obstacles: None,
+            obstacle_policy: ObstaclePolicy::default(),
+            heatmap: None,
+            channel: None,
+            touched: None,
+            turns: [0; 4],
+            steps: 0,
+            rule_changes: Vec::new(),
+            noise: None,
+            automaton: None,
+            observers: Vec::new(),
+            subscribed: EventKinds::NONE,
+            finished: false,
+            batch: 64,
+        }
+    }
+
+    /// Walk by `rule`, drawing the turns with a random generator started from `seed`.
+    /// The plain [`rule`](Self::rule) becomes its [likely one](StochasticRule::likely_rule)
+    ///
+    /// # Panics
+    ///
+    /// If the rule has more colors than the map can hold
+    pub fn with_stochastic_rule(mut self, rule: StochasticRule, seed: u64) -> Self {
+        assert!(
+            rule.colors() <= M::COLORS,
+            "rule {rule} needs {} colors but the map holds {}",
+            rule.colors(),
+            M::COLORS
+        );
+        self.rule = rule.likely_rule();
+        self.noise = Some(Noise::new(rule, seed));
+        self
+    }
+
+    /// Advance the board a generation of `rule` every `every` steps, after the ants, for
+    /// ants walking over a live field. Cells past the edge are dead unless the boundary
+    /// wraps. Checkpoints don't hold the automaton, like they don't hold obstacles
+    ///
+    /// # Panics
+    ///
+    /// If the rule has more states than the map can hold, or `every` is zero
+    pub fn with_automaton(mut self, rule: LifeRule, every: u64) -> Self {
+        assert!(
+            rule.states() <= M::COLORS,
+            "automaton {rule} needs {} colors but the map holds {}",
+            rule.states(),
+            M::COLORS
+        );
+        assert!(every > 0, "an automaton needs steps between generations");
+        self.automaton = Some((rule, every));
+        self
+    }
+
+    pub fn with_conflict_policy(mut self, conflict: ConflictPolicy) -> Self {
+        self.conflict = conflict;
+        self
+    }
+
+    /// Glue opposite edges together, ants walking off one side come back on the other
+    /// and never leave the map
+    /// Shorthand for [`with_boundary`](Self::with_boundary) with [`Boundary::Wrap`] or
+    /// [`Boundary::Stop`]
+    pub fn with_wrapping(self, wrap: bool) -> Self {
+        self.with_boundary(match wrap {
+            true => Boundary::Wrap,
+            false => Boundary::Stop,
+        })
+    }
+
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Keep ants off the `obstacles` cells, `policy` says what they do instead.
+    /// Ants can't be spawned on an obstacle either
+    pub fn with_obstacles(mut self, obstacles: Obstacles<W, H>, policy: ObstaclePolicy) -> Self {
+        self.obstacles = Some(obstacles);
+        self.obstacle_policy = policy;
+        self
+    }
+
+    /// Count how often ants step off every cell from now on, see [`heatmap`](Self::heatmap)
+    pub fn with_heatmap(mut self) -> Self {
+        self.heatmap = Some(Heatmap::new());
+        self
+    }
+
+    /// Update `channel` on every step from now on, replaces the previous channel,
+    /// see [`channel`](Self::channel)
+    pub fn with_channel<T: ChannelValue>(mut self, channel: CellChannel<W, H, T>) -> Self {
+        self.channel = Some(Box::new(channel));
+        self
+    }
+
+    /// Tell `observer` about every step and repainted cell from now on, next to any
+    /// observers added before, see [`observer`](Self::observer)
+    pub fn with_observer(mut self, observer: impl Observer<W, H, M> + 'static) -> Self {
+        self.subscribed = self.subscribed | observer.subscriptions();
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    /// Add an ant, returns its index
+    pub fn spawn(&mut self, pos: Pos, dir: Direction) -> Result<usize, AntventureError> {
+        self.spawn_ant(pos, dir, false)
+    }
+
+    /// Add an ant together with its symmetric copies around the map center,
+    /// returns the index of the first one
+    ///
+    /// The copies walk the mirrored or rotated path of the original, so the whole
+    /// board stays symmetric. No two of them ever share a cell on an even sized map
+    ///
+    /// # Panics
+    ///
+    /// On [`Symmetry::Rotate4`] if the map isn't square
+    pub fn spawn_symmetric(
+        &mut self,
+        pos: Pos,
+        dir: Direction,
+        symmetry: Symmetry,
+    ) -> Result<usize, AntventureError> {
+        let (width, height) = self.map.size();
+        assert!(
+            symmetry != Symmetry::Rotate4 || width == height,
+            "quarter turns need a square map"
+        );
+        MapPos::<W, H>::validate_within(pos, (width, height)).map_err(AntventureError::Spawn)?;
+
+        let (right, bottom) = (width as isize - 1, height as isize - 1);
+        let flip_x = |(p, d, m): (Pos, Direction, bool)| {
+            let d = match d {
+                Direction::East | Direction::West => d.cw().cw(),
+                _ => d,
+            };
+            (Pos::new(right - p.x, p.y), d, !m)
+        };
+        let flip_y = |(p, d, m): (Pos, Direction, bool)| {
+            let d = match d {
+                Direction::North | Direction::South => d.cw().cw(),
+                _ => d,
+            };
+            (Pos::new(p.x, bottom - p.y), d, !m)
+        };
+        let quarter = |(p, d, m): (Pos, Direction, bool)| (Pos::new(right - p.y, p.x), d.cw(), m);
+
+        let ant = (pos, dir, false);
+        let copies = match symmetry {
+            Symmetry::Mirror => vec![ant, flip_x(ant)],
+            Symmetry::Mirror4 => vec![ant, flip_x(ant), flip_y(ant), flip_y(flip_x(ant))],
+            // Mirroring both ways is half a turn, and works on any map unlike quarter turns
+            Symmetry::Rotate2 => vec![ant, flip_y(flip_x(ant))],
+            Symmetry::Rotate4 => {
+                let mut copies = vec![ant];
+                for _ in 1..4 {
+                    copies.push(quarter(*copies.last().expect("starts with the ant")));
+                }
+                copies
+            }
+        };
+
+        let first = self.ants.len();
+        for (pos, dir, mirrored) in copies {
+            self.spawn_ant(pos, dir, mirrored)?;
+        }
+        Ok(first)
+    }
+
+    fn spawn_ant(
+        &mut self,
+        pos: Pos,
+        dir: Direction,
+        mirrored: bool,
+    ) -> Result<usize, AntventureError> {
+        let pos = MapPos::validate_within(pos, self.map.size()).map_err(AntventureError::Spawn)?;
+        if self.obstacles.as_ref().is_some_and(|o| o.is_blocked(pos)) {
+            return Err(AntventureError::Spawn(pos.into()));
+        }
+        touch(&mut self.touched, pos.into());
+        self.ants.push(AntState {
+            pos,
+            dir,
+            on_map: true,
+            mirrored,
+        });
+        let ant = self.ants.len() - 1;
+        let pos = pos.into();
+        self.emit(SimEvent::Spawn { ant, pos });
+        Ok(ant)
+    }
+
+    pub fn map(&self) -> &M {
+        &self.map
+    }
+
+    /// For repainting cells between steps
+    pub fn map_mut(&mut self) -> &mut M {
+        &mut self.map
+    }
+
+    pub fn into_map(self) -> M {
+        self.map
+    }
+
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// Walk on with `rule` from the next tick, see [`rule_changes`](Self::rule_changes).
+    /// Cells in colors the new rule doesn't have are repainted in the color they'd have
+    /// if it cycled on, `color % rule.colors()`. A stochastic rule ends here, the
+    /// new one is followed to the letter
+    ///
+    /// # Panics
+    ///
+    /// If the rule has more colors than the map can hold
+    pub fn change_rule(&mut self, rule: Rule) {
+        assert!(
+            rule.colors() <= M::COLORS,
+            "rule {rule} needs {} colors but the map holds {}",
+            rule.colors(),
+            M::COLORS
+        );
+
+        if rule.colors() < self.rule.colors() {
+            let colors = rule.colors() as u8;
+            for (i, color) in self.map.to_colors().into_iter().enumerate() {
+                if color >= colors {
+                    let pos = Pos::new((i % W) as _, (i / W) as _);
+                    let pos = MapPos::validate_pos(pos).expect("cells are on the map");
+                    self.map.set_color(pos, color % colors);
+                }
+            }
+        }
+        self.rule_changes.push((self.steps, rule.clone()));
+        self.rule = rule;
+        self.noise = None;
+        self.notify(|observer, sim| observer.on_rule_change(sim));
+        self.emit(SimEvent::RuleChange { step: self.steps });
+    }
+
+    /// The rule set with [`with_stochastic_rule`](Self::with_stochastic_rule), if it's
+    /// still in force
+    pub fn stochastic_rule(&self) -> Option<&StochasticRule> {
+        self.noise.as_ref().map(|noise| &noise.rule)
+    }
+
+    /// Every [`change_rule`](Self::change_rule) so far, the tick the new rule took over
+    /// at and the rule
+    pub fn rule_changes(&self) -> &[(u64, Rule)] {
+        &self.rule_changes
+    }
+
+    /// Visit counts, if [`with_heatmap`](Self::with_heatmap) asked for them
+    pub fn obstacles(&self) -> Option<&Obstacles<W, H>> {
+        self.obstacles.as_ref()
+    }
+
+    pub fn heatmap(&self) -> Option<&Heatmap<W, H>> {
+        self.heatmap.as_ref()
+    }
+
+    /// The channel set with [`with_channel`](Self::with_channel), if its values are `T`
+    pub fn channel<T: ChannelValue>(&self) -> Option<&CellChannel<W, H, T>> {
+        self.channel.as_ref()?.as_any().downcast_ref()
+    }
+
+    /// Smallest rectangle holding every cell an ant stood on, as inclusive corners,
+    /// `None` before the first ant is spawned
+    ///
+    /// A simulation restored [`from_checkpoint`](Self::from_checkpoint) starts from
+    /// the cells that aren't white and where the ants are
+    pub fn touched(&self) -> Option<(Pos, Pos)> {
+        self.touched
+    }
+
+    /// Left and right turns the ants took so far, counted from the start of this run
+    /// as checkpoints don't keep them. U-turns and going straight count as neither
+    pub fn turns(&self) -> [u64; 2] {
+        [
+            self.turns[Turn::Left as usize],
+            self.turns[Turn::Right as usize],
+        ]
+    }
+
+    /// Every ant ever spawned, in spawn order
+    pub fn ants(&self) -> &[AntState<W, H>] {
+        &self.ants
+    }
+
+    /// Ticks done so far
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Advance every ant on the map by one step, returns whether any ant is still on it
+    pub fn step(&mut self) -> bool {
+        match self.conflict {
+            ConflictPolicy::Sequential => {
+                for i in 0..self.ants.len() {
+                    let color = self.map.color(self.ants[i].pos);
+                    self.move_ant(i, color, true);
+                }
+            }
+            ConflictPolicy::FlipOnce | ConflictPolicy::Exclusive => {
+                let exclusive = self.conflict == ConflictPolicy::Exclusive;
+                let mut taken = HashSet::with_capacity(self.ants.len());
+
+                // Read every color before anyone writes
+                let colors = self
+                    .ants
+                    .iter()
+                    .map(|ant| self.map.color(ant.pos))
+                    .collect::<Vec<_>>();
+
+                for (i, color) in colors.into_iter().enumerate() {
+                    let first = taken.insert(self.ants[i].pos.index());
+                    if exclusive && !first {
+                        continue;
+                    }
+                    self.move_ant(i, color, first);
+                }
+            }
+        }
+
+        self.steps += 1;
+        if let Some((rule, every)) = &self.automaton {
+            if self.steps.is_multiple_of(*every) {
+                rule.step(&mut self.map, self.boundary == Boundary::Wrap);
+                self.emit(SimEvent::Generation { step: self.steps });
+            }
+        }
+        let on_map = self.ants.iter().any(|ant| ant.on_map);
+        if !self.observers.is_empty() {
+            self.notify(|observer, sim| observer.on_step(sim));
+            self.emit(SimEvent::Step { step: self.steps });
+            if !on_map {
+                self.finish();
+            }
+        }
+        on_map
+    }
+
+    /// Tell the observers the run is over, for runs stopped before every ant left the map.
+    /// Only the first call counts, it's made by [`step`](Self::step) when the last ant leaves
+    pub fn finish(&mut self) {
+        if !self.finished {
+            self.finished = true;
+            self.notify(|observer, sim| observer.on_finish(sim));
+        }
+    }
+
+    /// First observer of type `T`, to read what it collected
+    pub fn observer<T: 'static>(&self) -> Option<&T> {
+        self.observers
+            .iter()
+            .find_map(|observer| observer.as_any().downcast_ref())
+    }
+
+    fn notify(&mut self, f: impl Fn(&mut dyn AnyObserver<W, H, M>, &Self)) {
+        // Taken out for the call, they get to see the rest of the simulation
+        let mut observers = std::mem::take(&mut self.observers);
+        for observer in &mut observers {
+            f(observer.as_mut(), self);
+        }
+        self.observers = observers;
+    }
+
+    /// Hand `event` to the observers subscribed to its kind
+    fn emit(&mut self, event: SimEvent) {
+        emit(&mut self.observers, self.subscribed, event);
+    }
+
+    /// Turn the ant by `color`, repaint the cell if asked and move it forward
+    fn move_ant(&mut self, i: usize, color: u8, paint: bool) {
+        let spread = self.ants.len() > 1;
+        let ant = &mut self.ants[i];
+        if !ant.on_map {
+            return;
+        }
+
+        if paint {
+            let next = self.rule.next_color(color);
+            self.map.set_color(ant.pos, next);
+            for observer in &mut self.observers {
+                observer.on_cell_flip(i, ant.pos, color, next);
+            }
+            let (pos, from, to) = (ant.pos.into(), color, next);
+            emit(
+                &mut self.observers,
+                self.subscribed,
+                SimEvent::Flip {
+                    ant: i,
+                    pos,
+                    from,
+                    to,
+                },
+            );
+        }
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.visit(ant.pos);
+        }
+        if let Some(channel) = &mut self.channel {
+            channel.visit(CellVisit {
+                pos: ant.pos,
+                color,
+                ant: i,
+                step: self.steps,
+            });
+        }
+
+        let turn = match &mut self.noise {
+            Some(noise) => noise.rule.turn(color, &mut noise.rng),
+            None => self.rule.turn(color),
+        };
+        let turn = match ant.mirrored {
+            true => turn.mirrored(),
+            false => turn,
+        } as usize;
+        self.turns[turn] += 1;
+        let (obstacles, size) = (self.obstacles.as_ref(), self.map.size());
+        let from = ant.pos.into();
+        let stride = ant.advance(turn, self.boundary, obstacles, self.obstacle_policy, size);
+        if stride.moved() {
+            touch(&mut self.touched, ant.pos.into());
+            // The cell ahead is read next tick, after every other ant's step. On a big
+            // board that's plenty of time to load it, where reading it cold would stall
+            if spread {
+                self.map.prefetch(ant.pos.into());
+            }
+        }
+        let left = !ant.on_map;
+        if stride.at_edge() {
+            self.emit(SimEvent::BoundaryHit { ant: i, pos: from });
+        }
+        if left {
+            self.emit(SimEvent::Despawn { ant: i, pos: from });
+        }
+    }
+
+    /// Like [`run_at_most`](Self::run_at_most), but also stops once `detector` finds
+    /// the first ant walking periodically. Once that ant is off the map the others
+    /// walk on unwatched
+    pub fn run_until_periodic(
+        &mut self,
+        steps: u64,
+        detector: &mut CycleDetector,
+    ) -> Option<Periodicity> {
+        for _ in 0..steps {
+            let watched = self.watched();
+            let on_map = self.step();
+            if let Some((color, ant)) = watched {
+                if let Some(periodicity) = detector.observe(color, ant.pos.into(), ant.dir) {
+                    return Some(periodicity);
+                }
+            }
+            if !on_map {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// Like [`run_at_most`](Self::run_at_most), but stops right after the step
+    /// `detector` reports a phase change of the first ant in. Once that ant is off
+    /// the map the others walk on unwatched
+    pub fn run_watching(&mut self, steps: u64, detector: &mut PhaseDetector) -> Option<PhaseEvent> {
+        if let Some(event) = detector.pending() {
+            self.emit(SimEvent::PhaseChange(event));
+            return Some(event);
+        }
+        for _ in 0..steps {
+            let watched = self.watched();
+            let on_map = self.step();
+            if let Some((color, ant)) = watched {
+                if let Some(event) = detector.observe(color, ant.pos.into(), ant.dir) {
+                    self.emit(SimEvent::PhaseChange(event));
+                    return Some(event);
+                }
+            }
+            if !on_map {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// The first ant and the color under it, while it's on the map
+    fn watched(&self) -> Option<(u8, AntState<W, H>)> {
+        let &ant = self.ants.first().filter(|ant| ant.on_map)?;
+        Some((self.map.color(ant.pos), ant))
+    }
+
+    /// Stable 64-bit hash of the map, the rule, the ants and the step counter, see
+    /// [`CellStore::digest`]. Simulations in the same state have the same digest
+    /// whatever store their map is kept in
+    ///
+    /// The boundary, policies, heatmap, channel and observers aren't part of it,
+    /// a stochastic rule and how far its dice rolled are
+    pub fn digest(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        hasher
+            .write_u64(self.map.digest())
+            .write(self.rule.to_string().as_bytes())
+            .write_u64(self.steps)
+            .write_u64(self.ants.len() as u64);
+        for ant in &self.ants {
+            hasher
+                .write_u64(ant.pos.x() as u64)
+                .write_u64(ant.pos.y() as u64)
+                .write(&[ant.dir as u8, ant.on_map as u8, ant.mirrored as u8]);
+        }
+        if let Some(noise) = &self.noise {
+            hasher
+                .write(noise.rule.to_string().as_bytes())
+                .write_u64(noise.seed)
+                .write(&noise.rng.get_word_pos().to_le_bytes());
+        }
+        hasher.finish()
+    }
+
+    /// Check what the simulation keeps track of as it goes against what it has: counts
+    /// the map keeps and the rectangle of [`touched`](Self::touched) cells, which has to
+    /// lie on the map and hold every ant on it
+    ///
+    /// None of it fails without a bug in a map store or in the simulation, but a week
+    /// long run had better find one every few million steps than at the end. Recounting
+    /// the map takes a pass over it, so it's too slow for every step
+    pub fn check_integrity(&self) -> Result<(), IntegrityError> {
+        self.map.check_integrity()?;
+
+        let on_map = |pos: Pos| MapPos::<W, H>::validate_within(pos, self.map.size()).is_ok();
+        if let Some((min, max)) = self.touched {
+            if !on_map(min) || !on_map(max) {
+                return Err(IntegrityError::OffMap(min, max));
+            }
+        }
+        for (i, ant) in self.ants.iter().enumerate() {
+            let pos: Pos = ant.pos.into();
+            let inside = self.touched.is_some_and(|(min, max)| {
+                (min.x..=max.x).contains(&pos.x) && (min.y..=max.y).contains(&pos.y)
+            });
+            if ant.on_map && !inside {
+                return Err(IntegrityError::Untouched(i));
+            }
+        }
+        Ok(())
+    }
+
+    /// Snapshot of the map, the ants, the step counter and the dice of a stochastic rule
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            width: W,
+            height: H,
+            rule: self.rule.to_string(),
+            conflict: self.conflict,
+            cells: Checkpoint::pack_cells(&self.map, self.rule.colors()),
+            ants: self
+                .ants
+                .iter()
+                .map(|ant| AntCheckpoint {
+                    x: ant.pos.x(),
+                    y: ant.pos.y(),
+                    dir: ant.dir,
+                    on_map: ant.on_map,
+                    mirrored: ant.mirrored,
+                })
+                .collect(),
+            steps: self.steps,
+            noise: self.noise.as_ref().map(|noise| NoiseCheckpoint {
+                rule: noise.rule.to_string(),
+                seed: noise.seed,
+                word_pos: noise.rng.get_word_pos(),
+            }),
+            semantics: SEMANTICS_VERSION,
+        }
+    }
+
+    /// Carry on from a [`checkpoint`](Self::checkpoint) taken on a map of the same size
+    ///
+    /// The boundary, the heatmap, the cell channel and observers aren't part of the checkpoint,
+    /// turn them back on with [`with_boundary`](Self::with_boundary),
+    /// [`with_heatmap`](Self::with_heatmap), [`with_channel`](Self::with_channel) and
+    /// [`with_observer`](Self::with_observer)
+    pub fn from_checkpoint(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        if (checkpoint.width, checkpoint.height) != (W, H) {
+            return Err(CheckpointError::Size(checkpoint.width, checkpoint.height));
+        }
+        if checkpoint.semantics != SEMANTICS_VERSION {
+            return Err(CheckpointError::Semantics(checkpoint.semantics));
+        }
+        let rule = checkpoint.rule()?;
+        if rule.colors() > M::COLORS {
+            return Err(CheckpointError::Corrupted);
+        }
+
+        let noise = match &checkpoint.noise {
+            Some(noise) => {
+                let mut restored = Noise::new(
+                    noise.rule.parse().map_err(CheckpointError::Rule)?,
+                    noise.seed,
+                );
+                restored.rng.set_word_pos(noise.word_pos);
+                Some(restored)
+            }
+            None => None,
+        };
+        let cells = checkpoint.unpack_cells(rule.colors())?;
+        let ants = checkpoint
+            .ants
+            .iter()
+            .map(|ant| {
+                Ok(AntState {
+                    pos: MapPos::validate_pos(Pos::new(ant.x as _, ant.y as _))
+                        .map_err(|_| CheckpointError::Corrupted)?,
+                    dir: ant.dir,
+                    on_map: ant.on_map,
+                    mirrored: ant.mirrored,
+                })
+            })
+            .collect::<Result<Vec<AntState<W, H>>, CheckpointError>>()?;
+
+        // Cells walked over but left white are gone, the colored ones are the best guess
+        let mut touched = None;
+        for (i, _) in cells.iter().enumerate().filter(|(_, &color)| color != 0) {
+            touch(&mut touched, Pos::new((i % W) as _, (i / W) as _));
+        }
+        for ant in &ants {
+            touch(&mut touched, ant.pos.into());
+        }
+
+        Ok(Self {
+            map: M::with_colors(cells),
+            rule,
+            ants,
+            conflict: checkpoint.conflict,
+            boundary: Boundary::Stop,
+            obstacles: None,
+            obstacle_policy: ObstaclePolicy::default(),
+            heatmap: None,
+            channel: None,
+            touched,
+            turns: [0; 4],
+            steps: checkpoint.steps,
+            rule_changes: Vec::new(),
+            noise,
+            automaton: None,
+            observers: Vec::new(),
+            subscribed: EventKinds::NONE,
+            finished: false,
+            batch: 64,
+        })
+    }
+
+    /// Independent copy of everything, the map, the ants, the step counter and
+    /// the heatmap and cell channel if there are any, for branching off a run
+    ///
+    /// Observers stay with the original, the fork has none
+    pub fn fork(&self) -> Self
+    where
+        M: Clone,
+    {
+        Self {
+            map: self.map.clone(),
+            rule: self.rule.clone(),
+            ants: self.ants.clone(),
+            conflict: self.conflict,
+            boundary: self.boundary,
+            obstacles: self.obstacles.clone(),
+            obstacle_policy: self.obstacle_policy,
+            heatmap: self.heatmap.clone(),
+            channel: self.channel.as_ref().map(|channel| channel.clone_box()),
+            touched: self.touched,
+            turns: self.turns,
+            steps: self.steps,
+            rule_changes: self.rule_changes.clone(),
+            noise: self.noise.clone(),
+            automaton: self.automaton.clone(),
+            observers: Vec::new(),
+            subscribed: EventKinds::NONE,
+            finished: self.finished,
+            batch: self.batch,
+        }
+    }
+
+    pub fn run_until_end(&mut self) {
+        while self.step() {}
+    }
+
+    /// Run for about `budget` of wall time, for a host that steps the simulation in its
+    /// frame loop. Returns how many ticks were done, fewer once every ant left the map
+    ///
+    /// The clock is read every so many steps, as many as take a sixteenth of the budget
+    /// at the pace measured so far, so a call runs over by about that much at most
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn step_for(&mut self, budget: Duration) -> u64 {
+        use std::time::Instant;
+
+        let started = Instant::now();
+        let first = self.steps;
+        let mut on_map = self.ants.iter().any(|ant| ant.on_map);
+        let slice = budget / 16;
+        while on_map && started.elapsed() < budget {
+            let batch_started = Instant::now();
+            on_map = self.run_at_most(self.batch);
+            let took = batch_started.elapsed();
+            if took < slice / 2 {
+                self.batch = self.batch.saturating_mul(2);
+            } else if took > slice {
+                self.batch = (self.batch / 2).max(1);
+            }
+        }
+        self.steps - first
+    }
+
+    /// Run until every ant left the map or `steps` ticks are done,
+    /// returns whether any ant is still on the map
+    pub fn run_at_most(&mut self, steps: u64) -> bool {
+        (0..steps).all(|_| self.step())
+    }
+
+    /// Like [`run_at_most`](Self::run_at_most), but held while `control` is paused, kept
+    /// to its speed and stopped with `Err` once it's cancelled. The handle is looked at
+    /// every [`CONTROL_EVERY`](Self::CONTROL_EVERY) steps, more often when slowed down
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn run_controlled(
+        &mut self,
+        steps: u64,
+        control: &ControlHandle,
+    ) -> Result<bool, Cancelled> {
+        let end = self.steps.saturating_add(steps);
+        let mut pacer = None;
+        while self.steps < end {
+            control.wait_while_paused()?;
+            let paced = Pacer::keep_to(&mut pacer, control.speed(), self.steps);
+            let stretch = Self::CONTROL_EVERY.min(end - self.steps);
+            if !self.run_at_most(paced.map_or(stretch, |paced| paced.min(stretch))) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Most steps [`run_controlled`](Self::run_controlled) walks between looks at its
+    /// handle
+    pub const CONTROL_EVERY: u64 = 1 << 14;
+
+    /// [`run_at_most`](Self::run_at_most) on up to `threads` threads, `0` for one per core
+    ///
+    /// Ants go in batches of [`PARALLEL_BATCH`](Self::PARALLEL_BATCH) ticks. Ants too
+    /// far apart to reach each other's cells within a batch walk it on threads of their
+    /// own, the colors they paint are put on the map once every group is done. The map
+    /// and the ants come out the same as stepping serially, on any number of threads.
+    /// Batches with every ant in one group, like densely packed ants, and simulations
+    /// with observers, a cell channel, a stochastic rule or an automaton that want to
+    /// see every tick in order step serially
+    pub fn run_parallel(&mut self, steps: u64, threads: usize) -> bool
+    where
+        M: Sync,
+    {
+        let threads = match threads {
+            0 => num_cpus::get(),
+            threads => threads,
+        };
+        let serial = threads == 1
+            || !self.observers.is_empty()
+            || self.channel.is_some()
+            || self.noise.is_some()
+            || self.automaton.is_some();
+
+        let mut left = steps;
+        while left > 0 {
+            let ticks = left.min(Self::PARALLEL_BATCH);
+            let patches = match serial {
+                true => Vec::new(),
+                false => self.patches(ticks),
+            };
+            if patches.len() < 2 {
+                if !self.run_at_most(ticks) {
+                    return false;
+                }
+                left -= ticks;
+                continue;
+            }
+
+            let walk = Walk {
+                map: &self.map,
+                rule: &self.rule,
+                conflict: self.conflict,
+                boundary: self.boundary,
+                obstacles: self.obstacles.as_ref(),
+                obstacle_policy: self.obstacle_policy,
+                visits: self.heatmap.is_some(),
+            };
+            let mut patches = patches;
+            let per_thread = patches.len().div_ceil(threads);
+            thread::scope(|s| {
+                for chunk in patches.chunks_mut(per_thread) {
+                    let walk = &walk;
+                    s.spawn(move || chunk.iter_mut().for_each(|patch| patch.walk(walk, ticks)));
+                }
+            });
+
+            let mut walked = 0;
+            for patch in patches {
+                for (pos, color) in patch.painted.into_values() {
+                    self.map.set_color(pos, color);
+                }
+                for (i, ant) in patch.ants {
+                    self.ants[i] = ant;
+                }
+                if let Some(heatmap) = &mut self.heatmap {
+                    patch.visits.into_iter().for_each(|pos| heatmap.visit(pos));
+                }
+                for (turns, walked) in self.turns.iter_mut().zip(patch.turns) {
+                    *turns += walked;
+                }
+                if let Some((min, max)) = patch.touched {
+                    touch(&mut self.touched, min);
+                    touch(&mut self.touched, max);
+                }
+                walked = walked.max(patch.ticks);
+            }
+            self.steps += walked;
+            if !self.ants.iter().any(|ant| ant.on_map) {
+                return false;
+            }
+            left -= ticks;
+        }
+        self.ants.iter().any(|ant| ant.on_map)
+    }
+
+    /// Ticks [`run_parallel`](Self::run_parallel) walks groups of ants apart before it
+    /// puts their colors together
+    pub const PARALLEL_BATCH: u64 = 128;
+
+    /// Ants in groups that can't reach each other's cells within `ticks`, in spawn
+    /// order in every group. Ants that left the map stay in a group to block its cell
+    /// like they do in a tick, groups of them alone are dropped
+    fn patches(&self, ticks: u64) -> Vec<Patch<W, H>> {
+        let reach = |ant: &AntState<W, H>| match ant.on_map {
+            true => ticks as isize,
+            false => 0,
+        };
+        let bounds = self
+            .ants
+            .iter()
+            .map(|ant| {
+                let (pos, reach) = (Pos::from(ant.pos), reach(ant));
+                let min = Pos::new(pos.x - reach, pos.y - reach);
+                (min, Pos::new(pos.x + reach, pos.y + reach))
+            })
+            .collect::<Vec<_>>();
+        // Wrapped around an edge an ant could turn up next to anyone
+        let (width, height) = self.map.size();
+        let inside = |(min, max): &(Pos, Pos)| {
+            min.x >= 0 && min.y >= 0 && max.x < width as isize && max.y < height as isize
+        };
+        if self.boundary == Boundary::Wrap && !bounds.iter().all(inside) {
+            return Vec::new();
+        }
+
+        // Sweep from left to right, joining ants whose reach overlaps
+        let mut order = (0..self.ants.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| bounds[i].0.x);
+        let mut group = (0..self.ants.len()).collect::<Vec<_>>();
+        fn root(group: &mut [usize], mut i: usize) -> usize {
+            while group[i] != i {
+                group[i] = group[group[i]];
+                i = group[i];
+            }
+            i
+        }
+        for (n, &i) in order.iter().enumerate() {
+            let (min, max) = bounds[i];
+            for &j in order[n + 1..]
+                .iter()
+                .take_while(|&&j| bounds[j].0.x <= max.x)
+            {
+                if bounds[j].0.y <= max.y && min.y <= bounds[j].1.y {
+                    let (a, b) = (root(&mut group, i), root(&mut group, j));
+                    group[a.max(b)] = a.min(b);
+                }
+            }
+        }
+
+        let mut patches = HashMap::<usize, Patch<W, H>>::new();
+        for (i, &ant) in self.ants.iter().enumerate() {
+            let patch = patches.entry(root(&mut group, i)).or_default();
+            patch.ants.push((i, ant));
+        }
+        let mut patches = patches
+            .into_values()
+            .filter(|patch| patch.ants.iter().any(|(_, ant)| ant.on_map))
+            .collect::<Vec<_>>();
+        patches.sort_by_key(|patch| patch.ants[0].0);
+        patches
+    }
+
+    /// Undo the last [`step`](Self::step): move every ant back, turn it back and
+    /// give the cell its color back. Nothing changes when it fails
+    ///
+    /// Every color has one color before it, so a turmite on a map with no walls can
+    /// always go back. The heatmap, the cell channel, the touched cells and observers
+    /// don't go back with it
+    pub fn step_back(&mut self) -> Result<(), ReverseError> {
+        if self.steps == 0 {
+            return Err(ReverseError::AtStart);
+        }
+        if let Some(ant) = self.ants.iter().position(|ant| !ant.on_map) {
+            return Err(ReverseError::AntLeft(ant));
+        }
+        if self.boundary == Boundary::Reflect {
+            return Err(ReverseError::Reflect);
+        }
+        if self.obstacles.is_some() {
+            return Err(ReverseError::Obstacles);
+        }
+        if self.conflict == ConflictPolicy::Exclusive {
+            return Err(ReverseError::Exclusive);
+        }
+        if self.noise.is_some() {
+            return Err(ReverseError::Stochastic);
+        }
+        if self.automaton.is_some() {
+            return Err(ReverseError::Automaton);
+        }
+        // The last tick was walked under the rule in force a step before it
+        if let Some(&(step, _)) = self.rule_changes.last().filter(|(at, _)| *at >= self.steps) {
+            return Err(ReverseError::RuleChange(step));
+        }
+
+        // Ants moved forward in the direction they face now
+        let (width, height) = self.map.size();
+        let mut from = Vec::with_capacity(self.ants.len());
+        for (i, ant) in self.ants.iter().enumerate() {
+            let (pos, shift) = (Pos::from(ant.pos), ant.dir.to_shift());
+            let back = Pos::new(pos.x - shift.x, pos.y - shift.y);
+            let back = match self.boundary {
+                Boundary::Wrap => Pos::new(
+                    back.x.rem_euclid(width as _),
+                    back.y.rem_euclid(height as _),
+                ),
+                _ => back,
+            };
+            let back = MapPos::validate_within(back, (width, height));
+            from.push(back.map_err(|_| ReverseError::AntLeft(i))?);
+        }
+
+        // Later ants walked over what earlier ones painted, they go back first
+        let colors = self.rule.colors() as u8;
+        for i in (0..self.ants.len()).rev() {
+            let pos = from[i];
+            let painted = self.map.color(pos);
+            let color = (painted + colors - 1) % colors;
+            let first = match self.conflict {
+                ConflictPolicy::Sequential => true,
+                _ => !from[..i].iter().any(|other| other.index() == pos.index()),
+            };
+            if first {
+                self.map.set_color(pos, color);
+            }
+
+            let ant = &mut self.ants[i];
+            let turn = match ant.mirrored {
+                true => self.rule.turn(color).mirrored(),
+                false => self.rule.turn(color),
+            };
+            ant.dir = ant.dir.turn(turn.inverse());
+            ant.pos = pos;
+            self.turns[turn as usize] -= 1;
+        }
+        self.steps -= 1;
+        Ok(())
+    }
+
+    /// Check the walk is reversible from here: walk `steps` forward and as many back
+    /// on a [`fork`](Self::fork), and compare the map, the ants and the step counter
+    /// with the ones now
+    ///
+    /// Meant for tests of rules, maps and policies that should keep a walk
+    /// reversible, the simulation itself is left as it is
+    pub fn verify_reversibility(&self, steps: u64) -> Result<(), ReverseError>
+    where
+        M: Clone,
+    {
+        let mut fork = self.fork();
+        fork.run_at_most(steps);
+        for _ in 0..steps {
+            fork.step_back()?;
+        }
+
+        let same_ants = self
+            .ants
+            .iter()
+            .zip(&fork.ants)
+            .all(|(a, b)| (a.pos.index(), a.dir, a.on_map) == (b.pos.index(), b.dir, b.on_map));
+        match same_ants && fork.steps == self.steps && fork.map.to_colors() == self.map.to_colors()
+        {
+            true => Ok(()),
+            false => Err(ReverseError::Diverged(steps)),
+        }
+    }
+}
+
+/// Grow `bounds` to hold `pos`
+/// What the groups of ants of [`Simulation::run_parallel`] share, the map only to read
+struct Walk<'a, const W: usize, const H: usize, M> {
+    map: &'a M,
+    rule: &'a Rule,
+    conflict: ConflictPolicy,
+    boundary: Boundary,
+    obstacles: Option<&'a Obstacles<W, H>>,
+    obstacle_policy: ObstaclePolicy,
+    /// Keep the cells the ants stood on, for the heatmap
+    visits: bool,
+}
+
+/// A group of ants walking a batch of ticks on its own, with the colors it painted kept
+/// aside and read before the map
+struct Patch<const W: usize, const H: usize> {
+    /// Index of every ant and where it is, in spawn order
+    ants: Vec<(usize, AntState<W, H>)>,
+    painted: HashMap<usize, (MapPos<'static, W, H>, u8)>,
+    visits: Vec<MapPos<'static, W, H>>,
+    turns: [u64; 4],
+    touched: Option<(Pos, Pos)>,
+    /// Ticks walked before every ant of the group left the map
+    ticks: u64,
+}
+
+impl<const W: usize, const H: usize> Default for Patch<W, H> {
+    fn default() -> Self {
+        Self {
+            ants: Vec::new(),
+            painted: HashMap::new(),
+            visits: Vec::new(),
+            turns: [0; 4],
+            touched: None,
+            ticks: 0,
+        }
+    }
+}
+
+impl<const W: usize, const H: usize> Patch<W, H> {
+    /// Walk `ticks` ticks the way [`Simulation::step`] does with these ants alone
+    fn walk<M: CellStore<W, H>>(&mut self, walk: &Walk<W, H, M>, ticks: u64) {
+        let mut taken = HashSet::with_capacity(self.ants.len());
+        let mut colors = Vec::with_capacity(self.ants.len());
+        while self.ticks < ticks && self.ants.iter().any(|(_, ant)| ant.on_map) {
+            // Every color before anyone writes, sequential ants look again when it's their turn
+            taken.clear();
+            colors.clear();
+            colors.extend(self.ants.iter().map(|(_, ant)| self.color(walk, ant.pos)));
+            for (n, &read) in colors.iter().enumerate() {
+                let pos = self.ants[n].1.pos;
+                let (color, paint) = match walk.conflict {
+                    ConflictPolicy::Sequential => (self.color(walk, pos), true),
+                    conflict => {
+                        let first = taken.insert(pos.index());
+                        if conflict == ConflictPolicy::Exclusive && !first {
+                            continue;
+                        }
+                        (read, first)
+                    }
+                };
+                self.move_ant(walk, n, color, paint);
+            }
+            self.ticks += 1;
+        }
+    }
+
+    fn color<M: CellStore<W, H>>(&self, walk: &Walk<W, H, M>, pos: MapPos<'static, W, H>) -> u8 {
+        match self.painted.get(&pos.index()) {
+            Some(&(_, color)) => color,
+            None => walk.map.color(pos),
+        }
+    }
+
+    /// [`Simulation::move_ant`] without observers, channels or dice
+    fn move_ant<M: CellStore<W, H>>(
+        &mut self,
+        walk: &Walk<W, H, M>,
+        n: usize,
+        color: u8,
+        paint: bool,
+    ) {
+        let ant = &mut self.ants[n].1;
+        if !ant.on_map {
+            return;
+        }
+
+        if paint {
+            let next = walk.rule.next_color(color);
+            self.painted.insert(ant.pos.index(), (ant.pos, next));
+        }
+        if walk.visits {
+            self.visits.push(ant.pos);
+        }
+
+        let turn = match ant.mirrored {
+            true => walk.rule.turn(color).mirrored(),
+            false => walk.rule.turn(color),
+        } as usize;
+        self.turns[turn] += 1;
+        let size = walk.map.size();
+        let stride = ant.advance(
+            turn,
+            walk.boundary,
+            walk.obstacles,
+            walk.obstacle_policy,
+            size,
+        );
+        if stride.moved() {
+            touch(&mut self.touched, ant.pos.into());
+        }
+    }
+}
+
+fn emit<const W: usize, const H: usize, M>(
+    observers: &mut [Box<dyn AnyObserver<W, H, M>>],
+    subscribed: EventKinds,
+    event: SimEvent,
+) {
+    let kind = event.kind();
+    if !subscribed.contains(kind) {
+        return;
+    }
+    for observer in observers {
+        if observer.subscriptions().contains(kind) {
+            observer.on_event(&event);
+        }
+    }
+}
+
+fn touch(bounds: &mut Option<(Pos, Pos)>, pos: Pos) {
+    let (min, max) = bounds.get_or_insert((pos, pos));
+    *min = Pos::new(min.x.min(pos.x), min.y.min(pos.y));
+    *max = Pos::new(max.x.max(pos.x), max.y.max(pos.y));
+}
+
+#[test]
+fn single_ant_matches_ant() {
+    use crate::Ant;
+
+    let mut map = Map::<64, 64>::new_white();
+    let mut ant = Ant::new(&mut map, Pos::new(20, 40), Direction::East).unwrap();
+    ant.walk_until_end();
+    let (pos, dir) = (Pos::from(ant.pos()), ant.dir());
+
+    let mut sim = Simulation::new(Map::<64, 64>::new_white(), Rule::default());
+    sim.spawn(Pos::new(20, 40), Direction::East).unwrap();
+    sim.run_until_end();
+
+    assert_eq!(Pos::from(sim.ants()[0].pos()), pos);
+    assert_eq!(sim.ants()[0].dir(), dir);
+    assert_eq!(sim.map().count_black_tiles(), map.count_black_tiles());
+}
+
+#[test]
+fn others_walk_on_after_the_watched_ant_left() {
+    let new_sim = || {
+        let mut sim = Simulation::new(Map::<256, 256>::new_white(), Rule::default());
+        sim.spawn(Pos::new(0, 0), Direction::North).unwrap();
+        sim.spawn(Pos::new(128, 128), Direction::North).unwrap();
+        sim
+    };
+
+    let mut sim = new_sim();
+    let mut detector = PhaseDetector::new(256, 256, 512);
+    while sim
+        .run_watching(1000 - sim.steps(), &mut detector)
+        .is_some()
+    {}
+    assert_eq!(sim.steps(), 1000);
+    assert!(!sim.ants()[0].is_on_map() && sim.ants()[1].is_on_map());
+
+    let mut sim = new_sim();
+    assert_eq!(
+        sim.run_until_periodic(1000, &mut CycleDetector::new(512)),
+        None
+    );
+    assert_eq!(sim.steps(), 1000);
+}
+
+#[test]
+fn conflict_policies() {
+    use Direction::*;
+
+    let run = |conflict| {
+        let mut sim = Simulation::new(Map::<8, 8>::new_white(), Rule::default())
+            .with_conflict_policy(conflict);
+        sim.spawn(Pos::new(4, 4), Direction::North).unwrap();
+        sim.spawn(Pos::new(4, 4), Direction::South).unwrap();
+        sim.step();
+        let dirs = sim.ants().iter().map(|a| a.dir()).collect::<Vec<_>>();
+        (sim.map().count_black_tiles(), dirs)
+    };
+
+    // The second ant finds the cell already black and turns the other way
+    assert_eq!(run(ConflictPolicy::Sequential), (0, vec![West, West]));
+    assert_eq!(run(ConflictPolicy::FlipOnce), (1, vec![West, East]));
+    assert_eq!(run(ConflictPolicy::Exclusive), (1, vec![West, South]));
+}
+
+#[test]
+fn checkpoint_round_trip() {
+    use crate::ColorMap;
+
+    let rule = "LRR".parse::<Rule>().unwrap();
+    let mut sim = Simulation::new(ColorMap::<32, 32>::new_white(), rule.clone());
+    sim.spawn(Pos::new(16, 16), Direction::North).unwrap();
+    sim.run_at_most(300);
+
+    let file = std::env::temp_dir().join("antventure_checkpoint_round_trip.bin");
+    sim.checkpoint().save(&file).unwrap();
+    let checkpoint = Checkpoint::load(&file).unwrap();
+    std::fs::remove_file(&file).ok();
+
+    let mut resumed = Simulation::<32, 32, ColorMap<32, 32>>::from_checkpoint(&checkpoint).unwrap();
+    assert_eq!(resumed.steps(), 300);
+    resumed.run_at_most(200);
+    sim.run_at_most(200);
+    assert_eq!(resumed.map().colors(), sim.map().colors());
+    assert_eq!(resumed.ants()[0].pos().index(), sim.ants()[0].pos().index());
+
+    let mut bits = Simulation::new(Map::<32, 32>::new_white(), Rule::default());
+    bits.spawn(Pos::new(3, 5), Direction::East).unwrap();
+    bits.run_until_end();
+    let resumed = Simulation::<32, 32>::from_checkpoint(&bits.checkpoint()).unwrap();
+    assert_eq!(
+        resumed.map().count_black_tiles(),
+        bits.map().count_black_tiles()
+    );
+    assert!(Simulation::<16, 16>::from_checkpoint(&bits.checkpoint()).is_err());
+}
+
+#[test]
+fn symmetric_ants_keep_the_board_symmetric() {
+    use crate::ColorMap;
+
+    let image = |symmetry: Symmetry, x: usize, y: usize| match symmetry {
+        Symmetry::Mirror => (31 - x, y),
+        Symmetry::Mirror4 => (x, 31 - y),
+        Symmetry::Rotate2 => (31 - x, 31 - y),
+        Symmetry::Rotate4 => (31 - y, x),
+    };
+
+    for symmetry in [
+        Symmetry::Mirror,
+        Symmetry::Mirror4,
+        Symmetry::Rotate2,
+        Symmetry::Rotate4,
+    ] {
+        let rule = "RLR".parse::<Rule>().unwrap();
+        let mut sim = Simulation::new(ColorMap::<32, 32>::new_white(), rule);
+        sim.spawn_symmetric(Pos::new(9, 12), Direction::East, symmetry)
+            .unwrap();
+        assert_eq!(sim.ants().len(), symmetry.ants());
+        sim.run_at_most(2000);
+
+        let colors = sim.map().colors();
+        assert!(colors.iter().any(|&c| c != 0));
+        for y in 0..32 {
+            for x in 0..32 {
+                let (ix, iy) = image(symmetry, x, y);
+                assert_eq!(colors[y * 32 + x], colors[iy * 32 + ix], "{symmetry:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn forks_walk_on_their_own() {
+    let mut sim = Simulation::new(Map::<64, 64>::new_white(), Rule::default()).with_heatmap();
+    sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+    sim.run_at_most(500);
+
+    let mut same = sim.fork();
+    let mut flipped = sim.fork();
+    let pos = MapPos::validate_pos(Pos::new(40, 20)).unwrap();
+    flipped.map.set(pos, !flipped.map().get(pos));
+
+    sim.run_at_most(3000);
+    same.run_at_most(3000);
+    flipped.run_at_most(3000);
+    assert_eq!(same.steps(), sim.steps());
+    assert_eq!(same.map().to_colors(), sim.map().to_colors());
+    assert_eq!(
+        same.heatmap().unwrap().counts(),
+        sim.heatmap().unwrap().counts()
+    );
+    assert_ne!(flipped.map().to_colors(), sim.map().to_colors());
+}
+
+#[test]
+fn tracks_touched_cells() {
+    use crate::Ant;
+
+    let mut map = Map::<64, 64>::new_white();
+    let mut ant = Ant::new(&mut map, Pos::new(32, 32), Direction::North).unwrap();
+    let mut expected = None;
+    for event in ant.steps().take(500) {
+        touch(&mut expected, event.pos.into());
+    }
+    touch(&mut expected, ant.pos().into());
+
+    let mut sim = Simulation::new(Map::<64, 64>::new_white(), Rule::default());
+    assert_eq!(sim.touched(), None);
+    sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+    sim.run_at_most(500);
+    assert_eq!(sim.touched(), expected);
+
+    // Only the colored cells survive a checkpoint, they can't reach further
+    let resumed = Simulation::<64, 64>::from_checkpoint(&sim.checkpoint()).unwrap();
+    let ((min, max), (outer_min, outer_max)) = (resumed.touched().unwrap(), expected.unwrap());
+    assert!(min.x >= outer_min.x && min.y >= outer_min.y);
+    assert!(max.x <= outer_max.x && max.y <= outer_max.y);
+}
+
+#[test]
+fn integrity_checks_find_drift() {
+    let mut sim =
+        Simulation::new(Map::<32, 32>::new_white(), Rule::default()).with_boundary(Boundary::Wrap);
+    sim.spawn(Pos::new(16, 16), Direction::North).unwrap();
+    sim.spawn(Pos::new(3, 3), Direction::East).unwrap();
+    for _ in 0..20 {
+        sim.run_at_most(1000);
+        assert_eq!(sim.check_integrity(), Ok(()));
+    }
+
+    sim.touched = Some((Pos::new(0, 0), Pos::new(1, 1)));
+    assert_eq!(sim.check_integrity(), Err(IntegrityError::Untouched(0)));
+    sim.touched = Some((Pos::new(0, 0), Pos::new(32, 31)));
+    assert!(matches!(
+        sim.check_integrity(),
+        Err(IntegrityError::OffMap(..))
+    ));
+}
+
+#[test]
+fn steps_fit_time_slices() {
+    let mut sim =
+        Simulation::new(Map::<64, 64>::new_white(), Rule::default()).with_boundary(Boundary::Wrap);
+    sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+    assert_eq!(sim.step_for(Duration::ZERO), 0);
+    let taken = sim.step_for(Duration::from_millis(20));
+    assert!(taken > 0);
+    assert_eq!(sim.steps(), taken);
+
+    // An ant that leaves ends the slice early
+    let mut sim = Simulation::new(Map::<16, 16>::new_white(), Rule::default());
+    sim.spawn(Pos::new(8, 8), Direction::North).unwrap();
+    let mut walk = sim.fork();
+    walk.run_until_end();
+    assert_eq!(sim.step_for(Duration::from_secs(60)), walk.steps());
+    assert_eq!(sim.step_for(Duration::from_secs(60)), 0);
+}
+
+#[test]
+fn reflecting_ants_stay_on_the_map() {
+    let mut stop = Simulation::new(Map::<1, 1>::new_white(), Rule::default());
+    let mut reflect =
+        Simulation::new(Map::<1, 1>::new_white(), Rule::default()).with_boundary(Boundary::Reflect);
+    stop.spawn(Pos::new(0, 0), Direction::North).unwrap();
+    reflect.spawn(Pos::new(0, 0), Direction::North).unwrap();
+    assert!(!stop.step());
+    assert!(reflect.step());
+    // Bounced straight back from where the stopped ant walked off
+    let (left, bounced) = (stop.ants()[0], reflect.ants()[0]);
+    assert_eq!(bounced.dir(), left.dir().cw().cw());
+    assert_eq!(Pos::from(bounced.pos()), Pos::from(left.pos()));
+
+    let mut sim = Simulation::new(Map::<16, 16>::new_white(), Rule::default())
+        .with_boundary(Boundary::Reflect);
+    sim.spawn(Pos::new(8, 8), Direction::North).unwrap();
+    assert!(sim.run_at_most(20_000));
+    assert_eq!(sim.touched(), Some((Pos::new(0, 0), Pos::new(15, 15))));
+    assert_eq!("Reflect".parse(), Ok(Boundary::Reflect));
+}
+
+#[test]
+fn obstacles_block_ants() {
+    // A wall across row 2 of a 5x5 map, ants start under it heading up
+    let wall = (0..25).map(|i| i / 5 == 2);
+    let new_sim = |policy| {
+        let mut sim = Simulation::new(Map::<5, 5>::new_white(), "LR".parse().unwrap())
+            .with_obstacles(Obstacles::from_cells(wall.clone()), policy);
+        // White turns left, so facing east it's about to walk north into the wall
+        sim.spawn(Pos::new(2, 3), Direction::East).unwrap();
+        sim
+    };
+    assert!(new_sim(ObstaclePolicy::Reflect)
+        .spawn(Pos::new(0, 2), Direction::North)
+        .is_err());
+
+    let mut reflect = new_sim(ObstaclePolicy::Reflect);
+    assert!(reflect.step());
+    assert_eq!(Pos::from(reflect.ants()[0].pos()), Pos::new(2, 3));
+    assert_eq!(reflect.ants()[0].dir(), Direction::South);
+
+    let mut wait = new_sim(ObstaclePolicy::Wait);
+    assert!(wait.step());
+    assert_eq!(wait.ants()[0].dir(), Direction::North);
+
+    let mut stop = new_sim(ObstaclePolicy::Stop);
+    assert!(!stop.step());
+    assert_eq!(Pos::from(stop.ants()[0].pos()), Pos::new(2, 3));
+
+    // However long it walks, nothing above the wall is touched
+    let mut sim = new_sim(ObstaclePolicy::Reflect);
+    sim.run_at_most(5_000);
+    assert!(sim.touched().is_some_and(|(min, _)| min.y == 3));
+    assert!(sim.map().count_black_tiles() > 0);
+}
+
+#[test]
+fn rules_change_mid_run() {
+    use crate::ColorMap;
+
+    let mut sim = Simulation::new(ColorMap::<32, 32>::new_white(), "LLRR".parse().unwrap());
+    sim.spawn(Pos::new(16, 16), Direction::North).unwrap();
+    sim.run_at_most(300);
+    assert!(sim.map().colors().iter().any(|&color| color > 1));
+
+    // Down to two colors, the cells in 2 and 3 fold onto 0 and 1
+    let before = sim.map().to_colors();
+    sim.change_rule("RL".parse().unwrap());
+    let folded = before.iter().map(|color| color % 2).collect::<Vec<_>>();
+    assert_eq!(sim.map().to_colors(), folded);
+    sim.run_at_most(200);
+    sim.change_rule("LRR".parse().unwrap());
+
+    let steps = sim.rule_changes().iter().map(|(step, _)| *step);
+    assert_eq!(steps.collect::<Vec<_>>(), [300, 500]);
+    assert_eq!(sim.rule().to_string(), "LRR");
+    // Forks carry on with the rule the original has now
+    assert_eq!(sim.fork().rule_changes(), sim.rule_changes());
+}
+
+#[test]
+fn steps_back_to_the_start() {
+    use crate::ColorMap;
+
+    let rule = "LRRL".parse::<Rule>().unwrap();
+    let mut sim = Simulation::new(ColorMap::<16, 16>::new_white(), rule)
+        .with_boundary(Boundary::Wrap)
+        .with_conflict_policy(ConflictPolicy::FlipOnce);
+    sim.spawn(Pos::new(3, 3), Direction::North).unwrap();
+    sim.spawn(Pos::new(3, 3), Direction::West).unwrap();
+    sim.spawn_symmetric(Pos::new(10, 2), Direction::East, Symmetry::Mirror)
+        .unwrap();
+    assert_eq!(sim.step_back(), Err(ReverseError::AtStart));
+    assert_eq!(sim.verify_reversibility(5000), Ok(()));
+
+    sim.run_at_most(700);
+    let colors = sim.map().to_colors();
+    sim.step();
+    sim.step_back().unwrap();
+    assert_eq!((sim.steps(), sim.map().to_colors()), (700, colors));
+    assert_eq!(sim.verify_reversibility(300), Ok(()));
+
+    let mut sim = Simulation::new(Map::<8, 8>::new_white(), Rule::default());
+    sim.spawn(Pos::new(0, 0), Direction::North).unwrap();
+    assert_eq!(sim.verify_reversibility(1), Err(ReverseError::AntLeft(0)));
+
+    let mut sim = Simulation::new(Map::<8, 8>::new_white(), Rule::default())
+        .with_conflict_policy(ConflictPolicy::Exclusive);
+    sim.spawn(Pos::new(4, 4), Direction::North).unwrap();
+    sim.step();
+    assert_eq!(sim.step_back(), Err(ReverseError::Exclusive));
+}
+
+#[test]
+fn stochastic_rules_replay() {
+    let rule = "L,R90".parse::<StochasticRule>().unwrap();
+    let noisy = |seed| {
+        let mut sim = Simulation::new(Map::<64, 64>::new_white(), Rule::default())
+            .with_boundary(Boundary::Wrap)
+            .with_stochastic_rule(rule.clone(), seed);
+        sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+        sim
+    };
+    let mut sim = noisy(1);
+    sim.run_at_most(2000);
+    let mut again = noisy(1);
+    again.run_at_most(2000);
+    let mut other = noisy(2);
+    other.run_at_most(2000);
+    assert_eq!(sim.digest(), again.digest());
+    assert_ne!(sim.map().to_colors(), other.map().to_colors());
+    assert_eq!(sim.step_back(), Err(ReverseError::Stochastic));
+
+    // The dice go on where they stopped
+    let mut resumed = Simulation::<64, 64>::from_checkpoint(&sim.checkpoint())
+        .unwrap()
+        .with_boundary(Boundary::Wrap);
+    assert_eq!(resumed.stochastic_rule(), Some(&rule));
+    resumed.run_at_most(2000);
+    sim.run_at_most(2000);
+    assert_eq!(resumed.digest(), sim.digest());
+
+    sim.change_rule(Rule::default());
+    assert_eq!(sim.stochastic_rule(), None);
+}
+
+#[test]
+fn digests_stay_put() {
+    use crate::ColorMap;
+
+    let mut sim = Simulation::new(Map::<128, 128>::new_white(), Rule::default());
+    let mut colors = Simulation::new(ColorMap::<128, 128>::new_white(), Rule::default());
+    sim.spawn(Pos::new(64, 64), Direction::North).unwrap();
+    colors.spawn(Pos::new(64, 64), Direction::North).unwrap();
+    assert_eq!(sim.map().digest(), colors.map().digest());
+    assert_eq!(sim.digest(), colors.digest());
+
+    // Golden values of the classic ant, they only change if the walk does
+    sim.run_at_most(11_000);
+    colors.run_at_most(11_000);
+    assert_eq!(sim.map().digest(), 0xde6e_a886_58ac_10b9);
+    assert_eq!(sim.digest(), 0xe19e_ab14_2265_d982);
+    assert_eq!(colors.digest(), sim.digest());
+
+    sim.step();
+    assert_ne!(colors.digest(), sim.digest());
+}
+
+#[test]
+fn ants_walk_over_live_fields() {
+    // A blinker by the ant's path, the ant alone never flips the same cells
+    let blinker = [(20, 30), (21, 30), (22, 30)];
+    let mut map = Map::<64, 64>::new_white();
+    for (x, y) in blinker {
+        map.set(MapPos::validate_pos(Pos::new(x, y)).unwrap(), false);
+    }
+    let mut alone = Simulation::new(map.clone(), Rule::default());
+    let mut sim = Simulation::new(map, Rule::default()).with_automaton(LifeRule::life(), 10);
+    for sim in [&mut alone, &mut sim] {
+        sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+    }
+    alone.run_at_most(9);
+    sim.run_at_most(9);
+    assert_eq!(sim.map().digest(), alone.map().digest());
+
+    // The tenth step is followed by a generation, the blinker turns upright
+    sim.step();
+    let cell = |sim: &Simulation<64, 64>, x, y| {
+        sim.map()
+            .color(MapPos::validate_pos(Pos::new(x, y)).unwrap())
+    };
+    assert_eq!((cell(&sim, 21, 29), cell(&sim, 20, 30)), (1, 0));
+    assert_eq!(sim.step_back(), Err(ReverseError::Automaton));
+    assert_eq!(sim.fork().map().digest(), sim.map().digest());
+}
+
+#[test]
+fn u_turns_and_straight_on() {
+    use crate::{Ant, ColorMap, UnboundedAnt};
+
+    // An ant walks the same over any board, and back the same way
+    let rule = "RLUN".parse::<Rule>().unwrap();
+    let mut map = ColorMap::<32, 32>::new_white();
+    let mut ant =
+        Ant::with_rule(&mut map, Pos::new(16, 16), Direction::North, rule.clone()).unwrap();
+    ant.walk_at_most(2000);
+    let (pos, dir) = (Pos::from(ant.pos()), ant.dir());
+
+    let mut sim = Simulation::new(ColorMap::<32, 32>::new_white(), rule.clone());
+    sim.spawn(Pos::new(16, 16), Direction::North).unwrap();
+    sim.run_at_most(2000);
+    assert_eq!(Pos::from(sim.ants()[0].pos()), pos);
+    assert_eq!(sim.ants()[0].dir(), dir);
+    assert_eq!(sim.map().to_colors(), map.to_colors());
+
+    // Mirrored copies keep U-turns and going straight as they are
+    let mut sim = Simulation::new(ColorMap::<32, 32>::new_white(), rule);
+    sim.spawn_symmetric(Pos::new(10, 16), Direction::East, Symmetry::Mirror)
+        .unwrap();
+    sim.run_at_most(100);
+    let colors = sim.map().to_colors();
+    assert!((0..32).all(|y| (0..32).all(|x| colors[y * 32 + x] == colors[y * 32 + 31 - x])));
+    assert_eq!(sim.verify_reversibility(100), Ok(()));
+
+    // White cells send the ant back the way it came, it walks straight over black ones
+    let mut ant = UnboundedAnt::with_rule(Pos::new(0, 0), Direction::East, "UN".parse().unwrap());
+    ant.walk_steps(3);
+    assert_eq!((ant.pos(), ant.dir()), (Pos::new(1, 0), Direction::East));
+}
+
+#[test]
+fn parallel_runs_match_serial_ones() {
+    use crate::ColorMap;
+
+    let run = |conflict, boundary, threads| {
+        let mut sim = Simulation::new(ColorMap::<1024, 1024>::new_white(), "LRRL".parse().unwrap())
+            .with_conflict_policy(conflict)
+            .with_boundary(boundary)
+            .with_heatmap();
+        for y in [150, 450, 750] {
+            for x in [150, 450, 750] {
+                sim.spawn(Pos::new(x, y), Direction::North).unwrap();
+            }
+        }
+        // Close enough to share cells, and one near the edge to leave the map
+        sim.spawn(Pos::new(452, 450), Direction::South).unwrap();
+        sim.spawn(Pos::new(1020, 10), Direction::East).unwrap();
+        let on_map = match threads {
+            1 => sim.run_at_most(1000),
+            threads => sim.run_parallel(1000, threads),
+        };
+        let ants = sim
+            .ants()
+            .iter()
+            .map(|ant| (Pos::from(ant.pos()), ant.dir(), ant.is_on_map()))
+            .collect::<Vec<_>>();
+        let heat = sim.heatmap().unwrap().counts().to_vec();
+        let touched = sim.touched();
+        (
+            on_map,
+            sim.steps(),
+            sim.turns(),
+            touched,
+            ants,
+            heat,
+            sim.map().digest(),
+        )
+    };
+
+    for conflict in [
+        ConflictPolicy::Sequential,
+        ConflictPolicy::FlipOnce,
+        ConflictPolicy::Exclusive,
+    ] {
+        let serial = run(conflict, Boundary::Stop, 1);
+        assert_eq!(run(conflict, Boundary::Stop, 3), serial);
+        assert_eq!(run(conflict, Boundary::Stop, 0), serial);
+    }
+    let serial = run(ConflictPolicy::Sequential, Boundary::Wrap, 1);
+    assert_eq!(run(ConflictPolicy::Sequential, Boundary::Wrap, 4), serial);
+
+    // Every ant leaves the map within the batch, the steps stop where they'd stop
+    let mut sim = Simulation::new(Map::<600, 600>::new_white(), Rule::default());
+    sim.spawn(Pos::new(0, 0), Direction::North).unwrap();
+    sim.spawn(Pos::new(599, 599), Direction::South).unwrap();
+    assert!(!sim.run_parallel(1000, 2));
+    assert_eq!(sim.steps(), 1);
+}
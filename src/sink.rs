@@ -0,0 +1,106 @@
+use std::io;
+
+use crate::{CellStore, Frame, Simulation};
+
+/// Somewhere the frames of a run go as it goes, like an animation, a video stream or a
+/// folder of snapshots, so one run can feed any number of them alike
+///
+/// ```
+/// use antventure::{
+///     Direction, Frame, Map, OutputSink, Pos, RawFormat, RawVideo, RunSummary, Simulation,
+/// };
+///
+/// let mut sim = Simulation::new(Map::<16, 16>::new_white(), Default::default());
+/// sim.spawn(Pos::new(8, 8), Direction::North).unwrap();
+/// let mut sinks: Vec<Box<dyn OutputSink>> = vec![
+///     Box::new(RawVideo::new(Vec::new(), RawFormat::Gray, 2, None)),
+///     Box::new(RawVideo::new(Vec::new(), RawFormat::Rgb, 2, None)),
+/// ];
+/// while sim.run_at_most(10) {
+///     let frame = Frame::new(sim.map(), 2, None);
+///     for sink in &mut sinks {
+///         sink.accept_frame(&frame, sim.steps()).unwrap();
+///     }
+/// }
+/// for sink in &mut sinks {
+///     sink.accept_summary(&RunSummary::of(&sim)).unwrap();
+///     sink.finalize().unwrap();
+/// }
+/// ```
+pub trait OutputSink {
+    /// Take the board as it is at `step`
+    fn accept_frame(&mut self, frame: &Frame, step: u64) -> io::Result<()>;
+
+    /// Take how the run ended, after its last frame. Nothing is done with it by default
+    fn accept_summary(&mut self, _summary: &RunSummary) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Write out whatever is left, no frames are taken after
+    fn finalize(&mut self) -> io::Result<()>;
+
+    /// Frames taken so far
+    fn frames(&self) -> usize;
+}
+
+/// How a run ended, for sinks that show more than the board
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RunSummary {
+    pub steps: u64,
+    /// Cells of any color but white
+    pub colored: usize,
+    /// Ants still on the map
+    pub on_map: usize,
+}
+
+impl RunSummary {
+    pub fn of<const W: usize, const H: usize, M: CellStore<W, H>>(
+        sim: &Simulation<W, H, M>,
+    ) -> Self {
+        Self {
+            steps: sim.steps(),
+            colored: sim.map().count_colored(),
+            on_map: sim.ants().iter().filter(|ant| ant.is_on_map()).count(),
+        }
+    }
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn sinks_take_the_same_frames() {
+    use crate::{
+        Animation, AnimationFormat, ColorMap, Direction, Pos, RawFormat, RawVideo, SnapshotWriter,
+    };
+
+    let dir = std::env::temp_dir().join("antventure_sinks");
+    let mut sim = Simulation::new(ColorMap::<32, 24>::new_white(), "LLR".parse().unwrap());
+    sim.spawn(Pos::new(16, 12), Direction::North).unwrap();
+    let mut sinks: Vec<Box<dyn OutputSink>> = vec![
+        Box::new(RawVideo::new(Vec::new(), RawFormat::Gray, 3, None)),
+        Box::new(SnapshotWriter::new(
+            dir.join("{step}.png").display().to_string(),
+            3,
+        )),
+        Box::new(
+            Animation::create(dir.with_extension("gif"), AnimationFormat::Gif, 32, 24, 3).unwrap(),
+        ),
+    ];
+    for _ in 0..5 {
+        sim.run_at_most(20);
+        let frame = Frame::new(sim.map(), 3, None);
+        for sink in &mut sinks {
+            sink.accept_frame(&frame, sim.steps()).unwrap();
+        }
+    }
+    let summary = RunSummary::of(&sim);
+    assert_eq!((summary.steps, summary.on_map), (100, 1));
+    for sink in &mut sinks {
+        sink.accept_summary(&summary).unwrap();
+        sink.finalize().unwrap();
+        assert_eq!(sink.frames(), 5);
+    }
+    assert!(dir.join("100.png").exists());
+    assert!(std::fs::metadata(dir.with_extension("gif")).unwrap().len() > 0);
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::remove_file(dir.with_extension("gif")).ok();
+}
@@ -0,0 +1,203 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+    thread::{self, JoinHandle},
+};
+
+use png::{BitDepth, ColorType, Compression, Encoder};
+
+use crate::{render::gray_depth, CellStore, Frame, MapPos, OutputSink, Pos};
+
+/// Grayscale PNGs of a map as a run goes, named after a template like
+/// `out/frame_{step}.png`
+///
+/// Every snapshot is packed into the same buffer, which is then encoded on a thread
+/// of its own while the ants keep walking. Saving the next one waits for the
+/// previous file to be written, so there is never more than one in flight
+pub struct SnapshotWriter {
+    template: String,
+    colors: usize,
+    rows: Vec<u8>,
+    pending: Option<JoinHandle<io::Result<Vec<u8>>>>,
+    saved: usize,
+}
+
+impl SnapshotWriter {
+    /// Snapshots of maps with `colors` colors, `{step}` in `template` is replaced
+    /// with the step the snapshot was taken at
+    pub fn new(template: impl Into<String>, colors: usize) -> Self {
+        Self {
+            template: template.into(),
+            colors,
+            rows: Vec::new(),
+            pending: None,
+            saved: 0,
+        }
+    }
+
+    /// File the snapshot at `step` goes to
+    pub fn path(&self, step: u64) -> PathBuf {
+        self.template.replace("{step}", &step.to_string()).into()
+    }
+
+    /// Start writing `map` as it is at `step`, returns the file it goes to
+    ///
+    /// Errors are those of the previous snapshot, or of creating this one's directory
+    pub fn save<const W: usize, const H: usize>(
+        &mut self,
+        map: &impl CellStore<W, H>,
+        step: u64,
+    ) -> io::Result<PathBuf> {
+        let color = |x: usize, y: usize| {
+            let pos =
+                MapPos::validate_pos(Pos::new(x as _, y as _)).expect("x and y are within the map");
+            map.color(pos)
+        };
+        let (width, height) = map.size();
+        self.save_cells(width, height, color, step)
+    }
+
+    fn save_cells(
+        &mut self,
+        width: usize,
+        height: usize,
+        color: impl Fn(usize, usize) -> u8,
+        step: u64,
+    ) -> io::Result<PathBuf> {
+        let mut rows = match self.pending.take() {
+            Some(pending) => pending.join().expect("snapshot thread panicked")?,
+            None => std::mem::take(&mut self.rows),
+        };
+        let depth = gray_depth(self.colors);
+        pack_rows(width, height, color, self.colors, depth, &mut rows);
+
+        let path = self.path(step);
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = path.clone();
+        self.pending = Some(thread::spawn(move || {
+            write_png(&file, width, height, depth, &rows)?;
+            Ok(rows)
+        }));
+        self.saved += 1;
+        Ok(path)
+    }
+
+    /// Snapshots started so far
+    pub fn saved(&self) -> usize {
+        self.saved
+    }
+
+    /// Wait for the last snapshot to be written
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some(pending) = self.pending.take() {
+            self.rows = pending.join().expect("snapshot thread panicked")?;
+        }
+        Ok(())
+    }
+}
+
+/// Every frame is a snapshot named after its step
+impl OutputSink for SnapshotWriter {
+    fn accept_frame(&mut self, frame: &Frame, step: u64) -> io::Result<()> {
+        let color = |x, y| frame.cells[y * frame.width + x];
+        self.save_cells(frame.width, frame.height, color, step)
+            .map(drop)
+    }
+
+    fn finalize(&mut self) -> io::Result<()> {
+        self.finish()
+    }
+
+    fn frames(&self) -> usize {
+        self.saved
+    }
+}
+
+impl Drop for SnapshotWriter {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Pack the `color` of every cell into grayscale PNG rows the way
+/// [`pack_gray`](crate::render::pack_gray) does, but into `rows` rather than a new buffer
+fn pack_rows(
+    width: usize,
+    height: usize,
+    color: impl Fn(usize, usize) -> u8,
+    colors: usize,
+    depth: BitDepth,
+    rows: &mut Vec<u8>,
+) {
+    let bits = depth as usize;
+    let per_byte = 8 / bits;
+    let white = (1 << bits) - 1;
+    let darkest = colors.saturating_sub(1).max(1);
+
+    rows.clear();
+    rows.reserve(width.div_ceil(per_byte) * height);
+    for y in 0..height {
+        for x in (0..width).step_by(per_byte) {
+            let byte = (x..width.min(x + per_byte))
+                .enumerate()
+                .fold(0, |byte, (i, x)| {
+                    let level = (white - color(x, y) as usize * white / darkest) as u8;
+                    byte | level << (8 - bits * (i + 1))
+                });
+            rows.push(byte);
+        }
+    }
+}
+
+fn write_png(
+    file: &Path,
+    width: usize,
+    height: usize,
+    depth: BitDepth,
+    rows: &[u8],
+) -> io::Result<()> {
+    let w = BufWriter::new(File::create(file)?);
+    let mut encoder = Encoder::new(w, width as _, height as _);
+    encoder.set_color(ColorType::Grayscale);
+    encoder.set_depth(depth);
+    // Snapshots are many and big, speed matters more than size here
+    encoder.set_compression(Compression::Fast);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(rows))
+        .map_err(io::Error::other)
+}
+
+#[test]
+fn snapshots_follow_the_template() {
+    use crate::{render::pack_gray, ColorMap};
+
+    let dir = std::env::temp_dir().join("antventure_snapshots_follow_the_template");
+    let _ = fs::remove_dir_all(&dir);
+    let template = dir.join("frame_{step}.png").display().to_string();
+
+    let mut map = ColorMap::<9, 3>::new_white();
+    let mut writer = SnapshotWriter::new(template, 3);
+    let first = writer.save(&map, 0).unwrap();
+    map.set_color(MapPos::validate_pos(Pos::new(8, 2)).unwrap(), 2);
+    map.set_color(MapPos::validate_pos(Pos::new(1, 0)).unwrap(), 1);
+    let second = writer.save(&map, 100).unwrap();
+    writer.finish().unwrap();
+    assert_eq!(writer.saved(), 2);
+    assert_eq!(first, dir.join("frame_0.png"));
+    assert_eq!(second, dir.join("frame_100.png"));
+
+    let decoder = png::Decoder::new(File::open(&second).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).unwrap();
+    assert_eq!((info.width, info.height), (9, 3));
+    assert_eq!(info.bit_depth, BitDepth::Two);
+    let expected = pack_gray(&map.to_colors(), 9, 3, BitDepth::Two);
+    assert_eq!(&data[..info.buffer_size()], &expected[..]);
+    assert!(first.exists());
+    fs::remove_dir_all(&dir).ok();
+}
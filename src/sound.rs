@@ -0,0 +1,311 @@
+use std::{
+    f64::consts::TAU,
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use crate::{CellStore, MapPos, Observer, Pos, Simulation};
+
+/// Bytes before the samples of a 16-bit mono WAV file
+const HEADER: u32 = 44;
+
+/// Loudness steps glide towards, per sample, so changes don't click
+const GLIDE: f64 = 0.01;
+
+/// What sets the pitch of every step of a [`SoundTrack`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Pitch {
+    /// Low for left turns, high for right ones
+    #[default]
+    Turns,
+    /// Rises as the first ant gets further from where it was first heard
+    Distance,
+    /// Rises with the color the first ant painted
+    Color,
+}
+
+impl FromStr for Pitch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "turns" => Ok(Pitch::Turns),
+            "distance" => Ok(Pitch::Distance),
+            "color" => Ok(Pitch::Color),
+            _ => Err(format!(
+                "unknown pitch {s:?}, expected one of turns, distance, color"
+            )),
+        }
+    }
+}
+
+/// What sets the loudness of every step of a [`SoundTrack`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Volume {
+    #[default]
+    Constant,
+    /// Loud when cells are painted, quiet when they go back to white
+    Flips,
+    /// Grows as the first ant gets further from where it was first heard
+    Distance,
+}
+
+impl FromStr for Volume {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "constant" => Ok(Volume::Constant),
+            "flips" => Ok(Volume::Flips),
+            "distance" => Ok(Volume::Distance),
+            _ => Err(format!(
+                "unknown volume {s:?}, expected one of constant, flips, distance"
+            )),
+        }
+    }
+}
+
+/// How a [`SoundTrack`] turns steps into sound
+#[derive(Clone, PartialEq, Debug)]
+pub struct Sound {
+    pub pitch: Pitch,
+    pub volume: Volume,
+    /// How long every step sounds, 44100 samples are a second at the default rate
+    pub samples_per_step: usize,
+    pub sample_rate: u32,
+    /// Lowest and highest pitch in Hz, pitches in between are spread evenly over octaves
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Default for Sound {
+    fn default() -> Self {
+        Self {
+            pitch: Pitch::default(),
+            volume: Volume::default(),
+            samples_per_step: 32,
+            sample_rate: 44100,
+            low: 220.0,
+            high: 880.0,
+        }
+    }
+}
+
+/// Plays a run as a sine tone into a 16-bit mono WAV file, a few samples per step, to
+/// listen to the chaos settling into a highway
+///
+/// Samples go straight to the file. Its header is only right once the run finishes,
+/// see [`Simulation::finish`]
+pub struct SoundTrack<Wr: Write + Seek = BufWriter<File>> {
+    out: Wr,
+    sound: Sound,
+    samples: u64,
+    phase: f64,
+    amplitude: f64,
+    /// Left and right turns before the current step
+    turns: [u64; 2],
+    /// Where the first ant was first heard
+    start: Option<Pos>,
+    /// Cells turned white and cells painted during the current step
+    flips: [usize; 2],
+    /// Last color the first ant painted
+    color: u8,
+    error: Option<io::Error>,
+}
+
+impl SoundTrack {
+    /// Play into a new file
+    pub fn create(file: impl AsRef<Path>, sound: Sound) -> io::Result<Self> {
+        let out = BufWriter::new(File::create(file)?);
+        Ok(Self::new(out, sound))
+    }
+}
+
+impl<Wr: Write + Seek> SoundTrack<Wr> {
+    pub fn new(out: Wr, sound: Sound) -> Self {
+        let mut track = Self {
+            out,
+            sound,
+            samples: 0,
+            phase: 0.0,
+            amplitude: 0.0,
+            turns: [0; 2],
+            start: None,
+            flips: [0; 2],
+            color: 0,
+            error: None,
+        };
+        // Sizes are filled in at the end
+        track.write(|out, sound| write_header(out, sound.sample_rate, 0));
+        track
+    }
+
+    /// Samples written so far
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// Length of the sound so far
+    pub fn seconds(&self) -> f64 {
+        self.samples as f64 / self.sound.sample_rate as f64
+    }
+
+    /// First error writing the sound, if there was one
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    pub fn into_inner(self) -> Wr {
+        self.out
+    }
+
+    fn write(&mut self, f: impl FnOnce(&mut Wr, &Sound) -> io::Result<()>) {
+        if self.error.is_none() {
+            self.error = f(&mut self.out, &self.sound).err();
+        }
+    }
+
+    /// How far the first ant on the map is from where it was first heard, `0` to `1`
+    /// for half the map size
+    fn distance<const W: usize, const H: usize, M: CellStore<W, H>>(
+        &mut self,
+        sim: &Simulation<W, H, M>,
+    ) -> f64 {
+        let Some(ant) = sim.ants().iter().find(|ant| ant.is_on_map()) else {
+            return 0.0;
+        };
+        let pos = Pos::from(ant.pos());
+        let start = *self.start.get_or_insert(pos);
+        let (dx, dy) = ((pos.x - start.x) as f64, (pos.y - start.y) as f64);
+        (dx.hypot(dy) / (W.max(H) as f64 / 2.0)).min(1.0)
+    }
+}
+
+fn write_header(out: &mut impl Write, sample_rate: u32, data: u32) -> io::Result<()> {
+    out.write_all(b"RIFF")?;
+    out.write_all(&(HEADER - 8 + data).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    // PCM, one channel
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&sample_rate.to_le_bytes())?;
+    out.write_all(&(sample_rate * 2).to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&data.to_le_bytes())
+}
+
+/// Plays every step and fixes the header at the end of the run
+impl<const W: usize, const H: usize, M: CellStore<W, H>, Wr: Write + Seek> Observer<W, H, M>
+    for SoundTrack<Wr>
+{
+    fn on_step(&mut self, sim: &Simulation<W, H, M>) {
+        let distance = self.distance(sim);
+        let [left, right] = sim.turns();
+        let turned = [left - self.turns[0], right - self.turns[1]];
+        self.turns = [left, right];
+        let flips = std::mem::take(&mut self.flips);
+
+        let pitch = match self.sound.pitch {
+            Pitch::Turns => match turned {
+                [0, 0] => 0.5,
+                [left, right] => right as f64 / (left + right) as f64,
+            },
+            Pitch::Distance => distance,
+            Pitch::Color => {
+                let darkest = sim.rule().colors().saturating_sub(1).max(1);
+                self.color as f64 / darkest as f64
+            }
+        };
+        let loudness = match self.sound.volume {
+            Volume::Constant => 1.0,
+            Volume::Flips => match flips {
+                [0, 0] => 0.25,
+                [white, painted] => 0.25 + 0.75 * painted as f64 / (white + painted) as f64,
+            },
+            Volume::Distance => 0.25 + 0.75 * distance,
+        };
+
+        let Sound {
+            samples_per_step,
+            sample_rate,
+            low,
+            high,
+            ..
+        } = self.sound;
+        let step = TAU * low * (high / low).powf(pitch) / sample_rate as f64;
+        let mut bytes = Vec::with_capacity(samples_per_step * 2);
+        for _ in 0..samples_per_step {
+            self.amplitude += (loudness - self.amplitude) * GLIDE;
+            self.phase = (self.phase + step) % TAU;
+            let sample = self.phase.sin() * self.amplitude * 0.8 * i16::MAX as f64;
+            bytes.extend((sample as i16).to_le_bytes());
+        }
+        self.write(|out, _| out.write_all(&bytes));
+        self.samples += samples_per_step as u64;
+    }
+
+    fn on_cell_flip(&mut self, ant: usize, _pos: MapPos<'static, W, H>, _from: u8, to: u8) {
+        self.flips[(to != 0) as usize] += 1;
+        if ant == 0 {
+            self.color = to;
+        }
+    }
+
+    fn on_finish(&mut self, _sim: &Simulation<W, H, M>) {
+        let data = (self.samples * 2).min((u32::MAX - HEADER) as u64) as u32;
+        self.write(|out, sound| {
+            out.seek(SeekFrom::Start(0))?;
+            write_header(out, sound.sample_rate, data)?;
+            out.seek(SeekFrom::End(0))?;
+            out.flush()
+        });
+    }
+}
+
+#[test]
+fn runs_sound_like_sine_waves() {
+    use std::io::Cursor;
+
+    use crate::{Direction, Map, Rule};
+
+    assert_eq!("Distance".parse(), Ok(Pitch::Distance));
+    assert_eq!("flips".parse(), Ok(Volume::Flips));
+    assert!("loud".parse::<Volume>().is_err());
+
+    let sound = Sound {
+        samples_per_step: 10,
+        volume: Volume::Flips,
+        ..Sound::default()
+    };
+    let track = SoundTrack::new(Cursor::new(Vec::new()), sound);
+    let mut sim = Simulation::new(Map::<16, 16>::new_white(), Rule::default()).with_observer(track);
+    sim.spawn(Pos::new(8, 8), Direction::North).unwrap();
+    sim.run_at_most(100);
+    sim.finish();
+
+    let track = sim.observer::<SoundTrack<Cursor<Vec<u8>>>>().unwrap();
+    assert!(track.error().is_none());
+    assert_eq!(track.samples(), 1000);
+    let wav = track.out.get_ref();
+    assert_eq!(wav.len(), HEADER as usize + 2000);
+    assert_eq!(&wav[..4], b"RIFF");
+    assert_eq!(&wav[4..8], &(36u32 + 2000).to_le_bytes());
+    assert_eq!(&wav[24..28], &44100u32.to_le_bytes());
+    assert_eq!(&wav[40..44], &2000u32.to_le_bytes());
+
+    let samples = wav[44..]
+        .chunks(2)
+        .map(|s| i16::from_le_bytes([s[0], s[1]]))
+        .collect::<Vec<_>>();
+    // Fading in from silence, never clipping
+    assert!(samples[0].abs() < 100);
+    assert!(samples.iter().any(|&s| s > i16::MAX / 4));
+    assert!(samples
+        .iter()
+        .all(|&s| s.unsigned_abs() <= (0.8 * i16::MAX as f64) as u16));
+}
@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+
+use crate::{Direction, Pos, Rule};
+
+/// Side of a square [`BitChunk`] in cells
+pub const CHUNK_SIZE: usize = 64;
+
+/// Position of a chunk, the chunk at `(x, y)` holds cells
+/// `x * CHUNK_SIZE..(x + 1) * CHUNK_SIZE` horizontally and the same vertically
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct ChunkCoord {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl ChunkCoord {
+    /// Chunk holding `pos` and the cell offset inside it
+    pub const fn of(pos: Pos) -> (Self, usize, usize) {
+        let size = CHUNK_SIZE as isize;
+        let coord = Self {
+            x: pos.x.div_euclid(size),
+            y: pos.y.div_euclid(size),
+        };
+        (
+            coord,
+            pos.x.rem_euclid(size) as _,
+            pos.y.rem_euclid(size) as _,
+        )
+    }
+
+    /// Top left cell of the chunk
+    pub const fn origin(self) -> Pos {
+        Pos::new(self.x * CHUNK_SIZE as isize, self.y * CHUNK_SIZE as isize)
+    }
+
+    /// Smallest of either coordinate, the top left corner of a box around both
+    fn min(self, other: Self) -> Self {
+        Self {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y),
+        }
+    }
+
+    fn max(self, other: Self) -> Self {
+        Self {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+        }
+    }
+}
+
+/// `CHUNK_SIZE`x`CHUNK_SIZE` cells, a row per word with bit `x` set for black cells
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BitChunk([u64; CHUNK_SIZE]);
+
+impl BitChunk {
+    const fn new_white() -> Self {
+        Self([0; CHUNK_SIZE])
+    }
+
+    pub fn is_black(&self, x: usize, y: usize) -> bool {
+        self.0[y] >> x & 1 == 1
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, is_black: bool) {
+        match is_black {
+            true => self.0[y] |= 1 << x,
+            false => self.0[y] &= !(1 << x),
+        }
+    }
+
+    /// Row `y` with bit `x` set for black cells
+    pub fn row(&self, y: usize) -> u64 {
+        self.0[y]
+    }
+
+    pub fn count_black_tiles(&self) -> usize {
+        self.0.iter().map(|row| row.count_ones() as usize).sum()
+    }
+}
+
+/// Cells the window of a [`SparseMap`] kept dense spans at most by default, 32 MiB of
+/// bits
+pub const DENSE_LIMIT: usize = 1 << 28;
+
+/// How a [`SparseMap`] keeps its chunks
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Storage {
+    /// In a square window grown as it's written out of, where finding a chunk is
+    /// arithmetic instead of a hash lookup
+    Dense,
+    /// In a hash map, taking no room for chunks never written however far apart the
+    /// written ones are
+    Chunked,
+}
+
+/// A [`SparseMap`] moved its chunks from one [`Storage`] to the other
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Migration {
+    /// Storage the chunks are in since
+    pub to: Storage,
+    /// Chunks written by then
+    pub chunks: usize,
+}
+
+/// Endless white board, only chunks something was written to are stored
+///
+/// Chunks start out in a dense window around the first one written, which doubles
+/// in size as writes land outside it. Once it would span more than the dense limit
+/// they move to a hash map, every move is kept in [`migrations`](Self::migrations)
+#[derive(Clone)]
+pub struct SparseMap {
+    cells: Cells,
+    dense_limit: usize,
+    migrations: Vec<Migration>,
+}
+
+#[derive(Clone)]
+enum Cells {
+    Dense(Window),
+    Chunked(HashMap<ChunkCoord, BitChunk>),
+}
+
+/// `side`x`side` chunks with `origin` at the top left, in row-major order
+#[derive(Clone, Default)]
+struct Window {
+    origin: ChunkCoord,
+    side: usize,
+    chunks: Vec<BitChunk>,
+    written: Vec<bool>,
+}
+
+impl Window {
+    fn index(&self, coord: ChunkCoord) -> Option<usize> {
+        let x = usize::try_from(coord.x - self.origin.x).ok()?;
+        let y = usize::try_from(coord.y - self.origin.y).ok()?;
+        (x < self.side && y < self.side).then_some(y * self.side + x)
+    }
+
+    fn get(&self, coord: ChunkCoord) -> Option<&BitChunk> {
+        let i = self.index(coord)?;
+        self.written[i].then_some(&self.chunks[i])
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (ChunkCoord, &BitChunk)> {
+        let (origin, side) = (self.origin, self.side as isize);
+        self.chunks
+            .iter()
+            .zip(&self.written)
+            .enumerate()
+            .filter(|(_, (_, written))| **written)
+            .map(move |(i, (chunk, _))| {
+                let (x, y) = (i as isize % side, i as isize / side);
+                let coord = ChunkCoord {
+                    x: origin.x + x,
+                    y: origin.y + y,
+                };
+                (coord, chunk)
+            })
+    }
+
+    /// Smallest window twice as big on a side as it takes, or one chunk, to hold
+    /// chunks `min..=max`. Nothing is allocated for it before [`SparseMap::regrow`]
+    fn around(min: ChunkCoord, max: ChunkCoord) -> Self {
+        let span = (max.x - min.x).max(max.y - min.y) as usize + 1;
+        let side = match span {
+            1 => 1,
+            _ => span.saturating_mul(2).next_power_of_two(),
+        };
+        let margin = (side - span) as isize / 2;
+        Self {
+            origin: ChunkCoord {
+                x: min.x - margin,
+                y: min.y - margin,
+            },
+            side,
+            ..Default::default()
+        }
+    }
+
+    fn cells(&self) -> usize {
+        self.side.saturating_mul(CHUNK_SIZE).saturating_pow(2)
+    }
+}
+
+impl Default for SparseMap {
+    fn default() -> Self {
+        Self::with_dense_limit(DENSE_LIMIT)
+    }
+}
+
+impl SparseMap {
+    pub fn new_white() -> Self {
+        Self::default()
+    }
+
+    /// White board whose dense window spans at most `cells` cells, `0` keeps every
+    /// chunk in a hash map from the start
+    pub fn with_dense_limit(cells: usize) -> Self {
+        Self {
+            cells: match cells {
+                0 => Cells::Chunked(HashMap::new()),
+                _ => Cells::Dense(Window::default()),
+            },
+            dense_limit: cells,
+            migrations: Vec::new(),
+        }
+    }
+
+    pub fn is_black(&self, pos: Pos) -> bool {
+        let (coord, x, y) = ChunkCoord::of(pos);
+        self.chunk(coord).is_some_and(|chunk| chunk.is_black(x, y))
+    }
+
+    pub fn set(&mut self, pos: Pos, is_black: bool) {
+        let (coord, x, y) = ChunkCoord::of(pos);
+        self.chunk_mut(coord).set(x, y, is_black);
+    }
+
+    pub fn chunk(&self, coord: ChunkCoord) -> Option<&BitChunk> {
+        match &self.cells {
+            Cells::Dense(window) => window.get(coord),
+            Cells::Chunked(chunks) => chunks.get(&coord),
+        }
+    }
+
+    /// Chunks written so far, in no particular order
+    pub fn chunks(&self) -> impl Iterator<Item = (ChunkCoord, &BitChunk)> {
+        let (dense, chunked) = match &self.cells {
+            Cells::Dense(window) => (Some(window.iter()), None),
+            Cells::Chunked(chunks) => (None, Some(chunks.iter())),
+        };
+        let chunked = chunked.into_iter().flatten();
+        dense
+            .into_iter()
+            .flatten()
+            .chain(chunked.map(|(&coord, chunk)| (coord, chunk)))
+    }
+
+    pub fn count_black_tiles(&self) -> usize {
+        self.chunks()
+            .map(|(_, chunk)| chunk.count_black_tiles())
+            .sum()
+    }
+
+    /// Top left and bottom right cells (inclusive) of the touched chunks,
+    /// `None` if nothing was written yet
+    pub fn bounding_box(&self) -> Option<(Pos, Pos)> {
+        let (min, max) = self.chunk_bounds()?;
+        let end = max.origin();
+        let last = CHUNK_SIZE as isize - 1;
+        Some((min.origin(), Pos::new(end.x + last, end.y + last)))
+    }
+
+    pub fn storage(&self) -> Storage {
+        match self.cells {
+            Cells::Dense(_) => Storage::Dense,
+            Cells::Chunked(_) => Storage::Chunked,
+        }
+    }
+
+    /// Every move between storages so far, in order
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Let the dense window span at most `cells` cells from now on. Chunks move to a
+    /// hash map if the window is bigger, or back into a window if they fit in one
+    pub fn set_dense_limit(&mut self, cells: usize) {
+        self.dense_limit = cells;
+        match &self.cells {
+            Cells::Dense(window) if window.cells() > cells || cells == 0 => self.migrate(None),
+            Cells::Chunked(_) => {
+                let window = match self.chunk_bounds() {
+                    Some((min, max)) => Window::around(min, max),
+                    None => Window::default(),
+                };
+                if window.cells() <= cells && cells > 0 {
+                    self.migrate(Some(window));
+                }
+            }
+            Cells::Dense(_) => {}
+        }
+    }
+
+    fn chunk_mut(&mut self, coord: ChunkCoord) -> &mut BitChunk {
+        if let Cells::Dense(window) = &self.cells {
+            if window.index(coord).is_none() {
+                let (min, max) = match self.chunk_bounds() {
+                    Some((min, max)) => (min.min(coord), max.max(coord)),
+                    None => (coord, coord),
+                };
+                let grown = Window::around(min, max);
+                match grown.cells() <= self.dense_limit {
+                    true => self.regrow(grown),
+                    false => self.migrate(None),
+                }
+            }
+        }
+        match &mut self.cells {
+            Cells::Dense(window) => {
+                let i = window
+                    .index(coord)
+                    .expect("the window was grown to hold it");
+                window.written[i] = true;
+                &mut window.chunks[i]
+            }
+            Cells::Chunked(chunks) => chunks.entry(coord).or_insert_with(BitChunk::new_white),
+        }
+    }
+
+    /// Move the chunks into `window`, one big enough to hold them
+    fn regrow(&mut self, mut window: Window) {
+        window.chunks = vec![BitChunk::new_white(); window.side * window.side];
+        window.written = vec![false; window.side * window.side];
+        for (coord, chunk) in self.chunks() {
+            let i = window
+                .index(coord)
+                .expect("the new window holds the old one");
+            window.chunks[i] = chunk.clone();
+            window.written[i] = true;
+        }
+        self.cells = Cells::Dense(window);
+    }
+
+    /// Move the chunks into `window`, or a hash map if there's none
+    fn migrate(&mut self, window: Option<Window>) {
+        let to = match window {
+            Some(window) => {
+                self.regrow(window);
+                Storage::Dense
+            }
+            None => {
+                let chunks = self.chunks().map(|(coord, chunk)| (coord, chunk.clone()));
+                self.cells = Cells::Chunked(chunks.collect());
+                Storage::Chunked
+            }
+        };
+        let chunks = self.chunks().count();
+        self.migrations.push(Migration { to, chunks });
+    }
+
+    /// Top left and bottom right written chunks
+    fn chunk_bounds(&self) -> Option<(ChunkCoord, ChunkCoord)> {
+        let mut coords = self.chunks().map(|(coord, _)| coord);
+        let first = coords.next()?;
+        Some(coords.fold((first, first), |(min, max), c| (min.min(c), max.max(c))))
+    }
+}
+
+/// Two-color turmite on a [`SparseMap`], it never leaves the board
+pub struct UnboundedAnt {
+    map: SparseMap,
+    rule: Rule,
+    pos: Pos,
+    dir: Direction,
+}
+
+impl UnboundedAnt {
+    pub fn new(pos: Pos, dir: Direction) -> Self {
+        Self::with_rule(pos, dir, Rule::default())
+    }
+
+    /// # Panics
+    ///
+    /// If the rule has more than two colors, chunks only hold black and white
+    pub fn with_rule(pos: Pos, dir: Direction, rule: Rule) -> Self {
+        assert!(
+            rule.colors() <= 2,
+            "rule {rule} needs {} colors but the sparse map holds 2",
+            rule.colors()
+        );
+
+        Self {
+            map: SparseMap::new_white(),
+            rule,
+            pos,
+            dir,
+        }
+    }
+
+    /// Keep the board in a dense window of at most `cells` cells, see
+    /// [`SparseMap::set_dense_limit`]
+    pub fn with_dense_limit(mut self, cells: usize) -> Self {
+        self.map.set_dense_limit(cells);
+        self
+    }
+
+    pub fn pos(&self) -> Pos {
+        self.pos
+    }
+
+    pub fn dir(&self) -> Direction {
+        self.dir
+    }
+
+    pub fn map(&self) -> &SparseMap {
+        &self.map
+    }
+
+    pub fn into_map(self) -> SparseMap {
+        self.map
+    }
+
+    pub fn walk(&mut self) {
+        let color = self.map.is_black(self.pos) as u8;
+        self.map.set(self.pos, self.rule.next_color(color) == 1);
+
+        let turn = self.rule.turn(color);
+        let (dir, dx, dy) = Direction::TURNS[turn as usize][self.dir as usize];
+        self.dir = dir;
+        self.pos = Pos::new(self.pos.x + dx, self.pos.y + dy);
+    }
+
+    pub fn walk_steps(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.walk();
+        }
+    }
+}
+
+#[test]
+fn unbounded_ant_matches_bounded_one() {
+    use crate::{Ant, Map};
+
+    let mut map = Map::<64, 64>::new_white();
+    let mut ant = Ant::new(&mut map, Pos::new(32, 32), Direction::North).unwrap();
+    let mut steps = 1;
+    while ant.walk() {
+        steps += 1;
+    }
+
+    // Start in a corner of a chunk so the walk spans several of them
+    let mut unbounded = UnboundedAnt::new(Pos::new(0, 0), Direction::North);
+    unbounded.walk_steps(steps);
+    // The bounded ant stays on the last cell, the unbounded one makes the step off the map
+    let shift = ant.dir().to_shift();
+    let expected = ant.pos() + Pos::new(shift.x - 32, shift.y - 32);
+    assert_eq!(unbounded.pos(), expected);
+    assert_eq!(unbounded.dir(), ant.dir());
+    assert_eq!(unbounded.map().count_black_tiles(), map.count_black_tiles());
+
+    let (min, max) = unbounded.map().bounding_box().unwrap();
+    assert_eq!((min.x.rem_euclid(64), max.x.rem_euclid(64)), (0, 63));
+    assert!(unbounded.map().chunks().count() > 1);
+}
+
+#[test]
+fn storages_hold_the_same_board() {
+    let walk = |limit| {
+        let mut ant = UnboundedAnt::new(Pos::new(0, 0), Direction::North).with_dense_limit(limit);
+        ant.walk_steps(30_000);
+        ant.into_map()
+    };
+    let cells = |map: &SparseMap| {
+        let mut chunks = map
+            .chunks()
+            .map(|(coord, chunk)| ((coord.y, coord.x), chunk.clone()))
+            .collect::<Vec<_>>();
+        chunks.sort_by_key(|(coord, _)| *coord);
+        chunks
+    };
+
+    let dense = walk(DENSE_LIMIT);
+    assert_eq!(
+        (dense.storage(), dense.migrations()),
+        (Storage::Dense, &[][..])
+    );
+    let chunked = walk(0);
+    assert_eq!(chunked.storage(), Storage::Chunked);
+    assert_eq!(cells(&chunked), cells(&dense));
+    assert_eq!(chunked.bounding_box(), dense.bounding_box());
+
+    // Four chunks on a side fit at first, the highway soon leaves them
+    let mut moved = walk((4 * CHUNK_SIZE).pow(2));
+    assert_eq!(moved.storage(), Storage::Chunked);
+    let [migration] = moved.migrations() else {
+        panic!("expected one migration, got {:?}", moved.migrations());
+    };
+    assert!(migration.chunks > 1 && migration.chunks < cells(&dense).len());
+    assert_eq!(cells(&moved), cells(&dense));
+
+    moved.set_dense_limit(DENSE_LIMIT);
+    assert_eq!(moved.storage(), Storage::Dense);
+    assert_eq!(moved.migrations()[1].to, Storage::Dense);
+    assert_eq!(cells(&moved), cells(&dense));
+    assert_eq!(moved.count_black_tiles(), dense.count_black_tiles());
+    // A limit of 0 hashes a dense board right away, not at its next write
+    let mut hashed = dense.clone();
+    hashed.set_dense_limit(0);
+    assert_eq!(hashed.storage(), Storage::Chunked);
+    assert_eq!(
+        hashed.migrations(),
+        [Migration {
+            to: Storage::Chunked,
+            chunks: cells(&dense).len(),
+        }]
+    );
+    assert_eq!(cells(&hashed), cells(&dense));
+}
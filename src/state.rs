@@ -0,0 +1,267 @@
+//! `.antv`, the binary format checkpoints are saved in
+//!
+//! Every number is little-endian and strings are a `u16` length and UTF-8, in order:
+//!
+//! - `ANTV` and the `u16` [`STATE_VERSION`], then the `u16` [`SEMANTICS_VERSION`] of the
+//!   release that saved it
+//! - width and height as `u32`, and the steps taken as `u64`
+//! - the rule as a string and the conflict policy as a `u8`
+//! - `0`, or `1` for a stochastic rule followed by its string, the seed `u64` and the
+//!   `u128` word position of its dice
+//! - the `u32` count of ants, then for each `x` and `y` as `u32`, the direction `0..4`
+//!   from north clockwise and flags: `1` still on the map, `2` mirrored
+//! - the `u32` count of cell bytes, then the cells in row-major order, 8 a byte with the
+//!   leftmost one in the highest bit for rules of two colors and a byte each otherwise
+//! - the `u64` FNV-1a hash of everything before it
+//!
+//! Unlike the serde encoding checkpoints used to have, it's spelled out here and stays
+//! the same whatever the structs look like, so states can be compared byte for byte
+//! and read by other programs
+//!
+//! Version 1 had no semantics, its states load as semantics 1. A state of other semantics
+//! still loads, but [`Simulation::from_checkpoint`](crate::Simulation::from_checkpoint)
+//! refuses to resume it, as it would walk on differently than the run it came from.
+//! Replays hold every repainted cell rather than the rule, so they play back the same
+//! whatever release steps them
+
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
+
+use crate::{
+    checkpoint::{AntCheckpoint, NoiseCheckpoint},
+    digest::Fnv1a,
+    formats::{STATE_MAGIC, STATE_VERSION},
+    Checkpoint, CheckpointError, ConflictPolicy, Direction, Format,
+};
+
+/// How a step works, bumped whenever the same state walks on differently than it did
+/// under earlier releases, e.g. when ants meeting on a cell or the dice of a stochastic
+/// rule change
+pub const SEMANTICS_VERSION: u16 = 1;
+
+/// Write `checkpoint` to `file` as `.antv`, next to it first and then moved over it,
+/// so a crash while saving keeps the previous state intact
+pub fn save_state(checkpoint: &Checkpoint, file: impl AsRef<Path>) -> Result<(), CheckpointError> {
+    let file = file.as_ref();
+    let tmp = file.with_extension("tmp");
+
+    let mut w = File::create(&tmp)?;
+    w.write_all(&encode(checkpoint))?;
+    w.sync_all()?;
+    fs::rename(tmp, file)?;
+    Ok(())
+}
+
+/// Read an `.antv` state written by [`save_state`]
+pub fn load_state(file: impl AsRef<Path>) -> Result<Checkpoint, CheckpointError> {
+    decode(&fs::read(file)?)
+}
+
+/// Bytes of `checkpoint` in the `.antv` format
+pub(crate) fn encode(checkpoint: &Checkpoint) -> Vec<u8> {
+    let mut out = Vec::with_capacity(64 + checkpoint.cells.len());
+    let string = |out: &mut Vec<u8>, s: &str| {
+        out.extend((s.len() as u16).to_le_bytes());
+        out.extend(s.as_bytes());
+    };
+
+    out.extend(STATE_MAGIC);
+    out.extend(STATE_VERSION.to_le_bytes());
+    out.extend(checkpoint.semantics.to_le_bytes());
+    out.extend((checkpoint.width as u32).to_le_bytes());
+    out.extend((checkpoint.height as u32).to_le_bytes());
+    out.extend(checkpoint.steps.to_le_bytes());
+    string(&mut out, &checkpoint.rule);
+    out.push(checkpoint.conflict as u8);
+    match &checkpoint.noise {
+        Some(noise) => {
+            out.push(1);
+            string(&mut out, &noise.rule);
+            out.extend(noise.seed.to_le_bytes());
+            out.extend(noise.word_pos.to_le_bytes());
+        }
+        None => out.push(0),
+    }
+    out.extend((checkpoint.ants.len() as u32).to_le_bytes());
+    for ant in &checkpoint.ants {
+        out.extend((ant.x as u32).to_le_bytes());
+        out.extend((ant.y as u32).to_le_bytes());
+        out.push(ant.dir as u8);
+        out.push(ant.on_map as u8 | (ant.mirrored as u8) << 1);
+    }
+    out.extend((checkpoint.cells.len() as u32).to_le_bytes());
+    out.extend(&checkpoint.cells);
+
+    let checksum = Fnv1a::new().write(&out).finish();
+    out.extend(checksum.to_le_bytes());
+    out
+}
+
+/// Checkpoint of `.antv` bytes, checked against their checksum
+pub(crate) fn decode(bytes: &[u8]) -> Result<Checkpoint, CheckpointError> {
+    let (body, checksum) = bytes
+        .split_last_chunk::<8>()
+        .ok_or(CheckpointError::Corrupted)?;
+    if !bytes.starts_with(STATE_MAGIC) {
+        return Err(CheckpointError::Corrupted);
+    }
+    if Fnv1a::new().write(body).finish() != u64::from_le_bytes(*checksum) {
+        return Err(CheckpointError::Checksum);
+    }
+
+    let mut r = Reader(&body[STATE_MAGIC.len()..]);
+    let version = u16::from_le_bytes(r.take()?);
+    if !Format::State.reads(version) {
+        return Err(CheckpointError::Version(version));
+    }
+    let semantics = match version {
+        1 => 1,
+        _ => u16::from_le_bytes(r.take()?),
+    };
+    let width = r.u32()? as usize;
+    let height = r.u32()? as usize;
+    let steps = u64::from_le_bytes(r.take()?);
+    let rule = r.string()?;
+    let conflict = match r.u8()? {
+        0 => ConflictPolicy::Sequential,
+        1 => ConflictPolicy::FlipOnce,
+        2 => ConflictPolicy::Exclusive,
+        _ => return Err(CheckpointError::Corrupted),
+    };
+    let noise = match r.u8()? {
+        0 => None,
+        1 => Some(NoiseCheckpoint {
+            rule: r.string()?,
+            seed: u64::from_le_bytes(r.take()?),
+            word_pos: u128::from_le_bytes(r.take()?),
+        }),
+        _ => return Err(CheckpointError::Corrupted),
+    };
+    let ants = (0..r.u32()?)
+        .map(|_| {
+            let (x, y) = (r.u32()? as usize, r.u32()? as usize);
+            let dir = *Direction::VARIANTS
+                .get(r.u8()? as usize)
+                .ok_or(CheckpointError::Corrupted)?;
+            let flags = r.u8()?;
+            Ok(AntCheckpoint {
+                x,
+                y,
+                dir,
+                on_map: flags & 1 != 0,
+                mirrored: flags & 2 != 0,
+            })
+        })
+        .collect::<Result<Vec<_>, CheckpointError>>()?;
+    let len = r.u32()? as usize;
+    let cells = r.bytes(len)?.to_vec();
+    if !r.0.is_empty() {
+        return Err(CheckpointError::Corrupted);
+    }
+
+    Ok(Checkpoint {
+        width,
+        height,
+        rule,
+        conflict,
+        cells,
+        ants,
+        steps,
+        noise,
+        semantics,
+    })
+}
+
+/// What's left of the bytes being decoded
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], CheckpointError> {
+        if self.0.len() < len {
+            return Err(CheckpointError::Corrupted);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], CheckpointError> {
+        Ok(self.bytes(N)?.try_into().expect("N bytes were taken"))
+    }
+
+    fn u8(&mut self) -> Result<u8, CheckpointError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, CheckpointError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn string(&mut self) -> Result<String, CheckpointError> {
+        let len = u16::from_le_bytes(self.take()?) as usize;
+        String::from_utf8(self.bytes(len)?.to_vec()).map_err(|_| CheckpointError::Corrupted)
+    }
+}
+
+#[test]
+fn states_round_trip() {
+    use crate::{ColorMap, Map, Pos, Rule, Simulation};
+
+    let mut sim = Simulation::new(Map::<64, 64>::new_white(), Rule::default());
+    sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+    sim.spawn(Pos::new(10, 50), Direction::West).unwrap();
+    sim.run_at_most(3000);
+    let bytes = encode(&sim.checkpoint());
+    assert!(bytes.starts_with(b"ANTV\x02\x00\x01\x00"));
+    let restored =
+        Simulation::<64, 64, Map<64, 64>>::from_checkpoint(&decode(&bytes).unwrap()).unwrap();
+    assert_eq!(restored.digest(), sim.digest());
+    // The same state always makes the same bytes
+    assert_eq!(encode(&restored.checkpoint()), bytes);
+
+    let mut sim = Simulation::new(ColorMap::<16, 8>::new_white(), "LLRR".parse().unwrap());
+    sim.spawn(Pos::new(8, 4), Direction::East).unwrap();
+    sim.run_at_most(100);
+    let checkpoint = decode(&encode(&sim.checkpoint())).unwrap();
+    assert_eq!(checkpoint.cells.len(), 16 * 8);
+    assert_eq!(checkpoint.rule().unwrap().to_string(), "LLRR");
+
+    let mut flipped = bytes.clone();
+    flipped[40] ^= 1;
+    assert!(matches!(decode(&flipped), Err(CheckpointError::Checksum)));
+    assert!(matches!(
+        decode(&bytes[..bytes.len() - 9]),
+        Err(CheckpointError::Checksum)
+    ));
+    assert!(matches!(decode(b"ANTV"), Err(CheckpointError::Corrupted)));
+}
+
+#[test]
+fn states_keep_their_semantics() {
+    use crate::{Map, Pos, Rule, Simulation};
+
+    let mut sim = Simulation::new(Map::<16, 16>::new_white(), Rule::default());
+    sim.spawn(Pos::new(8, 8), Direction::North).unwrap();
+    sim.run_at_most(50);
+    let bytes = encode(&sim.checkpoint());
+
+    // Version 1, the same without semantics
+    let mut old = [&bytes[..4], &[1, 0], &bytes[8..bytes.len() - 8]].concat();
+    old.extend(Fnv1a::new().write(&old).finish().to_le_bytes());
+    let checkpoint = decode(&old).unwrap();
+    assert_eq!(checkpoint.semantics(), 1);
+    let resumed = Simulation::<16, 16, Map<16, 16>>::from_checkpoint(&checkpoint).unwrap();
+    assert_eq!(resumed.digest(), sim.digest());
+
+    let mut checkpoint = sim.checkpoint();
+    checkpoint.semantics = SEMANTICS_VERSION + 1;
+    let later = decode(&encode(&checkpoint)).unwrap();
+    assert_eq!(later.semantics(), SEMANTICS_VERSION + 1);
+    assert!(matches!(
+        Simulation::<16, 16, Map<16, 16>>::from_checkpoint(&later),
+        Err(CheckpointError::Semantics(2))
+    ));
+}
@@ -0,0 +1,173 @@
+use std::{fmt::Write as _, fs, io, path::Path};
+
+use serde::Serialize;
+
+use crate::{CellStore, Observer, Pos, Simulation};
+
+/// Text formats a [`StatsLog`] can be written as
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StatsFormat {
+    /// A header row and a row per sample
+    Csv,
+    /// An array of sample objects
+    Json,
+}
+
+impl StatsFormat {
+    /// Guess the format from the file extension
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Numbers describing a simulation at one step
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
+pub struct StatsSample {
+    pub step: u64,
+    /// Cells that aren't white, the black ones for two-color rules
+    pub colored: usize,
+    pub left_turns: u64,
+    pub right_turns: u64,
+    /// Area of the smallest rectangle holding every cell an ant stood on
+    pub bounding_area: usize,
+    /// Straight line distance of the first ant from where it was when the log began
+    pub distance: f64,
+}
+
+/// Statistics of a [`Simulation`] sampled every few steps, to be exported as CSV or JSON
+///
+/// Unlike a report [`Series`](crate::Series) the spacing stays the same however long
+/// the run gets, so a log of a long run can get big
+#[derive(Clone, PartialEq, Debug)]
+pub struct StatsLog {
+    pub samples: Vec<StatsSample>,
+    every: u64,
+    origin: Option<Pos>,
+}
+
+impl StatsLog {
+    /// Log sampled every `every` steps
+    pub fn new(every: u64) -> Self {
+        Self {
+            samples: Vec::new(),
+            every: every.max(1),
+            origin: None,
+        }
+    }
+
+    /// Step the next sample is due at
+    pub fn next_at(&self) -> u64 {
+        self.samples
+            .last()
+            .map_or(0, |sample| sample.step + self.every)
+    }
+
+    /// Add a sample of `sim` as it is now, unless there is one of this step already
+    pub fn sample<const W: usize, const H: usize, M: CellStore<W, H>>(
+        &mut self,
+        sim: &Simulation<W, H, M>,
+    ) {
+        let step = sim.steps();
+        if self.samples.last().is_some_and(|last| last.step == step) {
+            return;
+        }
+
+        let ant = sim.ants().first().map(|ant| Pos::from(ant.pos()));
+        let origin = *self.origin.get_or_insert(ant.unwrap_or(Pos::new(0, 0)));
+        let distance = ant.map_or(0.0, |ant| {
+            let (dx, dy) = ((ant.x - origin.x) as f64, (ant.y - origin.y) as f64);
+            dx.hypot(dy)
+        });
+        let bounding_area = sim.touched().map_or(0, |(min, max)| {
+            ((max.x - min.x + 1) * (max.y - min.y + 1)) as usize
+        });
+        let [left_turns, right_turns] = sim.turns();
+
+        self.samples.push(StatsSample {
+            step,
+            colored: sim.map().count_colored(),
+            left_turns,
+            right_turns,
+            bounding_area,
+            distance,
+        });
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,colored,left_turns,right_turns,bounding_area,distance\n");
+        for s in &self.samples {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{:.3}",
+                s.step, s.colored, s.left_turns, s.right_turns, s.bounding_area, s.distance
+            );
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.samples).expect("samples are plain numbers")
+    }
+
+    pub fn save(&self, format: StatsFormat, file: impl AsRef<Path>) -> io::Result<()> {
+        let text = match format {
+            StatsFormat::Csv => self.to_csv(),
+            StatsFormat::Json => self.to_json(),
+        };
+        fs::write(file, text)
+    }
+}
+
+/// Samples when due and once more at the end of the run
+impl<const W: usize, const H: usize, M: CellStore<W, H>> Observer<W, H, M> for StatsLog {
+    fn on_step(&mut self, sim: &Simulation<W, H, M>) {
+        if sim.steps() >= self.next_at() {
+            self.sample(sim);
+        }
+    }
+
+    fn on_finish(&mut self, sim: &Simulation<W, H, M>) {
+        self.sample(sim);
+    }
+}
+
+#[test]
+fn samples_a_run() {
+    use crate::{Direction, Map};
+
+    let mut sim = Simulation::new(Map::<64, 64>::new_white(), Default::default());
+    sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+
+    let mut log = StatsLog::new(4);
+    while sim.steps() < 10 {
+        if sim.steps() >= log.next_at() {
+            log.sample(&sim);
+        }
+        sim.step();
+    }
+    log.sample(&sim);
+    log.sample(&sim);
+
+    let steps = log.samples.iter().map(|s| s.step).collect::<Vec<_>>();
+    assert_eq!(steps, [0, 4, 8, 10]);
+    let last = log.samples[3];
+    assert_eq!(last.left_turns + last.right_turns, 10);
+    // Four left turns on white make a square, the fifth step lands back on black
+    assert_eq!(log.samples[1].left_turns, 4);
+    assert_eq!(log.samples[1].distance, 0.0);
+    assert!(last.bounding_area >= 4 && last.colored > 0);
+
+    let csv = log.to_csv();
+    assert_eq!(csv.lines().count(), 5);
+    assert!(csv.starts_with(
+        "step,colored,left_turns,right_turns,bounding_area,distance\n0,0,0,0,1,0.000\n"
+    ));
+    let json = serde_json::from_str::<serde_json::Value>(&log.to_json()).unwrap();
+    assert_eq!(json[2]["step"], 8);
+    assert_eq!(StatsFormat::from_path("run.JSON"), Some(StatsFormat::Json));
+}
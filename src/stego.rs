@@ -0,0 +1,130 @@
+use std::{error::Error, fmt::Display};
+
+use sha2::{Digest, Sha256};
+
+/// Start of every hidden message, to tell it from the noise in any other image
+const MAGIC: [u8; 4] = *b"antv";
+/// Magic, payload length and checksum
+const HEADER: usize = 12;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum StegoError {
+    /// The image has room for `room` bytes of text, not `needed`
+    TooSmall { needed: usize, room: usize },
+    /// Nothing was hidden in the image
+    NotFound,
+    /// Something was hidden, but the image was changed since
+    Corrupt,
+}
+
+impl Display for StegoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StegoError::TooSmall { needed, room } => write!(
+                f,
+                "{needed} bytes of parameters don't fit, the image holds {room}"
+            ),
+            StegoError::NotFound => write!(f, "no parameters hidden in the image"),
+            StegoError::Corrupt => write!(
+                f,
+                "the hidden parameters are damaged, was the image edited or recompressed?"
+            ),
+        }
+    }
+}
+
+impl Error for StegoError {}
+
+/// Bytes of text `pixels` RGBA pixels can hide, see [`embed_params`]
+pub fn params_room(pixels: usize) -> usize {
+    (pixels * 3 / 8).saturating_sub(HEADER)
+}
+
+/// Hide `params` in the lowest bit of the red, green and blue bytes of RGBA pixels,
+/// so the picture carries the settings it was made with
+///
+/// A changed low bit is off by one in 255, nobody can see that. Alpha is left alone,
+/// editors tend to mess with the colors of transparent pixels
+pub fn embed_params(rgba: &mut [u8], params: &str) -> Result<(), StegoError> {
+    let room = params_room(rgba.len() / 4);
+    if params.len() > room {
+        return Err(StegoError::TooSmall {
+            needed: params.len(),
+            room,
+        });
+    }
+
+    for (channel, bit) in color_channels(rgba).zip(message_bits(params)) {
+        *channel = *channel & !1 | bit;
+    }
+    Ok(())
+}
+
+/// Bits [`embed_params`] puts in the color channels one after another, for images
+/// written a row at a time. Check the room with [`params_room`] first
+pub(crate) fn message_bits(params: &str) -> impl Iterator<Item = u8> {
+    let mut message = MAGIC.to_vec();
+    message.extend((params.len() as u32).to_be_bytes());
+    message.extend(&Sha256::digest(params)[..4]);
+    message.extend(params.bytes());
+
+    message
+        .into_iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| byte >> i & 1))
+}
+
+/// Read back what [`embed_params`] hid in RGBA pixels
+pub fn extract_params(rgba: &[u8]) -> Result<String, StegoError> {
+    let mut bits = rgba
+        .chunks_exact(4)
+        .flat_map(|pixel| &pixel[..3])
+        .map(|channel| channel & 1);
+    let mut next_bytes = |count: usize| {
+        let bytes = (0..count)
+            .map_while(|_| (0..8).try_fold(0, |byte, _| bits.next().map(|bit| byte << 1 | bit)))
+            .collect::<Vec<u8>>();
+        (bytes.len() == count).then_some(bytes)
+    };
+
+    let header = next_bytes(HEADER).ok_or(StegoError::NotFound)?;
+    if header[..4] != MAGIC {
+        return Err(StegoError::NotFound);
+    }
+    let len = u32::from_be_bytes(header[4..8].try_into().expect("4 bytes")) as usize;
+    if len > params_room(rgba.len() / 4) {
+        return Err(StegoError::Corrupt);
+    }
+    let params = next_bytes(len).ok_or(StegoError::Corrupt)?;
+    if Sha256::digest(&params)[..4] != header[8..] {
+        return Err(StegoError::Corrupt);
+    }
+    String::from_utf8(params).map_err(|_| StegoError::Corrupt)
+}
+
+fn color_channels(rgba: &mut [u8]) -> impl Iterator<Item = &mut u8> {
+    rgba.chunks_exact_mut(4)
+        .flat_map(|pixel| pixel[..3].iter_mut())
+}
+
+#[test]
+fn params_survive_in_the_low_bits() {
+    let params = "ANTVENTURE_RULE=LLRR serious --size 256 --max-steps 'ünïcode'";
+    let mut rgba = vec![200; 64 * 64 * 4];
+    let before = rgba.clone();
+    assert_eq!(extract_params(&rgba), Err(StegoError::NotFound));
+
+    embed_params(&mut rgba, params).unwrap();
+    assert!(rgba.iter().zip(&before).all(|(a, b)| a.abs_diff(*b) <= 1));
+    assert!(rgba.chunks_exact(4).all(|pixel| pixel[3] == 200));
+    assert_eq!(extract_params(&rgba).as_deref(), Ok(params));
+
+    // A flipped bit in the text
+    rgba[200] ^= 1;
+    assert_eq!(extract_params(&rgba), Err(StegoError::Corrupt));
+
+    let mut tiny = vec![0; 8 * 4];
+    assert_eq!(
+        embed_params(&mut tiny, "LR"),
+        Err(StegoError::TooSmall { needed: 2, room: 0 })
+    );
+}
@@ -0,0 +1,114 @@
+use std::io::{self, Write};
+
+use crate::{figure::rects, Frame, Palette, Renderer};
+
+/// SVG with every block of equally colored cells merged into one rectangle, so boards
+/// scale without blurring and stay small enough to embed in papers
+///
+/// A cell is one unit of the view box, ants get a plus shaped marker like in PNGs
+#[derive(Clone, Debug, Default)]
+pub struct SvgRenderer {
+    /// Needs an entry for each color, grays fading from white to black without one
+    pub palette: Option<Palette>,
+}
+
+impl Renderer for SvgRenderer {
+    fn render(&self, frame: &Frame, w: &mut dyn Write) -> io::Result<()> {
+        let gray = Palette::gray(frame.colors);
+        let palette = self.palette.as_ref().unwrap_or(&gray);
+        if palette.colors.len() < frame.colors {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} colors but the palette has only {}",
+                    frame.colors,
+                    palette.colors.len()
+                ),
+            ));
+        }
+
+        let (width, height) = (frame.width, frame.height);
+        writeln!(
+            w,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {width} {height}\" \
+             width=\"{width}\" height=\"{height}\" shape-rendering=\"crispEdges\">"
+        )?;
+        // White cells are the background, only the others are drawn
+        writeln!(
+            w,
+            "<rect width=\"{width}\" height=\"{height}\" fill=\"{}\"/>",
+            hex(palette.colors[0])
+        )?;
+
+        let mut rects = rects(&frame.cells, width);
+        rects.sort_by_key(|r| r.color);
+        for group in rects.chunk_by(|a, b| a.color == b.color) {
+            writeln!(
+                w,
+                "<g fill=\"{}\">",
+                hex(palette.colors[group[0].color as usize])
+            )?;
+            for r in group {
+                writeln!(
+                    w,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                    r.x, r.y, r.w, r.h
+                )?;
+            }
+            writeln!(w, "</g>")?;
+        }
+
+        if !frame.ants.is_empty() {
+            writeln!(w, "<g fill=\"{}\">", hex(palette.marker))?;
+            for ant in &frame.ants {
+                // The plus as a horizontal and a vertical bar, clipped by the view box
+                let (x, y) = (ant.x, ant.y);
+                writeln!(
+                    w,
+                    "<rect x=\"{}\" y=\"{y}\" width=\"3\" height=\"1\"/>\
+                     <rect x=\"{x}\" y=\"{}\" width=\"1\" height=\"3\"/>",
+                    x - 1,
+                    y - 1
+                )?;
+            }
+            writeln!(w, "</g>")?;
+        }
+        writeln!(w, "</svg>")
+    }
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+#[test]
+fn merges_cells_into_svg_rects() {
+    use crate::{CellStore, ColorMap, MapPos, Pos};
+
+    // 12.
+    // 1.1
+    let mut map = ColorMap::<3, 2>::new_white();
+    for (x, y, color) in [(0, 0, 1), (1, 0, 2), (0, 1, 1), (2, 1, 1)] {
+        map.set_color(MapPos::validate_pos(Pos::new(x, y)).unwrap(), color);
+    }
+
+    let frame = Frame::new(&map, 3, None).with_ants(&[Pos::new(2, 0)]);
+    let mut svg = Vec::new();
+    SvgRenderer::default().render(&frame, &mut svg).unwrap();
+    let svg = String::from_utf8(svg).unwrap();
+
+    assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>\n"));
+    assert!(svg.contains("viewBox=\"0 0 3 2\""));
+    assert!(svg.contains("<rect width=\"3\" height=\"2\" fill=\"#ffffff\"/>"));
+    // The first column merges into one rectangle
+    assert!(svg.contains(
+        "<g fill=\"#808080\">\n<rect x=\"0\" y=\"0\" width=\"1\" height=\"2\"/>\n\
+         <rect x=\"2\" y=\"1\" width=\"1\" height=\"1\"/>\n</g>"
+    ));
+    assert!(svg.contains("<g fill=\"#000000\">\n<rect x=\"1\" y=\"0\" width=\"1\" height=\"1\"/>"));
+    assert!(svg.contains("<g fill=\"#ff0000\">\n<rect x=\"1\" y=\"0\" width=\"3\""));
+
+    let palette = Some("#fff,#000".parse().unwrap());
+    let mut svg = Vec::new();
+    assert!(SvgRenderer { palette }.render(&frame, &mut svg).is_err());
+}
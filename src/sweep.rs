@@ -0,0 +1,166 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Runs whole simulations on a fixed set of worker threads, one simulation per thread at a time
+///
+/// By default the pool has one worker per physical core, hyperthread siblings
+/// fight over the same caches and only slow the walk down
+#[derive(Clone, Debug)]
+pub struct SweepExecutor {
+    workers: usize,
+    pin: bool,
+}
+
+/// How much work a single worker did during [`SweepExecutor::run`]
+#[derive(Clone, Debug, Default)]
+pub struct WorkerStats {
+    /// Core the worker was pinned to, if pinning was requested and succeeded
+    pub core: Option<usize>,
+    pub jobs: usize,
+    pub steps: u64,
+    pub busy: Duration,
+}
+
+impl WorkerStats {
+    pub fn steps_per_second(&self) -> f64 {
+        self.steps as f64 / self.busy.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+impl Default for SweepExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SweepExecutor {
+    pub fn new() -> Self {
+        Self {
+            workers: num_cpus::get_physical().max(1),
+            pin: false,
+        }
+    }
+
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Pin every worker to its own core
+    pub fn pin_to_cores(mut self, pin: bool) -> Self {
+        self.pin = pin;
+        self
+    }
+
+    /// Run every job and return their outputs in the original order
+    ///
+    /// A job returns its output along with the number of steps it simulated,
+    /// which is only used for throughput stats
+    pub fn run<T, F>(&self, jobs: Vec<F>) -> (Vec<T>, Vec<WorkerStats>)
+    where
+        T: Send,
+        F: FnOnce() -> (T, u64) + Send,
+    {
+        let count = jobs.len();
+        let jobs = jobs
+            .into_iter()
+            .map(|job| Mutex::new(Some(job)))
+            .collect::<Vec<_>>();
+        let outputs = (0..count).map(|_| Mutex::new(None)).collect::<Vec<_>>();
+        let next = AtomicUsize::new(0);
+
+        let cores = match self.pin {
+            true => core_affinity::get_core_ids().unwrap_or_default(),
+            false => Vec::new(),
+        };
+
+        let stats = thread::scope(|s| {
+            let handles = (0..self.workers.min(count.max(1)))
+                .map(|worker| {
+                    let core = cores.get(worker % cores.len().max(1)).copied();
+                    let (jobs, outputs, next) = (&jobs, &outputs, &next);
+
+                    s.spawn(move || {
+                        let mut stats = WorkerStats {
+                            core: core
+                                .filter(|c| core_affinity::set_for_current(*c))
+                                .map(|c| c.id),
+                            ..Default::default()
+                        };
+
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(job) = jobs.get(i) else {
+                                break;
+                            };
+                            let job = job.lock().unwrap().take().expect("every job runs once");
+
+                            let start = Instant::now();
+                            let (output, steps) = job();
+                            stats.busy += start.elapsed();
+                            stats.jobs += 1;
+                            stats.steps += steps;
+
+                            *outputs[i].lock().unwrap() = Some(output);
+                        }
+
+                        stats
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("sweep worker panicked"))
+                .collect::<Vec<_>>()
+        });
+
+        let outputs = outputs
+            .into_iter()
+            .map(|o| {
+                o.into_inner()
+                    .unwrap()
+                    .expect("every job produced an output")
+            })
+            .collect();
+
+        (outputs, stats)
+    }
+}
+
+#[test]
+fn sweep_keeps_job_order() {
+    use crate::{Ant, Direction, Map, Pos};
+
+    let jobs = (0..8)
+        .map(|i| {
+            move || {
+                let mut map = Map::<32, 32>::new_white();
+                let mut ant = Ant::new(&mut map, Pos::new(i * 4, 16), Direction::North).unwrap();
+                let mut steps = 0;
+                while ant.walk() {
+                    steps += 1;
+                }
+                (map.count_black_tiles(), steps)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let (counts, stats) = SweepExecutor::new().workers(3).pin_to_cores(true).run(jobs);
+
+    assert_eq!(counts.len(), 8);
+    assert_eq!(stats.len(), 3);
+    assert_eq!(stats.iter().map(|s| s.jobs).sum::<usize>(), 8);
+
+    let mut map = Map::<32, 32>::new_white();
+    Ant::new(&mut map, Pos::new(12, 16), Direction::North)
+        .unwrap()
+        .walk_until_end();
+    assert_eq!(counts[3], map.count_black_tiles());
+}
@@ -0,0 +1,200 @@
+//! Checks a [`CellStore`] has to pass to stand in for the boards of this crate,
+//! for crates bringing their own backend. Enable the `testing` feature and call
+//! [`conformance`] from a test:
+//!
+//! ```
+//! # // Any store works the same, the crate's own ones pass too
+//! # type MyStore = antventure::ColorMap<48, 32>;
+//! // In a #[test] of the crate with the store
+//! antventure::testing::conformance::<48, 32, MyStore>();
+//! ```
+//!
+//! Every check panics with what went wrong on the first difference it finds
+
+use crate::{naive, Ant, CellStore, ColorMap, Direction, MapPos, Pos, Rule, Simulation};
+
+/// Steps [`conformance`] walks every ant for at most
+pub const STEPS: usize = 20_000;
+
+/// Every check below, with [`STEPS`] steps per walk
+pub fn conformance<const W: usize, const H: usize, M: CellStore<W, H>>() {
+    check_store::<W, H, M>();
+    check_walks::<W, H, M>(STEPS);
+    check_rules::<W, H, M>(STEPS);
+}
+
+/// Cells read back what was written, boards built from colors give them back,
+/// colored cells are counted right and the digest is the usual one
+pub fn check_store<const W: usize, const H: usize, M: CellStore<W, H>>() {
+    let colors = M::COLORS.min(Rule::MAX_COLORS);
+    assert!(colors >= 2, "a store holds at least white and black");
+
+    let pattern = (0..W * H)
+        .map(|i| ((i * 7 + i / W) % colors) as u8)
+        .collect::<Vec<_>>();
+    let mut store = M::with_colors(pattern.clone());
+    assert!(
+        store.to_colors() == pattern,
+        "to_colors doesn't give back the colors of with_colors"
+    );
+    let colored = pattern.iter().filter(|&&c| c != 0).count();
+    assert_eq!(store.count_colored(), colored, "count_colored is off");
+    assert_eq!(
+        store.digest(),
+        ColorMap::<W, H>::with_colors(pattern.clone()).digest(),
+        "digest differs from the one of a ColorMap with the same colors"
+    );
+
+    for (i, &color) in pattern.iter().enumerate() {
+        let pos = cell::<W, H>(i);
+        assert_eq!(
+            store.color(pos),
+            color,
+            "color at {:?} differs from with_colors",
+            Pos::from(pos)
+        );
+        let next = ((color as usize + 1) % colors) as u8;
+        store.set_color(pos, next);
+        assert_eq!(
+            store.color(pos),
+            next,
+            "set_color at {:?} doesn't stick",
+            Pos::from(pos)
+        );
+    }
+    let shifted = pattern
+        .iter()
+        .map(|&c| ((c as usize + 1) % colors) as u8)
+        .collect::<Vec<_>>();
+    assert!(
+        store.to_colors() == shifted,
+        "set_color changed other cells than the one it was given"
+    );
+}
+
+/// [`Ant`] and [`Simulation`] on the store walk exactly like the ant of
+/// [`naive`], step by step, from the middle and next to every edge
+pub fn check_walks<const W: usize, const H: usize, M: CellStore<W, H>>(steps: usize) {
+    let (w, h) = (W as isize, H as isize);
+    let starts = [
+        (Pos::new(w / 2, h / 2), Direction::North),
+        (Pos::new(w / 3, h / 2), Direction::East),
+        (Pos::new(0, h - 1), Direction::South),
+        (Pos::new(w - 1, 0), Direction::West),
+    ];
+
+    for (start, dir) in starts {
+        let mut reference = naive::Map::<W, H>::new();
+        let mut naive_ant = naive::Ant::new(start, dir);
+
+        let mut store = M::with_colors(vec![0; W * H]);
+        let mut ant = Ant::new(&mut store, start, dir).expect("starts are on the map");
+        let mut sim = Simulation::new(M::with_colors(vec![0; W * H]), Rule::default());
+        sim.spawn(start, dir).expect("starts are on the map");
+
+        for step in 1..=steps {
+            let on_map = naive_ant.walk(&mut reference);
+            assert_eq!(
+                ant.walk(),
+                on_map,
+                "Ant from {start:?} leaves the map at another step than the naive one, step {step}"
+            );
+            assert_eq!(
+                sim.step(),
+                on_map,
+                "Simulation from {start:?} leaves the map at another step than the naive one, step {step}"
+            );
+            if !on_map {
+                break;
+            }
+            let expected = (naive_ant.pos, naive_ant.dir);
+            assert_eq!(
+                (Pos::from(ant.pos()), ant.dir()),
+                expected,
+                "Ant from {start:?} is off after {step} steps"
+            );
+            let state = sim.ants()[0];
+            assert_eq!(
+                (Pos::from(state.pos()), state.dir()),
+                expected,
+                "Simulation from {start:?} is off after {step} steps"
+            );
+        }
+
+        let expected = (0..W * H)
+            .map(|i| !reference.get(&cell::<W, H>(i).into()).unwrap() as u8)
+            .collect::<Vec<_>>();
+        assert!(
+            store.to_colors() == expected,
+            "Ant from {start:?} leaves another board than the naive one"
+        );
+        assert!(
+            sim.map().to_colors() == expected,
+            "Simulation from {start:?} leaves another board than the naive one"
+        );
+    }
+}
+
+/// Rules of more than two colors walk the same on the store as on a [`ColorMap`],
+/// with several ants at once. Rules with more colors than the store holds are skipped
+pub fn check_rules<const W: usize, const H: usize, M: CellStore<W, H>>(steps: usize) {
+    let rules = ["RL", "LLRR", "RLR", "LRRRRRLLR", "RRLLLRLLLRRR"];
+    let starts = [
+        (Pos::new(W as isize / 2, H as isize / 2), Direction::North),
+        (Pos::new(W as isize / 4, H as isize / 3), Direction::West),
+    ];
+
+    for rule in rules.map(|rule| rule.parse::<Rule>().expect("valid rules")) {
+        if rule.colors() > M::COLORS {
+            continue;
+        }
+        let mut sim = Simulation::new(M::with_colors(vec![0; W * H]), rule.clone());
+        let mut reference = Simulation::new(ColorMap::<W, H>::new_white(), rule.clone());
+        for (pos, dir) in starts {
+            sim.spawn(pos, dir).expect("starts are on the map");
+            reference.spawn(pos, dir).expect("starts are on the map");
+        }
+
+        for step in 1..=steps {
+            let on_map = reference.step();
+            assert_eq!(
+                sim.step(),
+                on_map,
+                "rule {rule} leaves the map at another step, step {step}"
+            );
+            assert_eq!(
+                ant_states(&sim),
+                ant_states(&reference),
+                "rule {rule} walks another way after {step} steps"
+            );
+            if !on_map {
+                break;
+            }
+        }
+        assert!(
+            sim.map().to_colors() == reference.map().to_colors(),
+            "rule {rule} leaves another board than on a ColorMap"
+        );
+    }
+}
+
+fn ant_states<const W: usize, const H: usize, M: CellStore<W, H>>(
+    sim: &Simulation<W, H, M>,
+) -> Vec<(Pos, Direction, bool)> {
+    sim.ants()
+        .iter()
+        .map(|ant| (Pos::from(ant.pos()), ant.dir(), ant.is_on_map()))
+        .collect()
+}
+
+fn cell<const W: usize, const H: usize>(i: usize) -> MapPos<'static, W, H> {
+    MapPos::validate_pos(Pos::new((i % W) as _, (i / W) as _)).expect("i is within the map")
+}
+
+#[test]
+fn builtin_stores_conform() {
+    use crate::Map;
+
+    conformance::<48, 40, Map<48, 40>>();
+    conformance::<30, 50, ColorMap<30, 50>>();
+}
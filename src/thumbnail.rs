@@ -0,0 +1,184 @@
+#[cfg(feature = "io")]
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+#[cfg(feature = "io")]
+use png::{BitDepth, ColorType, Encoder, EncodingError};
+
+use crate::{
+    color::{linear_to_srgb, srgb_to_linear},
+    BlockCounts, CellStore, MapPos, Palette, Pos, BLOCK_SIZE,
+};
+
+/// How dark every square block of a board is, a picture of it at a fraction of the
+/// size. Building it is one pass over the cells and nothing as big as the board is
+/// ever allocated, so even the biggest boards get a thumbnail in no time
+#[derive(Clone, PartialEq, Debug)]
+pub struct DensityGrid {
+    pub width: usize,
+    pub height: usize,
+    /// Cells on a side of a block
+    pub block: usize,
+    /// Darkness of every block in row-major order, in linear light from `0.0` for
+    /// white to `1.0` for black, colors fading from white to black like gray PNGs
+    pub values: Vec<f32>,
+}
+
+impl DensityGrid {
+    /// Grid of `map` no more than `side` blocks wide and high, blocks at the right and
+    /// bottom edges are cut short by the board
+    pub fn new<const W: usize, const H: usize>(
+        map: &impl CellStore<W, H>,
+        colors: usize,
+        side: usize,
+    ) -> Self {
+        let (cells_wide, cells_high) = map.size();
+        let block = cells_wide.max(cells_high).div_ceil(side.max(1)).max(1);
+        let (width, height) = (cells_wide.div_ceil(block), cells_high.div_ceil(block));
+        let darkness = Palette::gray(colors.max(2))
+            .colors
+            .iter()
+            .map(|[gray, ..]| 1.0 - srgb_to_linear(*gray))
+            .collect::<Vec<_>>();
+
+        let mut values = vec![0.0; width * height];
+        for y in 0..cells_high {
+            let row = &mut values[y / block * width..][..width];
+            for x in 0..cells_wide {
+                let pos = MapPos::validate_pos(Pos::new(x as _, y as _))
+                    .expect("x and y are within the map");
+                row[x / block] += darkness[map.color(pos) as usize];
+            }
+        }
+        for (i, value) in values.iter_mut().enumerate() {
+            let (x, y) = (i % width, i / width);
+            let cells_x = block.min(cells_wide - x * block);
+            let cells_y = block.min(cells_high - y * block);
+            *value /= (cells_x * cells_y) as f32;
+        }
+        Self {
+            width,
+            height,
+            block,
+            values,
+        }
+    }
+
+    /// Grid of the map `counts` follows, from its block counts without looking at a
+    /// cell, so it takes no longer on the biggest boards than on small ones. Blocks are
+    /// a whole number of counted blocks on a side, up to `side` of them, and every
+    /// color but white is as dark as black, as on a two color board
+    pub fn from_blocks<const W: usize, const H: usize>(
+        counts: &BlockCounts<W, H>,
+        side: usize,
+    ) -> Self {
+        let per_side = W.max(H).div_ceil(side.max(1)).div_ceil(BLOCK_SIZE).max(1);
+        let block = per_side * BLOCK_SIZE;
+        let (width, height) = (W.div_ceil(block), H.div_ceil(block));
+
+        let mut values = vec![0.0; width * height];
+        for (i, &count) in counts.counts().iter().enumerate() {
+            let (x, y) = (
+                i % BlockCounts::<W, H>::WIDTH,
+                i / BlockCounts::<W, H>::WIDTH,
+            );
+            values[y / per_side * width + x / per_side] += count as f32;
+        }
+        for (i, value) in values.iter_mut().enumerate() {
+            let (x, y) = (i % width, i / width);
+            let cells_x = block.min(W - x * block);
+            let cells_y = block.min(H - y * block);
+            *value /= (cells_x * cells_y) as f32;
+        }
+        Self {
+            width,
+            height,
+            block,
+            values,
+        }
+    }
+
+    /// 8-bit sRGB gray of every block, from white to black
+    pub fn to_gray(&self) -> Vec<u8> {
+        self.values
+            .iter()
+            .map(|darkness| linear_to_srgb(1.0 - darkness))
+            .collect()
+    }
+
+    /// Write the grid as an 8-bit grayscale PNG, a pixel per block
+    #[cfg(feature = "io")]
+    pub fn render(&self, w: impl Write) -> Result<(), EncodingError> {
+        let mut encoder = Encoder::new(w, self.width as _, self.height as _);
+        encoder.set_color(ColorType::Grayscale);
+        encoder.set_depth(BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.to_gray())
+    }
+
+    #[cfg(feature = "io")]
+    pub fn save(&self, file: impl AsRef<Path>) -> Result<(), EncodingError> {
+        let mut w = BufWriter::new(File::create(file)?);
+        self.render(&mut w)?;
+        Ok(w.flush()?)
+    }
+}
+
+#[test]
+fn blocks_average_their_cells() {
+    use crate::ColorMap;
+
+    let mut map = ColorMap::<5, 4>::new_white();
+    for (x, y, color) in [(0, 0, 2), (1, 1, 2), (4, 0, 1), (4, 3, 2)] {
+        map.set_color(MapPos::validate_pos(Pos::new(x, y)).unwrap(), color);
+    }
+    let grid = DensityGrid::new(&map, 3, 2);
+    assert_eq!((grid.width, grid.height, grid.block), (2, 2, 3));
+
+    // Two black cells of nine, a gray one of six, nothing and a black one of two
+    let half = 1.0 - srgb_to_linear(128);
+    let expected = [2.0 / 9.0, half / 6.0, 0.0, 0.5];
+    for (value, expected) in grid.values.iter().zip(expected) {
+        assert!((value - expected).abs() < 1e-6, "{value} is not {expected}");
+    }
+    let gray = grid.to_gray();
+    assert_eq!(gray[2], 255);
+    assert!(gray[0] > gray[3] && gray[3] > 128);
+
+    // A pixel per cell is the board as a gray PNG would have it
+    let full = DensityGrid::new(&map, 3, 5);
+    assert_eq!((full.width, full.height, full.block), (5, 4, 1));
+    assert_eq!(full.to_gray()[..5], [0, 255, 255, 255, 128]);
+}
+
+#[test]
+fn blocks_counted_as_they_flip_make_the_same_grid() {
+    use crate::{Crop, Direction, Map, Simulation};
+
+    let white = Map::<100, 70>::new_white();
+    let mut sim =
+        Simulation::new(white.clone(), Default::default()).with_observer(BlockCounts::new(&white));
+    sim.spawn(Pos::new(50, 35), Direction::North).unwrap();
+    sim.run_at_most(5000);
+    let counts = sim.observer::<BlockCounts<100, 70>>().unwrap();
+
+    for (side, block) in [(3, 48), (7, 16), (100, 16)] {
+        let grid = DensityGrid::from_blocks(counts, side);
+        assert_eq!((grid.width, grid.block), (100usize.div_ceil(block), block));
+        for (i, value) in grid.values.iter().enumerate() {
+            let (x, y) = (
+                (i % grid.width * block) as isize,
+                (i / grid.width * block) as isize,
+            );
+            let region = Crop {
+                min: Pos::new(x, y),
+                max: Pos::new(x + block as isize - 1, y + block as isize - 1),
+            };
+            let cells = sim.map().iter_region(region).count();
+            let black = sim.map().black_cells_in(region) as f32 / cells as f32;
+            assert!((value - black).abs() < 1e-6, "{value} is not {black}");
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::CellStore;
+
+/// How often neighbouring cells differ across the edges of a board laid out as tiles,
+/// compared to inside it
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Seams {
+    /// Share of differing pairs where the right edge meets the left one
+    pub vertical: f64,
+    /// Share of differing pairs where the bottom edge meets the top one
+    pub horizontal: f64,
+    /// Share of differing pairs of neighbours inside the board
+    pub interior: f64,
+}
+
+impl Seams {
+    pub fn measure<const W: usize, const H: usize>(map: &impl CellStore<W, H>) -> Self {
+        let cells = map.to_colors();
+        let at = |x: usize, y: usize| cells[y * W + x];
+        let share = |differ: usize, pairs: usize| differ as f64 / pairs.max(1) as f64;
+
+        let vertical = (0..H).filter(|&y| at(W - 1, y) != at(0, y)).count();
+        let horizontal = (0..W).filter(|&x| at(x, H - 1) != at(x, 0)).count();
+
+        let mut interior = 0;
+        for y in 0..H {
+            for x in 0..W {
+                interior += (x + 1 < W && at(x, y) != at(x + 1, y)) as usize;
+                interior += (y + 1 < H && at(x, y) != at(x, y + 1)) as usize;
+            }
+        }
+        let interior_pairs = (W - 1) * H + W * (H - 1);
+
+        Self {
+            vertical: share(vertical, H),
+            horizontal: share(horizontal, W),
+            interior: share(interior, interior_pairs),
+        }
+    }
+
+    /// Neither seam changes color much more often than the board does inside,
+    /// so tiles show no visible border
+    pub fn is_seamless(&self) -> bool {
+        // A single stray pair on a small board shouldn't count as a border
+        let limit = 2.0 * self.interior + 0.01;
+        self.vertical <= limit && self.horizontal <= limit
+    }
+}
+
+#[test]
+fn wrapped_board_tiles() {
+    use crate::{Direction, Map, MapPos, Pos, Rule, Simulation};
+
+    let mut sim = Simulation::new(Map::<64, 64>::new_white(), Rule::default()).with_wrapping(true);
+    sim.spawn(Pos::new(32, 32), Direction::North).unwrap();
+    // Long enough for the highway to come around a few times
+    assert!(sim.run_at_most(50_000));
+    let seams = Seams::measure(sim.map());
+    assert!(seams.interior > 0.1);
+    assert!(seams.is_seamless(), "{seams:?}");
+
+    // A stripe along the left edge only
+    let mut map = Map::<64, 64>::new_white();
+    for y in 0..64 {
+        map.set(MapPos::validate_pos(Pos::new(0, y)).unwrap(), false);
+    }
+    assert!(!Seams::measure(&map).is_seamless());
+}
@@ -0,0 +1,275 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use crate::{CellStore, Crop, Direction, Palette, Pos, Rule, Simulation};
+
+/// Something a [`Timeline`] does to a simulation at a step
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Cue {
+    /// `spawn X,Y DIR`, a new ant joins the walk
+    Spawn { pos: Pos, dir: Direction },
+    /// `rule RULE`, see [`Simulation::change_rule`]
+    Rule(Rule),
+    /// `flip X0,Y0 X1,Y1`, every cell between the corners moves on to the next color of
+    /// the rule, white and black swap under a two color one
+    Flip { min: Pos, max: Pos },
+    /// `palette COLORS`, the colors the board is drawn in from now on. The simulation has
+    /// no colors, the cue is handed back for the renderer to pick up
+    Palette(Palette),
+}
+
+impl FromStr for Cue {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (action, args) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let args = args.split_whitespace().collect::<Vec<_>>();
+        let cell = |s: &str| {
+            let (x, y) = s
+                .split_once(',')
+                .ok_or_else(|| format!("expected x,y, got {s:?}"))?;
+            let coord = |c: &str| {
+                c.trim()
+                    .parse()
+                    .map_err(|_| format!("bad coordinate {c:?}"))
+            };
+            Ok::<_, String>(Pos::new(coord(x)?, coord(y)?))
+        };
+
+        match (action.to_ascii_lowercase().as_str(), args.as_slice()) {
+            ("spawn", [pos, dir]) => Ok(Cue::Spawn {
+                pos: cell(pos)?,
+                dir: dir
+                    .parse()
+                    .map_err(|e: crate::ParseDirectionError| e.to_string())?,
+            }),
+            ("rule", [rule]) => Ok(Cue::Rule(rule.parse().map_err(|e| format!("{e}"))?)),
+            ("flip", [a, b]) => {
+                let (a, b) = (cell(a)?, cell(b)?);
+                Ok(Cue::Flip {
+                    min: Pos::new(a.x.min(b.x), a.y.min(b.y)),
+                    max: Pos::new(a.x.max(b.x), a.y.max(b.y)),
+                })
+            }
+            ("palette", [colors]) => Ok(Cue::Palette(colors.parse().map_err(|e| format!("{e}"))?)),
+            ("spawn" | "rule" | "flip" | "palette", _) => Err(format!("wrong arguments in {s:?}")),
+            _ => Err(format!(
+                "unknown action {action:?}, expected spawn, rule, flip or palette"
+            )),
+        }
+    }
+}
+
+/// A cue that couldn't be played
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TimelineError {
+    /// The ant of a [`Cue::Spawn`] would stand off the map or on an obstacle
+    CantSpawn { step: u64, pos: Pos },
+    /// The rule of a [`Cue::Rule`] has more colors than the map holds
+    TooManyColors { step: u64, rule: Rule },
+}
+
+impl Display for TimelineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimelineError::CantSpawn { step, pos } => write!(
+                f,
+                "can't spawn an ant at ({}, {}) at step {step}",
+                pos.x, pos.y
+            ),
+            TimelineError::TooManyColors { step, rule } => write!(
+                f,
+                "rule {rule} at step {step} has more colors than the map holds"
+            ),
+        }
+    }
+}
+
+impl Error for TimelineError {}
+
+/// Cues to play at given steps of a run, so a single config can stage a piece in
+/// several acts
+///
+/// Written as `STEP: ACTION ARGS` entries separated by `;` or new lines, e.g.
+/// `5000: spawn 10,10 north; 20000: rule LLRR; 30000: flip 0,0 63,63`.
+/// Cues of the same step play in the order they were written
+///
+/// ```
+/// use antventure::{Direction, Map, Pos, Rule, Simulation, Timeline};
+///
+/// let mut timeline = "10: spawn 4,4 east; 20: rule RL".parse::<Timeline>().unwrap();
+/// let mut sim = Simulation::new(Map::<16, 16>::new_white(), Rule::default());
+/// sim.spawn(Pos::new(8, 8), Direction::North).unwrap();
+/// while let Some(step) = timeline.next_step() {
+///     sim.run_at_most(step - sim.steps());
+///     timeline.play(&mut sim).unwrap();
+/// }
+/// assert_eq!((sim.ants().len(), sim.rule().to_string()), (2, "RL".into()));
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Timeline {
+    cues: Vec<(u64, Cue)>,
+    /// Cues before this one were played or skipped
+    next: usize,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a cue, after any other of the same step
+    pub fn with_cue(mut self, step: u64, cue: Cue) -> Self {
+        let at = self.cues.partition_point(|(s, _)| *s <= step);
+        self.cues.insert(at, (step, cue));
+        self
+    }
+
+    /// Every cue in the order they play
+    pub fn cues(&self) -> &[(u64, Cue)] {
+        &self.cues
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cues.is_empty()
+    }
+
+    /// Most colors of any rule the timeline switches to
+    pub fn colors(&self) -> usize {
+        self.cues
+            .iter()
+            .filter_map(|(_, cue)| match cue {
+                Cue::Rule(rule) => Some(rule.colors()),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Step of the next cue to play, `None` once all of them did
+    pub fn next_step(&self) -> Option<u64> {
+        self.cues.get(self.next).map(|(step, _)| *step)
+    }
+
+    /// Drop the cues before `step` unplayed, e.g. for a run resumed from a checkpoint
+    /// which has them played already
+    pub fn skip_to(&mut self, step: u64) {
+        while self.next_step().is_some_and(|at| at < step) {
+            self.next += 1;
+        }
+    }
+
+    /// Play every cue due by the current step of `sim`, returns the played ones
+    ///
+    /// A cue that can't be played stops the timeline there, the ones before it stay played
+    pub fn play<const W: usize, const H: usize, M: CellStore<W, H>>(
+        &mut self,
+        sim: &mut Simulation<W, H, M>,
+    ) -> Result<&[(u64, Cue)], TimelineError> {
+        let first = self.next;
+        while let Some(step) = self.next_step().filter(|&at| at <= sim.steps()) {
+            match &self.cues[self.next].1 {
+                Cue::Spawn { pos, dir } => {
+                    sim.spawn(*pos, *dir)
+                        .map_err(|_| TimelineError::CantSpawn { step, pos: *pos })?;
+                }
+                Cue::Rule(rule) if rule.colors() > M::COLORS => {
+                    let rule = rule.clone();
+                    return Err(TimelineError::TooManyColors { step, rule });
+                }
+                Cue::Rule(rule) => sim.change_rule(rule.clone()),
+                Cue::Flip { min, max } => flip(sim, *min, *max),
+                Cue::Palette(_) => {}
+            }
+            self.next += 1;
+        }
+        Ok(&self.cues[first..self.next])
+    }
+}
+
+impl FromStr for Timeline {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split([';', '\n'])
+            .filter(|entry| !entry.trim().is_empty())
+            .try_fold(Timeline::new(), |timeline, entry| {
+                let (step, cue) = entry
+                    .split_once(':')
+                    .ok_or_else(|| format!("expected step: action, got {:?}", entry.trim()))?;
+                let step = step
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("bad step {:?}", step.trim()))?;
+                Ok(timeline.with_cue(step, cue.parse()?))
+            })
+    }
+}
+
+/// Move the cells between the corners on to the next color, as far as the map goes
+fn flip<const W: usize, const H: usize, M: CellStore<W, H>>(
+    sim: &mut Simulation<W, H, M>,
+    min: Pos,
+    max: Pos,
+) {
+    let colors = sim.rule().colors() as u8;
+    sim.map_mut().cycle_rect(Crop { min, max }, colors);
+}
+
+#[test]
+fn timeline_plays_in_order() {
+    use crate::Map;
+
+    let mut timeline = "
+        30: flip -5,-5 1,1
+        10: spawn 2,2 w; 10: rule RLR
+        20: palette #ffffff,#000000,#ff0000"
+        .parse::<Timeline>()
+        .unwrap();
+    assert_eq!(timeline.next_step(), Some(10));
+    assert!(matches!(timeline.cues()[0].1, Cue::Spawn { .. }));
+    assert!(matches!(timeline.cues()[1].1, Cue::Rule(_)));
+    assert_eq!(timeline.colors(), 3);
+
+    let mut sim = Simulation::new(Map::<8, 8>::new_white(), Rule::default())
+        .with_boundary(crate::Boundary::Wrap);
+    sim.spawn(Pos::new(6, 6), Direction::North).unwrap();
+    assert_eq!(timeline.play(&mut sim), Ok(&[][..]));
+    sim.run_at_most(10);
+    assert!(matches!(
+        timeline.play(&mut sim),
+        Err(TimelineError::TooManyColors { step: 10, .. })
+    ));
+    assert_eq!(sim.ants().len(), 2);
+
+    // Past the rule a map of two colors can't hold
+    timeline.next += 1;
+    sim.run_at_most(20);
+    let corner = |sim: &Simulation<8, 8>| {
+        let colors = sim.map().to_colors();
+        [0, 1, 8, 9].iter().filter(|&&i| colors[i] == 1).count()
+    };
+    let black = corner(&sim);
+    assert!(matches!(
+        timeline.play(&mut sim).unwrap(),
+        [(20, Cue::Palette(_)), (30, Cue::Flip { .. })]
+    ));
+    assert_eq!(corner(&sim), 4 - black);
+    assert_eq!(timeline.next_step(), None);
+
+    let mut timeline = "0: spawn 9,0 north; 5: rule RL"
+        .parse::<Timeline>()
+        .unwrap();
+    let mut sim = Simulation::new(Map::<8, 8>::new_white(), Rule::default());
+    assert_eq!(
+        timeline.play(&mut sim),
+        Err(TimelineError::CantSpawn {
+            step: 0,
+            pos: Pos::new(9, 0)
+        })
+    );
+    timeline.skip_to(6);
+    assert_eq!(timeline.next_step(), None);
+    assert!("5: jump 1,1".parse::<Timeline>().is_err());
+    assert!("5 rule LR".parse::<Timeline>().is_err());
+}
@@ -0,0 +1,284 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+
+use crate::{CellStore, Direction, Observer, Simulation};
+
+/// File format of a [`TrajectoryLog`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TrajectoryFormat {
+    /// A header row and a row per ant and sample
+    Csv,
+    /// A JSON object per line, per ant and sample
+    Ndjson,
+}
+
+impl TrajectoryFormat {
+    /// Guess the format from the file extension
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "csv" => Some(Self::Csv),
+            "ndjson" | "jsonl" => Some(Self::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+/// Where an ant was and which way it looked at some step
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+pub struct TrajectoryPoint {
+    pub step: u64,
+    /// Index of the ant, in spawn order
+    pub ant: usize,
+    pub x: usize,
+    pub y: usize,
+    pub dir: Direction,
+}
+
+impl TrajectoryPoint {
+    /// Where every ant still on the map is now
+    fn of_ants<const W: usize, const H: usize, M: CellStore<W, H>>(
+        sim: &Simulation<W, H, M>,
+    ) -> impl Iterator<Item = Self> + '_ {
+        let ants = sim.ants().iter().enumerate();
+        ants.filter(|(_, state)| state.is_on_map())
+            .map(|(ant, state)| TrajectoryPoint {
+                step: sim.steps(),
+                ant,
+                x: state.pos().x(),
+                y: state.pos().y(),
+                dir: state.dir(),
+            })
+    }
+
+    fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        let TrajectoryPoint {
+            step,
+            ant,
+            x,
+            y,
+            dir,
+        } = self;
+        writeln!(out, "{step},{ant},{x},{y},{dir:?}")
+    }
+}
+
+/// Position and direction of every ant on the map every few steps, written out as
+/// the run goes since a trajectory of a long run doesn't fit in memory
+///
+/// Writing can't fail a run, the first error is kept for [`error`](Self::error)
+/// and nothing is written after it
+pub struct TrajectoryLog<Wr: Write = BufWriter<File>> {
+    out: Wr,
+    format: TrajectoryFormat,
+    every: u64,
+    points: u64,
+    error: Option<io::Error>,
+}
+
+impl TrajectoryLog {
+    /// Log into a new file
+    pub fn create(
+        file: impl AsRef<Path>,
+        format: TrajectoryFormat,
+        every: u64,
+    ) -> io::Result<Self> {
+        let out = BufWriter::new(File::create(file)?);
+        Ok(Self::new(out, format, every))
+    }
+}
+
+impl<Wr: Write> TrajectoryLog<Wr> {
+    /// Log into `out` every `every` steps
+    pub fn new(out: Wr, format: TrajectoryFormat, every: u64) -> Self {
+        let mut log = Self {
+            out,
+            format,
+            every: every.max(1),
+            points: 0,
+            error: None,
+        };
+        if format == TrajectoryFormat::Csv {
+            log.write(|out| writeln!(out, "step,ant,x,y,dir"));
+        }
+        log
+    }
+
+    /// Write where every ant on the map is now, whether or not it's due
+    pub fn record<const W: usize, const H: usize, M>(&mut self, sim: &Simulation<W, H, M>)
+    where
+        M: CellStore<W, H>,
+    {
+        for point in TrajectoryPoint::of_ants(sim) {
+            let format = self.format;
+            self.write(|out| match format {
+                TrajectoryFormat::Csv => point.write_csv(out),
+                TrajectoryFormat::Ndjson => {
+                    serde_json::to_writer(&mut *out, &point)?;
+                    writeln!(out)
+                }
+            });
+            self.points += 1;
+        }
+    }
+
+    /// Points written so far
+    pub fn points(&self) -> u64 {
+        self.points
+    }
+
+    /// First error writing the log, if there was one
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    pub fn into_inner(self) -> Wr {
+        self.out
+    }
+
+    fn write(&mut self, f: impl FnOnce(&mut Wr) -> io::Result<()>) {
+        if self.error.is_none() {
+            self.error = f(&mut self.out).err();
+        }
+    }
+}
+
+/// Records when due and flushes at the end of the run
+impl<const W: usize, const H: usize, M: CellStore<W, H>, Wr: Write> Observer<W, H, M>
+    for TrajectoryLog<Wr>
+{
+    fn on_step(&mut self, sim: &Simulation<W, H, M>) {
+        if sim.steps().is_multiple_of(self.every) {
+            self.record(sim);
+        }
+    }
+
+    fn on_finish(&mut self, _sim: &Simulation<W, H, M>) {
+        self.write(|out| out.flush());
+    }
+}
+
+/// The last few trajectory points of a run, for a bug report of a run that might not
+/// finish
+///
+/// Clones share the points, so one can stay outside the simulation and be read from a
+/// panic hook while another observes the run
+#[derive(Clone, Debug)]
+pub struct TrajectoryTail {
+    points: Arc<Mutex<VecDeque<TrajectoryPoint>>>,
+    capacity: usize,
+    every: u64,
+}
+
+impl TrajectoryTail {
+    /// Keep the last `capacity` points, taken every `every` steps
+    pub fn new(capacity: usize, every: u64) -> Self {
+        Self {
+            points: Arc::default(),
+            capacity,
+            every: every.max(1),
+        }
+    }
+
+    /// Take where every ant on the map is now, whether or not it's due
+    pub fn record<const W: usize, const H: usize, M>(&self, sim: &Simulation<W, H, M>)
+    where
+        M: CellStore<W, H>,
+    {
+        let mut points = self.lock();
+        for point in TrajectoryPoint::of_ants(sim) {
+            if points.len() == self.capacity {
+                points.pop_front();
+            }
+            points.push_back(point);
+        }
+    }
+
+    /// Points kept so far, oldest first
+    pub fn points(&self) -> Vec<TrajectoryPoint> {
+        self.lock().iter().copied().collect()
+    }
+
+    /// The points in the CSV layout of [`TrajectoryLog`]
+    pub fn write_csv(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "step,ant,x,y,dir")?;
+        for point in self.points() {
+            point.write_csv(&mut out)?;
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<TrajectoryPoint>> {
+        // A panic while holding it left whole points behind, they're still worth reading
+        self.points.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<const W: usize, const H: usize, M: CellStore<W, H>> Observer<W, H, M> for TrajectoryTail {
+    fn on_step(&mut self, sim: &Simulation<W, H, M>) {
+        if sim.steps().is_multiple_of(self.every) {
+            self.record(sim);
+        }
+    }
+}
+
+#[test]
+fn logs_every_ant() {
+    use crate::{Map, Pos, Rule};
+
+    let log = TrajectoryLog::new(Vec::new(), TrajectoryFormat::Csv, 2);
+    let mut sim = Simulation::new(Map::<8, 8>::new_white(), Rule::default()).with_observer(log);
+    sim.spawn(Pos::new(4, 4), Direction::North).unwrap();
+    sim.spawn(Pos::new(0, 0), Direction::North).unwrap();
+    sim.run_at_most(4);
+    sim.finish();
+
+    // The second ant walks off the map on the first step
+    let log = sim.observer::<TrajectoryLog<Vec<u8>>>().unwrap();
+    assert_eq!(log.points(), 2);
+    assert!(log.error().is_none());
+    let csv = String::from_utf8(log.out.clone()).unwrap();
+    assert_eq!(csv, "step,ant,x,y,dir\n2,0,3,5,South\n4,0,4,4,North\n");
+
+    let mut log = TrajectoryLog::new(Vec::new(), TrajectoryFormat::Ndjson, 1);
+    let mut sim = Simulation::new(Map::<8, 8>::new_white(), Rule::default());
+    sim.spawn(Pos::new(4, 4), Direction::East).unwrap();
+    log.record(&sim);
+    let line = String::from_utf8(log.into_inner()).unwrap();
+    assert_eq!(
+        line,
+        "{\"step\":0,\"ant\":0,\"x\":4,\"y\":4,\"dir\":\"East\"}\n"
+    );
+    assert_eq!(
+        TrajectoryFormat::from_path("walk.JSONL"),
+        Some(TrajectoryFormat::Ndjson)
+    );
+}
+
+#[test]
+fn tails_keep_the_last_points() {
+    use crate::{Map, Pos, Rule};
+
+    let tail = TrajectoryTail::new(3, 1);
+    let mut sim =
+        Simulation::new(Map::<8, 8>::new_white(), Rule::default()).with_observer(tail.clone());
+    sim.spawn(Pos::new(4, 4), Direction::North).unwrap();
+    sim.run_at_most(5);
+
+    let steps = tail
+        .points()
+        .iter()
+        .map(|point| point.step)
+        .collect::<Vec<_>>();
+    assert_eq!(steps, [3, 4, 5]);
+    let mut csv = Vec::new();
+    tail.write_csv(&mut csv).unwrap();
+    assert_eq!(csv.split(|&b| b == b'\n').count(), 5);
+}
@@ -0,0 +1,540 @@
+use std::{error::Error, fmt::Display, fs, io, path::Path, str::FromStr};
+
+use serde::Deserialize;
+
+use crate::{Direction, ParseDirectionError, Rule, Turn};
+
+/// Which way a turmite turns before moving on, relative to where it's looking
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TurmiteTurn {
+    /// Straight on, `N`
+    None,
+    /// Clockwise, `R`
+    Right,
+    /// Back the way it came, `U`
+    UTurn,
+    /// Counterclockwise, `L`
+    Left,
+}
+
+impl TurmiteTurn {
+    pub const fn apply(self, dir: Direction) -> Direction {
+        match self {
+            TurmiteTurn::None => dir,
+            TurmiteTurn::Right => dir.cw(),
+            TurmiteTurn::UTurn => dir.cw().cw(),
+            TurmiteTurn::Left => dir.ccw(),
+        }
+    }
+
+    /// The turn that undoes this one
+    pub const fn inverse(self) -> Self {
+        match self {
+            TurmiteTurn::Right => TurmiteTurn::Left,
+            TurmiteTurn::Left => TurmiteTurn::Right,
+            turn => turn,
+        }
+    }
+}
+
+impl From<Turn> for TurmiteTurn {
+    fn from(turn: Turn) -> Self {
+        match turn {
+            Turn::Left => TurmiteTurn::Left,
+            Turn::Right => TurmiteTurn::Right,
+            Turn::UTurn => TurmiteTurn::UTurn,
+            Turn::None => TurmiteTurn::None,
+        }
+    }
+}
+
+impl From<TurmiteTurn> for Turn {
+    fn from(turn: TurmiteTurn) -> Self {
+        match turn {
+            TurmiteTurn::Left => Turn::Left,
+            TurmiteTurn::Right => Turn::Right,
+            TurmiteTurn::UTurn => Turn::UTurn,
+            TurmiteTurn::None => Turn::None,
+        }
+    }
+}
+
+impl FromStr for TurmiteTurn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "N" | "NONE" => Ok(TurmiteTurn::None),
+            "R" | "RIGHT" => Ok(TurmiteTurn::Right),
+            "U" | "UTURN" => Ok(TurmiteTurn::UTurn),
+            "L" | "LEFT" => Ok(TurmiteTurn::Left),
+            _ => Err(format!("unknown turn {s:?}, expected N, R, U or L")),
+        }
+    }
+}
+
+/// Where a turmite heads next: a turn from where it's looking, or a compass
+/// direction whatever it was looking at, as absolute turmites do
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TurnAction {
+    Turn(TurmiteTurn),
+    Face(Direction),
+}
+
+impl TurnAction {
+    pub const fn apply(self, dir: Direction) -> Direction {
+        match self {
+            TurnAction::Turn(turn) => turn.apply(dir),
+            TurnAction::Face(dir) => dir,
+        }
+    }
+
+    /// Whether the heading before can be told from the one after, which
+    /// [`Face`](Self::Face) forgets
+    pub const fn is_relative(self) -> bool {
+        matches!(self, TurnAction::Turn(_))
+    }
+}
+
+impl From<TurmiteTurn> for TurnAction {
+    fn from(turn: TurmiteTurn) -> Self {
+        TurnAction::Turn(turn)
+    }
+}
+
+impl From<Turn> for TurnAction {
+    fn from(turn: Turn) -> Self {
+        TurnAction::Turn(turn.into())
+    }
+}
+
+/// What a turmite in some state does on a cell of some color
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Transition {
+    /// Color to repaint the cell with
+    pub write: u8,
+    pub action: TurnAction,
+    /// State to carry on in
+    pub next: usize,
+}
+
+/// Turmite with internal states, a transition for every state and cell color
+///
+/// A [`Rule`] is the one-state case, [`langton`](Self::langton) is the classic ant.
+/// Spec files list the transitions, each with a `turn` of `N`, `R`, `U` or `L` or a
+/// compass direction to `face`, in TOML:
+///
+/// ```toml
+/// name = "Fibonacci spiral"
+/// states = 2
+/// colors = 2
+/// transitions = [
+///     { state = 0, color = 0, write = 1, turn = "L", next = 1 },
+///     { state = 0, color = 1, write = 1, turn = "L", next = 1 },
+///     { state = 1, color = 0, write = 1, turn = "R", next = 1 },
+///     { state = 1, color = 1, write = 0, turn = "N", next = 0 },
+/// ]
+/// ```
+///
+/// or the same fields in JSON. `{ state = 0, color = 0, write = 1, face = "east", next = 0 }`
+/// heads east whichever way the turmite looked
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TurmiteRule {
+    name: Option<String>,
+    states: usize,
+    colors: usize,
+    /// Indexed by `state * colors + color`
+    table: Vec<Transition>,
+}
+
+impl TurmiteRule {
+    /// `transitions` as `(state, color, transition)`, every pair exactly once
+    pub fn new(
+        states: usize,
+        colors: usize,
+        transitions: impl IntoIterator<Item = (usize, u8, Transition)>,
+    ) -> Result<Self, TurmiteError> {
+        if states == 0 {
+            return Err(TurmiteError::Invalid("a turmite needs a state".to_owned()));
+        }
+        if !(2..=Rule::MAX_COLORS).contains(&colors) {
+            return Err(TurmiteError::Invalid(format!(
+                "{colors} colors, expected 2 to {}",
+                Rule::MAX_COLORS
+            )));
+        }
+
+        let mut table = vec![None; states * colors];
+        for (state, color, transition) in transitions {
+            if state >= states || color as usize >= colors {
+                return Err(TurmiteError::Invalid(format!(
+                    "transition for state {state} and color {color} is out of range"
+                )));
+            }
+            if transition.next >= states || transition.write as usize >= colors {
+                return Err(TurmiteError::Invalid(format!(
+                    "state {state} on color {color} goes to state {} writing color {}, \
+                     out of range",
+                    transition.next, transition.write
+                )));
+            }
+            let slot = &mut table[state * colors + color as usize];
+            if slot.replace(transition).is_some() {
+                return Err(TurmiteError::Invalid(format!(
+                    "state {state} on color {color} is defined twice"
+                )));
+            }
+        }
+
+        let table = table
+            .into_iter()
+            .enumerate()
+            .map(|(i, transition)| {
+                transition.ok_or_else(|| {
+                    TurmiteError::Invalid(format!(
+                        "nothing for state {} on color {}",
+                        i / colors,
+                        i % colors
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            name: None,
+            states,
+            colors,
+            table,
+        })
+    }
+
+    /// Classic Langton's ant, the same as [`Rule::default`]
+    pub fn langton() -> Self {
+        Rule::default().into()
+    }
+
+    /// One-state absolute turmite heading the `i`-th way on color `i`, which then
+    /// becomes `i + 1` like under a [`Rule`]
+    pub fn absolute(faces: &[Direction]) -> Result<Self, TurmiteError> {
+        let colors = faces.len();
+        let transitions = faces.iter().enumerate().map(|(color, &dir)| {
+            let transition = Transition {
+                write: ((color + 1) % colors) as u8,
+                action: TurnAction::Face(dir),
+                next: 0,
+            };
+            (0, color as u8, transition)
+        });
+        let mut rule = Self::new(1, colors, transitions)?;
+        rule.name = Some(
+            faces
+                .iter()
+                .map(|dir| format!("{dir:?}")[..1].to_owned())
+                .collect(),
+        );
+        Ok(rule)
+    }
+
+    /// [`absolute`](Self::absolute) from the first letters of the directions,
+    /// e.g. `"NESW"`
+    pub fn parse_absolute(s: &str) -> Result<Self, TurmiteError> {
+        let faces = s
+            .chars()
+            .map(|c| c.to_string().parse::<Direction>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| TurmiteError::Invalid(e.to_string()))?;
+        Self::absolute(&faces)
+    }
+
+    /// Read a spec, `.json` files are JSON and anything else TOML. With the `sandbox`
+    /// feature `.wasm` and `.wat` files are modules run in a default [`Sandbox`](crate::Sandbox)
+    pub fn load(file: impl AsRef<Path>) -> Result<Self, TurmiteError> {
+        let file = file.as_ref();
+        #[cfg(feature = "sandbox")]
+        if file
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("wasm") || ext.eq_ignore_ascii_case("wat"))
+        {
+            return crate::Sandbox::default()
+                .rule(&fs::read(file)?)
+                .map_err(|e| TurmiteError::Sandbox(Box::new(e)));
+        }
+        let spec = fs::read_to_string(file)?;
+        match file
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        {
+            true => Self::from_json(&spec),
+            false => Self::from_toml(&spec),
+        }
+    }
+
+    pub fn from_toml(spec: &str) -> Result<Self, TurmiteError> {
+        toml::from_str::<Spec>(spec)?.validate()
+    }
+
+    pub fn from_json(spec: &str) -> Result<Self, TurmiteError> {
+        serde_json::from_str::<Spec>(spec)?.validate()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn states(&self) -> usize {
+        self.states
+    }
+
+    /// Number of colors cells cycle through
+    pub fn colors(&self) -> usize {
+        self.colors
+    }
+
+    pub fn transition(&self, state: usize, color: u8) -> Transition {
+        self.table[state * self.colors + color as usize]
+    }
+
+    /// Whether every step can be undone from the state and cell color it leaves behind,
+    /// true for every plain [`Rule`] and never for turmites facing compass directions
+    pub fn is_reversible(&self) -> bool {
+        self.inverse_table().is_some()
+    }
+
+    /// State and color before a step, indexed by `next * colors + write` like the table,
+    /// `None` when two transitions leave the same state and color
+    pub(crate) fn inverse_table(&self) -> Option<Vec<(usize, u8)>> {
+        if !self.table.iter().all(|t| t.action.is_relative()) {
+            return None;
+        }
+        let mut inverse = vec![None; self.table.len()];
+        for (i, transition) in self.table.iter().enumerate() {
+            let slot = &mut inverse[transition.next * self.colors + transition.write as usize];
+            if slot
+                .replace((i / self.colors, (i % self.colors) as u8))
+                .is_some()
+            {
+                return None;
+            }
+        }
+        // As many transitions as slots, so none is left empty
+        inverse.into_iter().collect()
+    }
+}
+
+impl From<Rule> for TurmiteRule {
+    fn from(rule: Rule) -> Self {
+        Self {
+            name: Some(rule.to_string()),
+            states: 1,
+            colors: rule.colors(),
+            table: (0..rule.colors() as u8)
+                .map(|color| Transition {
+                    write: rule.next_color(color),
+                    action: rule.turn(color).into(),
+                    next: 0,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Spec file layout, see [`TurmiteRule`]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Spec {
+    name: Option<String>,
+    states: usize,
+    colors: usize,
+    transitions: Vec<TransitionSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransitionSpec {
+    state: usize,
+    color: u8,
+    write: u8,
+    turn: Option<String>,
+    face: Option<String>,
+    next: usize,
+}
+
+impl Spec {
+    fn validate(self) -> Result<TurmiteRule, TurmiteError> {
+        let transitions =
+            self.transitions
+                .into_iter()
+                .map(|t| {
+                    let action =
+                        match (t.turn, t.face) {
+                            (Some(turn), None) => {
+                                TurnAction::Turn(turn.parse().map_err(TurmiteError::Invalid)?)
+                            }
+                            (None, Some(dir)) => TurnAction::Face(dir.parse().map_err(
+                                |e: ParseDirectionError| TurmiteError::Invalid(e.to_string()),
+                            )?),
+                            _ => {
+                                return Err(TurmiteError::Invalid(format!(
+                            "state {} on color {} needs either a turn or a direction to face",
+                            t.state, t.color
+                        )))
+                            }
+                        };
+                    let transition = Transition {
+                        write: t.write,
+                        action,
+                        next: t.next,
+                    };
+                    Ok((t.state, t.color, transition))
+                })
+                .collect::<Result<Vec<_>, TurmiteError>>()?;
+
+        let mut rule = TurmiteRule::new(self.states, self.colors, transitions)?;
+        rule.name = self.name;
+        Ok(rule)
+    }
+}
+
+#[derive(Debug)]
+pub enum TurmiteError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    /// The spec parsed but doesn't describe a turmite
+    Invalid(String),
+    #[cfg(feature = "sandbox")]
+    Sandbox(Box<crate::SandboxError>),
+}
+
+impl Display for TurmiteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TurmiteError::Io(e) => write!(f, "{e}"),
+            TurmiteError::Toml(e) => write!(f, "bad turmite spec: {e}"),
+            TurmiteError::Json(e) => write!(f, "bad turmite spec: {e}"),
+            TurmiteError::Invalid(e) => write!(f, "invalid turmite: {e}"),
+            #[cfg(feature = "sandbox")]
+            TurmiteError::Sandbox(e) => write!(f, "sandboxed turmite: {e}"),
+        }
+    }
+}
+
+impl Error for TurmiteError {}
+
+impl From<io::Error> for TurmiteError {
+    fn from(e: io::Error) -> Self {
+        TurmiteError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for TurmiteError {
+    fn from(e: toml::de::Error) -> Self {
+        TurmiteError::Toml(e)
+    }
+}
+
+impl From<serde_json::Error> for TurmiteError {
+    fn from(e: serde_json::Error) -> Self {
+        TurmiteError::Json(e)
+    }
+}
+
+#[test]
+fn parse_turmite_specs() {
+    let toml = r#"
+        name = "Fibonacci spiral"
+        states = 2
+        colors = 2
+        transitions = [
+            { state = 0, color = 0, write = 1, turn = "L", next = 1 },
+            { state = 0, color = 1, write = 1, turn = "L", next = 1 },
+            { state = 1, color = 0, write = 1, turn = "R", next = 1 },
+            { state = 1, color = 1, write = 0, turn = "N", next = 0 },
+        ]
+    "#;
+    let rule = TurmiteRule::from_toml(toml).unwrap();
+    assert_eq!(rule.name(), Some("Fibonacci spiral"));
+    assert_eq!(
+        rule.transition(1, 1),
+        Transition {
+            write: 0,
+            action: TurnAction::Turn(TurmiteTurn::None),
+            next: 0
+        }
+    );
+
+    // The spiral keeps growing instead of turning into a highway
+    let mut map = crate::Map::<64, 64>::new_white();
+    let mut ant =
+        crate::Ant::with_rule(&mut map, crate::Pos::new(32, 32), Direction::North, rule).unwrap();
+    assert!(ant.walk_at_most(500));
+    assert!(map.count_black_tiles() > 100);
+
+    let json = r#"{"states": 1, "colors": 2, "transitions": [
+        {"state": 0, "color": 0, "write": 1, "turn": "L", "next": 0},
+        {"state": 0, "color": 1, "write": 0, "turn": "R", "next": 0}
+    ]}"#;
+    let mut langton = TurmiteRule::from_json(json).unwrap();
+    langton.name = TurmiteRule::langton().name;
+    assert_eq!(langton, TurmiteRule::langton());
+
+    let missing = "states = 2\ncolors = 2\ntransitions = [\
+        { state = 0, color = 0, write = 1, turn = \"L\", next = 1 }]";
+    assert!(matches!(
+        TurmiteRule::from_toml(missing),
+        Err(TurmiteError::Invalid(_))
+    ));
+    let bad_turn = json.replace("\"R\"", "\"X\"");
+    assert!(matches!(
+        TurmiteRule::from_json(&bad_turn),
+        Err(TurmiteError::Invalid(_))
+    ));
+    assert!(matches!(
+        TurmiteRule::from_json("{"),
+        Err(TurmiteError::Json(_))
+    ));
+}
+
+#[test]
+fn absolute_turmites_face_compass_directions() {
+    use crate::{Ant, Map, Pos};
+
+    let rule = TurmiteRule::parse_absolute("ns").unwrap();
+    assert_eq!(rule.name(), Some("NS"));
+    assert!(!rule.is_reversible());
+    assert_eq!(
+        rule.transition(0, 1).action,
+        TurnAction::Face(Direction::South)
+    );
+
+    // North off every white cell, whichever way the ant looked
+    let mut map = Map::<8, 8>::new_white();
+    let mut ant = Ant::with_rule(&mut map, Pos::new(4, 4), Direction::East, rule).unwrap();
+    ant.walk_at_most(3);
+    assert_eq!(
+        (Pos::from(ant.pos()), ant.dir()),
+        (Pos::new(4, 1), Direction::North)
+    );
+    drop(ant);
+    assert_eq!(map.count_black_tiles(), 3);
+
+    let spec = "states = 1\ncolors = 2\ntransitions = [\
+        { state = 0, color = 0, write = 1, face = \"east\", next = 0 },\
+        { state = 0, color = 1, write = 0, turn = \"L\", face = \"w\", next = 0 }]";
+    assert!(matches!(
+        TurmiteRule::from_toml(spec),
+        Err(TurmiteError::Invalid(_))
+    ));
+    let spec = spec.replace(", face = \"w\"", "");
+    let rule = TurmiteRule::from_toml(&spec).unwrap();
+    assert_eq!(
+        rule.transition(0, 0).action,
+        TurnAction::Face(Direction::East)
+    );
+    assert!(TurmiteRule::parse_absolute("NX").is_err());
+}
@@ -0,0 +1,566 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+#[cfg(feature = "io")]
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "io")]
+use png::{ColorType, Encoder, EncodingError};
+
+use crate::Direction;
+#[cfg(feature = "io")]
+use crate::Palette;
+
+/// Position of a voxel, `x` grows east, `y` south and `z` up, so every layer of
+/// constant `z` is laid out like a [`Map`](crate::Map)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Voxel {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
+impl Voxel {
+    pub const fn new(x: isize, y: isize, z: isize) -> Self {
+        Self { x, y, z }
+    }
+
+    const fn cross(self, other: Voxel) -> Voxel {
+        Voxel::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+}
+
+/// One of the six neighbours of a voxel
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VoxelDirection {
+    North,
+    East,
+    South,
+    West,
+    Up,
+    Down,
+}
+
+impl VoxelDirection {
+    pub const VARIANTS: [VoxelDirection; 6] = [
+        VoxelDirection::North,
+        VoxelDirection::East,
+        VoxelDirection::South,
+        VoxelDirection::West,
+        VoxelDirection::Up,
+        VoxelDirection::Down,
+    ];
+
+    pub const fn to_shift(self) -> Voxel {
+        match self {
+            VoxelDirection::North => Voxel::new(0, -1, 0),
+            VoxelDirection::East => Voxel::new(1, 0, 0),
+            VoxelDirection::South => Voxel::new(0, 1, 0),
+            VoxelDirection::West => Voxel::new(-1, 0, 0),
+            VoxelDirection::Up => Voxel::new(0, 0, 1),
+            VoxelDirection::Down => Voxel::new(0, 0, -1),
+        }
+    }
+
+    /// The direction of a unit shift along one axis
+    pub fn from_shift(shift: Voxel) -> Option<Self> {
+        Self::VARIANTS
+            .into_iter()
+            .find(|dir| dir.to_shift() == shift)
+    }
+
+    pub fn reverse(self) -> Self {
+        let Voxel { x, y, z } = self.to_shift();
+        Self::from_shift(Voxel::new(-x, -y, -z)).expect("unit shifts reverse to unit shifts")
+    }
+}
+
+impl From<Direction> for VoxelDirection {
+    fn from(dir: Direction) -> Self {
+        match dir {
+            Direction::North => VoxelDirection::North,
+            Direction::East => VoxelDirection::East,
+            Direction::South => VoxelDirection::South,
+            Direction::West => VoxelDirection::West,
+        }
+    }
+}
+
+/// Which way a 3D ant heads and which way is up for it, turns are relative to both
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Orientation {
+    forward: VoxelDirection,
+    up: VoxelDirection,
+}
+
+impl Orientation {
+    /// `None` unless `forward` and `up` are at right angles
+    pub fn new(forward: VoxelDirection, up: VoxelDirection) -> Option<Self> {
+        let (f, u) = (forward.to_shift(), up.to_shift());
+        let square = f.x * u.x + f.y * u.y + f.z * u.z == 0;
+        square.then_some(Self { forward, up })
+    }
+
+    pub fn forward(self) -> VoxelDirection {
+        self.forward
+    }
+
+    pub fn up(self) -> VoxelDirection {
+        self.up
+    }
+
+    pub fn turn(self, turn: VoxelTurn) -> Self {
+        let (forward, up) = (self.forward, self.up);
+        let left = VoxelDirection::from_shift(forward.to_shift().cross(up.to_shift()))
+            .expect("forward and up are at right angles");
+        let (forward, up) = match turn {
+            VoxelTurn::None => (forward, up),
+            VoxelTurn::Left => (left, up),
+            VoxelTurn::Right => (left.reverse(), up),
+            VoxelTurn::Up => (up, forward.reverse()),
+            VoxelTurn::Down => (up.reverse(), forward),
+            VoxelTurn::UTurn => (forward.reverse(), up),
+        };
+        Self { forward, up }
+    }
+}
+
+/// Heading the same way as on a flat map, with up out of the map. An ant keeping
+/// to `L` and `R` turns then walks the same as on a [`Map`](crate::Map)
+impl From<Direction> for Orientation {
+    fn from(dir: Direction) -> Self {
+        Self {
+            forward: dir.into(),
+            up: VoxelDirection::Up,
+        }
+    }
+}
+
+/// Which way a 3D turmite turns, relative to where it heads and where its up is
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VoxelTurn {
+    /// Straight on, `N`
+    None,
+    /// 90° to the left around its up, `L`
+    Left,
+    /// 90° to the right around its up, `R`
+    Right,
+    /// Pitch 90° up, its back becomes its up, `U`
+    Up,
+    /// Pitch 90° down, `D`
+    Down,
+    /// Back the way it came, `B`
+    UTurn,
+}
+
+impl VoxelTurn {
+    const fn letter(self) -> char {
+        match self {
+            VoxelTurn::None => 'N',
+            VoxelTurn::Left => 'L',
+            VoxelTurn::Right => 'R',
+            VoxelTurn::Up => 'U',
+            VoxelTurn::Down => 'D',
+            VoxelTurn::UTurn => 'B',
+        }
+    }
+}
+
+/// 3D turmite rule, a letter per color, e.g. `"LRUD"`
+///
+/// `L`/`R` turn left and right, `U`/`D` pitch up and down, `N` goes straight on and
+/// `B` turns back
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VoxelRule(Vec<VoxelTurn>);
+
+impl VoxelRule {
+    pub fn new(turns: Vec<VoxelTurn>) -> Result<Self, ParseVoxelRuleError> {
+        match turns.len() {
+            2..=crate::Rule::MAX_COLORS => Ok(Self(turns)),
+            len => Err(ParseVoxelRuleError::Length(len)),
+        }
+    }
+
+    /// Number of colors cells cycle through
+    pub fn colors(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn turn(&self, color: u8) -> VoxelTurn {
+        self.0[color as usize]
+    }
+
+    pub fn next_color(&self, color: u8) -> u8 {
+        ((color as usize + 1) % self.0.len()) as u8
+    }
+}
+
+impl FromStr for VoxelRule {
+    type Err = ParseVoxelRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let turns = s
+            .chars()
+            .map(|c| match c.to_ascii_uppercase() {
+                'N' => Ok(VoxelTurn::None),
+                'L' => Ok(VoxelTurn::Left),
+                'R' => Ok(VoxelTurn::Right),
+                'U' => Ok(VoxelTurn::Up),
+                'D' => Ok(VoxelTurn::Down),
+                'B' => Ok(VoxelTurn::UTurn),
+                _ => Err(ParseVoxelRuleError::Letter(c)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Self::new(turns)
+    }
+}
+
+impl Display for VoxelRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for turn in &self.0 {
+            write!(f, "{}", turn.letter())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseVoxelRuleError {
+    Letter(char),
+    Length(usize),
+}
+
+impl Display for ParseVoxelRuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseVoxelRuleError::Letter(c) => {
+                write!(f, "unknown 3D turn {c:?}, expected N, L, R, U, D or B")
+            }
+            ParseVoxelRuleError::Length(len) => write!(
+                f,
+                "rule has {len} colors, expected 2 to {}",
+                crate::Rule::MAX_COLORS
+            ),
+        }
+    }
+}
+
+impl Error for ParseVoxelRuleError {}
+
+/// `width`x`height`x`depth` box of voxels, stored layer by layer from the bottom
+#[derive(Clone)]
+pub struct VoxelMap {
+    width: usize,
+    height: usize,
+    depth: usize,
+    cells: Vec<u8>,
+}
+
+impl VoxelMap {
+    /// Most voxels along each side a `.vox` file holds
+    pub const MAX_VOX_SIZE: usize = 256;
+
+    pub fn new_white(width: usize, height: usize, depth: usize) -> Self {
+        Self {
+            width,
+            height,
+            depth,
+            cells: vec![0; width * height * depth],
+        }
+    }
+
+    /// Width, height and depth
+    pub fn size(&self) -> (usize, usize, usize) {
+        (self.width, self.height, self.depth)
+    }
+
+    /// Voxel in the middle of the box
+    pub fn center(&self) -> Voxel {
+        Voxel::new(
+            (self.width / 2) as _,
+            (self.height / 2) as _,
+            (self.depth / 2) as _,
+        )
+    }
+
+    fn index(&self, voxel: Voxel) -> Option<usize> {
+        let inside = (0..self.width as isize).contains(&voxel.x)
+            && (0..self.height as isize).contains(&voxel.y)
+            && (0..self.depth as isize).contains(&voxel.z);
+        inside.then(|| {
+            (voxel.z as usize * self.height + voxel.y as usize) * self.width + voxel.x as usize
+        })
+    }
+
+    pub fn contains(&self, voxel: Voxel) -> bool {
+        self.index(voxel).is_some()
+    }
+
+    pub fn color(&self, voxel: Voxel) -> Option<u8> {
+        self.index(voxel).map(|i| self.cells[i])
+    }
+
+    /// Returns whether `voxel` is in the box
+    pub fn set_color(&mut self, voxel: Voxel, color: u8) -> bool {
+        self.index(voxel).map(|i| self.cells[i] = color).is_some()
+    }
+
+    /// Cell colors of layer `z`, row by row
+    ///
+    /// # Panics
+    ///
+    /// If `z` is out of the box
+    pub fn layer(&self, z: usize) -> &[u8] {
+        let layer = self.width * self.height;
+        &self.cells[z * layer..(z + 1) * layer]
+    }
+
+    /// How many cells hold each of `colors` colors
+    pub fn count_colors(&self, colors: usize) -> Vec<usize> {
+        let mut counts = vec![0; colors];
+        for &c in &self.cells {
+            counts[c as usize] += 1;
+        }
+        counts
+    }
+
+    /// Save every layer as an 8-bit grayscale PNG, colors fade from white (`0`) to
+    /// black (`colors - 1`). `{z}` in `template` is replaced with the layer, counted
+    /// from the bottom, returns the files
+    #[cfg(feature = "io")]
+    pub fn save_slices(
+        &self,
+        colors: usize,
+        template: &str,
+    ) -> Result<Vec<PathBuf>, EncodingError> {
+        let levels = Palette::gray(colors);
+        let mut files = Vec::with_capacity(self.depth);
+        for z in 0..self.depth {
+            let file = PathBuf::from(template.replace("{z}", &z.to_string()));
+            if let Some(dir) = file.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                fs::create_dir_all(dir)?;
+            }
+            let data = self
+                .layer(z)
+                .iter()
+                .map(|&c| levels.colors[c as usize][0])
+                .collect::<Vec<_>>();
+
+            let w = BufWriter::new(File::create(&file)?);
+            let mut encoder = Encoder::new(w, self.width as _, self.height as _);
+            encoder.set_color(ColorType::Grayscale);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&data)?;
+            files.push(file);
+        }
+        Ok(files)
+    }
+
+    /// Save as a MagicaVoxel `.vox` model in `palette` colors, or in gray without
+    /// one. White voxels are left empty
+    ///
+    /// The format holds at most [`MAX_VOX_SIZE`](Self::MAX_VOX_SIZE) voxels a side
+    /// and 255 colors besides white
+    #[cfg(feature = "io")]
+    pub fn save_vox(
+        &self,
+        colors: usize,
+        palette: Option<&Palette>,
+        file: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        let (width, height, depth) = self.size();
+        if width.max(height).max(depth) > Self::MAX_VOX_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{width}x{height}x{depth} voxels, .vox holds {} a side",
+                    Self::MAX_VOX_SIZE
+                ),
+            ));
+        }
+        let gray = Palette::gray(colors);
+        let palette = palette.unwrap_or(&gray);
+        if palette.colors.len() < colors {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{colors} colors but the palette has only {}",
+                    palette.colors.len()
+                ),
+            ));
+        }
+
+        // Colors index the palette from 1, white is empty space
+        let mut voxels = Vec::new();
+        for (i, &color) in self.cells.iter().enumerate().filter(|(_, &c)| c != 0) {
+            let (x, y, z) = (i % width, i / width % height, i / (width * height));
+            // MagicaVoxel looks at the model with y away from the viewer
+            voxels.extend([x as u8, (height - 1 - y) as u8, z as u8, color]);
+        }
+        let mut rgba = [0; 256 * 4];
+        for (entry, [r, g, b]) in rgba.chunks_exact_mut(4).zip(&palette.colors[1..colors]) {
+            entry.copy_from_slice(&[*r, *g, *b, 255]);
+        }
+
+        let size = [width, height, depth].map(|side| side as u32);
+        let chunks = [
+            chunk(b"SIZE", &size.map(u32::to_le_bytes).concat()),
+            chunk(
+                b"XYZI",
+                &[&(voxels.len() as u32 / 4).to_le_bytes(), &voxels[..]].concat(),
+            ),
+            chunk(b"RGBA", &rgba),
+        ]
+        .concat();
+
+        let mut w = BufWriter::new(File::create(file)?);
+        w.write_all(b"VOX ")?;
+        w.write_all(&150u32.to_le_bytes())?;
+        w.write_all(b"MAIN")?;
+        w.write_all(&0u32.to_le_bytes())?;
+        w.write_all(&(chunks.len() as u32).to_le_bytes())?;
+        w.write_all(&chunks)?;
+        w.flush()
+    }
+}
+
+/// A `.vox` chunk with no children
+#[cfg(feature = "io")]
+fn chunk(id: &[u8; 4], content: &[u8]) -> Vec<u8> {
+    [
+        &id[..],
+        &(content.len() as u32).to_le_bytes(),
+        &0u32.to_le_bytes(),
+        content,
+    ]
+    .concat()
+}
+
+/// Turmite walking a [`VoxelMap`]
+pub struct VoxelAnt<'m> {
+    map: &'m mut VoxelMap,
+    rule: VoxelRule,
+    pos: Voxel,
+    orientation: Orientation,
+}
+
+impl<'m> VoxelAnt<'m> {
+    /// Returns `Err(pos)` if `pos` isn't in the box
+    pub fn new(
+        map: &'m mut VoxelMap,
+        pos: Voxel,
+        orientation: Orientation,
+        rule: VoxelRule,
+    ) -> Result<Self, Voxel> {
+        match map.contains(pos) {
+            true => Ok(Self {
+                map,
+                rule,
+                pos,
+                orientation,
+            }),
+            false => Err(pos),
+        }
+    }
+
+    pub fn pos(&self) -> Voxel {
+        self.pos
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Returns whether the ant can walk any further
+    pub fn walk(&mut self) -> bool {
+        let color = self.map.color(self.pos).expect("the ant is in the box");
+        self.map.set_color(self.pos, self.rule.next_color(color));
+
+        self.orientation = self.orientation.turn(self.rule.turn(color));
+        let shift = self.orientation.forward.to_shift();
+        self.pos = Voxel::new(
+            self.pos.x + shift.x,
+            self.pos.y + shift.y,
+            self.pos.z + shift.z,
+        );
+        self.map.contains(self.pos)
+    }
+
+    pub fn walk_until_end(&mut self) {
+        while self.walk() {}
+    }
+
+    /// Walk until the ant leaves the box or `steps` steps are done,
+    /// returns whether the ant is still in the box
+    pub fn walk_at_most(&mut self, steps: usize) -> bool {
+        (0..steps).all(|_| self.walk())
+    }
+}
+
+#[test]
+fn voxel_ant_walks() {
+    use crate::{Ant, Map, Pos};
+
+    use VoxelDirection::*;
+
+    let north = Orientation::from(Direction::North);
+    assert_eq!(north.turn(VoxelTurn::Left).forward(), West);
+    assert_eq!(north.turn(VoxelTurn::Right).forward(), East);
+    let up = north.turn(VoxelTurn::Up);
+    assert_eq!((up.forward(), up.up()), (Up, South));
+    assert_eq!(up.turn(VoxelTurn::Down), north);
+    assert_eq!(Orientation::new(Up, Down), None);
+    assert_eq!("lrudnb".parse::<VoxelRule>().unwrap().to_string(), "LRUDNB");
+    assert_eq!(
+        "LX".parse::<VoxelRule>(),
+        Err(ParseVoxelRuleError::Letter('X'))
+    );
+
+    // Turning only left and right keeps to a layer and walks like the flat ant
+    let mut flat = Map::<16, 16>::new_white();
+    let mut ant = Ant::new(&mut flat, Pos::new(8, 8), Direction::North).unwrap();
+    ant.walk_until_end();
+    let mut map = VoxelMap::new_white(16, 16, 3);
+    let mut ant =
+        VoxelAnt::new(&mut map, Voxel::new(8, 8, 1), north, "LR".parse().unwrap()).unwrap();
+    ant.walk_until_end();
+    assert_eq!(ant.pos().z, 1);
+    assert_eq!(map.count_colors(2)[1], flat.count_black_tiles());
+    assert!(map.layer(0).iter().all(|&c| c == 0));
+
+    let mut map = VoxelMap::new_white(8, 8, 8);
+    let center = map.center();
+    let mut ant = VoxelAnt::new(&mut map, center, north, "UD".parse().unwrap()).unwrap();
+    assert!(ant.walk_at_most(3));
+    assert_eq!(ant.pos(), Voxel::new(4, 5, 4));
+    assert_eq!(map.count_colors(2), [512 - 3, 3]);
+
+    #[cfg(feature = "io")]
+    {
+        let file = std::env::temp_dir().join("antventure_voxel_ant_walks.vox");
+        map.save_vox(2, None, &file).unwrap();
+        let vox = fs::read(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+        assert_eq!(vox[..8], *b"VOX \x96\0\0\0");
+        // MAIN header, SIZE chunk, then XYZI with a count of 3 voxels
+        assert_eq!(vox[32..44], [8, 0, 0, 0, 8, 0, 0, 0, 8, 0, 0, 0]);
+        assert_eq!(vox[44..48], *b"XYZI");
+        assert_eq!(vox[56..60], 3u32.to_le_bytes());
+        assert_eq!(vox[60..64], [4, 3, 4, 1]);
+
+        let dir = std::env::temp_dir().join("antventure_voxel_ant_walks");
+        let template = dir.join("z{z}.png").display().to_string();
+        let files = map.save_slices(2, &template).unwrap();
+        assert_eq!(files.len(), 8);
+        let image = image::open(&files[5]).unwrap().into_luma8();
+        assert_eq!(image.get_pixel(4, 4).0, [0]);
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,136 @@
+//! Bindings for driving a simulation from JavaScript, built with
+//! `wasm-pack build --target web -- --no-default-features --features wasm`
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    board::{new_board, Board},
+    AntLayer, CellLayer, Composition, Direction, LayeredRenderer, Palette, Pos, Rule,
+};
+
+/// A square board with ants on it, for a `<canvas>` of `size` by `size` pixels
+///
+/// ```js
+/// const sim = new WasmSimulation(256, "LR");
+/// sim.spawn(128, 128, "north");
+/// const pixels = new Uint8ClampedArray(sim.size * sim.size * 4);
+/// sim.step(1000);
+/// sim.copyRgba(pixels);
+/// context.putImageData(new ImageData(pixels, sim.size), 0, 0);
+/// ```
+#[wasm_bindgen]
+pub struct WasmSimulation {
+    board: Box<dyn Board>,
+    size: usize,
+    palette: Palette,
+    layers: Option<Composition>,
+}
+
+#[wasm_bindgen]
+impl WasmSimulation {
+    /// Empty white board of 64, 128, 256, 512 or 1024 cells a side
+    #[wasm_bindgen(constructor)]
+    pub fn new(size: usize, rule: &str) -> Result<WasmSimulation, JsError> {
+        let rule = rule
+            .parse::<Rule>()
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        let palette = Palette::gray(rule.colors());
+        // Bigger boards than these take more memory than a page should
+        let board = match size {
+            ..=1024 => new_board(size, rule),
+            _ => None,
+        };
+        let Some(board) = board else {
+            let message = format!("unsupported size {size}, expected 64, 128, 256, 512 or 1024");
+            return Err(JsError::new(&message));
+        };
+        Ok(Self {
+            board,
+            size,
+            palette,
+            layers: None,
+        })
+    }
+
+    /// Add an ant facing `dir`: north, east, south, west or their first letter
+    pub fn spawn(&mut self, x: i32, y: i32, dir: &str) -> Result<(), JsError> {
+        let dir = dir
+            .parse::<Direction>()
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        self.board
+            .spawn(Pos::new(x as _, y as _), dir)
+            .map(|_| ())
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Walk every ant `steps` steps, returns whether any ant is still on the map
+    pub fn step(&mut self, steps: u32) -> bool {
+        self.board.run_at_most(steps as u64)
+    }
+
+    /// Steps done so far, a plain number rather than a `BigInt`
+    #[wasm_bindgen(getter)]
+    pub fn steps(&self) -> f64 {
+        self.board.steps() as f64
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Comma separated `#rrggbb` colors, one for each cell color of the rule
+    #[wasm_bindgen(js_name = setPalette)]
+    pub fn set_palette(&mut self, palette: &str) -> Result<(), JsError> {
+        let palette = palette
+            .parse::<Palette>()
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        let colors = self.board.colors();
+        if palette.colors.len() < colors {
+            let message = format!(
+                "{colors} colors but the palette has only {}",
+                palette.colors.len()
+            );
+            return Err(JsError::new(&message));
+        }
+        self.palette = palette;
+        Ok(())
+    }
+
+    /// Paint `cells` and `ants` layers as `spec` says, e.g. `cells,ants=0.5`, or just
+    /// the cells again for an empty one
+    #[wasm_bindgen(js_name = setLayers)]
+    pub fn set_layers(&mut self, spec: &str) -> Result<(), JsError> {
+        let layers = spec.parse::<Composition>().map_err(|e| JsError::new(&e))?;
+        self.layers = (!layers.0.is_empty()).then_some(layers);
+        Ok(())
+    }
+
+    /// Copy the board into `rgba`, 4 bytes a cell like canvas `ImageData`
+    #[wasm_bindgen(js_name = copyRgba)]
+    pub fn copy_rgba(&self, rgba: &mut [u8]) -> Result<(), JsError> {
+        if rgba.len() != self.size * self.size * 4 {
+            let (len, size) = (rgba.len(), self.size);
+            let message = format!(
+                "{len} bytes, a {size}x{size} board needs {}",
+                size * size * 4
+            );
+            return Err(JsError::new(&message));
+        }
+        let frame = self.board.frame();
+        match &self.layers {
+            Some(layers) => {
+                let cells = CellLayer {
+                    palette: Some(self.palette.clone()),
+                };
+                LayeredRenderer::new()
+                    .with_layer("cells", cells)
+                    .with_layer("ants", AntLayer::default())
+                    .with_composition(layers)
+                    .paint_into(&frame, rgba)
+            }
+            None => frame.fill_rgba(&self.palette, rgba),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+//! Memory the words of a [`BitSet`](crate::bitset::BitSet) live in. A `Vec` unless
+//! the `huge-pages` feature puts big boards in 2 MiB-aligned memory, backed by huge
+//! pages on Linux, so a walk over gigabytes of cells takes far fewer TLB misses
+
+#[cfg(not(feature = "huge-pages"))]
+pub(crate) type Words = Vec<u64>;
+
+/// `len` words, each `fill`
+#[cfg(not(feature = "huge-pages"))]
+pub(crate) fn words(len: usize, fill: u64) -> Words {
+    vec![fill; len]
+}
+
+#[cfg(feature = "huge-pages")]
+pub(crate) use aligned::{words, Words};
+
+#[cfg(feature = "huge-pages")]
+mod aligned {
+    use std::{
+        fmt::{self, Debug},
+        ops::{Deref, DerefMut},
+        ptr::NonNull,
+        slice,
+    };
+
+    use memmap2::MmapMut;
+
+    /// Size and alignment of a huge page on x86-64 and most of aarch64
+    const HUGE_PAGE: usize = 1 << 21;
+
+    /// `len` words at `start`, in memory one of the other two owns. Reading them is
+    /// the same either way, so the walk pays nothing for it
+    pub(crate) struct Words {
+        start: NonNull<u64>,
+        len: usize,
+        /// For less than a huge page, where aligning it would waste more than it's
+        /// worth
+        _heap: Vec<u64>,
+        /// A huge page more than the words take, so they can start on one
+        _mapped: Option<MmapMut>,
+    }
+
+    // SAFETY: the words are owned like a `Vec`'s, only reached through `&self` and
+    // `&mut self`
+    unsafe impl Send for Words {}
+    unsafe impl Sync for Words {}
+
+    pub(crate) fn words(len: usize, fill: u64) -> Words {
+        let mapped = match len * 8 < HUGE_PAGE {
+            true => None,
+            // Out of address space the heap won't do any better, let it say so
+            false => MmapMut::map_anon(len * 8 + HUGE_PAGE).ok(),
+        };
+        let Some(mut mmap) = mapped else {
+            let mut words = vec![fill; len];
+            let start = NonNull::new(words.as_mut_ptr()).expect("vectors aren't null");
+            return Words {
+                start,
+                len,
+                _heap: words,
+                _mapped: None,
+            };
+        };
+        let offset = mmap.as_ptr().align_offset(HUGE_PAGE);
+        // Only a hint, the words are as good without huge pages
+        #[cfg(target_os = "linux")]
+        mmap.advise_range(memmap2::Advice::HugePage, offset, len * 8)
+            .ok();
+        let start = bytemuck::cast_slice_mut::<u8, u64>(&mut mmap[offset..][..len * 8]);
+        let start = NonNull::new(start.as_mut_ptr()).expect("mappings aren't null");
+        let mut words = Words {
+            start,
+            len,
+            _heap: Vec::new(),
+            _mapped: Some(mmap),
+        };
+        if fill != 0 {
+            words.fill(fill);
+        }
+        words
+    }
+
+    impl Deref for Words {
+        type Target = [u64];
+
+        fn deref(&self) -> &[u64] {
+            // SAFETY: `start` points at `len` words in memory `self` owns, which is never
+            // resized and lives as long as `self`
+            unsafe { slice::from_raw_parts(self.start.as_ptr(), self.len) }
+        }
+    }
+
+    impl DerefMut for Words {
+        fn deref_mut(&mut self) -> &mut [u64] {
+            // SAFETY: as in `deref`, and `&mut self` makes the access unique
+            unsafe { slice::from_raw_parts_mut(self.start.as_ptr(), self.len) }
+        }
+    }
+
+    impl Clone for Words {
+        fn clone(&self) -> Self {
+            let mut words = words(self.len, 0);
+            words.copy_from_slice(self);
+            words
+        }
+    }
+
+    impl PartialEq for Words {
+        fn eq(&self, other: &Self) -> bool {
+            **self == **other
+        }
+    }
+
+    impl Eq for Words {}
+
+    impl Debug for Words {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            (**self).fmt(f)
+        }
+    }
+
+    #[test]
+    fn big_boards_start_on_a_huge_page() {
+        let words = words(HUGE_PAGE / 8 * 3, u64::MAX);
+        assert!(words._mapped.is_some());
+        assert_eq!(words.as_ptr() as usize % HUGE_PAGE, 0);
+        assert!(words.iter().all(|&word| word == u64::MAX));
+        let copy = words.clone();
+        assert_eq!(copy, words);
+        assert!(self::words(100, 0)._mapped.is_none());
+    }
+}
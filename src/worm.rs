@@ -0,0 +1,314 @@
+use std::{collections::HashMap, error::Error, fmt::Display, str::FromStr};
+#[cfg(feature = "io")]
+use std::{fs::File, io::BufWriter, path::Path};
+
+#[cfg(feature = "io")]
+use png::{BitDepth, ColorType, Encoder, EncodingError};
+
+use crate::{Hex, HexDirection, HexTurn};
+
+/// Paterson's worm rule: which way to go the first time each new pattern of eaten edges
+/// comes up, in the hex turn letters `N`, `R`, `r`, `l` and `L`
+///
+/// The edge behind the worm is always eaten, so `U` never makes sense here
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WormRule(Vec<HexTurn>);
+
+impl WormRule {
+    pub fn new(genes: Vec<HexTurn>) -> Result<Self, ParseWormRuleError> {
+        if genes.is_empty() {
+            return Err(ParseWormRuleError::Empty);
+        }
+        match genes.iter().find(|&&gene| gene == HexTurn::UTurn) {
+            Some(gene) => Err(ParseWormRuleError::Letter(gene.letter())),
+            None => Ok(Self(genes)),
+        }
+    }
+
+    pub fn genes(&self) -> &[HexTurn] {
+        &self.0
+    }
+}
+
+impl FromStr for WormRule {
+    type Err = ParseWormRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let genes = s
+            .chars()
+            .map(|c| match c {
+                'N' => Ok(HexTurn::None),
+                'R' => Ok(HexTurn::Right1),
+                'r' => Ok(HexTurn::Right2),
+                'l' => Ok(HexTurn::Left2),
+                'L' => Ok(HexTurn::Left1),
+                _ => Err(ParseWormRuleError::Letter(c)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Self::new(genes)
+    }
+}
+
+impl Display for WormRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for gene in &self.0 {
+            write!(f, "{}", gene.letter())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWormRuleError {
+    Letter(char),
+    Empty,
+}
+
+impl Display for ParseWormRuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseWormRuleError::Letter(c) => {
+                write!(f, "unknown worm turn {c:?}, expected N, R, r, l or L")
+            }
+            ParseWormRuleError::Empty => write!(f, "a worm needs at least one gene"),
+        }
+    }
+}
+
+impl Error for ParseWormRuleError {}
+
+/// Turns by counterclockwise sixths
+const TURNS: [HexTurn; 6] = [
+    HexTurn::None,
+    HexTurn::Left1,
+    HexTurn::Left2,
+    HexTurn::UTurn,
+    HexTurn::Right2,
+    HexTurn::Right1,
+];
+
+/// Why a worm stopped eating
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WormEnd {
+    /// Every edge at its point is eaten, the worm is dead
+    Starved,
+    /// A new pattern of eaten edges came up and the rule has no genes left for it
+    Undecided,
+    /// The next gene points at an edge that's already eaten
+    Blocked,
+}
+
+/// Paterson's worm on the triangular lattice formed by the centers of [`Hex`] cells
+///
+/// It moves along the edges between neighbouring points and eats every edge it walks, never
+/// walking an eaten one again. Where only one edge is left it takes it, otherwise it
+/// does what it did the last time it saw the same eaten edges relative to its heading,
+/// and the first time it takes the next gene of its rule. Its very first move is straight on.
+pub struct Worm {
+    rule: WormRule,
+    genes_used: usize,
+    /// The turn taken for each pattern of eaten edges, bit `i` is `i` sixths counterclockwise
+    decisions: [Option<HexTurn>; 64],
+    /// Eaten edges around each point, bit `i` is the edge towards `HexDirection::VARIANTS[i]`
+    eaten: HashMap<Hex, u8>,
+    pos: Hex,
+    dir: HexDirection,
+    steps: usize,
+    end: Option<WormEnd>,
+}
+
+impl Worm {
+    pub fn new(pos: Hex, dir: HexDirection, rule: WormRule) -> Self {
+        Self {
+            rule,
+            genes_used: 0,
+            decisions: [None; 64],
+            eaten: HashMap::new(),
+            pos,
+            dir,
+            steps: 0,
+            end: None,
+        }
+    }
+
+    pub fn pos(&self) -> Hex {
+        self.pos
+    }
+
+    pub fn dir(&self) -> HexDirection {
+        self.dir
+    }
+
+    /// Number of edges eaten so far
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// How many genes of the rule it has needed so far
+    pub fn genes_used(&self) -> usize {
+        self.genes_used
+    }
+
+    /// `None` while the worm can still eat
+    pub fn end(&self) -> Option<WormEnd> {
+        self.end
+    }
+
+    pub fn is_eaten(&self, pos: Hex, dir: HexDirection) -> bool {
+        self.eaten
+            .get(&pos)
+            .is_some_and(|&edges| edges & 1 << dir as u8 != 0)
+    }
+
+    /// Eaten edges at the worm's point relative to its heading
+    fn pattern(&self) -> u8 {
+        let edges = self.eaten.get(&self.pos).copied().unwrap_or(0);
+        (0..6)
+            .filter(|&i| edges & 1 << ((self.dir as usize + i) % 6) != 0)
+            .fold(0, |pattern, i| pattern | 1 << i)
+    }
+
+    /// Returns whether the worm can eat any further
+    pub fn walk(&mut self) -> bool {
+        if self.end.is_some() {
+            return false;
+        }
+
+        let pattern = self.pattern();
+        let free = 6 - pattern.count_ones();
+        let turn = if pattern == 0 {
+            // Only the starting point has no eaten edges
+            HexTurn::None
+        } else if free == 0 {
+            self.end = Some(WormEnd::Starved);
+            return false;
+        } else if free == 1 {
+            let free = (0..6).find(|&i| pattern & 1 << i == 0).unwrap();
+            TURNS[free]
+        } else if let Some(turn) = self.decisions[pattern as usize] {
+            turn
+        } else {
+            let Some(&gene) = self.rule.0.get(self.genes_used) else {
+                self.end = Some(WormEnd::Undecided);
+                return false;
+            };
+            if pattern & 1 << gene.sixths().rem_euclid(6) != 0 {
+                self.end = Some(WormEnd::Blocked);
+                return false;
+            }
+            self.genes_used += 1;
+            self.decisions[pattern as usize] = Some(gene);
+            gene
+        };
+
+        self.dir = self.dir.turn(turn);
+        let next = self.pos.neighbour(self.dir);
+        *self.eaten.entry(self.pos).or_default() |= 1 << self.dir as u8;
+        *self.eaten.entry(next).or_default() |= 1 << self.dir.turn(HexTurn::UTurn) as u8;
+        self.pos = next;
+        self.steps += 1;
+        true
+    }
+
+    /// Walk until the worm stops or `steps` edges are eaten,
+    /// returns whether it can still eat
+    pub fn walk_at_most(&mut self, steps: usize) -> bool {
+        (0..steps).all(|_| self.walk())
+    }
+
+    /// Draw the eaten edges black on white into an 8-bit grayscale PNG,
+    /// neighbouring points `spacing` pixels apart
+    #[cfg(feature = "io")]
+    pub fn save(&self, spacing: usize, file: impl AsRef<Path>) -> Result<(), EncodingError> {
+        let spacing = spacing.max(2) as f64;
+        let point = |hex: Hex| {
+            let (q, r) = (hex.q as f64, hex.r as f64);
+            ((q + r / 2.0) * spacing, r * 3f64.sqrt() / 2.0 * spacing)
+        };
+
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (0f64, 0f64, 0f64, 0f64);
+        for (x, y) in self.eaten.keys().map(|&hex| point(hex)) {
+            (min_x, min_y) = (min_x.min(x), min_y.min(y));
+            (max_x, max_y) = (max_x.max(x), max_y.max(y));
+        }
+        let width = (max_x - min_x + 2.0 * spacing).ceil() as usize;
+        let height = (max_y - min_y + 2.0 * spacing).ceil() as usize;
+
+        let mut data = vec![255; width * height];
+        for (&hex, &edges) in &self.eaten {
+            // Each edge once, from the end it leaves eastwards or northwards
+            for dir in &HexDirection::VARIANTS[..3] {
+                if edges & 1 << *dir as u8 == 0 {
+                    continue;
+                }
+                let (x0, y0) = point(hex);
+                let (x1, y1) = point(hex.neighbour(*dir));
+                let samples = 2 * spacing as usize;
+                for i in 0..=samples {
+                    let t = i as f64 / samples as f64;
+                    let x = x0 + (x1 - x0) * t - min_x + spacing;
+                    let y = y0 + (y1 - y0) * t - min_y + spacing;
+                    data[y as usize * width + x as usize] = 0;
+                }
+            }
+        }
+
+        let w = BufWriter::new(File::create(file)?);
+        let mut encoder = Encoder::new(w, width as _, height as _);
+        encoder.set_color(ColorType::Grayscale);
+        encoder.set_depth(BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)
+    }
+}
+
+#[test]
+fn worms_eat_edges() {
+    assert_eq!("LrN".parse::<WormRule>().unwrap().to_string(), "LrN");
+    assert_eq!(
+        "LU".parse::<WormRule>(),
+        Err(ParseWormRuleError::Letter('U'))
+    );
+    assert_eq!("".parse::<WormRule>(), Err(ParseWormRuleError::Empty));
+
+    // Always turning left walks around a hexagon back to the start, where the eaten
+    // first edge makes a new pattern the one gene can't answer
+    let start = Hex::new(0, 0);
+    let mut worm = Worm::new(start, HexDirection::East, "L".parse().unwrap());
+    assert!(worm.walk_at_most(6));
+    assert!(!worm.walk());
+    assert_eq!(worm.end(), Some(WormEnd::Undecided));
+    assert_eq!((worm.pos(), worm.steps(), worm.genes_used()), (start, 6, 1));
+    assert!(worm.is_eaten(start, HexDirection::East));
+    assert!(worm.is_eaten(Hex::new(1, 0), HexDirection::West));
+    assert!(!worm.is_eaten(start, HexDirection::West));
+
+    // A second left there would take the first edge again
+    let mut worm = Worm::new(start, HexDirection::East, "LL".parse().unwrap());
+    assert!(!worm.walk_at_most(7));
+    assert_eq!(worm.end(), Some(WormEnd::Blocked));
+
+    let mut worm = Worm::new(start, HexDirection::East, "LNRr".parse().unwrap());
+    assert!(!worm.walk_at_most(100));
+    assert_eq!((worm.end(), worm.steps()), (Some(WormEnd::Starved), 28));
+
+    // Every step eats a new edge, whatever the rule
+    let mut worm = Worm::new(start, HexDirection::East, "LRrR".parse().unwrap());
+    worm.walk_at_most(10_000);
+    let edges: u32 = worm.eaten.values().map(|edges| edges.count_ones()).sum();
+    assert_eq!(edges as usize, 2 * worm.steps());
+
+    #[cfg(feature = "io")]
+    {
+        let mut worm = Worm::new(start, HexDirection::East, "L".parse().unwrap());
+        worm.walk_at_most(6);
+        let file = std::env::temp_dir().join("antventure_worms_eat_edges.png");
+        worm.save(8, &file).unwrap();
+        let image = image::open(&file).unwrap().into_luma8();
+        // The hexagon's lowest point is the start, its first edge runs right from there
+        // through the bottom row of the drawing and the middle stays white
+        assert_eq!(image.get_pixel(16, 21).0, [0]);
+        assert_eq!(image.get_pixel(16, 14).0, [255]);
+    }
+}